@@ -0,0 +1,38 @@
+#!/bin/bash
+
+# Builds the runtime library once per feature set, so naldom-cli can link each
+# program against the smallest runtime that provides what it uses. Variants
+# land in target/<profile>/runtime-variants/<name>/, next to the default
+# runtime; naldom-cli falls back to the default one for any variant missing.
+# Usage: ./build_runtime_variants.sh [--release]
+
+# Exit immediately if a command exits with a non-zero status.
+set -e
+
+profile="debug"
+cargo_flags=()
+if [ "$1" == "--release" ]; then
+    profile="release"
+    cargo_flags+=(--release)
+fi
+target_dir="${CARGO_TARGET_DIR:-target}"
+
+# Variant name -> naldom-runtime features. The names must match
+# `RuntimeFeatures::variant_name` in naldom-cli.
+declare -A VARIANTS=(
+    [minimal]=""
+    [async-sleep]="async-sleep"
+    [parallel-sort]="parallel-sort"
+    [async-sleep+parallel-sort]="async-sleep,parallel-sort"
+)
+
+for variant in minimal async-sleep parallel-sort async-sleep+parallel-sort; do
+    # A separate target directory, so the default runtime is not rebuilt
+    # without its features.
+    cargo build -q -p naldom-runtime "${cargo_flags[@]}" --no-default-features \
+        --features "${VARIANTS[$variant]}" --target-dir "$target_dir/runtime-variants"
+    variant_dir="$target_dir/$profile/runtime-variants/$variant"
+    mkdir -p "$variant_dir"
+    cp "$target_dir/runtime-variants/$profile/libnaldom_runtime.a" "$variant_dir/"
+    printf "%-28s %12d bytes\n" "$variant" "$(wc -c < "$variant_dir/libnaldom_runtime.a")"
+done
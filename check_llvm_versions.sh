@@ -0,0 +1,34 @@
+#!/bin/bash
+
+# Type-checks the workspace against every supported LLVM version, so a change
+# that only compiles with one inkwell API surfaces before it reaches CI.
+# The `-no-llvm-linking` inkwell features skip linking, so no LLVM
+# installation is needed. Pass versions to check a subset: ./check_llvm_versions.sh 18
+
+# Exit immediately if a command exits with a non-zero status.
+set -e
+
+# Naldom feature -> inkwell feature.
+declare -A INKWELL_FEATURES=(
+    [17]="llvm17-0"
+    [18]="llvm18-1"
+    [19]="llvm19-1"
+)
+
+versions=("$@")
+if [ ${#versions[@]} -eq 0 ]; then
+    versions=(17 18 19)
+fi
+
+for version in "${versions[@]}"; do
+    inkwell_feature="${INKWELL_FEATURES[$version]}"
+    if [ -z "$inkwell_feature" ]; then
+        echo "Unsupported LLVM version: $version (expected one of ${!INKWELL_FEATURES[*]})" >&2
+        exit 2
+    fi
+    echo "Checking with LLVM $version..."
+    cargo check --workspace --all-targets --no-default-features \
+        --features "naldom-cli/llvm$version,naldom-core/llvm$version,inkwell/$inkwell_feature-no-llvm-linking"
+done
+
+echo "All LLVM versions type-check."
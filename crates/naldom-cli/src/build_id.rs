@@ -0,0 +1,138 @@
+// crates/naldom-cli/src/build_id.rs
+
+//! Per-invocation build IDs and the temp directories named after them.
+//!
+//! Every build gets a short ID hashed from the source, the options and the
+//! moment it started. The ID names the directory holding the intermediates,
+//! is recorded in the manifest and is printed when the build finishes, so a
+//! bug report or a kept `.ll` file can be matched to the build that made it.
+
+use crate::manifest::sha256_hex;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Hex digits in a build ID.
+const BUILD_ID_LEN: usize = 6;
+
+/// Distinguishes IDs drawn within one process in the same clock tick.
+static DRAWS: AtomicU64 = AtomicU64::new(0);
+
+/// A short hex identifier for one build, e.g. `3fa9c2`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BuildId(String);
+
+impl BuildId {
+    /// Draws a new ID. Builds of the same source with the same options still
+    /// get different IDs.
+    pub fn generate(source_sha256: &str, options: &str) -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let seed = format!(
+            "{}\0{}\0{}\0{}\0{}",
+            source_sha256,
+            options,
+            nanos,
+            std::process::id(),
+            DRAWS.fetch_add(1, Ordering::Relaxed)
+        );
+        let mut hash = sha256_hex(seed.as_bytes());
+        hash.truncate(BUILD_ID_LEN);
+        Self(hash)
+    }
+}
+
+impl fmt::Display for BuildId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// The temp directory `naldom-<id>` a build writes its intermediates to.
+/// It is removed, with its contents, when dropped.
+#[derive(Debug)]
+pub struct WorkDir {
+    id: BuildId,
+    path: PathBuf,
+}
+
+impl WorkDir {
+    /// Creates a fresh directory under `parent`, drawing new IDs until the
+    /// directory name is unused, so no two builds ever share one.
+    pub fn create(parent: &Path, source_sha256: &str, options: &str) -> io::Result<Self> {
+        loop {
+            let id = BuildId::generate(source_sha256, options);
+            let path = parent.join(format!("naldom-{}", id));
+            match fs::create_dir(&path) {
+                Ok(()) => return Ok(Self { id, path }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub fn id(&self) -> &BuildId {
+        &self.id
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_same_inputs_get_distinct_short_ids() {
+        // Act
+        let first = BuildId::generate("abc", "-O2");
+        let second = BuildId::generate("abc", "-O2");
+
+        // Assert
+        assert_ne!(first, second);
+        assert_eq!(first.0.len(), BUILD_ID_LEN);
+        assert!(first.0.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn test_concurrent_builds_get_distinct_directories() {
+        // Arrange
+        let parent = assert_fs::TempDir::new().unwrap();
+
+        // Act: eight builds of the same file with the same options at once.
+        let dirs: Vec<WorkDir> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| WorkDir::create(parent.path(), "abc", "-O2").unwrap()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        // Assert
+        let ids: HashSet<_> = dirs.iter().map(|dir| dir.id().clone()).collect();
+        let paths: HashSet<_> = dirs.iter().map(|dir| dir.path().to_path_buf()).collect();
+        assert_eq!(ids.len(), dirs.len());
+        assert_eq!(paths.len(), dirs.len());
+        for dir in &dirs {
+            assert!(dir.path().is_dir());
+            assert!(dir.path().ends_with(format!("naldom-{}", dir.id())));
+        }
+        let removed = dirs[0].path().to_path_buf();
+        drop(dirs);
+        assert!(!removed.exists());
+    }
+}
@@ -0,0 +1,420 @@
+// crates/naldom-cli/src/cache.rs
+
+//! The build cache: files kept between builds under `$NALDOM_CACHE_DIR`
+//! (by default `$XDG_CACHE_HOME/naldom` or `~/.cache/naldom`), one
+//! directory per kind of entry.
+//!
+//! `index.json` records each entry's size and when it was last used, and
+//! how often lookups found an entry. It is rewritten on every lookup and
+//! store by writing a temporary file and renaming it over the old one, so
+//! a reader never sees half an index; concurrent builds may lose each
+//! other's bookkeeping, never the entries themselves. `cache gc` evicts the
+//! least recently used entries by the index.
+
+use crate::manifest::sha256_hex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The environment variable that moves the cache.
+pub const CACHE_DIR_ENV: &str = "NALDOM_CACHE_DIR";
+
+/// Bumped whenever a field of the index is renamed or removed. An index of
+/// another version is started over.
+pub const INDEX_VERSION: u32 = 1;
+
+const INDEX_FILE: &str = "index.json";
+
+/// What an entry holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheKind {
+    /// The model's answer for a program text and the settings it was
+    /// asked with, reused by `--cache` builds.
+    Llm,
+    /// Build outputs. Nothing stores them yet: incremental results only
+    /// live as long as the process that computed them.
+    Artifacts,
+    /// What the last `--run --diff-previous` of a source file printed, for
+    /// the next one to compare with.
+    Runs,
+}
+
+impl CacheKind {
+    pub const ALL: [CacheKind; 3] = [CacheKind::Llm, CacheKind::Artifacts, CacheKind::Runs];
+
+    /// The entries' directory under the cache root.
+    pub fn dir_name(self) -> &'static str {
+        match self {
+            CacheKind::Llm => "llm",
+            CacheKind::Artifacts => "artifacts",
+            CacheKind::Runs => "runs",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct IndexEntry {
+    size: u64,
+    /// The index's clock when the entry was last stored or found.
+    last_used: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Index {
+    index_version: u32,
+    /// Counts stores and hits, so entries order by use even within the
+    /// resolution of the system clock.
+    clock: u64,
+    hits: u64,
+    misses: u64,
+    /// By `<kind>/<key>`, the entry's path under the root.
+    entries: BTreeMap<String, IndexEntry>,
+}
+
+impl Default for Index {
+    fn default() -> Self {
+        Index {
+            index_version: INDEX_VERSION,
+            clock: 0,
+            hits: 0,
+            misses: 0,
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+impl Index {
+    fn touch(&mut self, name: String, size: u64) {
+        self.clock += 1;
+        self.entries.insert(
+            name,
+            IndexEntry {
+                size,
+                last_used: self.clock,
+            },
+        );
+    }
+}
+
+/// The entries of one kind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KindStats {
+    pub entries: u64,
+    pub bytes: u64,
+}
+
+/// What `cache stats` reports.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheStats {
+    pub llm: KindStats,
+    pub artifacts: KindStats,
+    pub runs: KindStats,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    pub fn entries(&self) -> u64 {
+        self.llm.entries + self.artifacts.entries + self.runs.entries
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.llm.bytes + self.artifacts.bytes + self.runs.bytes
+    }
+
+    /// The share of lookups that found an entry, or `None` before the first.
+    pub fn hit_rate(&self) -> Option<f64> {
+        let lookups = self.hits + self.misses;
+        (lookups > 0).then(|| self.hits as f64 / lookups as f64)
+    }
+}
+
+/// What `cache gc` removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcReport {
+    pub evicted: u64,
+    pub freed_bytes: u64,
+}
+
+/// A cache directory.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    root: PathBuf,
+}
+
+impl Cache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Cache { root: root.into() }
+    }
+
+    /// The cache at `$NALDOM_CACHE_DIR`, `$XDG_CACHE_HOME/naldom` or
+    /// `~/.cache/naldom`, whichever is set first.
+    pub fn from_env() -> Result<Self, String> {
+        let root = env::var_os(CACHE_DIR_ENV)
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("XDG_CACHE_HOME").map(|dir| PathBuf::from(dir).join("naldom")))
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache/naldom")))
+            .ok_or_else(|| {
+                format!(
+                    "There is no cache directory: set {} or HOME.",
+                    CACHE_DIR_ENV
+                )
+            })?;
+        Ok(Cache::new(root))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The key of the entry for `parts`, e.g. a program text and the
+    /// settings it is compiled with.
+    pub fn key(parts: &[&str]) -> String {
+        sha256_hex(parts.join("\0").as_bytes())
+    }
+
+    fn entry_path(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    /// The entry `key` of `kind`, recording the lookup in the index.
+    pub fn get(&self, kind: CacheKind, key: &str) -> Result<Option<String>, String> {
+        let name = entry_name(kind, key);
+        let contents = fs::read_to_string(self.entry_path(&name)).ok();
+        let mut index = self.load_index()?;
+        match &contents {
+            Some(contents) => {
+                index.hits += 1;
+                index.touch(name, contents.len() as u64);
+            }
+            None => {
+                index.misses += 1;
+                index.entries.remove(&name);
+            }
+        }
+        self.save_index(&index)?;
+        Ok(contents)
+    }
+
+    /// Stores `contents` as the entry `key` of `kind`.
+    pub fn put(&self, kind: CacheKind, key: &str, contents: &str) -> Result<(), String> {
+        let name = entry_name(kind, key);
+        let path = self.entry_path(&name);
+        write_atomically(&path, contents)?;
+        let mut index = self.load_index()?;
+        index.touch(name, contents.len() as u64);
+        self.save_index(&index)
+    }
+
+    /// The entries by kind, and the hits and misses of lookups so far.
+    pub fn stats(&self) -> Result<CacheStats, String> {
+        let index = self.load_index()?;
+        let mut stats = CacheStats {
+            hits: index.hits,
+            misses: index.misses,
+            ..CacheStats::default()
+        };
+        for (name, entry) in &index.entries {
+            let kind = if name.starts_with(&entry_name(CacheKind::Llm, "")) {
+                &mut stats.llm
+            } else if name.starts_with(&entry_name(CacheKind::Runs, "")) {
+                &mut stats.runs
+            } else {
+                &mut stats.artifacts
+            };
+            kind.entries += 1;
+            kind.bytes += entry.size;
+        }
+        Ok(stats)
+    }
+
+    /// Removes every entry of `kinds`; returns how many there were. Clearing
+    /// every kind also resets the hit rate.
+    pub fn clear(&self, kinds: &[CacheKind]) -> Result<u64, String> {
+        let mut index = self.load_index()?;
+        let mut removed = 0;
+        for kind in kinds {
+            let dir = self.root.join(kind.dir_name());
+            if let Ok(entries) = fs::read_dir(&dir) {
+                removed += entries.count() as u64;
+            }
+            match fs::remove_dir_all(&dir) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(format!("Error removing '{}': {}", dir.display(), e)),
+            }
+            let prefix = format!("{}/", kind.dir_name());
+            index.entries.retain(|name, _| !name.starts_with(&prefix));
+        }
+        if CacheKind::ALL.iter().all(|kind| kinds.contains(kind)) {
+            index = Index::default();
+        }
+        self.save_index(&index)?;
+        Ok(removed)
+    }
+
+    /// Evicts the least recently used entries until the rest take up at
+    /// most `max_bytes`.
+    pub fn gc(&self, max_bytes: u64) -> Result<GcReport, String> {
+        let mut index = self.load_index()?;
+        let mut by_use: Vec<(String, IndexEntry)> = index
+            .entries
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.clone()))
+            .collect();
+        by_use.sort_by_key(|(_, entry)| entry.last_used);
+        let mut total: u64 = by_use.iter().map(|(_, entry)| entry.size).sum();
+        let mut report = GcReport::default();
+        for (name, entry) in by_use {
+            if total <= max_bytes {
+                break;
+            }
+            let path = self.entry_path(&name);
+            match fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(format!("Error removing '{}': {}", path.display(), e)),
+            }
+            index.entries.remove(&name);
+            total -= entry.size;
+            report.evicted += 1;
+            report.freed_bytes += entry.size;
+        }
+        self.save_index(&index)?;
+        Ok(report)
+    }
+
+    /// The index, or an empty one if there is none yet or it cannot be
+    /// read: it only holds bookkeeping, which may start over.
+    fn load_index(&self) -> Result<Index, String> {
+        let Ok(json) = fs::read_to_string(self.root.join(INDEX_FILE)) else {
+            return Ok(Index::default());
+        };
+        Ok(serde_json::from_str::<Index>(&json)
+            .ok()
+            .filter(|index| index.index_version == INDEX_VERSION)
+            .unwrap_or_default())
+    }
+
+    fn save_index(&self, index: &Index) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(index).map_err(|e| e.to_string())?;
+        write_atomically(&self.root.join(INDEX_FILE), &(json + "\n"))
+    }
+}
+
+fn entry_name(kind: CacheKind, key: &str) -> String {
+    format!("{}/{}", kind.dir_name(), key)
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it
+/// over `path`, creating the directory if needed.
+fn write_atomically(path: &Path, contents: &str) -> Result<(), String> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir).map_err(|e| format!("Error creating '{}': {}", dir.display(), e))?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = dir.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+    fs::write(&temp, contents)
+        .and_then(|()| fs::rename(&temp, path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&temp);
+            format!("Error writing '{}': {}", path.display(), e)
+        })
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gc_keeps_the_most_recently_used_entries() {
+        // Arrange: three 10-byte answers, the oldest of which is used again.
+        let temp = assert_fs::TempDir::new().unwrap();
+        let cache = Cache::new(temp.path());
+        for key in ["a", "b", "c"] {
+            cache.put(CacheKind::Llm, key, "0123456789").unwrap();
+        }
+        assert!(cache.get(CacheKind::Llm, "a").unwrap().is_some());
+
+        // Act
+        let report = cache.gc(20).unwrap();
+
+        // Assert: "b" was the least recently used.
+        assert_eq!(
+            report,
+            GcReport {
+                evicted: 1,
+                freed_bytes: 10
+            }
+        );
+        assert!(!temp.path().join("llm/b").exists());
+        assert!(cache.get(CacheKind::Llm, "a").unwrap().is_some());
+        assert!(cache.get(CacheKind::Llm, "c").unwrap().is_some());
+        assert_eq!(cache.gc(20).unwrap(), GcReport::default());
+    }
+
+    #[test]
+    fn test_stats_count_entries_and_lookups() {
+        // Arrange
+        let temp = assert_fs::TempDir::new().unwrap();
+        let cache = Cache::new(temp.path());
+        cache.put(CacheKind::Llm, "answer", "[]").unwrap();
+        cache
+            .put(CacheKind::Artifacts, "program", "print()")
+            .unwrap();
+
+        // Act
+        cache.get(CacheKind::Llm, "answer").unwrap();
+        cache.get(CacheKind::Llm, "missing").unwrap();
+        cache.get(CacheKind::Llm, "answer").unwrap();
+        cache.get(CacheKind::Llm, "missing").unwrap();
+        let stats = cache.stats().unwrap();
+
+        // Assert
+        assert_eq!(
+            stats.llm,
+            KindStats {
+                entries: 1,
+                bytes: 2
+            }
+        );
+        assert_eq!(
+            stats.artifacts,
+            KindStats {
+                entries: 1,
+                bytes: 7
+            }
+        );
+        assert_eq!(stats.hit_rate(), Some(0.5));
+    }
+
+    #[test]
+    fn test_clearing_one_kind_leaves_the_other() {
+        // Arrange
+        let temp = assert_fs::TempDir::new().unwrap();
+        let cache = Cache::new(temp.path());
+        cache.put(CacheKind::Llm, "answer", "[]").unwrap();
+        cache
+            .put(CacheKind::Artifacts, "program", "print()")
+            .unwrap();
+
+        // Act
+        let removed = cache.clear(&[CacheKind::Llm]).unwrap();
+
+        // Assert
+        assert_eq!(removed, 1);
+        assert_eq!(cache.get(CacheKind::Llm, "answer").unwrap(), None);
+        assert_eq!(
+            cache
+                .get(CacheKind::Artifacts, "program")
+                .unwrap()
+                .as_deref(),
+            Some("print()")
+        );
+        assert_eq!(cache.clear(&CacheKind::ALL).unwrap(), 1);
+        assert_eq!(cache.stats().unwrap(), CacheStats::default());
+    }
+}
@@ -0,0 +1,169 @@
+// crates/naldom-cli/src/config.rs
+
+//! The effective build settings. Each one comes from the first source that
+//! sets it: a command-line flag, then the source file's front matter, then
+//! the built-in default.
+
+use naldom_core::error_mode::ErrorMode;
+use naldom_core::front_matter::FrontMatter;
+use naldom_core::targets::TargetKind;
+use std::fmt;
+
+/// Where an effective setting came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    CommandLine,
+    FrontMatter,
+    Default,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfigSource::CommandLine => "command line",
+            ConfigSource::FrontMatter => "front matter",
+            ConfigSource::Default => "default",
+        })
+    }
+}
+
+/// A setting's value and where it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: ConfigSource,
+}
+
+/// The settings a build actually uses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildConfig {
+    pub target: Setting<TargetKind>,
+    pub opt_level: Setting<u8>,
+    /// `None` leaves the program's random numbers seeded from the clock.
+    pub seed: Setting<Option<u64>>,
+    /// What the program does when a runtime function fails.
+    pub on_error: Setting<ErrorMode>,
+    /// Commands run on the artifact after a successful build, in order.
+    pub post_process: Setting<Vec<String>>,
+}
+
+impl BuildConfig {
+    /// Merges the command-line flags (`None` or empty when not given) over
+    /// the front matter. Post-process commands from the command line replace
+    /// those from the front matter rather than adding to them.
+    pub fn resolve(
+        target: Option<TargetKind>,
+        opt_level: Option<u8>,
+        seed: Option<u64>,
+        on_error: Option<ErrorMode>,
+        post_process: &[String],
+        front_matter: &FrontMatter,
+    ) -> Self {
+        BuildConfig {
+            target: pick(target, front_matter.target, TargetKind::Native),
+            opt_level: pick(opt_level, front_matter.opt_level, 0),
+            seed: pick(seed.map(Some), front_matter.seed.map(Some), None),
+            on_error: pick(on_error, front_matter.on_error, ErrorMode::Abort),
+            post_process: pick(
+                (!post_process.is_empty()).then(|| post_process.to_vec()),
+                front_matter.post_process.clone(),
+                Vec::new(),
+            ),
+        }
+    }
+
+    /// One `name = value (source)` line per setting, for `--print-config`.
+    pub fn render(&self) -> String {
+        let seed = self
+            .seed
+            .value
+            .map_or_else(|| "none".to_string(), |seed| seed.to_string());
+        let post_process = if self.post_process.value.is_empty() {
+            "none".to_string()
+        } else {
+            let hooks: Vec<String> = self
+                .post_process
+                .value
+                .iter()
+                .map(|hook| format!("{:?}", hook))
+                .collect();
+            hooks.join(", ")
+        };
+        format!(
+            "target = {} ({})\nopt_level = {} ({})\nseed = {} ({})\non_error = {} ({})\npost_process = {} ({})\n",
+            self.target.value,
+            self.target.source,
+            self.opt_level.value,
+            self.opt_level.source,
+            seed,
+            self.seed.source,
+            self.on_error.value,
+            self.on_error.source,
+            post_process,
+            self.post_process.source
+        )
+    }
+}
+
+fn pick<T>(flag: Option<T>, front_matter: Option<T>, default: T) -> Setting<T> {
+    match (flag, front_matter) {
+        (Some(value), _) => Setting {
+            value,
+            source: ConfigSource::CommandLine,
+        },
+        (None, Some(value)) => Setting {
+            value,
+            source: ConfigSource::FrontMatter,
+        },
+        (None, None) => Setting {
+            value: default,
+            source: ConfigSource::Default,
+        },
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags_override_front_matter_which_overrides_defaults() {
+        // Arrange
+        let front_matter = FrontMatter {
+            target: Some(TargetKind::Wasm),
+            opt_level: Some(2),
+            seed: None,
+            on_error: Some(ErrorMode::Continue),
+            post_process: Some(vec!["strip {output}".to_string()]),
+        };
+
+        // Act
+        let config = BuildConfig::resolve(
+            Some(TargetKind::Python),
+            None,
+            None,
+            None,
+            &[],
+            &front_matter,
+        );
+        let hooks = BuildConfig::resolve(
+            None,
+            None,
+            None,
+            Some(ErrorMode::Abort),
+            &["upload {output}".to_string()],
+            &front_matter,
+        );
+
+        // Assert
+        assert_eq!(
+            config.render(),
+            "target = python (command line)\nopt_level = 2 (front matter)\nseed = none (default)\non_error = continue (front matter)\npost_process = \"strip {output}\" (front matter)\n"
+        );
+        assert_eq!(hooks.post_process.value, ["upload {output}"]);
+        assert_eq!(hooks.post_process.source, ConfigSource::CommandLine);
+        assert_eq!(hooks.on_error.value, ErrorMode::Abort);
+        assert_eq!(hooks.on_error.source, ConfigSource::CommandLine);
+    }
+}
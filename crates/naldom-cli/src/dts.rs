@@ -0,0 +1,132 @@
+// crates/naldom-cli/src/dts.rs
+
+//! The TypeScript definitions written next to every wasm module. They describe
+//! the imports a JavaScript host must provide and the exports it gets back.
+
+use naldom_core::targets::WASM_ENTRY;
+use naldom_ir::runtime_abi::{AbiType, RuntimeFunction, WASM_IMPORT_MODULE};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// `program.wasm` -> `program.d.ts`.
+pub fn dts_path(module_path: &Path) -> PathBuf {
+    module_path.with_extension("d.ts")
+}
+
+/// Renders the definitions for a module importing `imports`.
+pub fn generate_dts(imports: &[&RuntimeFunction]) -> String {
+    let mut dts = String::from("// Generated by naldom-cli. Do not edit.\n\n");
+    dts.push_str("/** An address in the module's linear memory. */\n");
+    dts.push_str("export type Pointer = number;\n\n");
+
+    let _ = writeln!(
+        dts,
+        "/** The functions the host must provide as the `{}` import module. */",
+        WASM_IMPORT_MODULE
+    );
+    dts.push_str("export interface NaldomRuntimeImports {\n");
+    for function in imports {
+        let params: Vec<String> = function
+            .params
+            .iter()
+            .map(|param| format!("{}: {}", param.name, ts_type(param.ty)))
+            .collect();
+        let _ = writeln!(dts, "  /** {} */", function.doc);
+        let _ = writeln!(
+            dts,
+            "  {}({}): {};",
+            function.name,
+            params.join(", "),
+            ts_type(function.return_type)
+        );
+    }
+    dts.push_str("}\n\n");
+
+    dts.push_str("/** The import object to pass to `WebAssembly.instantiate`. */\n");
+    dts.push_str("export interface NaldomImports {\n");
+    let _ = writeln!(dts, "  {}: NaldomRuntimeImports;", WASM_IMPORT_MODULE);
+    dts.push_str("}\n\n");
+
+    dts.push_str("/** The exports of the instantiated module. */\n");
+    dts.push_str("export interface NaldomExports {\n");
+    dts.push_str("  memory: WebAssembly.Memory;\n");
+    let _ = writeln!(dts, "  /** Runs the program. */\n  {}(): void;", WASM_ENTRY);
+    dts.push_str("}\n");
+    dts
+}
+
+/// How a wasm32 value of `ty` appears in JavaScript.
+fn ts_type(ty: AbiType) -> &'static str {
+    match ty {
+        AbiType::Void => "void",
+        // The JS API passes wasm i64 values as BigInt.
+        AbiType::I64 | AbiType::U64 => "bigint",
+        AbiType::I32 | AbiType::F64 => "number",
+        AbiType::F64Ptr
+        | AbiType::I64Ptr
+        | AbiType::CStr
+        | AbiType::CStrArray
+        | AbiType::EntryFn
+        | AbiType::ArrayPtr
+        | AbiType::ConstArrayPtr
+        | AbiType::MatrixPtr
+        | AbiType::ConstMatrixPtr => "Pointer",
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use naldom_ir::runtime_abi::find_runtime_function;
+
+    #[test]
+    fn test_dts_snapshot() {
+        // Arrange
+        let imports = [
+            find_runtime_function("create_random_array").unwrap(),
+            find_runtime_function("sort_array").unwrap(),
+        ];
+
+        // Act
+        let dts = generate_dts(&imports);
+
+        // Assert
+        assert_eq!(
+            dts,
+            r#"// Generated by naldom-cli. Do not edit.
+
+/** An address in the module's linear memory. */
+export type Pointer = number;
+
+/** The functions the host must provide as the `naldom_runtime` import module. */
+export interface NaldomRuntimeImports {
+  /** Allocates an array of `size` random numbers in [0, 100). */
+  create_random_array(size: bigint): Pointer;
+  /** Sorts `arr` in place: ascending for order 0, descending for order 1. Algorithm 0 lets the runtime choose by length; 1 is quicksort, 2 merge sort, 3 bubble sort. */
+  sort_array(arr: Pointer, order: bigint, algorithm: bigint): void;
+}
+
+/** The import object to pass to `WebAssembly.instantiate`. */
+export interface NaldomImports {
+  naldom_runtime: NaldomRuntimeImports;
+}
+
+/** The exports of the instantiated module. */
+export interface NaldomExports {
+  memory: WebAssembly.Memory;
+  /** Runs the program. */
+  naldom_main(): void;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_dts_path_replaces_the_extension() {
+        assert_eq!(
+            dts_path(Path::new("out/program.wasm")),
+            PathBuf::from("out/program.d.ts")
+        );
+    }
+}
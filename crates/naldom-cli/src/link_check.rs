@@ -0,0 +1,142 @@
+// crates/naldom-cli/src/link_check.rs
+
+//! A pre-link probe: every function the generated module declares but does not
+//! define must be provided by the runtime of the selected target. Checking this
+//! against the runtime ABI registry turns an opaque linker error into a list of
+//! the offending symbols.
+
+use naldom_core::targets::TargetKind;
+use naldom_ir::runtime_abi::find_runtime_function;
+use std::fmt;
+
+/// A declared symbol the target's runtime cannot resolve.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedSymbol {
+    pub name: String,
+    /// Whether the native runtime would have provided it.
+    pub native_only: bool,
+}
+
+/// The symbols that would fail to link for a target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkCheckError {
+    pub target: TargetKind,
+    pub unresolved: Vec<UnresolvedSymbol>,
+}
+
+impl fmt::Display for LinkCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the program uses runtime functions the '{}' target does not provide:",
+            self.target
+        )?;
+        for symbol in &self.unresolved {
+            let provider = if symbol.native_only {
+                "only the native runtime provides it"
+            } else {
+                "no Naldom runtime provides it"
+            };
+            write!(f, "\n  {} ({})", symbol.name, provider)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for LinkCheckError {}
+
+/// The names of all functions declared (but not defined) in textual LLVM IR.
+pub fn declared_symbols(llvm_ir: &str) -> Vec<&str> {
+    llvm_ir
+        .lines()
+        .filter(|line| line.starts_with("declare "))
+        .filter_map(|line| {
+            let name = &line[line.find('@')? + 1..];
+            let name = &name[..name.find('(')?];
+            Some(name.trim_matches('"'))
+        })
+        // LLVM intrinsics are resolved by the backend, not the linker.
+        .filter(|name| !name.starts_with("llvm."))
+        .collect()
+}
+
+/// Checks that the runtime for `target` provides every declared symbol.
+pub fn check_symbols(target: TargetKind, llvm_ir: &str) -> Result<(), LinkCheckError> {
+    let unresolved: Vec<UnresolvedSymbol> = declared_symbols(llvm_ir)
+        .into_iter()
+        .filter_map(|name| match find_runtime_function(name) {
+            Some(function) if function.native_only && target == TargetKind::Wasm => {
+                Some(UnresolvedSymbol {
+                    name: name.to_string(),
+                    native_only: true,
+                })
+            }
+            Some(_) => None,
+            None => Some(UnresolvedSymbol {
+                name: name.to_string(),
+                native_only: false,
+            }),
+        })
+        .collect();
+    if unresolved.is_empty() {
+        Ok(())
+    } else {
+        Err(LinkCheckError { target, unresolved })
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLEEPY_IR: &str = r#"; ModuleID = 'naldom_module'
+declare ptr @create_random_array(i64)
+
+declare void @naldom_async_sleep(i64)
+
+declare void @llvm.memcpy.p0.p0.i64(ptr, ptr, i64, i1)
+
+define void @main() {
+entry:
+  call void @naldom_async_sleep(i64 100)
+  ret void
+}
+"#;
+
+    #[test]
+    fn test_declared_symbols_skip_definitions_and_intrinsics() {
+        assert_eq!(
+            declared_symbols(SLEEPY_IR),
+            ["create_random_array", "naldom_async_sleep"]
+        );
+    }
+
+    #[test]
+    fn test_wasm_reports_native_only_symbols() {
+        // Act
+        let native = check_symbols(TargetKind::Native, SLEEPY_IR);
+        let wasm = check_symbols(TargetKind::Wasm, SLEEPY_IR).unwrap_err();
+
+        // Assert
+        assert!(native.is_ok());
+        assert_eq!(
+            wasm.to_string(),
+            "the program uses runtime functions the 'wasm' target does not provide:\n  naldom_async_sleep (only the native runtime provides it)"
+        );
+    }
+
+    #[test]
+    fn test_unknown_symbols_are_unresolved_everywhere() {
+        let error =
+            check_symbols(TargetKind::Native, "declare void @launch_rocket()\n").unwrap_err();
+
+        assert_eq!(
+            error.unresolved,
+            [UnresolvedSymbol {
+                name: "launch_rocket".to_string(),
+                native_only: false,
+            }]
+        );
+    }
+}
@@ -0,0 +1,156 @@
+// crates/naldom-cli/src/lockfile.rs
+
+//! The lockfile `<source>.naldom-lock`: the model's answer for a source
+//! file, recorded with `--write-lock` so that `--locked` builds compile the
+//! same intents without asking the model again.
+
+use crate::manifest::sha256_hex;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever a field is renamed or removed.
+pub const LOCK_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Lockfile {
+    lock_version: u32,
+    /// SHA-256 of the program text the answer was given for.
+    source_sha256: String,
+    /// The model's raw answer.
+    intent_json: String,
+}
+
+/// `<source>.naldom-lock`, next to the source file.
+pub fn lock_path(source_path: &Path) -> PathBuf {
+    let mut path = OsString::from(source_path.as_os_str());
+    path.push(".naldom-lock");
+    PathBuf::from(path)
+}
+
+/// Records `intent_json` as the answer for `program_text`.
+pub fn write(path: &Path, program_text: &str, intent_json: &str) -> Result<(), String> {
+    let lockfile = Lockfile {
+        lock_version: LOCK_VERSION,
+        source_sha256: sha256_hex(program_text.as_bytes()),
+        intent_json: intent_json.to_string(),
+    };
+    let json = serde_json::to_string_pretty(&lockfile).map_err(|e| e.to_string())?;
+    fs::write(path, json + "\n")
+        .map_err(|e| format!("Error writing lockfile '{}': {}", path.display(), e))
+}
+
+/// The answer recorded for `program_text`. Fails when there is no lockfile
+/// or it was recorded for a different program text.
+pub fn read(path: &Path, program_text: &str) -> Result<String, String> {
+    let json = fs::read_to_string(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            format!(
+                "--locked needs the lockfile '{}', which does not exist. Build once with --write-lock to record the model's answer.",
+                path.display()
+            )
+        } else {
+            format!("Error reading lockfile '{}': {}", path.display(), e)
+        }
+    })?;
+    let lockfile = parse(path, &json)?;
+    if lockfile.source_sha256 != sha256_hex(program_text.as_bytes()) {
+        return Err(format!(
+            "The lockfile '{}' was recorded for a different version of the source. Rebuild it with --write-lock.",
+            path.display()
+        ));
+    }
+    Ok(lockfile.intent_json)
+}
+
+/// The answer recorded in the lockfile at `path`, whatever program text it
+/// was recorded for. For tools that only look at the answer.
+pub fn read_answer(path: &Path) -> Result<String, String> {
+    let json = fs::read_to_string(path)
+        .map_err(|e| format!("Error reading lockfile '{}': {}", path.display(), e))?;
+    Ok(parse(path, &json)?.intent_json)
+}
+
+/// The lockfile `json` read from `path`, if this compiler reads its version.
+fn parse(path: &Path, json: &str) -> Result<Lockfile, String> {
+    let lockfile: Lockfile = serde_json::from_str(json)
+        .map_err(|e| format!("Malformed lockfile '{}': {}", path.display(), e))?;
+    if lockfile.lock_version != LOCK_VERSION {
+        return Err(format!(
+            "The lockfile '{}' has version {}, but this compiler reads version {}. Rebuild it with --write-lock.",
+            path.display(),
+            lockfile.lock_version,
+            LOCK_VERSION
+        ));
+    }
+    Ok(lockfile)
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROGRAM: &str = ":::naldom\nCreate an array of 5 numbers.\n:::\n";
+    const ANSWER: &str = r#"[{"intent": "CreateArray", "parameters": {"size": 5}}]"#;
+
+    fn scratch_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "naldom-lockfile-{}-{}.naldom-lock",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn test_lock_path_is_next_to_the_source() {
+        assert_eq!(
+            lock_path(Path::new("dir/program.md")),
+            PathBuf::from("dir/program.md.naldom-lock")
+        );
+    }
+
+    #[test]
+    fn test_a_recorded_answer_is_read_back_for_the_same_program_only() {
+        // Arrange
+        let path = scratch_path("round-trip");
+        write(&path, PROGRAM, ANSWER).unwrap();
+
+        // Act
+        let answer = read(&path, PROGRAM);
+        let stale = read(&path, ":::naldom\nCreate an array of 6 numbers.\n:::\n");
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert_eq!(answer.unwrap(), ANSWER);
+        assert!(
+            stale
+                .unwrap_err()
+                .contains("different version of the source")
+        );
+    }
+
+    #[test]
+    fn test_tools_read_the_answer_whatever_the_source() {
+        // Arrange
+        let path = scratch_path("answer");
+        write(&path, PROGRAM, ANSWER).unwrap();
+
+        // Act
+        let answer = read_answer(&path);
+        let _ = fs::remove_file(&path);
+
+        // Assert
+        assert_eq!(answer.unwrap(), ANSWER);
+    }
+
+    #[test]
+    fn test_a_missing_lockfile_says_how_to_create_one() {
+        let error = read(&scratch_path("missing"), PROGRAM).unwrap_err();
+
+        assert!(error.contains("--locked needs the lockfile"), "{}", error);
+        assert!(error.contains("--write-lock"), "{}", error);
+    }
+}
@@ -1,139 +1,1631 @@
 // crates/naldom-cli/src/main.rs
 
-use clap::Parser;
-use naldom_core::codegen_llvm::generate_llvm_ir;
-use naldom_core::llm_inference::run_inference;
-use naldom_core::lowering::LoweringContext;
-use naldom_core::lowering_hl_to_ll::lower_hl_to_ll;
-use naldom_core::parser::parse_to_intent_graph;
-use naldom_core::semantic_analyzer::SemanticAnalyzer;
+use build_id::{BuildId, WorkDir};
+use cache::{Cache, CacheKind};
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use config::BuildConfig;
+use manifest::{
+    Artifacts, BuildManifest, MANIFEST_VERSION, SourceRecord, StageTiming, ToolRecord,
+    manifest_path, normalize_path, sha256_hex,
+};
+use naldom_core::attribution::SizeAttribution;
+use naldom_core::codegen_python::PythonCodeGenerator;
+use naldom_core::compiler::{CompileOutput, Compiler};
+use naldom_core::cost::DEFAULT_SLOW_THRESHOLD;
+use naldom_core::error_mode::ErrorMode;
+use naldom_core::eval::{EvalReport, evaluate, load_cases, render_table};
+use naldom_core::explain::{describe_error_mode, explain, explain_raw};
+use naldom_core::front_matter::{FrontMatter, split_front_matter};
+use naldom_core::fs_policy::FsPolicy;
+use naldom_core::graph::{DependencyGraph, intent_graph_to_dot, intent_graph_to_mermaid};
+use naldom_core::intent_diff::diff_intents;
+use naldom_core::interpreter::Interpreter;
+use naldom_core::ir_size::{DEFAULT_HARD_LIMIT, DEFAULT_SOFT_LIMIT, IrSizeLimits};
+use naldom_core::lints::{LintLevel, LintRegistry};
+use naldom_core::llm_inference::complete;
+use naldom_core::multi::program_symbol;
+use naldom_core::observer::{CollectedArtifacts, CollectingObserver};
+use naldom_core::parser::{RawIntent, parse_raw_intents};
+use naldom_core::privacy::{DEFAULT_CONTEXT_SENTENCES, Privacy};
+use naldom_core::progress::{
+    HumanProgress, JsonProgress, ProgressEvent, ProgressSink, Stage, TimingRecorder,
+};
+use naldom_core::prompt::{DEFAULT_TEMPERATURE, PromptOptions, Sampling};
+use naldom_core::runtime_header::generate_runtime_header;
+use naldom_core::runtime_shim::{ShimBackend, generate_runtime_shim};
+use naldom_core::targets::{LLVM_MAJOR, TargetKind, resolve_triple};
+use naldom_ir::LLProgram;
+use naldom_ir::runtime_abi::find_runtime_function;
+use pipeline::{ExecOptions, Flow, StageSpec};
+use post_process::HookContext;
+use runtime_variant::RuntimeFeatures;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Command, ExitCode, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use summary::{
+    CompileStats, StageTracker, failure_footer, format_size, stats_report, success_footer,
+};
+use toolchain::{ToolCommand, Toolchain, run_commands};
+
+mod build_id;
+mod cache;
+mod config;
+mod dts;
+mod link_check;
+mod lockfile;
+mod manifest;
+mod object_size;
+mod pipeline;
+mod post_process;
+mod run_output;
+mod runtime_variant;
+mod summary;
+mod toolchain;
 
 /// The Naldom Compiler CLI
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
-    file_path: PathBuf,
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+    /// The Naldom source file to compile (several with `--multi`).
+    #[arg(required = true)]
+    file_paths: Vec<PathBuf>,
     #[arg(short, long)]
     output: Option<PathBuf>,
-    #[arg(long, default_value = "native")]
-    target: String,
-    #[arg(short = 'O', long, default_value = "0")]
-    opt_level: u8,
+    /// Read the input file as an IntentGraph JSON array and skip inference.
+    #[arg(long)]
+    from_intents: bool,
+    /// Build every source file into one executable that runs the program
+    /// named by its first argument: the file name up to the first dot.
+    #[arg(long)]
+    multi: bool,
+    /// The backend to compile for: native, wasm or python. Defaults to the
+    /// source file's front matter, then native.
+    #[arg(long)]
+    target: Option<TargetKind>,
+    /// What to produce for the native target: an executable, or an object
+    /// file or static library for embedding in a host application. The
+    /// library kinds do not link the runtime; the host links it.
+    #[arg(long, value_enum, value_name = "KIND", default_value_t = CrateType::Bin)]
+    crate_type: CrateType,
+    /// The name of the generated entry function (default: `main`, or
+    /// `naldom_main` for wasm). Only with `--crate-type obj` or `staticlib`.
+    #[arg(long, value_name = "NAME", value_parser = parse_entry_name)]
+    entry_name: Option<String>,
+    /// Overrides the target triple (defaults to the host for native builds).
+    #[arg(long, value_name = "TRIPLE")]
+    target_triple: Option<String>,
+    /// Defaults to the source file's front matter, then 0.
+    #[arg(short = 'O', long)]
+    opt_level: Option<u8>,
+    /// Make the program's random numbers the same on every run.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// What the program does when a runtime function fails: `abort` ends it,
+    /// `continue` warns and goes on with an empty result. Defaults to the
+    /// source file's front matter, then abort.
+    #[arg(long, value_name = "MODE")]
+    on_error: Option<ErrorMode>,
+    /// Rule out every source of nondeterminism: implies `--seed 0` and
+    /// `--locked`, keeps timestamps out of the manifest, sorts on one thread,
+    /// orders `--multi` sources by path and has the model sample greedily
+    /// from a fixed seed. Fails if an option given explicitly disagrees.
+    #[arg(long)]
+    deterministic: bool,
+    /// Compile the model's answer recorded in `<source>.naldom-lock` instead
+    /// of asking the model; fails if there is none.
+    #[arg(long, conflicts_with = "write_lock")]
+    locked: bool,
+    /// Record the model's answer in `<source>.naldom-lock`.
+    #[arg(long)]
+    write_lock: bool,
+    /// Reuse the model's answer from the build cache when the program text
+    /// and the model settings are unchanged, and cache new answers.
+    #[arg(long, conflicts_with_all = ["locked", "from_intents"])]
+    cache: bool,
+    /// The temperature the model samples its answer at.
+    #[arg(long, value_name = "TEMPERATURE")]
+    llm_temperature: Option<f32>,
+    /// Let the model's answer use any parameter names and values, for models
+    /// that stall under the grammar listing each intent's parameters.
+    #[arg(long)]
+    loose_grammar: bool,
+    /// Run this command on the artifact after a successful build; `{output}`,
+    /// `{target}` and `{source}` are replaced by the artifact, the target and
+    /// the source file. May be repeated; the commands run in order.
+    #[arg(long = "post-process", value_name = "COMMAND")]
+    post_process: Vec<String>,
+    /// Print the effective target, optimization level, seed, error mode and
+    /// post-process commands, and where each one came from, instead of
+    /// compiling.
+    #[arg(long)]
+    print_config: bool,
+    /// Print every stage's output, starting with the prompt sent to the model.
     #[arg(long)]
     trace: bool,
+    /// Print what each IR pass changed, as a unified diff, to stderr.
+    #[arg(long)]
+    print_pass_diff: bool,
     #[arg(long)]
     run: bool,
+    /// With --run, capture the program's output (still printed as it
+    /// arrives) and show how it differs from the previous run of the same
+    /// source file.
+    #[arg(long, requires = "run")]
+    diff_previous: bool,
+    /// With --diff-previous, compare with the stored output but keep this
+    /// run's output out of the cache.
+    #[arg(long, requires = "diff_previous")]
+    no_store_output: bool,
+    /// Print this instead of building: `llvm-ir`, the validated plan as
+    /// `intent-json`, or its dependency graph as `intent-graph` (Graphviz
+    /// DOT) or `intent-graph-mermaid`.
     #[arg(long, value_name = "FORMAT")]
     emit: Option<String>,
+    /// Print a plain-language plan of the validated program instead of building it.
+    #[arg(long)]
+    explain: bool,
+    /// Analyze the program and report its errors and warnings, without
+    /// generating code or building anything.
+    #[arg(long, conflicts_with_all = ["emit", "explain", "run", "print_link_command"])]
+    check: bool,
+    /// Warn when the program is estimated to run longer than this many seconds.
+    #[arg(long, value_name = "SECONDS")]
+    slow_threshold: Option<u64>,
+    /// Warn when the generated LLVM IR has more instructions than this.
+    #[arg(long, value_name = "INSTRUCTIONS", default_value_t = DEFAULT_SOFT_LIMIT)]
+    ir_soft_limit: usize,
+    /// Fail when the generated LLVM IR has more instructions than this.
+    #[arg(long, value_name = "INSTRUCTIONS", default_value_t = DEFAULT_HARD_LIMIT)]
+    ir_hard_limit: usize,
+    /// Make the program print the time spent in each intent to stderr on exit.
+    #[arg(long)]
+    profile: bool,
+    /// After the build, print how much code each generated function
+    /// accounts for; with `-v`, also the intents that account for the most.
+    #[arg(long, conflicts_with = "multi")]
+    stats: bool,
+    /// Print more detail, e.g. the per-intent table of `--stats`.
+    #[arg(short, long)]
+    verbose: bool,
+    /// Repair semantic errors by applying the first suggestion for each error.
+    #[arg(long)]
+    apply_suggestions: bool,
+    /// Ask the model again, with corrective feedback, up to this many times
+    /// when its plan uses an array before the source creates it.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    retries: u32,
+    /// Silence a lint. May be repeated.
+    #[arg(long = "allow", value_name = "LINT")]
+    allow_lints: Vec<String>,
+    /// Report a lint as a warning. May be repeated.
+    #[arg(long = "warn", value_name = "LINT")]
+    warn_lints: Vec<String>,
+    /// Fail the compilation when a lint fires. May be repeated.
+    #[arg(long = "deny", value_name = "LINT")]
+    deny_lints: Vec<String>,
+    /// Write `<output>.naldom-manifest.json` describing the build.
+    #[arg(long)]
+    manifest: bool,
+    /// Also record the build ID and the stage timings in the manifest. They
+    /// change with every build, so they are left out by default.
+    #[arg(long)]
+    manifest_timings: bool,
+    /// Keep the intermediate `.ll` and `.o` files next to the output.
+    #[arg(long)]
+    save_temps: bool,
+    /// Print the llc and linker commands instead of running them.
+    #[arg(long)]
+    print_link_command: bool,
+    /// Report pipeline progress on stderr.
+    #[arg(long, value_enum, value_name = "MODE")]
+    progress: Option<ProgressMode>,
+    /// How much of the document outside its `:::naldom` blocks is sent to
+    /// the model: nothing (strict), or a few sentences around each block.
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = PrivacyMode::Normal)]
+    privacy: PrivacyMode,
+    /// The sentences sent on each side of a block with `--privacy normal`.
+    #[arg(long, value_name = "SENTENCES", default_value_t = DEFAULT_CONTEXT_SENTENCES)]
+    privacy_context: usize,
+    /// Let file intents use paths under this directory too, besides the
+    /// source file's own. Relative to the source file's directory. Repeatable.
+    #[arg(long, value_name = "PATH")]
+    allow_fs: Vec<PathBuf>,
+    /// Reject every file intent, whatever `--allow-fs` grants.
+    #[arg(long)]
+    deny_fs: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum CliCommand {
+    /// Write a support file instead of compiling a program.
+    Emit {
+        artifact: EmitArtifact,
+        /// Write to this file instead of stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Show what Naldom does, using a built-in program. Needs no LLM, no LLVM
+    /// tools and no input file.
+    Demo,
+    /// Show, clear or trim the build cache.
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Compare the intents of two IntentGraph files or lockfiles, including
+    /// intents this version of Naldom does not know.
+    Diff { before: PathBuf, after: PathBuf },
+    /// Check that the tools a build needs are installed.
+    Doctor {
+        /// Also exercise every runtime function in-process.
+        #[arg(long)]
+        runtime: bool,
+    },
+    /// Score a prompt on labeled cases: a directory of `NAME.nld` sources,
+    /// each with the intents it should produce in `NAME.json`.
+    Eval {
+        cases: PathBuf,
+        /// How often each case is sent to the model.
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        runs: u32,
+        /// Use the instructions in this file instead of the built-in ones.
+        #[arg(long, value_name = "FILE")]
+        prompt: Option<PathBuf>,
+        /// Also score the instructions in this file, side by side.
+        #[arg(long, value_name = "FILE")]
+        compare: Option<PathBuf>,
+        /// Write the scores as JSON to this file.
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheAction {
+    /// Print the entries of each kind, their size and the share of lookups
+    /// that found an entry.
+    Stats,
+    /// Remove cached entries.
+    #[command(group(ArgGroup::new("kinds").required(true).multiple(true)))]
+    Clear {
+        /// The model's answers.
+        #[arg(long, group = "kinds")]
+        llm: bool,
+        /// Build outputs.
+        #[arg(long, group = "kinds")]
+        artifacts: bool,
+        /// The output of earlier `--run --diff-previous` runs.
+        #[arg(long, group = "kinds")]
+        runs: bool,
+        /// Everything, including the hit rate.
+        #[arg(long, group = "kinds")]
+        all: bool,
+    },
+    /// Evict the least recently used entries until the cache fits.
+    Gc {
+        /// The most the entries may take up, in MiB.
+        #[arg(long, value_name = "MB")]
+        max_size: u64,
+    },
+}
+
+/// The external tools `doctor` looks for.
+const DOCTOR_TOOLS: [&str; 3] = ["llc", "clang", "wasm-ld"];
+
+/// The demo program: the intents the model produces for `DEMO_SOURCE`.
+const DEMO_SOURCE: &str = "Create an array of 8 random numbers. Print it. Wait half a second. \
+Sort it in descending order and print it again.";
+const DEMO_INTENTS: &str = r#"[
+    {"intent": "CreateArray", "parameters": {"size": 8}},
+    {"intent": "PrintArray"},
+    {"intent": "Wait", "parameters": {"durationMs": 500}},
+    {"intent": "SortArray", "parameters": {"order": "descending"}},
+    {"intent": "PrintArray"}
+]"#;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum EmitArtifact {
+    /// `naldom_runtime.h`: the runtime's C ABI, generated from the ABI registry.
+    RuntimeHeader,
+    /// `naldom_runtime.py`: the runtime `--target python` programs call,
+    /// generated from the ABI registry.
+    PythonRuntime,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum CrateType {
+    /// An executable linked against the runtime.
+    Bin,
+    /// The program's object file alone.
+    Obj,
+    /// A static library holding the program's object file.
+    Staticlib,
+}
+
+impl CrateType {
+    fn name(self) -> &'static str {
+        match self {
+            CrateType::Bin => "bin",
+            CrateType::Obj => "obj",
+            CrateType::Staticlib => "staticlib",
+        }
+    }
+}
+
+/// Accepts names a C or Rust host can declare: an ASCII identifier.
+fn parse_entry_name(name: &str) -> Result<String, String> {
+    let mut chars = name.chars();
+    let valid_start = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+    if valid_start && chars.all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        Ok(name.to_string())
+    } else {
+        Err(format!("'{}' is not a valid C identifier", name))
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ProgressMode {
+    /// Status lines for people watching a terminal.
+    Human,
+    /// Newline-delimited JSON events for editors and other tools.
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum PrivacyMode {
+    /// Send only the blocks, with a `[redacted]` marker for each stretch of prose.
+    Strict,
+    /// Send the blocks and the sentences right next to them.
+    Normal,
+}
+
+impl Args {
+    fn ir_size_limits(&self) -> IrSizeLimits {
+        IrSizeLimits {
+            soft: self.ir_soft_limit,
+            hard: self.ir_hard_limit,
+        }
+    }
+
+    fn sampling(&self) -> Sampling {
+        if self.deterministic {
+            return Sampling::DETERMINISTIC;
+        }
+        Sampling {
+            temperature: self.llm_temperature.unwrap_or(DEFAULT_TEMPERATURE),
+            ..Sampling::default()
+        }
+    }
+
+    /// Sets what `--deterministic` implies, after checking that no option
+    /// given on the command line asks for something else.
+    fn apply_deterministic(&mut self) -> Result<(), String> {
+        if !self.deterministic {
+            return Ok(());
+        }
+        let conflicts: Vec<String> = [
+            self.seed
+                .filter(|&seed| seed != 0)
+                .map(|seed| format!("--seed {}: it implies --seed 0", seed)),
+            self.llm_temperature
+                .filter(|&temperature| temperature != 0.0)
+                .map(|temperature| {
+                    format!(
+                        "--llm-temperature {}: it implies --llm-temperature 0",
+                        temperature
+                    )
+                }),
+            self.manifest_timings
+                .then(|| "--manifest-timings: it keeps timestamps out of the manifest".to_string()),
+            self.write_lock
+                .then(|| "--write-lock: it implies --locked".to_string()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !conflicts.is_empty() {
+            return Err(format!(
+                "--deterministic conflicts with options given on the command line:\n  {}",
+                conflicts.join("\n  ")
+            ));
+        }
+        self.seed = Some(0);
+        self.locked = true;
+        self.file_paths.sort();
+        Ok(())
+    }
+
+    /// The runtime features to link for programs that need `features`.
+    fn runtime_features(&self, features: RuntimeFeatures) -> RuntimeFeatures {
+        if self.deterministic {
+            features.single_threaded()
+        } else {
+            features
+        }
+    }
+
+    /// The paths the file intents of `file_path` may use.
+    fn fs_policy(&self, file_path: &Path) -> FsPolicy {
+        let source_dir = file_path.parent().unwrap_or(Path::new(""));
+        self.allow_fs
+            .iter()
+            .fold(FsPolicy::new(source_dir), |policy, dir| {
+                policy.with_allowed(dir)
+            })
+            .with_deny_all(self.deny_fs)
+    }
+
+    fn privacy(&self) -> Privacy {
+        match self.privacy {
+            PrivacyMode::Strict => Privacy::Strict,
+            PrivacyMode::Normal => Privacy::Normal {
+                context_sentences: self.privacy_context,
+            },
+        }
+    }
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> ExitCode {
     naldom_runtime::ensure_linked();
 
     let args = Args::parse();
-    let output_path = args.output.clone().unwrap_or_else(|| {
-        if args.target == "wasm" {
-            PathBuf::from("a.out.wasm")
-        } else {
-            PathBuf::from("a.out")
+    // The footer would interleave with the JSON events on stderr.
+    let footer = args.progress != Some(ProgressMode::Json);
+    let failed_stage = Arc::default();
+    // Errors are printed with Display: several span multiple lines.
+    match run(args, &failed_stage).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            if footer {
+                let stage = *failed_stage.lock().unwrap_or_else(|e| e.into_inner());
+                eprint!("{}", failure_footer(stage, &e.to_string()));
+            }
+            ExitCode::FAILURE
         }
-    });
-
-    let llvm_ir = run_compiler_pipeline(&args).await?;
+    }
+}
 
-    if let Some(emit_format) = &args.emit
-        && emit_format == "llvm-ir"
+/// Runs the invocation `args` describes. The compiler stage that reported
+/// the error, if any, is left in `failed_stage`.
+async fn run(
+    mut args: Args,
+    failed_stage: &Arc<Mutex<Option<Stage>>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(command) = &args.command {
+        return run_command(command).await.map_err(Into::into);
+    }
+    args.apply_deterministic()?;
+    if args.multi {
+        return run_multi(&args).await;
+    }
+    let started = Instant::now();
+    let [file_path] = args.file_paths.as_slice() else {
+        return Err("Compiling several source files into one executable needs --multi".into());
+    };
+    let source_code = fs::read_to_string(file_path)
+        .map_err(|e| format!("Error reading file '{}': {}", file_path.display(), e))?;
+    // An IntentGraph file is plain JSON, without front matter.
+    let (front_matter, program_text) = if args.from_intents {
+        (FrontMatter::default(), source_code.as_str())
+    } else {
+        split_front_matter(&source_code).map_err(|e| e.to_string())?
+    };
+    if args.from_intents
+        && let Some(plan) = emit_unknown_plan(&args, &source_code)
     {
-        println!("{}", llvm_ir);
+        print!("{}", plan);
+        return Ok(());
+    }
+    let config = BuildConfig::resolve(
+        args.target,
+        args.opt_level,
+        args.seed,
+        args.on_error,
+        &args.post_process,
+        &front_matter,
+    );
+    if args.print_config {
+        print!("{}", config.render());
         return Ok(());
     }
+    let target = config.target.value;
+    let opt_level = config.opt_level.value;
+    check_crate_type(&args, target)?;
 
-    let compile_result = if args.target == "wasm" {
-        compile_wasm(&llvm_ir, &output_path, args.opt_level)
-    } else {
-        compile_native(&llvm_ir, &output_path, args.opt_level)
+    let output_path = args
+        .output
+        .clone()
+        .unwrap_or_else(|| match (target, args.crate_type) {
+            (TargetKind::Native, CrateType::Obj) => PathBuf::from("a.o"),
+            (TargetKind::Native, CrateType::Staticlib) => PathBuf::from("liba.a"),
+            (TargetKind::Native, CrateType::Bin) => PathBuf::from("a.out"),
+            (TargetKind::Wasm, _) => PathBuf::from("a.out.wasm"),
+            (TargetKind::Python, _) => PathBuf::from("a.out.py"),
+        });
+
+    if args.emit.as_deref() == Some("llvm-ir") && !target.uses_llvm() {
+        return Err(format!(
+            "--emit llvm-ir is not available for the '{}' target",
+            target
+        )
+        .into());
+    }
+
+    // Resolve the triple before inference, so an unusable target fails fast.
+    if target.uses_llvm() {
+        resolve_triple(&target, args.target_triple.as_deref()).map_err(|e| e.to_string())?;
+    }
+
+    let lints = lint_registry(&args).map_err(|e| e.to_string())?;
+    let recorder = TimingRecorder::new(Box::new(StageTracker::new(
+        progress_sink(args.progress),
+        Arc::clone(failed_stage),
+    )));
+    let timings = recorder.timings();
+    // Trace and `--emit llvm-ir` read the artifacts from here.
+    let collector = Arc::new(CollectingObserver::new());
+    let compiler = Arc::new(
+        Compiler::new()
+            .with_progress(Box::new(recorder))
+            .with_observer(collector.clone())
+            .with_apply_suggestions(args.apply_suggestions)
+            .with_retries(args.retries)
+            .with_pass_diffs(args.print_pass_diff)
+            .with_profile(args.profile)
+            .with_seed(config.seed.value)
+            .with_error_mode(config.on_error.value)
+            .with_sampling(args.sampling())
+            .with_loose_grammar(args.loose_grammar)
+            .with_lints(lints)
+            .with_slow_threshold(
+                args.slow_threshold
+                    .map_or(DEFAULT_SLOW_THRESHOLD, Duration::from_secs),
+            )
+            .with_target(target)
+            .with_target_triple(args.target_triple.clone())
+            .with_entry_name(args.entry_name.clone())
+            .with_ir_size_limits(args.ir_size_limits())
+            .with_fs_policy(args.fs_policy(file_path))
+            .with_privacy(args.privacy())
+            .with_check_only(args.check || args.emit.as_deref() == Some(EMIT_INTENT_JSON)),
+    );
+    let mut build = Build {
+        args: &args,
+        compiler: &compiler,
+        collector: &collector,
+        file_path,
+        source_code: &source_code,
+        program_text,
+        target,
+        opt_level,
+        error_mode: config.on_error.value,
+        output_path,
+        output: None,
+        artifacts: CollectedArtifacts::default(),
+        work_dir: None,
+        link_plan: None,
+    };
+    let options = ExecOptions {
+        trace: args.trace,
+        // `--explain` asks for the analysis stage's output in plain language.
+        emit: if args.explain {
+            Some(EMIT_EXPLAIN)
+        } else {
+            args.emit.as_deref()
+        },
+        stop_after: args.check.then_some(Stage::Analysis),
     };
+    let stopped = pipeline::execute(
+        &build_stages(),
+        &mut build,
+        &options,
+        compiler.progress(),
+        &mut std::io::stdout(),
+    )?;
+    if stopped.is_some() {
+        if args.check {
+            println!("'{}' has no errors.", file_path.display());
+        }
+        return Ok(());
+    }
+    let Build {
+        output: Some(output),
+        work_dir: Some(work_dir),
+        link_plan,
+        output_path,
+        ..
+    } = build
+    else {
+        return Err("The build stages ended without linking the program".into());
+    };
+    let code = &output.code;
 
-    if let Err(e) = compile_result {
-        return Err(format!("Failed to compile for target '{}': {}", args.target, e).into());
+    let progress = compiler.progress();
+    if let Some(event) = ProgressEvent::artifact_written(&output_path) {
+        progress.emit(&event);
+    }
+    if target == TargetKind::Wasm {
+        let imports: Vec<_> = link_check::declared_symbols(code)
+            .into_iter()
+            .filter_map(find_runtime_function)
+            .collect();
+        let path = dts::dts_path(&output_path);
+        fs::write(&path, dts::generate_dts(&imports))?;
+        if let Some(event) = ProgressEvent::artifact_written(&path) {
+            progress.emit(&event);
+        }
     }
 
-    println!("Successfully compiled to '{}'", output_path.display());
+    let hooks = &config.post_process.value;
+    post_process::run_hooks(
+        hooks,
+        &HookContext {
+            output: &output_path,
+            target,
+            source: file_path,
+        },
+    )?;
+    if let Some(note) =
+        post_process::wasm_opt_suggestion(target, hooks, post_process::wasm_opt_installed)
+    {
+        eprintln!("note: {}", note);
+    }
+
+    if args.manifest {
+        let stage_timings = timings.lock().map(|t| t.clone()).unwrap_or_default();
+        let manifest = build_manifest(
+            &args,
+            work_dir.id(),
+            &config,
+            &output,
+            &output_path,
+            link_plan.as_ref(),
+            &stage_timings,
+        )?;
+        let path = manifest_path(&output_path);
+        manifest.write(&path)?;
+        if let Some(event) = ProgressEvent::artifact_written(&path) {
+            progress.emit(&event);
+        }
+    }
+    let stats = CompileStats {
+        build_id: work_dir.id().to_string(),
+        artifact: output_path.clone(),
+        bytes: fs::metadata(&output_path).ok().map(|m| m.len()),
+        target,
+        crate_type: args.crate_type,
+        opt_level,
+        elapsed: started.elapsed(),
+        run: args.run,
+        size: args.stats.then(|| {
+            let object = link_plan
+                .as_ref()
+                .map(|(_, obj_path, _)| obj_path.as_path());
+            code_size(&output, object)
+        }),
+    };
+    print!("{}", success_footer(&stats));
+    print!("{}", stats_report(&stats, args.verbose));
 
     if args.run {
-        if args.target == "wasm" {
-            println!(
+        match target {
+            TargetKind::Native if args.diff_previous => {
+                run_and_diff(&output_path, file_path, !args.no_store_output)?
+            }
+            TargetKind::Native => {
+                run_native_executable(&output_path, false)?;
+            }
+            TargetKind::Wasm => println!(
                 "\nCannot run wasm target directly. Use a Wasm runtime like wasmtime or a browser."
+            ),
+            TargetKind::Python => println!(
+                "\nCannot run python target directly. The generated module expects the Naldom Python runtime; write it with `naldom-cli emit python-runtime -o naldom_runtime.py`."
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+/// The code each function and intent of `output` accounts for, with the
+/// machine code sizes read from `object` when the build left one.
+fn code_size(output: &CompileOutput, object: Option<&Path>) -> SizeAttribution {
+    let mut size = output.size.clone();
+    if let Some(object) = object.filter(|object| object.exists()) {
+        match object_size::function_sizes(object) {
+            Ok(sizes) => size.add_object_bytes(&sizes),
+            Err(e) => eprintln!("note: {}; `--stats` leaves object bytes out", e),
+        }
+    }
+    size
+}
+
+async fn run_command(command: &CliCommand) -> Result<(), String> {
+    match command {
+        CliCommand::Emit { artifact, output } => {
+            let contents = match artifact {
+                EmitArtifact::RuntimeHeader => generate_runtime_header(),
+                EmitArtifact::PythonRuntime => {
+                    generate_runtime_shim(ShimBackend::Python).map_err(|e| e.to_string())?
+                }
+            };
+            match output {
+                Some(path) => fs::write(path, contents)
+                    .map_err(|e| format!("Error writing '{}': {}", path.display(), e)),
+                None => {
+                    print!("{}", contents);
+                    Ok(())
+                }
+            }
+        }
+        CliCommand::Cache { action } => run_cache(action),
+        CliCommand::Demo => run_demo(),
+        CliCommand::Diff { before, after } => run_diff(before, after),
+        CliCommand::Doctor { runtime } => run_doctor(*runtime),
+        CliCommand::Eval {
+            cases,
+            runs,
+            prompt,
+            compare,
+            report,
+        } => {
+            let prompts = match compare {
+                Some(compare) => vec![prompt.as_deref(), Some(compare.as_path())],
+                None => vec![prompt.as_deref()],
+            };
+            run_eval(cases, *runs, &prompts, report.as_deref()).await
+        }
+    }
+}
+
+/// Scores each prompt, a file of instructions or `None` for the built-in
+/// ones, on the cases in `cases_dir` and prints the scores side by side.
+async fn run_eval(
+    cases_dir: &Path,
+    runs: u32,
+    prompts: &[Option<&Path>],
+    report_path: Option<&Path>,
+) -> Result<(), String> {
+    let cases = load_cases(cases_dir)?;
+    let mut reports: Vec<(String, EvalReport)> = Vec::new();
+    for prompt in prompts {
+        let (label, instructions) = match prompt {
+            Some(path) => (
+                path.display().to_string(),
+                Some(
+                    fs::read_to_string(path)
+                        .map_err(|e| format!("Error reading '{}': {}", path.display(), e))?,
+                ),
+            ),
+            None => ("built-in".to_string(), None),
+        };
+        let options = PromptOptions {
+            instructions,
+            ..PromptOptions::default()
+        };
+        let report = evaluate(&cases, runs, &options, |prompt| async move {
+            complete(&prompt).await
+        })
+        .await;
+        reports.push((label, report));
+    }
+
+    for (label, report) in &reports {
+        for case in &report.cases {
+            if let Some(diff) = &case.first_mismatch {
+                println!(
+                    "{}: first wrong answer for '{}':\n{}",
+                    label, case.name, diff
+                );
+            }
+        }
+    }
+    let columns: Vec<(&str, &EvalReport)> = reports
+        .iter()
+        .map(|(label, report)| (label.as_str(), report))
+        .collect();
+    print!("{}", render_table(&columns));
+
+    if let Some(path) = report_path {
+        let json = serde_json::json!({
+            "runs": runs,
+            "prompts": reports
+                .iter()
+                .map(|(label, report)| serde_json::json!({ "prompt": label, "report": report }))
+                .collect::<Vec<_>>(),
+        });
+        let contents = serde_json::to_string_pretty(&json)
+            .map_err(|e| format!("Error serializing the report: {}", e))?;
+        fs::write(path, contents + "\n")
+            .map_err(|e| format!("Error writing '{}': {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Runs a `cache` subcommand on the cache the builds use.
+/// Prints how the intents in `after` differ from those in `before`.
+fn run_diff(before: &Path, after: &Path) -> Result<(), String> {
+    let before = read_raw_intents(before)?;
+    let after = read_raw_intents(after)?;
+    match diff_intents(&before, &after) {
+        Some(diff) => print!("{}", diff),
+        None => println!("The intents are the same."),
+    }
+    Ok(())
+}
+
+/// The intents in an IntentGraph file, or the answer in a lockfile.
+fn read_raw_intents(path: &Path) -> Result<Vec<RawIntent>, String> {
+    let json = if path.to_string_lossy().ends_with(".naldom-lock") {
+        lockfile::read_answer(path)?
+    } else {
+        fs::read_to_string(path)
+            .map_err(|e| format!("Error reading file '{}': {}", path.display(), e))?
+    };
+    parse_raw_intents(&json)
+        .map_err(|e| format!("'{}' is not an IntentGraph: {}", path.display(), e))
+}
+
+fn run_cache(action: &CacheAction) -> Result<(), String> {
+    let cache = Cache::from_env()?;
+    match action {
+        CacheAction::Stats => {
+            let stats = cache.stats()?;
+            println!("Cache: {}", cache.root().display());
+            for (name, kind) in [
+                ("llm", stats.llm),
+                ("artifacts", stats.artifacts),
+                ("runs", stats.runs),
+            ] {
+                println!(
+                    "  {:<10} {} entries, {}",
+                    format!("{}:", name),
+                    kind.entries,
+                    format_size(kind.bytes)
+                );
+            }
+            println!(
+                "  {:<10} {} entries, {}",
+                "total:",
+                stats.entries(),
+                format_size(stats.bytes())
             );
+            match stats.hit_rate() {
+                Some(rate) => println!(
+                    "  {:<10} {:.0}% ({} of {} lookups)",
+                    "hit rate:",
+                    rate * 100.0,
+                    stats.hits,
+                    stats.hits + stats.misses
+                ),
+                None => println!("  {:<10} no lookups yet", "hit rate:"),
+            }
+        }
+        CacheAction::Clear {
+            llm,
+            artifacts,
+            runs,
+            all,
+        } => {
+            let kinds: Vec<CacheKind> = CacheKind::ALL
+                .into_iter()
+                .filter(|kind| {
+                    *all || match kind {
+                        CacheKind::Llm => *llm,
+                        CacheKind::Artifacts => *artifacts,
+                        CacheKind::Runs => *runs,
+                    }
+                })
+                .collect();
+            let removed = cache.clear(&kinds)?;
+            println!("Removed {} cache entries.", removed);
+        }
+        CacheAction::Gc { max_size } => {
+            let report = cache.gc(max_size.saturating_mul(1024 * 1024))?;
+            println!(
+                "Evicted {} cache entries, freeing {}.",
+                report.evicted,
+                format_size(report.freed_bytes)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Reports each check on its own line and fails if any of them failed.
+fn run_doctor(check_runtime: bool) -> Result<(), String> {
+    let mut failures = 0;
+    let mut report = |passed: bool, name: &str, detail: &str| {
+        if !passed {
+            failures += 1;
+        }
+        println!(
+            "  {:<4} {}: {}",
+            if passed { "ok" } else { "FAIL" },
+            name,
+            detail
+        );
+    };
+
+    println!("Toolchain:");
+    let toolchain = Toolchain::from_env();
+    for name in DOCTOR_TOOLS {
+        let command = toolchain.tool_command(name);
+        match command.version() {
+            Some(version) => report(true, name, &version),
+            None => report(
+                false,
+                name,
+                &format!("`{}` could not be run", command.render()),
+            ),
+        }
+    }
+
+    println!("Cache:");
+    match Cache::from_env().and_then(|cache| Ok((cache.stats()?, cache))) {
+        Ok((stats, cache)) => report(
+            true,
+            "cache",
+            &format!(
+                "{} entries, {} in {}",
+                stats.entries(),
+                format_size(stats.bytes()),
+                cache.root().display()
+            ),
+        ),
+        Err(e) => report(false, "cache", &e),
+    }
+
+    if check_runtime {
+        println!("Runtime self-test:");
+        let scratch_dir = env::temp_dir().join(format!("naldom-doctor-{}", std::process::id()));
+        fs::create_dir_all(&scratch_dir)
+            .map_err(|e| format!("Error creating '{}': {}", scratch_dir.display(), e))?;
+        // The runtime blocks on its own Tokio executor, which cannot start
+        // inside the CLI's.
+        let results = std::thread::scope(|scope| {
+            scope
+                .spawn(|| naldom_runtime::self_test::run(&scratch_dir))
+                .join()
+        })
+        .map_err(|_| "The runtime self-test crashed.".to_string())?;
+        for result in results {
+            match &result.outcome {
+                Ok(()) => report(true, result.name, "passed"),
+                Err(reason) => report(false, result.name, reason),
+            }
+        }
+        let _ = fs::remove_dir_all(&scratch_dir);
+    }
+
+    match failures {
+        0 => Ok(()),
+        1 => Err("1 check failed.".to_string()),
+        n => Err(format!("{} checks failed.", n)),
+    }
+}
+
+/// `--multi`: compiles each source file into its own entry function and links
+/// them into one executable whose `main` runs the program named by its first
+/// argument.
+async fn run_multi(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    check_multi(args)?;
+    let started = Instant::now();
+    let mut programs: Vec<(String, LLProgram)> = Vec::new();
+    let mut sources = Vec::new();
+    let mut runtime_features = RuntimeFeatures::default();
+    // The executable is linked once, at the highest level any program asks for.
+    let mut opt_level = 0;
+    for file_path in &args.file_paths {
+        let name = program_name(file_path)?;
+        if programs.iter().any(|(existing, _)| *existing == name) {
+            return Err(format!("Two source files define the program '{}'", name).into());
+        }
+        let source_code = fs::read_to_string(file_path)
+            .map_err(|e| format!("Error reading file '{}': {}", file_path.display(), e))?;
+        let (front_matter, program_text) = if args.from_intents {
+            (FrontMatter::default(), source_code.as_str())
         } else {
-            run_native_executable(&output_path)?;
+            split_front_matter(&source_code).map_err(|e| e.to_string())?
+        };
+        let config = BuildConfig::resolve(
+            args.target,
+            args.opt_level,
+            args.seed,
+            args.on_error,
+            &[],
+            &front_matter,
+        );
+        if config.target.value != TargetKind::Native {
+            return Err(format!(
+                "'{}' targets {}, but --multi only builds native executables",
+                file_path.display(),
+                config.target.value
+            )
+            .into());
+        }
+        opt_level = opt_level.max(config.opt_level.value);
+
+        let compiler = Compiler::new()
+            .with_apply_suggestions(args.apply_suggestions)
+            .with_retries(args.retries)
+            .with_pass_diffs(args.print_pass_diff)
+            .with_profile(args.profile)
+            .with_seed(config.seed.value)
+            .with_error_mode(config.on_error.value)
+            .with_sampling(args.sampling())
+            .with_loose_grammar(args.loose_grammar)
+            .with_lints(lint_registry(args).map_err(|e| e.to_string())?)
+            .with_slow_threshold(
+                args.slow_threshold
+                    .map_or(DEFAULT_SLOW_THRESHOLD, Duration::from_secs),
+            )
+            .with_target_triple(args.target_triple.clone())
+            .with_entry_name(Some(program_symbol(&name)))
+            .with_ir_size_limits(args.ir_size_limits())
+            .with_fs_policy(args.fs_policy(file_path))
+            .with_privacy(args.privacy());
+        let output = compile_program(args, &Arc::new(compiler), file_path, program_text).await?;
+        for warning in &output.warnings {
+            eprintln!("warning: {}: {}", name, warning);
         }
+        for pass_diff in &output.pass_diffs {
+            eprint!("{}: {}", name, pass_diff.render());
+        }
+        sources.push(source_code);
+        runtime_features =
+            runtime_features.union(RuntimeFeatures::required_by(&output.validated_intent_graph));
+        programs.push((name, output.ll_program));
+    }
+
+    let collector = Arc::new(CollectingObserver::new());
+    let code = Compiler::new()
+        .with_target_triple(args.target_triple.clone())
+        .with_observer(collector.clone())
+        .compile_dispatcher(programs)?;
+    if args.emit.as_deref() == Some("llvm-ir") {
+        println!("{}", collector.take().llvm_ir.unwrap_or_default());
+        return Ok(());
+    }
+
+    let output_path = args
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("a.out"));
+    let temp_dir = env::temp_dir();
+    let work_dir = WorkDir::create(
+        &temp_dir,
+        &sha256_hex(sources.concat().as_bytes()),
+        &format!("{:?}", args),
+    )
+    .map_err(|e| {
+        format!(
+            "Error creating a build directory in '{}': {}",
+            temp_dir.display(),
+            e
+        )
+    })?;
+    let (ll_path, obj_path, commands) = link_commands(
+        TargetKind::Native,
+        CrateType::Bin,
+        &output_path,
+        opt_level,
+        args.save_temps,
+        work_dir.path(),
+        args.runtime_features(runtime_features),
+    )?
+    .ok_or("The native target has no link step")?;
+    if args.print_link_command {
+        for command in &commands {
+            println!("{}", command.render());
+        }
+        return Ok(());
     }
+    warn_on_llvm_mismatch(&commands);
+    link_check::check_symbols(TargetKind::Native, &code)
+        .map_err(|e| e.to_string())
+        .and_then(|()| {
+            run_commands(
+                &code,
+                &ll_path,
+                &obj_path,
+                &output_path,
+                &commands,
+                args.save_temps,
+            )
+            .map_err(|e| e.to_string())
+        })
+        .map_err(|e| format!("Failed to compile for target 'native': {}", e))?;
 
+    print!(
+        "{}",
+        success_footer(&CompileStats {
+            build_id: work_dir.id().to_string(),
+            artifact: output_path.clone(),
+            bytes: fs::metadata(&output_path).ok().map(|m| m.len()),
+            target: TargetKind::Native,
+            crate_type: CrateType::Bin,
+            opt_level,
+            elapsed: started.elapsed(),
+            run: false,
+            size: None,
+        })
+    );
     Ok(())
 }
 
-async fn run_compiler_pipeline(args: &Args) -> Result<String, String> {
-    let source_code = fs::read_to_string(&args.file_path)
-        .map_err(|e| format!("Error reading file '{}': {}", args.file_path.display(), e))?;
+/// The name `--multi` runs a source file's program under: its file name up
+/// to the first dot, so `demo1.md` and `demo1.intents.json` are both `demo1`.
+fn program_name(file_path: &Path) -> Result<String, String> {
+    let file_name = file_path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let name = file_name.split('.').next().unwrap_or_default();
+    parse_entry_name(name).map_err(|e| {
+        format!(
+            "Cannot name a program after '{}': {}",
+            file_path.display(),
+            e
+        )
+    })
+}
+
+/// Rejects flags that have no meaning for a `--multi` build.
+fn check_multi(args: &Args) -> Result<(), String> {
+    let conflicts = [
+        (args.explain, "--explain"),
+        (
+            args.emit
+                .as_deref()
+                .is_some_and(|format| format.starts_with("intent-graph")),
+            "--emit intent-graph",
+        ),
+        (
+            args.emit.as_deref() == Some(EMIT_INTENT_JSON),
+            "--emit intent-json",
+        ),
+        (args.print_config, "--print-config"),
+        (args.manifest, "--manifest"),
+        (args.run, "--run"),
+        (!args.post_process.is_empty(), "--post-process"),
+        (args.entry_name.is_some(), "--entry-name"),
+        (args.crate_type != CrateType::Bin, "--crate-type"),
+    ];
+    match conflicts.iter().find(|(set, _)| *set) {
+        Some((_, flag)) => Err(format!("--multi cannot be combined with {}", flag)),
+        None => Ok(()),
+    }
+}
+
+/// Compiles the embedded demo program and runs it in the IR-HL interpreter.
+fn run_demo() -> Result<(), String> {
+    let output = Compiler::new()
+        .with_target(TargetKind::Python)
+        .compile_llm_output(DEMO_INTENTS)?;
+    let program_output = Interpreter::new(42)
+        .run(&output.hl_program)
+        .map_err(|e| e.to_string())?;
 
-    let llm_response = run_inference(&source_code).await?;
+    println!("Naldom source:\n  {}\n", DEMO_SOURCE);
+    println!(
+        "== Plan ==\n{}\n",
+        explain(&output.validated_intent_graph, &output.defaulted)
+    );
+    println!(
+        "== Pseudo-code ==\n{}\n",
+        PythonCodeGenerator::new().generate(&output.hl_program)
+    );
+    println!("== Output ==\n{}", program_output);
+    println!(
+        "Next: write your own program in a `:::naldom` block in a .md file and run \
+`naldom-cli program.md --run` (needs a llama.cpp server and LLVM), or \
+`--target python` without LLVM."
+    );
+    Ok(())
+}
 
-    let intent_graph = parse_to_intent_graph(&llm_response).map_err(|e| {
+/// The built-in lints with the levels requested on the command line. Levels
+/// are applied allow, then warn, then deny, so the strictest flag wins.
+fn lint_registry(args: &Args) -> Result<LintRegistry, naldom_core::lints::LintError> {
+    let mut registry = LintRegistry::new();
+    let requested = [
+        (&args.allow_lints, LintLevel::Allow),
+        (&args.warn_lints, LintLevel::Warn),
+        (&args.deny_lints, LintLevel::Deny),
+    ];
+    for (names, level) in requested {
+        for name in names {
+            registry.set_level(name, level)?;
+        }
+    }
+    Ok(registry)
+}
+
+fn progress_sink(mode: Option<ProgressMode>) -> Box<dyn ProgressSink> {
+    match mode {
+        Some(ProgressMode::Json) => Box::new(JsonProgress::stderr()),
+        Some(ProgressMode::Human) => Box::new(HumanProgress::new()),
+        None => Box::new(naldom_core::progress::NoProgress),
+    }
+}
+
+/// The `--emit` format `--explain` stands for.
+const EMIT_EXPLAIN: &str = "explain";
+/// The `--emit` format that prints the validated plan as JSON. It needs no
+/// code, so the compiler stops after analysis.
+const EMIT_INTENT_JSON: &str = "intent-json";
+
+/// What the stages of a single-file build share: the invocation, and what
+/// each stage has produced so far.
+struct Build<'a> {
+    args: &'a Args,
+    /// Reports to `collector`, which the stages read the artifacts from.
+    compiler: &'a Arc<Compiler>,
+    collector: &'a CollectingObserver,
+    file_path: &'a Path,
+    source_code: &'a str,
+    program_text: &'a str,
+    target: TargetKind,
+    opt_level: u8,
+    error_mode: ErrorMode,
+    output_path: PathBuf,
+    output: Option<CompileOutput>,
+    artifacts: CollectedArtifacts,
+    work_dir: Option<WorkDir>,
+    link_plan: Option<(PathBuf, PathBuf, Vec<ToolCommand>)>,
+}
+
+/// The stages of a single-file build. The compiler runs inference through
+/// code generation in one go and reports their progress itself; the stages
+/// after inference pick up what it produced, so `--trace` and `--emit`
+/// attach to the stage each artifact comes from.
+fn build_stages<'a>() -> Vec<StageSpec<Build<'a>>> {
+    vec![
+        StageSpec::new(Stage::Inference, compile).with_trace(|build| {
+            (!build.args.from_intents && !build.args.locked).then(|| {
+                (
+                    "Prompt (as sent)",
+                    build.compiler.prompt(build.program_text).text,
+                )
+            })
+        }),
+        StageSpec::new(Stage::Parse, compiled).with_trace(|build| {
+            let output = build.output.as_ref()?;
+            Some((
+                "IntentGraph (Parsed)",
+                format!("{:#?}", output.intent_graph),
+            ))
+        }),
+        StageSpec::new(Stage::Analysis, compiled)
+            .with_trace(|build| {
+                let intents = build.artifacts.intents.as_ref()?;
+                Some(("IntentGraph (Validated)", format!("{:#?}", intents)))
+            })
+            .with_emit(emit_plan),
+        StageSpec::new(Stage::Lowering, compiled).with_trace(|build| {
+            let hl_program = build.artifacts.hl_program.as_ref()?;
+            Some((
+                "High-Level IR",
+                hl_program.to_string().trim_end().to_string(),
+            ))
+        }),
+        StageSpec::new(Stage::LowLevelLowering, compiled).with_trace(|build| {
+            let ll_program = build.artifacts.ll_program.as_ref()?;
+            Some((
+                "Low-Level IR",
+                ll_program.to_string().trim_end().to_string(),
+            ))
+        }),
+        StageSpec::new(Stage::Codegen, compiled)
+            .with_trace(|build| Some(("LLVM IR", build.artifacts.llvm_ir.clone()?)))
+            .with_emit(|build, format| {
+                (format == "llvm-ir")
+                    .then(|| build.artifacts.llvm_ir.clone().unwrap_or_default() + "\n")
+            }),
+        StageSpec::new(Stage::Link, link).reporting_progress(),
+    ]
+}
+
+/// Runs the compiler on the source and reports its warnings and pass diffs.
+fn compile(build: &mut Build) -> Result<Flow, String> {
+    // The stages run synchronously; inference still awaits on the CLI's runtime.
+    let output = tokio::task::block_in_place(|| {
+        tokio::runtime::Handle::current().block_on(compile_program(
+            build.args,
+            build.compiler,
+            build.file_path,
+            build.program_text,
+        ))
+    })?;
+    for warning in &output.warnings {
+        eprintln!("warning: {}", warning);
+    }
+    for pass_diff in &output.pass_diffs {
+        eprint!("{}", pass_diff.render());
+    }
+    build.artifacts = build.collector.take();
+    build.output = Some(output);
+    Ok(Flow::Continue)
+}
+
+/// A stage the compiler already ran as part of [`compile`].
+fn compiled(_build: &mut Build) -> Result<Flow, String> {
+    Ok(Flow::Continue)
+}
+
+/// The validated plan in `format`: as JSON, its dependency graph, or with
+/// `--explain` in plain language.
+fn emit_plan(build: &Build, format: &str) -> Option<String> {
+    let output = build.output.as_ref()?;
+    let intents = &output.validated_intent_graph;
+    match format {
+        EMIT_INTENT_JSON => serde_json::to_string_pretty(intents)
+            .ok()
+            .map(|json| json + "\n"),
+        "intent-graph" => Some(intent_graph_to_dot(intents)),
+        "intent-graph-mermaid" => Some(intent_graph_to_mermaid(intents)),
+        EMIT_EXPLAIN => {
+            let mut text = explain(intents, &output.defaulted) + "\n";
+            text.push_str(&format!("{}\n", describe_error_mode(build.error_mode)));
+            if let Some(cost) = &output.cost {
+                text.push_str(&format!("{}\n", cost.render()));
+            }
+            for warning in &output.warnings {
+                text.push_str(&format!("  warning: {}\n", warning));
+            }
+            Some(text)
+        }
+        _ => None,
+    }
+}
+
+/// With `--explain` or `--emit intent-graph`, the plan of an IntentGraph
+/// file holding intents this version of Naldom does not know, which cannot
+/// be analyzed. It is shown as written instead, after a note naming them.
+fn emit_unknown_plan(args: &Args, intent_json: &str) -> Option<String> {
+    let format = if args.explain {
+        EMIT_EXPLAIN
+    } else {
+        args.emit.as_deref()?
+    };
+    let raw = parse_raw_intents(intent_json).ok()?;
+    let unknown: Vec<String> = raw
+        .iter()
+        .filter(|raw| !raw.is_known())
+        .map(|raw| format!("'{}'", raw.name))
+        .collect();
+    if unknown.is_empty() {
+        return None;
+    }
+    let plan = match format {
+        EMIT_EXPLAIN => explain_raw(&raw) + "\n",
+        "intent-graph" => DependencyGraph::from_raw(&raw).to_dot(),
+        "intent-graph-mermaid" => DependencyGraph::from_raw(&raw).to_mermaid(),
+        _ => return None,
+    };
+    eprintln!(
+        "note: this version of Naldom does not know the intent {}; the plan is shown as written, without analysis",
+        unknown.join(", ")
+    );
+    Some(plan)
+}
+
+/// Links the generated code into the output, or writes it out for targets
+/// without a link step. With `--print-link-command` it prints the commands
+/// and stops instead.
+fn link(build: &mut Build) -> Result<Flow, String> {
+    let args = build.args;
+    let target = build.target;
+    let Some(output) = &build.output else {
+        return Err("There is no compiled program to link".to_string());
+    };
+    let temp_dir = env::temp_dir();
+    let work_dir = WorkDir::create(
+        &temp_dir,
+        &sha256_hex(build.source_code.as_bytes()),
+        &format!("{:?}", args),
+    )
+    .map_err(|e| {
         format!(
-            "Error parsing LLM response into IntentGraph: {}\n--- LLM Response ---\n{}\n--------------------",
-            e, llm_response
+            "Error creating a build directory in '{}': {}",
+            temp_dir.display(),
+            e
         )
     })?;
-    if args.trace {
-        println!("\n... IntentGraph (Parsed) ...\n{:#?}", intent_graph);
-    }
-    let mut analyzer = SemanticAnalyzer::new();
-    let validated_intent_graph = analyzer.analyze(&intent_graph)?;
-    if args.trace {
-        println!(
-            "\n... IntentGraph (Validated) ...\n{:#?}",
-            validated_intent_graph
-        );
+    let link_plan = link_commands(
+        target,
+        args.crate_type,
+        &build.output_path,
+        build.opt_level,
+        args.save_temps,
+        work_dir.path(),
+        args.runtime_features(RuntimeFeatures::required_by(&output.validated_intent_graph)),
+    )?;
+    if args.print_link_command {
+        let Some((_, _, commands)) = &link_plan else {
+            return Err(format!("The '{}' target has no link step", target));
+        };
+        for command in commands {
+            println!("{}", command.render());
+        }
+        return Ok(Flow::Stop);
     }
-    let mut lowering_context = LoweringContext::new();
-    let hl_program = lowering_context.lower(&validated_intent_graph);
-    if args.trace {
-        println!("\n... High-Level IR ...\n{:#?}", hl_program);
+
+    let code = &output.code;
+    let result = match &link_plan {
+        Some((ll_path, obj_path, commands)) => {
+            warn_on_llvm_mismatch(commands);
+            // Probe for unresolvable runtime symbols before invoking any tool.
+            link_check::check_symbols(target, code)
+                .map_err(|e| e.to_string())
+                .and_then(|()| {
+                    run_commands(
+                        code,
+                        ll_path,
+                        obj_path,
+                        &build.output_path,
+                        commands,
+                        args.save_temps,
+                    )
+                    .map_err(|e| e.to_string())
+                })
+        }
+        None => fs::write(&build.output_path, code).map_err(|e| e.to_string()),
+    };
+    build.work_dir = Some(work_dir);
+    build.link_plan = link_plan;
+    result
+        .map(|()| Flow::Continue)
+        .map_err(|e| format!("Failed to compile for target '{}': {}", target, e))
+}
+
+/// Compiles the source file at `file_path`: its intents with
+/// `--from-intents`, the answer in its lockfile with `--locked`, otherwise
+/// the model's answer, recorded in the lockfile with `--write-lock`.
+async fn compile_program(
+    args: &Args,
+    compiler: &Arc<Compiler>,
+    file_path: &Path,
+    program_text: &str,
+) -> Result<CompileOutput, String> {
+    if args.from_intents {
+        return Ok(compiler.compile_llm_output(program_text)?);
     }
-    let ll_program = lower_hl_to_ll(&hl_program);
-    if args.trace {
-        println!("\n... Low-Level IR ...\n{:#?}", ll_program);
+    let lock_path = lockfile::lock_path(file_path);
+    if args.locked {
+        let intent_json = lockfile::read(&lock_path, program_text)?;
+        return Ok(compiler.compile_llm_output(&intent_json)?);
     }
-    let target_triple_string = if args.target == "wasm" {
-        "wasm32-unknown-unknown".to_string()
+    let cached = if args.cache {
+        let cache = Cache::from_env()?;
+        let key = Cache::key(&[
+            program_text,
+            &format!("{:?}", args.sampling()),
+            &format!("{:?}", args.privacy()),
+            &args.loose_grammar.to_string(),
+        ]);
+        if let Some(intent_json) = cache.get(CacheKind::Llm, &key)? {
+            return Ok(compiler.compile_llm_output(&intent_json)?);
+        }
+        Some((cache, key))
     } else {
-        inkwell::targets::TargetMachine::get_default_triple()
-            .as_str()
-            .to_str()
-            .unwrap()
-            .to_string()
+        None
     };
-    generate_llvm_ir(&ll_program, &target_triple_string)
+    let output = Arc::clone(compiler)
+        .compile_async(program_text.to_string())
+        .await?;
+    if let Some((cache, key)) = cached {
+        cache.put(CacheKind::Llm, &key, &output.intent_json)?;
+    }
+    if args.write_lock {
+        lockfile::write(&lock_path, program_text, &output.intent_json)?;
+    }
+    Ok(output)
+}
+
+/// Assembles the `--manifest` record for a finished build.
+fn build_manifest(
+    args: &Args,
+    build_id: &BuildId,
+    config: &BuildConfig,
+    output: &CompileOutput,
+    output_path: &Path,
+    link_plan: Option<&(PathBuf, PathBuf, Vec<ToolCommand>)>,
+    stage_timings: &[(Stage, f64)],
+) -> Result<BuildManifest, String> {
+    let source_path = args.file_paths.first().cloned().unwrap_or_default();
+    let source = fs::read(&source_path)
+        .map_err(|e| format!("Error reading file '{}': {}", source_path.display(), e))?;
+    let current_dir = env::current_dir().unwrap_or_default();
+    let normalize = |path: &Path| normalize_path(path, &current_dir);
+    let kept = |path: &PathBuf| (args.save_temps && path.exists()).then(|| normalize(path));
+    let target = config.target.value;
+    // Already resolved successfully before inference.
+    let target_triple = target
+        .uses_llvm()
+        .then(|| resolve_triple(&target, args.target_triple.as_deref()).ok())
+        .flatten();
+    Ok(BuildManifest {
+        manifest_version: MANIFEST_VERSION,
+        build_id: args.manifest_timings.then(|| build_id.to_string()),
+        source: SourceRecord {
+            path: normalize(&source_path),
+            sha256: sha256_hex(&source),
+        },
+        intent_json_sha256: sha256_hex(output.intent_json.as_bytes()),
+        target: target.to_string(),
+        target_triple,
+        opt_level: config.opt_level.value,
+        seed: config.seed.value,
+        artifacts: Artifacts {
+            output: normalize(output_path),
+            llvm_ir: link_plan.and_then(|(ll_path, _, _)| kept(ll_path)),
+            object: link_plan.and_then(|(_, obj_path, _)| kept(obj_path)),
+        },
+        toolchain: link_plan
+            .map(|(_, _, commands)| commands.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|command| ToolRecord {
+                program: command.program.clone(),
+                version: command.version(),
+            })
+            .collect(),
+        timings: args.manifest_timings.then(|| {
+            stage_timings
+                .iter()
+                .map(|(stage, duration_ms)| StageTiming {
+                    stage: stage.as_str().to_string(),
+                    duration_ms: *duration_ms,
+                })
+                .collect()
+        }),
+    })
 }
 
-fn run_native_executable(executable_path: &Path) -> Result<(), std::io::Error> {
+/// Runs the executable. With `capture`, its stdout comes through a pipe,
+/// still printed as it arrives, and is returned.
+fn run_native_executable(
+    executable_path: &Path,
+    capture: bool,
+) -> Result<Option<String>, std::io::Error> {
     println!("\nRunning '{}'...\n", executable_path.display());
-    let mut command_path = PathBuf::from("./");
-    command_path.push(executable_path);
+    let command_path = executable_command_path(executable_path);
 
-    // Instead of capturing output, we inherit the stdio handles.
-    // This connects the child process's output directly to our terminal,
-    // which fixes the buffering issue and allows us to see output in real-time.
-    let status = Command::new(&command_path)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .status()?; // Use .status() instead of .output()
+    let (status, output) = if capture {
+        let mut child = Command::new(&command_path)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+        let stdout = child.stdout.take().expect("the child's stdout is piped");
+        let output = run_output::tee(stdout, std::io::stdout())?;
+        (child.wait()?, Some(output))
+    } else {
+        // Instead of capturing output, we inherit the stdio handles.
+        // This connects the child process's output directly to our terminal,
+        // which fixes the buffering issue and allows us to see output in real-time.
+        let status = Command::new(&command_path)
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .status()?; // Use .status() instead of .output()
+        (status, None)
+    };
 
     if !status.success() {
         eprintln!(
@@ -142,102 +1634,338 @@ fn run_native_executable(executable_path: &Path) -> Result<(), std::io::Error> {
         );
     }
 
+    Ok(output)
+}
+
+/// `--run --diff-previous`: runs the executable built from `source` and
+/// reports how its output differs from the previous run's, then keeps this
+/// output for the next run if `store` is set.
+fn run_and_diff(executable_path: &Path, source: &Path, store: bool) -> Result<(), String> {
+    let cache = Cache::from_env()?;
+    let previous = run_output::previous(&cache, source)?;
+    let output = run_native_executable(executable_path, true)
+        .map_err(|e| format!("Error running '{}': {}", executable_path.display(), e))?
+        .unwrap_or_default();
+    print!("\n{}", run_output::report(previous.as_deref(), &output));
+    if store {
+        run_output::store(&cache, source, &output)?;
+    }
     Ok(())
 }
 
-fn compile_native(llvm_ir: &str, output_path: &Path, opt_level: u8) -> Result<(), String> {
-    let (llc_path, clang_path) = match env::var("LLVM_PREFIX") {
-        Ok(prefix) => {
-            let llvm_path = PathBuf::from(prefix);
-            (llvm_path.join("bin/llc"), llvm_path.join("bin/clang"))
-        }
-        Err(_) => (PathBuf::from("llc"), PathBuf::from("clang")),
-    };
-    let temp_dir = std::env::temp_dir();
-    let stem = output_path.file_stem().unwrap().to_str().unwrap();
-    let ll_path = temp_dir.join(format!("{}.ll", stem));
-    fs::write(&ll_path, llvm_ir).map_err(|e| e.to_string())?;
-    let obj_path = temp_dir.join(format!("{}.o", stem));
-    let opt_flag = format!("-O{}", opt_level);
-    let llc_output = Command::new(&llc_path)
-        .arg(&opt_flag)
-        .arg("-filetype=obj")
-        .arg(&ll_path)
-        .arg("-o")
-        .arg(&obj_path)
-        .output()
-        .map_err(|e| e.to_string())?;
-    if !llc_output.status.success() {
-        return Err(String::from_utf8_lossy(&llc_output.stderr).to_string());
+/// The directory holding `libnaldom_runtime`. Cargo builds it next to this
+/// binary, so look there first; the working-directory-relative fallback only
+/// matters if the executable's location cannot be determined.
+fn runtime_library_dir() -> PathBuf {
+    env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| {
+            PathBuf::from(if cfg!(debug_assertions) {
+                "target/debug"
+            } else {
+                "target/release"
+            })
+        })
+}
+
+/// `Command` looks bare names like `a.out` up on `PATH`, so those need an explicit
+/// `./`. Absolute paths and paths with a directory component are used as-is.
+fn executable_command_path(executable_path: &Path) -> PathBuf {
+    let is_bare_name = executable_path
+        .parent()
+        .is_some_and(|parent| parent.as_os_str().is_empty());
+    if is_bare_name {
+        Path::new(".").join(executable_path)
+    } else {
+        executable_path.to_path_buf()
     }
-    let runtime_path = "runtime/native/naldom_runtime.c";
+}
 
-    let linker_path = if cfg!(debug_assertions) {
-        "target/debug"
+/// The intermediate `.ll` and `.o` paths for `output_path`: in the build's
+/// work directory, or next to the output when they are kept. Built from the
+/// raw file stem, so spaces and non-UTF-8 names survive intact.
+fn intermediate_paths(
+    output_path: &Path,
+    keep: bool,
+    work_dir: &Path,
+) -> Result<(PathBuf, PathBuf), String> {
+    let stem = output_path
+        .file_stem()
+        .ok_or_else(|| format!("Invalid output path '{}'", output_path.display()))?;
+    let temp_dir = if keep {
+        output_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
     } else {
-        "target/release"
-    };
-
-    let clang_output = Command::new(&clang_path)
-        .arg(&obj_path)
-        .arg(runtime_path)
-        .arg("-L")
-        .arg(linker_path)
-        .arg("-lnaldom_runtime")
-        .arg("-o")
-        .arg(output_path)
-        .arg(&opt_flag)
-        .output()
-        .map_err(|e| e.to_string())?;
+        work_dir.to_path_buf()
+    };
+    let with_extension = |extension: &str| {
+        let mut file_name = stem.to_os_string();
+        file_name.push(extension);
+        temp_dir.join(file_name)
+    };
+    Ok((with_extension(".ll"), with_extension(".o")))
+}
 
-    if !clang_output.status.success() {
-        return Err(String::from_utf8_lossy(&clang_output.stderr).to_string());
+/// Rejects flags that do not fit the requested crate type, before any
+/// inference runs.
+fn check_crate_type(args: &Args, target: TargetKind) -> Result<(), String> {
+    let crate_type = args.crate_type;
+    if crate_type == CrateType::Bin {
+        if args.entry_name.is_some() {
+            return Err(
+                "--entry-name needs --crate-type obj or staticlib: an executable's entry point is always `main`"
+                    .to_string(),
+            );
+        }
+        return Ok(());
+    }
+    if target != TargetKind::Native {
+        return Err(format!(
+            "--crate-type {} is only available for the native target",
+            crate_type.name()
+        ));
+    }
+    if args.run {
+        return Err(format!(
+            "--run cannot be used with --crate-type {}: it produces no executable",
+            crate_type.name()
+        ));
     }
-    let _ = fs::remove_file(&ll_path);
-    let _ = fs::remove_file(&obj_path);
     Ok(())
 }
 
-fn compile_wasm(llvm_ir: &str, output_path: &Path, opt_level: u8) -> Result<(), String> {
-    let (llc_path, wasm_ld_path) = match env::var("LLVM_PREFIX") {
-        Ok(prefix) => {
-            let llvm_path = PathBuf::from(prefix);
-            (llvm_path.join("bin/llc"), llvm_path.join("bin/wasm-ld"))
-        }
-        Err(_) => (PathBuf::from("llc"), PathBuf::from("wasm-ld")),
-    };
-    let temp_dir = std::env::temp_dir();
-    let stem = output_path.file_stem().unwrap().to_str().unwrap();
-    let ll_path = temp_dir.join(format!("{}.ll", stem));
-    fs::write(&ll_path, llvm_ir).map_err(|e| e.to_string())?;
-    let obj_path = temp_dir.join(format!("{}.o", stem));
-    let opt_flag = format!("-O{}", opt_level);
-    let llc_output = Command::new(&llc_path)
-        .arg(&opt_flag)
-        .arg("-march=wasm32")
-        .arg("-filetype=obj")
-        .arg(&ll_path)
-        .arg("-o")
-        .arg(&obj_path)
-        .output()
-        .map_err(|e| e.to_string())?;
-    if !llc_output.status.success() {
-        return Err(String::from_utf8_lossy(&llc_output.stderr).to_string());
-    }
-    let wasm_ld_output = Command::new(&wasm_ld_path)
-        .arg(&obj_path)
-        .arg("-o")
-        .arg(output_path)
-        .arg("--no-entry")
-        .arg("--export-all")
-        .arg("--allow-undefined")
-        .arg(&opt_flag)
-        .output()
-        .map_err(|e| e.to_string())?;
-    if !wasm_ld_output.status.success() {
-        return Err(String::from_utf8_lossy(&wasm_ld_output.stderr).to_string());
+/// Warns about tools in `commands` from another LLVM version than the one the
+/// IR was generated for; `llc` may reject it or miscompile.
+fn warn_on_llvm_mismatch(commands: &[ToolCommand]) {
+    for warning in toolchain::version_mismatches(commands, LLVM_MAJOR) {
+        eprintln!("warning: {}", warning);
+    }
+}
+
+/// The tool commands that turn the generated IR into `output_path`, or `None`
+/// for targets without a link step. Executables link against the smallest
+/// prebuilt runtime providing `runtime_features`.
+fn link_commands(
+    target: TargetKind,
+    crate_type: CrateType,
+    output_path: &Path,
+    opt_level: u8,
+    keep_intermediates: bool,
+    work_dir: &Path,
+    runtime_features: RuntimeFeatures,
+) -> Result<Option<(PathBuf, PathBuf, Vec<ToolCommand>)>, String> {
+    let toolchain = Toolchain::from_env();
+    let (ll_path, obj_path) = intermediate_paths(output_path, keep_intermediates, work_dir)?;
+    let commands = match (target, crate_type) {
+        // The object file is the output itself.
+        (TargetKind::Native, CrateType::Obj) => {
+            let commands = toolchain.object_commands(&ll_path, output_path, opt_level);
+            return Ok(Some((ll_path, output_path.to_path_buf(), commands)));
+        }
+        (TargetKind::Native, CrateType::Staticlib) => {
+            toolchain.staticlib_commands(&ll_path, &obj_path, output_path, opt_level)
+        }
+        (TargetKind::Native, CrateType::Bin) => toolchain.native_commands(
+            &ll_path,
+            &obj_path,
+            output_path,
+            &runtime_variant::library_dir(&runtime_library_dir(), runtime_features),
+            opt_level,
+        ),
+        (TargetKind::Wasm, _) => {
+            toolchain.wasm_commands(&ll_path, &obj_path, output_path, opt_level)
+        }
+        (TargetKind::Python, _) => return Ok(None),
+    };
+    Ok(Some((ll_path, obj_path, commands)))
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use naldom_core::fs_policy::PathArg;
+
+    #[test]
+    fn test_only_bare_names_get_a_dot_slash_prefix() {
+        assert_eq!(
+            executable_command_path(Path::new("a.out")),
+            PathBuf::from("./a.out")
+        );
+        assert_eq!(
+            executable_command_path(Path::new("/tmp/my project/a.out")),
+            PathBuf::from("/tmp/my project/a.out")
+        );
+        assert_eq!(
+            executable_command_path(Path::new("build dir/a.out")),
+            PathBuf::from("build dir/a.out")
+        );
+    }
+
+    fn parse(args: &[&str]) -> Args {
+        Args::try_parse_from(["naldom-cli"].iter().chain(args)).unwrap()
+    }
+
+    #[test]
+    fn test_deterministic_sets_what_it_implies() {
+        // Arrange
+        let mut args = parse(&["--deterministic", "--multi", "b.md", "a.md"]);
+
+        // Act
+        args.apply_deterministic().unwrap();
+
+        // Assert
+        assert_eq!(args.seed, Some(0));
+        assert!(args.locked);
+        assert_eq!(
+            args.file_paths,
+            [PathBuf::from("a.md"), PathBuf::from("b.md")]
+        );
+        assert_eq!(args.sampling(), Sampling::DETERMINISTIC);
+        let features = RuntimeFeatures {
+            async_sleep: false,
+            parallel_sort: true,
+        };
+        assert_eq!(args.runtime_features(features), RuntimeFeatures::default());
+    }
+
+    #[test]
+    fn test_diff_previous_options_need_what_they_build_on() {
+        // Act
+        let without_run = Args::try_parse_from(["naldom-cli", "a.md", "--diff-previous"]);
+        let without_diff =
+            Args::try_parse_from(["naldom-cli", "a.md", "--run", "--no-store-output"]);
+        let args = parse(&["a.md", "--run", "--diff-previous", "--no-store-output"]);
+
+        // Assert
+        assert!(without_run.is_err());
+        assert!(without_diff.is_err());
+        assert!(args.diff_previous && args.no_store_output);
+    }
+
+    #[test]
+    fn test_fs_flags_grant_directories_next_to_the_source_file_or_deny_all() {
+        // Arrange
+        let source = Path::new("/home/ada/docs/program.md");
+        let granted = parse(&["--allow-fs", "../assets", "--allow-fs", "/srv/data", "a.md"]);
+        let denied = parse(&["--allow-fs", "/srv/data", "--deny-fs", "a.md"]);
+
+        // Act
+        let granted = granted.fs_policy(source);
+        let denied = denied.fs_policy(source);
+
+        // Assert
+        assert_eq!(granted.check(PathArg::Literal("notes.txt")), Ok(()));
+        assert_eq!(granted.check(PathArg::Literal("../assets/a.csv")), Ok(()));
+        assert_eq!(granted.check(PathArg::Literal("/srv/data/b.csv")), Ok(()));
+        assert!(granted.check(PathArg::Literal("../secrets.txt")).is_err());
+        assert!(denied.check(PathArg::Literal("/srv/data/b.csv")).is_err());
+    }
+
+    #[test]
+    fn test_deterministic_lists_every_conflicting_option() {
+        // Arrange
+        let mut agreeing = parse(&["--deterministic", "--seed", "0", "program.md"]);
+        let mut conflicting = parse(&[
+            "--deterministic",
+            "--seed",
+            "7",
+            "--llm-temperature",
+            "0.9",
+            "--manifest-timings",
+            "program.md",
+        ]);
+
+        // Act
+        let agreed = agreeing.apply_deterministic();
+        let error = conflicting.apply_deterministic().unwrap_err();
+
+        // Assert
+        assert_eq!(agreed, Ok(()));
+        assert_eq!(
+            error,
+            "--deterministic conflicts with options given on the command line:\n  --seed 7: it implies --seed 0\n  --llm-temperature 0.9: it implies --llm-temperature 0\n  --manifest-timings: it keeps timestamps out of the manifest"
+        );
+    }
+
+    #[test]
+    fn test_program_names_come_from_the_file_name_before_the_first_dot() {
+        assert_eq!(program_name(Path::new("dir/demo1.md")).unwrap(), "demo1");
+        assert_eq!(
+            program_name(Path::new("demo_2.intents.json")).unwrap(),
+            "demo_2"
+        );
+        assert!(
+            program_name(Path::new("my-demo.md"))
+                .unwrap_err()
+                .contains("'my-demo' is not a valid C identifier")
+        );
+    }
+
+    #[test]
+    fn test_intermediate_paths_keep_spaces_and_unicode() {
+        let work_dir = Path::new("/tmp/naldom-3fa9c2");
+        let (ll_path, obj_path) =
+            intermediate_paths(Path::new("/tmp/my project/prögram ü.wasm"), false, work_dir)
+                .unwrap();
+
+        assert_eq!(ll_path.file_name().unwrap(), "prögram ü.ll");
+        assert_eq!(obj_path.file_name().unwrap(), "prögram ü.o");
+        assert_eq!(ll_path.parent().unwrap(), work_dir);
+    }
+
+    #[test]
+    fn test_kept_intermediates_sit_next_to_the_output() {
+        let work_dir = Path::new("/tmp/naldom-3fa9c2");
+        let (ll_path, _) = intermediate_paths(Path::new("build/prog"), true, work_dir).unwrap();
+        let (bare_ll_path, _) = intermediate_paths(Path::new("prog"), true, work_dir).unwrap();
+
+        assert_eq!(ll_path, PathBuf::from("build/prog.ll"));
+        assert_eq!(bare_ll_path, PathBuf::from("prog.ll"));
+    }
+
+    /// The test files under `tests/` in `dir`, relative to `root`.
+    fn test_files(root: &Path, dir: &Path, files: &mut Vec<String>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                test_files(root, &path, files);
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                let relative = path.strip_prefix(root).unwrap();
+                files.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_file_under_tests_is_a_cargo_target() {
+        // Arrange: Cargo only builds a test outside a crate's own `tests/`
+        // when a manifest names its path.
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+        let mut files = Vec::new();
+        test_files(&root, &root.join("tests"), &mut files);
+
+        // Act
+        let manifests: String = std::fs::read_dir(root.join("crates"))
+            .unwrap()
+            .filter_map(|entry| {
+                std::fs::read_to_string(entry.unwrap().path().join("Cargo.toml")).ok()
+            })
+            .collect();
+        let unbuilt: Vec<&String> = files
+            .iter()
+            .filter(|file| !manifests.contains(&format!("path = \"../../{}\"", file)))
+            .collect();
+
+        // Assert
+        assert!(!files.is_empty());
+        assert!(
+            unbuilt.is_empty(),
+            "no [[test]] target builds {:?}",
+            unbuilt
+        );
     }
-    let _ = fs::remove_file(&ll_path);
-    let _ = fs::remove_file(&obj_path);
-    Ok(())
 }
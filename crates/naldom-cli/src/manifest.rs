@@ -0,0 +1,209 @@
+// crates/naldom-cli/src/manifest.rs
+
+//! The build manifest written by `--manifest`: a machine-readable record of
+//! what a build consumed and produced, for IDE plugins and build systems.
+//!
+//! Two builds of the same inputs write the same manifest: the build ID and
+//! the stage timings, which differ every time, are only recorded on request,
+//! and paths are written relative to the working directory.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Bumped whenever a field is renamed or removed.
+pub const MANIFEST_VERSION: u32 = 2;
+
+/// The contents of `<output>.naldom-manifest.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildManifest {
+    pub manifest_version: u32,
+    /// The ID printed when the build finished, also naming its temp directory.
+    /// Only recorded with `--manifest-timings`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_id: Option<String>,
+    pub source: SourceRecord,
+    /// SHA-256 of the raw intent JSON the program was compiled from.
+    pub intent_json_sha256: String,
+    pub target: String,
+    /// `None` for targets that do not go through LLVM.
+    pub target_triple: Option<String>,
+    pub opt_level: u8,
+    /// The seed of the program's random numbers, if it was fixed.
+    pub seed: Option<u64>,
+    pub artifacts: Artifacts,
+    /// The external tools that ran, in order.
+    pub toolchain: Vec<ToolRecord>,
+    /// Wall-clock time of each pipeline stage, in completion order. Only
+    /// recorded with `--manifest-timings`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<Vec<StageTiming>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SourceRecord {
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+/// Files produced by the build. Intermediates are listed only when kept
+/// (`--save-temps`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Artifacts {
+    pub output: PathBuf,
+    pub llvm_ir: Option<PathBuf>,
+    pub object: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolRecord {
+    pub program: PathBuf,
+    /// The first line of `<program> --version`, if it could be run.
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StageTiming {
+    pub stage: String,
+    pub duration_ms: f64,
+}
+
+impl BuildManifest {
+    /// Writes the manifest as pretty-printed JSON.
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json + "\n")
+            .map_err(|e| format!("Error writing manifest '{}': {}", path.display(), e))
+    }
+}
+
+/// `<output>.naldom-manifest.json`, next to the build output.
+pub fn manifest_path(output_path: &Path) -> PathBuf {
+    let mut path = OsString::from(output_path.as_os_str());
+    path.push(".naldom-manifest.json");
+    PathBuf::from(path)
+}
+
+/// `path` as recorded in a manifest: relative to `base` when it lies inside
+/// it, and without `.` components, so the manifest does not depend on where
+/// the project was checked out.
+pub fn normalize_path(path: &Path, base: &Path) -> PathBuf {
+    let relative = path.strip_prefix(base).unwrap_or(path);
+    relative
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+/// The lowercase hex SHA-256 of `bytes`.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_manifest() -> BuildManifest {
+        BuildManifest {
+            manifest_version: MANIFEST_VERSION,
+            build_id: Some("3fa9c2".to_string()),
+            source: SourceRecord {
+                path: PathBuf::from("program.md"),
+                sha256: sha256_hex(b"source"),
+            },
+            intent_json_sha256: sha256_hex(b"[]"),
+            target: "native".to_string(),
+            target_triple: Some("x86_64-unknown-linux-gnu".to_string()),
+            opt_level: 2,
+            seed: Some(7),
+            artifacts: Artifacts {
+                output: PathBuf::from("a.out"),
+                llvm_ir: Some(PathBuf::from("a.ll")),
+                object: None,
+            },
+            toolchain: vec![ToolRecord {
+                program: PathBuf::from("llc"),
+                version: None,
+            }],
+            timings: Some(vec![StageTiming {
+                stage: "parse".to_string(),
+                duration_ms: 0.5,
+            }]),
+        }
+    }
+
+    #[test]
+    fn test_manifest_round_trips_through_json() {
+        // Arrange
+        let manifest = sample_manifest();
+
+        // Act
+        let json = serde_json::to_string(&manifest).unwrap();
+        let parsed: BuildManifest = serde_json::from_str(&json).unwrap();
+
+        // Assert
+        assert_eq!(parsed, manifest);
+    }
+
+    #[test]
+    fn test_manifest_path_and_hash() {
+        assert_eq!(
+            manifest_path(Path::new("out/my prog")),
+            PathBuf::from("out/my prog.naldom-manifest.json")
+        );
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_manifest_without_timings_leaves_them_out() {
+        // Arrange
+        let manifest = BuildManifest {
+            build_id: None,
+            timings: None,
+            ..sample_manifest()
+        };
+
+        // Act
+        let json = serde_json::to_string(&manifest).unwrap();
+
+        // Assert
+        assert!(!json.contains("build_id"));
+        assert!(!json.contains("timings"));
+        assert_eq!(
+            serde_json::from_str::<BuildManifest>(&json).unwrap(),
+            manifest
+        );
+    }
+
+    #[test]
+    fn test_paths_are_recorded_relative_to_the_working_directory() {
+        let base = Path::new("/home/me/project");
+        assert_eq!(
+            normalize_path(Path::new("/home/me/project/./out/prog"), base),
+            PathBuf::from("out/prog")
+        );
+        assert_eq!(
+            normalize_path(Path::new("./prog.md"), base),
+            PathBuf::from("prog.md")
+        );
+        assert_eq!(
+            normalize_path(Path::new("/tmp/prog.md"), base),
+            PathBuf::from("/tmp/prog.md")
+        );
+    }
+}
@@ -0,0 +1,119 @@
+// crates/naldom-cli/src/object_size.rs
+
+//! The machine code size of each function in an object file, for `--stats`.
+
+use object::{Object, ObjectSection, ObjectSymbol, SymbolKind};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// A function symbol: its name, address and recorded size.
+struct FunctionSymbol {
+    name: String,
+    address: u64,
+    /// `0` where the format records no sizes, as Mach-O does.
+    size: u64,
+    section: Option<usize>,
+}
+
+/// The size in bytes of every function defined in the object file at
+/// `path`, by name. Mach-O's leading underscore is dropped, so the names
+/// are the ones the IR uses.
+pub fn function_sizes(path: &Path) -> Result<BTreeMap<String, u64>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("cannot read '{}': {}", path.display(), e))?;
+    let file = object::File::parse(&*bytes)
+        .map_err(|e| format!("cannot read '{}' as an object file: {}", path.display(), e))?;
+    let underscored = file.format() == object::BinaryFormat::MachO;
+    let symbols = file
+        .symbols()
+        .filter(|symbol| symbol.is_definition() && symbol.kind() == SymbolKind::Text)
+        .filter_map(|symbol| {
+            let name = symbol.name().ok()?;
+            let name = if underscored {
+                name.strip_prefix('_').unwrap_or(name)
+            } else {
+                name
+            };
+            Some(FunctionSymbol {
+                name: name.to_string(),
+                address: symbol.address(),
+                size: symbol.size(),
+                section: symbol.section_index().map(|index| index.0),
+            })
+        })
+        .collect();
+    let section_ends = file
+        .sections()
+        .map(|section| (section.index().0, section.address() + section.size()))
+        .collect();
+    Ok(sizes(symbols, &section_ends))
+}
+
+/// The size of each symbol: the recorded one, or else the distance to the
+/// next symbol in its section (or to the section's end).
+fn sizes(
+    mut symbols: Vec<FunctionSymbol>,
+    section_ends: &BTreeMap<usize, u64>,
+) -> BTreeMap<String, u64> {
+    symbols.sort_by_key(|symbol| (symbol.section, symbol.address));
+    let mut sizes = BTreeMap::new();
+    for (position, symbol) in symbols.iter().enumerate() {
+        let size = if symbol.size > 0 {
+            symbol.size
+        } else {
+            let end = symbols[position + 1..]
+                .iter()
+                .find(|next| next.section == symbol.section && next.address > symbol.address)
+                .map(|next| next.address)
+                .or_else(|| {
+                    symbol
+                        .section
+                        .and_then(|section| section_ends.get(&section).copied())
+                })
+                .unwrap_or(symbol.address);
+            end - symbol.address
+        };
+        sizes.insert(symbol.name.clone(), size);
+    }
+    sizes
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol(name: &str, address: u64, size: u64) -> FunctionSymbol {
+        FunctionSymbol {
+            name: name.to_string(),
+            address,
+            size,
+            section: Some(1),
+        }
+    }
+
+    #[test]
+    fn test_unsized_symbols_extend_to_the_next_one_or_the_section_end() {
+        // Arrange: Mach-O style, no sizes recorded.
+        let symbols = vec![symbol("run_b", 0x40, 0), symbol("main", 0x0, 0)];
+        let section_ends = BTreeMap::from([(1, 0x70)]);
+
+        // Act
+        let sizes = sizes(symbols, &section_ends);
+
+        // Assert
+        assert_eq!(
+            sizes,
+            BTreeMap::from([("main".to_string(), 0x40), ("run_b".to_string(), 0x30)])
+        );
+    }
+
+    #[test]
+    fn test_recorded_sizes_are_kept() {
+        // Act
+        let sizes = sizes(vec![symbol("main", 0x10, 12)], &BTreeMap::new());
+
+        // Assert
+        assert_eq!(sizes, BTreeMap::from([("main".to_string(), 12)]));
+    }
+}
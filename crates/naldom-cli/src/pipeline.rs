@@ -0,0 +1,317 @@
+// crates/naldom-cli/src/pipeline.rs
+
+//! The build as a list of stages and the executor that runs them.
+//!
+//! Each stage is a function over the build's state. The executor handles
+//! what every stage shares: `--trace` output, progress for the stages the
+//! compiler does not report itself, and stopping early once `--emit`,
+//! `--explain` or `--check` has what it asked for.
+
+use naldom_core::progress::{ProgressEvent, ProgressSink, Stage};
+use std::io::Write;
+use std::time::Instant;
+
+/// Whether the build goes on after a stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    Stop,
+}
+
+/// One stage of a build over the state `S`.
+pub struct StageSpec<S> {
+    pub stage: Stage,
+    run: fn(&mut S) -> Result<Flow, String>,
+    trace: fn(&S) -> Option<(&'static str, String)>,
+    emit: fn(&S, &str) -> Option<String>,
+    reports_progress: bool,
+}
+
+impl<S> StageSpec<S> {
+    /// A stage that runs `run` and has nothing to trace or emit.
+    pub fn new(stage: Stage, run: fn(&mut S) -> Result<Flow, String>) -> Self {
+        StageSpec {
+            stage,
+            run,
+            trace: |_| None,
+            emit: |_, _| None,
+            reports_progress: false,
+        }
+    }
+
+    /// Traces the stage's output under a title once it has run, even if it
+    /// failed: a failed inference still has the prompt it sent.
+    pub fn with_trace(mut self, trace: fn(&S) -> Option<(&'static str, String)>) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// Renders the stage's output in the `--emit` format it is given, or
+    /// `None` if the stage does not produce that format.
+    pub fn with_emit(mut self, emit: fn(&S, &str) -> Option<String>) -> Self {
+        self.emit = emit;
+        self
+    }
+
+    /// Has the executor report the stage's progress. The compiler reports
+    /// its own stages, so only the CLI's stages need this.
+    pub fn reporting_progress(mut self) -> Self {
+        self.reports_progress = true;
+        self
+    }
+}
+
+/// What the command line asks of the executor.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExecOptions<'a> {
+    pub trace: bool,
+    /// The output to print instead of building on, e.g. `llvm-ir`.
+    pub emit: Option<&'a str>,
+    /// The last stage to run, as for `--check`.
+    pub stop_after: Option<Stage>,
+}
+
+/// Runs `stages` in order on `state`, writing traces and emitted output to
+/// `out`. Returns the stage the build stopped after, or `None` if every
+/// stage ran; the first error ends the build.
+pub fn execute<S>(
+    stages: &[StageSpec<S>],
+    state: &mut S,
+    options: &ExecOptions,
+    progress: &dyn ProgressSink,
+    out: &mut dyn Write,
+) -> Result<Option<Stage>, String> {
+    let write_error = |e: std::io::Error| format!("Error writing the output: {}", e);
+    for spec in stages {
+        let result = run_stage(spec, state, progress);
+        if options.trace
+            && let Some((title, text)) = (spec.trace)(state)
+        {
+            writeln!(out, "\n... {} ...\n{}", title, text).map_err(write_error)?;
+        }
+        if result? == Flow::Stop {
+            return Ok(Some(spec.stage));
+        }
+        if let Some(emitted) = options.emit.and_then(|format| (spec.emit)(state, format)) {
+            write!(out, "{}", emitted).map_err(write_error)?;
+            return Ok(Some(spec.stage));
+        }
+        if options.stop_after == Some(spec.stage) {
+            return Ok(Some(spec.stage));
+        }
+    }
+    Ok(None)
+}
+
+fn run_stage<S>(
+    spec: &StageSpec<S>,
+    state: &mut S,
+    progress: &dyn ProgressSink,
+) -> Result<Flow, String> {
+    if !spec.reports_progress {
+        return (spec.run)(state);
+    }
+    progress.emit(&ProgressEvent::StageStart { stage: spec.stage });
+    let start = Instant::now();
+    let result = (spec.run)(state);
+    if let Err(e) = &result {
+        progress.emit(&ProgressEvent::Diagnostic {
+            level: "error",
+            message: e,
+        });
+    }
+    progress.emit(&ProgressEvent::stage_end(spec.stage, start.elapsed()));
+    result
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use naldom_core::progress::NoProgress;
+    use std::sync::Mutex;
+
+    /// The stages that ran, in order.
+    #[derive(Default)]
+    struct Ran(Vec<&'static str>);
+
+    fn stages() -> Vec<StageSpec<Ran>> {
+        vec![
+            StageSpec::new(Stage::Parse, |ran: &mut Ran| {
+                ran.0.push("parse");
+                Ok(Flow::Continue)
+            })
+            .with_trace(|ran| Some(("Parsed", format!("{} stage(s)", ran.0.len())))),
+            StageSpec::new(Stage::Analysis, |ran: &mut Ran| {
+                ran.0.push("analysis");
+                Ok(Flow::Continue)
+            })
+            .with_emit(|_, format| (format == "plan").then(|| "the plan\n".to_string())),
+            StageSpec::new(Stage::Codegen, |ran: &mut Ran| {
+                ran.0.push("codegen");
+                Ok(Flow::Continue)
+            })
+            .with_emit(|_, format| (format == "code").then(|| "the code\n".to_string())),
+            StageSpec::new(Stage::Link, |ran: &mut Ran| {
+                ran.0.push("link");
+                Ok(Flow::Continue)
+            }),
+        ]
+    }
+
+    fn run(
+        stages: &[StageSpec<Ran>],
+        options: ExecOptions,
+    ) -> (Ran, Result<Option<Stage>, String>, String) {
+        let mut ran = Ran::default();
+        let mut out = Vec::new();
+        let result = execute(stages, &mut ran, &options, &NoProgress, &mut out);
+        (ran, result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_every_stage_runs_without_options() {
+        // Act
+        let (ran, result, out) = run(&stages(), ExecOptions::default());
+
+        // Assert
+        assert_eq!(ran.0, ["parse", "analysis", "codegen", "link"]);
+        assert_eq!(result, Ok(None));
+        assert_eq!(out, "");
+    }
+
+    #[test]
+    fn test_emit_stops_after_the_stage_that_produces_the_format() {
+        // Act
+        let (plan_ran, plan, plan_out) = run(
+            &stages(),
+            ExecOptions {
+                emit: Some("plan"),
+                ..ExecOptions::default()
+            },
+        );
+        let (code_ran, code, code_out) = run(
+            &stages(),
+            ExecOptions {
+                emit: Some("code"),
+                ..ExecOptions::default()
+            },
+        );
+
+        // Assert
+        assert_eq!(plan_ran.0, ["parse", "analysis"]);
+        assert_eq!(plan, Ok(Some(Stage::Analysis)));
+        assert_eq!(plan_out, "the plan\n");
+        assert_eq!(code_ran.0, ["parse", "analysis", "codegen"]);
+        assert_eq!(code, Ok(Some(Stage::Codegen)));
+        assert_eq!(code_out, "the code\n");
+    }
+
+    #[test]
+    fn test_check_stops_after_analysis() {
+        // Act
+        let (ran, result, out) = run(
+            &stages(),
+            ExecOptions {
+                stop_after: Some(Stage::Analysis),
+                ..ExecOptions::default()
+            },
+        );
+
+        // Assert
+        assert_eq!(ran.0, ["parse", "analysis"]);
+        assert_eq!(result, Ok(Some(Stage::Analysis)));
+        assert_eq!(out, "");
+    }
+
+    #[test]
+    fn test_a_failed_stage_ends_the_build_with_its_error() {
+        // Arrange
+        let mut stages = stages();
+        stages[2] = StageSpec::new(Stage::Codegen, |_| Err("no LLVM".to_string()));
+
+        // Act
+        let (ran, result, _) = run(
+            &stages,
+            ExecOptions {
+                emit: Some("code"),
+                ..ExecOptions::default()
+            },
+        );
+
+        // Assert
+        assert_eq!(ran.0, ["parse", "analysis"]);
+        assert_eq!(result, Err("no LLVM".to_string()));
+    }
+
+    #[test]
+    fn test_a_stage_can_stop_the_build_itself() {
+        // Arrange
+        let mut stages = stages();
+        stages[1] = StageSpec::new(Stage::Analysis, |_| Ok(Flow::Stop));
+
+        // Act
+        let (ran, result, _) = run(&stages, ExecOptions::default());
+
+        // Assert
+        assert_eq!(ran.0, ["parse"]);
+        assert_eq!(result, Ok(Some(Stage::Analysis)));
+    }
+
+    #[test]
+    fn test_traces_follow_the_stage_that_produced_them() {
+        // Act
+        let (_, _, out) = run(
+            &stages(),
+            ExecOptions {
+                trace: true,
+                emit: Some("plan"),
+                ..ExecOptions::default()
+            },
+        );
+
+        // Assert
+        assert_eq!(out, "\n... Parsed ...\n1 stage(s)\nthe plan\n");
+    }
+
+    struct Events(Mutex<Vec<String>>);
+
+    impl ProgressSink for Events {
+        fn emit(&self, event: &ProgressEvent) {
+            let name = match event {
+                ProgressEvent::StageStart { stage } => format!("start {}", stage.as_str()),
+                ProgressEvent::StageEnd { stage, .. } => format!("end {}", stage.as_str()),
+                ProgressEvent::Diagnostic { level, message } => format!("{}: {}", level, message),
+                ProgressEvent::ArtifactWritten { path } => format!("wrote {}", path),
+            };
+            self.0.lock().unwrap().push(name);
+        }
+    }
+
+    #[test]
+    fn test_only_cli_stages_report_their_own_progress() {
+        // Arrange
+        let events = Events(Mutex::default());
+        let stages = [
+            StageSpec::new(Stage::Codegen, |_: &mut Ran| Ok(Flow::Continue)),
+            StageSpec::new(Stage::Link, |_| Err("could not start `clang`".to_string()))
+                .reporting_progress(),
+        ];
+
+        // Act
+        let result = execute(
+            &stages,
+            &mut Ran::default(),
+            &ExecOptions::default(),
+            &events,
+            &mut Vec::new(),
+        );
+
+        // Assert
+        assert!(result.is_err());
+        assert_eq!(
+            *events.0.lock().unwrap(),
+            ["start link", "error: could not start `clang`", "end link"]
+        );
+    }
+}
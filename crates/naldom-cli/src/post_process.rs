@@ -0,0 +1,213 @@
+// crates/naldom-cli/src/post_process.rs
+
+//! Commands run on the finished artifact (`--post-process`), e.g. to strip,
+//! sign, optimize or upload it as part of the build.
+//!
+//! A hook is a command line such as `wasm-opt -O2 {output} -o {output}`. It is
+//! split into words first, honouring single and double quotes, and the
+//! placeholders are substituted into each word afterwards, so a path with
+//! spaces stays one argument. No shell is involved; hooks that need one can
+//! run `sh -c '...'` themselves. Hooks run in order, and the first one that
+//! fails fails the build.
+
+use crate::toolchain::ToolCommand;
+use naldom_core::targets::TargetKind;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// What the placeholders of a hook stand for.
+#[derive(Debug, Clone, Copy)]
+pub struct HookContext<'a> {
+    /// `{output}`: the artifact the build produced.
+    pub output: &'a Path,
+    /// `{target}`: `native`, `wasm` or `python`.
+    pub target: TargetKind,
+    /// `{source}`: the source file the artifact was built from.
+    pub source: &'a Path,
+}
+
+impl HookContext<'_> {
+    /// `word` with every placeholder replaced.
+    fn substitute(&self, word: &str) -> String {
+        word.replace("{output}", &self.output.to_string_lossy())
+            .replace("{target}", &self.target.to_string())
+            .replace("{source}", &self.source.to_string_lossy())
+    }
+}
+
+/// The command `hook` runs in `context`.
+pub fn hook_command(hook: &str, context: &HookContext) -> Result<ToolCommand, String> {
+    let words = split_words(hook)?;
+    let Some((program, args)) = words.split_first() else {
+        return Err("a post-process command cannot be empty".to_string());
+    };
+    Ok(ToolCommand {
+        program: PathBuf::from(context.substitute(program)),
+        args: args
+            .iter()
+            .map(|arg| OsString::from(context.substitute(arg)))
+            .collect(),
+    })
+}
+
+/// Runs `hooks` in order, stopping at the first that cannot be started or
+/// exits with a non-zero status.
+pub fn run_hooks(hooks: &[String], context: &HookContext) -> Result<(), String> {
+    for hook in hooks {
+        let command = hook_command(hook, context)
+            .map_err(|e| format!("Post-process Error: '{}': {}", hook, e))?;
+        command
+            .run()
+            .map_err(|e| format!("Post-process Error: {}", e))?;
+    }
+    Ok(())
+}
+
+/// A note pointing wasm builds at `wasm-opt` when it is installed and no hook
+/// runs it already. `installed` is only asked when the note could apply.
+pub fn wasm_opt_suggestion(
+    target: TargetKind,
+    hooks: &[String],
+    installed: impl FnOnce() -> bool,
+) -> Option<String> {
+    if target != TargetKind::Wasm || hooks.iter().any(|hook| hook.contains("wasm-opt")) {
+        return None;
+    }
+    installed().then(|| {
+        "`wasm-opt` is installed; `--post-process \"wasm-opt -O2 {output} -o {output}\"` would shrink the module"
+            .to_string()
+    })
+}
+
+/// Whether `wasm-opt` can be run from `PATH`.
+pub fn wasm_opt_installed() -> bool {
+    ToolCommand {
+        program: PathBuf::from("wasm-opt"),
+        args: Vec::new(),
+    }
+    .version()
+    .is_some()
+}
+
+/// Splits `command` at unquoted whitespace. Quotes group words and are
+/// removed; there are no escapes.
+fn split_words(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(open) = quote {
+        return Err(format!("the {} quote is never closed", open));
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    fn context() -> HookContext<'static> {
+        HookContext {
+            output: Path::new("build/my prog.wasm"),
+            target: TargetKind::Wasm,
+            source: Path::new("prog.md"),
+        }
+    }
+
+    #[test]
+    fn test_placeholders_are_substituted_after_splitting() {
+        // Act
+        let command = hook_command(
+            r#"tool --in={output} "{target} build" '' {source}"#,
+            &context(),
+        );
+        let unclosed = hook_command("tool 'oops", &context());
+
+        // Assert
+        assert_eq!(
+            command.unwrap().render(),
+            "tool '--in=build/my prog.wasm' 'wasm build' '' prog.md"
+        );
+        assert_eq!(unclosed.unwrap_err(), "the ' quote is never closed");
+        assert!(hook_command("  ", &context()).is_err());
+    }
+
+    /// A script that appends each of its arguments, one per line, to
+    /// `argv.log` next to it.
+    #[cfg(unix)]
+    fn recording_shim(dir: &assert_fs::TempDir) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let shim = dir.child("record.sh");
+        shim.write_str("#!/bin/sh\nprintf '%s\\n' \"$@\" >> \"$(dirname \"$0\")/argv.log\"\n")
+            .unwrap();
+        std::fs::set_permissions(shim.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+        shim.path().to_path_buf()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hooks_run_in_order_with_their_arguments() {
+        // Arrange
+        let dir = assert_fs::TempDir::new().unwrap();
+        let shim = recording_shim(&dir);
+        let hooks = [
+            format!("'{}' first {{output}}", shim.display()),
+            format!("'{}' second --target={{target}} {{source}}", shim.display()),
+        ];
+
+        // Act
+        let result = run_hooks(&hooks, &context());
+
+        // Assert
+        assert_eq!(result, Ok(()));
+        dir.child("argv.log")
+            .assert("first\nbuild/my prog.wasm\nsecond\n--target=wasm\nprog.md\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_a_failing_hook_stops_the_rest() {
+        // Arrange
+        let dir = assert_fs::TempDir::new().unwrap();
+        let shim = recording_shim(&dir);
+        let hooks = [
+            "sh -c 'echo signing failed >&2; exit 3'".to_string(),
+            format!("'{}' never", shim.display()),
+        ];
+
+        // Act
+        let error = run_hooks(&hooks, &context()).unwrap_err();
+
+        // Assert
+        assert_eq!(
+            error,
+            "Post-process Error: `sh -c 'echo signing failed >&2; exit 3'` exited with status 3\nsigning failed"
+        );
+        dir.child("argv.log").assert(predicates::path::missing());
+    }
+
+    #[test]
+    fn test_wasm_opt_is_suggested_only_for_wasm_builds_without_it() {
+        let hooks = ["wasm-opt -O3 {output} -o {output}".to_string()];
+
+        assert!(wasm_opt_suggestion(TargetKind::Wasm, &[], || true).is_some());
+        assert!(wasm_opt_suggestion(TargetKind::Wasm, &[], || false).is_none());
+        assert!(wasm_opt_suggestion(TargetKind::Wasm, &hooks, || true).is_none());
+        assert!(wasm_opt_suggestion(TargetKind::Native, &[], || unreachable!()).is_none());
+    }
+}
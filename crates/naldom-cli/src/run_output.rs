@@ -0,0 +1,122 @@
+// crates/naldom-cli/src/run_output.rs
+
+//! `--run --diff-previous`: the program's output is compared with what the
+//! previous run of the same source file printed. That output is kept in the
+//! cache under `runs/`, keyed by the source file's absolute path, so moving
+//! a file starts its history over.
+
+use crate::cache::{Cache, CacheKind};
+use naldom_core::passes::unified_diff;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+/// The cache key of the output of `source`'s program.
+fn key(source: &Path) -> String {
+    let path = fs::canonicalize(source).unwrap_or_else(|_| source.to_path_buf());
+    Cache::key(&[&path.to_string_lossy()])
+}
+
+/// What the previous run of `source`'s program printed, if it was stored.
+pub fn previous(cache: &Cache, source: &Path) -> Result<Option<String>, String> {
+    cache.get(CacheKind::Runs, &key(source))
+}
+
+/// Keeps `output` as what `source`'s program printed, for the next run.
+pub fn store(cache: &Cache, source: &Path, output: &str) -> Result<(), String> {
+    cache.put(CacheKind::Runs, &key(source), output)
+}
+
+/// How `current` differs from the `previous` output: a unified diff, or a
+/// line saying there is nothing to compare or nothing changed.
+pub fn report(previous: Option<&str>, current: &str) -> String {
+    let Some(previous) = previous else {
+        return "No earlier output of this program to compare with.\n".to_string();
+    };
+    match unified_diff(previous, current, "output") {
+        Some(diff) => format!("Output changed since the previous run:\n{}", diff),
+        None => "Output unchanged since the previous run.\n".to_string(),
+    }
+}
+
+/// Copies `reader` to `writer` as it arrives, flushing after every read so
+/// the output shows up live, and returns everything copied.
+pub fn tee(mut reader: impl Read, mut writer: impl Write) -> io::Result<String> {
+    let mut copied = Vec::new();
+    let mut buffer = [0; 4096];
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buffer[..read])?;
+        writer.flush()?;
+        copied.extend_from_slice(&buffer[..read]);
+    }
+    Ok(String::from_utf8_lossy(&copied).into_owned())
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_is_stored_per_source_file() {
+        // Arrange
+        let temp = assert_fs::TempDir::new().unwrap();
+        let cache = Cache::new(temp.path().join("cache"));
+        let first = temp.path().join("first.md");
+        let second = temp.path().join("second.md");
+        fs::write(&first, "").unwrap();
+        fs::write(&second, "").unwrap();
+
+        // Act
+        store(&cache, &first, "[1, 2, 3]\n").unwrap();
+
+        // Assert
+        assert_eq!(
+            previous(&cache, &first).unwrap().as_deref(),
+            Some("[1, 2, 3]\n")
+        );
+        assert_eq!(previous(&cache, &second).unwrap(), None);
+        assert_eq!(cache.stats().unwrap().runs.entries, 1);
+    }
+
+    #[test]
+    fn test_report_shows_a_diff_only_when_the_output_changed() {
+        // Act
+        let first = report(None, "[1, 2]\n");
+        let unchanged = report(Some("[1, 2]\n"), "[1, 2]\n");
+        let changed = report(Some("sum: 3\n[1, 2]\n"), "sum: 4\n[1, 2]\n");
+
+        // Assert
+        assert_eq!(
+            first,
+            "No earlier output of this program to compare with.\n"
+        );
+        assert_eq!(unchanged, "Output unchanged since the previous run.\n");
+        assert!(changed.starts_with("Output changed since the previous run:\n--- "));
+        assert!(
+            changed.contains("\n-sum: 3\n+sum: 4\n [1, 2]\n"),
+            "{}",
+            changed
+        );
+    }
+
+    #[test]
+    fn test_tee_copies_everything_it_returns() {
+        // Arrange
+        let input = "line one\nline two\n".repeat(1_000);
+        let mut passed_through = Vec::new();
+
+        // Act
+        let captured = tee(input.as_bytes(), &mut passed_through).unwrap();
+
+        // Assert
+        assert_eq!(captured, input);
+        assert_eq!(passed_through, input.as_bytes());
+    }
+}
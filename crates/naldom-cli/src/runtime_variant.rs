@@ -0,0 +1,338 @@
+// crates/naldom-cli/src/runtime_variant.rs
+
+//! Choosing the runtime library a native executable links against.
+//!
+//! `build_runtime_variants.sh` builds `libnaldom_runtime.a` once per feature
+//! set into `runtime-variants/<name>/` next to the default runtime. A program
+//! links against the smallest variant that provides what its intents use,
+//! or the default (full) runtime when that variant was not built.
+
+use naldom_ir::Intent;
+use naldom_runtime::sort::PARALLEL_SORT_MIN_LEN;
+use std::path::{Path, PathBuf};
+
+/// The directory, next to the default runtime, holding the prebuilt variants.
+pub const VARIANTS_DIR: &str = "runtime-variants";
+
+/// The file name of the runtime static library.
+const RUNTIME_LIBRARY: &str = "libnaldom_runtime.a";
+
+/// The optional `naldom-runtime` features a program needs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuntimeFeatures {
+    /// `async-sleep`: the program waits.
+    pub async_sleep: bool,
+    /// `parallel-sort`: the program sorts an array long enough to benefit.
+    pub parallel_sort: bool,
+}
+
+impl RuntimeFeatures {
+    /// The features the validated `intents` need. `file-io` is never
+    /// required: no intent reads or writes files.
+    pub fn required_by(intents: &[Intent]) -> Self {
+        let mut features = RuntimeFeatures::default();
+        features.add_required_by(intents, &mut Vec::new());
+        features
+    }
+
+    /// Adds the features `intents` need, given the sizes of the arrays
+    /// created before them, in creation order. Appends the sizes of the
+    /// arrays they create and updates those they resize. `None` is a size
+    /// not known before the program runs; sorting such an array needs the
+    /// parallel sort, in case it is long.
+    fn add_required_by(&mut self, intents: &[Intent], sizes: &mut Vec<Option<usize>>) {
+        for intent in intents {
+            match intent {
+                Intent::CreateArray(params) => {
+                    sizes.push(params.literal_size().map(|size| size as usize))
+                }
+                Intent::ConcatArrays(params) => {
+                    let mut size = |ordinal: Option<u32>| *ordinal_size(sizes, ordinal)?;
+                    let first = size(params.first_ordinal);
+                    let second = size(params.second_ordinal);
+                    sizes.push(first.zip(second).map(|(first, second)| first + second));
+                }
+                Intent::CopyArray(params) => {
+                    let size = ordinal_size(sizes, params.target_ordinal).and_then(|size| *size);
+                    sizes.push(size);
+                }
+                Intent::SortArray(_) => {
+                    let size = ordinal_size(sizes, intent.target_ordinal());
+                    self.parallel_sort |= size.is_some_and(|size| {
+                        size.is_none_or(|length| length >= PARALLEL_SORT_MIN_LEN)
+                    });
+                }
+                Intent::SliceArray(params) => {
+                    if let Some(Some(length)) = ordinal_size(sizes, params.target_ordinal) {
+                        let start = params.start as usize;
+                        *length = match params.end {
+                            _ if params.from_end => start.min(*length),
+                            Some(end) => (end as usize).min(*length).saturating_sub(start),
+                            None => length.saturating_sub(start),
+                        };
+                    }
+                }
+                Intent::AppendToArray(params) => {
+                    if let Some(Some(length)) = ordinal_size(sizes, params.target_ordinal) {
+                        *length += 1;
+                    }
+                }
+                Intent::ClearArray(_) => {
+                    if let Some(size) = ordinal_size(sizes, intent.target_ordinal()) {
+                        *size = Some(0);
+                    }
+                }
+                // Later passes start from the sizes the first one left, so
+                // the sizes it changed are unknown from the second pass on.
+                Intent::Repeat(params) => {
+                    let before = sizes.clone();
+                    self.add_required_by(&params.body, sizes);
+                    if params.count > 1 && *sizes != before {
+                        for (size, before) in sizes.iter_mut().zip(&before) {
+                            if size != before {
+                                *size = None;
+                            }
+                        }
+                        self.add_required_by(&params.body, sizes);
+                    }
+                }
+                // Arrays created in a branch end with it; an array the
+                // branches resize differently has an unknown size after it.
+                Intent::Conditional(params) => {
+                    let mut then_sizes = sizes.clone();
+                    let mut else_sizes = sizes.clone();
+                    self.add_required_by(&params.then_body, &mut then_sizes);
+                    self.add_required_by(&params.else_body, &mut else_sizes);
+                    for (size, (then_size, else_size)) in
+                        sizes.iter_mut().zip(then_sizes.iter().zip(&else_sizes))
+                    {
+                        *size = if then_size == else_size {
+                            *then_size
+                        } else {
+                            None
+                        };
+                    }
+                }
+                Intent::Wait(_) => self.async_sleep = true,
+                _ => {}
+            }
+        }
+    }
+
+    /// The features either `self` or `other` need, e.g. for several programs
+    /// linked into one executable.
+    pub fn union(self, other: RuntimeFeatures) -> Self {
+        RuntimeFeatures {
+            async_sleep: self.async_sleep || other.async_sleep,
+            parallel_sort: self.parallel_sort || other.parallel_sort,
+        }
+    }
+
+    /// The features without those that run on several threads.
+    pub fn single_threaded(self) -> Self {
+        RuntimeFeatures {
+            parallel_sort: false,
+            ..self
+        }
+    }
+
+    /// The variant's directory name: the enabled features joined by `+`, or
+    /// `minimal` without any.
+    pub fn variant_name(&self) -> String {
+        let enabled: Vec<&str> = [
+            (self.async_sleep, "async-sleep"),
+            (self.parallel_sort, "parallel-sort"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect();
+        if enabled.is_empty() {
+            "minimal".to_string()
+        } else {
+            enabled.join("+")
+        }
+    }
+}
+
+/// The size of the array at `ordinal`, or of the most recent one without an
+/// ordinal. `None` if there is no such array.
+fn ordinal_size(sizes: &mut [Option<usize>], ordinal: Option<u32>) -> Option<&mut Option<usize>> {
+    match ordinal {
+        Some(ordinal) => sizes.get_mut((ordinal as usize).checked_sub(1)?),
+        None => sizes.last_mut(),
+    }
+}
+
+/// The directory to pass to the linker: the prebuilt variant for `features`
+/// under `runtime_dir` if there is one, otherwise `runtime_dir` itself.
+pub fn library_dir(runtime_dir: &Path, features: RuntimeFeatures) -> PathBuf {
+    let variant_dir = runtime_dir.join(VARIANTS_DIR).join(features.variant_name());
+    if variant_dir.join(RUNTIME_LIBRARY).is_file() {
+        variant_dir
+    } else {
+        runtime_dir.to_path_buf()
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use naldom_ir::{
+        ConcatParams, CopyParams, CountArg, CreateArrayParams, SliceParams, SortArrayParams,
+        WaitParams,
+    };
+
+    fn create(size: CountArg) -> Intent {
+        Intent::CreateArray(CreateArrayParams {
+            size: Some(size),
+            name: None,
+            values: Vec::new(),
+            dtype: None,
+        })
+    }
+
+    fn sort(target_ordinal: u32) -> Intent {
+        Intent::SortArray(SortArrayParams {
+            order: Some("ascending".to_string()),
+            algorithm: None,
+            target_ordinal: Some(target_ordinal),
+            target: None,
+        })
+    }
+
+    #[test]
+    fn test_features_follow_the_intents() {
+        // Arrange
+        let large = PARALLEL_SORT_MIN_LEN as u32;
+        let small_sort = [
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(large)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(10)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            sort(2),
+        ];
+        let large_sort_and_wait = [
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(large)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            sort(1),
+            Intent::Wait(WaitParams { duration_ms: 5 }),
+        ];
+
+        // Act
+        let small = RuntimeFeatures::required_by(&small_sort);
+        let large = RuntimeFeatures::required_by(&large_sort_and_wait);
+
+        // Assert
+        assert_eq!(small.variant_name(), "minimal");
+        assert_eq!(large.variant_name(), "async-sleep+parallel-sort");
+        assert_eq!(large.single_threaded().variant_name(), "async-sleep");
+        assert_eq!(
+            small
+                .union(RuntimeFeatures {
+                    async_sleep: true,
+                    parallel_sort: false,
+                })
+                .variant_name(),
+            "async-sleep"
+        );
+    }
+
+    #[test]
+    fn test_every_array_producing_intent_keeps_the_ordinals_in_step() {
+        // Arrange
+        let large = PARALLEL_SORT_MIN_LEN as u32;
+        let copy = |target_ordinal| {
+            Intent::CopyArray(CopyParams {
+                name: None,
+                target: None,
+                target_ordinal: Some(target_ordinal),
+            })
+        };
+        let copy_then_large = [
+            create(CountArg::Literal(10)),
+            copy(1),
+            create(CountArg::Literal(large)),
+            sort(3),
+        ];
+        let copy_of_small = [
+            create(CountArg::Literal(large)),
+            create(CountArg::Literal(10)),
+            copy(2),
+            sort(3),
+        ];
+        let concat_of_halves = [
+            create(CountArg::Literal(large / 2 + 1)),
+            create(CountArg::Literal(large / 2 + 1)),
+            Intent::ConcatArrays(ConcatParams {
+                first: "a".to_string(),
+                second: "b".to_string(),
+                name: None,
+                first_ordinal: Some(1),
+                second_ordinal: Some(2),
+            }),
+            sort(3),
+        ];
+        let sliced = [
+            create(CountArg::Literal(large)),
+            Intent::SliceArray(SliceParams {
+                start: 0,
+                end: Some(10),
+                from_end: false,
+                target_ordinal: Some(1),
+            }),
+            sort(1),
+        ];
+        let sized_by_a_result = [create(CountArg::Variable("total".to_string())), sort(1)];
+
+        // Act
+        let parallel: Vec<bool> = [
+            &copy_then_large[..],
+            &copy_of_small,
+            &concat_of_halves,
+            &sliced,
+            &sized_by_a_result,
+        ]
+        .iter()
+        .map(|intents| RuntimeFeatures::required_by(intents).parallel_sort)
+        .collect();
+
+        // Assert
+        assert_eq!(parallel, [true, false, true, false, true]);
+    }
+
+    #[test]
+    fn test_missing_variants_fall_back_to_the_default_runtime() {
+        // Arrange
+        let runtime_dir =
+            std::env::temp_dir().join(format!("naldom-variants-{}", std::process::id()));
+        let minimal_dir = runtime_dir.join(VARIANTS_DIR).join("minimal");
+        std::fs::create_dir_all(&minimal_dir).unwrap();
+        std::fs::write(minimal_dir.join(RUNTIME_LIBRARY), b"").unwrap();
+        let waits = RuntimeFeatures {
+            async_sleep: true,
+            parallel_sort: false,
+        };
+
+        // Act
+        let minimal = library_dir(&runtime_dir, RuntimeFeatures::default());
+        let fallback = library_dir(&runtime_dir, waits);
+
+        // Assert
+        assert_eq!(minimal, minimal_dir);
+        assert_eq!(fallback, runtime_dir);
+        let _ = std::fs::remove_dir_all(&runtime_dir);
+    }
+}
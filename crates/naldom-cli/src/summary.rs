@@ -0,0 +1,365 @@
+// crates/naldom-cli/src/summary.rs
+
+//! The footer printed when a build ends: on success what was built and what
+//! to do with it next, on failure the stage that failed and the most likely
+//! fix.
+//!
+//! Errors reach the CLI as messages, so a failure is classified from the
+//! stage that reported it and the wording of the stage's error. The hints
+//! are kept in one table, [`HINTS`], keyed by the resulting class.
+
+use crate::CrateType;
+use naldom_core::attribution::SizeAttribution;
+use naldom_core::progress::{ProgressEvent, ProgressSink, Stage};
+use naldom_core::targets::TargetKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// What a successful build produced, for its footer.
+#[derive(Debug, Clone)]
+pub struct CompileStats {
+    pub build_id: String,
+    pub artifact: PathBuf,
+    /// The artifact's size, if it could be read.
+    pub bytes: Option<u64>,
+    pub target: TargetKind,
+    pub crate_type: CrateType,
+    pub opt_level: u8,
+    pub elapsed: Duration,
+    /// Whether `--run` runs the artifact right after the footer.
+    pub run: bool,
+    /// The code each function and intent accounts for, with `--stats`.
+    pub size: Option<SizeAttribution>,
+}
+
+/// How many intents `--stats -v` lists.
+pub const STATS_TOP_INTENTS: usize = 10;
+
+/// Why a build failed, as far as the hint for it is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureClass {
+    /// The llama.cpp server could not be reached.
+    LlmUnreachable,
+    /// An LLVM tool (clang, llc, wasm-ld) could not be started.
+    ToolMissing,
+    /// The model's intents do not form a valid program.
+    InvalidProgram,
+    /// Anything else; it gets no hint.
+    Other,
+}
+
+/// The hint shown for each failure class.
+pub const HINTS: &[(FailureClass, &str)] = &[
+    (
+        FailureClass::LlmUnreachable,
+        "start a llama.cpp server on 127.0.0.1:8080, e.g. `./bin/server -m /path/to/model.gguf --host 127.0.0.1 --port 8080`",
+    ),
+    (
+        FailureClass::ToolMissing,
+        "run `naldom-cli doctor` to see which LLVM tools were found",
+    ),
+    (
+        FailureClass::InvalidProgram,
+        "try `--explain` to see the intents the model produced, or `--retries 2` to ask it again",
+    ),
+];
+
+impl FailureClass {
+    /// Classifies the error `message`, reported by `stage` if it came from
+    /// one.
+    pub fn of(stage: Option<Stage>, message: &str) -> Self {
+        if message.contains("Failed to send request to LLM server") {
+            FailureClass::LlmUnreachable
+        } else if message.contains("could not start `") {
+            // `ToolchainError::Spawn`: the tool is not installed where it was looked up.
+            FailureClass::ToolMissing
+        } else if stage == Some(Stage::Analysis) || message.starts_with("Semantic Error") {
+            FailureClass::InvalidProgram
+        } else {
+            FailureClass::Other
+        }
+    }
+
+    /// The hint for this class, if it has one.
+    pub fn hint(&self) -> Option<&'static str> {
+        HINTS
+            .iter()
+            .find(|(class, _)| class == self)
+            .map(|(_, hint)| *hint)
+    }
+}
+
+/// The footer of a successful build. Its first line starts with
+/// `build <id>`, which scripts read the build ID from.
+pub fn success_footer(stats: &CompileStats) -> String {
+    let size = stats
+        .bytes
+        .map(|bytes| format!(" ({})", format_size(bytes)))
+        .unwrap_or_default();
+    format!(
+        "build {} finished in {:.1} s\n  artifact: {}{}\n  target:   {}, opt-level {}\n  hint: {}\n",
+        stats.build_id,
+        stats.elapsed.as_secs_f64(),
+        stats.artifact.display(),
+        size,
+        stats.target,
+        stats.opt_level,
+        success_hint(stats)
+    )
+}
+
+/// The `--stats` report: the code each generated function accounts for
+/// and, when `verbose`, the intents accounting for the most. Empty without
+/// `--stats`.
+pub fn stats_report(stats: &CompileStats, verbose: bool) -> String {
+    let Some(size) = &stats.size else {
+        return String::new();
+    };
+    let mut report = format!("code size:\n{}", size.render_functions());
+    if verbose {
+        report.push_str(&format!(
+            "code size by intent:\n{}",
+            size.render_top_intents(STATS_TOP_INTENTS)
+        ));
+    }
+    report
+}
+
+/// What to do with a freshly built artifact.
+fn success_hint(stats: &CompileStats) -> String {
+    match stats.target {
+        TargetKind::Native if stats.crate_type == CrateType::Bin && !stats.run => {
+            format!("run it with `{}`", command_path(&stats.artifact))
+        }
+        TargetKind::Wasm if !stats.run => {
+            "load it with a Wasm runtime such as wasmtime, or in a browser".to_string()
+        }
+        TargetKind::Python => "it imports the Naldom runtime; write it next to the module with `naldom-cli emit python-runtime -o naldom_runtime.py`".to_string(),
+        _ => "try `--emit llvm-ir` to inspect the IR".to_string(),
+    }
+}
+
+/// The footer of a failed build: a recap of the stage that failed, if the
+/// failure came from one, and the hint for its class. Empty when there is
+/// nothing to add to the error itself.
+pub fn failure_footer(stage: Option<Stage>, message: &str) -> String {
+    let mut footer = String::new();
+    if let Some(stage) = stage {
+        footer.push_str(&format!(
+            "build failed in the {} stage\n",
+            stage_name(stage)
+        ));
+    }
+    if let Some(hint) = FailureClass::of(stage, message).hint() {
+        footer.push_str(&format!("  hint: {}\n", hint));
+    }
+    footer
+}
+
+fn stage_name(stage: Stage) -> &'static str {
+    match stage {
+        Stage::LowLevelLowering => "low-level lowering",
+        stage => stage.as_str(),
+    }
+}
+
+/// `path` as a shell would run it: a bare file name needs `./`.
+fn command_path(path: &Path) -> String {
+    if path.is_relative() && path.parent() == Some(Path::new("")) {
+        format!("./{}", path.display())
+    } else {
+        path.display().to_string()
+    }
+}
+
+pub(crate) fn format_size(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    let size = bytes as f64;
+    if size < KIB {
+        format!("{} B", bytes)
+    } else if size < KIB * KIB {
+        format!("{:.1} KiB", size / KIB)
+    } else {
+        format!("{:.1} MiB", size / (KIB * KIB))
+    }
+}
+
+/// Passes events on to `inner`, remembering the stage that reported an
+/// error.
+pub struct StageTracker {
+    inner: Box<dyn ProgressSink>,
+    current: Mutex<Option<Stage>>,
+    failed: Arc<Mutex<Option<Stage>>>,
+}
+
+impl StageTracker {
+    /// Records the failed stage in `failed`.
+    pub fn new(inner: Box<dyn ProgressSink>, failed: Arc<Mutex<Option<Stage>>>) -> Self {
+        StageTracker {
+            inner,
+            current: Mutex::new(None),
+            failed,
+        }
+    }
+}
+
+impl ProgressSink for StageTracker {
+    fn emit(&self, event: &ProgressEvent) {
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        match event {
+            ProgressEvent::StageStart { stage } => *current = Some(*stage),
+            ProgressEvent::StageEnd { .. } => *current = None,
+            ProgressEvent::Diagnostic { level: "error", .. } if current.is_some() => {
+                *self.failed.lock().unwrap_or_else(|e| e.into_inner()) = *current;
+            }
+            _ => {}
+        }
+        drop(current);
+        self.inner.emit(event);
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use naldom_core::progress::NoProgress;
+
+    fn stats(target: TargetKind, artifact: &str) -> CompileStats {
+        CompileStats {
+            build_id: "3fa9c2".to_string(),
+            artifact: PathBuf::from(artifact),
+            bytes: Some(16_589),
+            target,
+            crate_type: CrateType::Bin,
+            opt_level: 2,
+            elapsed: Duration::from_millis(1_240),
+            run: false,
+            size: None,
+        }
+    }
+
+    #[test]
+    fn test_success_footer_names_the_artifact_and_how_to_run_it() {
+        // Act
+        let footer = success_footer(&stats(TargetKind::Native, "a.out"));
+
+        // Assert
+        assert_eq!(
+            footer,
+            "build 3fa9c2 finished in 1.2 s\n  artifact: a.out (16.2 KiB)\n  target:   native, opt-level 2\n  hint: run it with `./a.out`\n"
+        );
+    }
+
+    #[test]
+    fn test_success_hint_points_at_the_ir_once_the_program_ran() {
+        // Arrange
+        let stats = CompileStats {
+            bytes: Some(900),
+            run: true,
+            ..stats(TargetKind::Native, "/tmp/prog")
+        };
+
+        // Act
+        let footer = success_footer(&stats);
+
+        // Assert
+        assert!(footer.contains("  artifact: /tmp/prog (900 B)\n"));
+        assert!(footer.ends_with("  hint: try `--emit llvm-ir` to inspect the IR\n"));
+    }
+
+    #[test]
+    fn test_stats_report_adds_the_intent_table_when_verbose() {
+        // Arrange
+        let with_size = CompileStats {
+            size: Some(SizeAttribution::default()),
+            ..stats(TargetKind::Python, "out.py")
+        };
+
+        // Act
+        let quiet = stats_report(&with_size, false);
+        let verbose = stats_report(&with_size, true);
+
+        // Assert
+        assert_eq!(stats_report(&stats(TargetKind::Python, "out.py"), true), "");
+        assert!(quiet.starts_with("code size:\n"));
+        assert!(!quiet.contains("code size by intent:"));
+        assert!(verbose.starts_with(&quiet));
+        assert!(verbose.contains("code size by intent:\n"));
+    }
+
+    #[test]
+    fn test_unreachable_llm_footer_says_how_to_start_the_server() {
+        // Act
+        let footer = failure_footer(
+            Some(Stage::Inference),
+            "Failed to send request to LLM server: error sending request for url (http://127.0.0.1:8080/completion)",
+        );
+
+        // Assert
+        assert_eq!(
+            footer,
+            "build failed in the inference stage\n  hint: start a llama.cpp server on 127.0.0.1:8080, e.g. `./bin/server -m /path/to/model.gguf --host 127.0.0.1 --port 8080`\n"
+        );
+    }
+
+    #[test]
+    fn test_missing_tool_footer_points_at_doctor() {
+        // Act
+        let footer = failure_footer(
+            Some(Stage::Link),
+            "could not start `clang-18 -o a.out prog.o`: No such file or directory (os error 2)",
+        );
+
+        // Assert
+        assert_eq!(
+            footer,
+            "build failed in the link stage\n  hint: run `naldom-cli doctor` to see which LLVM tools were found\n"
+        );
+    }
+
+    #[test]
+    fn test_semantic_error_footer_suggests_explain() {
+        // Act
+        let footer = failure_footer(
+            Some(Stage::Analysis),
+            "Semantic Error: PrintMatrix needs a CreateMatrix before it.",
+        );
+
+        // Assert
+        assert_eq!(
+            footer,
+            "build failed in the analysis stage\n  hint: try `--explain` to see the intents the model produced, or `--retries 2` to ask it again\n"
+        );
+    }
+
+    #[test]
+    fn test_unclassified_errors_outside_a_stage_get_no_footer() {
+        assert_eq!(failure_footer(None, "Error reading file 'x.md'"), "");
+    }
+
+    #[test]
+    fn test_stage_tracker_records_the_stage_that_reported_an_error() {
+        // Arrange
+        let failed = Arc::default();
+        let tracker = StageTracker::new(Box::new(NoProgress), Arc::clone(&failed));
+
+        // Act
+        tracker.emit(&ProgressEvent::StageStart {
+            stage: Stage::Parse,
+        });
+        tracker.emit(&ProgressEvent::stage_end(Stage::Parse, Duration::ZERO));
+        tracker.emit(&ProgressEvent::StageStart {
+            stage: Stage::Analysis,
+        });
+        tracker.emit(&ProgressEvent::Diagnostic {
+            level: "error",
+            message: "Semantic Error",
+        });
+        tracker.emit(&ProgressEvent::stage_end(Stage::Analysis, Duration::ZERO));
+
+        // Assert
+        assert_eq!(*failed.lock().unwrap(), Some(Stage::Analysis));
+    }
+}
@@ -0,0 +1,480 @@
+// crates/naldom-cli/src/toolchain.rs
+
+//! Turning generated LLVM IR into a native executable, object file or static
+//! library, or a wasm module, with the external LLVM tools. Every tool
+//! invocation is first described as a [`ToolCommand`], so it can be printed
+//! (`--print-link-command`) or quoted in an error exactly as it would run.
+//!
+//! Without `LLVM_PREFIX`, the tools are looked up on `PATH`, preferring the
+//! versioned names distributions install (`llc-18`) for the LLVM version this
+//! build targets over the unversioned ones.
+
+use naldom_core::targets::{LLVM_MAJOR, WASM_ENTRY};
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// An external command: the program and its arguments, not yet executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolCommand {
+    pub program: PathBuf,
+    pub args: Vec<OsString>,
+}
+
+impl ToolCommand {
+    fn new(program: PathBuf) -> Self {
+        ToolCommand {
+            program,
+            args: Vec::new(),
+        }
+    }
+
+    fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// The command as a single line a POSIX shell would run unchanged.
+    pub fn render(&self) -> String {
+        std::iter::once(self.program.as_os_str())
+            .chain(self.args.iter().map(OsString::as_os_str))
+            .map(|part| shell_quote(&part.to_string_lossy()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The first line of `<program> --version`, or `None` if it cannot be run.
+    pub fn version(&self) -> Option<String> {
+        let output = Command::new(&self.program).arg("--version").output().ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_string)
+    }
+
+    /// The LLVM major version `<program> --version` reports, or `None` if it
+    /// cannot be run or does not say.
+    pub fn llvm_major_version(&self) -> Option<u32> {
+        let output = Command::new(&self.program).arg("--version").output().ok()?;
+        parse_major_version(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Runs the command, capturing its output.
+    pub fn run(&self) -> Result<(), ToolchainError> {
+        let output = Command::new(&self.program)
+            .args(&self.args)
+            .output()
+            .map_err(|e| ToolchainError::Spawn {
+                command: self.render(),
+                message: e.to_string(),
+            })?;
+        if !output.status.success() {
+            return Err(ToolchainError::Failed {
+                command: self.render(),
+                status: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr)
+                    .trim_end()
+                    .to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Quotes `word` for a POSIX shell, leaving obviously safe words untouched.
+fn shell_quote(word: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c);
+    if !word.is_empty() && word.chars().all(is_safe) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+/// The major version in `--version` output such as "Ubuntu LLVM version
+/// 18.1.3" or "clang version 17.0.6 (...)".
+fn parse_major_version(version_output: &str) -> Option<u32> {
+    let mut words = version_output.split_whitespace();
+    words.find(|word| *word == "version")?;
+    words.next()?.split(['.', '-']).next()?.parse().ok()
+}
+
+/// Warnings for the distinct tools in `commands` that report an LLVM major
+/// version other than `expected`. Tools that do not report one are trusted.
+pub fn version_mismatches(commands: &[ToolCommand], expected: Option<u32>) -> Vec<String> {
+    let Some(expected) = expected else {
+        return Vec::new();
+    };
+    let mut seen = Vec::new();
+    let mut warnings = Vec::new();
+    for command in commands {
+        if seen.contains(&command.program) {
+            continue;
+        }
+        seen.push(command.program.clone());
+        if let Some(found) = command.llvm_major_version()
+            && found != expected
+        {
+            warnings.push(format!(
+                "this build of Naldom generates IR for LLVM {}, but `{}` is LLVM {}; \
+                 install the LLVM {} tools or point LLVM_PREFIX at them",
+                expected,
+                command.program.display(),
+                found,
+                expected
+            ));
+        }
+    }
+    warnings
+}
+
+/// An error from preparing or running an external tool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolchainError {
+    /// An intermediate file could not be written.
+    Io { path: PathBuf, message: String },
+    /// The tool could not be started (usually: not installed).
+    Spawn { command: String, message: String },
+    /// The tool ran and reported failure.
+    Failed {
+        command: String,
+        status: Option<i32>,
+        stderr: String,
+    },
+}
+
+impl fmt::Display for ToolchainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolchainError::Io { path, message } => {
+                write!(f, "could not write '{}': {}", path.display(), message)
+            }
+            ToolchainError::Spawn { command, message } => {
+                write!(f, "could not start `{}`: {}", command, message)
+            }
+            ToolchainError::Failed {
+                command,
+                status,
+                stderr,
+            } => {
+                match status {
+                    Some(code) => write!(f, "`{}` exited with status {}", command, code)?,
+                    None => write!(f, "`{}` was terminated by a signal", command)?,
+                }
+                if !stderr.is_empty() {
+                    write!(f, "\n{}", stderr)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ToolchainError {}
+
+/// Where the LLVM tools live: `$LLVM_PREFIX/bin`, or `PATH` when unset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Toolchain {
+    prefix: Option<PathBuf>,
+    /// On `PATH`, `<tool>-<major>` is preferred over `<tool>` when it exists.
+    llvm_major: Option<u32>,
+}
+
+impl Toolchain {
+    /// Reads `LLVM_PREFIX` from the environment, and prefers the tools for the
+    /// LLVM version this build targets.
+    pub fn from_env() -> Self {
+        let toolchain = env::var_os("LLVM_PREFIX").map_or_else(Self::default, Self::with_prefix);
+        match LLVM_MAJOR {
+            Some(major) => toolchain.with_llvm_major(major),
+            None => toolchain,
+        }
+    }
+
+    /// Uses the tools under `prefix/bin`.
+    pub fn with_prefix(prefix: impl Into<PathBuf>) -> Self {
+        Toolchain {
+            prefix: Some(prefix.into()),
+            llvm_major: None,
+        }
+    }
+
+    /// Prefers `<tool>-<major>` on `PATH`, e.g. `llc-18`.
+    pub fn with_llvm_major(mut self, major: u32) -> Self {
+        self.llvm_major = Some(major);
+        self
+    }
+
+    fn tool(&self, name: &str) -> PathBuf {
+        if let Some(prefix) = &self.prefix {
+            return prefix.join("bin").join(name);
+        }
+        self.llvm_major
+            .map(|major| format!("{}-{}", name, major))
+            .filter(|versioned| find_on_path(versioned).is_some())
+            .map_or_else(|| PathBuf::from(name), PathBuf::from)
+    }
+
+    /// A command running the tool `name` without arguments, e.g. to ask for
+    /// its [`ToolCommand::version`].
+    pub fn tool_command(&self, name: &str) -> ToolCommand {
+        ToolCommand::new(self.tool(name))
+    }
+
+    /// The `llc` then `clang` commands that build a native executable.
+    pub fn native_commands(
+        &self,
+        ll_path: &Path,
+        obj_path: &Path,
+        output_path: &Path,
+        runtime_dir: &Path,
+        opt_level: u8,
+    ) -> Vec<ToolCommand> {
+        let opt_flag = format!("-O{}", opt_level);
+        vec![
+            self.llc_native(ll_path, obj_path, opt_level),
+            ToolCommand::new(self.tool("clang"))
+                .arg(obj_path)
+                .arg("-L")
+                .arg(runtime_dir)
+                .arg("-lnaldom_runtime")
+                .arg("-o")
+                .arg(output_path)
+                .arg(&opt_flag),
+        ]
+    }
+
+    /// The `llc` command that writes a native object file to `output_path`,
+    /// for linking into a host application together with the runtime.
+    pub fn object_commands(
+        &self,
+        ll_path: &Path,
+        output_path: &Path,
+        opt_level: u8,
+    ) -> Vec<ToolCommand> {
+        vec![self.llc_native(ll_path, output_path, opt_level)]
+    }
+
+    /// The `llc` then `llvm-ar` commands that build a static library holding
+    /// the program's object file. The runtime is not included. The archive is
+    /// written in deterministic mode (`D`), with zeroed timestamps and owners,
+    /// so identical objects give identical archives.
+    pub fn staticlib_commands(
+        &self,
+        ll_path: &Path,
+        obj_path: &Path,
+        output_path: &Path,
+        opt_level: u8,
+    ) -> Vec<ToolCommand> {
+        vec![
+            self.llc_native(ll_path, obj_path, opt_level),
+            ToolCommand::new(self.tool("llvm-ar"))
+                .arg("rcsD")
+                .arg(output_path)
+                .arg(obj_path),
+        ]
+    }
+
+    fn llc_native(&self, ll_path: &Path, obj_path: &Path, opt_level: u8) -> ToolCommand {
+        ToolCommand::new(self.tool("llc"))
+            .arg(format!("-O{}", opt_level))
+            .arg("-filetype=obj")
+            .arg(ll_path)
+            .arg("-o")
+            .arg(obj_path)
+    }
+
+    /// The `llc` then `wasm-ld` commands that build a wasm module. The module
+    /// exports only its entry function and `memory`; runtime functions stay
+    /// undefined and become imports.
+    pub fn wasm_commands(
+        &self,
+        ll_path: &Path,
+        obj_path: &Path,
+        output_path: &Path,
+        opt_level: u8,
+    ) -> Vec<ToolCommand> {
+        let opt_flag = format!("-O{}", opt_level);
+        vec![
+            ToolCommand::new(self.tool("llc"))
+                .arg(&opt_flag)
+                .arg("-march=wasm32")
+                .arg("-filetype=obj")
+                .arg(ll_path)
+                .arg("-o")
+                .arg(obj_path),
+            ToolCommand::new(self.tool("wasm-ld"))
+                .arg(obj_path)
+                .arg("-o")
+                .arg(output_path)
+                .arg("--no-entry")
+                .arg(format!("--export={}", WASM_ENTRY))
+                .arg("--export-memory")
+                .arg("--allow-undefined")
+                .arg(&opt_flag),
+        ]
+    }
+}
+
+/// The first file called `name` in a `PATH` directory.
+fn find_on_path(name: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Writes `llvm_ir` to `ll_path` and runs `commands` in order. Unless
+/// `keep_intermediates` is set, the `.ll` and `.o` files are removed once
+/// everything succeeded; an `.o` that is the `output_path` itself is kept.
+pub fn run_commands(
+    llvm_ir: &str,
+    ll_path: &Path,
+    obj_path: &Path,
+    output_path: &Path,
+    commands: &[ToolCommand],
+    keep_intermediates: bool,
+) -> Result<(), ToolchainError> {
+    fs::write(ll_path, llvm_ir).map_err(|e| ToolchainError::Io {
+        path: ll_path.to_path_buf(),
+        message: e.to_string(),
+    })?;
+    for command in commands {
+        command.run()?;
+    }
+    if keep_intermediates {
+        return Ok(());
+    }
+    let _ = fs::remove_file(ll_path);
+    if obj_path != output_path {
+        let _ = fs::remove_file(obj_path);
+    }
+    Ok(())
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_native_link_command_names_runtime_and_output() {
+        // Arrange
+        let toolchain = Toolchain::with_prefix("/opt/fake llvm");
+
+        // Act
+        let commands = toolchain.native_commands(
+            Path::new("/tmp/prog.ll"),
+            Path::new("/tmp/prog.o"),
+            Path::new("/home/me/my prog"),
+            Path::new("/build/target/debug"),
+            2,
+        );
+
+        // Assert
+        assert_eq!(
+            commands[0].render(),
+            "'/opt/fake llvm/bin/llc' -O2 -filetype=obj /tmp/prog.ll -o /tmp/prog.o"
+        );
+        assert_eq!(
+            commands[1].render(),
+            "'/opt/fake llvm/bin/clang' /tmp/prog.o -L /build/target/debug -lnaldom_runtime -o '/home/me/my prog' -O2"
+        );
+    }
+
+    #[test]
+    fn test_wasm_link_command_uses_wasm_ld() {
+        let commands = Toolchain::default().wasm_commands(
+            Path::new("prog.ll"),
+            Path::new("prog.o"),
+            Path::new("out.wasm"),
+            0,
+        );
+
+        assert_eq!(
+            commands[1].render(),
+            "wasm-ld prog.o -o out.wasm --no-entry --export=naldom_main --export-memory --allow-undefined -O0"
+        );
+    }
+
+    #[test]
+    fn test_library_commands_do_not_link_the_runtime() {
+        // Arrange
+        let toolchain = Toolchain::default();
+
+        // Act
+        let object = toolchain.object_commands(Path::new("prog.ll"), Path::new("prog.o"), 1);
+        let staticlib = toolchain.staticlib_commands(
+            Path::new("prog.ll"),
+            Path::new("/tmp/prog.o"),
+            Path::new("libprog.a"),
+            1,
+        );
+
+        // Assert
+        let render = |commands: &[ToolCommand]| -> Vec<String> {
+            commands.iter().map(ToolCommand::render).collect()
+        };
+        assert_eq!(render(&object), ["llc -O1 -filetype=obj prog.ll -o prog.o"]);
+        assert_eq!(
+            render(&staticlib),
+            [
+                "llc -O1 -filetype=obj prog.ll -o /tmp/prog.o",
+                "llvm-ar rcsD libprog.a /tmp/prog.o",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_major_version_is_read_from_version_output() {
+        assert_eq!(
+            parse_major_version("Ubuntu LLVM version 18.1.3\n  Optimized build."),
+            Some(18)
+        );
+        assert_eq!(
+            parse_major_version("clang version 19.1.0-rc3 (https://github.com/llvm/llvm-project)"),
+            Some(19)
+        );
+        assert_eq!(parse_major_version("LLVM (http://llvm.org/):"), None);
+    }
+
+    #[test]
+    fn test_versioned_tools_fall_back_to_plain_names() {
+        // Arrange
+        let toolchain = Toolchain::default().with_llvm_major(4242);
+
+        // Act
+        let commands = toolchain.object_commands(Path::new("prog.ll"), Path::new("prog.o"), 0);
+
+        // Assert
+        assert_eq!(commands[0].program, PathBuf::from("llc"));
+        assert!(version_mismatches(&commands, None).is_empty());
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("-lnaldom_runtime"), "-lnaldom_runtime");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("it's ü"), r"'it'\''s ü'");
+    }
+
+    #[test]
+    fn test_failures_quote_the_command() {
+        // Arrange
+        let command = ToolCommand::new(PathBuf::from("/nonexistent/naldom-test-tool")).arg("x y");
+
+        // Act
+        let error = command.run().unwrap_err();
+
+        // Assert
+        assert!(
+            error
+                .to_string()
+                .starts_with("could not start `/nonexistent/naldom-test-tool 'x y'`:")
+        );
+    }
+}
@@ -0,0 +1,39 @@
+// crates/naldom-core/benches/analysis.rs
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use naldom_core::parallel_analysis::{AnalysisUnit, analyze_units};
+use naldom_core::parser::parse_to_intent_graph;
+use naldom_core::program_generator::generate_program;
+use naldom_core::semantic_analyzer::SemanticAnalyzer;
+use std::hint::black_box;
+use std::sync::Arc;
+
+/// A synthetic document of this many independent blocks.
+const BLOCKS: usize = 50;
+/// The length of each block, in intents.
+const INTENTS_PER_BLOCK: usize = 200;
+
+fn bench_analysis(c: &mut Criterion) {
+    let units: Vec<AnalysisUnit> = (0..BLOCKS)
+        .map(|block| AnalysisUnit {
+            name: format!("block_{}", block),
+            intents: parse_to_intent_graph(&generate_program(INTENTS_PER_BLOCK)).unwrap(),
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("analysis_50_blocks");
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            for unit in &units {
+                black_box(SemanticAnalyzer::new().analyze(&unit.intents).unwrap());
+            }
+        })
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| black_box(analyze_units(&units, Arc::default())))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_analysis);
+criterion_main!(benches);
@@ -0,0 +1,49 @@
+// crates/naldom-core/benches/pipeline.rs
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+#[cfg(feature = "llvm")]
+use naldom_core::codegen_llvm::generate_llvm_ir;
+use naldom_core::lowering::LoweringContext;
+use naldom_core::lowering_hl_to_ll::lower_hl_to_ll;
+use naldom_core::parser::parse_to_intent_graph;
+use naldom_core::program_generator::generate_program;
+use naldom_core::semantic_analyzer::SemanticAnalyzer;
+use std::hint::black_box;
+
+/// Program lengths, in intents. Each step is five times the last, so a pass
+/// that scales worse than linearly stands out in the report.
+const SIZES: [usize; 3] = [40, 200, 1_000];
+
+fn bench_pipeline(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pipeline");
+    for intents in SIZES {
+        let json = generate_program(intents);
+        group.bench_with_input(BenchmarkId::new("to_ll", intents), &json, |b, json| {
+            b.iter(|| {
+                let intent_graph = parse_to_intent_graph(json).unwrap();
+                let validated_graph = SemanticAnalyzer::new().analyze(&intent_graph).unwrap();
+                let hl_program = LoweringContext::new().lower(&validated_graph);
+                black_box(lower_hl_to_ll(&hl_program).unwrap())
+            })
+        });
+
+        #[cfg(feature = "llvm")]
+        {
+            let intent_graph = parse_to_intent_graph(&json).unwrap();
+            let validated_graph = SemanticAnalyzer::new().analyze(&intent_graph).unwrap();
+            let ll_program =
+                lower_hl_to_ll(&LoweringContext::new().lower(&validated_graph)).unwrap();
+            group.bench_with_input(
+                BenchmarkId::new("llvm_codegen", intents),
+                &ll_program,
+                |b, ll_program| {
+                    b.iter(|| generate_llvm_ir(ll_program, "x86_64-unknown-linux-gnu").unwrap())
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_pipeline);
+criterion_main!(benches);
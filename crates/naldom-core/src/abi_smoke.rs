@@ -0,0 +1,292 @@
+// crates/naldom-core/src/abi_smoke.rs
+
+//! A smoke program for the runtime ABI: one `main` that calls every function
+//! in the registry once, with arguments of the registered types.
+//!
+//! C does not check types across object files, so a registry entry that
+//! drifts from the runtime (an `i32` where the runtime takes an `i64`) links
+//! fine and corrupts memory at run time. The runtime's own ABI test compares
+//! its exports with the registry; this program covers the other side by
+//! going through code generation and the real linker, and running the result.
+
+use crate::lowering_hl_to_ll::NORETURN_FUNCTIONS;
+use naldom_ir::runtime_abi::{AbiType, RUNTIME_FUNCTIONS, RuntimeFunction};
+use naldom_ir::{
+    BasicBlock, LLConstant, LLFunction, LLInstruction, LLProgram, LLType, LLValue, Register,
+    Terminator,
+};
+
+/// The entry function the smoke program registers with the runtime.
+pub const SMOKE_ENTRY: &str = "naldom_abi_smoke_entry";
+
+/// The string passed for every `const char *` parameter.
+pub const SMOKE_STRING: &str = "smoke";
+
+/// `main`'s parameters, which `naldom_dispatch` is called with.
+const ARGC: Register = Register(0);
+const ARGV: Register = Register(1);
+
+/// Builds the smoke program: an empty [`SMOKE_ENTRY`], then an
+/// `int main(int argc, char **argv)` that calls the registry's functions in
+/// order. Functions that never return are called last, so the program ends
+/// with `naldom_exit(0)`.
+///
+/// Array and matrix parameters get the array or matrix the latest earlier
+/// call returned, so the registry must list a constructor before the first
+/// function that takes one; other parameters get small constants.
+pub fn abi_smoke_program() -> LLProgram {
+    let (returning, noreturn): (Vec<&RuntimeFunction>, Vec<&RuntimeFunction>) = RUNTIME_FUNCTIONS
+        .iter()
+        .partition(|function| !NORETURN_FUNCTIONS.contains(&function.name));
+
+    let mut next_register = ARGV.0 + 1;
+    let mut latest_array = None;
+    let mut latest_matrix = None;
+    let mut instructions = Vec::new();
+    for function in returning.into_iter().chain(noreturn) {
+        let arguments = function
+            .params
+            .iter()
+            .map(|param| match param.ty {
+                AbiType::ArrayPtr | AbiType::ConstArrayPtr => LLValue::Register(
+                    latest_array.unwrap_or_else(|| missing_constructor(function, "array")),
+                ),
+                AbiType::MatrixPtr | AbiType::ConstMatrixPtr => LLValue::Register(
+                    latest_matrix.unwrap_or_else(|| missing_constructor(function, "matrix")),
+                ),
+                AbiType::CStrArray => LLValue::Register(ARGV),
+                ty => LLValue::Constant(smoke_constant(function, ty)),
+            })
+            .collect();
+        let dest = (function.return_type != AbiType::Void).then(|| {
+            next_register += 1;
+            Register(next_register - 1)
+        });
+        match function.return_type {
+            AbiType::ArrayPtr => latest_array = dest,
+            AbiType::MatrixPtr => latest_matrix = dest,
+            _ => {}
+        }
+        instructions.push(LLInstruction::Call {
+            dest,
+            function_name: function.name.to_string(),
+            arguments,
+        });
+    }
+
+    let terminator = match instructions.last() {
+        Some(LLInstruction::Call { function_name, .. })
+            if NORETURN_FUNCTIONS.contains(&function_name.as_str()) =>
+        {
+            Terminator::Unreachable
+        }
+        _ => Terminator::Return(Some(LLValue::Constant(LLConstant::I32(0)))),
+    };
+    LLProgram {
+        functions: vec![
+            LLFunction {
+                name: SMOKE_ENTRY.to_string(),
+                parameters: Vec::new(),
+                return_type: LLType::Void,
+                basic_blocks: vec![BasicBlock {
+                    id: 0,
+                    instructions: Vec::new(),
+                    terminator: Terminator::Return(None),
+                    annotations: Vec::new(),
+                }],
+            },
+            LLFunction {
+                name: "main".to_string(),
+                parameters: vec![
+                    (LLType::I32, ARGC),
+                    (
+                        LLType::Pointer(Box::new(LLType::Pointer(Box::new(LLType::I8)))),
+                        ARGV,
+                    ),
+                ],
+                return_type: LLType::I32,
+                basic_blocks: vec![BasicBlock {
+                    id: 0,
+                    instructions,
+                    terminator,
+                    annotations: Vec::new(),
+                }],
+            },
+        ],
+    }
+}
+
+/// The constant passed for a parameter of type `ty`: values every runtime
+/// function accepts, and an exit code of 0. Integer constants are widened
+/// to the declared type by code generation.
+fn smoke_constant(function: &RuntimeFunction, ty: AbiType) -> LLConstant {
+    match ty {
+        AbiType::I32 => LLConstant::I32(0),
+        AbiType::I64 => LLConstant::I64(1),
+        // Seeds and sleeps: 0 keeps the program fast.
+        AbiType::U64 => LLConstant::I64(0),
+        AbiType::F64 => LLConstant::F64(1.5),
+        AbiType::I64Ptr => LLConstant::I64Array(vec![3, 1, 2]),
+        AbiType::CStr => LLConstant::Str(SMOKE_STRING.to_string()),
+        AbiType::EntryFn => LLConstant::Function(SMOKE_ENTRY.to_string()),
+        AbiType::Void
+        | AbiType::F64Ptr
+        | AbiType::CStrArray
+        | AbiType::ArrayPtr
+        | AbiType::ConstArrayPtr
+        | AbiType::MatrixPtr
+        | AbiType::ConstMatrixPtr => panic!(
+            "the ABI smoke program has no constant of type {:?} for '{}'",
+            ty, function.name
+        ),
+    }
+}
+
+fn missing_constructor(function: &RuntimeFunction, kind: &str) -> Register {
+    panic!(
+        "'{}' takes a {}, but no earlier runtime function returns one",
+        function.name, kind
+    )
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn main_calls(program: &LLProgram) -> &[LLInstruction] {
+        &program.functions[1].basic_blocks[0].instructions
+    }
+
+    /// The ABI type of `value` as the program produces it.
+    fn value_abi(value: &LLValue, registers: &HashMap<Register, AbiType>) -> AbiType {
+        match value {
+            LLValue::Register(register) => registers[register],
+            LLValue::Constant(LLConstant::I32(_)) => AbiType::I32,
+            LLValue::Constant(LLConstant::I64(_)) => AbiType::I64,
+            LLValue::Constant(LLConstant::F64(_)) => AbiType::F64,
+            LLValue::Constant(LLConstant::Str(_)) => AbiType::CStr,
+            LLValue::Constant(LLConstant::Function(_)) => AbiType::EntryFn,
+            LLValue::Constant(LLConstant::I64Array(_)) => AbiType::I64Ptr,
+        }
+    }
+
+    /// Whether an argument of type `given` may be passed for `declared`:
+    /// the same type, a const view of a mutable pointer, or an integer
+    /// constant code generation widens.
+    fn compatible(given: AbiType, declared: AbiType) -> bool {
+        given == declared
+            || matches!(
+                (given, declared),
+                (AbiType::ArrayPtr, AbiType::ConstArrayPtr)
+                    | (AbiType::MatrixPtr, AbiType::ConstMatrixPtr)
+                    | (AbiType::I32, AbiType::I64)
+                    | (AbiType::I64, AbiType::U64)
+            )
+    }
+
+    #[test]
+    fn test_every_runtime_function_is_called_once() {
+        // Act
+        let program = abi_smoke_program();
+
+        // Assert
+        let mut called: Vec<&str> = main_calls(&program)
+            .iter()
+            .map(|call| match call {
+                LLInstruction::Call { function_name, .. } => function_name.as_str(),
+                other => panic!("unexpected instruction {:?}", other),
+            })
+            .collect();
+        assert_eq!(called.pop(), Some("naldom_exit"));
+        let mut registered: Vec<&str> = RUNTIME_FUNCTIONS
+            .iter()
+            .map(|function| function.name)
+            .filter(|name| *name != "naldom_exit")
+            .collect();
+        called.sort();
+        registered.sort();
+        assert_eq!(called, registered);
+        assert_eq!(
+            program.functions[1].basic_blocks[0].terminator,
+            Terminator::Unreachable
+        );
+    }
+
+    #[test]
+    fn test_arguments_have_the_registered_types() {
+        // Arrange
+        let program = abi_smoke_program();
+        let mut registers = HashMap::from([(ARGC, AbiType::I32), (ARGV, AbiType::CStrArray)]);
+
+        // Act & Assert
+        for call in main_calls(&program) {
+            let LLInstruction::Call {
+                dest,
+                function_name,
+                arguments,
+            } = call
+            else {
+                panic!("unexpected instruction {:?}", call);
+            };
+            let function = RUNTIME_FUNCTIONS
+                .iter()
+                .find(|function| function.name == function_name)
+                .unwrap();
+            assert_eq!(arguments.len(), function.params.len(), "{}", function_name);
+            for (argument, param) in arguments.iter().zip(function.params) {
+                let given = value_abi(argument, &registers);
+                assert!(
+                    compatible(given, param.ty),
+                    "'{}' gets a {:?} for '{}', which is a {:?}",
+                    function_name,
+                    given,
+                    param.name,
+                    param.ty
+                );
+            }
+            assert_eq!(dest.is_some(), function.return_type != AbiType::Void);
+            if let Some(dest) = dest {
+                registers.insert(*dest, function.return_type);
+            }
+        }
+    }
+
+    #[test]
+    fn test_exit_is_called_with_code_zero() {
+        // Act
+        let program = abi_smoke_program();
+
+        // Assert
+        assert_eq!(
+            main_calls(&program).last(),
+            Some(&LLInstruction::Call {
+                dest: None,
+                function_name: "naldom_exit".to_string(),
+                arguments: vec![LLValue::Constant(LLConstant::I32(0))],
+            })
+        );
+    }
+
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_smoke_program_verifies_and_declares_every_runtime_function() {
+        // Act
+        let llvm_ir =
+            crate::codegen_llvm::generate_llvm_ir(&abi_smoke_program(), "x86_64-unknown-linux-gnu")
+                .expect("the smoke program should verify");
+
+        // Assert
+        for function in RUNTIME_FUNCTIONS {
+            let symbol = format!("@{}(", function.name);
+            assert!(
+                llvm_ir
+                    .lines()
+                    .any(|line| line.starts_with("declare ") && line.contains(&symbol)),
+                "'{}' is not declared",
+                function.name
+            );
+        }
+    }
+}
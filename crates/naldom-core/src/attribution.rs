@@ -0,0 +1,428 @@
+// crates/naldom-core/src/attribution.rs
+
+//! How much of the generated code each function and each intent accounts for
+//! (`--stats`), so a program that grew large shows which sentence grew it.
+//!
+//! IR-LL instructions carry the IR-HL statement they were lowered from, code
+//! generation counts the LLVM instructions (one per line of IR) each
+//! statement produced, and lowering records which statements each intent
+//! became. Object files only know the size of whole functions, so a
+//! function's bytes are shared out among the intents in proportion to their
+//! LLVM instructions; those figures are approximate.
+
+use crate::interpreter::group_thousands;
+use crate::ir_size::IrSize;
+use crate::lowering_hl_to_ll::PROVENANCE_KEY;
+use naldom_ir::{Intent, LLProgram};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// The size of some part of the generated code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CodeSize {
+    pub ll_instructions: usize,
+    /// `None` for targets that do not go through LLVM.
+    pub llvm_instructions: Option<usize>,
+    /// `None` until [`SizeAttribution::add_object_bytes`] is called.
+    pub object_bytes: Option<u64>,
+}
+
+impl CodeSize {
+    /// What object bytes are shared out by: LLVM instructions if there are
+    /// any, IR-LL instructions otherwise.
+    fn weight(&self) -> usize {
+        self.llvm_instructions.unwrap_or(self.ll_instructions)
+    }
+
+    fn add(&mut self, other: &CodeSize) {
+        self.ll_instructions += other.ll_instructions;
+        self.llvm_instructions = sum(self.llvm_instructions, other.llvm_instructions);
+        self.object_bytes = sum(self.object_bytes, other.object_bytes);
+    }
+}
+
+fn sum<T: std::ops::Add<Output = T>>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
+
+/// The code one intent accounts for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntentSize {
+    /// The intent's index in the validated graph, or `None` for the code no
+    /// intent asked for: the program's prelude and its return.
+    pub index: Option<usize>,
+    /// The intent's name, e.g. `SortArray`.
+    pub name: &'static str,
+    pub size: CodeSize,
+}
+
+/// The code in one generated function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionSize {
+    pub name: String,
+    pub size: CodeSize,
+}
+
+/// The generated code, by function and by intent. Either list adds up to the
+/// whole program.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SizeAttribution {
+    /// One entry per intent, in program order, then the code no intent
+    /// accounts for.
+    pub intents: Vec<IntentSize>,
+    pub functions: Vec<FunctionSize>,
+}
+
+impl SizeAttribution {
+    /// Attributes `ll_program`, lowered from `intents` whose statements are
+    /// `intent_spans` (see [`LoweringContext::intent_spans`]), and the LLVM
+    /// instructions generated from it, if it went through LLVM.
+    ///
+    /// [`LoweringContext::intent_spans`]: crate::lowering::LoweringContext::intent_spans
+    pub fn new(
+        intents: &[Intent],
+        intent_spans: &[(usize, Range<usize>)],
+        ll_program: &LLProgram,
+        ir_size: Option<&IrSize>,
+    ) -> Self {
+        let intent_of = |statement: usize| {
+            intent_spans
+                .iter()
+                .find(|(_, span)| span.contains(&statement))
+                .map(|(index, _)| *index)
+        };
+
+        let mut by_intent: BTreeMap<Option<usize>, CodeSize> = BTreeMap::new();
+        let mut functions = Vec::new();
+        for function in &ll_program.functions {
+            let mut ll_instructions = 0;
+            for block in &function.basic_blocks {
+                ll_instructions += block.instructions.len();
+                for annotation in &block.annotations {
+                    if annotation.key == PROVENANCE_KEY
+                        && let Ok(statement) = annotation.value.parse()
+                    {
+                        by_intent
+                            .entry(intent_of(statement))
+                            .or_default()
+                            .ll_instructions += 1;
+                    }
+                }
+            }
+            functions.push(FunctionSize {
+                name: function.name.clone(),
+                size: CodeSize {
+                    ll_instructions,
+                    llvm_instructions: ir_size
+                        .map(|size| size.by_function.get(&function.name).copied().unwrap_or(0)),
+                    object_bytes: None,
+                },
+            });
+        }
+        if let Some(ir_size) = ir_size {
+            for (&statement, &instructions) in &ir_size.by_statement {
+                let size = by_intent.entry(intent_of(statement)).or_default();
+                size.llvm_instructions = sum(size.llvm_instructions, Some(instructions));
+            }
+        }
+
+        let mut attribution = SizeAttribution {
+            intents: intents
+                .iter()
+                .enumerate()
+                .map(|(index, intent)| {
+                    let mut size = by_intent.get(&Some(index)).copied().unwrap_or_default();
+                    if ir_size.is_some() {
+                        size.llvm_instructions.get_or_insert(0);
+                    }
+                    IntentSize {
+                        index: Some(index),
+                        name: intent.name(),
+                        size,
+                    }
+                })
+                .collect(),
+            functions,
+        };
+        // Whatever the intents do not account for is the rest's.
+        let total = attribution.total_of_functions();
+        let attributed = attribution.total();
+        attribution.intents.push(IntentSize {
+            index: None,
+            name: "(no intent)",
+            size: CodeSize {
+                ll_instructions: total.ll_instructions - attributed.ll_instructions,
+                llvm_instructions: total
+                    .llvm_instructions
+                    .map(|total| total - attributed.llvm_instructions.unwrap_or(0)),
+                object_bytes: None,
+            },
+        });
+        attribution
+    }
+
+    /// The size of the whole program, added up over the intents.
+    pub fn total(&self) -> CodeSize {
+        let mut total = CodeSize::default();
+        for intent in &self.intents {
+            total.add(&intent.size);
+        }
+        total
+    }
+
+    fn total_of_functions(&self) -> CodeSize {
+        let mut total = CodeSize::default();
+        for function in &self.functions {
+            total.add(&function.size);
+        }
+        total
+    }
+
+    /// Records the size of each function's machine code, from the object
+    /// file's symbols by name, and shares it out among the intents. The code
+    /// no intent accounts for takes what rounding leaves over.
+    pub fn add_object_bytes(&mut self, symbol_sizes: &BTreeMap<String, u64>) {
+        for function in &mut self.functions {
+            function.size.object_bytes =
+                Some(symbol_sizes.get(&function.name).copied().unwrap_or(0));
+        }
+        let bytes = self.total_of_functions().object_bytes.unwrap_or(0);
+        let weights: usize = self.intents.iter().map(|intent| intent.size.weight()).sum();
+        let mut shared = 0;
+        let Some((rest, intents)) = self.intents.split_last_mut() else {
+            return;
+        };
+        for intent in intents {
+            let share = match weights {
+                0 => 0,
+                _ => bytes * intent.size.weight() as u64 / weights as u64,
+            };
+            intent.size.object_bytes = Some(share);
+            shared += share;
+        }
+        rest.size.object_bytes = Some(bytes - shared);
+    }
+
+    /// A table of the functions' sizes and the total.
+    pub fn render_functions(&self) -> String {
+        let rows: Vec<(String, CodeSize)> = self
+            .functions
+            .iter()
+            .map(|function| (format!("@{}", function.name), function.size))
+            .collect();
+        render_table("function", &rows, self.total(), false)
+    }
+
+    /// A table of the `top` intents accounting for the most code, largest
+    /// first, then the code no intent accounts for and the total.
+    pub fn render_top_intents(&self, top: usize) -> String {
+        let Some((rest, intents)) = self.intents.split_last() else {
+            return String::new();
+        };
+        let mut largest: Vec<&IntentSize> = intents.iter().collect();
+        largest.sort_by(|a, b| {
+            let key = |intent: &IntentSize| {
+                (
+                    intent.size.object_bytes,
+                    intent.size.llvm_instructions,
+                    intent.size.ll_instructions,
+                )
+            };
+            key(b).cmp(&key(a)).then(a.index.cmp(&b.index))
+        });
+        let mut rows: Vec<(String, CodeSize)> = largest
+            .into_iter()
+            .take(top)
+            .map(|intent| {
+                let index = intent.index.map_or(0, |index| index + 1);
+                (format!("#{} {}", index, intent.name), intent.size)
+            })
+            .collect();
+        rows.push((rest.name.to_string(), rest.size));
+        let heading = format!(
+            "intent (top {} of {})",
+            top.min(intents.len()),
+            intents.len()
+        );
+        render_table(&heading, &rows, self.total(), true)
+    }
+}
+
+/// `rows` under `heading` and a total line, one column per measure. Shared
+/// object bytes are marked `~` when `approximate`.
+fn render_table(
+    heading: &str,
+    rows: &[(String, CodeSize)],
+    total: CodeSize,
+    approximate: bool,
+) -> String {
+    let cells = |size: &CodeSize, approximate: bool| {
+        [
+            group_thousands(size.ll_instructions),
+            size.llvm_instructions
+                .map_or("-".to_string(), group_thousands),
+            size.object_bytes.map_or("-".to_string(), |bytes| {
+                let bytes = group_thousands(bytes as usize);
+                if approximate {
+                    format!("~{}", bytes)
+                } else {
+                    bytes
+                }
+            }),
+        ]
+    };
+    let mut lines: Vec<(String, [String; 3])> = vec![(
+        heading.to_string(),
+        [
+            "IR-LL".to_string(),
+            "LLVM IR".to_string(),
+            "object bytes".to_string(),
+        ],
+    )];
+    lines.extend(
+        rows.iter()
+            .map(|(label, size)| (label.clone(), cells(size, approximate))),
+    );
+    lines.push(("total".to_string(), cells(&total, false)));
+
+    let label_width = lines
+        .iter()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or(0);
+    let widths: Vec<usize> = (0..3)
+        .map(|column| {
+            lines
+                .iter()
+                .map(|(_, cells)| cells[column].len())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    lines
+        .iter()
+        .map(|(label, cells)| {
+            format!(
+                "  {:<label_width$}  {:>w0$}  {:>w1$}  {:>w2$}\n",
+                label,
+                cells[0],
+                cells[1],
+                cells[2],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+            )
+        })
+        .collect()
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lowering::LoweringContext;
+    use crate::lowering_hl_to_ll::lower_hl_to_ll;
+    use crate::parser::parse_to_intent_graph;
+
+    const CANONICAL_PROGRAM: &str = r#"[
+        {"intent": "CreateArray", "parameters": {"size": 5}},
+        {"intent": "SortArray", "parameters": {"order": "ascending"}},
+        {"intent": "PrintArray"}
+    ]"#;
+
+    /// The canonical program, seeded so it has a prelude, with its LLVM
+    /// instructions made up: two per IR-LL instruction, plus a return.
+    fn canonical_attribution() -> SizeAttribution {
+        let intents = parse_to_intent_graph(CANONICAL_PROGRAM).unwrap();
+        let mut context = LoweringContext::new().with_seed(Some(7));
+        let hl_program = context.lower(&intents);
+        let ll_program = lower_hl_to_ll(&hl_program).unwrap();
+        let mut ir_size = IrSize::default();
+        for annotation in &ll_program.functions[0].basic_blocks[0].annotations {
+            let statement = annotation.value.parse().unwrap();
+            *ir_size.by_statement.entry(statement).or_default() += 2;
+            ir_size.instructions += 2;
+        }
+        ir_size.instructions += 1;
+        ir_size
+            .by_function
+            .insert("main".to_string(), ir_size.instructions);
+        SizeAttribution::new(
+            &intents,
+            context.intent_spans(),
+            &ll_program,
+            Some(&ir_size),
+        )
+    }
+
+    #[test]
+    fn test_every_intent_of_the_canonical_program_accounts_for_code() {
+        // Act
+        let attribution = canonical_attribution();
+
+        // Assert
+        let names: Vec<&str> = attribution
+            .intents
+            .iter()
+            .map(|intent| intent.name)
+            .collect();
+        assert_eq!(
+            names,
+            ["CreateArray", "SortArray", "PrintArray", "(no intent)"]
+        );
+        for intent in &attribution.intents[..3] {
+            assert!(intent.size.ll_instructions > 0, "{:?}", intent);
+            assert!(intent.size.llvm_instructions > Some(0), "{:?}", intent);
+        }
+        // The seed call and the return.
+        assert_eq!(attribution.intents[3].size.ll_instructions, 1);
+        assert_eq!(attribution.intents[3].size.llvm_instructions, Some(3));
+    }
+
+    #[test]
+    fn test_intents_and_functions_add_up_to_the_same_totals() {
+        // Arrange
+        let mut attribution = canonical_attribution();
+
+        // Act
+        attribution.add_object_bytes(&BTreeMap::from([("main".to_string(), 1_000)]));
+
+        // Assert
+        let total = attribution.total();
+        assert_eq!(total, attribution.functions[0].size);
+        assert_eq!(total.object_bytes, Some(1_000));
+        assert!(
+            attribution.intents[..3]
+                .iter()
+                .all(|intent| intent.size.object_bytes > Some(0))
+        );
+    }
+
+    #[test]
+    fn test_top_intents_table_lists_the_largest_first() {
+        // Arrange
+        let mut attribution = canonical_attribution();
+        attribution.add_object_bytes(&BTreeMap::from([("main".to_string(), 1_000)]));
+
+        // Act
+        let table = attribution.render_top_intents(2);
+
+        // Assert
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("  intent (top 2 of 3)"), "{}", table);
+        assert!(
+            lines[0].ends_with("IR-LL  LLVM IR  object bytes"),
+            "{}",
+            table
+        );
+        assert!(lines[1].starts_with("  #1 CreateArray"), "{}", table);
+        assert!(lines[3].starts_with("  (no intent)"), "{}", table);
+        assert!(lines[4].starts_with("  total"), "{}", table);
+        assert!(lines[4].ends_with("  1,000"), "{}", table);
+        assert!(lines[1].contains("~"), "{}", table);
+    }
+}
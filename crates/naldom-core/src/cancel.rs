@@ -0,0 +1,53 @@
+// crates/naldom-core/src/cancel.rs
+
+//! Cooperative cancellation of an in-flight compilation.
+//!
+//! An editor embedding the [`Compiler`](crate::compiler::Compiler) hands it a
+//! [`CancellationToken`] and keeps a clone. Cancelling the clone (e.g. because
+//! the user typed again) abandons the inference request and makes the
+//! pipeline stop at the next stage boundary or loop iteration.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Notify;
+
+/// A shared flag that, once set, stays set. Clones observe the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    /// A token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels every compilation holding this token or a clone of it.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Completes once the token is cancelled.
+    pub async fn cancelled(&self) {
+        loop {
+            // Register before checking, so a `cancel` in between still wakes us.
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
@@ -1,138 +1,884 @@
 // crates/naldom-core/src/codegen_llvm.rs
 
+use crate::ir_size::IrSize;
+use crate::lowering_hl_to_ll::PROVENANCE_KEY;
+use crate::unsupported::{UnsupportedConstruct, UnsupportedFeature};
+use inkwell::IntPredicate;
+use inkwell::OptimizationLevel;
+use inkwell::attributes::AttributeLoc;
+use inkwell::basic_block::BasicBlock as LlvmBlock;
 use inkwell::builder::Builder;
 use inkwell::context::Context;
+use inkwell::memory_buffer::MemoryBuffer;
+use inkwell::module::Linkage;
 use inkwell::module::Module;
-use inkwell::targets::TargetTriple;
+use inkwell::targets::{
+    CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetTriple,
+};
 use inkwell::types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum};
-use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue, PointerValue};
+use inkwell::values::{
+    BasicMetadataValueEnum, BasicValue, BasicValueEnum, FunctionValue, InstructionOpcode,
+    InstructionValue, IntValue, PhiValue, PointerValue, ValueKind,
+};
+use naldom_ir::runtime_abi::{AbiType, RuntimeFunction, WASM_IMPORT_MODULE, find_runtime_function};
 use naldom_ir::{
-    BasicBlock, LLConstant, LLFunction, LLInstruction, LLProgram, LLType, LLValue as NaldomValue,
-    Register, Terminator,
+    Annotation, BasicBlock, BinOp, ICmpPredicate, LLConstant, LLFunction, LLInstruction, LLProgram,
+    LLType, LLValue as NaldomValue, Register, Terminator,
 };
 use std::collections::HashMap;
+use std::fmt;
+
+/// An error raised while generating LLVM IR.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodegenError {
+    /// A call passed a different number of arguments than the callee declares.
+    ArgumentCountMismatch {
+        function: String,
+        call_index: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A call argument cannot be converted to the declared parameter type.
+    ArgumentTypeMismatch {
+        function: String,
+        call_index: usize,
+        argument_index: usize,
+        expected: String,
+        found: String,
+    },
+    /// The operands of a comparison or arithmetic cannot be brought to a
+    /// common integer type.
+    OperandTypeMismatch { lhs: String, rhs: String },
+    /// A stored value cannot be converted to the type its slot holds.
+    StoreTypeMismatch { slot: String, value: String },
+    /// A branch names a basic block its function does not have.
+    UnknownBlock { function: String, block: usize },
+    /// A function reference names a function the program does not define.
+    UnknownFunction { function: String, name: String },
+    /// A phi's incoming blocks are not exactly the predecessors of its
+    /// block: `missing` branch to it without a value, and `unexpected` have a
+    /// value without branching to it.
+    PhiPredecessorMismatch {
+        function: String,
+        block: usize,
+        dest: Register,
+        missing: Vec<usize>,
+        unexpected: Vec<usize>,
+    },
+    /// LLVM rejected the finished module.
+    Verification { message: String, ir: String },
+    /// LLVM could not turn a module into an object file for the triple.
+    ObjectEmission { triple: String, message: String },
+    /// The program uses a construct codegen cannot handle yet.
+    Unsupported(UnsupportedFeature),
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodegenError::ArgumentCountMismatch {
+                function,
+                call_index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Codegen Error: call #{} to '{}' passes {} arguments, but it is declared with {}.",
+                call_index + 1,
+                function,
+                found,
+                expected
+            ),
+            CodegenError::ArgumentTypeMismatch {
+                function,
+                call_index,
+                argument_index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Codegen Error: call #{} to '{}' passes {} as argument {}, but the parameter is declared as {}.",
+                call_index + 1,
+                function,
+                found,
+                argument_index + 1,
+                expected
+            ),
+            CodegenError::OperandTypeMismatch { lhs, rhs } => write!(
+                f,
+                "Codegen Error: cannot combine a value of type {} with one of type {}.",
+                lhs, rhs
+            ),
+            CodegenError::StoreTypeMismatch { slot, value } => write!(
+                f,
+                "Codegen Error: cannot store a value of type {} in a slot of type {}.",
+                value, slot
+            ),
+            CodegenError::UnknownBlock { function, block } => write!(
+                f,
+                "Codegen Error: '{}' branches to basic block {}, which does not exist.",
+                function, block
+            ),
+            CodegenError::UnknownFunction { function, name } => write!(
+                f,
+                "Codegen Error: '{}' refers to the function '{}', which the program does not define.",
+                function, name
+            ),
+            CodegenError::PhiPredecessorMismatch {
+                function,
+                block,
+                dest,
+                missing,
+                unexpected,
+            } => {
+                let blocks = |ids: &[usize]| {
+                    ids.iter()
+                        .map(|id| format!("bb{}", id))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                let mut problems = Vec::new();
+                if !missing.is_empty() {
+                    problems.push(format!("has no value for predecessor {}", blocks(missing)));
+                }
+                if !unexpected.is_empty() {
+                    problems.push(format!(
+                        "has a value for {}, which does not branch to it",
+                        blocks(unexpected)
+                    ));
+                }
+                write!(
+                    f,
+                    "Codegen Error: the phi defining {} in bb{} of '{}' {}.",
+                    dest,
+                    block,
+                    function,
+                    problems.join(" and ")
+                )
+            }
+            CodegenError::Verification { message, ir } => write!(
+                f,
+                "LLVM module verification failed: {}\nGenerated IR:\n{}",
+                message, ir
+            ),
+            CodegenError::ObjectEmission { triple, message } => write!(
+                f,
+                "Codegen Error: cannot emit an object file for '{}': {}",
+                triple, message
+            ),
+            CodegenError::Unsupported(feature) => write!(f, "{}", feature),
+        }
+    }
+}
 
+impl std::error::Error for CodegenError {}
+
+/// A phi built without its incoming values, which are added once every block
+/// of its function exists.
+struct PendingPhi<'ctx> {
+    phi: PhiValue<'ctx>,
+    ty: BasicTypeEnum<'ctx>,
+    dest: Register,
+    /// The id of the block the phi is in.
+    block: usize,
+    incoming: Vec<(NaldomValue, usize)>,
+}
+
+/// The state of one compilation. Everything that depends on the target
+/// lives here, never in statics, so a host can generate the same program
+/// for several targets in one process, one after another or at once.
 pub struct CodeGenContext<'ctx> {
     context: &'ctx Context,
     builder: Builder<'ctx>,
     module: Module<'ctx>,
+    /// The stack slot of every register. This map and `blocks` are only
+    /// looked up, never iterated, so the module's contents follow the program's
+    /// order and are the same from one build to the next.
     registers: HashMap<Register, (PointerValue<'ctx>, LLType)>,
-    #[allow(dead_code)]
+    /// The LLVM blocks of the current function, by `BasicBlock::id`.
+    blocks: HashMap<usize, LlvmBlock<'ctx>>,
+    /// How many calls have been generated so far, for error messages.
+    call_count: usize,
+    /// How many allocas have been generated so far. They go to the top of the
+    /// entry block, away from the instruction that asked for them.
+    alloca_count: usize,
+    /// How many phis have been generated so far. Like allocas, they go to
+    /// the top of their block.
+    phi_count: usize,
+    /// The phis of the current function, whose incoming values are added
+    /// once every block exists.
+    pending_phis: Vec<PendingPhi<'ctx>>,
+    /// The instructions generated so far, by provenance.
+    size: IrSize,
+    /// Whether runtime functions are wasm imports from `WASM_IMPORT_MODULE`.
+    wasm_imports: bool,
     current_function: Option<FunctionValue<'ctx>>,
+    /// The block id and index of the instruction being generated, for error
+    /// messages.
+    current_instruction: (usize, usize),
 }
 
 impl<'ctx> CodeGenContext<'ctx> {
-    fn new(context: &'ctx Context, module_name: &str) -> Self {
+    fn new(context: &'ctx Context, module_name: &str, target: &TargetConfig) -> Self {
         let module = context.create_module(module_name);
+        module.set_triple(&TargetTriple::create(&target.triple));
         let builder = context.create_builder();
         CodeGenContext {
             context,
             builder,
             module,
             registers: HashMap::new(),
+            blocks: HashMap::new(),
+            call_count: 0,
+            alloca_count: 0,
+            phi_count: 0,
+            pending_phis: Vec::new(),
+            size: IrSize::default(),
+            wasm_imports: target.triple.starts_with("wasm"),
             current_function: None,
+            current_instruction: (0, 0),
         }
     }
 
-    fn codegen_function(&mut self, func: &LLFunction) {
-        let fn_type = self.to_llvm_fn_type(&func.parameters, &func.return_type);
-        let function = self.module.add_function(&func.name, fn_type, None);
+    /// Adds `func` to the module without a body, so calls and references
+    /// from any function can find it, whichever is generated first.
+    fn declare_function(&self, func: &LLFunction) -> Result<FunctionValue<'ctx>, CodegenError> {
+        // No function is being generated yet, so the signature is the location.
+        let fn_type = self
+            .to_llvm_fn_type(&func.parameters, &func.return_type)
+            .map_err(|_| {
+                CodegenError::Unsupported(UnsupportedFeature::new(
+                    UnsupportedConstruct::VoidValue,
+                    format!("the parameters of function '{}'", func.name),
+                ))
+            })?;
+        Ok(self.module.add_function(&func.name, fn_type, None))
+    }
+
+    /// Generates the body of `func` into `function`, its declaration.
+    fn codegen_function(
+        &mut self,
+        func: &LLFunction,
+        function: FunctionValue<'ctx>,
+    ) -> Result<(), CodegenError> {
         self.current_function = Some(function);
+        let before = self.size.instructions;
 
-        let entry_block = self.context.append_basic_block(function, "entry");
+        // Every block exists before any is filled in, so branches can refer
+        // to blocks further down. The first block is the entry.
+        self.blocks.clear();
+        self.pending_phis.clear();
+        for (position, block) in func.basic_blocks.iter().enumerate() {
+            let name = if position == 0 {
+                "entry".to_string()
+            } else {
+                format!("bb{}", block.id)
+            };
+            let llvm_block = self.context.append_basic_block(function, &name);
+            self.blocks.insert(block.id, llvm_block);
+        }
+        let Some(entry_block) = function.get_first_basic_block() else {
+            return Ok(());
+        };
         self.builder.position_at_end(entry_block);
 
-        if let Some(block) = func.basic_blocks.first() {
-            self.codegen_basic_block(block);
+        // Registers are numbered per function.
+        self.registers.clear();
+        let ((), added) = self.measure(|this| {
+            for ((ty, register), value) in func.parameters.iter().zip(function.get_param_iter()) {
+                let slot =
+                    this.build_entry_alloca(value.get_type(), &format!("reg_{}", register.0));
+                this.builder.build_store(slot, value).unwrap();
+                this.registers.insert(*register, (slot, ty.clone()));
+            }
+            Ok(())
+        })?;
+        self.size.instructions += added;
+
+        for block in &func.basic_blocks {
+            self.builder.position_at_end(self.blocks[&block.id]);
+            self.codegen_basic_block(block)?;
+        }
+        self.complete_phis(func)?;
+        self.size
+            .by_function
+            .insert(func.name.clone(), self.size.instructions - before);
+        Ok(())
+    }
+
+    /// Allocates a stack slot at the top of the current function's entry
+    /// block, so it dominates every use whichever block asks for it.
+    fn build_entry_alloca(&mut self, ty: BasicTypeEnum<'ctx>, name: &str) -> PointerValue<'ctx> {
+        let entry = self
+            .current_function
+            .and_then(|function| function.get_first_basic_block())
+            .expect("allocas are built inside a function body");
+        let builder = self.context.create_builder();
+        match entry.get_first_instruction() {
+            Some(first) => builder.position_before(&first),
+            None => builder.position_at_end(entry),
+        }
+        self.alloca_count += 1;
+        builder.build_alloca(ty, name).unwrap()
+    }
+
+    /// Runs `generate` and returns its result with the number of instructions
+    /// it added: allocas and phis anywhere, everything else after the
+    /// builder's position.
+    fn measure<T>(
+        &mut self,
+        generate: impl FnOnce(&mut Self) -> Result<T, CodegenError>,
+    ) -> Result<(T, usize), CodegenError> {
+        let block = self
+            .builder
+            .get_insert_block()
+            .expect("the builder is positioned in a block");
+        let last = block.get_last_instruction();
+        let (allocas, phis) = (self.alloca_count, self.phi_count);
+        let result = generate(self)?;
+
+        let mut added = (self.alloca_count - allocas) + (self.phi_count - phis);
+        let mut next = match last {
+            Some(last) => last.get_next_instruction(),
+            None => block.get_first_instruction(),
+        };
+        while let Some(instruction) = next {
+            // Counted above; they may sit in this very block.
+            if !matches!(
+                instruction.get_opcode(),
+                InstructionOpcode::Alloca | InstructionOpcode::Phi
+            ) {
+                added += 1;
+            }
+            next = instruction.get_next_instruction();
         }
+        Ok((result, added))
     }
 
-    fn codegen_basic_block(&mut self, block: &BasicBlock) {
-        for instr in &block.instructions {
-            self.codegen_instruction(instr);
+    fn codegen_basic_block(&mut self, block: &BasicBlock) -> Result<(), CodegenError> {
+        // Grouped up front: looking each instruction's annotations up in turn
+        // would scan the whole list once per instruction.
+        let mut annotations: Vec<Vec<&Annotation>> = vec![Vec::new(); block.instructions.len()];
+        for annotation in &block.annotations {
+            if let Some(group) = annotations.get_mut(annotation.instruction) {
+                group.push(annotation);
+            }
+        }
+        for (index, (instr, annotations)) in block.instructions.iter().zip(annotations).enumerate()
+        {
+            self.current_instruction = (block.id, index);
+            let (emitted, added) = self.measure(|this| this.codegen_instruction(instr))?;
+            self.size.instructions += added;
+            for annotation in annotations {
+                if annotation.key == PROVENANCE_KEY
+                    && let Ok(statement) = annotation.value.parse()
+                {
+                    *self.size.by_statement.entry(statement).or_default() += added;
+                }
+                self.attach_annotation(emitted, annotation);
+            }
         }
-        self.codegen_terminator(&block.terminator);
+        let ((), added) = self.measure(|this| this.codegen_terminator(&block.terminator))?;
+        self.size.instructions += added;
+        Ok(())
     }
 
-    fn codegen_instruction(&mut self, instr: &LLInstruction) {
+    /// Attaches `annotation` to `instruction` as `!naldom.<key> !{!"<value>"}`.
+    fn attach_annotation(&self, instruction: InstructionValue<'ctx>, annotation: &Annotation) {
+        let kind_id = self
+            .context
+            .get_kind_id(&format!("naldom.{}", annotation.key));
+        let node = self
+            .context
+            .metadata_node(&[self.context.metadata_string(&annotation.value).into()]);
+        instruction
+            .set_metadata(node, kind_id)
+            .expect("a metadata node is valid instruction metadata");
+    }
+
+    /// Generates `instr` and returns the LLVM instruction that carries its
+    /// annotations: the `alloca`, the `call`, the comparison, the
+    /// arithmetic, the `load`, the `store` or the `phi`.
+    fn codegen_instruction(
+        &mut self,
+        instr: &LLInstruction,
+    ) -> Result<InstructionValue<'ctx>, CodegenError> {
         match instr {
             LLInstruction::Alloc { dest, ty } => {
-                let llvm_type = self.to_llvm_type(ty);
-                let alloca = self
-                    .builder
-                    .build_alloca(llvm_type, &format!("reg_{}", dest.0))
-                    .unwrap();
+                let llvm_type = self.to_llvm_type(ty)?;
+                let alloca = self.build_entry_alloca(llvm_type, &format!("reg_{}", dest.0));
                 self.registers.insert(*dest, (alloca, ty.clone()));
+                Ok(alloca
+                    .as_instruction()
+                    .expect("an alloca is an instruction"))
             }
             LLInstruction::Call {
                 dest,
                 function_name,
                 arguments,
             } => {
-                let callee = self.module.get_function(function_name).unwrap_or_else(|| {
-                    // This logic is now robust enough to declare our new sleep function
-                    self.declare_placeholder_function(function_name, arguments, dest.is_some())
-                });
+                let callee = match self.module.get_function(function_name) {
+                    Some(callee) => callee,
+                    // Runtime functions are declared with their registered signature;
+                    // anything else gets a signature inferred from the call site.
+                    None => match find_runtime_function(function_name) {
+                        Some(runtime_function) => {
+                            self.declare_runtime_function(runtime_function)?
+                        }
+                        None => self.declare_placeholder_function(
+                            function_name,
+                            arguments,
+                            dest.is_some(),
+                        )?,
+                    },
+                };
 
-                let args: Vec<BasicMetadataValueEnum> = arguments
-                    .iter()
-                    .map(|arg| self.codegen_value(arg).into())
-                    .collect();
+                let call_index = self.call_count;
+                self.call_count += 1;
+                let args = self.codegen_call_arguments(callee, arguments, call_index)?;
 
                 let call_site_value = self.builder.build_call(callee, &args, "call_tmp").unwrap();
+                let call = match call_site_value.try_as_basic_value() {
+                    ValueKind::Basic(value) => value
+                        .as_instruction_value()
+                        .expect("a call is an instruction"),
+                    ValueKind::Instruction(instruction) => instruction,
+                };
 
                 if let Some(dest_reg) = dest {
                     let return_value = call_site_value
                         .try_as_basic_value()
-                        .left()
+                        .basic()
                         .expect("Call did not return a value");
                     let return_type = return_value.get_type();
 
-                    let dest_ptr = self
-                        .builder
-                        .build_alloca(return_type, &format!("reg_{}", dest_reg.0))
-                        .unwrap();
-                    let naldom_return_type = self.inkwell_type_to_naldom_type(return_type);
+                    let dest_ptr =
+                        self.build_entry_alloca(return_type, &format!("reg_{}", dest_reg.0));
+                    let naldom_return_type = self
+                        .inkwell_type_to_naldom_type(return_type)
+                        .ok_or_else(|| {
+                            self.unsupported(UnsupportedConstruct::AggregateReturnValue)
+                        })?;
                     self.registers
                         .insert(*dest_reg, (dest_ptr, naldom_return_type));
                     self.builder.build_store(dest_ptr, return_value).unwrap();
                 }
+                Ok(call)
+            }
+            LLInstruction::ICmp {
+                dest,
+                predicate,
+                lhs,
+                rhs,
+            } => {
+                let lhs = self.codegen_value(lhs)?;
+                let rhs = self.codegen_value(rhs)?;
+                let (BasicValueEnum::IntValue(lhs), Some(BasicValueEnum::IntValue(rhs))) =
+                    (lhs, self.coerce_value(rhs, lhs.get_type().into()))
+                else {
+                    return Err(CodegenError::OperandTypeMismatch {
+                        lhs: lhs.get_type().print_to_string().to_string(),
+                        rhs: rhs.get_type().print_to_string().to_string(),
+                    });
+                };
+                let predicate = match predicate {
+                    ICmpPredicate::Eq => IntPredicate::EQ,
+                    ICmpPredicate::Ne => IntPredicate::NE,
+                    ICmpPredicate::Slt => IntPredicate::SLT,
+                    ICmpPredicate::Sle => IntPredicate::SLE,
+                    ICmpPredicate::Sgt => IntPredicate::SGT,
+                    ICmpPredicate::Sge => IntPredicate::SGE,
+                };
+                let flag = self
+                    .builder
+                    .build_int_compare(predicate, lhs, rhs, "cmp_tmp")
+                    .unwrap();
+                let slot =
+                    self.build_entry_alloca(flag.get_type().into(), &format!("reg_{}", dest.0));
+                self.registers.insert(*dest, (slot, LLType::I1));
+                self.builder.build_store(slot, flag).unwrap();
+                Ok(flag
+                    .as_instruction_value()
+                    .expect("a comparison is an instruction"))
+            }
+            LLInstruction::BinaryOp {
+                dest,
+                op,
+                lhs,
+                rhs,
+                ty,
+            } => {
+                let target = self.to_llvm_type(ty)?;
+                let lhs = self.codegen_value(lhs)?;
+                let rhs = self.codegen_value(rhs)?;
+                let (Some(BasicValueEnum::IntValue(lhs)), Some(BasicValueEnum::IntValue(rhs))) = (
+                    self.coerce_value(lhs, target.into()),
+                    self.coerce_value(rhs, target.into()),
+                ) else {
+                    return Err(CodegenError::OperandTypeMismatch {
+                        lhs: lhs.get_type().print_to_string().to_string(),
+                        rhs: rhs.get_type().print_to_string().to_string(),
+                    });
+                };
+                let result = match op {
+                    BinOp::Add => self.builder.build_int_add(lhs, rhs, "add_tmp"),
+                    BinOp::Sub => self.builder.build_int_sub(lhs, rhs, "sub_tmp"),
+                    BinOp::Mul => self.builder.build_int_mul(lhs, rhs, "mul_tmp"),
+                    BinOp::SDiv => self.builder.build_int_signed_div(lhs, rhs, "div_tmp"),
+                }
+                .unwrap();
+                let slot = self.build_entry_alloca(target, &format!("reg_{}", dest.0));
+                self.registers.insert(*dest, (slot, ty.clone()));
+                let store = self.builder.build_store(slot, result).unwrap();
+                // Constant operands fold, leaving the store as the only
+                // instruction.
+                Ok(result.as_instruction_value().unwrap_or(store))
+            }
+            LLInstruction::Phi { dest, ty, incoming } => {
+                // The incoming values are loaded in the predecessors, which
+                // may not have been generated yet.
+                let llvm_type = self.to_llvm_type(ty)?;
+                let phi = self.build_phi_at_top(llvm_type, &format!("phi_{}", dest.0));
+                let slot = self.build_entry_alloca(llvm_type, &format!("reg_{}", dest.0));
+                self.registers.insert(*dest, (slot, ty.clone()));
+                self.builder
+                    .build_store(slot, phi.as_basic_value())
+                    .unwrap();
+                self.pending_phis.push(PendingPhi {
+                    phi,
+                    ty: llvm_type,
+                    dest: *dest,
+                    block: self.current_instruction.0,
+                    incoming: incoming.clone(),
+                });
+                Ok(phi.as_instruction())
+            }
+            LLInstruction::Load { dest, source_ptr } => {
+                // An `Alloc` register's slot is the allocated memory itself.
+                let (source, ty) = self.register(*source_ptr)?;
+                let llvm_type = self.to_llvm_type(&ty)?;
+                let value = self
+                    .builder
+                    .build_load(llvm_type, source, &format!("load_slot_{}", source_ptr.0))
+                    .unwrap();
+                let slot = self.build_entry_alloca(llvm_type, &format!("reg_{}", dest.0));
+                self.registers.insert(*dest, (slot, ty));
+                self.builder.build_store(slot, value).unwrap();
+                Ok(value
+                    .as_instruction_value()
+                    .expect("a load is an instruction"))
+            }
+            LLInstruction::Store { value, dest_ptr } => {
+                let (slot, ty) = self.register(*dest_ptr)?;
+                let target = self.to_llvm_type(&ty)?;
+                let value = self.codegen_value(value)?;
+                let converted = self.coerce_value(value, target.into()).ok_or_else(|| {
+                    CodegenError::StoreTypeMismatch {
+                        slot: target.print_to_string().to_string(),
+                        value: value.get_type().print_to_string().to_string(),
+                    }
+                })?;
+                Ok(self.builder.build_store(slot, converted).unwrap())
             }
-            _ => unimplemented!("Instruction not yet supported in codegen"),
         }
     }
 
-    fn codegen_terminator(&mut self, term: &Terminator) {
+    /// The stack slot of `register` and the type stored there.
+    fn register(&self, register: Register) -> Result<(PointerValue<'ctx>, LLType), CodegenError> {
+        self.registers
+            .get(&register)
+            .cloned()
+            .ok_or_else(|| self.unsupported(UnsupportedConstruct::UnallocatedRegister))
+    }
+
+    /// The name of the function being generated, for error messages.
+    fn current_function_name(&self) -> String {
+        self.current_function
+            .map(|function| function.get_name().to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// An error for `construct`, located at the instruction being generated.
+    fn unsupported(&self, construct: UnsupportedConstruct) -> CodegenError {
+        let function = self.current_function_name();
+        let (block, index) = self.current_instruction;
+        CodegenError::Unsupported(UnsupportedFeature::new(
+            construct,
+            format!(
+                "function '{}', basic block {}, instruction #{}",
+                function,
+                block,
+                index + 1
+            ),
+        ))
+    }
+
+    /// Generates call arguments, converting each one to the callee's declared
+    /// parameter type. A callee is declared from its first call (or the ABI
+    /// registry), so later calls may pass e.g. an `i32` where it expects `i64`.
+    fn codegen_call_arguments(
+        &self,
+        callee: FunctionValue<'ctx>,
+        arguments: &[NaldomValue],
+        call_index: usize,
+    ) -> Result<Vec<BasicMetadataValueEnum<'ctx>>, CodegenError> {
+        let function = callee.get_name().to_string_lossy().into_owned();
+        let param_types = callee.get_type().get_param_types();
+        if param_types.len() != arguments.len() {
+            return Err(CodegenError::ArgumentCountMismatch {
+                function,
+                call_index,
+                expected: param_types.len(),
+                found: arguments.len(),
+            });
+        }
+
+        arguments
+            .iter()
+            .zip(param_types)
+            .enumerate()
+            .map(|(argument_index, (arg, expected))| {
+                let value = self.codegen_value(arg)?;
+                self.coerce_value(value, expected)
+                    .map(Into::into)
+                    .ok_or_else(|| CodegenError::ArgumentTypeMismatch {
+                        function: function.clone(),
+                        call_index,
+                        argument_index,
+                        expected: expected.print_to_string().to_string(),
+                        found: value.get_type().print_to_string().to_string(),
+                    })
+            })
+            .collect()
+    }
+
+    /// Converts `value` to `target`: integers are sign-extended or truncated,
+    /// floats extended or truncated, and pointers cast. Returns `None` when no
+    /// such conversion exists.
+    fn coerce_value(
+        &self,
+        value: BasicValueEnum<'ctx>,
+        target: BasicMetadataTypeEnum<'ctx>,
+    ) -> Option<BasicValueEnum<'ctx>> {
+        let target = BasicTypeEnum::try_from(target).ok()?;
+        if value.get_type() == target {
+            return Some(value);
+        }
+        match (value, target) {
+            (BasicValueEnum::IntValue(int), BasicTypeEnum::IntType(int_type)) => {
+                let converted = if int.get_type().get_bit_width() < int_type.get_bit_width() {
+                    self.builder.build_int_s_extend(int, int_type, "sext_tmp")
+                } else {
+                    self.builder.build_int_truncate(int, int_type, "trunc_tmp")
+                };
+                converted.ok().map(Into::into)
+            }
+            (BasicValueEnum::FloatValue(float), BasicTypeEnum::FloatType(float_type)) => self
+                .builder
+                .build_float_cast(float, float_type, "fcast_tmp")
+                .ok()
+                .map(Into::into),
+            (BasicValueEnum::PointerValue(ptr), BasicTypeEnum::PointerType(ptr_type)) => self
+                .builder
+                .build_pointer_cast(ptr, ptr_type, "ptrcast_tmp")
+                .ok()
+                .map(Into::into),
+            _ => None,
+        }
+    }
+
+    /// Generates a terminator. A returned value is converted to the function's
+    /// return type, e.g. a runtime's `i64` status to `main`'s `i32`.
+    fn codegen_terminator(&mut self, term: &Terminator) -> Result<(), CodegenError> {
         match term {
             Terminator::Return(Some(val)) => {
-                let llvm_val = self.codegen_value(val);
+                let value = self.codegen_value(val)?;
+                let llvm_val = self
+                    .current_function
+                    .and_then(|function| function.get_type().get_return_type())
+                    .and_then(|ty| self.coerce_value(value, ty.into()))
+                    .unwrap_or(value);
                 self.builder.build_return(Some(&llvm_val)).unwrap();
             }
             Terminator::Return(None) => {
                 self.builder.build_return(None).unwrap();
             }
+            Terminator::Br { target } => {
+                let target = self.block(*target)?;
+                self.builder.build_unconditional_branch(target).unwrap();
+            }
+            Terminator::CondBr {
+                cond,
+                then_block,
+                else_block,
+            } => {
+                let then_block = self.block(*then_block)?;
+                let else_block = self.block(*else_block)?;
+                let cond = self.codegen_value(cond)?;
+                let BasicValueEnum::IntValue(cond) = cond else {
+                    return Err(CodegenError::OperandTypeMismatch {
+                        lhs: "i1".to_string(),
+                        rhs: cond.get_type().print_to_string().to_string(),
+                    });
+                };
+                self.builder
+                    .build_conditional_branch(cond, then_block, else_block)
+                    .unwrap();
+            }
+            Terminator::Unreachable => {
+                self.builder.build_unreachable().unwrap();
+            }
         }
+        Ok(())
+    }
+
+    /// Builds a phi in the current block, below its other phis and above
+    /// everything else, as LLVM requires.
+    fn build_phi_at_top(&mut self, ty: BasicTypeEnum<'ctx>, name: &str) -> PhiValue<'ctx> {
+        let block = self
+            .builder
+            .get_insert_block()
+            .expect("the builder is positioned in a block");
+        let mut first = block.get_first_instruction();
+        while let Some(instruction) = first
+            && instruction.get_opcode() == InstructionOpcode::Phi
+        {
+            first = instruction.get_next_instruction();
+        }
+        let builder = self.context.create_builder();
+        match first {
+            Some(first) => builder.position_before(&first),
+            None => builder.position_at_end(block),
+        }
+        self.phi_count += 1;
+        builder.build_phi(ty, name).unwrap()
+    }
+
+    /// Adds the incoming values of `func`'s phis, now that every block has
+    /// been generated. Each value is loaded at the end of its predecessor,
+    /// just before the branch. A phi must have exactly one value for each
+    /// predecessor of its block.
+    fn complete_phis(&mut self, func: &LLFunction) -> Result<(), CodegenError> {
+        for pending in std::mem::take(&mut self.pending_phis) {
+            let predecessors = func.predecessors(pending.block);
+            let from: Vec<usize> = pending.incoming.iter().map(|(_, block)| *block).collect();
+            let missing: Vec<usize> = predecessors
+                .iter()
+                .copied()
+                .filter(|id| !from.contains(id))
+                .collect();
+            let unexpected: Vec<usize> = from
+                .iter()
+                .enumerate()
+                .filter(|(position, id)| {
+                    !predecessors.contains(id) || from[..*position].contains(id)
+                })
+                .map(|(_, id)| *id)
+                .collect();
+            if !missing.is_empty() || !unexpected.is_empty() {
+                return Err(CodegenError::PhiPredecessorMismatch {
+                    function: func.name.clone(),
+                    block: pending.block,
+                    dest: pending.dest,
+                    missing,
+                    unexpected,
+                });
+            }
+
+            for (value, id) in &pending.incoming {
+                let block = self.block(*id)?;
+                let terminator = block
+                    .get_terminator()
+                    .expect("every generated block ends in a terminator");
+                let before = instruction_count(block);
+                self.builder.position_before(&terminator);
+                let value = self.codegen_value(value)?;
+                let converted = self.coerce_value(value, pending.ty.into()).ok_or_else(|| {
+                    CodegenError::OperandTypeMismatch {
+                        lhs: pending.ty.print_to_string().to_string(),
+                        rhs: value.get_type().print_to_string().to_string(),
+                    }
+                })?;
+                pending.phi.add_incoming(&[(&converted, block)]);
+                self.size.instructions += instruction_count(block) - before;
+            }
+        }
+        Ok(())
+    }
+
+    /// The LLVM block for the current function's block `id`.
+    fn block(&self, id: usize) -> Result<LlvmBlock<'ctx>, CodegenError> {
+        self.blocks
+            .get(&id)
+            .copied()
+            .ok_or_else(|| CodegenError::UnknownBlock {
+                function: self.current_function_name(),
+                block: id,
+            })
     }
 
-    fn codegen_value(&self, val: &NaldomValue) -> BasicValueEnum<'ctx> {
-        match val {
+    fn codegen_value(&self, val: &NaldomValue) -> Result<BasicValueEnum<'ctx>, CodegenError> {
+        Ok(match val {
             NaldomValue::Constant(c) => match c {
                 LLConstant::I64(i) => self.context.i64_type().const_int(*i as u64, false).into(),
                 LLConstant::I32(i) => self.context.i32_type().const_int(*i as u64, false).into(),
                 LLConstant::F64(f) => self.context.f64_type().const_float(*f).into(),
+                LLConstant::Str(text) => {
+                    // The analyzer rejects NULs in string parameters; one here
+                    // would silently cut the C string short.
+                    assert!(
+                        !text.contains('\0'),
+                        "string constant {:?} contains a NUL character",
+                        text
+                    );
+                    self.builder
+                        .build_global_string_ptr(text, "str")
+                        .unwrap()
+                        .as_pointer_value()
+                        .into()
+                }
+                LLConstant::I64Array(values) => {
+                    // A private constant the runtime copies from; it is only
+                    // read through the pointer passed to the call.
+                    let i64_type = self.context.i64_type();
+                    let elements: Vec<IntValue> = values
+                        .iter()
+                        .map(|&value| i64_type.const_int(value as u64, false))
+                        .collect();
+                    let array = i64_type.const_array(&elements);
+                    let global = self.module.add_global(array.get_type(), None, "values");
+                    global.set_initializer(&array);
+                    global.set_constant(true);
+                    global.set_linkage(Linkage::Private);
+                    global.set_unnamed_addr(true);
+                    global.as_pointer_value().into()
+                }
+                LLConstant::Function(name) => self
+                    .module
+                    .get_function(name)
+                    .ok_or_else(|| CodegenError::UnknownFunction {
+                        function: self.current_function_name(),
+                        name: name.clone(),
+                    })?
+                    .as_global_value()
+                    .as_pointer_value()
+                    .into(),
             },
             NaldomValue::Register(reg) => {
-                let (ptr, ty) = self.registers.get(reg).expect("Register not allocated");
-                let llvm_type = self.to_llvm_type(ty);
+                let (ptr, ty) = self.register(*reg)?;
+                let llvm_type = self.to_llvm_type(&ty)?;
                 self.builder
-                    .build_load(llvm_type, *ptr, &format!("load_reg_{}", reg.0))
+                    .build_load(llvm_type, ptr, &format!("load_reg_{}", reg.0))
                     .unwrap()
             }
-        }
+        })
     }
 
-    fn to_llvm_type(&self, ty: &LLType) -> BasicTypeEnum<'ctx> {
-        match ty {
+    /// The LLVM type of a value of type `ty`. No value has type void.
+    fn to_llvm_type(&self, ty: &LLType) -> Result<BasicTypeEnum<'ctx>, CodegenError> {
+        Ok(match ty {
+            LLType::I1 => self.context.bool_type().into(),
+            LLType::I8 => self.context.i8_type().into(),
             LLType::I32 => self.context.i32_type().into(),
             LLType::I64 => self.context.i64_type().into(),
             LLType::F64 => self.context.f64_type().into(),
@@ -140,22 +886,22 @@ impl<'ctx> CodeGenContext<'ctx> {
                 .context
                 .ptr_type(inkwell::AddressSpace::default())
                 .into(),
-            LLType::Void => panic!("Cannot convert Void to a BasicTypeEnum"),
-        }
+            LLType::Void => return Err(self.unsupported(UnsupportedConstruct::VoidValue)),
+        })
     }
 
-    fn inkwell_type_to_naldom_type(&self, ty: BasicTypeEnum) -> LLType {
+    /// The Naldom type of an LLVM value type, or `None` for the aggregate
+    /// types Naldom has no counterpart for.
+    fn inkwell_type_to_naldom_type(&self, ty: BasicTypeEnum) -> Option<LLType> {
         match ty {
-            BasicTypeEnum::IntType(i) => {
-                if i.get_bit_width() == 32 {
-                    LLType::I32
-                } else {
-                    LLType::I64
-                }
-            }
-            BasicTypeEnum::FloatType(_) => LLType::F64,
-            BasicTypeEnum::PointerType(_) => LLType::Pointer(Box::new(LLType::F64)),
-            _ => unimplemented!(),
+            BasicTypeEnum::IntType(i) => Some(match i.get_bit_width() {
+                1 => LLType::I1,
+                32 => LLType::I32,
+                _ => LLType::I64,
+            }),
+            BasicTypeEnum::FloatType(_) => Some(LLType::F64),
+            BasicTypeEnum::PointerType(_) => Some(LLType::Pointer(Box::new(LLType::F64))),
+            _ => None,
         }
     }
 
@@ -163,15 +909,67 @@ impl<'ctx> CodeGenContext<'ctx> {
         &self,
         params: &[(LLType, Register)],
         ret: &LLType,
-    ) -> inkwell::types::FunctionType<'ctx> {
-        let param_types: Vec<BasicMetadataTypeEnum> = params
+    ) -> Result<inkwell::types::FunctionType<'ctx>, CodegenError> {
+        let param_types = params
             .iter()
-            .map(|(ty, _)| self.to_llvm_type(ty).into())
-            .collect();
-        match ret {
+            .map(|(ty, _)| self.to_llvm_type(ty).map(Into::into))
+            .collect::<Result<Vec<BasicMetadataTypeEnum>, _>>()?;
+        Ok(match ret {
             LLType::Void => self.context.void_type().fn_type(&param_types, false),
-            _ => self.to_llvm_type(ret).fn_type(&param_types, false),
+            _ => self.to_llvm_type(ret)?.fn_type(&param_types, false),
+        })
+    }
+
+    /// Declares a runtime function exactly as the ABI registry describes it.
+    fn declare_runtime_function(
+        &self,
+        function: &RuntimeFunction,
+    ) -> Result<FunctionValue<'ctx>, CodegenError> {
+        let param_types = function
+            .params
+            .iter()
+            .map(|param| self.abi_type_to_llvm(param.ty).map(Into::into))
+            .collect::<Result<Vec<BasicMetadataTypeEnum>, _>>()?;
+        let fn_type = match function.return_type {
+            AbiType::Void => self.context.void_type().fn_type(&param_types, false),
+            ty => self.abi_type_to_llvm(ty)?.fn_type(&param_types, false),
+        };
+        let declaration = self.module.add_function(function.name, fn_type, None);
+        if self.wasm_imports {
+            for (key, value) in [
+                ("wasm-import-module", WASM_IMPORT_MODULE),
+                ("wasm-import-name", function.name),
+            ] {
+                declaration.add_attribute(
+                    AttributeLoc::Function,
+                    self.context.create_string_attribute(key, value),
+                );
+            }
         }
+        Ok(declaration)
+    }
+
+    /// The LLVM type of a runtime parameter or return value of type `ty`. A
+    /// void parameter in the registry is reported at the call declaring it.
+    fn abi_type_to_llvm(&self, ty: AbiType) -> Result<BasicTypeEnum<'ctx>, CodegenError> {
+        Ok(match ty {
+            AbiType::I32 => self.context.i32_type().into(),
+            AbiType::I64 | AbiType::U64 => self.context.i64_type().into(),
+            AbiType::F64 => self.context.f64_type().into(),
+            AbiType::F64Ptr
+            | AbiType::I64Ptr
+            | AbiType::CStr
+            | AbiType::CStrArray
+            | AbiType::EntryFn
+            | AbiType::ArrayPtr
+            | AbiType::ConstArrayPtr
+            | AbiType::MatrixPtr
+            | AbiType::ConstMatrixPtr => self
+                .context
+                .ptr_type(inkwell::AddressSpace::default())
+                .into(),
+            AbiType::Void => return Err(self.unsupported(UnsupportedConstruct::VoidValue)),
+        })
     }
 
     // This function is now more robust and can handle our new sleep function correctly.
@@ -180,8 +978,8 @@ impl<'ctx> CodeGenContext<'ctx> {
         name: &str,
         args: &[NaldomValue],
         has_return: bool,
-    ) -> FunctionValue<'ctx> {
-        let arg_types: Vec<BasicMetadataTypeEnum> = args
+    ) -> Result<FunctionValue<'ctx>, CodegenError> {
+        let arg_types = args
             .iter()
             .map(|arg| {
                 let naldom_type = match arg {
@@ -189,19 +987,16 @@ impl<'ctx> CodeGenContext<'ctx> {
                         LLConstant::I32(_) => LLType::I32,
                         LLConstant::I64(_) => LLType::I64,
                         LLConstant::F64(_) => LLType::F64,
+                        LLConstant::I64Array(_) => LLType::Pointer(Box::new(LLType::I64)),
+                        LLConstant::Str(_) | LLConstant::Function(_) => {
+                            LLType::Pointer(Box::new(LLType::I8))
+                        }
                     },
-                    NaldomValue::Register(reg) => {
-                        // The `_ptr` is the PointerValue, `ty` is the LLType
-                        let (_ptr, ty) = self
-                            .registers
-                            .get(reg)
-                            .expect("Register not found during function declaration");
-                        ty.clone()
-                    }
+                    NaldomValue::Register(reg) => self.register(*reg)?.1,
                 };
-                self.to_llvm_type(&naldom_type).into()
+                self.to_llvm_type(&naldom_type).map(Into::into)
             })
-            .collect();
+            .collect::<Result<Vec<BasicMetadataTypeEnum>, _>>()?;
 
         let fn_type = if has_return {
             // Assuming pointer return for now, as that's what create_random_array does
@@ -211,29 +1006,985 @@ impl<'ctx> CodeGenContext<'ctx> {
         } else {
             self.context.void_type().fn_type(&arg_types, false)
         };
-        self.module.add_function(name, fn_type, None)
+        Ok(self.module.add_function(name, fn_type, None))
+    }
+}
+
+/// The number of instructions in `block`.
+fn instruction_count(block: LlvmBlock<'_>) -> usize {
+    let mut count = 0;
+    let mut next = block.get_first_instruction();
+    while let Some(instruction) = next {
+        count += 1;
+        next = instruction.get_next_instruction();
+    }
+    count
+}
+
+/// What a module is generated for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetConfig {
+    /// The LLVM target triple, e.g. `x86_64-unknown-linux-gnu`. Runtime
+    /// functions become wasm imports for `wasm*` triples.
+    pub triple: String,
+}
+
+impl TargetConfig {
+    pub fn new(triple: impl Into<String>) -> Self {
+        TargetConfig {
+            triple: triple.into(),
+        }
+    }
+}
+
+/// Generates `ll_program` into a new module in `context` and verifies it,
+/// returning the live module instead of its text. Embedders can add their own
+/// functions to it or link other modules into it before emitting it; they
+/// should verify it again afterwards.
+///
+/// The module borrows `context` and cannot outlive it. A `Context` may move
+/// to another thread but is not `Sync`, and the module is not `Send`, so the
+/// module stays on the thread that generated it. Modules linked together
+/// must come from the same context.
+pub fn codegen_module<'ctx>(
+    context: &'ctx Context,
+    ll_program: &LLProgram,
+    target: &TargetConfig,
+) -> Result<Module<'ctx>, CodegenError> {
+    codegen_module_with_size(context, ll_program, target).map(|(module, _)| module)
+}
+
+/// Like [`codegen_module`], but also returns the size of the module, counted
+/// while generating it.
+fn codegen_module_with_size<'ctx>(
+    context: &'ctx Context,
+    ll_program: &LLProgram,
+    target: &TargetConfig,
+) -> Result<(Module<'ctx>, IrSize), CodegenError> {
+    let mut codegen_context = CodeGenContext::new(context, "naldom_module", target);
+    // Every function is declared before any body is generated, so a
+    // function can call or refer to one defined after it.
+    let declarations = ll_program
+        .functions
+        .iter()
+        .map(|function| codegen_context.declare_function(function))
+        .collect::<Result<Vec<_>, _>>()?;
+    for (function, declaration) in ll_program.functions.iter().zip(declarations) {
+        codegen_context.codegen_function(function, declaration)?;
     }
+
+    if let Err(e) = codegen_context.module.verify() {
+        return Err(CodegenError::Verification {
+            message: e.to_string(),
+            ir: codegen_context.module.print_to_string().to_string(),
+        });
+    }
+
+    Ok((codegen_context.module, codegen_context.size))
 }
 
-pub fn generate_llvm_ir(ll_program: &LLProgram, target_triple: &str) -> Result<String, String> {
+/// Generates `ll_program` as LLVM IR text for `target_triple`. See
+/// [`codegen_module`] to keep working with the module instead.
+pub fn generate_llvm_ir(
+    ll_program: &LLProgram,
+    target_triple: &str,
+) -> Result<String, CodegenError> {
+    generate_llvm_ir_with_size(ll_program, target_triple).map(|(llvm_ir, _)| llvm_ir)
+}
+
+/// Like [`generate_llvm_ir`], but also returns the size of the module,
+/// counted while generating it.
+pub fn generate_llvm_ir_with_size(
+    ll_program: &LLProgram,
+    target_triple: &str,
+) -> Result<(String, IrSize), CodegenError> {
     let context = Context::create();
-    let mut codegen_context = CodeGenContext::new(&context, "naldom_module");
+    let (module, size) =
+        codegen_module_with_size(&context, ll_program, &TargetConfig::new(target_triple))?;
+    Ok((module.print_to_string().to_string(), size))
+}
 
+/// Compiles LLVM IR text, as [`generate_llvm_ir`] returns it, to an object
+/// file for `target_triple` in memory, without running `llc`. For wasm the
+/// result is a relocatable module that still needs `wasm-ld` to become a
+/// program, but it can already be validated and inspected.
+pub fn emit_object(llvm_ir: &str, target_triple: &str) -> Result<Vec<u8>, CodegenError> {
+    let error = |message: String| CodegenError::ObjectEmission {
+        triple: target_triple.to_string(),
+        message,
+    };
+    let config = InitializationConfig::default();
+    if target_triple.starts_with("wasm") {
+        Target::initialize_webassembly(&config);
+    } else {
+        Target::initialize_x86(&config);
+    }
     let triple = TargetTriple::create(target_triple);
-    codegen_context.module.set_triple(&triple);
+    let target = Target::from_triple(&triple).map_err(|e| error(e.to_string()))?;
+    let machine = target
+        .create_target_machine(
+            &triple,
+            "generic",
+            "",
+            OptimizationLevel::None,
+            RelocMode::PIC,
+            CodeModel::Default,
+        )
+        .ok_or_else(|| error("LLVM has no target machine for it".to_string()))?;
+
+    let context = Context::create();
+    let buffer = MemoryBuffer::create_from_memory_range_copy(llvm_ir.as_bytes(), "naldom_module");
+    let module = context
+        .create_module_from_ir(buffer)
+        .map_err(|e| error(e.to_string()))?;
+    machine
+        .write_to_memory_buffer(&module, FileType::Object)
+        .map(|object| object.as_slice().to_vec())
+        .map_err(|e| error(e.to_string()))
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lowering::LoweringContext;
+    use crate::lowering_hl_to_ll::lower_hl_to_ll;
+    use naldom_ir::{
+        CountArg, CreateArrayParams, HLExpression, HLProgram, HLStatement, HLValue, Intent,
+        SortArrayParams,
+    };
+
+    /// A `main` that calls `function_name` once per argument list.
+    fn program_with_calls(function_name: &str, calls: Vec<Vec<NaldomValue>>) -> LLProgram {
+        let instructions = calls
+            .into_iter()
+            .map(|arguments| LLInstruction::Call {
+                dest: None,
+                function_name: function_name.to_string(),
+                arguments,
+            })
+            .collect();
+        LLProgram {
+            functions: vec![LLFunction {
+                name: "main".to_string(),
+                parameters: vec![],
+                return_type: LLType::Void,
+                basic_blocks: vec![BasicBlock {
+                    id: 0,
+                    instructions,
+                    terminator: Terminator::Return(None),
+                    annotations: Vec::new(),
+                }],
+            }],
+        }
+    }
 
-    for function in &ll_program.functions {
-        codegen_context.codegen_function(function);
+    #[test]
+    fn test_later_calls_are_coerced_to_the_first_declaration() {
+        // Arrange
+        let program = program_with_calls(
+            "external_fn",
+            vec![
+                vec![NaldomValue::Constant(LLConstant::I64(1))],
+                vec![NaldomValue::Constant(LLConstant::I32(2))],
+            ],
+        );
+
+        // Act
+        let llvm_ir = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu")
+            .expect("The i32 argument should be widened to i64");
+
+        // Assert
+        assert!(llvm_ir.contains("declare void @external_fn(i64)"));
+        assert!(llvm_ir.contains("call void @external_fn(i64 1)"));
+        assert!(llvm_ir.contains("call void @external_fn(i64 2)"));
     }
 
-    if let Err(e) = codegen_context.module.verify() {
-        let ir_string = codegen_context.module.print_to_string().to_string();
-        return Err(format!(
-            "LLVM module verification failed: {}\nGenerated IR:\n{}",
-            e.to_string(),
-            ir_string
+    #[test]
+    fn test_uncoercible_argument_names_both_types_and_the_call() {
+        // Arrange
+        let program = program_with_calls(
+            "external_fn",
+            vec![
+                vec![NaldomValue::Constant(LLConstant::I64(1))],
+                vec![NaldomValue::Constant(LLConstant::F64(2.0))],
+            ],
+        );
+
+        // Act
+        let error = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu").unwrap_err();
+
+        // Assert
+        assert_eq!(
+            error,
+            CodegenError::ArgumentTypeMismatch {
+                function: "external_fn".to_string(),
+                call_index: 1,
+                argument_index: 0,
+                expected: "i64".to_string(),
+                found: "double".to_string(),
+            }
+        );
+        assert!(error.to_string().contains("call #2 to 'external_fn'"));
+    }
+
+    #[test]
+    fn test_reassigned_variable_is_stored_to_and_loaded_from_its_slot() {
+        // Arrange: `var_0` holds one array, then another; each is printed.
+        let hl_program = HLProgram {
+            statements: [3, 5]
+                .into_iter()
+                .flat_map(|size| {
+                    [
+                        HLStatement::Assign {
+                            variable: "var_0".to_string(),
+                            expression: HLExpression::FunctionCall {
+                                function: "create_random_array".to_string(),
+                                arguments: vec![HLExpression::Literal(HLValue::Integer(size))],
+                            },
+                        },
+                        HLStatement::Call {
+                            function: "print_array".to_string(),
+                            arguments: vec![
+                                HLExpression::Variable("var_0".to_string()),
+                                HLExpression::Literal(HLValue::Integer(0)),
+                            ],
+                        },
+                    ]
+                })
+                .collect(),
+        };
+        let program = lower_hl_to_ll(&hl_program).unwrap();
+
+        // Act
+        let llvm_ir = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu")
+            .expect("the reassignment should verify");
+
+        // Assert: one slot for the variable, written by both calls' results
+        // and read before each print.
+        assert_eq!(
+            llvm_ir.matches("%reg_1 = alloca ptr").count(),
+            1,
+            "{}",
+            llvm_ir
+        );
+        let stores = llvm_ir
+            .lines()
+            .filter(|line| {
+                line.trim_start().starts_with("store ") && line.contains(", ptr %reg_1,")
+            })
+            .count();
+        assert_eq!(stores, 2, "{}", llvm_ir);
+        assert_eq!(
+            llvm_ir.matches("= load ptr, ptr %reg_1").count(),
+            2,
+            "{}",
+            llvm_ir
+        );
+    }
+
+    #[test]
+    fn test_store_converts_register_operands_to_the_slot_type() {
+        // Arrange: an `i32` register stored in an `i64` slot and read back.
+        let program = LLProgram {
+            functions: vec![LLFunction {
+                name: "compute".to_string(),
+                parameters: vec![(LLType::I32, Register(0))],
+                return_type: LLType::I64,
+                basic_blocks: vec![BasicBlock {
+                    id: 0,
+                    instructions: vec![
+                        LLInstruction::Alloc {
+                            dest: Register(1),
+                            ty: LLType::I64,
+                        },
+                        LLInstruction::Store {
+                            value: NaldomValue::Register(Register(0)),
+                            dest_ptr: Register(1),
+                        },
+                        LLInstruction::Load {
+                            dest: Register(2),
+                            source_ptr: Register(1),
+                        },
+                    ],
+                    terminator: Terminator::Return(Some(NaldomValue::Register(Register(2)))),
+                    annotations: Vec::new(),
+                }],
+            }],
+        };
+
+        // Act
+        let llvm_ir = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu")
+            .expect("the memory accesses should verify");
+
+        // Assert
+        assert!(
+            llvm_ir.contains("sext i32 %load_reg_0 to i64"),
+            "{}",
+            llvm_ir
+        );
+        assert!(llvm_ir.contains("load i64, ptr %reg_1"), "{}", llvm_ir);
+    }
+
+    #[test]
+    fn test_arithmetic_results_land_in_registers_later_instructions_read() {
+        // Arrange: `(x + 2) * x`, then the other two operators on the product.
+        let x = NaldomValue::Register(Register(0));
+        let arithmetic = |dest, op, lhs, rhs| LLInstruction::BinaryOp {
+            dest: Register(dest),
+            op,
+            lhs,
+            rhs,
+            ty: LLType::I64,
+        };
+        let program = LLProgram {
+            functions: vec![LLFunction {
+                name: "compute".to_string(),
+                parameters: vec![(LLType::I64, Register(0))],
+                return_type: LLType::I64,
+                basic_blocks: vec![BasicBlock {
+                    id: 0,
+                    instructions: vec![
+                        arithmetic(
+                            1,
+                            BinOp::Add,
+                            x.clone(),
+                            NaldomValue::Constant(LLConstant::I32(2)),
+                        ),
+                        arithmetic(2, BinOp::Mul, NaldomValue::Register(Register(1)), x),
+                        arithmetic(
+                            3,
+                            BinOp::Sub,
+                            NaldomValue::Register(Register(2)),
+                            NaldomValue::Constant(LLConstant::I64(1)),
+                        ),
+                        arithmetic(
+                            4,
+                            BinOp::SDiv,
+                            NaldomValue::Register(Register(3)),
+                            NaldomValue::Constant(LLConstant::I64(3)),
+                        ),
+                    ],
+                    terminator: Terminator::Return(Some(NaldomValue::Register(Register(4)))),
+                    annotations: Vec::new(),
+                }],
+            }],
+        };
+
+        // Act
+        let llvm_ir = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu")
+            .expect("the arithmetic should verify");
+
+        // Assert: the `i32` constant is widened to the instruction's type.
+        assert!(llvm_ir.contains("add i64 %load_reg_0, 2"), "{}", llvm_ir);
+        // `x` is loaded twice, so the second load's name gets a suffix.
+        assert!(
+            llvm_ir.contains("mul i64 %load_reg_1, %load_reg_0"),
+            "{}",
+            llvm_ir
+        );
+        assert!(llvm_ir.contains("sub i64 %load_reg_2, 1"), "{}", llvm_ir);
+        assert!(llvm_ir.contains("sdiv i64 %load_reg_3, 3"), "{}", llvm_ir);
+    }
+
+    #[test]
+    fn test_branches_jump_to_blocks_by_id_and_unreferenced_ones_are_kept() {
+        // Arrange: `bb0` tests its parameter and jumps to `bb1`; nothing
+        // jumps to `bb2`.
+        let program = |terminator| LLProgram {
+            functions: vec![LLFunction {
+                name: "branch".to_string(),
+                parameters: vec![(LLType::I32, Register(0))],
+                return_type: LLType::Void,
+                basic_blocks: vec![
+                    BasicBlock {
+                        id: 0,
+                        instructions: vec![LLInstruction::ICmp {
+                            dest: Register(1),
+                            predicate: ICmpPredicate::Ne,
+                            lhs: NaldomValue::Register(Register(0)),
+                            rhs: NaldomValue::Constant(LLConstant::I32(0)),
+                        }],
+                        terminator,
+                        annotations: Vec::new(),
+                    },
+                    BasicBlock {
+                        id: 1,
+                        instructions: Vec::new(),
+                        terminator: Terminator::Return(None),
+                        annotations: Vec::new(),
+                    },
+                    BasicBlock {
+                        id: 2,
+                        instructions: Vec::new(),
+                        terminator: Terminator::Return(None),
+                        annotations: Vec::new(),
+                    },
+                ],
+            }],
+        };
+        let jump = program(Terminator::Br { target: 1 });
+        let conditional = program(Terminator::CondBr {
+            cond: NaldomValue::Register(Register(1)),
+            then_block: 1,
+            else_block: 1,
+        });
+
+        // Act
+        let jump_ir =
+            generate_llvm_ir(&jump, "x86_64-unknown-linux-gnu").expect("the jump should verify");
+        let conditional_ir = generate_llvm_ir(&conditional, "x86_64-unknown-linux-gnu")
+            .expect("the conditional jump should verify");
+
+        // Assert
+        assert!(jump_ir.contains("br label %bb1"), "{}", jump_ir);
+        assert!(jump_ir.contains("\nbb2:"), "{}", jump_ir);
+        assert!(conditional_ir.contains("br i1 "), "{}", conditional_ir);
+        assert!(
+            conditional_ir.contains("label %bb1, label %bb1"),
+            "{}",
+            conditional_ir
+        );
+    }
+
+    /// A diamond: `bb0` branches on its parameter to `bb1` or `bb2`, which
+    /// both jump to `bb3`, whose phi gets `1` from `bb1` and the parameter
+    /// from `bb2`, or only what `incoming` lists.
+    fn diamond(incoming: Vec<(NaldomValue, usize)>) -> LLProgram {
+        let block = |id, instructions, terminator| BasicBlock {
+            id,
+            instructions,
+            terminator,
+            annotations: Vec::new(),
+        };
+        LLProgram {
+            functions: vec![LLFunction {
+                name: "pick".to_string(),
+                parameters: vec![(LLType::I32, Register(0))],
+                return_type: LLType::I64,
+                basic_blocks: vec![
+                    block(
+                        0,
+                        vec![LLInstruction::ICmp {
+                            dest: Register(1),
+                            predicate: ICmpPredicate::Ne,
+                            lhs: NaldomValue::Register(Register(0)),
+                            rhs: NaldomValue::Constant(LLConstant::I32(0)),
+                        }],
+                        Terminator::CondBr {
+                            cond: NaldomValue::Register(Register(1)),
+                            then_block: 1,
+                            else_block: 2,
+                        },
+                    ),
+                    block(1, Vec::new(), Terminator::Br { target: 3 }),
+                    block(2, Vec::new(), Terminator::Br { target: 3 }),
+                    block(
+                        3,
+                        vec![LLInstruction::Phi {
+                            dest: Register(2),
+                            ty: LLType::I64,
+                            incoming,
+                        }],
+                        Terminator::Return(Some(NaldomValue::Register(Register(2)))),
+                    ),
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_phis_take_a_value_from_each_predecessor_of_a_diamond() {
+        // Arrange
+        let program = diamond(vec![
+            (NaldomValue::Constant(LLConstant::I64(1)), 1),
+            (NaldomValue::Register(Register(0)), 2),
+        ]);
+
+        // Act
+        let llvm_ir = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu")
+            .expect("the diamond should verify");
+
+        // Assert: LLVM follows each label with a `; preds = ...` comment, so
+        // the phi is matched as the first line of `bb3`'s body.
+        let bb3 = &llvm_ir[llvm_ir.find("\nbb3:").unwrap()..];
+        let phi = bb3.lines().nth(2).unwrap_or_default();
+        assert!(
+            phi.starts_with("  %phi_2 = phi i64 [ 1, %bb1 ], [ %"),
+            "{}",
+            llvm_ir
+        );
+        assert!(phi.ends_with(", %bb2 ]"), "{}", llvm_ir);
+        // The parameter is loaded and widened in `bb2`, before its branch.
+        let bb2 = &llvm_ir[llvm_ir.find("\nbb2:").unwrap()..llvm_ir.find("\nbb3:").unwrap()];
+        assert!(bb2.contains("sext i32"), "{}", llvm_ir);
+        assert!(bb2.trim_end().ends_with("br label %bb3"), "{}", llvm_ir);
+    }
+
+    #[test]
+    fn test_phi_without_a_value_for_a_predecessor_is_a_descriptive_error() {
+        // Arrange
+        let program = diamond(vec![
+            (NaldomValue::Constant(LLConstant::I64(1)), 1),
+            (NaldomValue::Constant(LLConstant::I64(2)), 0),
+        ]);
+
+        // Act
+        let error = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu").unwrap_err();
+
+        // Assert
+        assert_eq!(
+            error,
+            CodegenError::PhiPredecessorMismatch {
+                function: "pick".to_string(),
+                block: 3,
+                dest: Register(2),
+                missing: vec![2],
+                unexpected: vec![0],
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Codegen Error: the phi defining %2 in bb3 of 'pick' has no value for predecessor bb2 and has a value for bb0, which does not branch to it."
+        );
+    }
+
+    #[test]
+    fn test_argument_count_mismatch_is_reported() {
+        // Arrange
+        let program = program_with_calls(
+            "print_array",
+            vec![vec![
+                NaldomValue::Constant(LLConstant::I64(1)),
+                NaldomValue::Constant(LLConstant::I64(2)),
+                NaldomValue::Constant(LLConstant::I64(3)),
+            ]],
+        );
+
+        // Act
+        let error = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu").unwrap_err();
+
+        // Assert
+        assert!(matches!(
+            error,
+            CodegenError::ArgumentCountMismatch {
+                expected: 2,
+                found: 3,
+                ..
+            }
         ));
     }
 
-    Ok(codegen_context.module.print_to_string().to_string())
+    /// A `main` that calls `helper` and registers it as a program, followed
+    /// by `helper` itself when `define_helper`.
+    fn main_before_helper(define_helper: bool) -> LLProgram {
+        let mut program = program_with_calls("helper", vec![vec![]]);
+        let main = &mut program.functions[0];
+        main.basic_blocks[0].instructions.push(LLInstruction::Call {
+            dest: None,
+            function_name: "naldom_register_program".to_string(),
+            arguments: vec![
+                NaldomValue::Constant(LLConstant::Str("helper".to_string())),
+                NaldomValue::Constant(LLConstant::Function("helper".to_string())),
+            ],
+        });
+        if define_helper {
+            program.functions.push(LLFunction {
+                name: "helper".to_string(),
+                parameters: vec![],
+                return_type: LLType::Void,
+                basic_blocks: vec![BasicBlock {
+                    id: 0,
+                    instructions: Vec::new(),
+                    terminator: Terminator::Return(None),
+                    annotations: Vec::new(),
+                }],
+            });
+        }
+        program
+    }
+
+    #[test]
+    fn test_main_calls_and_refers_to_a_function_defined_after_it() {
+        // Act
+        let llvm_ir =
+            generate_llvm_ir(&main_before_helper(true), "x86_64-unknown-linux-gnu").unwrap();
+
+        // Assert: one `helper`, defined, and no renamed duplicate.
+        assert!(llvm_ir.contains("call void @helper()"));
+        assert!(llvm_ir.contains(", ptr @helper)"));
+        assert!(llvm_ir.contains("define void @helper()"));
+        assert!(!llvm_ir.contains("@helper.1"));
+        assert!(!llvm_ir.contains("declare void @helper"));
+    }
+
+    #[test]
+    fn test_reference_to_an_undefined_function_is_an_error() {
+        // Arrange: only the reference to `helper` is left, and no function
+        // of that name is defined or called.
+        let mut program = main_before_helper(false);
+        program.functions[0].basic_blocks[0].instructions.remove(0);
+
+        // Act
+        let error = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu").unwrap_err();
+
+        // Assert
+        assert_eq!(
+            error,
+            CodegenError::UnknownFunction {
+                function: "main".to_string(),
+                name: "helper".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Codegen Error: 'main' refers to the function 'helper', which the program does not define."
+        );
+    }
+
+    #[test]
+    fn test_unsupported_constructs_are_reported_with_their_location() {
+        // Arrange: one program per construct codegen cannot handle.
+        let mut void_alloc = program_with_calls("print_integer", vec![]);
+        void_alloc.functions[0].basic_blocks[0]
+            .instructions
+            .push(LLInstruction::Alloc {
+                dest: Register(0),
+                ty: LLType::Void,
+            });
+        let mut void_parameter = program_with_calls("print_integer", vec![]);
+        void_parameter.functions[0].parameters = vec![(LLType::Void, Register(0))];
+        let cases = [
+            (
+                program_with_calls(
+                    "print_integer",
+                    vec![vec![NaldomValue::Register(Register(7))]],
+                ),
+                UnsupportedConstruct::UnallocatedRegister,
+                "function 'main', basic block 0, instruction #1",
+            ),
+            (
+                void_alloc,
+                UnsupportedConstruct::VoidValue,
+                "function 'main', basic block 0, instruction #1",
+            ),
+            (
+                void_parameter,
+                UnsupportedConstruct::VoidValue,
+                "the parameters of function 'main'",
+            ),
+        ];
+
+        for (program, construct, location) in cases {
+            // Act
+            let error = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu").unwrap_err();
+
+            // Assert
+            let CodegenError::Unsupported(feature) = &error else {
+                panic!("Expected an unsupported feature, got {:?}", error);
+            };
+            assert_eq!(feature.construct, construct);
+            assert_eq!(feature.location, location);
+            assert!(
+                error.to_string().starts_with("Naldom does not support"),
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "contains a NUL character")]
+    fn test_string_constants_with_nul_are_refused() {
+        let program = program_with_calls(
+            "external_fn",
+            vec![vec![NaldomValue::Constant(LLConstant::Str(
+                "a\u{0}b".to_string(),
+            ))]],
+        );
+
+        let _ = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu");
+    }
+
+    #[test]
+    fn test_wasm_runtime_functions_import_from_the_runtime_module() {
+        // Arrange
+        let program = program_with_calls(
+            "naldom_seed_random",
+            vec![vec![NaldomValue::Constant(LLConstant::I64(7))]],
+        );
+
+        // Act
+        let wasm_ir = generate_llvm_ir(&program, "wasm32-unknown-unknown").unwrap();
+        let native_ir = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu").unwrap();
+
+        // Assert
+        assert!(wasm_ir.contains("\"wasm-import-module\"=\"naldom_runtime\""));
+        assert!(wasm_ir.contains("\"wasm-import-name\"=\"naldom_seed_random\""));
+        assert!(!native_ir.contains("wasm-import"));
+    }
+
+    #[test]
+    fn test_objects_are_emitted_in_memory_for_wasm() {
+        // Arrange
+        let program = program_with_calls(
+            "naldom_seed_random",
+            vec![vec![NaldomValue::Constant(LLConstant::I64(7))]],
+        );
+        let wasm_ir = generate_llvm_ir(&program, "wasm32-unknown-unknown").unwrap();
+
+        // Act
+        let object = emit_object(&wasm_ir, "wasm32-unknown-unknown").unwrap();
+        let invalid = emit_object("this is not IR", "wasm32-unknown-unknown");
+
+        // Assert
+        assert!(object.starts_with(b"\0asm"));
+        assert!(matches!(
+            invalid,
+            Err(CodegenError::ObjectEmission { triple, .. }) if triple == "wasm32-unknown-unknown"
+        ));
+    }
+
+    #[test]
+    fn test_embedders_can_extend_and_link_the_live_module() {
+        // Arrange
+        let program = program_with_calls(
+            "naldom_seed_random",
+            vec![vec![NaldomValue::Constant(LLConstant::I64(7))]],
+        );
+        let context = Context::create();
+        let module = codegen_module(
+            &context,
+            &program,
+            &TargetConfig::new("x86_64-unknown-linux-gnu"),
+        )
+        .unwrap();
+
+        // Act: a shim in the generated module calls a runtime function it
+        // already declares, and a second module joins it.
+        let builder = context.create_builder();
+        let i64_type = context.i64_type();
+        let shim = module.add_function("host_shim", context.void_type().fn_type(&[], false), None);
+        builder.position_at_end(context.append_basic_block(shim, "entry"));
+        let seed = module.get_function("naldom_seed_random").unwrap();
+        builder
+            .build_call(seed, &[i64_type.const_int(42, false).into()], "")
+            .unwrap();
+        builder.build_return(None).unwrap();
+
+        let host = context.create_module("host");
+        host.set_triple(&module.get_triple());
+        let answer = host.add_function("host_answer", i64_type.fn_type(&[], false), None);
+        builder.position_at_end(context.append_basic_block(answer, "entry"));
+        builder
+            .build_return(Some(&i64_type.const_int(42, false)))
+            .unwrap();
+        module.link_in_module(host).unwrap();
+
+        // Assert
+        assert!(module.verify().is_ok());
+        for name in ["main", "host_shim", "host_answer"] {
+            assert!(module.get_function(name).is_some(), "{} is missing", name);
+        }
+        let llvm_ir = module.print_to_string().to_string();
+        assert!(llvm_ir.contains("call void @naldom_seed_random(i64 42)"));
+    }
+
+    #[test]
+    fn test_generate_llvm_ir_prints_the_module() {
+        // Arrange
+        let program = program_with_calls(
+            "naldom_seed_random",
+            vec![vec![NaldomValue::Constant(LLConstant::I64(7))]],
+        );
+        let context = Context::create();
+
+        // Act
+        let module = codegen_module(
+            &context,
+            &program,
+            &TargetConfig::new("wasm32-unknown-unknown"),
+        )
+        .unwrap();
+        let llvm_ir = generate_llvm_ir(&program, "wasm32-unknown-unknown").unwrap();
+
+        // Assert
+        assert_eq!(module.print_to_string().to_string(), llvm_ir);
+    }
+
+    #[test]
+    fn test_targets_do_not_leak_between_compilations() {
+        // Arrange
+        let hl_program = LoweringContext::new().lower(&[
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(10)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::SortArray(SortArrayParams {
+                order: Some("ascending".to_string()),
+                algorithm: None,
+                target_ordinal: Some(1),
+                target: None,
+            }),
+            Intent::PrintArray(None),
+        ]);
+        let program = lower_hl_to_ll(&hl_program).unwrap();
+        let triples = ["x86_64-unknown-linux-gnu", "wasm32-unknown-unknown"];
+        let check = |triple: &str, llvm_ir: &str| {
+            let other = triples.iter().find(|t| **t != triple).unwrap();
+            assert!(llvm_ir.contains(&format!("target triple = \"{}\"", triple)));
+            assert!(!llvm_ir.contains(other));
+            assert_eq!(llvm_ir.contains("wasm-import"), triple.starts_with("wasm"));
+        };
+
+        // Act: one after another, in one shared context, and at once.
+        let sequential: Vec<String> = triples
+            .iter()
+            .map(|triple| generate_llvm_ir(&program, triple).unwrap())
+            .collect();
+        let context = Context::create();
+        let shared: Vec<String> = triples
+            .iter()
+            .map(|triple| {
+                codegen_module(&context, &program, &TargetConfig::new(*triple))
+                    .unwrap()
+                    .print_to_string()
+                    .to_string()
+            })
+            .collect();
+        let concurrent: Vec<String> = std::thread::scope(|scope| {
+            let program = &program;
+            let handles: Vec<_> = triples
+                .iter()
+                .map(|triple| scope.spawn(move || generate_llvm_ir(program, triple).unwrap()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        // Assert: every module verified, and each carries only its own target.
+        for outputs in [&sequential, &shared, &concurrent] {
+            for (triple, llvm_ir) in triples.iter().zip(outputs) {
+                check(triple, llvm_ir);
+            }
+        }
+        assert_eq!(sequential, concurrent);
+        assert_eq!(sequential, shared);
+    }
+
+    #[test]
+    fn test_sum_array_returns_an_integer() {
+        // Arrange
+        let hl_program = LoweringContext::new().lower(&[
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::SumArray(None),
+        ]);
+        let program = lower_hl_to_ll(&hl_program).unwrap();
+
+        // Act
+        let llvm_ir = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu").unwrap();
+
+        // Assert
+        assert!(llvm_ir.contains("declare i64 @sum_array("));
+        assert!(llvm_ir.contains("call void @print_integer(i64 "));
+    }
+
+    #[test]
+    fn test_listed_values_become_a_constant_array() {
+        // Arrange
+        let hl_program = LoweringContext::new().lower(&[
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(3)),
+                name: None,
+                values: vec![3, 1, 4],
+                dtype: None,
+            }),
+            Intent::PrintArray(None),
+        ]);
+        let program = lower_hl_to_ll(&hl_program).unwrap();
+
+        // Act
+        let llvm_ir = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu").unwrap();
+
+        // Assert
+        assert!(llvm_ir.contains("private unnamed_addr constant [3 x i64] [i64 3, i64 1, i64 4]"));
+        assert!(llvm_ir.contains("@create_array_from_values("));
+        assert!(llvm_ir.contains(", i64 3)"));
+    }
+
+    #[test]
+    fn test_size_counts_every_instruction_by_provenance() {
+        // Arrange: three calls from two statements, the last needing a sext.
+        let mut program = program_with_calls(
+            "naldom_seed_random",
+            vec![
+                vec![NaldomValue::Constant(LLConstant::I64(1))],
+                vec![NaldomValue::Constant(LLConstant::I64(2))],
+                vec![NaldomValue::Register(Register(0))],
+            ],
+        );
+        let block = &mut program.functions[0].basic_blocks[0];
+        block.instructions.insert(
+            0,
+            LLInstruction::Alloc {
+                dest: Register(0),
+                ty: LLType::I32,
+            },
+        );
+        for (instruction, statement) in [(0, "0"), (1, "0"), (2, "0"), (3, "1")] {
+            block.annotations.push(Annotation {
+                instruction,
+                key: PROVENANCE_KEY.to_string(),
+                value: statement.to_string(),
+            });
+        }
+
+        // Act
+        let (llvm_ir, size) =
+            generate_llvm_ir_with_size(&program, "x86_64-unknown-linux-gnu").unwrap();
+
+        // Assert: alloca, 2 calls | load, sext, call | ret.
+        assert_eq!(size.instructions, 7);
+        assert_eq!(size.by_statement, [(0, 3), (1, 3)].into());
+        assert_eq!(size.by_function, [("main".to_string(), 7)].into());
+        let body_lines = llvm_ir
+            .lines()
+            .skip_while(|line| !line.starts_with("define"))
+            .filter(|line| line.starts_with("  "))
+            .count();
+        assert_eq!(body_lines, size.instructions);
+    }
+
+    #[test]
+    fn test_annotations_become_instruction_metadata() {
+        // Arrange
+        let mut program = program_with_calls(
+            "naldom_profile_end",
+            vec![vec![NaldomValue::Constant(LLConstant::I64(0))]],
+        );
+        program.functions[0].basic_blocks[0]
+            .annotations
+            .push(Annotation {
+                instruction: 0,
+                key: "pass".to_string(),
+                value: "kept by dce".to_string(),
+            });
+
+        // Act
+        let llvm_ir = generate_llvm_ir(&program, "x86_64-unknown-linux-gnu").unwrap();
+
+        // Assert
+        let call = llvm_ir
+            .lines()
+            .find(|line| line.contains("call void @naldom_profile_end("))
+            .unwrap();
+        assert!(call.contains("!naldom.pass !"));
+        assert!(llvm_ir.contains("!{!\"kept by dce\"}"));
+    }
 }
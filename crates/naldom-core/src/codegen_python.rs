@@ -1,6 +1,6 @@
 // crates/naldom-core/src/codegen_python.rs
 
-use naldom_ir::{HLExpression, HLProgram, HLStatement, HLValue};
+use naldom_ir::{BinOp, HLExpression, HLProgram, HLStatement, HLValue};
 
 /// A struct responsible for generating Python code from IR-HL.
 pub struct PythonCodeGenerator;
@@ -49,9 +49,41 @@ impl PythonCodeGenerator {
                     .join(", ");
                 format!("{}({})", function, args_str)
             }
+            HLStatement::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                let mut lines = vec![
+                    format!("if {}:", self.generate_expression(condition)),
+                    self.generate_block(then_body),
+                ];
+                if !else_body.is_empty() {
+                    lines.push("else:".to_string());
+                    lines.push(self.generate_block(else_body));
+                }
+                lines.join("\n")
+            }
         }
     }
 
+    /// Generates the indented body of an `if` or `else`.
+    fn generate_block(&self, statements: &[HLStatement]) -> String {
+        if statements.is_empty() {
+            return "    pass".to_string();
+        }
+        statements
+            .iter()
+            .flat_map(|statement| {
+                self.generate_statement(statement)
+                    .lines()
+                    .map(|line| format!("    {}", line))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     /// Generates a Python expression from an HLExpression.
     fn generate_expression(&self, expression: &HLExpression) -> String {
         match expression {
@@ -68,6 +100,32 @@ impl PythonCodeGenerator {
                     .join(", ");
                 format!("{}({})", function, args_str)
             }
+            // `//` rounds toward negative infinity; IR division rounds toward zero.
+            HLExpression::BinaryOp {
+                op: BinOp::SDiv,
+                lhs,
+                rhs,
+            } => format!(
+                "int({} / {})",
+                self.generate_expression(lhs),
+                self.generate_expression(rhs)
+            ),
+            HLExpression::BinaryOp { op, lhs, rhs } => format!(
+                "({} {} {})",
+                self.generate_expression(lhs),
+                op.symbol(),
+                self.generate_expression(rhs)
+            ),
+            HLExpression::Conditional {
+                condition,
+                then_value,
+                else_value,
+            } => format!(
+                "({} if {} else {})",
+                self.generate_expression(then_value),
+                self.generate_expression(condition),
+                self.generate_expression(else_value)
+            ),
         }
     }
 
@@ -75,7 +133,12 @@ impl PythonCodeGenerator {
     fn generate_value(&self, value: &HLValue) -> String {
         match value {
             HLValue::Integer(i) => i.to_string(),
+            HLValue::Float(f) => format!("{:?}", f),
             HLValue::String(s) => format!("'{}'", s), // Wrap strings in single quotes for Python
+            HLValue::IntegerArray(values) => {
+                let items: Vec<String> = values.iter().map(i64::to_string).collect();
+                format!("[{}]", items.join(", "))
+            }
         }
     }
 }
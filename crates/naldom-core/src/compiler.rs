@@ -0,0 +1,1617 @@
+// crates/naldom-core/src/compiler.rs
+
+//! A small facade over the compiler pipeline, so frontends (the CLI, editors,
+//! a future playground) drive every stage the same way.
+
+use crate::attribution::SizeAttribution;
+use crate::cancel::CancellationToken;
+use crate::codegen_python::PythonCodeGenerator;
+use crate::cost::{CostEstimate, DEFAULT_SLOW_THRESHOLD, estimate};
+use crate::error_mode::ErrorMode;
+use crate::fs_policy::FsPolicy;
+use crate::ir_size::{IrSize, IrSizeLimits};
+use crate::lints::{LintContext, LintRegistry};
+use crate::llm_inference::run_inference;
+use crate::lowering::LoweringContext;
+use crate::lowering_hl_to_ll::{LoweringError, lower_hl_to_ll_with_entry};
+use crate::multi::link_programs;
+use crate::observer::ArtifactObserver;
+use crate::parser::parse_to_intent_graph;
+use crate::passes::{PassDiff, PassManager};
+use crate::privacy::{Privacy, program_text};
+use crate::progress::{NoProgress, ProgressEvent, ProgressSink, Stage};
+use crate::prompt::{Prompt, PromptOptions, Sampling, build_prompt};
+use crate::semantic_analyzer::{DefaultedParam, MAX_STRING_BYTES, SemanticAnalyzer, SemanticError};
+use crate::stdlib;
+use crate::suggestions::{auto_fix, render_with_suggestions};
+use crate::targets::{TargetKind, resolve_triple};
+use crate::unsupported::UnsupportedFeature;
+use naldom_ir::{HLProgram, Intent, LLProgram, Terminator};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Every artifact produced by a successful compilation, in pipeline order.
+#[derive(Debug, Clone)]
+pub struct CompileOutput {
+    /// The raw LLM response the IntentGraph was parsed from.
+    pub intent_json: String,
+    pub intent_graph: Vec<Intent>,
+    pub validated_intent_graph: Vec<Intent>,
+    /// The parameters of `validated_intent_graph` the model left out, which
+    /// hold their registry defaults.
+    pub defaulted: Vec<DefaultedParam>,
+    pub hl_program: HLProgram,
+    pub ll_program: LLProgram,
+    /// The generated code: LLVM IR for native and wasm, Python source for python.
+    pub code: String,
+    /// Non-fatal findings (e.g. automatically applied fixes) the user should see.
+    pub warnings: Vec<String>,
+    /// The estimated run time, or `None` when an array size is not known.
+    pub cost: Option<CostEstimate>,
+    /// What each pass changed, when the compiler records pass diffs.
+    pub pass_diffs: Vec<PassDiff>,
+    /// How much of the generated code each function and intent accounts
+    /// for. Object bytes are left for the caller that builds the object.
+    pub size: SizeAttribution,
+}
+
+/// Why a compilation produced no output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompileError {
+    /// The compiler's cancellation token was cancelled.
+    Cancelled,
+    /// A stage failed; the message is ready to show to the user.
+    Failed(String),
+    /// The model used an array before creating it although the source
+    /// creates it first, so the plan rather than the program is at fault.
+    InvalidPlan {
+        /// The diagnosis, ready to show to the user.
+        message: String,
+        /// What the model is told when it is asked again.
+        feedback: String,
+    },
+    /// The program uses a construct the backends cannot compile yet.
+    Unsupported(UnsupportedFeature),
+    /// An [`ArtifactObserver`] returned this error.
+    Aborted(String),
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::Cancelled => write!(f, "Compilation Cancelled."),
+            CompileError::Failed(message) | CompileError::InvalidPlan { message, .. } => {
+                f.write_str(message)
+            }
+            CompileError::Unsupported(feature) => write!(f, "{}", feature),
+            CompileError::Aborted(reason) => write!(f, "Compilation Aborted: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+impl From<String> for CompileError {
+    fn from(message: String) -> Self {
+        CompileError::Failed(message)
+    }
+}
+
+impl From<UnsupportedFeature> for CompileError {
+    fn from(feature: UnsupportedFeature) -> Self {
+        CompileError::Unsupported(feature)
+    }
+}
+
+impl From<LoweringError> for CompileError {
+    fn from(error: LoweringError) -> Self {
+        match error {
+            LoweringError::Unsupported(feature) => CompileError::Unsupported(feature),
+            other => CompileError::Failed(other.to_string()),
+        }
+    }
+}
+
+impl From<CompileError> for String {
+    fn from(error: CompileError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Runs the Naldom pipeline from source text (or a raw LLM response) to code
+/// for the configured target.
+pub struct Compiler {
+    progress: Box<dyn ProgressSink>,
+    apply_suggestions: bool,
+    profile: bool,
+    seed: Option<u64>,
+    error_mode: ErrorMode,
+    lints: LintRegistry,
+    slow_threshold: Duration,
+    ir_size_limits: IrSizeLimits,
+    max_string_bytes: usize,
+    fs_policy: Option<FsPolicy>,
+    target: TargetKind,
+    target_triple: Option<String>,
+    entry_name: Option<String>,
+    prompt_options: PromptOptions,
+    retries: u32,
+    passes: PassManager,
+    record_pass_diffs: bool,
+    check_only: bool,
+    observers: Vec<Arc<dyn ArtifactObserver>>,
+    cancellation: CancellationToken,
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compiler {
+    /// Creates a compiler for the native target that reports no progress.
+    pub fn new() -> Self {
+        Compiler {
+            progress: Box::new(NoProgress),
+            apply_suggestions: false,
+            profile: false,
+            seed: None,
+            error_mode: ErrorMode::Abort,
+            lints: LintRegistry::new(),
+            slow_threshold: DEFAULT_SLOW_THRESHOLD,
+            ir_size_limits: IrSizeLimits::default(),
+            max_string_bytes: MAX_STRING_BYTES,
+            fs_policy: None,
+            target: TargetKind::Native,
+            target_triple: None,
+            entry_name: None,
+            prompt_options: PromptOptions::default(),
+            retries: 0,
+            passes: PassManager::default(),
+            record_pass_diffs: false,
+            check_only: false,
+            observers: Vec::new(),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    /// When enabled, the generated program records the time spent in each
+    /// intent and prints a table to stderr when it exits.
+    pub fn with_profile(mut self, profile: bool) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Seeds the program's random numbers, so every run prints the same arrays.
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Selects what the program does when a runtime function fails (by
+    /// default it stops).
+    pub fn with_error_mode(mut self, error_mode: ErrorMode) -> Self {
+        self.error_mode = error_mode;
+        self
+    }
+
+    /// Replaces the lints run after analysis (by default the built-in ones,
+    /// all at warning level).
+    pub fn with_lints(mut self, lints: LintRegistry) -> Self {
+        self.lints = lints;
+        self
+    }
+
+    /// Warns when the program is estimated to run longer than `threshold`.
+    pub fn with_slow_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_threshold = threshold;
+        self
+    }
+
+    /// Replaces the instruction counts at which the generated LLVM IR draws a
+    /// warning or fails the compilation.
+    pub fn with_ir_size_limits(mut self, limits: IrSizeLimits) -> Self {
+        self.ir_size_limits = limits;
+        self
+    }
+
+    /// Limits the length of string parameters, in bytes (by default 4 KiB).
+    pub fn with_max_string_bytes(mut self, max_string_bytes: usize) -> Self {
+        self.max_string_bytes = max_string_bytes;
+        self
+    }
+
+    /// Restricts the paths file intents may use. Without a policy every path
+    /// is allowed.
+    pub fn with_fs_policy(mut self, fs_policy: FsPolicy) -> Self {
+        self.fs_policy = Some(fs_policy);
+        self
+    }
+
+    /// Selects the backend to generate code for.
+    pub fn with_target(mut self, target: TargetKind) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Overrides the triple that would otherwise be derived from the target.
+    pub fn with_target_triple(mut self, target_triple: Option<String>) -> Self {
+        self.target_triple = target_triple;
+        self
+    }
+
+    /// Names the generated entry function, instead of the target's default
+    /// (`main`, or `naldom_main` for wasm).
+    pub fn with_entry_name(mut self, entry_name: Option<String>) -> Self {
+        self.entry_name = entry_name;
+        self
+    }
+
+    /// Limits what the model sees of the source document besides its
+    /// `:::naldom` blocks (by default a sentence on each side of a block).
+    pub fn with_privacy(mut self, privacy: Privacy) -> Self {
+        self.prompt_options.privacy = privacy;
+        self
+    }
+
+    /// How the model samples its answer (by default at
+    /// [`DEFAULT_TEMPERATURE`](crate::prompt::DEFAULT_TEMPERATURE), with a
+    /// seed of the provider's choosing).
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.prompt_options.sampling = sampling;
+        self
+    }
+
+    /// Constrains the model's answer with the loose grammar, which admits any
+    /// parameters, instead of the strict one generated from the registry.
+    pub fn with_loose_grammar(mut self, loose_grammar: bool) -> Self {
+        self.prompt_options.loose_grammar = loose_grammar;
+        self
+    }
+
+    /// How many times the model is asked again, with corrective feedback,
+    /// after it produced an invalid plan (by default never).
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Replaces the passes run between lowering and code generation (by
+    /// default dead code elimination).
+    pub fn with_passes(mut self, passes: PassManager) -> Self {
+        self.passes = passes;
+        self
+    }
+
+    /// When enabled, the output records a diff of the IR around every pass.
+    pub fn with_pass_diffs(mut self, record_pass_diffs: bool) -> Self {
+        self.record_pass_diffs = record_pass_diffs;
+        self
+    }
+
+    /// When enabled, the compilation stops after analysis: the output holds
+    /// the validated intents and their diagnostics, with empty IR and code.
+    pub fn with_check_only(mut self, check_only: bool) -> Self {
+        self.check_only = check_only;
+        self
+    }
+
+    /// Makes the compilation stop with [`CompileError::Cancelled`] once
+    /// `cancellation` is cancelled.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Adds an observer that sees every artifact as soon as it is produced.
+    /// Observers are called in the order they were added.
+    pub fn with_observer(mut self, observer: Arc<dyn ArtifactObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Replaces the progress sink that receives stage events.
+    pub fn with_progress(mut self, progress: Box<dyn ProgressSink>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// When enabled, semantic errors are repaired by applying the first
+    /// suggestion for each error instead of failing the compilation.
+    pub fn with_apply_suggestions(mut self, apply_suggestions: bool) -> Self {
+        self.apply_suggestions = apply_suggestions;
+        self
+    }
+
+    /// The progress sink, so callers can report their own stages (e.g. linking).
+    pub fn progress(&self) -> &dyn ProgressSink {
+        self.progress.as_ref()
+    }
+
+    /// The prompt sent to the model for `source_code`, exactly as inference
+    /// will send it.
+    pub fn prompt(&self, source_code: &str) -> Prompt {
+        build_prompt(source_code, &self.prompt_options)
+    }
+
+    /// Compiles Naldom source text, asking the LLM to produce the IntentGraph
+    /// first. An invalid plan is retried as [`with_retries`](Self::with_retries)
+    /// allows.
+    pub async fn compile_source(&self, source_code: &str) -> Result<CompileOutput, CompileError> {
+        self.compile_with_retries(source_code, |prompt_options| async move {
+            run_inference(source_code, &prompt_options).await
+        })
+        .await
+    }
+
+    /// Compiles `source_code` with the responses of `infer`, which is called
+    /// with the prompt options of each attempt.
+    async fn compile_with_retries<F>(
+        &self,
+        source_code: &str,
+        mut infer: impl FnMut(PromptOptions) -> F,
+    ) -> Result<CompileOutput, CompileError>
+    where
+        F: Future<Output = Result<String, String>>,
+    {
+        let mut prompt_options = self.prompt_options.clone();
+        let mut attempt = 1;
+        loop {
+            let llm_response = self.infer(infer(prompt_options.clone())).await?;
+            let result = self.compile_response(&llm_response, Some(source_code));
+            match self.retry_feedback(result, attempt)? {
+                Ok(output) => return Ok(output),
+                Err(feedback) => prompt_options.feedback = Some(feedback),
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Like [`compile_source`](Self::compile_source), but safe to await from
+    /// a server's async tasks: inference runs on the caller's runtime, and
+    /// everything after it, including all LLVM work, runs on a blocking
+    /// thread. The returned future is `Send`. LLVM objects never leave that
+    /// thread; only the generated code (as text) in [`CompileOutput`] does.
+    pub async fn compile_async(
+        self: Arc<Self>,
+        source_code: String,
+    ) -> Result<CompileOutput, CompileError> {
+        let source_code = Arc::new(source_code);
+        let mut prompt_options = self.prompt_options.clone();
+        let mut attempt = 1;
+        loop {
+            let inference = {
+                let source_code = Arc::clone(&source_code);
+                let prompt_options = prompt_options.clone();
+                async move { run_inference(&source_code, &prompt_options).await }
+            };
+            let result = Arc::clone(&self)
+                .compile_inferred_async(inference, Arc::clone(&source_code))
+                .await;
+            match self.retry_feedback(result, attempt)? {
+                Ok(output) => return Ok(output),
+                Err(feedback) => prompt_options.feedback = Some(feedback),
+            }
+            attempt += 1;
+        }
+    }
+
+    async fn compile_inferred_async(
+        self: Arc<Self>,
+        inference: impl Future<Output = Result<String, String>> + Send,
+        source_code: Arc<String>,
+    ) -> Result<CompileOutput, CompileError> {
+        let llm_response = self.infer(inference).await?;
+        tokio::task::spawn_blocking(move || {
+            self.compile_response(&llm_response, Some(&source_code))
+        })
+        .await
+        .map_err(|e| CompileError::Failed(format!("The compiler thread failed: {}", e)))?
+    }
+
+    /// Decides what follows attempt number `attempt`: its output, the
+    /// feedback to ask the model again with (`Ok(Err(feedback))`), or the
+    /// final error. Out of retries, an invalid plan ends with advice instead.
+    fn retry_feedback(
+        &self,
+        result: Result<CompileOutput, CompileError>,
+        attempt: u32,
+    ) -> Result<Result<CompileOutput, String>, CompileError> {
+        match result {
+            Ok(output) => Ok(Ok(output)),
+            Err(CompileError::InvalidPlan { feedback, .. }) if attempt <= self.retries => {
+                self.progress.emit(&ProgressEvent::Diagnostic {
+                    level: "warning",
+                    message: &format!(
+                        "the language model produced an invalid plan; retrying with corrective feedback (attempt {} of {})",
+                        attempt + 1,
+                        self.retries + 1
+                    ),
+                });
+                Ok(Err(feedback))
+            }
+            Err(CompileError::InvalidPlan { message, feedback }) => {
+                Err(CompileError::InvalidPlan {
+                    message: format!(
+                        "{}\n  help: the source creates the array first; rephrase the request, try another model, or enable retries to ask the model again with corrective feedback",
+                        message
+                    ),
+                    feedback,
+                })
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Runs `inference` as the inference stage. The inference future is
+    /// dropped as soon as the token is cancelled, which aborts an in-flight
+    /// HTTP request.
+    async fn infer(
+        &self,
+        inference: impl Future<Output = Result<String, String>>,
+    ) -> Result<String, CompileError> {
+        self.check_cancelled()?;
+        self.progress.emit(&ProgressEvent::StageStart {
+            stage: Stage::Inference,
+        });
+        let start = Instant::now();
+        let llm_response = tokio::select! {
+            response = inference => response.map_err(CompileError::Failed),
+            () = self.cancellation.cancelled() => Err(CompileError::Cancelled),
+        };
+        self.finish_stage(Stage::Inference, start, llm_response)
+    }
+
+    /// Compiles an already available LLM response (the JSON intent array).
+    pub fn compile_llm_output(&self, llm_response: &str) -> Result<CompileOutput, CompileError> {
+        self.compile_response(llm_response, None)
+    }
+
+    /// Compiles `llm_response`. With the `source_code` it was inferred from,
+    /// a use before creation the source does not ask for is reported as an
+    /// invalid plan.
+    fn compile_response(
+        &self,
+        llm_response: &str,
+        source_code: Option<&str>,
+    ) -> Result<CompileOutput, CompileError> {
+        let intent_graph = self.run_stage(Stage::Parse, || {
+            parse_to_intent_graph(llm_response).map_err(|e| {
+                CompileError::Failed(format!(
+                    "Error parsing LLM response into IntentGraph: {}\n--- LLM Response ---\n{}\n--------------------",
+                    e, llm_response
+                ))
+            })
+        })?;
+
+        let mut warnings = Vec::new();
+        let (validated_intent_graph, defaulted) = self.run_stage(Stage::Analysis, || {
+            self.analyze(&intent_graph, source_code, &mut warnings)
+        })?;
+        self.notify(|observer| observer.on_intents(&validated_intent_graph))?;
+        let cost = self.estimate_cost(&validated_intent_graph, &mut warnings);
+        if self.check_only {
+            return Ok(CompileOutput {
+                intent_json: llm_response.to_string(),
+                intent_graph,
+                validated_intent_graph,
+                defaulted,
+                hl_program: HLProgram {
+                    statements: Vec::new(),
+                },
+                ll_program: LLProgram {
+                    functions: Vec::new(),
+                },
+                code: String::new(),
+                warnings,
+                cost,
+                pass_diffs: Vec::new(),
+                size: SizeAttribution::default(),
+            });
+        }
+
+        let mut pass_diffs = Vec::new();
+        let mut lowering = LoweringContext::new()
+            .with_profiling(self.profile)
+            .with_seed(self.seed)
+            .with_error_mode(self.error_mode)
+            .with_cancellation(self.cancellation.clone());
+        let hl_program = self.run_stage(Stage::Lowering, || {
+            let mut hl_program = lowering.lower(&validated_intent_graph);
+            pass_diffs.extend(self.passes.run_hl(&mut hl_program, self.record_pass_diffs));
+            Ok(hl_program)
+        })?;
+        self.notify(|observer| observer.on_hl(&hl_program))?;
+
+        let ll_program = self.run_stage(Stage::LowLevelLowering, || {
+            let entry_name = self
+                .entry_name
+                .as_deref()
+                .unwrap_or(self.target.entry_name());
+            let mut ll_program = lower_hl_to_ll_with_entry(&hl_program, entry_name)?;
+            pass_diffs.extend(self.passes.run_ll(&mut ll_program, self.record_pass_diffs));
+            Ok(ll_program)
+        })?;
+        self.notify(|observer| observer.on_ll(&ll_program))?;
+
+        let (code, ir_size) = self.run_stage(Stage::Codegen, || {
+            self.generate_code(&hl_program, &ll_program, &mut warnings)
+                .map_err(CompileError::Failed)
+        })?;
+        let size = SizeAttribution::new(
+            &validated_intent_graph,
+            lowering.intent_spans(),
+            &ll_program,
+            ir_size.as_ref(),
+        );
+        if self.target.uses_llvm() {
+            self.notify(|observer| observer.on_llvm_ir(&code))?;
+        }
+
+        Ok(CompileOutput {
+            intent_json: llm_response.to_string(),
+            intent_graph,
+            validated_intent_graph,
+            defaulted,
+            hl_program,
+            ll_program,
+            code,
+            warnings,
+            cost,
+            pass_diffs,
+            size,
+        })
+    }
+
+    /// Generates one module from separately compiled programs, given as
+    /// `(name, ll_program)`, with a `main` that runs the program named by its
+    /// first argument. Each program must have been compiled with
+    /// [`multi::program_symbol`](crate::multi::program_symbol) as its entry name.
+    pub fn compile_dispatcher(
+        &self,
+        programs: Vec<(String, LLProgram)>,
+    ) -> Result<String, CompileError> {
+        let llvm_ir = self.run_stage(Stage::Codegen, || {
+            if !self.target.uses_llvm() {
+                return Err(CompileError::Failed(format!(
+                    "The '{}' target cannot link several programs into one",
+                    self.target
+                )));
+            }
+            let triple = resolve_triple(&self.target, self.target_triple.as_deref())
+                .map_err(|e| e.to_string())?;
+            let (llvm_ir, _) = emit_llvm_ir(&link_programs(programs), &triple)?;
+            Ok(llvm_ir)
+        })?;
+        self.notify(|observer| observer.on_llvm_ir(&llvm_ir))?;
+        Ok(llvm_ir)
+    }
+
+    /// Estimates the run time and warns when it exceeds the slow threshold.
+    /// Purely advisory: a slow program still compiles.
+    fn estimate_cost(
+        &self,
+        validated: &[Intent],
+        warnings: &mut Vec<String>,
+    ) -> Option<CostEstimate> {
+        let cost = estimate(validated)?;
+        if let Some(message) = cost.warning(self.slow_threshold) {
+            self.progress.emit(&ProgressEvent::Diagnostic {
+                level: "warning",
+                message: &message,
+            });
+            warnings.push(message);
+        }
+        Some(cost)
+    }
+
+    /// Runs semantic analysis, optionally repairing the graph with suggestions.
+    /// Returns the validated graph and the parameters that were defaulted.
+    fn analyze(
+        &self,
+        intent_graph: &[Intent],
+        source_code: Option<&str>,
+        warnings: &mut Vec<String>,
+    ) -> Result<(Vec<Intent>, Vec<DefaultedParam>), CompileError> {
+        let expansion = stdlib::expand(intent_graph).map_err(|e| e.to_string())?;
+        let graph = if self.apply_suggestions {
+            let (fixed, applied) = auto_fix(&expansion.intents).map_err(|e| e.to_string())?;
+            for description in applied {
+                let message = format!("applied suggestion: {}", description);
+                self.progress.emit(&ProgressEvent::Diagnostic {
+                    level: "warning",
+                    message: &message,
+                });
+                warnings.push(message);
+            }
+            fixed
+        } else {
+            expansion.intents.clone()
+        };
+
+        let mut analyzer = SemanticAnalyzer::new()
+            .with_max_string_bytes(self.max_string_bytes)
+            .with_fs_policy(self.fs_policy.clone())
+            .with_cancellation(self.cancellation.clone());
+        let validated = analyzer.analyze(&graph).map_err(|e| {
+            let mut message = render_with_suggestions(&e, &graph);
+            if let Some(origin) = expansion.describe(e.intent_index()) {
+                message.push_str(&format!("\n  note: {}", origin));
+            }
+            match source_code.and_then(|source| invalid_plan_feedback(&e, source)) {
+                Some(feedback) => CompileError::InvalidPlan {
+                    message: format!(
+                        "Model Error: the language model produced an invalid plan.\n{}",
+                        message
+                    ),
+                    feedback,
+                },
+                None => CompileError::Failed(message),
+            }
+        })?;
+        for warning in analyzer.warnings() {
+            self.progress.emit(&ProgressEvent::Diagnostic {
+                level: "warning",
+                message: warning,
+            });
+        }
+        warnings.extend_from_slice(analyzer.warnings());
+        // The analyzer stopped early; `run_stage` reports the cancellation.
+        if self.cancellation.is_cancelled() {
+            return Ok((validated, Vec::new()));
+        }
+
+        let report = self.lints.run(
+            &validated,
+            &LintContext {
+                target: self.target,
+            },
+        );
+        for warning in &report.warnings {
+            self.progress.emit(&ProgressEvent::Diagnostic {
+                level: "warning",
+                message: warning,
+            });
+        }
+        warnings.extend(report.warnings);
+        if !report.errors.is_empty() {
+            return Err(CompileError::Failed(format!(
+                "Lint Error: {}",
+                report.errors.join("\nLint Error: ")
+            )));
+        }
+        Ok((validated, analyzer.defaulted().to_vec()))
+    }
+
+    /// Generates code for the configured target. Python is emitted from IR-HL;
+    /// every other target goes through LLVM, and its size is checked against
+    /// the limits and returned.
+    fn generate_code(
+        &self,
+        hl_program: &HLProgram,
+        ll_program: &LLProgram,
+        warnings: &mut Vec<String>,
+    ) -> Result<(String, Option<IrSize>), String> {
+        if self.target == TargetKind::Python {
+            return Ok((PythonCodeGenerator::new().generate(hl_program), None));
+        }
+        let triple = resolve_triple(&self.target, self.target_triple.as_deref())
+            .map_err(|e| e.to_string())?;
+        for message in unreferenced_block_warnings(ll_program) {
+            self.progress.emit(&ProgressEvent::Diagnostic {
+                level: "warning",
+                message: &message,
+            });
+            warnings.push(message);
+        }
+        let (llvm_ir, size) = emit_llvm_ir(ll_program, &triple)?;
+        if let Some(message) = self.ir_size_limits.check(&size, hl_program)? {
+            self.progress.emit(&ProgressEvent::Diagnostic {
+                level: "warning",
+                message: &message,
+            });
+            warnings.push(message);
+        }
+        Ok((llvm_ir, Some(size)))
+    }
+
+    /// Runs one synchronous stage, bracketing it with start/end events. A
+    /// stage is not started once the token is cancelled, and the result of a
+    /// stage that was cancelled while running is discarded.
+    fn run_stage<T>(
+        &self,
+        stage: Stage,
+        run: impl FnOnce() -> Result<T, CompileError>,
+    ) -> Result<T, CompileError> {
+        self.check_cancelled()?;
+        self.progress.emit(&ProgressEvent::StageStart { stage });
+        let start = Instant::now();
+        let result = run().and_then(|value| self.check_cancelled().map(|()| value));
+        self.finish_stage(stage, start, result)
+    }
+
+    /// Hands an artifact to every observer in turn, stopping at the first
+    /// that returns an error.
+    fn notify(
+        &self,
+        callback: impl Fn(&dyn ArtifactObserver) -> Result<(), String>,
+    ) -> Result<(), CompileError> {
+        self.observers
+            .iter()
+            .try_for_each(|observer| callback(observer.as_ref()))
+            .map_err(CompileError::Aborted)
+    }
+
+    fn check_cancelled(&self) -> Result<(), CompileError> {
+        if self.cancellation.is_cancelled() {
+            return Err(CompileError::Cancelled);
+        }
+        Ok(())
+    }
+
+    fn finish_stage<T>(
+        &self,
+        stage: Stage,
+        start: Instant,
+        result: Result<T, CompileError>,
+    ) -> Result<T, CompileError> {
+        if let Err(error) = &result
+            && *error != CompileError::Cancelled
+        {
+            self.progress.emit(&ProgressEvent::Diagnostic {
+                level: "error",
+                message: &error.to_string(),
+            });
+        }
+        self.progress
+            .emit(&ProgressEvent::stage_end(stage, start.elapsed()));
+        result
+    }
+}
+
+/// The feedback for the model when `error` is a use before creation although
+/// the program in `source_code` creates an array before it names the
+/// operation, i.e. the model rather than the author got the order wrong.
+fn invalid_plan_feedback(error: &SemanticError, source_code: &str) -> Option<String> {
+    let SemanticError::UseBeforeCreate {
+        intent_index,
+        operation,
+    } = error
+    else {
+        return None;
+    };
+    let keyword = operation
+        .split_whitespace()
+        .find(|word| !matches!(*word, "find" | "the" | "to" | "of"))?;
+    let text = program_text(source_code).to_lowercase();
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    let created = words.iter().position(|word| word.starts_with("creat"))?;
+    let used = words.iter().position(|word| word.starts_with(keyword))?;
+    (created < used).then(|| {
+        format!(
+            "Intent #{} tried to {} an array before any CreateArray. The request creates the array first: every array MUST be created with CreateArray before an intent uses it, so list the intents in the order the request gives them.",
+            intent_index + 1,
+            operation
+        )
+    })
+}
+
+/// A warning for every block no branch reaches. Codegen emits them anyway;
+/// LLVM drops them when optimizing. Empty blocks that end in `unreachable`
+/// are left out: lowering leaves one behind as the merge block of an `If`
+/// whose branches both exit.
+fn unreferenced_block_warnings(ll_program: &LLProgram) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for function in &ll_program.functions {
+        for id in function.unreferenced_blocks() {
+            let Some(block) = function.basic_blocks.iter().find(|block| block.id == id) else {
+                continue;
+            };
+            if block.instructions.is_empty() && block.terminator == Terminator::Unreachable {
+                continue;
+            }
+            warnings.push(format!(
+                "'{}' has basic block {}, which no branch reaches; it is generated but never runs",
+                function.name, id
+            ));
+        }
+    }
+    warnings
+}
+
+#[cfg(feature = "llvm")]
+fn emit_llvm_ir(ll_program: &LLProgram, target_triple: &str) -> Result<(String, IrSize), String> {
+    crate::codegen_llvm::generate_llvm_ir_with_size(ll_program, target_triple)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "llvm"))]
+fn emit_llvm_ir(_ll_program: &LLProgram, _target_triple: &str) -> Result<(String, IrSize), String> {
+    Err(crate::targets::TargetError::LlvmUnavailable.to_string())
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observer::CollectingObserver;
+    use crate::progress::JsonProgress;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// A `Write` implementation whose contents the test can still read after
+    /// handing it to the compiler.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn events(&self) -> Vec<serde_json::Value> {
+            let bytes = self.0.lock().unwrap().clone();
+            String::from_utf8(bytes)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).expect("Each line must be JSON"))
+                .collect()
+        }
+    }
+
+    const TEST_TRIPLE: &str = "x86_64-unknown-linux-gnu";
+
+    fn test_compiler() -> Compiler {
+        Compiler::new().with_target_triple(Some(TEST_TRIPLE.to_string()))
+    }
+
+    const MOCKED_LLM_RESPONSE: &str = r#"[
+        {"intent": "CreateArray", "parameters": {"size": 5}},
+        {"intent": "SortArray", "parameters": {"order": "ascending"}},
+        {"intent": "PrintArray"}
+    ]"#;
+
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_json_progress_reports_every_stage_in_order() {
+        // Arrange
+        let buffer = SharedBuffer::default();
+        let compiler = test_compiler().with_progress(Box::new(JsonProgress::new(buffer.clone())));
+
+        // Act
+        let result = compiler.compile_llm_output(MOCKED_LLM_RESPONSE);
+
+        // Assert
+        assert!(result.is_ok());
+        let events = buffer.events();
+        let expected_stages = [
+            "parse",
+            "analysis",
+            "lowering",
+            "low_level_lowering",
+            "codegen",
+        ];
+        assert_eq!(events.len(), expected_stages.len() * 2);
+        for (pair, stage) in events.chunks(2).zip(expected_stages) {
+            assert_eq!(pair[0]["event"], "stage_start");
+            assert_eq!(pair[0]["stage"], stage);
+            assert_eq!(pair[1]["event"], "stage_end");
+            assert_eq!(pair[1]["stage"], stage);
+            assert!(pair[1]["duration_ms"].as_f64().unwrap() >= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_json_progress_reports_diagnostic_on_failure() {
+        // Arrange
+        let buffer = SharedBuffer::default();
+        let compiler = test_compiler().with_progress(Box::new(JsonProgress::new(buffer.clone())));
+
+        // Act
+        let result = compiler.compile_llm_output(r#"[{"intent": "PrintArray"}]"#);
+
+        // Assert
+        assert!(result.is_err());
+        let events = buffer.events();
+        let kinds: Vec<&str> = events
+            .iter()
+            .map(|event| event["event"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "stage_start",
+                "stage_end",
+                "stage_start",
+                "diagnostic",
+                "stage_end"
+            ]
+        );
+        assert_eq!(events[3]["level"], "error");
+        assert!(
+            events[3]["message"]
+                .as_str()
+                .unwrap()
+                .contains("suggestion: create an array")
+        );
+        assert!(
+            events[3]["message"]
+                .as_str()
+                .unwrap()
+                .contains("Attempted to print")
+        );
+    }
+
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_apply_suggestions_fixes_sort_before_create() {
+        // Arrange
+        let llm_response = r#"[
+            {"intent": "SortArray", "parameters": {"order": "ascending"}},
+            {"intent": "CreateArray", "parameters": {"size": 5}},
+            {"intent": "PrintArray"}
+        ]"#;
+
+        // Act
+        let without_flag = test_compiler().compile_llm_output(llm_response);
+        let with_flag = test_compiler()
+            .with_apply_suggestions(true)
+            .compile_llm_output(llm_response);
+
+        // Assert
+        let error = without_flag.unwrap_err().to_string();
+        assert!(error.contains("suggestion: move the CreateArray at intent #2 before intent #1"));
+        let output = with_flag.expect("The suggestion should make the program compile");
+        assert_eq!(output.warnings.len(), 1);
+        assert!(matches!(
+            output.validated_intent_graph[0],
+            Intent::CreateArray(_)
+        ));
+        assert!(output.code.contains("call void @sort_array"));
+    }
+
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_identical_inputs_give_identical_llvm_ir() {
+        // Arrange
+        let program = crate::program_generator::generate_program(30);
+        let build = || {
+            test_compiler()
+                .with_seed(Some(7))
+                .compile_llm_output(&program)
+                .expect("Compilation failed")
+                .code
+        };
+
+        // Act
+        let first = build();
+        let second = build();
+
+        // Assert
+        assert!(first.contains("call void @naldom_seed_random(i64 7)"));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_python_target_skips_llvm() {
+        // Arrange
+        let compiler = Compiler::new().with_target(TargetKind::Python);
+
+        // Act
+        let output = compiler
+            .compile_llm_output(MOCKED_LLM_RESPONSE)
+            .expect("The python target must compile without LLVM");
+
+        // Assert
+        assert_eq!(
+            output.code,
+            "var_0 = create_random_array(5)\nsort_array(var_0, 'ascending', 0)\nprint_array(var_0, 0)"
+        );
+    }
+
+    #[test]
+    fn test_timers_reach_the_ir_as_start_and_elapsed_calls() {
+        // Arrange
+        let compiler = Compiler::new().with_target(TargetKind::Python);
+        let llm_response = r#"[
+            {"intent": "CreateArray", "parameters": {"size": 5}},
+            {"intent": "StartTimer"},
+            {"intent": "SortArray", "parameters": {"order": "ascending"}},
+            {"intent": "PrintElapsed", "parameters": {}}
+        ]"#;
+
+        // Act
+        let output = compiler.compile_llm_output(llm_response).unwrap();
+
+        // Assert
+        assert_eq!(
+            output.code,
+            "var_0 = create_random_array(5)\nvar_1 = start_timer()\nsort_array(var_0, 'ascending', 0)\nprint_elapsed(var_1)"
+        );
+        let ll_program = output.ll_program.to_string();
+        let start = ll_program.find("call @start_timer()").unwrap();
+        let sort = ll_program.find("call @sort_array(").unwrap();
+        let elapsed = ll_program.find("call @print_elapsed(").unwrap();
+        assert!(start < sort && sort < elapsed, "{}", ll_program);
+    }
+
+    /// Counts each callback and remembers the size of what it was given.
+    #[derive(Default)]
+    struct CountingObserver {
+        calls: Mutex<Vec<(&'static str, usize)>>,
+        fail_on_hl: bool,
+    }
+
+    impl ArtifactObserver for CountingObserver {
+        fn on_intents(&self, intents: &[Intent]) -> Result<(), String> {
+            self.calls.lock().unwrap().push(("intents", intents.len()));
+            Ok(())
+        }
+
+        fn on_hl(&self, hl_program: &HLProgram) -> Result<(), String> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(("hl", hl_program.statements.len()));
+            if self.fail_on_hl {
+                return Err("the editor was closed".to_string());
+            }
+            Ok(())
+        }
+
+        fn on_ll(&self, ll_program: &LLProgram) -> Result<(), String> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(("ll", ll_program.functions.len()));
+            Ok(())
+        }
+
+        fn on_llvm_ir(&self, llvm_ir: &str) -> Result<(), String> {
+            self.calls.lock().unwrap().push(("llvm_ir", llvm_ir.len()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_observers_see_each_artifact_once_between_stages() {
+        // Arrange
+        let observer = Arc::new(CountingObserver::default());
+        let compiler = Compiler::new()
+            .with_target(TargetKind::Python)
+            .with_observer(observer.clone());
+
+        // Act
+        let output = compiler.compile_llm_output(MOCKED_LLM_RESPONSE).unwrap();
+
+        // Assert
+        assert_eq!(
+            *observer.calls.lock().unwrap(),
+            vec![
+                ("intents", 3),
+                ("hl", output.hl_program.statements.len()),
+                ("ll", output.ll_program.functions.len()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_only_stops_after_analysis() {
+        // Arrange
+        let observer = Arc::new(CountingObserver::default());
+        let compiler = Compiler::new()
+            .with_check_only(true)
+            .with_observer(observer.clone());
+
+        // Act
+        let output = compiler.compile_llm_output(MOCKED_LLM_RESPONSE).unwrap();
+
+        // Assert
+        assert_eq!(*observer.calls.lock().unwrap(), vec![("intents", 3)]);
+        assert_eq!(output.validated_intent_graph.len(), 3);
+        assert!(output.hl_program.statements.is_empty());
+        assert!(output.code.is_empty());
+    }
+
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_observers_see_the_llvm_ir_for_llvm_targets() {
+        // Arrange
+        let observer = Arc::new(CountingObserver::default());
+        let compiler = test_compiler().with_observer(observer.clone());
+
+        // Act
+        let output = compiler.compile_llm_output(MOCKED_LLM_RESPONSE).unwrap();
+
+        // Assert
+        let calls = observer.calls.lock().unwrap();
+        assert_eq!(calls.len(), 4);
+        assert_eq!(calls[3], ("llvm_ir", output.code.len()));
+    }
+
+    #[test]
+    fn test_an_observer_error_aborts_the_pipeline() {
+        // Arrange
+        let observer = Arc::new(CountingObserver {
+            fail_on_hl: true,
+            ..Default::default()
+        });
+        let compiler = Compiler::new()
+            .with_target(TargetKind::Python)
+            .with_observer(observer.clone());
+
+        // Act
+        let result = compiler.compile_llm_output(MOCKED_LLM_RESPONSE);
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(CompileError::Aborted(ref reason)) if reason == "the editor was closed"
+        ));
+        let stages: Vec<_> = observer
+            .calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(stage, _)| *stage)
+            .collect();
+        assert_eq!(stages, ["intents", "hl"]);
+    }
+
+    #[test]
+    fn test_collecting_observer_keeps_copies_of_the_artifacts() {
+        // Arrange
+        let collector = Arc::new(CollectingObserver::new());
+        let compiler = Compiler::new()
+            .with_target(TargetKind::Python)
+            .with_observer(collector.clone());
+
+        // Act
+        let output = compiler.compile_llm_output(MOCKED_LLM_RESPONSE).unwrap();
+
+        // Assert
+        let artifacts = collector.take();
+        assert_eq!(
+            format!("{:?}", artifacts.intents),
+            format!("{:?}", Some(output.validated_intent_graph))
+        );
+        assert_eq!(artifacts.hl_program, Some(output.hl_program));
+        assert_eq!(artifacts.ll_program, Some(output.ll_program));
+        assert_eq!(artifacts.llvm_ir, None);
+        assert!(collector.take().intents.is_none());
+    }
+
+    #[test]
+    fn test_procedures_expand_before_analysis() {
+        // Arrange
+        let compiler = Compiler::new().with_target(TargetKind::Python);
+        let llm_response = r#"[
+            {"intent": "UseProcedure", "parameters": {"name": "show_sorted", "arguments": {"size": 3}}}
+        ]"#;
+
+        // Act
+        let output = compiler.compile_llm_output(llm_response).unwrap();
+
+        // Assert
+        assert_eq!(output.intent_graph.len(), 1);
+        assert_eq!(output.validated_intent_graph.len(), 4);
+        assert_eq!(
+            output.code,
+            "var_0 = create_random_array(3)\nprint_array(var_0, 0)\nsort_array(var_0, 'ascending', 0)\nprint_array(var_0, 0)"
+        );
+    }
+
+    #[test]
+    fn test_denied_lints_fail_the_compilation() {
+        // Arrange
+        let llm_response = r#"[
+            {"intent": "CreateArray", "parameters": {"size": 3}},
+            {"intent": "Wait", "parameters": {"durationMs": 0}},
+            {"intent": "PrintArray"}
+        ]"#;
+        let mut lints = LintRegistry::new();
+        lints
+            .set_level("zero_wait", crate::lints::LintLevel::Deny)
+            .unwrap();
+
+        // Act
+        let warned = Compiler::new()
+            .with_target(TargetKind::Python)
+            .compile_llm_output(llm_response)
+            .unwrap();
+        let denied = Compiler::new()
+            .with_target(TargetKind::Python)
+            .with_lints(lints)
+            .compile_llm_output(llm_response);
+
+        // Assert
+        assert_eq!(
+            warned.warnings,
+            ["zero_wait (intent #2): waiting 0 ms has no effect"]
+        );
+        assert_eq!(
+            denied.unwrap_err().to_string(),
+            "Lint Error: zero_wait (intent #2): waiting 0 ms has no effect"
+        );
+    }
+
+    #[test]
+    fn test_slow_programs_warn_but_still_compile() {
+        // Arrange
+        let llm_response = r#"[
+            {"intent": "CreateArray", "parameters": {"size": 3}},
+            {"intent": "Wait", "parameters": {"durationMs": 20000}}
+        ]"#;
+
+        // Act
+        let output = Compiler::new()
+            .with_target(TargetKind::Python)
+            .with_lints(LintRegistry::empty())
+            .compile_llm_output(llm_response)
+            .unwrap();
+        let relaxed = Compiler::new()
+            .with_target(TargetKind::Python)
+            .with_lints(LintRegistry::empty())
+            .with_slow_threshold(Duration::from_secs(60))
+            .compile_llm_output(llm_response)
+            .unwrap();
+
+        // Assert
+        assert_eq!(output.warnings, ["estimated ~20 s: waiting 20 s"]);
+        assert!(relaxed.warnings.is_empty());
+        assert!(relaxed.cost.is_some());
+    }
+
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_ir_size_limits_warn_or_fail_the_compilation() {
+        // Arrange
+        let limits = |soft, hard| {
+            test_compiler()
+                .with_lints(LintRegistry::empty())
+                .with_ir_size_limits(IrSizeLimits { soft, hard })
+        };
+
+        // Act
+        let normal = limits(1_000, 2_000).compile_llm_output(MOCKED_LLM_RESPONSE);
+        let warned = limits(5, 2_000).compile_llm_output(MOCKED_LLM_RESPONSE);
+        let refused = limits(2, 5).compile_llm_output(MOCKED_LLM_RESPONSE);
+
+        // Assert
+        assert!(normal.unwrap().warnings.is_empty());
+        let warnings = warned.unwrap().warnings;
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("over the soft limit of 5; most come from statement "));
+        assert!(warnings[0].contains("(`create_random_array`, "));
+        let error = refused.unwrap_err().to_string();
+        assert!(error.starts_with("IR Size Error: "));
+        assert!(error.ends_with("split the program into several files."));
+    }
+
+    #[test]
+    fn test_output_attributes_the_code_to_every_intent() {
+        // Arrange
+        let compiler = Compiler::new().with_target(TargetKind::Python);
+
+        // Act
+        let output = compiler.compile_llm_output(MOCKED_LLM_RESPONSE).unwrap();
+
+        // Assert
+        let size = &output.size;
+        assert_eq!(size.intents.len(), 4);
+        assert!(
+            size.intents[..3]
+                .iter()
+                .all(|intent| intent.size.ll_instructions > 0)
+        );
+        let instructions: usize = output.ll_program.functions[0]
+            .basic_blocks
+            .iter()
+            .map(|block| block.instructions.len())
+            .sum();
+        assert_eq!(size.total().ll_instructions, instructions);
+        assert_eq!(size.total().llvm_instructions, None);
+    }
+
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_llvm_instructions_are_attributed_and_add_up_to_the_module() {
+        // Act
+        let output = test_compiler()
+            .compile_llm_output(MOCKED_LLM_RESPONSE)
+            .unwrap();
+
+        // Assert: one line per instruction in the function bodies.
+        let body_lines = output
+            .code
+            .lines()
+            .skip_while(|line| !line.starts_with("define"))
+            .filter(|line| line.starts_with("  "))
+            .count();
+        let size = &output.size;
+        assert_eq!(size.total().llvm_instructions, Some(body_lines));
+        assert_eq!(size.functions[0].size, size.total());
+        assert!(
+            size.intents[..3]
+                .iter()
+                .all(|intent| intent.size.llvm_instructions > Some(0))
+        );
+    }
+
+    /// Records which stages start, and optionally cancels a token as soon as
+    /// a given stage starts.
+    #[derive(Clone, Default)]
+    struct StageRecorder {
+        started: Arc<Mutex<Vec<Stage>>>,
+        cancel_at: Option<(Stage, CancellationToken)>,
+    }
+
+    impl ProgressSink for StageRecorder {
+        fn emit(&self, event: &ProgressEvent) {
+            if let ProgressEvent::StageStart { stage } = event {
+                self.started.lock().unwrap().push(*stage);
+                if let Some((cancel_stage, token)) = &self.cancel_at
+                    && cancel_stage == stage
+                {
+                    token.cancel();
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_abandons_a_slow_inference() {
+        // Arrange
+        let token = CancellationToken::new();
+        let recorder = StageRecorder::default();
+        let compiler = test_compiler()
+            .with_progress(Box::new(recorder.clone()))
+            .with_cancellation(token.clone());
+        let slow_inference = |_| async {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(MOCKED_LLM_RESPONSE.to_string())
+        };
+        let cancel_soon = async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            token.cancel();
+        };
+
+        // Act
+        let start = Instant::now();
+        let (result, ()) = tokio::join!(
+            compiler.compile_with_retries("", slow_inference),
+            cancel_soon
+        );
+
+        // Assert
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(matches!(result, Err(CompileError::Cancelled)));
+        assert_eq!(*recorder.started.lock().unwrap(), [Stage::Inference]);
+    }
+
+    #[test]
+    fn test_cancellation_skips_the_remaining_stages() {
+        // Arrange
+        let token = CancellationToken::new();
+        let recorder = StageRecorder {
+            cancel_at: Some((Stage::Analysis, token.clone())),
+            ..StageRecorder::default()
+        };
+        let compiler = test_compiler()
+            .with_target(TargetKind::Python)
+            .with_progress(Box::new(recorder.clone()))
+            .with_cancellation(token);
+
+        // Act
+        let result = compiler.compile_llm_output(MOCKED_LLM_RESPONSE);
+
+        // Assert
+        assert_eq!(result.unwrap_err(), CompileError::Cancelled);
+        assert_eq!(
+            *recorder.started.lock().unwrap(),
+            [Stage::Parse, Stage::Analysis]
+        );
+    }
+
+    /// A document that creates its array first, and a plan that sorts first.
+    const IN_ORDER_SOURCE: &str =
+        "Some notes.\n:::naldom\nCreate an array of 5 numbers. Sort it, then print it.\n:::";
+    const OUT_OF_ORDER_PLAN: &str = r#"[
+        {"intent": "SortArray", "parameters": {"order": "ascending"}},
+        {"intent": "CreateArray", "parameters": {"size": 5}},
+        {"intent": "PrintArray"}
+    ]"#;
+
+    #[test]
+    fn test_use_before_create_is_the_models_fault_only_for_in_order_sources() {
+        // Arrange
+        let sort_first = SemanticError::UseBeforeCreate {
+            intent_index: 0,
+            operation: "sort",
+        };
+
+        // Act
+        let in_order = invalid_plan_feedback(&sort_first, IN_ORDER_SOURCE);
+        let out_of_order = invalid_plan_feedback(&sort_first, "Sort the array. Create an array.");
+        let unrelated = invalid_plan_feedback(
+            &SemanticError::MissingSize { intent_index: 0 },
+            IN_ORDER_SOURCE,
+        );
+
+        // Assert
+        assert!(
+            in_order
+                .unwrap()
+                .starts_with("Intent #1 tried to sort an array before any CreateArray.")
+        );
+        assert_eq!(out_of_order, None);
+        assert_eq!(unrelated, None);
+    }
+
+    /// Answers with `responses` in turn, recording the feedback of each request.
+    fn scripted_model(
+        responses: &[&str],
+        feedback: &Arc<Mutex<Vec<Option<String>>>>,
+    ) -> impl FnMut(PromptOptions) -> std::future::Ready<Result<String, String>> {
+        let mut responses = responses.iter().map(|response| response.to_string());
+        let feedback = Arc::clone(feedback);
+        move |prompt_options| {
+            feedback.lock().unwrap().push(prompt_options.feedback);
+            std::future::ready(
+                responses
+                    .next()
+                    .ok_or_else(|| "out of responses".to_string()),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_an_invalid_plan_is_retried_with_corrective_feedback() {
+        // Arrange
+        let buffer = SharedBuffer::default();
+        let compiler = Compiler::new()
+            .with_target(TargetKind::Python)
+            .with_retries(1)
+            .with_progress(Box::new(JsonProgress::new(buffer.clone())));
+        let feedback = Arc::new(Mutex::new(Vec::new()));
+        let model = scripted_model(&[OUT_OF_ORDER_PLAN, MOCKED_LLM_RESPONSE], &feedback);
+
+        // Act
+        let result = compiler.compile_with_retries(IN_ORDER_SOURCE, model).await;
+
+        // Assert
+        let output = result.expect("The second plan should compile");
+        assert!(output.code.starts_with("var_0 = create_random_array(5)"));
+        let feedback = feedback.lock().unwrap();
+        assert_eq!(feedback.len(), 2);
+        assert_eq!(feedback[0], None);
+        assert!(
+            feedback[1]
+                .as_ref()
+                .unwrap()
+                .contains("Intent #1 tried to sort")
+        );
+        let diagnostics: Vec<String> = buffer
+            .events()
+            .iter()
+            .filter(|event| event["event"] == "diagnostic")
+            .map(|event| event["message"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(diagnostics.len(), 2);
+        assert!(
+            diagnostics[0].starts_with("Model Error: the language model produced an invalid plan.")
+        );
+        assert_eq!(
+            diagnostics[1],
+            "the language model produced an invalid plan; retrying with corrective feedback (attempt 2 of 2)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_without_retries_an_invalid_plan_ends_with_advice() {
+        // Arrange
+        let compiler = Compiler::new().with_target(TargetKind::Python);
+        let feedback = Arc::new(Mutex::new(Vec::new()));
+        let model = scripted_model(&[OUT_OF_ORDER_PLAN, MOCKED_LLM_RESPONSE], &feedback);
+
+        // Act
+        let result = compiler.compile_with_retries(IN_ORDER_SOURCE, model).await;
+        let sort_first = compiler.compile_with_retries(
+            "Sort the array, then create an array of 5 numbers.",
+            scripted_model(&[OUT_OF_ORDER_PLAN], &feedback),
+        );
+
+        // Assert
+        let Err(CompileError::InvalidPlan { message, .. }) = result else {
+            panic!("expected an invalid plan, got {:?}", result);
+        };
+        assert!(message.contains("Attempted to sort"));
+        assert!(message.contains("help: the source creates the array first; rephrase the request"));
+        assert_eq!(feedback.lock().unwrap().len(), 1);
+        assert!(matches!(sort_first.await, Err(CompileError::Failed(_))));
+    }
+
+    #[cfg(feature = "llvm")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_async_compilations_keep_their_own_results() {
+        // Arrange: eight programs that differ only in their array size.
+        let compiler = Arc::new(test_compiler());
+        let sizes = 1..=8u32;
+
+        // Act: `tokio::spawn` only accepts the future because it is `Send`.
+        let handles: Vec<_> = sizes
+            .clone()
+            .map(|size| {
+                let response = format!(
+                    r#"[{{"intent": "CreateArray", "parameters": {{"size": {}}}}}, {{"intent": "PrintArray"}}]"#,
+                    size
+                );
+                tokio::spawn(
+                    Arc::clone(&compiler)
+                        .compile_inferred_async(async move { Ok(response) }, Arc::default()),
+                )
+            })
+            .collect();
+
+        // Assert
+        for (size, handle) in sizes.zip(handles) {
+            let output = handle.await.unwrap().expect("Compilation failed");
+            assert!(output.intent_json.contains(&format!("\"size\": {}", size)));
+            assert!(
+                output
+                    .code
+                    .contains(&format!("@create_random_array(i64 {})", size)),
+                "program {} got someone else's code",
+                size
+            );
+        }
+    }
+
+    #[test]
+    fn test_unreferenced_blocks_warn_unless_lowering_left_them_as_placeholders() {
+        // Arrange: both branches exit, so the merge block is an empty placeholder.
+        let exit = || naldom_ir::HLStatement::Call {
+            function: "naldom_exit".to_string(),
+            arguments: vec![],
+        };
+        let hl_program = HLProgram {
+            statements: vec![naldom_ir::HLStatement::If {
+                condition: naldom_ir::HLExpression::FunctionCall {
+                    function: "is_sorted".to_string(),
+                    arguments: vec![],
+                },
+                then_body: vec![exit()],
+                else_body: vec![exit()],
+            }],
+        };
+        let mut ll_program = crate::lowering_hl_to_ll::lower_hl_to_ll(&hl_program).unwrap();
+        let placeholders_only = unreferenced_block_warnings(&ll_program);
+        let main = &mut ll_program.functions[0];
+        main.basic_blocks.push(naldom_ir::BasicBlock {
+            id: 9,
+            instructions: Vec::new(),
+            terminator: Terminator::Return(None),
+            annotations: Vec::new(),
+        });
+
+        // Act
+        let warnings = unreferenced_block_warnings(&ll_program);
+
+        // Assert
+        assert!(placeholders_only.is_empty());
+        assert_eq!(
+            warnings,
+            ["'main' has basic block 9, which no branch reaches; it is generated but never runs"]
+        );
+    }
+}
@@ -0,0 +1,559 @@
+// crates/naldom-core/src/cost.rs
+
+//! A static estimate of how long a validated program will run.
+//!
+//! Each builtin has a rough cost formula over the array sizes known at compile
+//! time: creating, concatenating, printing, summing, averaging, reversing,
+//! mapping, searching and finding extremes are linear, counting is constant, sorting
+//! and taking the median (which sorts a copy) are `n log n` (a bubble sort is
+//! quadratic), appending is
+//! constant (and grows the array for later intents), slicing is linear in the
+//! elements kept (and shrinks the array for later intents), a wait costs
+//! exactly its duration, a repeat costs its body times its count, and a
+//! conditional costs its test plus its costlier branch. The estimate is
+//! advisory only; it produces a warning for slow programs and a breakdown for
+//! `--explain`, but never fails a build.
+
+use crate::interpreter::group_thousands;
+use naldom_ir::{Intent, PrintArrayParams};
+use std::time::Duration;
+
+/// Programs estimated to run longer than this get a warning, unless the
+/// compiler is configured with a different threshold.
+pub const DEFAULT_SLOW_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// Filling one element with a random number.
+const CREATE_NS_PER_ELEMENT: f64 = 5.0;
+/// One comparison (and the moves around it) while sorting.
+const SORT_NS_PER_COMPARISON: f64 = 10.0;
+/// Formatting and writing one element.
+const PRINT_NS_PER_ELEMENT: f64 = 100.0;
+/// Adding one element to a running total.
+const SUM_NS_PER_ELEMENT: f64 = 1.0;
+/// Swapping one element with its mirror (each swap moves two).
+const REVERSE_NS_PER_ELEMENT: f64 = 1.0;
+/// Comparing one element with the smallest or largest so far.
+const EXTREME_NS_PER_ELEMENT: f64 = 1.0;
+/// Comparing one element with the value searched for.
+const CONTAINS_NS_PER_ELEMENT: f64 = 1.0;
+/// Finding the range of one element, then counting it in its bucket.
+const HISTOGRAM_NS_PER_ELEMENT: f64 = 2.0;
+/// Comparing one element with the smallest, the largest and its neighbour.
+const DESCRIBE_NS_PER_ELEMENT: f64 = 3.0;
+/// Copying one element into a new array.
+const CONCAT_NS_PER_ELEMENT: f64 = 1.0;
+/// Applying arithmetic to one element.
+const MAP_NS_PER_ELEMENT: f64 = 1.0;
+/// Converting one element into a new array.
+const CONVERT_NS_PER_ELEMENT: f64 = 1.0;
+/// Moving one kept element to the front of the array.
+const SLICE_NS_PER_ELEMENT: f64 = 1.0;
+/// Overwriting one element.
+const FILL_NS_PER_ELEMENT: f64 = 1.0;
+/// Setting an array's length to zero.
+const CLEAR_NS: f64 = 1.0;
+/// Reading an array's length.
+const COUNT_NS: f64 = 1.0;
+/// Appending one element, with the occasional reallocation spread over the
+/// appends that fill the new storage.
+const APPEND_NS: f64 = 5.0;
+
+/// Arrays longer than twice this print only their first and last elements,
+/// as the runtime's `print_array` does.
+const PRINT_EDGE: u64 = 20;
+
+/// Contributions below this share of the total are left out of the warning.
+const SUMMARY_SHARE: f64 = 0.1;
+
+/// The estimated cost of one intent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntentCost {
+    pub intent_index: usize,
+    pub seconds: f64,
+    /// What the time is spent on, e.g. "sorting 1,000 elements".
+    pub description: String,
+}
+
+/// The estimated run time of a whole program, intent by intent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostEstimate {
+    /// Intents that take no measurable time (e.g. notes) are left out.
+    pub intents: Vec<IntentCost>,
+}
+
+impl CostEstimate {
+    pub fn total_seconds(&self) -> f64 {
+        self.intents.iter().map(|cost| cost.seconds).sum()
+    }
+
+    /// A one-line warning naming the main contributors, or `None` when the
+    /// program is estimated to finish within `threshold`.
+    pub fn warning(&self, threshold: Duration) -> Option<String> {
+        let total = self.total_seconds();
+        if total <= threshold.as_secs_f64() {
+            return None;
+        }
+        let contributors: Vec<&str> = self
+            .intents
+            .iter()
+            .filter(|cost| cost.seconds >= total * SUMMARY_SHARE)
+            .map(|cost| cost.description.as_str())
+            .collect();
+        Some(format!(
+            "estimated {}: {}",
+            format_estimate(total),
+            join_with_and(&contributors)
+        ))
+    }
+
+    /// The per-intent breakdown printed by `--explain`.
+    pub fn render(&self) -> String {
+        let mut lines = vec![format!(
+            "Estimated run time: {}",
+            format_estimate(self.total_seconds())
+        )];
+        for cost in &self.intents {
+            lines.push(format!(
+                "  {}. {:<9} {}",
+                cost.intent_index + 1,
+                format_estimate(cost.seconds),
+                cost.description
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Estimates the run time of a validated IntentGraph. Returns `None` when the
+/// size of an array an intent acts on cannot be determined.
+pub fn estimate(intents: &[Intent]) -> Option<CostEstimate> {
+    let intents = estimate_intents(intents, &mut Vec::new(), &mut Vec::new())?;
+    Some(CostEstimate { intents })
+}
+
+/// The cost of each of `intents`, given the sizes of the arrays and matrices
+/// created before them. Appends the sizes of the ones they create.
+fn estimate_intents(
+    intents: &[Intent],
+    sizes: &mut Vec<u64>,
+    matrices: &mut Vec<u64>,
+) -> Option<Vec<IntentCost>> {
+    let mut costs = Vec::new();
+    for (intent_index, intent) in intents.iter().enumerate() {
+        let (seconds, description) = match intent {
+            Intent::CreateArray(params) => {
+                let n = u64::from(params.literal_size()?);
+                sizes.push(n);
+                (
+                    n as f64 * CREATE_NS_PER_ELEMENT / 1e9,
+                    format!("creating {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::ConcatArrays(params) => {
+                let size = |ordinal: Option<u32>| sizes.get((ordinal? as usize).checked_sub(1)?);
+                let n = size(params.first_ordinal)? + size(params.second_ordinal)?;
+                sizes.push(n);
+                (
+                    n as f64 * CONCAT_NS_PER_ELEMENT / 1e9,
+                    format!("copying {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::CopyArray(_) => {
+                let n = target_size(sizes, intent)?;
+                sizes.push(n);
+                (
+                    n as f64 * CONCAT_NS_PER_ELEMENT / 1e9,
+                    format!("copying {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::CreateMatrix(params) => {
+                let n = u64::from(params.rows) * u64::from(params.cols);
+                matrices.push(n);
+                (
+                    n as f64 * CREATE_NS_PER_ELEMENT / 1e9,
+                    format!("creating {} elements", group_thousands(n as usize)),
+                )
+            }
+            // Matrices print in full.
+            Intent::PrintMatrix(_) => {
+                let n = *matrices.last()?;
+                (
+                    n as f64 * PRINT_NS_PER_ELEMENT / 1e9,
+                    format!("printing {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::SortArray(params) => {
+                let n = target_size(sizes, intent)?;
+                let comparisons = if params.algorithm.as_deref() == Some("bubble") {
+                    n as f64 * n as f64 / 2.0
+                } else {
+                    n as f64 * (n.max(2) as f64).log2()
+                };
+                (
+                    comparisons * SORT_NS_PER_COMPARISON / 1e9,
+                    format!("sorting {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::PrintArray(params) => {
+                let full = params.as_ref().is_some_and(|p| p.full == Some(true));
+                let shown = |n: u64| if full { n } else { n.min(2 * PRINT_EDGE) };
+                let printed = if params.as_ref().is_some_and(PrintArrayParams::prints_all) {
+                    let count = intent.target_ordinal().map_or(sizes.len(), |c| c as usize);
+                    sizes.iter().take(count).copied().map(shown).sum()
+                } else {
+                    shown(target_size(sizes, intent)?)
+                };
+                (
+                    printed as f64 * PRINT_NS_PER_ELEMENT / 1e9,
+                    format!("printing {} elements", group_thousands(printed as usize)),
+                )
+            }
+            Intent::PrintHistogram(params) => {
+                let n = target_size(sizes, intent)?;
+                let buckets = params.as_ref().and_then(|p| p.buckets).unwrap_or(0);
+                (
+                    (n as f64 * HISTOGRAM_NS_PER_ELEMENT + buckets as f64 * PRINT_NS_PER_ELEMENT)
+                        / 1e9,
+                    format!(
+                        "charting {} elements in {} buckets",
+                        group_thousands(n as usize),
+                        buckets
+                    ),
+                )
+            }
+            Intent::DescribeArray(_) => {
+                let n = target_size(sizes, intent)?;
+                (
+                    n as f64 * DESCRIBE_NS_PER_ELEMENT / 1e9,
+                    format!("describing {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::SumArray(_) => {
+                let n = target_size(sizes, intent)?;
+                (
+                    n as f64 * SUM_NS_PER_ELEMENT / 1e9,
+                    format!("summing {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::ContainsValue(_) | Intent::FindIndex(_) => {
+                let n = target_size(sizes, intent)?;
+                (
+                    n as f64 * CONTAINS_NS_PER_ELEMENT / 1e9,
+                    format!("searching {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::AverageArray(_) => {
+                let n = target_size(sizes, intent)?;
+                (
+                    n as f64 * SUM_NS_PER_ELEMENT / 1e9,
+                    format!("averaging {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::MedianArray(_) => {
+                let n = target_size(sizes, intent)?;
+                let comparisons = n as f64 * (n.max(2) as f64).log2();
+                (
+                    comparisons * SORT_NS_PER_COMPARISON / 1e9,
+                    format!("sorting a copy of {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::ReverseArray(_) => {
+                let n = target_size(sizes, intent)?;
+                (
+                    n as f64 * REVERSE_NS_PER_ELEMENT / 1e9,
+                    format!("reversing {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::MapArray(_) => {
+                let n = target_size(sizes, intent)?;
+                (
+                    n as f64 * MAP_NS_PER_ELEMENT / 1e9,
+                    format!("updating {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::ConvertArray(_) => {
+                let n = target_size(sizes, intent)?;
+                (
+                    n as f64 * CONVERT_NS_PER_ELEMENT / 1e9,
+                    format!("converting {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::AppendToArray(_) => {
+                let index = match intent.target_ordinal() {
+                    Some(ordinal) => (ordinal as usize).checked_sub(1)?,
+                    None => sizes.len().checked_sub(1)?,
+                };
+                *sizes.get_mut(index)? += 1;
+                (APPEND_NS / 1e9, "appending an element".to_string())
+            }
+            Intent::FillArray(_) => {
+                let n = target_size(sizes, intent)?;
+                (
+                    n as f64 * FILL_NS_PER_ELEMENT / 1e9,
+                    format!("filling {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::ClearArray(_) => {
+                let index = match intent.target_ordinal() {
+                    Some(ordinal) => (ordinal as usize).checked_sub(1)?,
+                    None => sizes.len().checked_sub(1)?,
+                };
+                *sizes.get_mut(index)? = 0;
+                (CLEAR_NS / 1e9, "emptying the array".to_string())
+            }
+            Intent::SliceArray(params) => {
+                let index = match intent.target_ordinal() {
+                    Some(ordinal) => (ordinal as usize).checked_sub(1)?,
+                    None => sizes.len().checked_sub(1)?,
+                };
+                let size = sizes.get_mut(index)?;
+                let kept = if params.from_end {
+                    u64::from(params.start).min(*size)
+                } else {
+                    let end = params.end.map_or(*size, |end| u64::from(end).min(*size));
+                    end.saturating_sub(u64::from(params.start))
+                };
+                *size = kept;
+                (
+                    kept as f64 * SLICE_NS_PER_ELEMENT / 1e9,
+                    format!("keeping {} elements", group_thousands(kept as usize)),
+                )
+            }
+            Intent::CountElements(_) => {
+                target_size(sizes, intent)?;
+                (COUNT_NS / 1e9, "counting the elements".to_string())
+            }
+            Intent::MinArray(_) | Intent::MaxArray(_) => {
+                let n = target_size(sizes, intent)?;
+                (
+                    n as f64 * EXTREME_NS_PER_ELEMENT / 1e9,
+                    format!("scanning {} elements", group_thousands(n as usize)),
+                )
+            }
+            Intent::Repeat(params) => {
+                let body = estimate_intents(&params.body, sizes, matrices)?;
+                if body.is_empty() {
+                    continue;
+                }
+                let once: f64 = body.iter().map(|cost| cost.seconds).sum();
+                let descriptions: Vec<&str> =
+                    body.iter().map(|cost| cost.description.as_str()).collect();
+                (
+                    once * f64::from(params.count),
+                    format!("{} {} times", join_with_and(&descriptions), params.count),
+                )
+            }
+            // The costlier branch counts, on top of the test itself.
+            Intent::Conditional(params) => {
+                let n = target_size(sizes, intent)?;
+                let (tested, test) = if params.condition == "is_sorted" {
+                    (
+                        n,
+                        format!(
+                            "checking the order of {} elements",
+                            group_thousands(n as usize)
+                        ),
+                    )
+                } else {
+                    (0, "checking for elements".to_string())
+                };
+                let mut branch_costs = Vec::new();
+                for body in [&params.then_body, &params.else_body] {
+                    branch_costs.push(estimate_intents(
+                        body,
+                        &mut sizes.clone(),
+                        &mut matrices.clone(),
+                    )?);
+                }
+                let total = |costs: &[IntentCost]| costs.iter().map(|c| c.seconds).sum::<f64>();
+                let worst = branch_costs
+                    .iter()
+                    .max_by(|a, b| total(a).total_cmp(&total(b)))
+                    .expect("a conditional has two branches");
+                let mut descriptions = vec![test.as_str()];
+                descriptions.extend(worst.iter().map(|cost| cost.description.as_str()));
+                (
+                    tested as f64 * EXTREME_NS_PER_ELEMENT / 1e9 + total(worst),
+                    join_with_and(&descriptions),
+                )
+            }
+            Intent::Wait(params) => (
+                params.duration_ms as f64 / 1e3,
+                format!("waiting {}", format_wait(params.duration_ms)),
+            ),
+            // Reading the clock is too cheap to count.
+            Intent::Note(_)
+            | Intent::SetVariable(_)
+            | Intent::StartTimer(_)
+            | Intent::PrintElapsed(_)
+            | Intent::Exit(_) => continue,
+            // Procedures are expanded before analysis; an unexpanded one has
+            // no known cost.
+            Intent::UseProcedure(_) => return None,
+        };
+        costs.push(IntentCost {
+            intent_index,
+            seconds,
+            description,
+        });
+    }
+    Some(costs)
+}
+
+/// The size of the array `intent` acts on: the one at its ordinal, or the
+/// most recently created one.
+fn target_size(sizes: &[u64], intent: &Intent) -> Option<u64> {
+    match intent.target_ordinal() {
+        Some(ordinal) => sizes.get((ordinal as usize).checked_sub(1)?).copied(),
+        None => sizes.last().copied(),
+    }
+}
+
+/// An approximate duration: "<1 ms", "~250 ms", "~43 s" or "~12 min".
+fn format_estimate(seconds: f64) -> String {
+    if seconds < 0.5e-3 {
+        "<1 ms".to_string()
+    } else if seconds < 1.0 {
+        format!("~{:.0} ms", seconds * 1e3)
+    } else if seconds < 120.0 {
+        format!("~{:.0} s", seconds)
+    } else {
+        format!("~{:.0} min", seconds / 60.0)
+    }
+}
+
+/// A wait's exact duration: "30 s" or "1500 ms".
+fn format_wait(duration_ms: u64) -> String {
+    if duration_ms >= 1000 && duration_ms.is_multiple_of(1000) {
+        format!("{} s", duration_ms / 1000)
+    } else {
+        format!("{} ms", duration_ms)
+    }
+}
+
+/// "a", "a and b", "a, b and c".
+fn join_with_and(items: &[&str]) -> String {
+    match items {
+        [] => String::new(),
+        [only] => only.to_string(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use naldom_ir::{CountArg, CreateArrayParams, RepeatParams, SortArrayParams, WaitParams};
+
+    fn create(size: u32) -> Intent {
+        Intent::CreateArray(CreateArrayParams {
+            size: Some(CountArg::Literal(size)),
+            name: None,
+            values: Vec::new(),
+            dtype: None,
+        })
+    }
+
+    fn sort(target_ordinal: Option<u32>) -> Intent {
+        Intent::SortArray(SortArrayParams {
+            order: Some("ascending".to_string()),
+            algorithm: None,
+            target_ordinal,
+            target: None,
+        })
+    }
+
+    fn wait(duration_ms: u64) -> Intent {
+        Intent::Wait(WaitParams { duration_ms })
+    }
+
+    #[test]
+    fn test_estimate_applies_the_cost_formulas() {
+        // Arrange
+        let intents = [
+            create(1_000_000),
+            create(1024),
+            sort(Some(2)),
+            Intent::PrintArray(Some(PrintArrayParams {
+                target_ordinal: Some(1),
+                target: None,
+                full: Some(false),
+            })),
+            wait(1500),
+        ];
+
+        // Act
+        let cost = estimate(&intents).unwrap();
+
+        // Assert
+        let expected = [5e-3, 5.12e-6, 1.024e-4, 4e-6, 1.5];
+        for (intent, seconds) in cost.intents.iter().zip(expected) {
+            assert!((intent.seconds - seconds).abs() < 1e-12, "{:?}", intent);
+        }
+        assert_eq!(cost.intents[2].description, "sorting 1,024 elements");
+        assert_eq!(cost.intents[3].description, "printing 40 elements");
+        assert_eq!(cost.intents[4].description, "waiting 1500 ms");
+    }
+
+    #[test]
+    fn test_warning_names_the_main_contributors_above_the_threshold() {
+        // Arrange
+        let intents = [create(50_000_000), sort(None), wait(30_000)];
+        let cost = estimate(&intents).unwrap();
+
+        // Act
+        let warning = cost.warning(DEFAULT_SLOW_THRESHOLD);
+        let silenced = cost.warning(Duration::from_secs(60));
+        let fast = estimate(&[create(10), sort(None)])
+            .unwrap()
+            .warning(DEFAULT_SLOW_THRESHOLD);
+
+        // Assert
+        assert_eq!(
+            warning.as_deref(),
+            Some("estimated ~43 s: sorting 50,000,000 elements and waiting 30 s")
+        );
+        assert_eq!(silenced, None);
+        assert_eq!(fast, None);
+        assert_eq!(
+            cost.render(),
+            [
+                "Estimated run time: ~43 s",
+                "  1. ~250 ms   creating 50,000,000 elements",
+                "  2. ~13 s     sorting 50,000,000 elements",
+                "  3. ~30 s     waiting 30 s",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_repeat_multiplies_the_cost_of_its_body() {
+        // Arrange
+        let intents = [
+            create(1024),
+            Intent::Repeat(RepeatParams {
+                count: 4,
+                body: vec![sort(None), wait(250)],
+            }),
+        ];
+
+        // Act
+        let cost = estimate(&intents).unwrap();
+
+        // Assert
+        assert!((cost.intents[1].seconds - 4.0 * (1.024e-4 + 0.25)).abs() < 1e-12);
+        assert_eq!(
+            cost.intents[1].description,
+            "sorting 1,024 elements and waiting 250 ms 4 times"
+        );
+    }
+
+    #[test]
+    fn test_unknown_sizes_give_no_estimate() {
+        // An ordinal past the created arrays, as in an unvalidated graph.
+        assert_eq!(estimate(&[create(5), sort(Some(3))]), None);
+        assert_eq!(estimate(&[sort(None)]), None);
+    }
+}
@@ -0,0 +1,59 @@
+// crates/naldom-core/src/error_mode.rs
+
+//! What a compiled program does when a runtime function fails, chosen with
+//! `--on-error` or the `on_error` front-matter key.
+
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// How a program handles runtime errors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorMode {
+    /// Print the error and end the program.
+    #[default]
+    Abort,
+    /// Print a warning and carry on with a neutral result, e.g. an empty
+    /// array, for best-effort documents.
+    Continue,
+}
+
+impl ErrorMode {
+    /// The name accepted by `--on-error`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorMode::Abort => "abort",
+            ErrorMode::Continue => "continue",
+        }
+    }
+
+    /// The code `naldom_set_error_mode` takes for this mode.
+    pub fn code(&self) -> i64 {
+        match self {
+            ErrorMode::Abort => 0,
+            ErrorMode::Continue => 1,
+        }
+    }
+}
+
+impl fmt::Display for ErrorMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ErrorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "abort" => Ok(ErrorMode::Abort),
+            "continue" => Ok(ErrorMode::Continue),
+            other => Err(format!(
+                "unknown error mode '{}' (expected 'abort' or 'continue')",
+                other
+            )),
+        }
+    }
+}
@@ -0,0 +1,419 @@
+// crates/naldom-core/src/eval.rs
+
+//! Measures how well a prompt turns sources into the intended IntentGraph,
+//! for `naldom-cli eval`.
+//!
+//! A case is a source with the intents it should produce. Each case is sent
+//! to the model several times, and every answer is scored three ways: does
+//! it parse, does it compile, and is it exactly the expected IntentGraph.
+//! Answers are compared in a canonical form, the pretty-printed intents, so
+//! key order, whitespace and text around the JSON array do not count.
+
+use crate::compiler::Compiler;
+use crate::parser::parse_to_intent_graph;
+use crate::passes::unified_diff;
+use crate::prompt::{Prompt, PromptOptions, build_prompt};
+use crate::targets::TargetKind;
+use naldom_ir::Intent;
+use serde::Serialize;
+use std::fs;
+use std::future::Future;
+use std::path::Path;
+
+/// The extension of a case's source; its expected intents sit next to it in
+/// a `.json` file of the same name.
+pub const CASE_SOURCE_EXTENSION: &str = "nld";
+
+/// A labeled case: a source and the intents it should produce.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalCase {
+    pub name: String,
+    pub source: String,
+    /// The expected intents, in canonical form.
+    pub expected: String,
+}
+
+/// Loads every case in `dir`, sorted by name.
+pub fn load_cases(dir: &Path) -> Result<Vec<EvalCase>, String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Error reading '{}': {}", dir.display(), e))?;
+    let mut cases = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("Error reading '{}': {}", dir.display(), e))?
+            .path();
+        if path
+            .extension()
+            .is_none_or(|ext| ext != CASE_SOURCE_EXTENSION)
+        {
+            continue;
+        }
+        let read = |path: &Path| {
+            fs::read_to_string(path)
+                .map_err(|e| format!("Error reading '{}': {}", path.display(), e))
+        };
+        let expected_path = path.with_extension("json");
+        let expected = parse_to_intent_graph(&read(&expected_path)?)
+            .map_err(|e| format!("'{}' is not an IntentGraph: {}", expected_path.display(), e))?;
+        cases.push(EvalCase {
+            name: path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            source: read(&path)?,
+            expected: canonical_form(&expected),
+        });
+    }
+    if cases.is_empty() {
+        return Err(format!(
+            "'{}' holds no cases (`*.{}` files)",
+            dir.display(),
+            CASE_SOURCE_EXTENSION
+        ));
+    }
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cases)
+}
+
+/// The form in which answers are compared.
+fn canonical_form(intents: &[Intent]) -> String {
+    format!("{:#?}\n", intents)
+}
+
+/// The scores of one case.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CaseReport {
+    pub name: String,
+    pub runs: u32,
+    /// Answers that parsed as an IntentGraph.
+    pub parsed: u32,
+    /// Answers that compiled.
+    pub valid: u32,
+    /// Answers that were exactly the expected IntentGraph.
+    pub exact: u32,
+    /// How the first parsed but wrong answer differs from the expected one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_mismatch: Option<String>,
+}
+
+/// The scores of every case for one prompt.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EvalReport {
+    pub cases: Vec<CaseReport>,
+    pub parse_success_rate: f64,
+    pub semantic_validity_rate: f64,
+    pub exact_match_rate: f64,
+}
+
+impl EvalReport {
+    fn new(cases: Vec<CaseReport>) -> Self {
+        let total = |count: fn(&CaseReport) -> u32| cases.iter().map(count).sum::<u32>();
+        let runs = total(|case| case.runs);
+        let rate = |count: u32| {
+            if runs == 0 {
+                0.0
+            } else {
+                f64::from(count) / f64::from(runs)
+            }
+        };
+        EvalReport {
+            parse_success_rate: rate(total(|case| case.parsed)),
+            semantic_validity_rate: rate(total(|case| case.valid)),
+            exact_match_rate: rate(total(|case| case.exact)),
+            cases,
+        }
+    }
+
+    /// The parse success, semantic validity and exact match rates.
+    fn rates(&self) -> [f64; 3] {
+        [
+            self.parse_success_rate,
+            self.semantic_validity_rate,
+            self.exact_match_rate,
+        ]
+    }
+}
+
+/// Sends every case to `model` `runs` times, with prompts built from
+/// `prompt_options`. A failed request scores like an unparseable answer.
+pub async fn evaluate<F, Fut>(
+    cases: &[EvalCase],
+    runs: u32,
+    prompt_options: &PromptOptions,
+    mut model: F,
+) -> EvalReport
+where
+    F: FnMut(Prompt) -> Fut,
+    Fut: Future<Output = Result<String, String>>,
+{
+    // Only the Python target: validity must not depend on an LLVM install.
+    let compiler = Compiler::new().with_target(TargetKind::Python);
+    let mut reports = Vec::new();
+    for case in cases {
+        let mut report = CaseReport {
+            name: case.name.clone(),
+            runs,
+            ..CaseReport::default()
+        };
+        for _ in 0..runs {
+            let Ok(answer) = model(build_prompt(&case.source, prompt_options)).await else {
+                continue;
+            };
+            let Ok(intents) = parse_to_intent_graph(&answer) else {
+                continue;
+            };
+            report.parsed += 1;
+            if compiler.compile_llm_output(&answer).is_ok() {
+                report.valid += 1;
+            }
+            let actual = canonical_form(&intents);
+            if actual == case.expected {
+                report.exact += 1;
+            } else if report.first_mismatch.is_none() {
+                report.first_mismatch = unified_diff(&case.expected, &actual, &case.name);
+            }
+        }
+        reports.push(report);
+    }
+    EvalReport::new(reports)
+}
+
+/// A table with a column per labeled report: the exact matches of each case,
+/// then the aggregate rates.
+pub fn render_table(reports: &[(&str, &EvalReport)]) -> String {
+    let mut rows: Vec<(String, Vec<String>)> = Vec::new();
+    let case_names = reports
+        .first()
+        .map(|(_, report)| report.cases.iter().map(|case| case.name.clone()))
+        .into_iter()
+        .flatten();
+    for (i, name) in case_names.enumerate() {
+        let cells = reports
+            .iter()
+            .map(|(_, report)| {
+                report
+                    .cases
+                    .get(i)
+                    .map(|case| format!("{}/{}", case.exact, case.runs))
+                    .unwrap_or_default()
+            })
+            .collect();
+        rows.push((name, cells));
+    }
+    for (row, label) in ["parse success", "semantic validity", "exact match"]
+        .into_iter()
+        .enumerate()
+    {
+        let cells = reports
+            .iter()
+            .map(|(_, report)| format!("{:.1}%", report.rates()[row] * 100.0))
+            .collect();
+        rows.push((label.to_string(), cells));
+    }
+
+    let first_width = rows
+        .iter()
+        .map(|(name, _)| name.len())
+        .chain(["case".len()])
+        .max()
+        .unwrap_or_default();
+    let widths: Vec<usize> = reports
+        .iter()
+        .enumerate()
+        .map(|(i, (label, _))| {
+            rows.iter()
+                .map(|(_, cells)| cells[i].len())
+                .chain([label.len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let render_row = |first: &str, cells: Vec<&str>| {
+        let mut line = format!("{:<width$}", first, width = first_width);
+        for (cell, width) in cells.iter().zip(&widths) {
+            line.push_str(&format!("  {:>width$}", cell, width = width));
+        }
+        line.push('\n');
+        line
+    };
+    let mut out = render_row("case", reports.iter().map(|(label, _)| *label).collect());
+    for (name, cells) in &rows {
+        out.push_str(&render_row(
+            name,
+            cells.iter().map(String::as_str).collect(),
+        ));
+    }
+    out
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    const CREATE_AND_PRINT: &str = r#"[
+        {"intent": "CreateArray", "parameters": {"size": 5}},
+        {"intent": "PrintArray"}
+    ]"#;
+
+    fn case(name: &str, expected: &str) -> EvalCase {
+        EvalCase {
+            name: name.to_string(),
+            source: format!("source of {}", name),
+            expected: canonical_form(&parse_to_intent_graph(expected).unwrap()),
+        }
+    }
+
+    /// Answers with `responses` in turn, recording the prompts it was sent.
+    fn scripted_model(
+        responses: &[&str],
+        prompts: &Arc<Mutex<Vec<String>>>,
+    ) -> impl FnMut(Prompt) -> std::future::Ready<Result<String, String>> {
+        let mut responses = responses.iter().map(|response| response.to_string());
+        let prompts = Arc::clone(prompts);
+        move |prompt| {
+            prompts.lock().unwrap().push(prompt.text);
+            std::future::ready(
+                responses
+                    .next()
+                    .ok_or_else(|| "out of responses".to_string()),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rates_count_correct_malformed_and_wrong_answers() {
+        // Arrange
+        let cases = [
+            case("create", CREATE_AND_PRINT),
+            case(
+                "sort",
+                r#"[{"intent": "CreateArray", "parameters": {"size": 3}}, {"intent": "SortArray", "parameters": {"order": "ascending"}}]"#,
+            ),
+        ];
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let model = scripted_model(
+            &[
+                // "create": correct, with different layout; malformed; correct.
+                "Here you go: [{\"parameters\": {\"size\": 5}, \"intent\": \"CreateArray\"}, {\"intent\": \"PrintArray\"}]",
+                "[{\"intent\": \"CreateArray\", \"parameters\": ",
+                CREATE_AND_PRINT,
+                // "sort": wrong size; sorts before creating; correct.
+                r#"[{"intent": "CreateArray", "parameters": {"size": 4}}, {"intent": "SortArray", "parameters": {"order": "ascending"}}]"#,
+                r#"[{"intent": "SortArray", "parameters": {"order": "ascending"}}, {"intent": "CreateArray", "parameters": {"size": 3}}]"#,
+                r#"[{"intent": "CreateArray", "parameters": {"size": 3}}, {"intent": "SortArray", "parameters": {"order": "ascending"}}]"#,
+            ],
+            &prompts,
+        );
+
+        // Act
+        let report = evaluate(&cases, 3, &PromptOptions::default(), model).await;
+
+        // Assert
+        let counts: Vec<(u32, u32, u32, u32)> = report
+            .cases
+            .iter()
+            .map(|case| (case.runs, case.parsed, case.valid, case.exact))
+            .collect();
+        assert_eq!(counts, vec![(3, 2, 2, 2), (3, 3, 2, 1)]);
+        assert_eq!(report.parse_success_rate, 5.0 / 6.0);
+        assert_eq!(report.semantic_validity_rate, 4.0 / 6.0);
+        assert_eq!(report.exact_match_rate, 3.0 / 6.0);
+        assert_eq!(report.cases[0].first_mismatch, None);
+        let mismatch = report.cases[1].first_mismatch.as_ref().unwrap();
+        assert!(mismatch.contains("-                    3,\n"));
+        assert!(mismatch.contains("+                    4,\n"));
+        assert_eq!(prompts.lock().unwrap().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_failed_requests_score_nothing() {
+        // Arrange
+        let cases = [case("create", CREATE_AND_PRINT)];
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let model = scripted_model(&[CREATE_AND_PRINT], &prompts);
+
+        // Act
+        let report = evaluate(&cases, 2, &PromptOptions::default(), model).await;
+
+        // Assert
+        assert_eq!(report.cases[0].parsed, 1);
+        assert_eq!(report.exact_match_rate, 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_prompt_instructions_reach_the_model() {
+        // Arrange
+        let cases = [case("create", CREATE_AND_PRINT)];
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let model = scripted_model(&[CREATE_AND_PRINT], &prompts);
+        let options = PromptOptions {
+            instructions: Some("Variant B.\n".to_string()),
+            ..PromptOptions::default()
+        };
+
+        // Act
+        evaluate(&cases, 1, &options, model).await;
+
+        // Assert
+        let prompts = prompts.lock().unwrap();
+        assert!(prompts[0].starts_with("Variant B.\n"));
+        assert!(prompts[0].ends_with("source of create"));
+    }
+
+    #[test]
+    fn test_table_puts_the_prompts_side_by_side() {
+        // Arrange
+        let cases = vec![CaseReport {
+            name: "create".to_string(),
+            runs: 4,
+            parsed: 4,
+            valid: 3,
+            exact: 2,
+            first_mismatch: None,
+        }];
+        let a = EvalReport::new(cases.clone());
+        let b = EvalReport::new(vec![CaseReport {
+            exact: 4,
+            ..cases[0].clone()
+        }]);
+
+        // Act
+        let table = render_table(&[("a.txt", &a), ("b.txt", &b)]);
+
+        // Assert
+        assert_eq!(
+            table,
+            "case                a.txt   b.txt\n\
+             create                2/4     4/4\n\
+             parse success      100.0%  100.0%\n\
+             semantic validity   75.0%   75.0%\n\
+             exact match         50.0%  100.0%\n"
+        );
+    }
+
+    #[test]
+    fn test_cases_load_sorted_and_need_their_expected_intents() {
+        // Arrange
+        let dir = std::env::temp_dir().join(format!("naldom-eval-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("b.nld"), "Print it.").unwrap();
+        fs::write(dir.join("b.json"), r#"[{"intent": "PrintArray"}]"#).unwrap();
+        fs::write(dir.join("a.nld"), "Create an array.").unwrap();
+        fs::write(dir.join("a.json"), CREATE_AND_PRINT).unwrap();
+        fs::write(dir.join("notes.md"), "Not a case.").unwrap();
+
+        // Act
+        let cases = load_cases(&dir).unwrap();
+        fs::write(dir.join("c.nld"), "Sort it.").unwrap();
+        let missing = load_cases(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Assert
+        let names: Vec<&str> = cases.iter().map(|case| case.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+        assert_eq!(cases[1].source, "Print it.");
+        assert!(missing.unwrap_err().contains("c.json"));
+    }
+}
@@ -0,0 +1,360 @@
+// crates/naldom-core/src/explain.rs
+
+//! Human-readable explanations of a validated IntentGraph (`--explain`).
+
+use crate::error_mode::ErrorMode;
+use crate::parser::RawIntent;
+use crate::semantic_analyzer::{DefaultedParam, ordinal_name};
+use naldom_ir::{Intent, PrintArrayParams};
+
+/// Describes what a single intent will do, in plain words.
+pub fn describe_intent(intent: &Intent) -> String {
+    let description = match intent {
+        Intent::CreateArray(params) => {
+            let contents = if params.values.is_empty() {
+                let size = params
+                    .size
+                    .as_ref()
+                    .map_or_else(|| "?".to_string(), |size| size.to_string());
+                if params.dtype.as_deref() == Some("int") {
+                    format!("{} random whole numbers", size)
+                } else {
+                    format!("{} random numbers", size)
+                }
+            } else {
+                let values: Vec<String> = params.values.iter().map(i64::to_string).collect();
+                format!("the values {}", values.join(", "))
+            };
+            match &params.name {
+                Some(name) => format!("create an array '{}' of {}", name, contents),
+                None => format!("create an array of {}", contents),
+            }
+        }
+        Intent::SortArray(params) => {
+            let sort = match &params.order {
+                Some(order) => format!("sort {} in {} order", target_description(intent), order),
+                None => format!("sort {}", target_description(intent)),
+            };
+            match &params.algorithm {
+                Some(algorithm) => format!("{} with {} sort", sort, algorithm),
+                None => sort,
+            }
+        }
+        Intent::PrintArray(params) => {
+            let in_full = if params.as_ref().is_some_and(|p| p.full == Some(true)) {
+                " in full"
+            } else {
+                ""
+            };
+            let target = if params.as_ref().is_some_and(PrintArrayParams::prints_all) {
+                "every array".to_string()
+            } else {
+                target_description(intent)
+            };
+            format!("print {}{}", target, in_full)
+        }
+        Intent::PrintHistogram(params) => match params.as_ref().and_then(|p| p.buckets) {
+            Some(buckets) => format!(
+                "print a histogram of {} with {} buckets",
+                target_description(intent),
+                buckets
+            ),
+            None => format!("print a histogram of {}", target_description(intent)),
+        },
+        Intent::DescribeArray(_) => {
+            format!("print a sentence describing {}", target_description(intent))
+        }
+        Intent::SumArray(_) => format!("print the sum of {}", target_description(intent)),
+        Intent::ReverseArray(_) => format!("reverse {}", target_description(intent)),
+        Intent::AppendToArray(params) => {
+            format!("append {} to {}", params.value, target_description(intent))
+        }
+        Intent::FillArray(params) => {
+            format!("fill {} with {}", target_description(intent), params.value)
+        }
+        Intent::ClearArray(_) => format!("empty {}", target_description(intent)),
+        Intent::ConcatArrays(params) => {
+            let joined = format!("'{}' and '{}'", params.first, params.second);
+            match &params.name {
+                Some(name) => format!("create an array '{}' joining {}", name, joined),
+                None => format!("create an array joining {}", joined),
+            }
+        }
+        Intent::CopyArray(params) => match &params.name {
+            Some(name) => format!(
+                "create an array '{}' copying {}",
+                name,
+                target_description(intent)
+            ),
+            None => format!("create a copy of {}", target_description(intent)),
+        },
+        Intent::MapArray(params) => {
+            let (operand, target) = (&params.operand, target_description(intent));
+            match params.operation.as_str() {
+                "add" => format!("add {} to each element of {}", operand, target),
+                "subtract" => format!("subtract {} from each element of {}", operand, target),
+                operation => format!("{} each element of {} by {}", operation, target, operand),
+            }
+        }
+        Intent::ConvertArray(params) => {
+            let to = match params.to.as_str() {
+                "int" => "whole numbers",
+                _ => "floating-point numbers",
+            };
+            format!(
+                "convert the elements of {} to {}",
+                target_description(intent),
+                to
+            )
+        }
+        Intent::SliceArray(params) if params.from_end => format!(
+            "keep the last {} elements of {}",
+            params.start,
+            target_description(intent)
+        ),
+        Intent::SliceArray(params) => match params.end {
+            Some(end) => format!(
+                "keep elements {} to {} of {}",
+                params.start,
+                end,
+                target_description(intent)
+            ),
+            None => format!(
+                "keep the elements of {} from {} on",
+                target_description(intent),
+                params.start
+            ),
+        },
+        Intent::MinArray(_) => {
+            format!("print the smallest value of {}", target_description(intent))
+        }
+        Intent::MaxArray(_) => format!("print the largest value of {}", target_description(intent)),
+        Intent::CountElements(_) => format!(
+            "print the number of elements of {}",
+            target_description(intent)
+        ),
+        Intent::AverageArray(_) => format!("print the average of {}", target_description(intent)),
+        Intent::MedianArray(_) => format!("print the median of {}", target_description(intent)),
+        Intent::ContainsValue(params) => format!(
+            "print whether {} contains {}",
+            target_description(intent),
+            params.value
+        ),
+        Intent::FindIndex(params) => format!(
+            "print where {} first holds {}, or -1 if it does not",
+            target_description(intent),
+            params.value
+        ),
+        Intent::Repeat(params) => {
+            let body: Vec<String> = params.body.iter().map(describe_intent).collect();
+            format!("repeat {} times: {}", params.count, body.join(", then "))
+        }
+        Intent::Conditional(params) => {
+            let branch = |body: &[Intent]| {
+                let steps: Vec<String> = body.iter().map(describe_intent).collect();
+                if steps.is_empty() {
+                    "do nothing".to_string()
+                } else {
+                    steps.join(", then ")
+                }
+            };
+            let mut text = format!(
+                "if {} {}: {}",
+                target_description(intent),
+                params.condition.replace('_', " "),
+                branch(&params.then_body)
+            );
+            if !params.else_body.is_empty() {
+                text.push_str(&format!("; otherwise: {}", branch(&params.else_body)));
+            }
+            text
+        }
+        Intent::Wait(params) => format!("wait for {} ms", params.duration_ms),
+        Intent::CreateMatrix(params) => format!(
+            "create a {}x{} matrix of random numbers",
+            params.rows, params.cols
+        ),
+        Intent::PrintMatrix(_) => "print the matrix".to_string(),
+        Intent::StartTimer(_) => "start a timer".to_string(),
+        Intent::PrintElapsed(_) => "print the time since the timer started".to_string(),
+        Intent::Exit(params) => match params.as_ref().and_then(|p| p.code) {
+            Some(code) if code != 0 => format!("stop the program with exit code {}", code),
+            _ => "stop the program".to_string(),
+        },
+        Intent::Note(params) => format!("note: \"{}\"", params.text),
+        Intent::SetVariable(params) => format!("let {} be {}", params.name, params.value),
+        Intent::UseProcedure(params) => format!("use procedure {}", params.name),
+    };
+    match intent.result_name() {
+        Some(name) => format!("{} and call it '{}'", description, name),
+        None => description,
+    }
+}
+
+/// "the array", "the 2nd array" once an ordinal is known, or the array's
+/// name when the model used one.
+fn target_description(intent: &Intent) -> String {
+    if let Some(name) = intent.target_name() {
+        return format!("'{}'", name);
+    }
+    match intent.target_ordinal() {
+        Some(ordinal) => format!("the {} array", ordinal_name(ordinal)),
+        None => "the array".to_string(),
+    }
+}
+
+/// Renders the whole plan, one numbered line per intent. Each parameter in
+/// `defaulted` follows its intent on a line of its own, e.g.
+/// `order: ascending (default)`.
+pub fn explain(intent_graph: &[Intent], defaulted: &[DefaultedParam]) -> String {
+    let mut lines = vec![format!("Plan ({} intents):", intent_graph.len())];
+    for (index, intent) in intent_graph.iter().enumerate() {
+        let head = format!("  {}. {:<12} ", index + 1, intent.name());
+        lines.push(format!("{}{}", head, describe_intent(intent)));
+        for param in defaulted.iter().filter(|p| p.intent_index == index) {
+            lines.push(format!(
+                "{}{}: {} (default)",
+                " ".repeat(head.len()),
+                param.name,
+                param.value
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Renders a plan that may hold intents this compiler does not know, e.g.
+/// from a newer schema, in the layout of [`explain`]. Known intents are
+/// described as written, before analysis; unknown ones by their parameters.
+pub fn explain_raw(raw_intents: &[RawIntent]) -> String {
+    let mut lines = vec![format!("Plan ({} intents):", raw_intents.len())];
+    for (index, raw) in raw_intents.iter().enumerate() {
+        let description = match raw.to_intent() {
+            Ok(intent) => describe_intent(&intent),
+            Err(error) if raw.is_known() => format!("cannot be read: {}", error),
+            Err(_) if raw.parameters.is_null() => "unknown to this version of Naldom".to_string(),
+            Err(_) => format!(
+                "unknown to this version of Naldom; parameters: {}",
+                raw.parameters
+            ),
+        };
+        lines.push(format!("  {}. {:<12} {}", index + 1, raw.name, description));
+    }
+    lines.join("\n")
+}
+
+/// The line `--explain` adds after the plan to say what happens when a
+/// runtime function fails.
+pub fn describe_error_mode(error_mode: ErrorMode) -> String {
+    match error_mode {
+        ErrorMode::Abort => "On a runtime error: stop the program (abort).".to_string(),
+        ErrorMode::Continue => {
+            "On a runtime error: warn and go on with an empty result (continue).".to_string()
+        }
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::semantic_analyzer::SemanticAnalyzer;
+    use naldom_ir::{CountArg, CreateArrayParams, NoteParams, SortArrayParams};
+
+    #[test]
+    fn test_explain_lists_every_intent_including_notes() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(10)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::Note(NoteParams {
+                text: "The numbers are only for show.".to_string(),
+            }),
+            Intent::PrintArray(None),
+        ];
+
+        // Act
+        let plan = explain(&intent_graph, &[]);
+
+        // Assert
+        assert_eq!(
+            plan,
+            [
+                "Plan (3 intents):",
+                "  1. CreateArray  create an array of 10 random numbers",
+                "  2. Note         note: \"The numbers are only for show.\"",
+                "  3. PrintArray   print the array",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_explain_marks_defaulted_parameters() {
+        // Arrange
+        let mut analyzer = SemanticAnalyzer::new();
+        let validated = analyzer
+            .analyze(&[
+                Intent::CreateArray(CreateArrayParams {
+                    size: Some(CountArg::Literal(10)),
+                    name: None,
+                    values: Vec::new(),
+                    dtype: None,
+                }),
+                Intent::SortArray(SortArrayParams {
+                    order: None,
+                    algorithm: None,
+                    target_ordinal: None,
+                    target: None,
+                }),
+            ])
+            .unwrap();
+
+        // Act
+        let plan = explain(&validated, analyzer.defaulted());
+
+        // Assert
+        assert_eq!(
+            plan,
+            [
+                "Plan (2 intents):",
+                "  1. CreateArray  create an array of 10 random numbers",
+                "  2. SortArray    sort the 1st array in ascending order",
+                "                  order: ascending (default)",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_explain_raw_names_unknown_intents_with_their_parameters() {
+        // Arrange
+        let raw = crate::parser::parse_raw_intents(
+            r#"[
+                {"intent": "CreateArray", "parameters": {"size": 10}},
+                {"intent": "GroupBy", "parameters": {"key": "parity"}},
+                {"intent": "PrintArray"}
+            ]"#,
+        )
+        .unwrap();
+
+        // Act
+        let plan = explain_raw(&raw);
+
+        // Assert
+        assert_eq!(
+            plan,
+            [
+                "Plan (3 intents):",
+                "  1. CreateArray  create an array of 10 random numbers",
+                "  2. GroupBy      unknown to this version of Naldom; parameters: {\"key\":\"parity\"}",
+                "  3. PrintArray   print the array",
+            ]
+            .join("\n")
+        );
+    }
+}
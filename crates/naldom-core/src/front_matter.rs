@@ -0,0 +1,187 @@
+// crates/naldom-core/src/front_matter.rs
+
+//! Per-file build defaults in a YAML front-matter block at the top of a source
+//! file:
+//!
+//! ```text
+//! ---
+//! naldom:
+//!   target: wasm
+//!   opt_level: 2
+//!   seed: 7
+//!   on_error: continue
+//!   post_process:
+//!     - wasm-opt -O2 {output} -o {output}
+//! ---
+//! ```
+//!
+//! Keys outside `naldom` are ignored, so other Markdown tools can share the
+//! block. Command-line flags override every value set here.
+
+use crate::error_mode::ErrorMode;
+use crate::targets::TargetKind;
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+
+/// The defaults a source file sets for its own build.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FrontMatter {
+    #[serde(default, deserialize_with = "deserialize_target")]
+    pub target: Option<TargetKind>,
+    pub opt_level: Option<u8>,
+    pub seed: Option<u64>,
+    /// What the program does when a runtime function fails.
+    pub on_error: Option<ErrorMode>,
+    /// Commands run on the artifact after a successful build.
+    pub post_process: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct Document {
+    #[serde(default)]
+    naldom: Option<FrontMatter>,
+}
+
+fn deserialize_target<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<TargetKind>, D::Error> {
+    let Some(name) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    name.parse().map(Some).map_err(serde::de::Error::custom)
+}
+
+/// A front-matter block that cannot be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontMatterError {
+    /// The 1-based line in the source file.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for FrontMatterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Front Matter Error: line {}: {}",
+            self.line, self.message
+        )
+    }
+}
+
+impl std::error::Error for FrontMatterError {}
+
+/// Splits `source` into its front matter and the program text after it. A
+/// source without a leading `---` line has no front matter.
+pub fn split_front_matter(source: &str) -> Result<(FrontMatter, &str), FrontMatterError> {
+    let mut lines = source.split_inclusive('\n');
+    if lines.next().map(str::trim_end) != Some("---") {
+        return Ok((FrontMatter::default(), source));
+    }
+
+    let yaml_start = source.find('\n').map_or(source.len(), |i| i + 1);
+    let mut offset = yaml_start;
+    for line in lines {
+        if line.trim_end() == "---" {
+            let yaml = &source[yaml_start..offset];
+            let body = &source[offset + line.len()..];
+            return Ok((parse_yaml(yaml)?, body));
+        }
+        offset += line.len();
+    }
+    Err(FrontMatterError {
+        line: 1,
+        message: "the front matter is never closed with a `---` line".to_string(),
+    })
+}
+
+/// Parses the text between the `---` lines, which starts on line 2 of the file.
+fn parse_yaml(yaml: &str) -> Result<FrontMatter, FrontMatterError> {
+    if yaml.trim().is_empty() {
+        return Ok(FrontMatter::default());
+    }
+    let document: Document = serde_yaml::from_str(yaml).map_err(|e| {
+        let mut message = e.to_string();
+        let line = match e.location() {
+            Some(location) => {
+                // serde_yaml appends the position within the block; report it
+                // as a file line instead.
+                let suffix = format!(" at line {} column {}", location.line(), location.column());
+                if let Some(stripped) = message.strip_suffix(&suffix) {
+                    message = stripped.to_string();
+                }
+                location.line() + 1
+            }
+            None => 1,
+        };
+        FrontMatterError { line, message }
+    })?;
+    Ok(document.naldom.unwrap_or_default())
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_front_matter_sets_defaults_and_is_stripped() {
+        // Arrange
+        let source = "---\ntitle: Sorting\nnaldom:\n  target: wasm\n  opt_level: 2\n  seed: 7\n  on_error: continue\n  post_process: [\"strip {output}\"]\n---\n:::naldom\nCreate an array.\n:::\n";
+
+        // Act
+        let (front_matter, body) = split_front_matter(source).unwrap();
+
+        // Assert
+        assert_eq!(
+            front_matter,
+            FrontMatter {
+                target: Some(TargetKind::Wasm),
+                opt_level: Some(2),
+                seed: Some(7),
+                on_error: Some(ErrorMode::Continue),
+                post_process: Some(vec!["strip {output}".to_string()]),
+            }
+        );
+        assert_eq!(body, ":::naldom\nCreate an array.\n:::\n");
+    }
+
+    #[test]
+    fn test_sources_without_front_matter_are_unchanged() {
+        let source = ":::naldom\n---\n:::";
+
+        assert_eq!(
+            split_front_matter(source).unwrap(),
+            (FrontMatter::default(), source)
+        );
+    }
+
+    #[test]
+    fn test_malformed_front_matter_reports_the_file_line() {
+        // Arrange
+        let unknown_key = "---\nnaldom:\n  target: native\n  optlevel: 2\n---\n";
+        let bad_target = "---\nnaldom:\n  target: riscv\n---\n";
+        let unclosed = "---\nnaldom:\n  seed: 1\n";
+
+        // Act
+        let unknown_key = split_front_matter(unknown_key).unwrap_err();
+        let bad_target = split_front_matter(bad_target).unwrap_err();
+        let unclosed = split_front_matter(unclosed).unwrap_err();
+
+        // Assert
+        assert_eq!(unknown_key.line, 4);
+        assert!(
+            unknown_key
+                .message
+                .starts_with("naldom: unknown field `optlevel`")
+        );
+        assert_eq!(bad_target.line, 3);
+        assert!(
+            bad_target
+                .to_string()
+                .starts_with("Front Matter Error: line 3: ")
+        );
+        assert_eq!(unclosed.line, 1);
+    }
+}
@@ -0,0 +1,244 @@
+// crates/naldom-core/src/fs_policy.rs
+
+//! The compile-time gate for intents that touch the filesystem, so compiling
+//! an untrusted document cannot produce a program that reads or writes
+//! anywhere it likes.
+//!
+//! By default a program may only use paths inside the directory of its
+//! source file. More directories can be granted (`--allow-fs <path>`), and
+//! file access can be denied outright (`--deny-fs`), which wins over every
+//! grant. Paths are judged statically on their literal text: they are
+//! normalized lexically, resolving `.` and `..` without touching the disk,
+//! and must then lie under a granted directory. A path that is not a literal
+//! is always rejected, since its value is unknown at compile time.
+//!
+//! The CLI builds the policy from its flags and hands it to the analyzer
+//! through [`Compiler::with_fs_policy`](crate::compiler::Compiler::with_fs_policy).
+//! No intent reads or writes files yet, so nothing is checked against it
+//! until the first one does.
+
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+/// A path as an intent gives it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathArg<'a> {
+    /// Written out in the program, e.g. `"data/input.txt"`.
+    Literal(&'a str),
+    /// Held by the named variable, so unknown until the program runs.
+    Variable(&'a str),
+}
+
+/// Why an intent may not use a path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsPolicyError {
+    /// File access is denied altogether.
+    Denied { path: String },
+    /// The path lies outside every granted directory.
+    OutsideAllowed { path: String, resolved: PathBuf },
+    /// The path is not a literal.
+    NotLiteral { variable: String },
+}
+
+impl fmt::Display for FsPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FsPolicyError::Denied { path } => write!(
+                f,
+                "Permission Error: the program uses the file '{}', but file access is denied (--deny-fs).",
+                path
+            ),
+            FsPolicyError::OutsideAllowed { path, resolved } => write!(
+                f,
+                "Permission Error: the file '{}' ({}) is outside the source file's directory; grant access with --allow-fs <path>.",
+                path,
+                resolved.display()
+            ),
+            FsPolicyError::NotLiteral { variable } => write!(
+                f,
+                "Permission Error: the path held by '{}' is only known at run time, so it cannot be checked; write the path out instead.",
+                variable
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FsPolicyError {}
+
+/// Which paths a program's file intents may use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsPolicy {
+    /// The directory relative paths start from: the source file's.
+    base_dir: PathBuf,
+    /// Normalized directories whose contents may be used, starting with
+    /// `base_dir`.
+    allowed: Vec<PathBuf>,
+    deny_all: bool,
+}
+
+impl FsPolicy {
+    /// Allows the contents of `source_dir`, the directory of the source file,
+    /// and nothing else.
+    pub fn new(source_dir: impl AsRef<Path>) -> Self {
+        let base_dir = normalize(source_dir.as_ref());
+        FsPolicy {
+            allowed: vec![base_dir.clone()],
+            base_dir,
+            deny_all: false,
+        }
+    }
+
+    /// Also allows the contents of `dir`, which is relative to the source
+    /// file's directory unless it is absolute.
+    pub fn with_allowed(mut self, dir: impl AsRef<Path>) -> Self {
+        let dir = normalize(&self.base_dir.join(dir));
+        self.allowed.push(dir);
+        self
+    }
+
+    /// Denies every path, whatever else is allowed.
+    pub fn with_deny_all(mut self, deny_all: bool) -> Self {
+        self.deny_all = deny_all;
+        self
+    }
+
+    /// Checks one path an intent uses.
+    pub fn check(&self, path: PathArg) -> Result<(), FsPolicyError> {
+        let literal = match path {
+            PathArg::Variable(variable) => {
+                return Err(FsPolicyError::NotLiteral {
+                    variable: variable.to_string(),
+                });
+            }
+            PathArg::Literal(literal) => literal,
+        };
+        if self.deny_all {
+            return Err(FsPolicyError::Denied {
+                path: literal.to_string(),
+            });
+        }
+        let resolved = normalize(&self.base_dir.join(literal));
+        if self.allowed.iter().any(|dir| resolved.starts_with(dir)) {
+            Ok(())
+        } else {
+            Err(FsPolicyError::OutsideAllowed {
+                path: literal.to_string(),
+                resolved,
+            })
+        }
+    }
+}
+
+/// Resolves `.` and `..` in `path` without touching the disk. `..` never
+/// climbs above the root.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                // `pop` keeps the root, so `/..` stays `/`.
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> FsPolicy {
+        FsPolicy::new("/home/ada/docs")
+    }
+
+    #[test]
+    fn test_paths_inside_the_source_directory_are_allowed() {
+        assert_eq!(policy().check(PathArg::Literal("data/input.txt")), Ok(()));
+        assert_eq!(policy().check(PathArg::Literal("./out.txt")), Ok(()));
+        assert_eq!(
+            policy().check(PathArg::Literal("/home/ada/docs/notes/a.txt")),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_absolute_paths_elsewhere_are_denied() {
+        // Act
+        let error = policy().check(PathArg::Literal("/etc/passwd")).unwrap_err();
+
+        // Assert
+        assert_eq!(
+            error,
+            FsPolicyError::OutsideAllowed {
+                path: "/etc/passwd".to_string(),
+                resolved: PathBuf::from("/etc/passwd"),
+            }
+        );
+        // A shared prefix of characters is not a shared directory.
+        assert!(
+            policy()
+                .check(PathArg::Literal("/home/ada/docs2/a.txt"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_traversal_out_of_the_directory_is_denied() {
+        // Act
+        let escape = policy().check(PathArg::Literal("data/../../secrets.txt"));
+        let round_trip = policy().check(PathArg::Literal("../docs/data/input.txt"));
+        let past_root = policy().check(PathArg::Literal("../../../../../etc/passwd"));
+
+        // Assert
+        assert!(matches!(
+            escape,
+            Err(FsPolicyError::OutsideAllowed { resolved, .. })
+                if resolved == Path::new("/home/ada/secrets.txt")
+        ));
+        assert_eq!(round_trip, Ok(()));
+        assert!(past_root.is_err());
+    }
+
+    #[test]
+    fn test_allow_grants_and_deny_overrides_everything() {
+        // Arrange
+        let granted = policy()
+            .with_allowed("/srv/shared")
+            .with_allowed("../assets");
+        let denied = granted.clone().with_deny_all(true);
+
+        // Act & Assert
+        assert_eq!(granted.check(PathArg::Literal("/srv/shared/a.csv")), Ok(()));
+        assert_eq!(
+            granted.check(PathArg::Literal("../assets/logo.txt")),
+            Ok(())
+        );
+        assert!(granted.check(PathArg::Literal("/srv/other/a.csv")).is_err());
+        assert!(matches!(
+            denied.check(PathArg::Literal("data/input.txt")),
+            Err(FsPolicyError::Denied { .. })
+        ));
+    }
+
+    #[test]
+    fn test_non_literal_paths_are_always_rejected() {
+        // Act
+        let error = policy()
+            .with_allowed("/")
+            .check(PathArg::Variable("report_path"))
+            .unwrap_err();
+
+        // Assert
+        assert!(
+            error
+                .to_string()
+                .starts_with("Permission Error: the path held by 'report_path'")
+        );
+    }
+}
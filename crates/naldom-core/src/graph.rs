@@ -0,0 +1,560 @@
+// crates/naldom-core/src/graph.rs
+
+//! The validated IntentGraph as a diagram (`--emit intent-graph`), for
+//! reviewing what the model planned before building it.
+//!
+//! Every intent is a node labelled with its position, its name and its key
+//! parameters; the intents inside a `Repeat` or `Conditional` hang off their
+//! block by dotted edges. Solid edges are data dependencies: they run from the
+//! intent that created or last changed an array to every intent that uses it.
+//! Dashed edges are control dependencies: they chain the intents whose
+//! effects are visible in order, i.e. everything that prints and every wait.
+//! The entry node, where the program starts, is highlighted.
+
+use crate::parser::RawIntent;
+use crate::race_check::{Access, array_accesses};
+use naldom_ir::Intent;
+use std::collections::HashMap;
+
+/// Notes longer than this are shortened in their label.
+const NOTE_LABEL_CHARS: usize = 24;
+
+/// One intent of the diagram.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphNode {
+    /// `1`, `2`, ... at the top level and `3.1`, `3.2`, ... inside intent 3.
+    pub position: String,
+    pub name: String,
+    /// Key parameters, e.g. `order: descending`, one per label line.
+    pub details: Vec<String>,
+}
+
+/// Why one node follows another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// The target uses the array (counting from 1) the source produced.
+    Data(u32),
+    /// Both have visible effects, and the source's come first.
+    Control,
+    /// The target is in the source's `body`, `then` or `else` block.
+    Block(&'static str),
+}
+
+/// A dependency between two nodes, given as indices into
+/// [`DependencyGraph::nodes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+/// The nodes and edges of a program, in program order. Node 0 is the entry.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DependencyGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+impl DependencyGraph {
+    /// Builds the diagram of a validated IntentGraph.
+    pub fn new(intents: &[Intent]) -> DependencyGraph {
+        let mut builder = Builder::default();
+        builder.add_block(intents, None, "", 1);
+        builder.graph
+    }
+
+    /// Builds the diagram of a plan that may hold intents this compiler does
+    /// not know, e.g. from a newer schema. An unknown intent gets a node but
+    /// no data edges, since which arrays it uses is unknown; it may have
+    /// visible effects, so it stays in the chain of control edges.
+    pub fn from_raw(raw_intents: &[RawIntent]) -> DependencyGraph {
+        let mut builder = Builder::default();
+        for (index, raw) in raw_intents.iter().enumerate() {
+            match raw.to_intent() {
+                Ok(intent) => builder.add_block(std::slice::from_ref(&intent), None, "", index + 1),
+                Err(_) => builder.add_unknown(raw, index + 1),
+            }
+        }
+        builder.graph
+    }
+
+    /// Renders the diagram in Graphviz DOT.
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec![
+            "digraph intents {".to_string(),
+            "  node [shape=box];".to_string(),
+        ];
+        for (index, node) in self.nodes.iter().enumerate() {
+            let label = dot_escape(&node.label_lines().join("\n"));
+            let entry = if index == 0 {
+                ", style=\"bold,filled\", fillcolor=lightyellow"
+            } else {
+                ""
+            };
+            lines.push(format!("  n{} [label=\"{}\"{}];", index, label, entry));
+        }
+        for edge in &self.edges {
+            let attributes = match &edge.kind {
+                EdgeKind::Data(ordinal) => format!("label=\"array {}\"", ordinal),
+                EdgeKind::Control => "style=dashed".to_string(),
+                EdgeKind::Block(block) => format!("style=dotted, label=\"{}\"", block),
+            };
+            lines.push(format!(
+                "  n{} -> n{} [{}];",
+                edge.from, edge.to, attributes
+            ));
+        }
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+
+    /// Renders the diagram as a Mermaid flowchart.
+    pub fn to_mermaid(&self) -> String {
+        let mut lines = vec!["flowchart TD".to_string()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            let label: Vec<String> = node
+                .label_lines()
+                .iter()
+                .map(|l| mermaid_escape(l))
+                .collect();
+            lines.push(format!("  n{}[\"{}\"]", index, label.join("<br/>")));
+        }
+        for edge in &self.edges {
+            let arrow = match &edge.kind {
+                EdgeKind::Data(ordinal) => format!("-->|array {}|", ordinal),
+                EdgeKind::Control => "-.->".to_string(),
+                EdgeKind::Block(block) => format!("-.-|{}|", block),
+            };
+            lines.push(format!("  n{} {} n{}", edge.from, arrow, edge.to));
+        }
+        if !self.nodes.is_empty() {
+            lines.push("  classDef entry fill:#ffffe0,stroke-width:3px".to_string());
+            lines.push("  class n0 entry".to_string());
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+impl GraphNode {
+    fn label_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("{}. {}", self.position, self.name)];
+        lines.extend(self.details.iter().cloned());
+        lines
+    }
+}
+
+/// Renders a validated IntentGraph in Graphviz DOT.
+pub fn intent_graph_to_dot(intents: &[Intent]) -> String {
+    DependencyGraph::new(intents).to_dot()
+}
+
+/// Renders a validated IntentGraph as a Mermaid flowchart.
+pub fn intent_graph_to_mermaid(intents: &[Intent]) -> String {
+    DependencyGraph::new(intents).to_mermaid()
+}
+
+#[derive(Default)]
+struct Builder {
+    graph: DependencyGraph,
+    /// The arrays created so far, as `array_accesses` counts them.
+    arrays: u32,
+    /// The node that created or last changed each array.
+    last_writer: HashMap<u32, usize>,
+    /// The last node with a visible effect.
+    last_effect: Option<usize>,
+}
+
+impl Builder {
+    /// Adds `intents` at the top level, or as `parent`'s block of that name.
+    /// Positions continue from `first`, so the `else` block of intent 3
+    /// numbers on from its `then` block.
+    fn add_block(
+        &mut self,
+        intents: &[Intent],
+        parent: Option<(usize, &'static str)>,
+        prefix: &str,
+        first: usize,
+    ) {
+        for (index, intent) in intents.iter().enumerate() {
+            let position = format!("{}{}", prefix, first + index);
+            let node = self.graph.nodes.len();
+            self.graph.nodes.push(GraphNode {
+                position: position.clone(),
+                name: intent.name().to_string(),
+                details: details(intent),
+            });
+            if let Some((parent, block)) = parent {
+                self.graph.edges.push(GraphEdge {
+                    from: parent,
+                    to: node,
+                    kind: EdgeKind::Block(block),
+                });
+            }
+            self.add_dependencies(intent, node);
+
+            let prefix = format!("{}.", position);
+            match intent {
+                Intent::Repeat(params) => {
+                    self.add_block(&params.body, Some((node, "body")), &prefix, 1);
+                }
+                // Arrays created in a branch end with it.
+                Intent::Conditional(params) => {
+                    let arrays = self.arrays;
+                    self.add_block(&params.then_body, Some((node, "then")), &prefix, 1);
+                    self.arrays = arrays;
+                    let first = params.then_body.len() + 1;
+                    self.add_block(&params.else_body, Some((node, "else")), &prefix, first);
+                    self.arrays = arrays;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Adds a top-level node for an intent that cannot be typed.
+    fn add_unknown(&mut self, raw: &RawIntent, position: usize) {
+        let node = self.graph.nodes.len();
+        self.graph.nodes.push(GraphNode {
+            position: position.to_string(),
+            name: raw.name.clone(),
+            details: vec!["unknown intent".to_string()],
+        });
+        if let Some(previous) = self.last_effect {
+            self.graph.edges.push(GraphEdge {
+                from: previous,
+                to: node,
+                kind: EdgeKind::Control,
+            });
+        }
+        self.last_effect = Some(node);
+    }
+
+    /// Adds the edges into `node` from the intents it depends on.
+    fn add_dependencies(&mut self, intent: &Intent, node: usize) {
+        let accesses = match intent {
+            // The blocks' intents have nodes of their own; a conditional
+            // itself only reads the array it tests.
+            Intent::Repeat(_) => Vec::new(),
+            Intent::Conditional(_) => {
+                let ordinal = intent.target_ordinal().unwrap_or(self.arrays);
+                (1..=self.arrays)
+                    .contains(&ordinal)
+                    .then_some((ordinal, Access::Read))
+                    .into_iter()
+                    .collect()
+            }
+            _ => array_accesses(intent, &mut self.arrays),
+        };
+        for (ordinal, access) in accesses {
+            let creates = matches!(
+                intent,
+                Intent::CreateArray(_) | Intent::ConcatArrays(_) | Intent::CopyArray(_)
+            );
+            if !(creates && access == Access::Write)
+                && let Some(&writer) = self.last_writer.get(&ordinal)
+            {
+                self.graph.edges.push(GraphEdge {
+                    from: writer,
+                    to: node,
+                    kind: EdgeKind::Data(ordinal),
+                });
+            }
+            if access == Access::Write {
+                self.last_writer.insert(ordinal, node);
+            }
+        }
+
+        if has_visible_effect(intent) {
+            if let Some(previous) = self.last_effect {
+                self.graph.edges.push(GraphEdge {
+                    from: previous,
+                    to: node,
+                    kind: EdgeKind::Control,
+                });
+            }
+            self.last_effect = Some(node);
+        }
+    }
+}
+
+/// Whether the intent prints, waits, reads the clock or ends the program, so
+/// moving it would change what the user sees. Matrices have no data edges, so
+/// creating one counts too: it keeps the matrix ahead of the `PrintMatrix`
+/// that shows it.
+fn has_visible_effect(intent: &Intent) -> bool {
+    matches!(
+        intent,
+        Intent::PrintArray(_)
+            | Intent::SumArray(_)
+            | Intent::MinArray(_)
+            | Intent::MaxArray(_)
+            | Intent::CountElements(_)
+            | Intent::AverageArray(_)
+            | Intent::MedianArray(_)
+            | Intent::ContainsValue(_)
+            | Intent::FindIndex(_)
+            | Intent::PrintHistogram(_)
+            | Intent::DescribeArray(_)
+            | Intent::Wait(_)
+            | Intent::StartTimer(_)
+            | Intent::PrintElapsed(_)
+            | Intent::Exit(_)
+            | Intent::CreateMatrix(_)
+            | Intent::PrintMatrix(_)
+    )
+}
+
+/// The parameters shown under an intent's name. Array references are left
+/// out; the data edges show them.
+fn details(intent: &Intent) -> Vec<String> {
+    match intent {
+        Intent::CreateArray(params) => {
+            let mut details = vec![if params.values.is_empty() {
+                let size = params
+                    .size
+                    .as_ref()
+                    .map_or("?".to_string(), |s| s.to_string());
+                format!("size: {}", size)
+            } else {
+                let values: Vec<String> = params.values.iter().map(i64::to_string).collect();
+                format!("values: {}", values.join(", "))
+            }];
+            details.extend(params.name.as_ref().map(|name| format!("name: {}", name)));
+            // Float is the default; only whole-number arrays are called out.
+            if params.dtype.as_deref() == Some("int") {
+                details.push("dtype: int".to_string());
+            }
+            details
+        }
+        Intent::SortArray(params) => params
+            .order
+            .iter()
+            .map(|order| format!("order: {}", order))
+            .chain(
+                params
+                    .algorithm
+                    .iter()
+                    .map(|algorithm| format!("algorithm: {}", algorithm)),
+            )
+            .collect(),
+        Intent::PrintArray(params) => params
+            .as_ref()
+            .filter(|p| p.full == Some(true))
+            .map(|_| "full".to_string())
+            .into_iter()
+            .collect(),
+        Intent::AppendToArray(params) => vec![format!("value: {}", params.value)],
+        Intent::FillArray(params) => vec![format!("value: {}", params.value)],
+        Intent::ContainsValue(params) => vec![format!("value: {}", params.value)],
+        Intent::FindIndex(params) => vec![format!("value: {}", params.value)],
+        Intent::PrintHistogram(params) => params
+            .as_ref()
+            .and_then(|p| p.buckets)
+            .map(|buckets| format!("buckets: {}", buckets))
+            .into_iter()
+            .collect(),
+        Intent::ConcatArrays(params) => {
+            let mut details = vec![format!("{} + {}", params.first, params.second)];
+            details.extend(params.name.as_ref().map(|name| format!("name: {}", name)));
+            details
+        }
+        Intent::CopyArray(params) => params
+            .name
+            .iter()
+            .map(|name| format!("name: {}", name))
+            .collect(),
+        Intent::MapArray(params) => vec![format!("{} {}", params.operation, params.operand)],
+        Intent::ConvertArray(params) => vec![format!("to: {}", params.to)],
+        Intent::SliceArray(params) if params.from_end => vec![format!("last {}", params.start)],
+        Intent::SliceArray(params) => match params.end {
+            Some(end) => vec![format!("{}..{}", params.start, end)],
+            None => vec![format!("{}..", params.start)],
+        },
+        Intent::Repeat(params) => vec![format!("count: {}", params.count)],
+        Intent::Conditional(params) => vec![format!("condition: {}", params.condition)],
+        Intent::Wait(params) => vec![format!("{} ms", params.duration_ms)],
+        Intent::Exit(params) => params
+            .as_ref()
+            .and_then(|p| p.code)
+            .map(|code| format!("code: {}", code))
+            .into_iter()
+            .collect(),
+        Intent::CreateMatrix(params) => vec![format!("{} x {}", params.rows, params.cols)],
+        Intent::StartTimer(_)
+        | Intent::PrintElapsed(_)
+        | Intent::PrintMatrix(_)
+        | Intent::DescribeArray(_) => Vec::new(),
+        Intent::Note(params) => {
+            let mut text: String = params.text.chars().take(NOTE_LABEL_CHARS).collect();
+            if params.text.chars().count() > NOTE_LABEL_CHARS {
+                text.push('…');
+            }
+            vec![format!("\"{}\"", text)]
+        }
+        Intent::SetVariable(params) => vec![format!("{} = {}", params.name, params.value)],
+        Intent::UseProcedure(params) => vec![format!("procedure: {}", params.name)],
+        Intent::SumArray(_)
+        | Intent::MinArray(_)
+        | Intent::MaxArray(_)
+        | Intent::CountElements(_) => intent
+            .result_name()
+            .map(|name| format!("name: {}", name))
+            .into_iter()
+            .collect(),
+        Intent::ReverseArray(_)
+        | Intent::ClearArray(_)
+        | Intent::AverageArray(_)
+        | Intent::MedianArray(_) => Vec::new(),
+    }
+}
+
+/// Escapes a DOT string; line breaks become `\n`.
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Escapes a Mermaid label, which has no backslash escapes of its own.
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_to_intent_graph;
+    use crate::semantic_analyzer::SemanticAnalyzer;
+
+    fn validated(json: &str) -> Vec<Intent> {
+        let graph = parse_to_intent_graph(json).unwrap();
+        SemanticAnalyzer::new().analyze(&graph).unwrap()
+    }
+
+    /// Two arrays: the first is sorted and printed, the second printed after
+    /// a wait.
+    fn two_arrays() -> Vec<Intent> {
+        validated(
+            r#"[
+                {"intent": "CreateArray", "parameters": {"size": 8}},
+                {"intent": "CreateArray", "parameters": {"values": [3, 1, 4], "name": "small"}},
+                {"intent": "SortArray", "parameters": {"order": "descending", "targetOrdinal": 1}},
+                {"intent": "PrintArray", "parameters": {"targetOrdinal": 1}},
+                {"intent": "Wait", "parameters": {"durationMs": 500}},
+                {"intent": "PrintArray", "parameters": {"target": "small"}}
+            ]"#,
+        )
+    }
+
+    #[test]
+    fn test_dot_links_each_array_to_its_users_and_chains_effects() {
+        // Act
+        let dot = intent_graph_to_dot(&two_arrays());
+
+        // Assert
+        assert_eq!(
+            dot,
+            r#"digraph intents {
+  node [shape=box];
+  n0 [label="1. CreateArray\nsize: 8", style="bold,filled", fillcolor=lightyellow];
+  n1 [label="2. CreateArray\nvalues: 3, 1, 4\nname: small"];
+  n2 [label="3. SortArray\norder: descending"];
+  n3 [label="4. PrintArray"];
+  n4 [label="5. Wait\n500 ms"];
+  n5 [label="6. PrintArray"];
+  n0 -> n2 [label="array 1"];
+  n2 -> n3 [label="array 1"];
+  n3 -> n4 [style=dashed];
+  n1 -> n5 [label="array 2"];
+  n4 -> n5 [style=dashed];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_mermaid_has_the_same_nodes_and_edges() {
+        // Act
+        let mermaid = intent_graph_to_mermaid(&two_arrays());
+
+        // Assert
+        let lines: Vec<&str> = mermaid.lines().collect();
+        assert_eq!(lines[0], "flowchart TD");
+        assert_eq!(lines[1], "  n0[\"1. CreateArray<br/>size: 8\"]");
+        assert!(lines.contains(&"  n0 -->|array 1| n2"));
+        assert!(lines.contains(&"  n3 -.-> n4"));
+        assert_eq!(lines.last(), Some(&"  class n0 entry"));
+        assert_eq!(lines.iter().filter(|l| l.contains("-->|")).count(), 3);
+    }
+
+    #[test]
+    fn test_block_intents_hang_off_their_block() {
+        // Arrange
+        let intents = validated(
+            r#"[
+                {"intent": "CreateArray", "parameters": {"size": 4}},
+                {"intent": "Conditional", "parameters": {
+                    "condition": "is_sorted",
+                    "then": [{"intent": "PrintArray"}],
+                    "else": [{"intent": "SortArray", "parameters": {}}, {"intent": "PrintArray"}]
+                }}
+            ]"#,
+        );
+
+        // Act
+        let graph = DependencyGraph::new(&intents);
+
+        // Assert
+        let positions: Vec<&str> = graph.nodes.iter().map(|n| n.position.as_str()).collect();
+        assert_eq!(positions, ["1", "2", "2.1", "2.2", "2.3"]);
+        let edge = |from, to, kind| GraphEdge { from, to, kind };
+        assert!(graph.edges.contains(&edge(0, 1, EdgeKind::Data(1))));
+        assert!(graph.edges.contains(&edge(1, 4, EdgeKind::Block("else"))));
+        assert!(graph.edges.contains(&edge(3, 4, EdgeKind::Data(1))));
+        assert!(graph.edges.contains(&edge(2, 4, EdgeKind::Control)));
+        assert!(
+            DependencyGraph::new(&[])
+                .to_dot()
+                .ends_with("shape=box];\n}\n")
+        );
+    }
+
+    #[test]
+    fn test_unknown_intents_get_a_node_in_the_chain_of_effects() {
+        // Arrange
+        let raw = crate::parser::parse_raw_intents(
+            r#"[
+                {"intent": "CreateArray", "parameters": {"size": 4}},
+                {"intent": "PrintArray"},
+                {"intent": "GroupBy", "parameters": {"key": "parity"}},
+                {"intent": "PrintArray"}
+            ]"#,
+        )
+        .unwrap();
+
+        // Act
+        let graph = DependencyGraph::from_raw(&raw);
+
+        // Assert
+        assert_eq!(
+            graph.nodes[2],
+            GraphNode {
+                position: "3".to_string(),
+                name: "GroupBy".to_string(),
+                details: vec!["unknown intent".to_string()],
+            }
+        );
+        let edge = |from, to, kind| GraphEdge { from, to, kind };
+        assert!(graph.edges.contains(&edge(0, 3, EdgeKind::Data(1))));
+        assert!(graph.edges.contains(&edge(1, 2, EdgeKind::Control)));
+        assert!(graph.edges.contains(&edge(2, 3, EdgeKind::Control)));
+        assert!(
+            graph
+                .edges
+                .iter()
+                .all(|edge| edge.to != 2 || edge.kind == EdgeKind::Control)
+        );
+    }
+}
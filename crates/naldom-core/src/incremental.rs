@@ -0,0 +1,318 @@
+// crates/naldom-core/src/incremental.rs
+
+//! Incremental analysis and lowering, for editors and watch loops that
+//! recompile a document on every change.
+//!
+//! Editing usually touches the end of a document, so most of a new IntentGraph
+//! equals the previous one. [`IncrementalPipeline`] keeps, for every intent
+//! of the last graph, the analyzer and lowering state from just before it,
+//! along with what it produced. An update finds the longest prefix the two
+//! graphs share, goes back to the state at the first intent that differs, and
+//! analyzes and lowers only from there; the prefix' results are reused. The
+//! result is the same as [`SemanticAnalyzer::analyze`] followed by
+//! [`LoweringContext::lower`] on the whole graph.
+//!
+//! Intents are compared in a canonical form, their `Debug` rendering, so a
+//! reformatted JSON answer still counts as unchanged.
+
+use crate::lowering::{LoweringContext, LoweringSnapshot};
+use crate::semantic_analyzer::{AnalyzerSnapshot, DefaultedParam, SemanticAnalyzer, SemanticError};
+use naldom_ir::{HLProgram, HLStatement, Intent};
+
+/// What one intent of the last graph left behind.
+struct Checkpoint {
+    /// The intent, in canonical form.
+    canonical: String,
+    /// The state just before the intent.
+    analyzer: AnalyzerSnapshot,
+    lowering: LoweringSnapshot,
+    validated: Intent,
+    statements: Vec<HLStatement>,
+    warnings: Vec<String>,
+    defaulted: Vec<DefaultedParam>,
+}
+
+/// How much work the updates so far have done.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IncrementalStats {
+    /// Intents run through the analyzer.
+    pub analyzed: u64,
+    /// Intents run through lowering.
+    pub lowered: u64,
+    /// Intents whose earlier results were reused.
+    pub reused: u64,
+    /// Updates that started over from the first intent.
+    pub full_rebuilds: u64,
+}
+
+/// The result of an update: what a full compilation of the graph gives.
+#[derive(Debug, Clone)]
+pub struct IncrementalOutput {
+    pub validated_intent_graph: Vec<Intent>,
+    pub hl_program: HLProgram,
+    pub warnings: Vec<String>,
+    pub defaulted: Vec<DefaultedParam>,
+}
+
+/// Analyzes and lowers successive versions of one IntentGraph, redoing only
+/// the intents from the first change on. Procedures must be expanded before,
+/// as for the analyzer.
+pub struct IncrementalPipeline {
+    analyzer: SemanticAnalyzer,
+    lowering: LoweringContext,
+    /// One per intent of the last graph, as far as it analyzed. The analyzer
+    /// and lowering are always in the state after the last of them.
+    checkpoints: Vec<Checkpoint>,
+    stats: IncrementalStats,
+}
+
+impl IncrementalPipeline {
+    /// Creates a pipeline around a configured analyzer and lowering context.
+    /// Both are reset first.
+    pub fn new(mut analyzer: SemanticAnalyzer, mut lowering: LoweringContext) -> Self {
+        analyzer.reset();
+        lowering.reset();
+        IncrementalPipeline {
+            analyzer,
+            lowering,
+            checkpoints: Vec::new(),
+            stats: IncrementalStats::default(),
+        }
+    }
+
+    /// The work done by every update so far.
+    pub fn stats(&self) -> IncrementalStats {
+        self.stats
+    }
+
+    /// Forgets the last graph, so the next update starts over.
+    pub fn invalidate(&mut self) {
+        self.checkpoints.clear();
+        self.analyzer.reset();
+        self.lowering.reset();
+    }
+
+    /// Analyzes and lowers `intent_graph`, reusing the results for the
+    /// intents it shares with the start of the last graph. After an error
+    /// the intents before the offending one are still kept for reuse.
+    pub fn update(&mut self, intent_graph: &[Intent]) -> Result<IncrementalOutput, SemanticError> {
+        let canonical: Vec<String> = intent_graph.iter().map(canonical_form).collect();
+        let prefix = self
+            .checkpoints
+            .iter()
+            .zip(&canonical)
+            .take_while(|(checkpoint, canonical)| checkpoint.canonical == **canonical)
+            .count();
+        if prefix == 0 {
+            self.invalidate();
+            self.stats.full_rebuilds += 1;
+        } else if let Some(checkpoint) = self.checkpoints.get(prefix) {
+            self.analyzer.restore(&checkpoint.analyzer);
+            self.lowering.restore(&checkpoint.lowering);
+        }
+        self.checkpoints.truncate(prefix);
+        self.stats.reused += prefix as u64;
+
+        for (index, intent) in intent_graph.iter().enumerate().skip(prefix) {
+            let analyzer = self.analyzer.snapshot();
+            let lowering = self.lowering.snapshot();
+            // Clears the findings, leaving only this intent's afterwards.
+            self.analyzer.restore(&analyzer);
+            self.stats.analyzed += 1;
+            let validated = match self.analyzer.analyze_at(index, intent) {
+                Ok(validated) => validated,
+                Err(error) => {
+                    self.analyzer.restore(&analyzer);
+                    return Err(error);
+                }
+            };
+            self.stats.lowered += 1;
+            let statements = self.lowering.lower_at(index, &validated);
+            self.checkpoints.push(Checkpoint {
+                canonical: canonical[index].clone(),
+                analyzer,
+                lowering,
+                validated,
+                statements,
+                warnings: self.analyzer.warnings().to_vec(),
+                defaulted: self.analyzer.defaulted().to_vec(),
+            });
+        }
+
+        let mut statements = self.lowering.prelude(intent_graph.len());
+        let mut output = IncrementalOutput {
+            validated_intent_graph: Vec::with_capacity(self.checkpoints.len()),
+            hl_program: HLProgram {
+                statements: Vec::new(),
+            },
+            warnings: Vec::new(),
+            defaulted: Vec::new(),
+        };
+        for checkpoint in &self.checkpoints {
+            output
+                .validated_intent_graph
+                .push(checkpoint.validated.clone());
+            statements.extend_from_slice(&checkpoint.statements);
+            output.warnings.extend_from_slice(&checkpoint.warnings);
+            output.defaulted.extend_from_slice(&checkpoint.defaulted);
+        }
+        output.hl_program.statements = statements;
+        Ok(output)
+    }
+}
+
+/// The form in which intents are compared.
+fn canonical_form(intent: &Intent) -> String {
+    format!("{:?}", intent)
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use naldom_ir::{
+        AppendParams, CountArg, CreateArrayParams, PrintArrayParams, SetVariableParams,
+    };
+
+    fn create(size: u32) -> Intent {
+        Intent::CreateArray(CreateArrayParams {
+            size: Some(CountArg::Literal(size)),
+            name: None,
+            values: Vec::new(),
+            dtype: None,
+        })
+    }
+
+    fn append(value: i64) -> Intent {
+        Intent::AppendToArray(AppendParams {
+            value,
+            target_ordinal: None,
+        })
+    }
+
+    /// A program of `len` intents: an array, then more arrays, appends and prints.
+    fn program(len: usize) -> Vec<Intent> {
+        let mut intents = vec![create(5)];
+        intents.extend((1..len as i64).map(|i| match i % 3 {
+            0 => Intent::PrintArray(Some(PrintArrayParams::default())),
+            1 => create(2),
+            _ => append(i),
+        }));
+        intents
+    }
+
+    fn pipeline() -> IncrementalPipeline {
+        IncrementalPipeline::new(
+            SemanticAnalyzer::new(),
+            LoweringContext::new()
+                .with_seed(Some(7))
+                .with_profiling(true),
+        )
+    }
+
+    /// Compiles `intents` from scratch, in the shape of an update's output.
+    fn full_compile(intents: &[Intent]) -> (String, HLProgram, Vec<String>, Vec<DefaultedParam>) {
+        let mut analyzer = SemanticAnalyzer::new();
+        let validated = analyzer.analyze(intents).unwrap();
+        let hl_program = LoweringContext::new()
+            .with_seed(Some(7))
+            .with_profiling(true)
+            .lower(&validated);
+        (
+            format!("{:?}", validated),
+            hl_program,
+            analyzer.warnings().to_vec(),
+            analyzer.defaulted().to_vec(),
+        )
+    }
+
+    fn assert_matches_full_compile(output: &IncrementalOutput, intents: &[Intent]) {
+        let (validated, hl_program, warnings, defaulted) = full_compile(intents);
+        assert_eq!(format!("{:?}", output.validated_intent_graph), validated);
+        assert_eq!(output.hl_program, hl_program);
+        assert_eq!(output.warnings, warnings);
+        assert_eq!(output.defaulted, defaulted);
+    }
+
+    #[test]
+    fn test_appending_one_intent_reanalyzes_only_that_intent() {
+        // Arrange
+        let mut pipeline = pipeline();
+        let mut intents = program(500);
+        pipeline.update(&intents).unwrap();
+        let before = pipeline.stats();
+        intents.push(append(42));
+
+        // Act
+        let output = pipeline.update(&intents).unwrap();
+
+        // Assert
+        let after = pipeline.stats();
+        assert_eq!(after.analyzed - before.analyzed, 1);
+        assert_eq!(after.lowered - before.lowered, 1);
+        assert_eq!(after.reused - before.reused, 500);
+        assert_eq!(after.full_rebuilds, 1);
+        assert_matches_full_compile(&output, &intents);
+    }
+
+    #[test]
+    fn test_an_edit_in_the_middle_redoes_the_rest() {
+        // Arrange
+        let mut pipeline = pipeline();
+        let mut intents = program(20);
+        pipeline.update(&intents).unwrap();
+        let before = pipeline.stats();
+        intents[15] = Intent::SetVariable(SetVariableParams {
+            name: "limit".to_string(),
+            value: 3,
+        });
+
+        // Act
+        let output = pipeline.update(&intents).unwrap();
+
+        // Assert
+        assert_eq!(pipeline.stats().analyzed - before.analyzed, 5);
+        assert_matches_full_compile(&output, &intents);
+    }
+
+    #[test]
+    fn test_a_change_to_the_first_intent_rebuilds_everything() {
+        // Arrange
+        let mut pipeline = pipeline();
+        let mut intents = program(10);
+        pipeline.update(&intents).unwrap();
+        intents[0] = create(8);
+
+        // Act
+        let output = pipeline.update(&intents).unwrap();
+
+        // Assert
+        let stats = pipeline.stats();
+        assert_eq!(stats.full_rebuilds, 2);
+        assert_eq!(stats.analyzed, 20);
+        assert_matches_full_compile(&output, &intents);
+    }
+
+    #[test]
+    fn test_an_error_keeps_the_intents_before_it() {
+        // Arrange
+        let mut pipeline = pipeline();
+        let mut intents = program(10);
+        intents.push(Intent::PrintArray(Some(PrintArrayParams {
+            target_ordinal: Some(99),
+            ..PrintArrayParams::default()
+        })));
+
+        // Act
+        let error = pipeline.update(&intents).unwrap_err();
+        let before = pipeline.stats();
+        intents.pop();
+        let output = pipeline.update(&intents).unwrap();
+
+        // Assert
+        assert_eq!(error.intent_index(), 10);
+        assert_eq!(pipeline.stats().analyzed, before.analyzed);
+        assert_eq!(pipeline.stats().reused - before.reused, 10);
+        assert_matches_full_compile(&output, &intents);
+    }
+}
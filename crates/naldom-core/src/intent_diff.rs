@@ -0,0 +1,71 @@
+// crates/naldom-core/src/intent_diff.rs
+
+//! Compares two IntentGraphs (`naldom-cli diff`), e.g. a lockfile before and
+//! after the model was asked again. The comparison works on the raw form, so
+//! intents this compiler does not know are compared, and named, like any
+//! other.
+
+use crate::parser::RawIntent;
+use crate::passes::unified_diff;
+
+/// One line per intent: its name and its parameters as compact JSON, with
+/// unknown intents marked.
+pub fn intent_lines(raw_intents: &[RawIntent]) -> String {
+    raw_intents
+        .iter()
+        .map(|raw| {
+            let mut line = raw.name.clone();
+            if !raw.parameters.is_null() {
+                line.push_str(&format!(" {}", raw.parameters));
+            }
+            if !raw.is_known() {
+                line.push_str("  (unknown intent)");
+            }
+            line + "\n"
+        })
+        .collect()
+}
+
+/// A unified diff from `before` to `after`, one line per intent, or `None`
+/// when they plan the same intents.
+pub fn diff_intents(before: &[RawIntent], after: &[RawIntent]) -> Option<String> {
+    unified_diff(&intent_lines(before), &intent_lines(after), "intents")
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_raw_intents;
+
+    #[test]
+    fn test_diff_names_unknown_intents() {
+        // Arrange
+        let before = parse_raw_intents(
+            r#"[
+                {"intent": "CreateArray", "parameters": {"size": 5}},
+                {"intent": "GroupBy", "parameters": {"key": "parity"}},
+                {"intent": "PrintArray"}
+            ]"#,
+        )
+        .unwrap();
+        let after = parse_raw_intents(
+            r#"[
+                {"intent": "CreateArray", "parameters": {"size": 5}},
+                {"intent": "GroupBy", "parameters": {"key": "sign"}},
+                {"intent": "PrintArray"}
+            ]"#,
+        )
+        .unwrap();
+
+        // Act
+        let diff = diff_intents(&before, &after).unwrap();
+
+        // Assert
+        assert_eq!(
+            diff,
+            "--- before intents\n+++ after intents\n@@ -1,3 +1,3 @@\n CreateArray {\"size\":5}\n-GroupBy {\"key\":\"parity\"}  (unknown intent)\n+GroupBy {\"key\":\"sign\"}  (unknown intent)\n PrintArray\n"
+        );
+        assert_eq!(diff_intents(&before, &before), None);
+    }
+}
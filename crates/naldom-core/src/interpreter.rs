@@ -0,0 +1,954 @@
+// crates/naldom-core/src/interpreter.rs
+
+//! A tree-walking interpreter for IR-HL.
+//!
+//! It runs a program without LLVM or the native runtime, which is enough for
+//! demos and quick checks. Random numbers come from a seeded generator, so the
+//! same seed always produces the same output.
+
+use naldom_ir::{BinOp, HLExpression, HLProgram, HLStatement, HLValue};
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::time::{Duration, Instant};
+
+/// A runtime value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<f64>),
+    /// A matrix's elements, row by row, with its dimensions.
+    Matrix {
+        values: Vec<f64>,
+        rows: usize,
+        cols: usize,
+    },
+    /// The result of a function that returns nothing.
+    Unit,
+}
+
+/// An error raised while interpreting a program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpretError {
+    UnknownFunction(String),
+    UndefinedVariable(String),
+    /// A function was called with arguments it cannot accept.
+    InvalidArguments {
+        function: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for InterpretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterpretError::UnknownFunction(name) => {
+                write!(f, "Interpreter Error: Unknown function '{}'.", name)
+            }
+            InterpretError::UndefinedVariable(name) => {
+                write!(f, "Interpreter Error: Variable '{}' is not defined.", name)
+            }
+            InterpretError::InvalidArguments { function, reason } => {
+                write!(
+                    f,
+                    "Interpreter Error: Invalid call to '{}': {}.",
+                    function, reason
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for InterpretError {}
+
+/// Executes IR-HL programs, collecting everything they print.
+pub struct Interpreter {
+    variables: HashMap<String, Value>,
+    rng_state: u64,
+    output: String,
+    /// The instant timers count from, like the runtime's.
+    epoch: Instant,
+    /// Whether the program called `naldom_exit`; nothing runs after it.
+    exited: bool,
+}
+
+impl Interpreter {
+    /// Creates an interpreter whose random numbers are derived from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Interpreter {
+            variables: HashMap::new(),
+            rng_state: mix_seed(seed),
+            output: String::new(),
+            epoch: Instant::now(),
+            exited: false,
+        }
+    }
+
+    /// Runs `program` and returns its output. A program that exits early
+    /// returns what it printed until then; the exit code is not kept.
+    pub fn run(mut self, program: &HLProgram) -> Result<String, InterpretError> {
+        self.execute(&program.statements)?;
+        Ok(self.output)
+    }
+
+    fn execute(&mut self, statements: &[HLStatement]) -> Result<(), InterpretError> {
+        for statement in statements {
+            if self.exited {
+                break;
+            }
+            match statement {
+                HLStatement::Assign {
+                    variable,
+                    expression,
+                } => {
+                    let value = self.evaluate(expression)?;
+                    self.variables.insert(variable.clone(), value);
+                }
+                HLStatement::Call {
+                    function,
+                    arguments,
+                } => {
+                    self.call(function, arguments)?;
+                }
+                HLStatement::If {
+                    condition,
+                    then_body,
+                    else_body,
+                } => match self.evaluate(condition)? {
+                    Value::Integer(0) => self.execute(else_body)?,
+                    Value::Integer(_) => self.execute(then_body)?,
+                    _ => {
+                        return Err(InterpretError::InvalidArguments {
+                            function: "if".to_string(),
+                            reason: "the condition is not an integer".to_string(),
+                        });
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn evaluate(&mut self, expression: &HLExpression) -> Result<Value, InterpretError> {
+        match expression {
+            HLExpression::Literal(HLValue::Integer(value)) => Ok(Value::Integer(*value)),
+            HLExpression::Literal(HLValue::Float(value)) => Ok(Value::Float(*value)),
+            HLExpression::Literal(HLValue::String(value)) => Ok(Value::String(value.clone())),
+            HLExpression::Literal(HLValue::IntegerArray(values)) => Ok(Value::Array(
+                values.iter().map(|&value| value as f64).collect(),
+            )),
+            HLExpression::Variable(name) => self
+                .variables
+                .get(name)
+                .cloned()
+                .ok_or_else(|| InterpretError::UndefinedVariable(name.clone())),
+            HLExpression::FunctionCall {
+                function,
+                arguments,
+            } => self.call(function, arguments),
+            HLExpression::BinaryOp { op, lhs, rhs } => {
+                let (Value::Integer(lhs), Value::Integer(rhs)) =
+                    (self.evaluate(lhs)?, self.evaluate(rhs)?)
+                else {
+                    return Err(InterpretError::InvalidArguments {
+                        function: op.symbol().to_string(),
+                        reason: "the operands are not integers".to_string(),
+                    });
+                };
+                let result = match op {
+                    BinOp::Add => lhs.wrapping_add(rhs),
+                    BinOp::Sub => lhs.wrapping_sub(rhs),
+                    BinOp::Mul => lhs.wrapping_mul(rhs),
+                    BinOp::SDiv => {
+                        lhs.checked_div(rhs)
+                            .ok_or_else(|| InterpretError::InvalidArguments {
+                                function: op.symbol().to_string(),
+                                reason: "division by zero or overflow".to_string(),
+                            })?
+                    }
+                };
+                Ok(Value::Integer(result))
+            }
+            HLExpression::Conditional {
+                condition,
+                then_value,
+                else_value,
+            } => match self.evaluate(condition)? {
+                Value::Integer(0) => self.evaluate(else_value),
+                Value::Integer(_) => self.evaluate(then_value),
+                _ => Err(InterpretError::InvalidArguments {
+                    function: "if".to_string(),
+                    reason: "the condition is not an integer".to_string(),
+                }),
+            },
+        }
+    }
+
+    fn call(
+        &mut self,
+        function: &str,
+        arguments: &[HLExpression],
+    ) -> Result<Value, InterpretError> {
+        let values = arguments
+            .iter()
+            .map(|argument| self.evaluate(argument))
+            .collect::<Result<Vec<_>, _>>()?;
+        let invalid = |reason: &str| InterpretError::InvalidArguments {
+            function: function.to_string(),
+            reason: reason.to_string(),
+        };
+
+        match (function, values.as_slice()) {
+            ("create_random_array", [Value::Integer(size)]) => {
+                let _ = writeln!(
+                    self.output,
+                    "Runtime: Creating an array of {} random numbers...",
+                    size
+                );
+                let values = (0..*size.max(&0))
+                    .map(|_| self.next_f64() * 100.0)
+                    .collect();
+                Ok(Value::Array(values))
+            }
+            ("create_random_int_array", [Value::Integer(size)]) => {
+                let _ = writeln!(
+                    self.output,
+                    "Runtime: Creating an array of {} random whole numbers...",
+                    size
+                );
+                let values = (0..*size.max(&0))
+                    .map(|_| (self.next_f64() * 100.0).floor())
+                    .collect();
+                Ok(Value::Array(values))
+            }
+            ("create_random_matrix", [Value::Integer(rows), Value::Integer(cols)]) => {
+                let _ = writeln!(
+                    self.output,
+                    "Runtime: Creating a {}x{} matrix of random numbers...",
+                    rows, cols
+                );
+                let (rows, cols) = ((*rows).max(0) as usize, (*cols).max(0) as usize);
+                let values = (0..rows * cols).map(|_| self.next_f64() * 100.0).collect();
+                Ok(Value::Matrix { values, rows, cols })
+            }
+            ("print_matrix", [Value::Matrix { values, rows, cols }]) => {
+                for row in 0..*rows {
+                    let row = &values[row * cols..(row + 1) * cols];
+                    let _ = writeln!(self.output, "[{}]", render_values(row, true, 2));
+                }
+                Ok(Value::Unit)
+            }
+            ("create_array_from_values", [Value::Array(values), Value::Integer(len)]) => {
+                let _ = writeln!(
+                    self.output,
+                    "Runtime: Creating an array of {} values...",
+                    len
+                );
+                Ok(Value::Array(values.clone()))
+            }
+            ("sort_array", [Value::Array(_), Value::String(order), Value::Integer(_)]) => {
+                let HLExpression::Variable(name) = &arguments[0] else {
+                    return Err(invalid("the array must be a variable"));
+                };
+                let _ = writeln!(self.output, "Runtime: Sorting the array...");
+                if let Some(Value::Array(values)) = self.variables.get_mut(name) {
+                    if order == "descending" {
+                        values.sort_by(|a, b| b.total_cmp(a));
+                    } else {
+                        values.sort_by(|a, b| a.total_cmp(b));
+                    }
+                }
+                Ok(Value::Unit)
+            }
+            ("print_array", [Value::Array(values), Value::Integer(full)]) => {
+                let _ = writeln!(self.output, "[{}]", render_values(values, *full == 1, 2));
+                Ok(Value::Unit)
+            }
+            ("print_histogram", [Value::Array(values), Value::Integer(buckets)]) => {
+                if let Ok(buckets @ 1..) = usize::try_from(*buckets)
+                    && !values.is_empty()
+                {
+                    self.output.push_str(&render_histogram(values, buckets));
+                }
+                Ok(Value::Unit)
+            }
+            ("print_int_array", [Value::Array(values), Value::Integer(full)]) => {
+                let _ = writeln!(self.output, "[{}]", render_values(values, *full == 1, 0));
+                Ok(Value::Unit)
+            }
+            ("reverse_array", [Value::Array(_)]) => {
+                let HLExpression::Variable(name) = &arguments[0] else {
+                    return Err(invalid("the array must be a variable"));
+                };
+                if let Some(Value::Array(values)) = self.variables.get_mut(name) {
+                    values.reverse();
+                }
+                Ok(Value::Unit)
+            }
+            ("fill_array", [Value::Array(_), Value::Integer(value)]) => {
+                let HLExpression::Variable(name) = &arguments[0] else {
+                    return Err(invalid("the array must be a variable"));
+                };
+                if let Some(Value::Array(values)) = self.variables.get_mut(name) {
+                    values.fill(*value as f64);
+                }
+                Ok(Value::Unit)
+            }
+            ("clear_array", [Value::Array(_)]) => {
+                let HLExpression::Variable(name) = &arguments[0] else {
+                    return Err(invalid("the array must be a variable"));
+                };
+                if let Some(Value::Array(values)) = self.variables.get_mut(name) {
+                    values.clear();
+                }
+                Ok(Value::Unit)
+            }
+            ("append_to_array", [Value::Array(_), Value::Integer(value)]) => {
+                let HLExpression::Variable(name) = &arguments[0] else {
+                    return Err(invalid("the array must be a variable"));
+                };
+                if let Some(Value::Array(values)) = self.variables.get_mut(name) {
+                    values.push(*value as f64);
+                }
+                Ok(Value::Unit)
+            }
+            ("concat_arrays", [Value::Array(first), Value::Array(second)]) => {
+                Ok(Value::Array([first.as_slice(), second.as_slice()].concat()))
+            }
+            ("copy_array", [Value::Array(values)]) => Ok(Value::Array(values.clone())),
+            ("array_to_float", [Value::Array(values)]) => Ok(Value::Array(values.clone())),
+            ("array_to_int", [Value::Array(values)]) => Ok(Value::Array(
+                values.iter().map(|value| value.trunc() + 0.0).collect(),
+            )),
+            ("slice_array", [Value::Array(_), Value::Integer(start), Value::Integer(end)]) => {
+                let HLExpression::Variable(name) = &arguments[0] else {
+                    return Err(invalid("the array must be a variable"));
+                };
+                if let Some(Value::Array(values)) = self.variables.get_mut(name) {
+                    let len = values.len() as i64;
+                    let end = (*end).clamp(0, len) as usize;
+                    let start = ((*start).clamp(0, len) as usize).min(end);
+                    values.truncate(end);
+                    values.drain(..start);
+                }
+                Ok(Value::Unit)
+            }
+            // Like the runtime, an unknown operation or a division by zero
+            // leaves the array unchanged.
+            ("map_array", [Value::Array(_), Value::Integer(op), Value::Integer(operand)]) => {
+                let HLExpression::Variable(name) = &arguments[0] else {
+                    return Err(invalid("the array must be a variable"));
+                };
+                let operand = *operand as f64;
+                let apply: fn(f64, f64) -> f64 = match op {
+                    0 => |value, operand| value + operand,
+                    1 => |value, operand| value - operand,
+                    2 => |value, operand| value * operand,
+                    3 if operand != 0.0 => |value, operand| value / operand,
+                    _ => return Ok(Value::Unit),
+                };
+                if let Some(Value::Array(values)) = self.variables.get_mut(name) {
+                    for value in values.iter_mut() {
+                        *value = apply(*value, operand);
+                    }
+                }
+                Ok(Value::Unit)
+            }
+            ("sum_array", [Value::Array(values)]) => {
+                Ok(Value::Integer(values.iter().sum::<f64>().round() as i64))
+            }
+            ("min_array", [Value::Array(values)]) => Ok(Value::Integer(
+                values
+                    .iter()
+                    .copied()
+                    .reduce(f64::min)
+                    .unwrap_or(0.0)
+                    .round() as i64,
+            )),
+            ("max_array", [Value::Array(values)]) => Ok(Value::Integer(
+                values
+                    .iter()
+                    .copied()
+                    .reduce(f64::max)
+                    .unwrap_or(0.0)
+                    .round() as i64,
+            )),
+            ("array_length", [Value::Array(values)]) => Ok(Value::Integer(values.len() as i64)),
+            ("average_array", [Value::Array(values)]) => Ok(Value::Float(if values.is_empty() {
+                0.0
+            } else {
+                values.iter().sum::<f64>() / values.len() as f64
+            })),
+            ("median_array", [Value::Array(values)]) => {
+                let mut sorted = values.clone();
+                sorted.sort_unstable_by(f64::total_cmp);
+                let mid = sorted.len() / 2;
+                Ok(Value::Float(match sorted.len() {
+                    0 => 0.0,
+                    n if n % 2 == 0 => (sorted[mid - 1] + sorted[mid]) / 2.0,
+                    _ => sorted[mid],
+                }))
+            }
+            ("array_contains", [Value::Array(values), Value::Integer(value)]) => {
+                Ok(Value::Integer(i64::from(values.contains(&(*value as f64)))))
+            }
+            ("find_index", [Value::Array(values), Value::Integer(value)]) => Ok(Value::Integer(
+                values
+                    .iter()
+                    .position(|element| *element == *value as f64)
+                    .map_or(-1, |index| index as i64),
+            )),
+            ("is_sorted", [Value::Array(values)]) => Ok(Value::Integer(i64::from(
+                values.is_sorted_by(|a, b| a.total_cmp(b).is_le()),
+            ))),
+            ("describe_array", [Value::Array(values), Value::String(name)]) => {
+                self.output.push_str(&render_description(values, name));
+                Ok(Value::Unit)
+            }
+            ("is_empty", [Value::Array(values)]) => {
+                Ok(Value::Integer(i64::from(values.is_empty())))
+            }
+            ("print_integer", [Value::Integer(value)]) => {
+                let _ = writeln!(self.output, "{}", value);
+                Ok(Value::Unit)
+            }
+            ("print_bool", [Value::Integer(value)]) => {
+                let _ = writeln!(self.output, "{}", *value != 0);
+                Ok(Value::Unit)
+            }
+            ("print_float", [Value::Float(value)]) => {
+                let _ = writeln!(self.output, "{:.2}", value);
+                Ok(Value::Unit)
+            }
+            ("start_timer", []) => Ok(Value::Integer(
+                i64::try_from(self.epoch.elapsed().as_nanos()).unwrap_or(i64::MAX),
+            )),
+            ("print_elapsed", [Value::Integer(timer)]) => {
+                let now = i64::try_from(self.epoch.elapsed().as_nanos()).unwrap_or(i64::MAX);
+                let elapsed_ms = now.saturating_sub(*timer).max(0) as f64 / 1e6;
+                let _ = writeln!(self.output, "elapsed: {:.2} ms", elapsed_ms);
+                Ok(Value::Unit)
+            }
+            // Seeds travel through IR-HL as the bits of an `i64`.
+            ("naldom_seed_random", [Value::Integer(seed)]) => {
+                self.rng_state = mix_seed(*seed as u64);
+                Ok(Value::Unit)
+            }
+            // Nothing the interpreter runs fails, so there is no error to handle.
+            ("naldom_set_error_mode", [Value::Integer(_)]) => Ok(Value::Unit),
+            ("naldom_exit", [Value::Integer(_)]) => {
+                self.exited = true;
+                Ok(Value::Unit)
+            }
+            ("naldom_async_sleep", [Value::Integer(ms)]) => {
+                std::thread::sleep(Duration::from_millis((*ms).max(0) as u64));
+                Ok(Value::Unit)
+            }
+            // Profiling markers only matter to the native runtime.
+            ("naldom_profile_init" | "naldom_profile_begin" | "naldom_profile_end", _) => {
+                Ok(Value::Unit)
+            }
+            (
+                "create_random_array"
+                | "create_random_int_array"
+                | "create_array_from_values"
+                | "create_random_matrix"
+                | "print_matrix"
+                | "sort_array"
+                | "print_array"
+                | "print_int_array"
+                | "print_histogram"
+                | "reverse_array"
+                | "append_to_array"
+                | "fill_array"
+                | "clear_array"
+                | "slice_array"
+                | "map_array"
+                | "concat_arrays"
+                | "copy_array"
+                | "array_to_float"
+                | "array_to_int"
+                | "sum_array"
+                | "min_array"
+                | "max_array"
+                | "array_length"
+                | "average_array"
+                | "median_array"
+                | "array_contains"
+                | "find_index"
+                | "is_sorted"
+                | "describe_array"
+                | "is_empty"
+                | "print_integer"
+                | "print_bool"
+                | "print_float"
+                | "start_timer"
+                | "print_elapsed"
+                | "naldom_exit"
+                | "naldom_set_error_mode"
+                | "naldom_seed_random"
+                | "naldom_async_sleep",
+                _,
+            ) => Err(invalid("unexpected argument types")),
+            _ => Err(InterpretError::UnknownFunction(function.to_string())),
+        }
+    }
+
+    /// The same xorshift generator as the native runtime; values in `[0.0, 1.0)`.
+    fn next_f64(&mut self) -> f64 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 7;
+        self.rng_state ^= self.rng_state << 17;
+        (self.rng_state >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Arrays longer than twice this print only their first and last `PRINT_EDGE`
+/// elements, like the native runtime's `print_array`.
+const PRINT_EDGE: usize = 20;
+
+/// The longest bar `print_histogram` prints, like the native runtime's.
+const MAX_BAR_WIDTH: usize = 50;
+
+/// The rows `print_histogram` prints for the non-empty `values`.
+fn render_histogram(values: &[f64], buckets: usize) -> String {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let width = (max - min) / buckets as f64;
+    let mut counts = vec![0usize; buckets];
+    for value in values {
+        let bucket = if width > 0.0 {
+            (((value - min) / width) as usize).min(buckets - 1)
+        } else {
+            0
+        };
+        counts[bucket] += 1;
+    }
+    let largest = counts.iter().copied().max().unwrap_or(0);
+    let mut text = String::new();
+    for (bucket, count) in counts.iter().enumerate() {
+        let last = bucket + 1 == buckets;
+        let low = min + width * bucket as f64;
+        let high = if last {
+            max
+        } else {
+            min + width * (bucket + 1) as f64
+        };
+        let bar = if largest > MAX_BAR_WIDTH {
+            (count * MAX_BAR_WIDTH).div_ceil(largest)
+        } else {
+            *count
+        };
+        let _ = writeln!(
+            text,
+            "[{:>8.2}, {:>8.2}{} {} {}",
+            low,
+            high,
+            if last { ']' } else { ')' },
+            "#".repeat(bar),
+            count
+        );
+    }
+    text
+}
+
+/// The sentence `describe_array` prints for `values`.
+fn render_description(values: &[f64], name: &str) -> String {
+    let count = match values.len() {
+        1 => "1 element".to_string(),
+        len => format!("{} elements", len),
+    };
+    let sorted = if values.is_sorted_by(|a, b| a.total_cmp(b).is_le()) {
+        "yes"
+    } else {
+        "no"
+    };
+    let min = values.iter().copied().reduce(f64::min);
+    let max = values.iter().copied().reduce(f64::max);
+    match min.zip(max) {
+        Some((min, max)) => format!(
+            "Array {}: {}, min {}, max {}, sorted ascending: {}\n",
+            name,
+            count,
+            min.round() as i64,
+            max.round() as i64,
+            sorted
+        ),
+        None => format!("Array {}: {}, sorted ascending: {}\n", name, count, sorted),
+    }
+}
+
+/// The elements as the runtime prints them, with `decimals` digits after the
+/// point, truncated unless `full`.
+fn render_values(values: &[f64], full: bool, decimals: usize) -> String {
+    let render = |values: &[f64]| {
+        values
+            .iter()
+            .map(|v| format!("{:.*}", decimals, v))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if full || values.len() <= 2 * PRINT_EDGE {
+        return render(values);
+    }
+    format!(
+        "{}, … ({} omitted), {}",
+        render(&values[..PRINT_EDGE]),
+        group_thousands(values.len() - 2 * PRINT_EDGE),
+        render(&values[values.len() - PRINT_EDGE..])
+    )
+}
+
+/// Formats `n` with comma thousands separators: 9999960 -> "9,999,960".
+pub(crate) fn group_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Spreads small seeds over all bits, as `naldom_seed_random` does; xorshift's
+/// first outputs are otherwise close to zero.
+fn mix_seed(seed: u64) -> u64 {
+    seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lowering::LoweringContext;
+    use crate::parser::parse_to_intent_graph;
+    use crate::semantic_analyzer::SemanticAnalyzer;
+    use crate::stdlib;
+    use std::path::Path;
+
+    fn lower(json: &str) -> HLProgram {
+        LoweringContext::new().lower(&parse_to_intent_graph(json).unwrap())
+    }
+
+    #[test]
+    fn test_interprets_create_sort_print() {
+        // Arrange
+        let program = lower(
+            r#"[
+                {"intent": "CreateArray", "parameters": {"size": 4}},
+                {"intent": "SortArray", "parameters": {"order": "descending"}},
+                {"intent": "PrintArray"}
+            ]"#,
+        );
+
+        // Act
+        let output = Interpreter::new(7).run(&program).unwrap();
+
+        // Assert
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            "Runtime: Creating an array of 4 random numbers..."
+        );
+        assert_eq!(lines[1], "Runtime: Sorting the array...");
+        let values: Vec<f64> = lines[2]
+            .trim_matches(|c| c == '[' || c == ']')
+            .split(", ")
+            .map(|v| v.parse().unwrap())
+            .collect();
+        assert_eq!(values.len(), 4);
+        assert!(values.windows(2).all(|w| w[0] >= w[1]));
+        assert_eq!(Interpreter::new(7).run(&program).unwrap(), output);
+    }
+
+    #[test]
+    fn test_long_arrays_print_like_the_runtime() {
+        // Arrange
+        let create = |full: bool| {
+            lower(&format!(
+                r#"[
+                    {{"intent": "CreateArray", "parameters": {{"size": 10045}}}},
+                    {{"intent": "PrintArray", "parameters": {{"full": {}}}}}
+                ]"#,
+                full
+            ))
+        };
+
+        // Act
+        let truncated = Interpreter::new(1).run(&create(false)).unwrap();
+        let full = Interpreter::new(1).run(&create(true)).unwrap();
+
+        // Assert
+        let truncated = truncated.lines().nth(1).unwrap();
+        assert_eq!(truncated.matches(", ").count(), 40);
+        assert!(truncated.contains(", … (10,005 omitted), "));
+        assert_eq!(full.lines().nth(1).unwrap().matches(", ").count(), 10044);
+    }
+
+    #[test]
+    fn test_converted_arrays_print_in_their_element_type() {
+        // Arrange
+        let program = lower(
+            r#"[
+                {"intent": "CreateArray", "parameters": {"values": [7, -3, 10], "dtype": "float"}},
+                {"intent": "ConvertArray", "parameters": {"to": "int"}},
+                {"intent": "MapArray", "parameters": {"operation": "divide", "operand": 2}},
+                {"intent": "PrintArray"},
+                {"intent": "ConvertArray", "parameters": {"to": "float"}},
+                {"intent": "PrintArray"}
+            ]"#,
+        );
+
+        // Act
+        let output = Interpreter::new(7).run(&program).unwrap();
+
+        // Assert
+        let lines: Vec<&str> = output.lines().skip(1).collect();
+        assert_eq!(lines, ["[3, -1, 5]", "[3.00, -1.00, 5.00]"]);
+    }
+
+    #[test]
+    fn test_int_arrays_hold_and_print_whole_numbers() {
+        // Arrange
+        let program = lower(
+            r#"[
+                {"intent": "CreateArray", "parameters": {"size": 5, "dtype": "int"}},
+                {"intent": "PrintArray"}
+            ]"#,
+        );
+
+        // Act
+        let output = Interpreter::new(7).run(&program).unwrap();
+
+        // Assert
+        assert_eq!(
+            output.lines().next(),
+            Some("Runtime: Creating an array of 5 random whole numbers...")
+        );
+        let printed = output.lines().nth(1).unwrap();
+        assert!(!printed.contains('.'), "{}", printed);
+    }
+
+    #[test]
+    fn test_matrices_print_one_row_per_line() {
+        // Arrange
+        let program = lower(
+            r#"[
+                {"intent": "CreateMatrix", "parameters": {"rows": 3, "cols": 2}},
+                {"intent": "PrintMatrix"}
+            ]"#,
+        );
+
+        // Act
+        let output = Interpreter::new(7).run(&program).unwrap();
+
+        // Assert
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            "Runtime: Creating a 3x2 matrix of random numbers..."
+        );
+        assert_eq!(lines.len(), 4);
+        assert!(
+            lines[1..]
+                .iter()
+                .all(|line| line.matches(", ").count() == 1)
+        );
+    }
+
+    #[test]
+    fn test_histograms_print_one_row_per_bucket() {
+        // Arrange
+        let program = lower(
+            r#"[
+                {"intent": "CreateArray", "parameters": {"values": [0, 1, 3, 9, 10]}},
+                {"intent": "PrintHistogram", "parameters": {"buckets": 2}}
+            ]"#,
+        );
+
+        // Act
+        let output = Interpreter::new(7).run(&program).unwrap();
+
+        // Assert
+        let lines: Vec<&str> = output.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            ["[    0.00,     5.00) ### 3", "[    5.00,    10.00] ## 2"]
+        );
+    }
+
+    #[test]
+    fn test_exit_stops_the_program() {
+        // Arrange
+        let program = lower(
+            r#"[
+                {"intent": "CreateArray", "parameters": {"values": [4, 17, 3]}},
+                {"intent": "SumArray"},
+                {"intent": "Exit", "parameters": {"code": 3}},
+                {"intent": "CountElements"}
+            ]"#,
+        );
+
+        // Act
+        let output = Interpreter::new(7).run(&program).unwrap();
+
+        // Assert
+        assert!(output.ends_with("24\n"), "{}", output);
+    }
+
+    #[test]
+    fn test_a_named_sum_sizes_a_later_array() {
+        // Arrange: "sum the array, call it total, create a new array of
+        // total random numbers and count them".
+        let program = lower(
+            r#"[
+                {"intent": "CreateArray", "parameters": {"values": [4, 17, 3]}},
+                {"intent": "SumArray", "parameters": {"name": "total"}},
+                {"intent": "CreateArray", "parameters": {"size": "total"}},
+                {"intent": "CountElements"}
+            ]"#,
+        );
+
+        // Act
+        let output = Interpreter::new(7).run(&program).unwrap();
+
+        // Assert
+        assert!(
+            output.ends_with("24\nRuntime: Creating an array of 24 random numbers...\n24\n"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_conditional_runs_one_branch() {
+        // Arrange: sort only if the array is not sorted yet, then print it.
+        let program = lower(
+            r#"[
+                {"intent": "CreateArray", "parameters": {"size": 6}},
+                {"intent": "Conditional", "parameters": {
+                    "condition": "is_sorted",
+                    "then": [],
+                    "else": [{"intent": "SortArray", "parameters": {"order": "ascending"}}]
+                }},
+                {"intent": "Conditional", "parameters": {
+                    "condition": "is_sorted",
+                    "then": [{"intent": "PrintArray"}],
+                    "else": [{"intent": "SumArray"}]
+                }}
+            ]"#,
+        );
+
+        // Act
+        let output = Interpreter::new(3).run(&program).unwrap();
+
+        // Assert
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3, "{}", output);
+        assert_eq!(lines[1], "Runtime: Sorting the array...");
+        assert!(lines[2].starts_with('['));
+    }
+
+    #[test]
+    fn test_conditional_value_evaluates_only_the_chosen_side() {
+        // Arrange: `launch_rocket` is unknown, so evaluating it fails.
+        let program = |condition| HLProgram {
+            statements: vec![HLStatement::Assign {
+                variable: "var_0".to_string(),
+                expression: HLExpression::Conditional {
+                    condition: Box::new(HLExpression::Literal(HLValue::Integer(condition))),
+                    then_value: Box::new(HLExpression::Literal(HLValue::Integer(7))),
+                    else_value: Box::new(HLExpression::FunctionCall {
+                        function: "launch_rocket".to_string(),
+                        arguments: vec![],
+                    }),
+                },
+            }],
+        };
+
+        // Act
+        let taken = Interpreter::new(3).run(&program(1));
+        let not_taken = Interpreter::new(3).run(&program(0));
+
+        // Assert
+        assert!(taken.is_ok());
+        assert!(not_taken.is_err());
+    }
+
+    #[test]
+    fn test_reports_unknown_functions_and_variables() {
+        let unknown = HLProgram {
+            statements: vec![HLStatement::Call {
+                function: "launch_rocket".to_string(),
+                arguments: vec![],
+            }],
+        };
+        let undefined = HLProgram {
+            statements: vec![HLStatement::Call {
+                function: "print_array".to_string(),
+                arguments: vec![HLExpression::Variable("var_9".to_string())],
+            }],
+        };
+
+        assert_eq!(
+            Interpreter::new(1).run(&unknown),
+            Err(InterpretError::UnknownFunction("launch_rocket".to_string()))
+        );
+        assert_eq!(
+            Interpreter::new(1).run(&undefined),
+            Err(InterpretError::UndefinedVariable("var_9".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_conformance_corpus_matches_the_interpreter() {
+        // Arrange: each entry as the conformance runner compiles it, with
+        // seed 42. The native runtime frames every printed array; the
+        // interpreter prints only the array.
+        let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../tests/conformance");
+        let mut entries: Vec<_> = std::fs::read_dir(&corpus)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.to_string_lossy().ends_with(".intents.json"))
+            .collect();
+        entries.sort();
+        let unframed = |text: &str| -> Vec<String> {
+            text.lines()
+                .filter(|line| {
+                    ![
+                        "",
+                        "--- Naldom Native Output ---",
+                        "--------------------------",
+                    ]
+                    .contains(line)
+                })
+                .map(str::to_string)
+                .collect()
+        };
+
+        // Act
+        let mismatched: Vec<String> = entries
+            .iter()
+            .filter(|intents| {
+                let json = std::fs::read_to_string(intents).unwrap();
+                let graph = parse_to_intent_graph(&json).unwrap();
+                let expanded = stdlib::expand(&graph).unwrap().intents;
+                let validated = SemanticAnalyzer::new().analyze(&expanded).unwrap();
+                let program = LoweringContext::new().with_seed(Some(42)).lower(&validated);
+                let expected_path = intents
+                    .to_string_lossy()
+                    .replace(".intents.json", ".expected.txt");
+                let expected = std::fs::read_to_string(expected_path).unwrap();
+                unframed(&Interpreter::new(42).run(&program).unwrap()) != unframed(&expected)
+            })
+            .map(|intents| intents.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+
+        // Assert
+        assert!(!entries.is_empty());
+        assert!(mismatched.is_empty(), "{:?}", mismatched);
+    }
+}
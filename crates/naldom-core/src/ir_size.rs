@@ -0,0 +1,158 @@
+// crates/naldom-core/src/ir_size.rs
+
+//! Limits on the size of the generated LLVM IR.
+//!
+//! Code generation counts the instructions it emits, attributing each one to
+//! the IR-HL statement it was lowered from through the provenance annotations.
+//! A module past the soft limit still compiles, with a warning naming the
+//! statements that contribute the most; one past the hard limit is refused
+//! before `llc` spends minutes on it.
+
+use crate::interpreter::group_thousands;
+use naldom_ir::{HLExpression, HLProgram, HLStatement};
+use std::collections::BTreeMap;
+
+/// Modules with more instructions than this compile with a warning.
+pub const DEFAULT_SOFT_LIMIT: usize = 100_000;
+
+/// Modules with more instructions than this are refused.
+pub const DEFAULT_HARD_LIMIT: usize = 1_000_000;
+
+/// How many statements a diagnostic names.
+const TOP_CONTRIBUTORS: usize = 3;
+
+/// The size of a generated module.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IrSize {
+    /// Every instruction in the module, allocas and terminators included.
+    pub instructions: usize,
+    /// Instructions by the index of the IR-HL statement they were lowered
+    /// from. Instructions without provenance (e.g. returns) are left out.
+    pub by_statement: BTreeMap<usize, usize>,
+    /// Instructions by the function they are in.
+    pub by_function: BTreeMap<String, usize>,
+}
+
+/// The instruction counts at which a module draws a warning or is refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IrSizeLimits {
+    pub soft: usize,
+    pub hard: usize,
+}
+
+impl Default for IrSizeLimits {
+    fn default() -> Self {
+        IrSizeLimits {
+            soft: DEFAULT_SOFT_LIMIT,
+            hard: DEFAULT_HARD_LIMIT,
+        }
+    }
+}
+
+impl IrSizeLimits {
+    /// Checks `size` against the limits. Returns a warning past the soft
+    /// limit, and an error past the hard one. `hl_program` is the program the
+    /// module was generated from, to describe the largest contributors.
+    pub fn check(&self, size: &IrSize, hl_program: &HLProgram) -> Result<Option<String>, String> {
+        if size.instructions > self.hard {
+            return Err(format!(
+                "IR Size Error: the generated LLVM IR has {} instructions, over the limit of {}; {}. Lower the `Repeat` counts or split the program into several files.",
+                group_thousands(size.instructions),
+                group_thousands(self.hard),
+                describe_contributors(size, hl_program)
+            ));
+        }
+        if size.instructions > self.soft {
+            return Ok(Some(format!(
+                "the generated LLVM IR has {} instructions, over the soft limit of {}; {}",
+                group_thousands(size.instructions),
+                group_thousands(self.soft),
+                describe_contributors(size, hl_program)
+            )));
+        }
+        Ok(None)
+    }
+}
+
+/// "most come from statement 2 (`print_array`, 1,000 instructions), ...".
+fn describe_contributors(size: &IrSize, hl_program: &HLProgram) -> String {
+    let mut contributors: Vec<(&usize, &usize)> = size.by_statement.iter().collect();
+    contributors.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    let described: Vec<String> = contributors
+        .into_iter()
+        .take(TOP_CONTRIBUTORS)
+        .map(|(&statement, &instructions)| {
+            let name = hl_program
+                .statements
+                .get(statement)
+                .map_or("?", statement_name);
+            format!(
+                "statement {} (`{}`, {} instructions)",
+                statement + 1,
+                name,
+                group_thousands(instructions)
+            )
+        })
+        .collect();
+    if described.is_empty() {
+        return "no statement stands out".to_string();
+    }
+    format!("most come from {}", described.join(", "))
+}
+
+/// The function a statement calls, or `if` for a conditional.
+fn statement_name(statement: &HLStatement) -> &str {
+    match statement {
+        HLStatement::Call { function, .. } => function,
+        HLStatement::Assign {
+            expression: HLExpression::FunctionCall { function, .. },
+            ..
+        } => function,
+        HLStatement::Assign { variable, .. } => variable,
+        HLStatement::If { .. } => "if",
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn print_statement() -> HLStatement {
+        HLStatement::Call {
+            function: "print_array".to_string(),
+            arguments: vec![HLExpression::Variable("var_0".to_string())],
+        }
+    }
+
+    #[test]
+    fn test_limits_warn_and_then_refuse() {
+        // Arrange
+        let hl_program = HLProgram {
+            statements: vec![print_statement(), print_statement()],
+        };
+        let size = IrSize {
+            instructions: 1_500,
+            by_statement: BTreeMap::from([(0, 400), (1, 1_000)]),
+            by_function: BTreeMap::from([("main".to_string(), 1_500)]),
+        };
+        let limits = |soft, hard| IrSizeLimits { soft, hard };
+
+        // Act
+        let within = limits(2_000, 3_000).check(&size, &hl_program);
+        let soft = limits(1_000, 3_000).check(&size, &hl_program);
+        let hard = limits(500, 1_000).check(&size, &hl_program);
+
+        // Assert
+        assert_eq!(within, Ok(None));
+        assert_eq!(
+            soft,
+            Ok(Some(
+                "the generated LLVM IR has 1,500 instructions, over the soft limit of 1,000; most come from statement 2 (`print_array`, 1,000 instructions), statement 1 (`print_array`, 400 instructions)".to_string()
+            ))
+        );
+        assert!(hard.unwrap_err().starts_with(
+            "IR Size Error: the generated LLVM IR has 1,500 instructions, over the limit of 1,000;"
+        ));
+    }
+}
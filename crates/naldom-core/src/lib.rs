@@ -2,26 +2,63 @@
 
 //! The core compiler components for the Naldom language.
 
+pub mod abi_smoke;
+pub mod attribution;
+pub mod cancel;
+#[cfg(feature = "llvm")]
 pub mod codegen_llvm;
 pub mod codegen_python;
+pub mod compiler;
+pub mod cost;
+pub mod error_mode;
+pub mod eval;
+pub mod explain;
+pub mod front_matter;
+pub mod fs_policy;
+pub mod graph;
+pub mod incremental;
+pub mod intent_diff;
+pub mod interpreter;
+pub mod ir_size;
+pub mod lints;
 pub mod llm_inference;
 pub mod lowering;
 pub mod lowering_hl_to_ll;
+pub mod multi;
+pub mod observer;
+pub mod parallel_analysis;
 pub mod parser;
+pub mod passes;
+pub mod privacy;
+pub mod program_generator;
+pub mod progress;
+pub mod prompt;
+pub mod race_check;
+pub mod runtime_header;
+pub mod runtime_shim;
+pub mod schema;
 pub mod semantic_analyzer;
+pub mod stdlib;
+pub mod suggestions;
+pub mod targets;
+pub mod unsupported;
 
 // --- Integration Tests for the Compiler Pipeline ---
 #[cfg(test)]
 mod pipeline_tests {
+    #[cfg(feature = "llvm")]
     use crate::codegen_llvm::generate_llvm_ir;
     use crate::lowering::LoweringContext;
     use crate::lowering_hl_to_ll::lower_hl_to_ll;
     use crate::parser::parse_to_intent_graph;
     use crate::semantic_analyzer::SemanticAnalyzer;
     use naldom_ir::Intent;
+    #[cfg(feature = "llvm")]
+    use naldom_ir::LLInstruction;
 
     /// This test simulates the entire compiler pipeline from a mocked LLM response
     /// down to the final LLVM IR, without any external dependencies.
+    #[cfg(feature = "llvm")]
     #[test]
     fn test_full_pipeline_to_llvm_ir_for_wait_program() {
         // Arrange:
@@ -62,10 +99,23 @@ mod pipeline_tests {
         assert_eq!(hl_program.statements.len(), 4);
 
         // 5. Lower to IR-LL
-        let ll_program = lower_hl_to_ll(&hl_program);
+        let ll_program = lower_hl_to_ll(&hl_program).unwrap();
+        let called: Vec<&str> = ll_program.functions[0].basic_blocks[0]
+            .instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                LLInstruction::Call { function_name, .. } => Some(function_name.as_str()),
+                _ => None,
+            })
+            .collect();
         assert_eq!(
-            ll_program.functions[0].basic_blocks[0].instructions.len(),
-            4
+            called,
+            [
+                "create_random_array",
+                "print_array",
+                "naldom_async_sleep",
+                "print_array"
+            ]
         );
 
         // 6. Generate LLVM IR
@@ -79,4 +129,551 @@ mod pipeline_tests {
         assert!(llvm_ir.contains("call void @naldom_async_sleep(i64 100)"));
         assert!(llvm_ir.contains("call void @print_array"));
     }
+
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_min_and_max_are_declared_returning_integers() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "CreateArray", "parameters": {"size": 5}},
+            {"intent": "MinArray"},
+            {"intent": "MaxArray"}
+        ]
+        "#;
+
+        // Act
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+        let llvm_ir = generate_llvm_ir(
+            &lower_hl_to_ll(&hl_program).unwrap(),
+            "x86_64-unknown-linux-gnu",
+        )
+        .expect("Codegen failed");
+
+        // Assert
+        assert!(llvm_ir.contains("call i64 @min_array"));
+        assert!(llvm_ir.contains("call i64 @max_array"));
+        assert_eq!(llvm_ir.matches("call void @print_integer").count(), 2);
+    }
+
+    /// "Create 7 numbers, then print how many there are."
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_count_elements_prints_the_array_length() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "CreateArray", "parameters": {"size": 7}},
+            {"intent": "CountElements"}
+        ]
+        "#;
+
+        // Act
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+        let llvm_ir = generate_llvm_ir(
+            &lower_hl_to_ll(&hl_program).unwrap(),
+            "x86_64-unknown-linux-gnu",
+        )
+        .expect("Codegen failed");
+
+        // Assert
+        assert!(llvm_ir.contains("declare i64 @array_length("));
+        assert!(llvm_ir.contains("call i64 @array_length"));
+        assert!(llvm_ir.contains("call void @print_integer(i64 "));
+        let unvalidated = parse_to_intent_graph(r#"[{"intent": "CountElements"}]"#).unwrap();
+        assert!(SemanticAnalyzer::new().analyze(&unvalidated).is_err());
+    }
+
+    /// "Create 9 numbers, then print their average and their median."
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_average_and_median_print_floats() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "CreateArray", "parameters": {"size": 9}},
+            {"intent": "AverageArray"},
+            {"intent": "MedianArray", "parameters": {"targetOrdinal": 1}}
+        ]
+        "#;
+
+        // Act
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+        let llvm_ir = generate_llvm_ir(
+            &lower_hl_to_ll(&hl_program).unwrap(),
+            "x86_64-unknown-linux-gnu",
+        )
+        .expect("Codegen failed");
+
+        // Assert
+        assert!(llvm_ir.contains("declare double @average_array("));
+        assert!(llvm_ir.contains("declare double @median_array("));
+        assert!(llvm_ir.contains("call double @average_array"));
+        assert!(llvm_ir.contains("call double @median_array"));
+        assert_eq!(llvm_ir.matches("call void @print_float(double ").count(), 2);
+        let unvalidated = parse_to_intent_graph(r#"[{"intent": "MedianArray"}]"#).unwrap();
+        assert!(SemanticAnalyzer::new().analyze(&unvalidated).is_err());
+    }
+
+    /// "Create 10 numbers, then check whether the array contains 17."
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_contains_value_prints_a_boolean() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "CreateArray", "parameters": {"size": 10}},
+            {"intent": "ContainsValue", "parameters": {"value": 17}}
+        ]
+        "#;
+
+        // Act
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+        let llvm_ir = generate_llvm_ir(
+            &lower_hl_to_ll(&hl_program).unwrap(),
+            "x86_64-unknown-linux-gnu",
+        )
+        .expect("Codegen failed");
+
+        // Assert
+        assert!(llvm_ir.contains("call i32 @array_contains("));
+        assert!(llvm_ir.contains("i64 17)"));
+        assert!(llvm_ir.contains("call void @print_bool(i32 "));
+        let unvalidated =
+            parse_to_intent_graph(r#"[{"intent": "ContainsValue", "parameters": {"value": 17}}]"#)
+                .unwrap();
+        assert!(SemanticAnalyzer::new().analyze(&unvalidated).is_err());
+    }
+
+    /// "Create 10 numbers, then find where 42 is and print its position."
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_find_index_returns_an_i64_position() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "CreateArray", "parameters": {"size": 10}},
+            {"intent": "FindIndex", "parameters": {"value": 42}}
+        ]
+        "#;
+
+        // Act
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+        let llvm_ir = generate_llvm_ir(
+            &lower_hl_to_ll(&hl_program).unwrap(),
+            "x86_64-unknown-linux-gnu",
+        )
+        .expect("Codegen failed");
+
+        // Assert
+        assert!(llvm_ir.contains("call i64 @find_index("));
+        assert!(llvm_ir.contains("call void @print_integer(i64 "));
+        let unvalidated =
+            parse_to_intent_graph(r#"[{"intent": "FindIndex", "parameters": {"value": 42}}]"#)
+                .unwrap();
+        assert!(SemanticAnalyzer::new().analyze(&unvalidated).is_err());
+    }
+
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_exit_ends_main_without_a_return() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "CreateArray", "parameters": {"size": 10}},
+            {"intent": "Exit", "parameters": {"code": 3}},
+            {"intent": "PrintArray"}
+        ]
+        "#;
+
+        // Act
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let mut analyzer = SemanticAnalyzer::new();
+        let validated_graph = analyzer.analyze(&intent_graph).expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+        let llvm_ir = generate_llvm_ir(
+            &lower_hl_to_ll(&hl_program).unwrap(),
+            "x86_64-unknown-linux-gnu",
+        )
+        .expect("Codegen failed");
+
+        // Assert: the module verifies, and nothing follows the exit.
+        assert!(llvm_ir.contains("call void @naldom_exit(i32 3)"));
+        assert!(llvm_ir.contains("unreachable"));
+        assert!(!llvm_ir.contains("call void @print_array("));
+        assert!(!llvm_ir.contains("ret i32"));
+        assert_eq!(analyzer.warnings().len(), 1);
+    }
+
+    /// "Create arrays a and b, join them into c and print c."
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_concat_arrays_calls_the_runtime_for_a_new_array() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "CreateArray", "parameters": {"size": 5, "name": "a"}},
+            {"intent": "CreateArray", "parameters": {"values": [1, 2], "name": "b"}},
+            {"intent": "ConcatArrays", "parameters": {"first": "a", "second": "b", "name": "c"}},
+            {"intent": "PrintArray", "parameters": {"target": "c"}}
+        ]
+        "#;
+
+        // Act
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+        let llvm_ir = generate_llvm_ir(
+            &lower_hl_to_ll(&hl_program).unwrap(),
+            "x86_64-unknown-linux-gnu",
+        )
+        .expect("Codegen failed");
+
+        // Assert
+        assert!(llvm_ir.contains("declare ptr @concat_arrays("));
+        assert!(llvm_ir.contains("call ptr @concat_arrays(ptr "));
+    }
+
+    /// "If the array is sorted, print it. Otherwise, sort it first."
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_conditional_branches_on_the_runtime_test() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "CreateArray", "parameters": {"size": 5}},
+            {
+                "intent": "Conditional",
+                "parameters": {
+                    "condition": "is_sorted",
+                    "then": [{"intent": "PrintArray"}],
+                    "else": [{"intent": "SortArray", "parameters": {}}, {"intent": "PrintArray"}]
+                }
+            }
+        ]
+        "#;
+
+        // Act
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+        let llvm_ir = generate_llvm_ir(
+            &lower_hl_to_ll(&hl_program).unwrap(),
+            "x86_64-unknown-linux-gnu",
+        )
+        .expect("Codegen failed");
+
+        // Assert
+        assert!(llvm_ir.contains("call i32 @is_sorted("));
+        assert!(llvm_ir.contains("icmp ne i32"));
+        assert!(llvm_ir.contains("br i1 "));
+        assert_eq!(llvm_ir.matches("br label %bb3").count(), 2);
+        assert_eq!(llvm_ir.matches("call void @print_array").count(), 2);
+    }
+
+    /// "Create an array of 5 numbers and print it three times."
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_repeat_prints_the_array_three_times() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "CreateArray", "parameters": {"size": 5}},
+            {
+                "intent": "Repeat",
+                "parameters": {"count": 3, "body": [{"intent": "PrintArray"}]}
+            }
+        ]
+        "#;
+
+        // Act
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+        let llvm_ir = generate_llvm_ir(
+            &lower_hl_to_ll(&hl_program).unwrap(),
+            "x86_64-unknown-linux-gnu",
+        )
+        .expect("Codegen failed");
+
+        // Assert
+        assert_eq!(llvm_ir.matches("call void @print_array").count(), 3);
+    }
+
+    /// Serializes `value` to JSON and reads it back.
+    fn json_round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+        let json = serde_json::to_string(value).expect("Serializing failed");
+        serde_json::from_str(&json).expect("Deserializing failed")
+    }
+
+    #[test]
+    fn test_every_ir_round_trips_through_json() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "SetVariable", "parameters": {"name": "n", "value": 4}},
+            {"intent": "CreateArray", "parameters": {"size": "n", "name": "a"}},
+            {"intent": "CreateArray", "parameters": {"values": [3, 1, 2], "name": "b", "dtype": "float"}},
+            {"intent": "ConcatArrays", "parameters": {"first": "a", "second": "b"}},
+            {
+                "intent": "Conditional",
+                "parameters": {
+                    "condition": "is_sorted",
+                    "then": [{"intent": "PrintArray"}],
+                    "else": [{"intent": "SortArray", "parameters": {"order": "descending"}}]
+                }
+            },
+            {"intent": "Repeat", "parameters": {"count": 2, "body": [{"intent": "SumArray"}]}},
+            {"intent": "PrintArray", "parameters": {"target": "all", "full": true}},
+            {"intent": "Note", "parameters": {"text": "done"}}
+        ]
+        "#;
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+        let ll_program = crate::lowering_hl_to_ll::lower_hl_to_ll(&hl_program).unwrap();
+
+        // Act
+        let intents_again = json_round_trip(&intent_graph);
+        let validated_again = json_round_trip(&validated_graph);
+
+        // Assert
+        assert_eq!(intents_again, intent_graph);
+        // `ConcatArrays` writes out the ordinals the analyzer resolved but
+        // never reads them back, so the validated graph is only stable from
+        // its first reading on.
+        assert_eq!(json_round_trip(&validated_again), validated_again);
+        assert_eq!(json_round_trip(&hl_program), hl_program);
+        assert_eq!(json_round_trip(&ll_program), ll_program);
+    }
+
+    /// "Sum the array, call it total, create a new array of total random numbers."
+    const NAMED_SUM_PROGRAM: &str = r#"
+    [
+        {"intent": "CreateArray", "parameters": {"values": [4, 17, 3]}},
+        {"intent": "SumArray", "parameters": {"name": "total"}},
+        {"intent": "CreateArray", "parameters": {"size": "total"}}
+    ]
+    "#;
+
+    #[test]
+    fn test_a_named_sum_reaches_the_next_array_through_a_register() {
+        // Act
+        let intent_graph = parse_to_intent_graph(NAMED_SUM_PROGRAM).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+        let ll_program = lower_hl_to_ll(&hl_program).unwrap();
+
+        // Assert: the sum is kept in a slot, and the size is loaded from it.
+        let text: Vec<String> = ll_program.functions[0].basic_blocks[0]
+            .instructions
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            text[4..],
+            [
+                "%3 = call @sum_array(%2)",
+                "%4 = alloc i64",
+                "store %3, %4",
+                "%5 = load %4",
+                "call @print_integer(%5)",
+                "%6 = load %4",
+                "%7 = call @create_random_array(%6)",
+                "%8 = alloc i8*",
+                "store %7, %8",
+            ]
+        );
+    }
+
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_a_named_sum_sizes_the_next_array_in_verified_ir() {
+        // Act
+        let intent_graph = parse_to_intent_graph(NAMED_SUM_PROGRAM).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+        // `generate_llvm_ir` verifies the module before printing it.
+        let llvm_ir = generate_llvm_ir(
+            &lower_hl_to_ll(&hl_program).unwrap(),
+            "x86_64-unknown-linux-gnu",
+        )
+        .expect("Codegen failed");
+
+        // Assert
+        assert!(llvm_ir.contains("call i64 @sum_array"));
+        assert!(
+            llvm_ir.contains("@create_random_array(i64 %"),
+            "{}",
+            llvm_ir
+        );
+    }
+
+    /// "Create two arrays, a and b, and sort only b."
+    #[test]
+    fn test_only_the_named_array_is_sorted() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "CreateArray", "parameters": {"size": 5, "name": "a"}},
+            {"intent": "CreateArray", "parameters": {"size": 3, "name": "b"}},
+            {"intent": "SortArray", "parameters": {"target": "b"}},
+            {"intent": "PrintArray", "parameters": {"target": "a"}}
+        ]
+        "#;
+
+        // Act
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+
+        // Assert
+        let python = crate::codegen_python::PythonCodeGenerator::new().generate(&hl_program);
+        assert_eq!(
+            python,
+            "var_0 = create_random_array(5)\nvar_1 = create_random_array(3)\nsort_array(var_1, 'ascending', 0)\nprint_array(var_0, 0)"
+        );
+    }
+
+    /// "Create an array of 5 numbers and sort it with bubble sort."
+    #[test]
+    fn test_a_named_sort_algorithm_reaches_the_runtime_call() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "CreateArray", "parameters": {"size": 5}},
+            {"intent": "SortArray", "parameters": {"algorithm": "bubble"}}
+        ]
+        "#;
+
+        // Act
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+
+        // Assert
+        assert!(matches!(
+            &intent_graph[1],
+            Intent::SortArray(params) if params.algorithm.as_deref() == Some("bubble")
+        ));
+        let python = crate::codegen_python::PythonCodeGenerator::new().generate(&hl_program);
+        assert_eq!(
+            python,
+            "var_0 = create_random_array(5)\nsort_array(var_0, 'ascending', 3)"
+        );
+    }
+
+    /// "Create a 2 by 3 matrix and print it."
+    #[test]
+    fn test_matrices_lower_to_the_matrix_runtime_calls() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "CreateMatrix", "parameters": {"rows": 2, "cols": 3}},
+            {"intent": "PrintMatrix"}
+        ]
+        "#;
+
+        // Act
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+
+        // Assert
+        let python = crate::codegen_python::PythonCodeGenerator::new().generate(&hl_program);
+        assert_eq!(
+            python,
+            "var_0 = create_random_matrix(2, 3)\nprint_matrix(var_0)"
+        );
+    }
+
+    #[test]
+    fn test_note_intent_parses_and_lowers_to_nothing() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "CreateArray", "parameters": {"size": 3}},
+            {"intent": "Note", "parameters": {"text": "Remember to smile."}},
+            {"intent": "PrintArray"}
+        ]
+        "#;
+
+        // Act
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let mut analyzer = SemanticAnalyzer::new();
+        let validated_graph = analyzer.analyze(&intent_graph).expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+
+        // Assert
+        assert!(
+            matches!(&intent_graph[1], Intent::Note(params) if params.text == "Remember to smile.")
+        );
+        assert_eq!(analyzer.warnings().len(), 1);
+        assert_eq!(hl_program.statements.len(), 2);
+    }
+
+    #[test]
+    fn test_ordinal_references_bind_to_arrays_in_creation_order() {
+        // Arrange
+        let mocked_llm_response = r#"
+        [
+            {"intent": "CreateArray", "parameters": {"size": 5}},
+            {"intent": "CreateArray", "parameters": {"size": 3}},
+            {"intent": "SortArray", "parameters": {"order": "ascending", "targetOrdinal": 1}},
+            {"intent": "PrintArray", "parameters": {"targetOrdinal": 2}}
+        ]
+        "#;
+
+        // Act
+        let intent_graph = parse_to_intent_graph(mocked_llm_response).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+        let hl_program = LoweringContext::new().lower(&validated_graph);
+
+        // Assert
+        let python = crate::codegen_python::PythonCodeGenerator::new().generate(&hl_program);
+        assert_eq!(
+            python,
+            "var_0 = create_random_array(5)\nvar_1 = create_random_array(3)\nsort_array(var_0, 'ascending', 0)\nprint_array(var_1, 0)"
+        );
+    }
 }
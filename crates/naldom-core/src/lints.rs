@@ -0,0 +1,408 @@
+// crates/naldom-core/src/lints.rs
+
+//! Lints: optional checks that run on the validated IntentGraph and report
+//! programs that are legal but probably not what the user meant.
+//!
+//! Each lint has a name and a level (allow, warn or deny). Library users can
+//! register their own lints next to the built-in ones.
+
+use crate::targets::TargetKind;
+use naldom_ir::Intent;
+use std::collections::HashMap;
+use std::fmt;
+
+/// How a lint's findings are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    /// Findings are discarded.
+    Allow,
+    /// Findings are reported as warnings.
+    Warn,
+    /// Findings fail the compilation.
+    Deny,
+}
+
+/// A single finding reported by a lint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintDiagnostic {
+    /// The offending intent, if the finding points at one.
+    pub intent_index: Option<usize>,
+    pub message: String,
+}
+
+impl LintDiagnostic {
+    pub fn at(intent_index: usize, message: impl Into<String>) -> Self {
+        LintDiagnostic {
+            intent_index: Some(intent_index),
+            message: message.into(),
+        }
+    }
+}
+
+/// What a lint may know about the compilation besides the graph itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintContext {
+    pub target: TargetKind,
+}
+
+/// A check over a validated IntentGraph (every array reference resolved).
+pub trait Lint: Send + Sync {
+    /// The name used in `--allow`/`--warn`/`--deny` and in reports.
+    fn name(&self) -> &'static str;
+    fn check(&self, intents: &[Intent], ctx: &LintContext) -> Vec<LintDiagnostic>;
+}
+
+/// An error in the lint configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintError {
+    UnknownLint {
+        name: String,
+        available: Vec<&'static str>,
+    },
+}
+
+impl fmt::Display for LintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintError::UnknownLint { name, available } => write!(
+                f,
+                "Unknown lint '{}'. Available lints: {}.",
+                name,
+                available.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LintError {}
+
+/// The findings of every lint that is not allowed, split by level.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintReport {
+    pub warnings: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// The registered lints and their levels.
+pub struct LintRegistry {
+    lints: Vec<Box<dyn Lint>>,
+    levels: HashMap<&'static str, LintLevel>,
+}
+
+impl Default for LintRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LintRegistry {
+    /// A registry holding the built-in lints, all at `Warn`.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register(Box::new(UnusedArray));
+        registry.register(Box::new(ZeroWait));
+        registry.register(Box::new(HugeArray));
+        registry.register(Box::new(DuplicateIntents));
+        registry
+    }
+
+    /// A registry without any lints.
+    pub fn empty() -> Self {
+        LintRegistry {
+            lints: Vec::new(),
+            levels: HashMap::new(),
+        }
+    }
+
+    /// Adds a lint at `Warn` level. A lint with the same name is replaced.
+    pub fn register(&mut self, lint: Box<dyn Lint>) {
+        self.lints.retain(|existing| existing.name() != lint.name());
+        self.levels.insert(lint.name(), LintLevel::Warn);
+        self.lints.push(lint);
+    }
+
+    /// The names of all registered lints, in registration order.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.lints.iter().map(|lint| lint.name()).collect()
+    }
+
+    /// Changes the level of the lint called `name`.
+    pub fn set_level(&mut self, name: &str, level: LintLevel) -> Result<(), LintError> {
+        let Some(lint) = self.lints.iter().find(|lint| lint.name() == name) else {
+            return Err(LintError::UnknownLint {
+                name: name.to_string(),
+                available: self.names(),
+            });
+        };
+        self.levels.insert(lint.name(), level);
+        Ok(())
+    }
+
+    /// Runs every lint that is not allowed.
+    pub fn run(&self, intents: &[Intent], ctx: &LintContext) -> LintReport {
+        let mut report = LintReport::default();
+        for lint in &self.lints {
+            let level = self.levels[lint.name()];
+            if level == LintLevel::Allow {
+                continue;
+            }
+            for diagnostic in lint.check(intents, ctx) {
+                let message = match diagnostic.intent_index {
+                    Some(index) => format!(
+                        "{} (intent #{}): {}",
+                        lint.name(),
+                        index + 1,
+                        diagnostic.message
+                    ),
+                    None => format!("{}: {}", lint.name(), diagnostic.message),
+                };
+                match level {
+                    LintLevel::Deny => report.errors.push(message),
+                    _ => report.warnings.push(message),
+                }
+            }
+        }
+        report
+    }
+}
+
+/// Arrays larger than this trigger `huge_array`.
+pub const HUGE_ARRAY_ELEMENTS: u32 = 10_000_000;
+
+/// An array that is created but never sorted or printed.
+struct UnusedArray;
+
+impl Lint for UnusedArray {
+    fn name(&self) -> &'static str {
+        "unused_array"
+    }
+
+    fn check(&self, intents: &[Intent], _ctx: &LintContext) -> Vec<LintDiagnostic> {
+        let creations: Vec<usize> = intents
+            .iter()
+            .enumerate()
+            .filter(|(_, intent)| matches!(intent, Intent::CreateArray(_)))
+            .map(|(index, _)| index)
+            .collect();
+        creations
+            .iter()
+            .enumerate()
+            .filter(|(position, _)| {
+                let ordinal = *position as u32 + 1;
+                !intents.iter().any(|intent| match intent {
+                    // Printing every array uses all of them up to its ordinal.
+                    Intent::PrintArray(Some(params)) if params.prints_all() => {
+                        params.target_ordinal.is_some_and(|last| ordinal <= last)
+                    }
+                    _ => intent.target_ordinal() == Some(ordinal),
+                })
+            })
+            .map(|(_, &index)| LintDiagnostic::at(index, "this array is never sorted or printed"))
+            .collect()
+    }
+}
+
+/// A wait of zero milliseconds, which does nothing.
+struct ZeroWait;
+
+impl Lint for ZeroWait {
+    fn name(&self) -> &'static str {
+        "zero_wait"
+    }
+
+    fn check(&self, intents: &[Intent], _ctx: &LintContext) -> Vec<LintDiagnostic> {
+        intents
+            .iter()
+            .enumerate()
+            .filter(|(_, intent)| matches!(intent, Intent::Wait(params) if params.duration_ms == 0))
+            .map(|(index, _)| LintDiagnostic::at(index, "waiting 0 ms has no effect"))
+            .collect()
+    }
+}
+
+/// An array large enough to make the program slow or memory hungry.
+struct HugeArray;
+
+impl Lint for HugeArray {
+    fn name(&self) -> &'static str {
+        "huge_array"
+    }
+
+    fn check(&self, intents: &[Intent], _ctx: &LintContext) -> Vec<LintDiagnostic> {
+        intents
+            .iter()
+            .enumerate()
+            .filter_map(|(index, intent)| match intent {
+                Intent::CreateArray(params) => {
+                    let size = params
+                        .literal_size()
+                        .filter(|&size| size > HUGE_ARRAY_ELEMENTS)?;
+                    Some(LintDiagnostic::at(
+                        index,
+                        format!(
+                            "an array of {} elements is very large; was this intended?",
+                            size
+                        ),
+                    ))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// An intent that repeats the previous one without any effect, e.g. sorting
+/// the same array the same way twice in a row.
+struct DuplicateIntents;
+
+impl Lint for DuplicateIntents {
+    fn name(&self) -> &'static str {
+        "duplicate_intents"
+    }
+
+    fn check(&self, intents: &[Intent], _ctx: &LintContext) -> Vec<LintDiagnostic> {
+        intents
+            .windows(2)
+            .enumerate()
+            .filter(|(_, pair)| match (&pair[0], &pair[1]) {
+                (Intent::SortArray(first), Intent::SortArray(second)) => {
+                    first.order == second.order && first.target_ordinal == second.target_ordinal
+                }
+                _ => false,
+            })
+            .map(|(index, _)| {
+                LintDiagnostic::at(
+                    index + 1,
+                    "this sort repeats the previous one and has no effect",
+                )
+            })
+            .collect()
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::semantic_analyzer::SemanticAnalyzer;
+    use naldom_ir::{CountArg, CreateArrayParams, PrintArrayParams, SortArrayParams, WaitParams};
+
+    fn ctx() -> LintContext {
+        LintContext {
+            target: TargetKind::Native,
+        }
+    }
+
+    fn validated(intents: &[Intent]) -> Vec<Intent> {
+        SemanticAnalyzer::new().analyze(intents).unwrap()
+    }
+
+    fn sort() -> Intent {
+        Intent::SortArray(SortArrayParams {
+            order: Some("ascending".to_string()),
+            algorithm: None,
+            target_ordinal: None,
+            target: None,
+        })
+    }
+
+    #[test]
+    fn test_builtin_lints_fire() {
+        // Arrange
+        let intents = validated(&[
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(20_000_000)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(3)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            sort(),
+            sort(),
+            Intent::Wait(WaitParams { duration_ms: 0 }),
+            Intent::PrintArray(Some(PrintArrayParams {
+                target_ordinal: Some(2),
+                target: None,
+                full: Some(false),
+            })),
+        ]);
+
+        // Act
+        let report = LintRegistry::new().run(&intents, &ctx());
+
+        // Assert
+        assert!(report.errors.is_empty());
+        assert_eq!(
+            report.warnings,
+            [
+                "unused_array (intent #1): this array is never sorted or printed",
+                "zero_wait (intent #5): waiting 0 ms has no effect",
+                "huge_array (intent #1): an array of 20000000 elements is very large; was this intended?",
+                "duplicate_intents (intent #4): this sort repeats the previous one and has no effect",
+            ]
+        );
+    }
+
+    struct NoWaits;
+
+    impl Lint for NoWaits {
+        fn name(&self) -> &'static str {
+            "no_waits"
+        }
+
+        fn check(&self, intents: &[Intent], _ctx: &LintContext) -> Vec<LintDiagnostic> {
+            intents
+                .iter()
+                .enumerate()
+                .filter(|(_, intent)| matches!(intent, Intent::Wait(_)))
+                .map(|(index, _)| LintDiagnostic::at(index, "waits are not allowed here"))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_custom_lint_fires_at_its_level() {
+        // Arrange
+        let mut registry = LintRegistry::empty();
+        registry.register(Box::new(NoWaits));
+        registry.set_level("no_waits", LintLevel::Deny).unwrap();
+        let intents = [Intent::Wait(WaitParams { duration_ms: 5 })];
+
+        // Act
+        let report = registry.run(&intents, &ctx());
+
+        // Assert
+        assert!(report.warnings.is_empty());
+        assert_eq!(
+            report.errors,
+            ["no_waits (intent #1): waits are not allowed here"]
+        );
+    }
+
+    #[test]
+    fn test_allowed_lints_are_silent() {
+        let mut registry = LintRegistry::new();
+        registry.set_level("zero_wait", LintLevel::Allow).unwrap();
+
+        let report = registry.run(&[Intent::Wait(WaitParams { duration_ms: 0 })], &ctx());
+
+        assert_eq!(report, LintReport::default());
+    }
+
+    #[test]
+    fn test_unknown_lint_lists_available_ones() {
+        let error = LintRegistry::new()
+            .set_level("unused_arary", LintLevel::Deny)
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Unknown lint 'unused_arary'. Available lints: unused_array, zero_wait, huge_array, duplicate_intents."
+        );
+    }
+}
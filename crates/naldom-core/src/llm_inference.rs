@@ -1,20 +1,31 @@
 // crates/naldom-core/src/llm_inference.rs
 
+use crate::prompt::{Prompt, PromptOptions, build_prompt};
+
+/// Builds the prompt for `user_prompt` and sends it to the configured provider.
+pub async fn run_inference(user_prompt: &str, opts: &PromptOptions) -> Result<String, String> {
+    let prompt = build_prompt(user_prompt, opts);
+    complete(&prompt).await
+}
+
+/// Sends an assembled prompt to the llama.cpp server and returns the completion.
 // This block is compiled ONLY when the `mock-llm` feature is NOT enabled.
 #[cfg(not(feature = "mock-llm"))]
-pub async fn run_inference(user_prompt: &str) -> Result<String, String> {
+pub async fn complete(prompt: &Prompt) -> Result<String, String> {
     use reqwest::Client;
     use serde::{Deserialize, Serialize};
 
     const LLM_SERVER_URL: &str = "http://127.0.0.1:8080/completion"; // Corrected IP address
 
     #[derive(Serialize)]
-    struct LlmRequest {
-        prompt: String,
+    struct LlmRequest<'a> {
+        prompt: &'a str,
         n_predict: i32,
         temperature: f32,
-        stop: Vec<String>,
-        grammar: String,
+        stop: &'a [String],
+        grammar: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        seed: Option<u64>,
     }
 
     #[derive(Deserialize)]
@@ -22,64 +33,13 @@ pub async fn run_inference(user_prompt: &str) -> Result<String, String> {
         content: String,
     }
 
-    let system_prompt = r#"
-CONTEXT:
-You are an expert Frontend Compiler. Your task is to analyze the user's request, which is written in a natural language called Naldom, and transform it into a strictly structured JSON array of "intents". This JSON is the Abstract Syntax Tree (AST) for the Naldom language.
-TASK:
-1. Analyze the user's request.
-2. Identify the sequence of operations the user wants to perform.
-3. For each operation, map it to one of the "AVAILABLE INTENTS".
-4. Construct a JSON object for each intent.
-5. Combine these objects into a single JSON array.
-6. Respond with ONLY the raw JSON array.
-IMPORTANT:
-- You MUST respond with ONLY the JSON array. Do not include any extra text, explanations, markdown formatting, or "think" blocks.
-- If a parameter is not specified by the user, you MUST use a sensible default value as specified below.
-- You MUST NOT generate an intent that operates on a variable before it has been created.
-DEFAULT VALUES:
-- For the "SortArray" intent, if the order is not specified, you MUST default to "ascending".
-AVAILABLE INTENTS (JSON Schema):
-[
-    {
-        "intent": "CreateArray",
-        "parameters": { "size": "u32" }
-    },
-    {
-        "intent": "SortArray",
-        "parameters": { "order": "String" }
-    },
-    {
-        "intent": "PrintArray"
-    },
-    {
-        "intent": "Wait",
-        "parameters": { "durationMs": "u64" }
-    }
-]
-USER REQUEST:
-"#;
-
-    let full_prompt = format!("{}{}", system_prompt, user_prompt);
-
-    let grammar = r#"
-root   ::= "[" ws intent ("," ws intent)* ws "]"
-intent ::= "{" ws "\"intent\"" ws ":" ws "\"" intent-name "\"" ("," ws "\"parameters\"" ws ":" ws params)? ws "}"
-params ::= "{" ws param ("," ws param)* ws "}"
-param  ::= "\"" string "\"" ws ":" ws value
-value  ::= string-literal | number
-string-literal ::= "\"" string "\""
-intent-name ::= "CreateArray" | "SortArray" | "PrintArray" | "Wait"
-string ::= ([^"\\] | "\\" (["\\/bfnrt] | "u" [0-9a-fA-F] [0-9a-fA-F] [0-9a-fA-F] [0-9a-fA-F]))*
-number ::= "-"? ([0-9] | [1-9] [0-9]*) ("." [0-9]+)? ([eE] [-+]? [0-9]+)?
-ws ::= [ \t\n\r]*
-"#;
-
     let request_body = LlmRequest {
-        prompt: full_prompt,
+        prompt: &prompt.text,
         n_predict: 512,
-        temperature: 0.1,
-        stop: vec!["\nUSER REQUEST:".to_string(), "ASSISTANT:".to_string()],
-        grammar: grammar.to_string(),
+        temperature: prompt.sampling.temperature,
+        stop: &prompt.stop,
+        grammar: &prompt.grammar,
+        seed: prompt.sampling.seed,
     };
 
     println!("Sending HTTP request to llama.cpp server...");
@@ -115,10 +75,19 @@ ws ::= [ \t\n\r]*
     Ok(content)
 }
 
+/// Names a file whose contents the mock LLM returns instead of its built-in response.
+#[cfg(feature = "mock-llm")]
+pub const MOCK_RESPONSE_ENV: &str = "NALDOM_MOCK_RESPONSE";
+
 // This block is compiled ONLY when the `mock-llm` feature IS enabled.
 #[cfg(feature = "mock-llm")]
-pub async fn run_inference(_user_prompt: &str) -> Result<String, String> {
+pub async fn complete(_prompt: &Prompt) -> Result<String, String> {
     println!("--- Using Mock LLM Inference ---");
+    // Tests can substitute their own intent graph.
+    if let Some(path) = std::env::var_os(MOCK_RESPONSE_ENV) {
+        return std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read mock response {:?}: {}", path, e));
+    }
     let mock_response = r#"
     [
         {
@@ -1,12 +1,61 @@
 // crates/naldom-core/src/lowering.rs
 
-use naldom_ir::{HLExpression, HLProgram, HLStatement, HLValue, Intent};
+use crate::cancel::CancellationToken;
+use crate::error_mode::ErrorMode;
+use crate::schema;
+use crate::semantic_analyzer::{MAP_OPERATIONS, SORT_ALGORITHMS};
+use naldom_ir::{
+    BinOp, CountArg, HLExpression, HLProgram, HLStatement, HLValue, Intent, NumberArg,
+    PrintArrayParams,
+};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// The arrays and constants a [`LoweringContext`] has lowered so far,
+/// captured by [`snapshot`](LoweringContext::snapshot) to go back to later.
+#[derive(Debug, Clone)]
+pub struct LoweringSnapshot {
+    variable_counter: u32,
+    created_arrays: Vec<String>,
+    int_arrays: HashSet<String>,
+    timer: Option<String>,
+    matrix: Option<String>,
+    constants: HashMap<String, i64>,
+    results: HashMap<String, String>,
+}
 
 /// A stateful struct that handles the lowering process from IntentGraph to IR-HL.
 /// It keeps track of generated variables to chain operations together.
 pub struct LoweringContext {
     variable_counter: u32,
-    last_created_variable: Option<String>,
+    /// The variables holding each created array, in creation order.
+    created_arrays: Vec<String>,
+    /// The variables holding arrays of whole numbers, as created or as
+    /// converted by `ConvertArray`; every other array holds floating-point
+    /// numbers.
+    int_arrays: HashSet<String>,
+    /// The variable holding the most recent `StartTimer`'s timer.
+    timer: Option<String>,
+    /// The variable holding the most recently created matrix.
+    matrix: Option<String>,
+    /// The constants set by `SetVariable`, by name. They are substituted
+    /// where they are used and never reach the IR-HL.
+    constants: HashMap<String, i64>,
+    /// The variables holding the results named by `SumArray`, `MinArray`,
+    /// `MaxArray` and `CountElements`, by name. Intents that use a result
+    /// read its variable.
+    results: HashMap<String, String>,
+    /// Whether to bracket every intent with runtime profiling markers.
+    profiling: bool,
+    /// The seed for the program's random numbers, if it should be reproducible.
+    seed: Option<u64>,
+    error_mode: ErrorMode,
+    cancellation: CancellationToken,
+    /// The statements each intent lowered to since the last [`reset`], by
+    /// intent index, as positions in the statement list they were appended to.
+    ///
+    /// [`reset`]: LoweringContext::reset
+    intent_spans: Vec<(usize, Range<usize>)>,
 }
 
 // Implement the `Default` trait as suggested by Clippy.
@@ -21,10 +70,49 @@ impl LoweringContext {
     pub fn new() -> Self {
         LoweringContext {
             variable_counter: 0,
-            last_created_variable: None,
+            created_arrays: Vec::new(),
+            int_arrays: HashSet::new(),
+            timer: None,
+            matrix: None,
+            constants: HashMap::new(),
+            results: HashMap::new(),
+            profiling: false,
+            seed: None,
+            error_mode: ErrorMode::Abort,
+            cancellation: CancellationToken::new(),
+            intent_spans: Vec::new(),
         }
     }
 
+    /// Enables `--profile` instrumentation: the program starts with
+    /// `naldom_profile_init(<intent count>)` and every intent is wrapped in
+    /// `naldom_profile_begin(<index>, <name>)` / `naldom_profile_end(<index>)`.
+    pub fn with_profiling(mut self, profiling: bool) -> Self {
+        self.profiling = profiling;
+        self
+    }
+
+    /// Makes the program start with `naldom_seed_random(<seed>)`, so its
+    /// random arrays are the same on every run.
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Makes the program start with `naldom_set_error_mode(<code>)` when
+    /// `error_mode` is not the runtime's default, [`ErrorMode::Abort`].
+    pub fn with_error_mode(mut self, error_mode: ErrorMode) -> Self {
+        self.error_mode = error_mode;
+        self
+    }
+
+    /// Stops lowering at the next intent once `cancellation` is cancelled. The
+    /// program returned then is incomplete and must be discarded.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
     /// Generates a new, unique variable name (e.g., "var_0", "var_1").
     fn new_variable_name(&mut self) -> String {
         let name = format!("var_{}", self.variable_counter);
@@ -32,70 +120,794 @@ impl LoweringContext {
         name
     }
 
+    /// Forgets every array and variable lowered so far, so numbering starts
+    /// again at `var_0`. Configuration is kept.
+    pub fn reset(&mut self) {
+        self.variable_counter = 0;
+        self.created_arrays.clear();
+        self.int_arrays.clear();
+        self.timer = None;
+        self.matrix = None;
+        self.constants.clear();
+        self.results.clear();
+        self.intent_spans.clear();
+    }
+
+    /// The statements each intent lowered to, by intent index: for the
+    /// program [`lower`](Self::lower) returned last, positions in it. Notes
+    /// and constants lower to no statements, i.e. an empty range.
+    pub fn intent_spans(&self) -> &[(usize, Range<usize>)] {
+        &self.intent_spans
+    }
+
+    /// Captures the variables, arrays and constants lowered so far.
+    pub fn snapshot(&self) -> LoweringSnapshot {
+        LoweringSnapshot {
+            variable_counter: self.variable_counter,
+            created_arrays: self.created_arrays.clone(),
+            int_arrays: self.int_arrays.clone(),
+            timer: self.timer.clone(),
+            matrix: self.matrix.clone(),
+            constants: self.constants.clone(),
+            results: self.results.clone(),
+        }
+    }
+
+    /// Goes back to the state of `snapshot`: variable numbering continues
+    /// from where it was then. Configuration is kept.
+    pub fn restore(&mut self, snapshot: &LoweringSnapshot) {
+        self.variable_counter = snapshot.variable_counter;
+        self.created_arrays = snapshot.created_arrays.clone();
+        self.int_arrays = snapshot.int_arrays.clone();
+        self.timer = snapshot.timer.clone();
+        self.matrix = snapshot.matrix.clone();
+        self.constants = snapshot.constants.clone();
+        self.results = snapshot.results.clone();
+    }
+
+    /// The statements [`lower`](Self::lower) starts every program with,
+    /// before those of the first intent.
+    pub fn prelude(&self, intent_count: usize) -> Vec<HLStatement> {
+        let mut statements = Vec::new();
+        if self.error_mode != ErrorMode::Abort {
+            statements.push(HLStatement::Call {
+                function: FUNC_SET_ERROR_MODE.to_string(),
+                arguments: vec![HLExpression::Literal(HLValue::Integer(
+                    self.error_mode.code(),
+                ))],
+            });
+        }
+        if let Some(seed) = self.seed {
+            statements.push(HLStatement::Call {
+                function: FUNC_SEED_RANDOM.to_string(),
+                // IR-HL integers are `i64`; the runtime reads the same bits as `u64`.
+                arguments: vec![HLExpression::Literal(HLValue::Integer(seed as i64))],
+            });
+        }
+        if self.profiling {
+            statements.push(HLStatement::Call {
+                function: FUNC_PROFILE_INIT.to_string(),
+                arguments: vec![HLExpression::Literal(HLValue::Integer(intent_count as i64))],
+            });
+        }
+        statements
+    }
+
     /// The main function that transforms a sequence of intents into an HLProgram.
+    /// Each call lowers a separate program: the context is [`reset`] first.
+    ///
+    /// [`reset`]: LoweringContext::reset
     pub fn lower(&mut self, intent_graph: &[Intent]) -> HLProgram {
+        self.reset();
+        let mut statements = self.prelude(intent_graph.len());
+        self.lower_intents(intent_graph, 0, self.profiling, &mut statements);
+        HLProgram { statements }
+    }
+
+    /// Lowers `intent_graph` as a continuation of the intents lowered so far,
+    /// e.g. the next line typed into a REPL: variable numbering continues and
+    /// earlier arrays can be referenced. The statements neither reseed the
+    /// random generator nor carry profiling markers; those belong to whole
+    /// programs.
+    pub fn extend(&mut self, intent_graph: &[Intent]) -> HLProgram {
+        let mut statements = Vec::new();
+        self.lower_intents(intent_graph, 0, false, &mut statements);
+        HLProgram { statements }
+    }
+
+    /// Lowers `intent`, the one at `index` of a program whose earlier intents
+    /// have been lowered already, with the profiling markers
+    /// [`lower`](Self::lower) would give it. The program starts with
+    /// [`prelude`](Self::prelude).
+    pub fn lower_at(&mut self, index: usize, intent: &Intent) -> Vec<HLStatement> {
         let mut statements = Vec::new();
+        self.lower_intents(
+            std::slice::from_ref(intent),
+            index,
+            self.profiling,
+            &mut statements,
+        );
+        statements
+    }
 
-        for intent in intent_graph {
+    /// Appends the statements for each intent, bracketed by profiling markers
+    /// if `profiling` is set. The intents are numbered from `first_index`.
+    fn lower_intents(
+        &mut self,
+        intent_graph: &[Intent],
+        first_index: usize,
+        profiling: bool,
+        statements: &mut Vec<HLStatement>,
+    ) {
+        for (offset, intent) in intent_graph.iter().enumerate() {
+            let index = first_index + offset;
+            if self.cancellation.is_cancelled() {
+                break;
+            }
+            let start = statements.len();
+            if profiling {
+                statements.push(HLStatement::Call {
+                    function: FUNC_PROFILE_BEGIN.to_string(),
+                    arguments: vec![
+                        HLExpression::Literal(HLValue::Integer(index as i64)),
+                        HLExpression::Literal(HLValue::String(intent.name().to_string())),
+                    ],
+                });
+            }
             match intent {
                 Intent::CreateArray(params) => {
                     let new_var = self.new_variable_name();
+                    let whole_numbers = params.holds_whole_numbers();
+                    let expression = if params.values.is_empty() {
+                        HLExpression::FunctionCall {
+                            function: if whole_numbers {
+                                FUNC_CREATE_RANDOM_INT_ARRAY
+                            } else {
+                                FUNC_CREATE_RANDOM_ARRAY
+                            }
+                            .to_string(),
+                            arguments: vec![
+                                params
+                                    .size
+                                    .as_ref()
+                                    .map_or(HLExpression::Literal(HLValue::Integer(0)), |size| {
+                                        self.count(size)
+                                    }),
+                            ],
+                        }
+                    } else {
+                        HLExpression::FunctionCall {
+                            function: FUNC_CREATE_ARRAY_FROM_VALUES.to_string(),
+                            arguments: vec![
+                                HLExpression::Literal(HLValue::IntegerArray(params.values.clone())),
+                                HLExpression::Literal(HLValue::Integer(params.values.len() as i64)),
+                            ],
+                        }
+                    };
                     statements.push(HLStatement::Assign {
                         variable: new_var.clone(),
-                        expression: HLExpression::FunctionCall {
-                            function: FUNC_CREATE_RANDOM_ARRAY.to_string(),
-                            arguments: vec![HLExpression::Literal(HLValue::Integer(
-                                params.size as i64,
-                            ))],
-                        },
+                        expression,
                     });
-                    self.last_created_variable = Some(new_var);
+                    if whole_numbers {
+                        self.int_arrays.insert(new_var.clone());
+                    }
+                    self.created_arrays.push(new_var);
                 }
                 Intent::SortArray(params) => {
-                    if let Some(var_to_sort) = &self.last_created_variable {
+                    if let Some(var_to_sort) = self.target_variable(intent) {
                         statements.push(HLStatement::Call {
                             function: FUNC_SORT_ARRAY.to_string(),
                             arguments: vec![
                                 HLExpression::Variable(var_to_sort.clone()),
-                                HLExpression::Literal(HLValue::String(params.order.clone())),
+                                HLExpression::Literal(HLValue::String(
+                                    params.order.clone().unwrap_or_else(default_sort_order),
+                                )),
+                                HLExpression::Literal(HLValue::Integer(sort_algorithm_code(
+                                    params.algorithm.as_deref(),
+                                ))),
+                            ],
+                        });
+                    }
+                }
+                Intent::PrintArray(params) => {
+                    let full = params.as_ref().is_some_and(|p| p.full == Some(true));
+                    // Printing every array prints the first `target_ordinal`
+                    // arrays, in the order they were created.
+                    let vars_to_print = if params.as_ref().is_some_and(PrintArrayParams::prints_all)
+                    {
+                        let count = intent
+                            .target_ordinal()
+                            .map_or(self.created_arrays.len(), |count| count as usize);
+                        self.created_arrays.iter().take(count).collect()
+                    } else {
+                        self.target_variable(intent).into_iter().collect::<Vec<_>>()
+                    };
+                    for var_to_print in vars_to_print {
+                        let function = if self.int_arrays.contains(var_to_print) {
+                            FUNC_PRINT_INT_ARRAY
+                        } else {
+                            FUNC_PRINT_ARRAY
+                        };
+                        statements.push(HLStatement::Call {
+                            function: function.to_string(),
+                            arguments: vec![
+                                HLExpression::Variable(var_to_print.clone()),
+                                HLExpression::Literal(HLValue::Integer(i64::from(full))),
+                            ],
+                        });
+                    }
+                }
+                Intent::PrintHistogram(params) => {
+                    if let Some(var_to_chart) = self.target_variable(intent) {
+                        let buckets = params
+                            .as_ref()
+                            .and_then(|p| p.buckets)
+                            .unwrap_or(DEFAULT_HISTOGRAM_BUCKETS);
+                        statements.push(HLStatement::Call {
+                            function: FUNC_PRINT_HISTOGRAM.to_string(),
+                            arguments: vec![
+                                HLExpression::Variable(var_to_chart.clone()),
+                                HLExpression::Literal(HLValue::Integer(i64::from(buckets))),
+                            ],
+                        });
+                    }
+                }
+                Intent::DescribeArray(_) => {
+                    if let Some(var_to_describe) = self.target_variable(intent) {
+                        let name = intent.target_name().unwrap_or(var_to_describe);
+                        statements.push(HLStatement::Call {
+                            function: FUNC_DESCRIBE_ARRAY.to_string(),
+                            arguments: vec![
+                                HLExpression::Variable(var_to_describe.clone()),
+                                HLExpression::Literal(HLValue::String(name.to_string())),
+                            ],
+                        });
+                    }
+                }
+                Intent::SumArray(_)
+                | Intent::MinArray(_)
+                | Intent::MaxArray(_)
+                | Intent::CountElements(_) => {
+                    let function = match intent {
+                        Intent::SumArray(_) => FUNC_SUM_ARRAY,
+                        Intent::MinArray(_) => FUNC_MIN_ARRAY,
+                        Intent::MaxArray(_) => FUNC_MAX_ARRAY,
+                        _ => FUNC_ARRAY_LENGTH,
+                    };
+                    if let Some(source_var) = self.target_variable(intent).cloned() {
+                        let result_var = self.new_variable_name();
+                        statements.push(HLStatement::Assign {
+                            variable: result_var.clone(),
+                            expression: HLExpression::FunctionCall {
+                                function: function.to_string(),
+                                arguments: vec![HLExpression::Variable(source_var)],
+                            },
+                        });
+                        statements.push(HLStatement::Call {
+                            function: FUNC_PRINT_INTEGER.to_string(),
+                            arguments: vec![HLExpression::Variable(result_var.clone())],
+                        });
+                        if let Some(name) = intent.result_name() {
+                            self.constants.remove(name);
+                            self.results.insert(name.to_string(), result_var);
+                        }
+                    }
+                }
+                Intent::AverageArray(_) | Intent::MedianArray(_) => {
+                    let function = match intent {
+                        Intent::AverageArray(_) => FUNC_AVERAGE_ARRAY,
+                        _ => FUNC_MEDIAN_ARRAY,
+                    };
+                    if let Some(source_var) = self.target_variable(intent).cloned() {
+                        let result_var = self.new_variable_name();
+                        statements.push(HLStatement::Assign {
+                            variable: result_var.clone(),
+                            expression: HLExpression::FunctionCall {
+                                function: function.to_string(),
+                                arguments: vec![HLExpression::Variable(source_var)],
+                            },
+                        });
+                        statements.push(HLStatement::Call {
+                            function: FUNC_PRINT_FLOAT.to_string(),
+                            arguments: vec![HLExpression::Variable(result_var)],
+                        });
+                    }
+                }
+                Intent::FindIndex(params) => {
+                    if let Some(source_var) = self.target_variable(intent).cloned() {
+                        let result_var = self.new_variable_name();
+                        statements.push(HLStatement::Assign {
+                            variable: result_var.clone(),
+                            expression: HLExpression::FunctionCall {
+                                function: FUNC_FIND_INDEX.to_string(),
+                                arguments: vec![
+                                    HLExpression::Variable(source_var),
+                                    HLExpression::Literal(HLValue::Integer(params.value)),
+                                ],
+                            },
+                        });
+                        statements.push(HLStatement::Call {
+                            function: FUNC_PRINT_INTEGER.to_string(),
+                            arguments: vec![HLExpression::Variable(result_var)],
+                        });
+                    }
+                }
+                Intent::ContainsValue(params) => {
+                    if let Some(source_var) = self.target_variable(intent).cloned() {
+                        let result_var = self.new_variable_name();
+                        statements.push(HLStatement::Assign {
+                            variable: result_var.clone(),
+                            expression: HLExpression::FunctionCall {
+                                function: FUNC_ARRAY_CONTAINS.to_string(),
+                                arguments: vec![
+                                    HLExpression::Variable(source_var),
+                                    HLExpression::Literal(HLValue::Integer(params.value)),
+                                ],
+                            },
+                        });
+                        statements.push(HLStatement::Call {
+                            function: FUNC_PRINT_BOOL.to_string(),
+                            arguments: vec![HLExpression::Variable(result_var)],
+                        });
+                    }
+                }
+                Intent::ReverseArray(_) => {
+                    if let Some(var_to_reverse) = self.target_variable(intent) {
+                        statements.push(HLStatement::Call {
+                            function: FUNC_REVERSE_ARRAY.to_string(),
+                            arguments: vec![HLExpression::Variable(var_to_reverse.clone())],
+                        });
+                    }
+                }
+                Intent::AppendToArray(params) => {
+                    if let Some(var_to_grow) = self.target_variable(intent) {
+                        statements.push(HLStatement::Call {
+                            function: FUNC_APPEND_TO_ARRAY.to_string(),
+                            arguments: vec![
+                                HLExpression::Variable(var_to_grow.clone()),
+                                HLExpression::Literal(HLValue::Integer(params.value)),
+                            ],
+                        });
+                    }
+                }
+                Intent::FillArray(params) => {
+                    if let Some(var_to_fill) = self.target_variable(intent) {
+                        statements.push(HLStatement::Call {
+                            function: FUNC_FILL_ARRAY.to_string(),
+                            arguments: vec![
+                                HLExpression::Variable(var_to_fill.clone()),
+                                HLExpression::Literal(HLValue::Integer(params.value)),
+                            ],
+                        });
+                    }
+                }
+                Intent::ClearArray(_) => {
+                    if let Some(var_to_clear) = self.target_variable(intent) {
+                        statements.push(HLStatement::Call {
+                            function: FUNC_CLEAR_ARRAY.to_string(),
+                            arguments: vec![HLExpression::Variable(var_to_clear.clone())],
+                        });
+                    }
+                }
+                // Dividing whole numbers truncates the quotients, by
+                // converting the array to whole numbers again.
+                Intent::MapArray(params) => {
+                    if let Some(var_to_map) = self.target_variable(intent).cloned() {
+                        statements.push(HLStatement::Call {
+                            function: FUNC_MAP_ARRAY.to_string(),
+                            arguments: vec![
+                                HLExpression::Variable(var_to_map.clone()),
+                                HLExpression::Literal(HLValue::Integer(map_op_code(
+                                    &params.operation,
+                                ))),
+                                self.number(&params.operand),
                             ],
                         });
+                        if params.operation == "divide" && self.int_arrays.contains(&var_to_map) {
+                            self.convert(intent, var_to_map, true, statements);
+                        }
+                    }
+                }
+                // Converting to the type the array already has is a no-op;
+                // the analyzer warns about it.
+                Intent::ConvertArray(params) => {
+                    if let Some(source_var) = self.target_variable(intent).cloned() {
+                        let to_int = params.to == "int";
+                        if self.int_arrays.contains(&source_var) != to_int {
+                            self.convert(intent, source_var, to_int, statements);
+                        }
+                    }
+                }
+                Intent::ConcatArrays(params) => {
+                    let operand = |ordinal: Option<u32>| {
+                        let index = (ordinal? as usize).checked_sub(1)?;
+                        self.created_arrays.get(index).cloned()
+                    };
+                    if let (Some(first), Some(second)) = (
+                        operand(params.first_ordinal),
+                        operand(params.second_ordinal),
+                    ) {
+                        let new_var = self.new_variable_name();
+                        statements.push(HLStatement::Assign {
+                            variable: new_var.clone(),
+                            expression: HLExpression::FunctionCall {
+                                function: FUNC_CONCAT_ARRAYS.to_string(),
+                                arguments: vec![
+                                    HLExpression::Variable(first.clone()),
+                                    HLExpression::Variable(second),
+                                ],
+                            },
+                        });
+                        // The analyzer rejects operands of differing types.
+                        if self.int_arrays.contains(&first) {
+                            self.int_arrays.insert(new_var.clone());
+                        }
+                        self.created_arrays.push(new_var);
                     }
                 }
-                Intent::PrintArray => {
-                    if let Some(var_to_print) = &self.last_created_variable {
+                Intent::CopyArray(_) => {
+                    if let Some(source) = self.target_variable(intent).cloned() {
+                        let new_var = self.new_variable_name();
+                        statements.push(HLStatement::Assign {
+                            variable: new_var.clone(),
+                            expression: HLExpression::FunctionCall {
+                                function: FUNC_COPY_ARRAY.to_string(),
+                                arguments: vec![HLExpression::Variable(source.clone())],
+                            },
+                        });
+                        if self.int_arrays.contains(&source) {
+                            self.int_arrays.insert(new_var.clone());
+                        }
+                        self.created_arrays.push(new_var);
+                    }
+                }
+                // An open-ended slice ends at the array's current length, and
+                // one counting from the end starts that far before it. A start
+                // before the first element is clamped by the runtime.
+                Intent::SliceArray(params) => {
+                    if let Some(var_to_slice) = self.target_variable(intent).cloned() {
+                        let start =
+                            HLExpression::Literal(HLValue::Integer(i64::from(params.start)));
+                        let (start, end) = match params.end {
+                            Some(end) if !params.from_end => (
+                                start,
+                                HLExpression::Literal(HLValue::Integer(i64::from(end))),
+                            ),
+                            _ => {
+                                let length_var = self.new_variable_name();
+                                statements.push(HLStatement::Assign {
+                                    variable: length_var.clone(),
+                                    expression: HLExpression::FunctionCall {
+                                        function: FUNC_ARRAY_LENGTH.to_string(),
+                                        arguments: vec![HLExpression::Variable(
+                                            var_to_slice.clone(),
+                                        )],
+                                    },
+                                });
+                                let length = HLExpression::Variable(length_var);
+                                let start = if params.from_end {
+                                    HLExpression::BinaryOp {
+                                        op: BinOp::Sub,
+                                        lhs: Box::new(length.clone()),
+                                        rhs: Box::new(start),
+                                    }
+                                } else {
+                                    start
+                                };
+                                (start, length)
+                            }
+                        };
                         statements.push(HLStatement::Call {
-                            function: FUNC_PRINT_ARRAY.to_string(),
-                            arguments: vec![HLExpression::Variable(var_to_print.clone())],
+                            function: FUNC_SLICE_ARRAY.to_string(),
+                            arguments: vec![HLExpression::Variable(var_to_slice), start, end],
+                        });
+                    }
+                }
+                // Repeats are unrolled. Profiling markers bracket the whole
+                // repeat, not each pass through its body.
+                Intent::Repeat(params) => {
+                    for _ in 0..params.count {
+                        self.lower_intents(&params.body, 0, false, statements);
+                    }
+                }
+                Intent::Conditional(params) => {
+                    if let Some(tested_var) = self.target_variable(intent).cloned() {
+                        let then_body = self.lower_branch(&params.then_body);
+                        let else_body = self.lower_branch(&params.else_body);
+                        statements.push(HLStatement::If {
+                            condition: HLExpression::FunctionCall {
+                                function: condition_function(&params.condition).to_string(),
+                                arguments: vec![HLExpression::Variable(tested_var)],
+                            },
+                            then_body,
+                            else_body,
                         });
                     }
                 }
                 Intent::Wait(params) => {
                     statements.push(HLStatement::Call {
                         function: FUNC_ASYNC_SLEEP.to_string(),
+                        // The analyzer bounds waits by `MAX_WAIT_MS`, well below i64::MAX.
                         arguments: vec![HLExpression::Literal(HLValue::Integer(
-                            params.duration_ms as i64,
+                            i64::try_from(params.duration_ms).unwrap_or(i64::MAX),
                         ))],
                     });
                 }
+                Intent::CreateMatrix(params) => {
+                    let matrix = self.new_variable_name();
+                    statements.push(HLStatement::Assign {
+                        variable: matrix.clone(),
+                        expression: HLExpression::FunctionCall {
+                            function: FUNC_CREATE_RANDOM_MATRIX.to_string(),
+                            arguments: vec![
+                                HLExpression::Literal(HLValue::Integer(i64::from(params.rows))),
+                                HLExpression::Literal(HLValue::Integer(i64::from(params.cols))),
+                            ],
+                        },
+                    });
+                    self.matrix = Some(matrix);
+                }
+                Intent::PrintMatrix(_) => {
+                    if let Some(matrix) = &self.matrix {
+                        statements.push(HLStatement::Call {
+                            function: FUNC_PRINT_MATRIX.to_string(),
+                            arguments: vec![HLExpression::Variable(matrix.clone())],
+                        });
+                    }
+                }
+                Intent::StartTimer(_) => {
+                    let timer = self.new_variable_name();
+                    statements.push(HLStatement::Assign {
+                        variable: timer.clone(),
+                        expression: HLExpression::FunctionCall {
+                            function: FUNC_START_TIMER.to_string(),
+                            arguments: Vec::new(),
+                        },
+                    });
+                    self.timer = Some(timer);
+                }
+                Intent::PrintElapsed(_) => {
+                    if let Some(timer) = &self.timer {
+                        statements.push(HLStatement::Call {
+                            function: FUNC_PRINT_ELAPSED.to_string(),
+                            arguments: vec![HLExpression::Variable(timer.clone())],
+                        });
+                    }
+                }
+                Intent::Exit(params) => {
+                    let code = params.as_ref().and_then(|p| p.code).unwrap_or(0);
+                    statements.push(HLStatement::Call {
+                        function: FUNC_EXIT.to_string(),
+                        arguments: vec![HLExpression::Literal(HLValue::Integer(code.into()))],
+                    });
+                }
+                // Notes carry no behavior; they only surface as analyzer warnings.
+                Intent::Note(_) => {}
+                Intent::SetVariable(params) => {
+                    self.results.remove(&params.name);
+                    self.constants.insert(params.name.clone(), params.value);
+                }
+                // Procedures are expanded by `stdlib::expand` before analysis.
+                Intent::UseProcedure(_) => {}
             }
+            if profiling {
+                statements.push(HLStatement::Call {
+                    function: FUNC_PROFILE_END.to_string(),
+                    arguments: vec![HLExpression::Literal(HLValue::Integer(index as i64))],
+                });
+            }
+            self.intent_spans.push((index, start..statements.len()));
         }
+    }
 
-        HLProgram { statements }
+    /// Lowers one branch of a `Conditional`. Arrays, matrices, timers,
+    /// constants and results it creates are only visible inside it; variable
+    /// numbering continues across branches.
+    fn lower_branch(&mut self, body: &[Intent]) -> Vec<HLStatement> {
+        // Cloned rather than truncated: a conversion in the branch rebinds
+        // an array created before it.
+        let created_arrays = self.created_arrays.clone();
+        let timer = self.timer.clone();
+        let matrix = self.matrix.clone();
+        let constants = self.constants.clone();
+        let results = self.results.clone();
+        let mut statements = Vec::new();
+        self.lower_intents(body, 0, false, &mut statements);
+        self.created_arrays = created_arrays;
+        self.timer = timer;
+        self.matrix = matrix;
+        self.constants = constants;
+        self.results = results;
+        statements
+    }
+
+    /// Binds a new variable to `source_var`'s array converted to whole
+    /// numbers (`to_int`) or floating-point numbers, and makes it the
+    /// variable of the array `intent` acts on.
+    fn convert(
+        &mut self,
+        intent: &Intent,
+        source_var: String,
+        to_int: bool,
+        statements: &mut Vec<HLStatement>,
+    ) {
+        let function = if to_int {
+            FUNC_ARRAY_TO_INT
+        } else {
+            FUNC_ARRAY_TO_FLOAT
+        };
+        let new_var = self.new_variable_name();
+        statements.push(HLStatement::Assign {
+            variable: new_var.clone(),
+            expression: HLExpression::FunctionCall {
+                function: function.to_string(),
+                arguments: vec![HLExpression::Variable(source_var)],
+            },
+        });
+        if to_int {
+            self.int_arrays.insert(new_var.clone());
+        }
+        let index = match intent.target_ordinal() {
+            Some(ordinal) => (ordinal as usize).saturating_sub(1),
+            None => self.created_arrays.len().saturating_sub(1),
+        };
+        if let Some(slot) = self.created_arrays.get_mut(index) {
+            *slot = new_var;
+        }
+    }
+
+    /// The value of a count parameter: a literal, or the variable holding a
+    /// named result. The analyzer has checked that every variable it names
+    /// was set.
+    fn count(&self, count: &CountArg) -> HLExpression {
+        match count {
+            CountArg::Literal(count) => HLExpression::Literal(HLValue::Integer(i64::from(*count))),
+            CountArg::Variable(name) => self.variable_value(name),
+        }
+    }
+
+    /// The value of a number parameter; see [`count`](Self::count).
+    fn number(&self, number: &NumberArg) -> HLExpression {
+        match number {
+            NumberArg::Literal(number) => HLExpression::Literal(HLValue::Integer(*number)),
+            NumberArg::Variable(name) => self.variable_value(name),
+        }
     }
+
+    /// The value `name` stands for: the variable holding the result of that
+    /// name, otherwise the constant's value.
+    fn variable_value(&self, name: &str) -> HLExpression {
+        match self.results.get(name) {
+            Some(variable) => HLExpression::Variable(variable.clone()),
+            None => HLExpression::Literal(HLValue::Integer(
+                self.constants.get(name).copied().unwrap_or_default(),
+            )),
+        }
+    }
+
+    /// The variable bound to the array `intent` acts on: the one the analyzer
+    /// resolved its ordinal to, or the most recently created array.
+    fn target_variable(&self, intent: &Intent) -> Option<&String> {
+        match intent.target_ordinal() {
+            Some(ordinal) => self.created_arrays.get((ordinal as usize).checked_sub(1)?),
+            None => self.created_arrays.last(),
+        }
+    }
+}
+
+/// The runtime function that makes the test `condition`, one of the
+/// analyzer's `CONDITIONS`.
+fn condition_function(condition: &str) -> &'static str {
+    match condition {
+        "is_empty" => FUNC_IS_EMPTY,
+        _ => FUNC_IS_SORTED,
+    }
+}
+
+/// The code `map_array` takes for `operation`: its position in the
+/// analyzer's `MAP_OPERATIONS`.
+fn map_op_code(operation: &str) -> i64 {
+    MAP_OPERATIONS
+        .iter()
+        .position(|known| *known == operation)
+        .unwrap_or_default() as i64
+}
+
+/// The code `sort_array` takes for `algorithm`: 0 to let the runtime choose,
+/// otherwise one past its position in the analyzer's `SORT_ALGORITHMS`.
+fn sort_algorithm_code(algorithm: Option<&str>) -> i64 {
+    algorithm
+        .and_then(|algorithm| SORT_ALGORITHMS.iter().position(|known| *known == algorithm))
+        .map_or(0, |position| position as i64 + 1)
 }
 
 // Constants for function names to avoid magic strings.
 const FUNC_CREATE_RANDOM_ARRAY: &str = "create_random_array";
+const FUNC_CREATE_RANDOM_INT_ARRAY: &str = "create_random_int_array";
+const FUNC_CREATE_ARRAY_FROM_VALUES: &str = "create_array_from_values";
 const FUNC_SORT_ARRAY: &str = "sort_array";
 const FUNC_PRINT_ARRAY: &str = "print_array";
+const FUNC_SUM_ARRAY: &str = "sum_array";
+const FUNC_MIN_ARRAY: &str = "min_array";
+const FUNC_MAX_ARRAY: &str = "max_array";
+const FUNC_ARRAY_LENGTH: &str = "array_length";
+const FUNC_PRINT_INTEGER: &str = "print_integer";
+const FUNC_AVERAGE_ARRAY: &str = "average_array";
+const FUNC_MEDIAN_ARRAY: &str = "median_array";
+const FUNC_PRINT_FLOAT: &str = "print_float";
+const FUNC_ARRAY_CONTAINS: &str = "array_contains";
+const FUNC_FIND_INDEX: &str = "find_index";
+const FUNC_PRINT_HISTOGRAM: &str = "print_histogram";
+const FUNC_DESCRIBE_ARRAY: &str = "describe_array";
+const FUNC_PRINT_BOOL: &str = "print_bool";
+const FUNC_REVERSE_ARRAY: &str = "reverse_array";
+const FUNC_APPEND_TO_ARRAY: &str = "append_to_array";
+const FUNC_FILL_ARRAY: &str = "fill_array";
+const FUNC_CLEAR_ARRAY: &str = "clear_array";
+const FUNC_SLICE_ARRAY: &str = "slice_array";
+const FUNC_MAP_ARRAY: &str = "map_array";
+const FUNC_CONCAT_ARRAYS: &str = "concat_arrays";
+const FUNC_COPY_ARRAY: &str = "copy_array";
+const FUNC_ARRAY_TO_FLOAT: &str = "array_to_float";
+const FUNC_ARRAY_TO_INT: &str = "array_to_int";
+const FUNC_PRINT_INT_ARRAY: &str = "print_int_array";
+const FUNC_IS_SORTED: &str = "is_sorted";
+const FUNC_IS_EMPTY: &str = "is_empty";
+const FUNC_SEED_RANDOM: &str = "naldom_seed_random";
+const FUNC_CREATE_RANDOM_MATRIX: &str = "create_random_matrix";
+const FUNC_PRINT_MATRIX: &str = "print_matrix";
+const FUNC_START_TIMER: &str = "start_timer";
+const FUNC_PRINT_ELAPSED: &str = "print_elapsed";
 const FUNC_ASYNC_SLEEP: &str = "naldom_async_sleep";
+const FUNC_EXIT: &str = "naldom_exit";
+const FUNC_SET_ERROR_MODE: &str = "naldom_set_error_mode";
+const FUNC_PROFILE_INIT: &str = "naldom_profile_init";
+const FUNC_PROFILE_BEGIN: &str = "naldom_profile_begin";
+const FUNC_PROFILE_END: &str = "naldom_profile_end";
+
+/// The registry default for `PrintHistogram.buckets`, for graphs lowered
+/// without analysis.
+const DEFAULT_HISTOGRAM_BUCKETS: u32 = 10;
+
+/// The registry default for `SortArray.order`, for graphs lowered without
+/// analysis.
+fn default_sort_order() -> String {
+    schema::default_value("SortArray", "order")
+        .as_ref()
+        .and_then(serde_json::Value::as_str)
+        .expect("the registry gives SortArray.order a string default")
+        .to_string()
+}
 
 // --- Unit Tests ---
 #[cfg(test)]
 mod tests {
     use super::*;
-    use naldom_ir::WaitParams;
+    use naldom_ir::{
+        AppendParams, ClearArrayParams, ConcatParams, ConvertParams, CopyParams,
+        CountElementsParams, CreateArrayParams, CreateMatrixParams, DescribeArrayParams,
+        ExitParams, FillParams, FindIndexParams, HistogramParams, MapParams, NoteParams,
+        RepeatParams, SetVariableParams, SliceParams, SortArrayParams, WaitParams,
+    };
+
+    #[test]
+    fn test_exit_calls_the_runtime_with_its_code() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::Exit(Some(ExitParams { code: Some(3) })),
+            Intent::Exit(None),
+        ];
+
+        // Act
+        let hl_program = LoweringContext::new().lower(&intent_graph);
+
+        // Assert
+        let exit = |code| HLStatement::Call {
+            function: FUNC_EXIT.to_string(),
+            arguments: vec![HLExpression::Literal(HLValue::Integer(code))],
+        };
+        assert_eq!(hl_program.statements, [exit(3), exit(0)]);
+    }
 
     #[test]
     fn test_lowering_wait_intent() {
@@ -114,4 +926,1249 @@ mod tests {
         };
         assert_eq!(hl_program.statements[0], expected_statement);
     }
+
+    #[test]
+    fn test_lowering_note_emits_nothing() {
+        // Arrange
+        let intent_graph = vec![Intent::Note(NoteParams {
+            text: "Just a remark.".to_string(),
+        })];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert!(hl_program.statements.is_empty());
+    }
+
+    #[test]
+    fn test_lowering_substitutes_constants_into_counts() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::SetVariable(SetVariableParams {
+                name: "count".to_string(),
+                value: 12,
+            }),
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Variable("count".to_string())),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements,
+            [HLStatement::Assign {
+                variable: "var_0".to_string(),
+                expression: HLExpression::FunctionCall {
+                    function: FUNC_CREATE_RANDOM_ARRAY.to_string(),
+                    arguments: vec![HLExpression::Literal(HLValue::Integer(12))],
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lowering_passes_a_named_result_by_its_variable() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::CountElements(Some(CountElementsParams {
+                target_ordinal: None,
+                name: Some("n".to_string()),
+            })),
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Variable("n".to_string())),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::MapArray(MapParams {
+                operation: "multiply".to_string(),
+                operand: NumberArg::Variable("n".to_string()),
+                target_ordinal: None,
+            }),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        let count = HLExpression::Variable("var_1".to_string());
+        assert_eq!(
+            hl_program.statements[3..],
+            [
+                HLStatement::Assign {
+                    variable: "var_2".to_string(),
+                    expression: HLExpression::FunctionCall {
+                        function: FUNC_CREATE_RANDOM_ARRAY.to_string(),
+                        arguments: vec![count.clone()],
+                    },
+                },
+                HLStatement::Call {
+                    function: FUNC_MAP_ARRAY.to_string(),
+                    arguments: vec![
+                        HLExpression::Variable("var_2".to_string()),
+                        HLExpression::Literal(HLValue::Integer(map_op_code("multiply"))),
+                        count,
+                    ],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lowering_passes_listed_values_and_their_count() {
+        // Arrange
+        let intent_graph = vec![Intent::CreateArray(CreateArrayParams {
+            size: Some(CountArg::Literal(3)),
+            name: None,
+            values: vec![3, 1, 4],
+            dtype: None,
+        })];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements,
+            [HLStatement::Assign {
+                variable: "var_0".to_string(),
+                expression: HLExpression::FunctionCall {
+                    function: FUNC_CREATE_ARRAY_FROM_VALUES.to_string(),
+                    arguments: vec![
+                        HLExpression::Literal(HLValue::IntegerArray(vec![3, 1, 4])),
+                        HLExpression::Literal(HLValue::Integer(3)),
+                    ],
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lowering_sum_array_binds_and_prints_the_total() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::SumArray(None),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements[1..],
+            [
+                HLStatement::Assign {
+                    variable: "var_1".to_string(),
+                    expression: HLExpression::FunctionCall {
+                        function: FUNC_SUM_ARRAY.to_string(),
+                        arguments: vec![HLExpression::Variable("var_0".to_string())],
+                    },
+                },
+                HLStatement::Call {
+                    function: FUNC_PRINT_INTEGER.to_string(),
+                    arguments: vec![HLExpression::Variable("var_1".to_string())],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lowering_reverse_array_calls_the_runtime_in_place() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::ReverseArray(None),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements[1..],
+            [HLStatement::Call {
+                function: FUNC_REVERSE_ARRAY.to_string(),
+                arguments: vec![HLExpression::Variable("var_0".to_string())],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lowering_fill_passes_the_array_and_the_value() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(3)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::FillArray(FillParams {
+                value: 0,
+                target_ordinal: Some(1),
+            }),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements[1..],
+            [HLStatement::Call {
+                function: FUNC_FILL_ARRAY.to_string(),
+                arguments: vec![
+                    HLExpression::Variable("var_0".to_string()),
+                    HLExpression::Literal(HLValue::Integer(0)),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lowering_clear_empties_the_targeted_array() {
+        // Arrange
+        let create = Intent::CreateArray(CreateArrayParams {
+            size: Some(CountArg::Literal(3)),
+            name: None,
+            values: Vec::new(),
+            dtype: None,
+        });
+        let intent_graph = vec![
+            create.clone(),
+            create,
+            Intent::ClearArray(Some(ClearArrayParams {
+                target_ordinal: Some(1),
+            })),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements[2..],
+            [HLStatement::Call {
+                function: FUNC_CLEAR_ARRAY.to_string(),
+                arguments: vec![HLExpression::Variable("var_0".to_string())],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lowering_append_passes_the_array_and_the_value() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(3)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::AppendToArray(AppendParams {
+                value: 42,
+                target_ordinal: Some(1),
+            }),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements[1..],
+            [HLStatement::Call {
+                function: FUNC_APPEND_TO_ARRAY.to_string(),
+                arguments: vec![
+                    HLExpression::Variable("var_0".to_string()),
+                    HLExpression::Literal(HLValue::Integer(42)),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lowering_copy_binds_a_new_array() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(3)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::CopyArray(CopyParams {
+                name: Some("b".to_string()),
+                target: None,
+                target_ordinal: Some(1),
+            }),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements[1],
+            HLStatement::Assign {
+                variable: "var_1".to_string(),
+                expression: HLExpression::FunctionCall {
+                    function: FUNC_COPY_ARRAY.to_string(),
+                    arguments: vec![HLExpression::Variable("var_0".to_string())],
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_printing_all_arrays_prints_each_in_creation_order() {
+        // Arrange
+        let create = |dtype: Option<&str>| {
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(3)),
+                name: None,
+                values: Vec::new(),
+                dtype: dtype.map(str::to_string),
+            })
+        };
+        let intent_graph = vec![
+            create(None),
+            create(Some("int")),
+            create(None),
+            Intent::PrintArray(Some(PrintArrayParams {
+                target: Some("all".to_string()),
+                target_ordinal: Some(3),
+                full: Some(true),
+            })),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        let print = |function: &str, var: &str| HLStatement::Call {
+            function: function.to_string(),
+            arguments: vec![
+                HLExpression::Variable(var.to_string()),
+                HLExpression::Literal(HLValue::Integer(1)),
+            ],
+        };
+        assert_eq!(
+            hl_program.statements[3..],
+            [
+                print(FUNC_PRINT_ARRAY, "var_0"),
+                print(FUNC_PRINT_INT_ARRAY, "var_1"),
+                print(FUNC_PRINT_ARRAY, "var_2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lowering_concat_binds_a_new_array() {
+        // Arrange
+        let create = Intent::CreateArray(CreateArrayParams {
+            size: Some(CountArg::Literal(3)),
+            name: None,
+            values: Vec::new(),
+            dtype: None,
+        });
+        let intent_graph = vec![
+            create.clone(),
+            create,
+            Intent::ConcatArrays(ConcatParams {
+                first: "a".to_string(),
+                second: "b".to_string(),
+                name: None,
+                first_ordinal: Some(2),
+                second_ordinal: Some(1),
+            }),
+            Intent::PrintArray(None),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements[2..],
+            [
+                HLStatement::Assign {
+                    variable: "var_2".to_string(),
+                    expression: HLExpression::FunctionCall {
+                        function: FUNC_CONCAT_ARRAYS.to_string(),
+                        arguments: vec![
+                            HLExpression::Variable("var_1".to_string()),
+                            HLExpression::Variable("var_0".to_string()),
+                        ],
+                    },
+                },
+                HLStatement::Call {
+                    function: FUNC_PRINT_ARRAY.to_string(),
+                    arguments: vec![
+                        HLExpression::Variable("var_2".to_string()),
+                        HLExpression::Literal(HLValue::Integer(0)),
+                    ],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lowering_map_passes_the_operation_code() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(10)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::MapArray(MapParams {
+                operation: "multiply".to_string(),
+                operand: NumberArg::Literal(3),
+                target_ordinal: Some(1),
+            }),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements[1..],
+            [HLStatement::Call {
+                function: FUNC_MAP_ARRAY.to_string(),
+                arguments: vec![
+                    HLExpression::Variable("var_0".to_string()),
+                    HLExpression::Literal(HLValue::Integer(2)),
+                    HLExpression::Literal(HLValue::Integer(3)),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lowering_int_arrays_creates_and_prints_whole_numbers() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(4)),
+                name: None,
+                values: Vec::new(),
+                dtype: Some("int".to_string()),
+            }),
+            Intent::PrintArray(None),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements,
+            [
+                HLStatement::Assign {
+                    variable: "var_0".to_string(),
+                    expression: HLExpression::FunctionCall {
+                        function: FUNC_CREATE_RANDOM_INT_ARRAY.to_string(),
+                        arguments: vec![HLExpression::Literal(HLValue::Integer(4))],
+                    },
+                },
+                HLStatement::Call {
+                    function: FUNC_PRINT_INT_ARRAY.to_string(),
+                    arguments: vec![
+                        HLExpression::Variable("var_0".to_string()),
+                        HLExpression::Literal(HLValue::Integer(0)),
+                    ],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_listed_values_print_as_whole_numbers_unless_typed_float() {
+        // Arrange
+        let listed = |dtype: Option<&str>| {
+            vec![
+                Intent::CreateArray(CreateArrayParams {
+                    size: Some(CountArg::Literal(2)),
+                    name: None,
+                    values: vec![1, 2],
+                    dtype: dtype.map(str::to_string),
+                }),
+                Intent::PrintArray(None),
+            ]
+        };
+        let print_function = |intent_graph: &[Intent]| match &LoweringContext::new()
+            .lower(intent_graph)
+            .statements[1]
+        {
+            HLStatement::Call { function, .. } => function.clone(),
+            other => panic!("Expected a call, got {:?}", other),
+        };
+
+        // Act
+        let untyped = print_function(&listed(None));
+        let float = print_function(&listed(Some("float")));
+
+        // Assert
+        assert_eq!(untyped, FUNC_PRINT_INT_ARRAY);
+        assert_eq!(float, FUNC_PRINT_ARRAY);
+    }
+
+    #[test]
+    fn test_lowering_prints_the_most_recent_matrix() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateMatrix(CreateMatrixParams { rows: 2, cols: 3 }),
+            Intent::CreateMatrix(CreateMatrixParams { rows: 4, cols: 1 }),
+            Intent::PrintMatrix(None),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        let create = |variable: &str, rows: i64, cols: i64| HLStatement::Assign {
+            variable: variable.to_string(),
+            expression: HLExpression::FunctionCall {
+                function: FUNC_CREATE_RANDOM_MATRIX.to_string(),
+                arguments: vec![
+                    HLExpression::Literal(HLValue::Integer(rows)),
+                    HLExpression::Literal(HLValue::Integer(cols)),
+                ],
+            },
+        };
+        assert_eq!(
+            hl_program.statements,
+            [
+                create("var_0", 2, 3),
+                create("var_1", 4, 1),
+                HLStatement::Call {
+                    function: FUNC_PRINT_MATRIX.to_string(),
+                    arguments: vec![HLExpression::Variable("var_1".to_string())],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lowering_convert_rebinds_the_array_and_dispatches_on_its_type() {
+        // Arrange
+        let convert = |to: &str| {
+            Intent::ConvertArray(ConvertParams {
+                to: to.to_string(),
+                target_ordinal: Some(1),
+            })
+        };
+        let print = Intent::PrintArray(Some(PrintArrayParams {
+            target_ordinal: Some(1),
+            ..PrintArrayParams::default()
+        }));
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(3)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            convert("int"),
+            convert("int"),
+            print.clone(),
+            convert("float"),
+            print,
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        let call = |function: &str, variable: &str| HLExpression::FunctionCall {
+            function: function.to_string(),
+            arguments: vec![HLExpression::Variable(variable.to_string())],
+        };
+        let print = |function: &str, variable: &str| HLStatement::Call {
+            function: function.to_string(),
+            arguments: vec![
+                HLExpression::Variable(variable.to_string()),
+                HLExpression::Literal(HLValue::Integer(0)),
+            ],
+        };
+        assert_eq!(
+            hl_program.statements[1..],
+            [
+                HLStatement::Assign {
+                    variable: "var_1".to_string(),
+                    expression: call(FUNC_ARRAY_TO_INT, "var_0"),
+                },
+                print(FUNC_PRINT_INT_ARRAY, "var_1"),
+                HLStatement::Assign {
+                    variable: "var_2".to_string(),
+                    expression: call(FUNC_ARRAY_TO_FLOAT, "var_1"),
+                },
+                print(FUNC_PRINT_ARRAY, "var_2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lowering_divides_whole_numbers_with_truncation() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(3)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::ConvertArray(ConvertParams {
+                to: "int".to_string(),
+                target_ordinal: Some(1),
+            }),
+            Intent::MapArray(MapParams {
+                operation: "divide".to_string(),
+                operand: NumberArg::Literal(2),
+                target_ordinal: Some(1),
+            }),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements.last(),
+            Some(&HLStatement::Assign {
+                variable: "var_2".to_string(),
+                expression: HLExpression::FunctionCall {
+                    function: FUNC_ARRAY_TO_INT.to_string(),
+                    arguments: vec![HLExpression::Variable("var_1".to_string())],
+                },
+            })
+        );
+        assert_eq!(context.target_variable(&intent_graph[2]).unwrap(), "var_2");
+    }
+
+    #[test]
+    fn test_lowering_slice_passes_the_bounds() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(10)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::SliceArray(SliceParams {
+                start: 0,
+                end: Some(5),
+                from_end: false,
+                target_ordinal: Some(1),
+            }),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements[1..],
+            [HLStatement::Call {
+                function: FUNC_SLICE_ARRAY.to_string(),
+                arguments: vec![
+                    HLExpression::Variable("var_0".to_string()),
+                    HLExpression::Literal(HLValue::Integer(0)),
+                    HLExpression::Literal(HLValue::Integer(5)),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lowering_open_ended_slice_ends_at_the_length() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(10)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::SliceArray(SliceParams {
+                start: 3,
+                end: None,
+                from_end: false,
+                target_ordinal: Some(1),
+            }),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements[1..],
+            [
+                HLStatement::Assign {
+                    variable: "var_1".to_string(),
+                    expression: HLExpression::FunctionCall {
+                        function: FUNC_ARRAY_LENGTH.to_string(),
+                        arguments: vec![HLExpression::Variable("var_0".to_string())],
+                    },
+                },
+                HLStatement::Call {
+                    function: FUNC_SLICE_ARRAY.to_string(),
+                    arguments: vec![
+                        HLExpression::Variable("var_0".to_string()),
+                        HLExpression::Literal(HLValue::Integer(3)),
+                        HLExpression::Variable("var_1".to_string()),
+                    ],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lowering_slice_from_the_end_subtracts_from_the_length() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(10)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::SliceArray(SliceParams {
+                start: 3,
+                end: None,
+                from_end: true,
+                target_ordinal: Some(1),
+            }),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements[2],
+            HLStatement::Call {
+                function: FUNC_SLICE_ARRAY.to_string(),
+                arguments: vec![
+                    HLExpression::Variable("var_0".to_string()),
+                    HLExpression::BinaryOp {
+                        op: BinOp::Sub,
+                        lhs: Box::new(HLExpression::Variable("var_1".to_string())),
+                        rhs: Box::new(HLExpression::Literal(HLValue::Integer(3))),
+                    },
+                    HLExpression::Variable("var_1".to_string()),
+                ],
+            }
+        );
+        assert_eq!(
+            hl_program.to_string().lines().last(),
+            Some("slice_array(var_0, (var_1 - 3), var_1)")
+        );
+    }
+
+    #[test]
+    fn test_lower_starts_fresh_while_extend_continues() {
+        // Arrange
+        let create = Intent::CreateArray(CreateArrayParams {
+            size: Some(CountArg::Literal(5)),
+            name: None,
+            values: Vec::new(),
+            dtype: None,
+        });
+        let mut context = LoweringContext::new().with_seed(Some(7));
+
+        // Act
+        let first = context.lower(std::slice::from_ref(&create));
+        let second = context.lower(std::slice::from_ref(&create));
+        let continued = context.extend(&[create, Intent::PrintArray(None)]);
+
+        // Assert
+        let assigned = |program: &HLProgram| -> Vec<String> {
+            program
+                .statements
+                .iter()
+                .filter_map(|statement| match statement {
+                    HLStatement::Assign { variable, .. } => Some(variable.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(assigned(&first), ["var_0"]);
+        assert_eq!(assigned(&second), ["var_0"]);
+        assert_eq!(assigned(&continued), ["var_1"]);
+        // No second seed: the continuation belongs to the same program.
+        assert_eq!(continued.statements.len(), 2);
+        assert_eq!(
+            continued.statements[1],
+            HLStatement::Call {
+                function: FUNC_PRINT_ARRAY.to_string(),
+                arguments: vec![
+                    HLExpression::Variable("var_1".to_string()),
+                    HLExpression::Literal(HLValue::Integer(0)),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_lowering_uses_resolved_ordinals() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(3)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::SortArray(SortArrayParams {
+                order: Some("ascending".to_string()),
+                algorithm: None,
+                target_ordinal: Some(1),
+                target: None,
+            }),
+            Intent::PrintArray(Some(PrintArrayParams {
+                target_ordinal: Some(2),
+                target: None,
+                full: Some(false),
+            })),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        let targets: Vec<&HLExpression> = hl_program.statements[2..]
+            .iter()
+            .map(|statement| match statement {
+                HLStatement::Call { arguments, .. } => &arguments[0],
+                other => panic!("Expected a call, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            targets,
+            [
+                &HLExpression::Variable("var_0".to_string()),
+                &HLExpression::Variable("var_1".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_algorithms_lower_to_integer_codes() {
+        // Arrange
+        let sort = |algorithm: Option<&str>| {
+            Intent::SortArray(SortArrayParams {
+                order: Some("descending".to_string()),
+                algorithm: algorithm.map(str::to_string),
+                target_ordinal: Some(1),
+                target: None,
+            })
+        };
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            sort(None),
+            sort(Some("quick")),
+            sort(Some("merge")),
+            sort(Some("bubble")),
+        ];
+
+        // Act
+        let hl_program = LoweringContext::new().lower(&intent_graph);
+
+        // Assert
+        let codes: Vec<HLExpression> = hl_program.statements[1..]
+            .iter()
+            .map(|statement| match statement {
+                HLStatement::Call { arguments, .. } => arguments[2].clone(),
+                other => panic!("Expected a call, got {:?}", other),
+            })
+            .collect();
+        let expected: Vec<HLExpression> = (0..=3)
+            .map(|code| HLExpression::Literal(HLValue::Integer(code)))
+            .collect();
+        assert_eq!(codes, expected);
+    }
+
+    #[test]
+    fn test_unanalyzed_sort_without_an_order_sorts_ascending() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::SortArray(SortArrayParams {
+                order: None,
+                algorithm: None,
+                target_ordinal: None,
+                target: None,
+            }),
+        ];
+
+        // Act
+        let hl_program = LoweringContext::new().lower(&intent_graph);
+
+        // Assert
+        match &hl_program.statements[1] {
+            HLStatement::Call { arguments, .. } => assert_eq!(
+                arguments[1],
+                HLExpression::Literal(HLValue::String("ascending".to_string()))
+            ),
+            other => panic!("Expected a call, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_profiling_brackets_every_intent() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::Wait(WaitParams { duration_ms: 10 }),
+        ];
+        let mut context = LoweringContext::new().with_profiling(true);
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        let calls: Vec<String> = hl_program
+            .statements
+            .iter()
+            .map(|statement| match statement {
+                HLStatement::Call { function, .. } => function.clone(),
+                HLStatement::Assign { variable, .. } => format!("{} =", variable),
+                HLStatement::If { .. } => "if".to_string(),
+            })
+            .collect();
+        assert_eq!(
+            calls,
+            [
+                FUNC_PROFILE_INIT,
+                FUNC_PROFILE_BEGIN,
+                "var_0 =",
+                FUNC_PROFILE_END,
+                FUNC_PROFILE_BEGIN,
+                FUNC_ASYNC_SLEEP,
+                FUNC_PROFILE_END,
+            ]
+        );
+        // The markers belong to their intent; `naldom_profile_init` to none.
+        assert_eq!(context.intent_spans(), [(0, 1..4), (1, 4..7)]);
+        assert_eq!(
+            hl_program.statements[4],
+            HLStatement::Call {
+                function: FUNC_PROFILE_BEGIN.to_string(),
+                arguments: vec![
+                    HLExpression::Literal(HLValue::Integer(1)),
+                    HLExpression::Literal(HLValue::String("Wait".to_string())),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_repeat_unrolls_its_body_inside_one_profiling_bracket() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::Repeat(RepeatParams {
+                count: 2,
+                body: vec![
+                    Intent::ReverseArray(None),
+                    Intent::Wait(WaitParams { duration_ms: 10 }),
+                ],
+            }),
+        ];
+        let mut context = LoweringContext::new().with_profiling(true);
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        let calls: Vec<&str> = hl_program.statements[4..]
+            .iter()
+            .filter_map(|statement| match statement {
+                HLStatement::Call { function, .. } => Some(function.as_str()),
+                HLStatement::Assign { .. } | HLStatement::If { .. } => None,
+            })
+            .collect();
+        assert_eq!(
+            calls,
+            [
+                FUNC_PROFILE_BEGIN,
+                FUNC_REVERSE_ARRAY,
+                FUNC_ASYNC_SLEEP,
+                FUNC_REVERSE_ARRAY,
+                FUNC_ASYNC_SLEEP,
+                FUNC_PROFILE_END,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_seed_is_set_before_anything_else() {
+        // Arrange
+        let intent_graph = vec![Intent::CreateArray(CreateArrayParams {
+            size: Some(CountArg::Literal(5)),
+            name: None,
+            values: Vec::new(),
+            dtype: None,
+        })];
+        let mut context = LoweringContext::new()
+            .with_profiling(true)
+            .with_seed(Some(7));
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements[0],
+            HLStatement::Call {
+                function: FUNC_SEED_RANDOM.to_string(),
+                arguments: vec![HLExpression::Literal(HLValue::Integer(7))],
+            }
+        );
+        assert_eq!(hl_program.statements.len(), 5);
+    }
+
+    #[test]
+    fn test_only_continue_mode_is_set_at_program_start() {
+        // Act
+        let abort = LoweringContext::new()
+            .with_error_mode(ErrorMode::Abort)
+            .lower(&[]);
+        let continue_ = LoweringContext::new()
+            .with_error_mode(ErrorMode::Continue)
+            .with_seed(Some(7))
+            .lower(&[]);
+
+        // Assert
+        assert!(abort.statements.is_empty());
+        assert_eq!(
+            continue_.statements[0],
+            HLStatement::Call {
+                function: FUNC_SET_ERROR_MODE.to_string(),
+                arguments: vec![HLExpression::Literal(HLValue::Integer(1))],
+            }
+        );
+        assert_eq!(continue_.statements.len(), 2);
+    }
+
+    #[test]
+    fn test_print_passes_the_full_flag() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(100)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::PrintArray(None),
+            Intent::PrintArray(Some(PrintArrayParams {
+                target_ordinal: None,
+                target: None,
+                full: Some(true),
+            })),
+        ];
+        let mut context = LoweringContext::new();
+
+        // Act
+        let hl_program = context.lower(&intent_graph);
+
+        // Assert
+        let print = |full| HLStatement::Call {
+            function: FUNC_PRINT_ARRAY.to_string(),
+            arguments: vec![
+                HLExpression::Variable("var_0".to_string()),
+                HLExpression::Literal(HLValue::Integer(full)),
+            ],
+        };
+        assert_eq!(hl_program.statements[1..], [print(0), print(1)]);
+    }
+
+    #[test]
+    fn test_describe_array_passes_the_array_name() {
+        // Arrange
+        let create = |name: Option<&str>| {
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(10)),
+                name: name.map(str::to_string),
+                values: Vec::new(),
+                dtype: None,
+            })
+        };
+        let intent_graph = vec![
+            create(None),
+            Intent::DescribeArray(None),
+            create(Some("scores")),
+            Intent::DescribeArray(Some(DescribeArrayParams {
+                target_ordinal: Some(2),
+                target: Some("scores".to_string()),
+            })),
+        ];
+
+        // Act
+        let hl_program = LoweringContext::new().lower(&intent_graph);
+
+        // Assert
+        let describe = |variable: &str, name: &str| HLStatement::Call {
+            function: FUNC_DESCRIBE_ARRAY.to_string(),
+            arguments: vec![
+                HLExpression::Variable(variable.to_string()),
+                HLExpression::Literal(HLValue::String(name.to_string())),
+            ],
+        };
+        assert_eq!(hl_program.statements[1], describe("var_0", "var_0"));
+        assert_eq!(hl_program.statements[3], describe("var_1", "scores"));
+    }
+
+    #[test]
+    fn test_find_index_prints_the_returned_position() {
+        // Arrange
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(10)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::FindIndex(FindIndexParams {
+                value: 42,
+                target_ordinal: Some(1),
+            }),
+        ];
+
+        // Act
+        let hl_program = LoweringContext::new().lower(&intent_graph);
+
+        // Assert
+        assert_eq!(
+            hl_program.statements[1..],
+            [
+                HLStatement::Assign {
+                    variable: "var_1".to_string(),
+                    expression: HLExpression::FunctionCall {
+                        function: FUNC_FIND_INDEX.to_string(),
+                        arguments: vec![
+                            HLExpression::Variable("var_0".to_string()),
+                            HLExpression::Literal(HLValue::Integer(42)),
+                        ],
+                    },
+                },
+                HLStatement::Call {
+                    function: FUNC_PRINT_INTEGER.to_string(),
+                    arguments: vec![HLExpression::Variable("var_1".to_string())],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_histograms_default_to_ten_buckets() {
+        // Arrange
+        let histogram = |buckets| {
+            Intent::PrintHistogram(Some(HistogramParams {
+                buckets,
+                target_ordinal: Some(1),
+            }))
+        };
+        let intent_graph = vec![
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(10)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            histogram(Some(4)),
+            histogram(None),
+        ];
+
+        // Act
+        let hl_program = LoweringContext::new().lower(&intent_graph);
+
+        // Assert
+        let print = |buckets| HLStatement::Call {
+            function: FUNC_PRINT_HISTOGRAM.to_string(),
+            arguments: vec![
+                HLExpression::Variable("var_0".to_string()),
+                HLExpression::Literal(HLValue::Integer(buckets)),
+            ],
+        };
+        assert_eq!(hl_program.statements[1..], [print(4), print(10)]);
+    }
 }
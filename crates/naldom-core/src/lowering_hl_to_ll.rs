@@ -1,30 +1,84 @@
 // crates/naldom-core/src/lowering_hl_to_ll.rs
 
+use crate::unsupported::{UnsupportedConstruct, UnsupportedFeature};
+use naldom_ir::runtime_abi::find_runtime_function;
 use naldom_ir::{
-    BasicBlock, HLExpression, HLProgram, HLStatement, HLValue, LLConstant, LLFunction,
-    LLInstruction, LLProgram, LLType, LLValue as LowLevelValue, Register, Terminator,
+    Annotation, BasicBlock, BinOp, HLExpression, HLProgram, HLStatement, HLValue, ICmpPredicate,
+    LLConstant, LLFunction, LLInstruction, LLProgram, LLType, LLValue as LowLevelValue, Register,
+    Terminator,
 };
 use std::collections::HashMap;
+use std::fmt;
+
+/// An error raised while lowering IR-HL to IR-LL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoweringError {
+    /// The program uses a construct lowering cannot handle yet.
+    Unsupported(UnsupportedFeature),
+    /// A division whose divisor is the constant zero, which has no result.
+    DivisionByZero { location: String },
+}
+
+impl fmt::Display for LoweringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoweringError::Unsupported(feature) => write!(f, "{}", feature),
+            LoweringError::DivisionByZero { location } => write!(
+                f,
+                "Lowering Error: {} divides by the constant zero.",
+                location
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoweringError {}
+
+impl From<UnsupportedFeature> for LoweringError {
+    fn from(feature: UnsupportedFeature) -> Self {
+        LoweringError::Unsupported(feature)
+    }
+}
 
 /// The context for the lowering process.
 /// It tracks the state of the compilation for a single function.
 struct LoweringContext {
     /// The next available register ID.
     next_register_id: u32,
-    /// Maps high-level variable names (e.g., "var_0") to the low-level
-    /// registers that hold their values.
-    variable_map: HashMap<String, Register>,
+    /// Maps high-level variable names (e.g., "var_0") to their stack slots:
+    /// the `Alloc` register and the type stored there.
+    variable_map: HashMap<String, (Register, LLType)>,
+    /// The blocks finished so far, each with its terminator.
+    blocks: Vec<BasicBlock>,
+    /// The id of the basic block being built.
+    current_block: usize,
+    /// The next available basic block id.
+    next_block_id: usize,
     /// The instructions for the current basic block being built.
     instructions: Vec<LLInstruction>,
+    /// Annotations on `instructions`.
+    annotations: Vec<Annotation>,
+    /// The index of the HL statement being lowered, recorded on every
+    /// instruction it produces.
+    statement_index: usize,
+    /// Whether the current block can no longer be reached, because a call
+    /// that never returns ended the block before it.
+    exited: bool,
 }
 
 impl LoweringContext {
-    /// Creates a new, empty context.
+    /// Creates a new, empty context, building block 0.
     fn new() -> Self {
         LoweringContext {
             next_register_id: 0,
             variable_map: HashMap::new(),
+            blocks: Vec::new(),
+            current_block: 0,
+            next_block_id: 1,
             instructions: Vec::new(),
+            annotations: Vec::new(),
+            statement_index: 0,
+            exited: false,
         }
     }
 
@@ -34,42 +88,124 @@ impl LoweringContext {
         self.next_register_id += 1;
         reg
     }
+
+    /// Allocates the id of a basic block to be built later.
+    fn new_block(&mut self) -> usize {
+        let id = self.next_block_id;
+        self.next_block_id += 1;
+        id
+    }
+
+    /// Appends `instruction` to the current block, recording which statement
+    /// it came from.
+    fn emit(&mut self, instruction: LLInstruction) {
+        self.annotations.push(Annotation {
+            instruction: self.instructions.len(),
+            key: PROVENANCE_KEY.to_string(),
+            value: self.statement_index.to_string(),
+        });
+        self.instructions.push(instruction);
+    }
+
+    /// Ends the current block with `terminator` and starts building `next`.
+    fn finish_block(&mut self, terminator: Terminator, next: usize) {
+        self.blocks.push(BasicBlock {
+            id: self.current_block,
+            instructions: std::mem::take(&mut self.instructions),
+            terminator,
+            annotations: std::mem::take(&mut self.annotations),
+        });
+        self.current_block = next;
+    }
+
+    /// The stack slot of `name` and the type stored there.
+    fn variable(&self, name: &str) -> Result<&(Register, LLType), LoweringError> {
+        self.variable_map
+            .get(name)
+            .ok_or_else(|| self.unsupported(UnsupportedConstruct::UnassignedVariable))
+    }
+
+    /// Where the statement being lowered is, e.g. `IR-HL statement #3`.
+    fn location(&self) -> String {
+        format!("IR-HL statement #{}", self.statement_index + 1)
+    }
+
+    /// An error for `construct`, located at the statement being lowered.
+    fn unsupported(&self, construct: UnsupportedConstruct) -> LoweringError {
+        UnsupportedFeature::new(construct, self.location()).into()
+    }
 }
 
+/// Runtime functions that never return. A call to one ends its block with
+/// `Terminator::Unreachable`, and the statements after it are not lowered.
+pub(crate) const NORETURN_FUNCTIONS: &[&str] = &["naldom_exit"];
+
+/// The annotation key naming the HL statement (by index) an instruction was
+/// lowered from. Statements nested in an `If` carry the index of the `If`.
+pub const PROVENANCE_KEY: &str = "hl_statement";
+
 /// The main entry point for lowering an HLProgram to an LLProgram.
-pub fn lower_hl_to_ll(hl_program: &HLProgram) -> LLProgram {
+pub fn lower_hl_to_ll(hl_program: &HLProgram) -> Result<LLProgram, LoweringError> {
+    lower_hl_to_ll_with_entry(hl_program, "main")
+}
+
+/// Lowers an HLProgram into a single function called `entry_name`.
+pub fn lower_hl_to_ll_with_entry(
+    hl_program: &HLProgram,
+    entry_name: &str,
+) -> Result<LLProgram, LoweringError> {
     let mut context = LoweringContext::new();
 
     // In the future, we will handle multiple functions. For now, we assume
     // the entire program is a single "main" function.
-    for statement in &hl_program.statements {
-        lower_statement(statement, &mut context);
+    for (index, statement) in hl_program.statements.iter().enumerate() {
+        context.statement_index = index;
+        lower_statement(statement, &mut context)?;
+        if context.exited {
+            break;
+        }
     }
 
-    // Create a single basic block for our simple main function.
-    let main_block = BasicBlock {
-        id: 0,
-        instructions: context.instructions,
-        // Every function must end with a return. We assume our main function returns nothing (void).
-        terminator: Terminator::Return(None),
+    // A C `main` must return the exit status; any other entry point is called
+    // by a host and returns nothing.
+    let (return_type, return_value) = if entry_name == "main" {
+        (
+            LLType::I32,
+            Some(LowLevelValue::Constant(LLConstant::I32(0))),
+        )
+    } else {
+        (LLType::Void, None)
     };
 
+    // The last block, where control ends up after every branch, returns,
+    // unless the program exited on every path.
+    let next = context.current_block;
+    let terminator = if context.exited {
+        Terminator::Unreachable
+    } else {
+        Terminator::Return(return_value)
+    };
+    context.finish_block(terminator, next);
+
     // Create the main function.
     let main_function = LLFunction {
-        name: "main".to_string(),
+        name: entry_name.to_string(),
         parameters: vec![],
-        return_type: LLType::Void,
-        basic_blocks: vec![main_block],
+        return_type,
+        basic_blocks: context.blocks,
     };
 
     // The final LLProgram contains just our main function.
-    LLProgram {
+    Ok(LLProgram {
         functions: vec![main_function],
-    }
+    })
 }
 
 /// Lowers a single HLStatement into one or more LLInstructions.
-fn lower_statement(statement: &HLStatement, context: &mut LoweringContext) {
+fn lower_statement(
+    statement: &HLStatement,
+    context: &mut LoweringContext,
+) -> Result<(), LoweringError> {
     match statement {
         HLStatement::Assign {
             variable,
@@ -77,13 +213,27 @@ fn lower_statement(statement: &HLStatement, context: &mut LoweringContext) {
         } => {
             // When we see `var_0 = ...`, we first lower the expression on the right.
             // This will return the register that holds the result.
-            let result_register = lower_expression(expression, context);
+            let result_register = lower_expression(expression, context)?;
 
-            // Then, we map the high-level variable name "var_0" to this register
-            // so we can find it later.
-            context
-                .variable_map
-                .insert(variable.clone(), result_register);
+            // Then we store it in the variable's stack slot, allocated on
+            // its first assignment. A reassignment overwrites the same slot.
+            let slot = match context.variable_map.get(variable) {
+                Some((slot, _)) => *slot,
+                None => {
+                    let ty = expression_type(expression, context)?;
+                    let slot = context.new_register();
+                    context.emit(LLInstruction::Alloc {
+                        dest: slot,
+                        ty: ty.clone(),
+                    });
+                    context.variable_map.insert(variable.clone(), (slot, ty));
+                    slot
+                }
+            };
+            context.emit(LLInstruction::Store {
+                value: LowLevelValue::Register(result_register),
+                dest_ptr: slot,
+            });
         }
         HLStatement::Call {
             function,
@@ -94,19 +244,104 @@ fn lower_statement(statement: &HLStatement, context: &mut LoweringContext) {
             let args = arguments
                 .iter()
                 .map(|arg| lower_expression_to_value(arg, context))
-                .collect();
+                .collect::<Result<_, _>>()?;
 
-            context.instructions.push(LLInstruction::Call {
+            context.emit(LLInstruction::Call {
                 dest: None,
                 function_name: function.clone(),
                 arguments: args,
             });
+            context.exited = NORETURN_FUNCTIONS.contains(&function.as_str());
+        }
+        HLStatement::If {
+            condition,
+            then_body,
+            else_body,
+        } => {
+            // Both branches continue in a common block.
+            let flag = lower_condition(condition, context)?;
+
+            let then_block = context.new_block();
+            let else_block = (!else_body.is_empty()).then(|| context.new_block());
+            let merge_block = context.new_block();
+            context.finish_block(
+                Terminator::CondBr {
+                    cond: LowLevelValue::Register(flag),
+                    then_block,
+                    else_block: else_block.unwrap_or(merge_block),
+                },
+                then_block,
+            );
+
+            let then_exits = lower_branch(then_body, context)?;
+            let merge = Terminator::Br {
+                target: merge_block,
+            };
+            context.finish_block(
+                branch_end(then_exits, &merge),
+                else_block.unwrap_or(merge_block),
+            );
+            // Without an `else`, the condition being false reaches the merge.
+            let mut else_exits = false;
+            if else_block.is_some() {
+                else_exits = lower_branch(else_body, context)?;
+                context.finish_block(branch_end(else_exits, &merge), merge_block);
+            }
+            // The merge block is reached only if a branch falls through.
+            context.exited = then_exits && else_exits;
+        }
+    }
+    Ok(())
+}
+
+/// Lowers the condition of an `If` or a `Conditional` into an `I1` register.
+/// The condition is an integer; any non-zero value takes the `then` branch.
+fn lower_condition(
+    condition: &HLExpression,
+    context: &mut LoweringContext,
+) -> Result<Register, LoweringError> {
+    let value = lower_expression(condition, context)?;
+    let flag = context.new_register();
+    context.emit(LLInstruction::ICmp {
+        dest: flag,
+        predicate: ICmpPredicate::Ne,
+        lhs: LowLevelValue::Register(value),
+        rhs: LowLevelValue::Constant(LLConstant::I64(0)),
+    });
+    Ok(flag)
+}
+
+/// Lowers the statements of one branch of an `If`, stopping after a call that
+/// never returns. Returns whether the branch ends in such a call.
+fn lower_branch(
+    statements: &[HLStatement],
+    context: &mut LoweringContext,
+) -> Result<bool, LoweringError> {
+    for statement in statements {
+        lower_statement(statement, context)?;
+        if context.exited {
+            context.exited = false;
+            return Ok(true);
         }
     }
+    Ok(false)
+}
+
+/// The terminator of a branch's last block: a jump to the merge block, or
+/// `Unreachable` if the branch never gets there.
+fn branch_end(exits: bool, merge: &Terminator) -> Terminator {
+    if exits {
+        Terminator::Unreachable
+    } else {
+        merge.clone()
+    }
 }
 
 /// Lowers an HLExpression into a register that holds the result.
-fn lower_expression(expression: &HLExpression, context: &mut LoweringContext) -> Register {
+fn lower_expression(
+    expression: &HLExpression,
+    context: &mut LoweringContext,
+) -> Result<Register, LoweringError> {
     match expression {
         HLExpression::FunctionCall {
             function,
@@ -116,22 +351,84 @@ fn lower_expression(expression: &HLExpression, context: &mut LoweringContext) ->
             let args = arguments
                 .iter()
                 .map(|arg| lower_expression_to_value(arg, context))
-                .collect();
+                .collect::<Result<_, _>>()?;
 
             // We need a new register to store the return value of the function.
             let dest_register = context.new_register();
 
-            context.instructions.push(LLInstruction::Call {
+            context.emit(LLInstruction::Call {
                 dest: Some(dest_register),
                 function_name: function.clone(),
                 arguments: args,
             });
 
-            dest_register
+            Ok(dest_register)
+        }
+        HLExpression::BinaryOp { op, lhs, rhs } => {
+            let lhs = lower_expression_to_value(lhs, context)?;
+            let rhs = lower_expression_to_value(rhs, context)?;
+            if *op == BinOp::SDiv && rhs == LowLevelValue::Constant(LLConstant::I64(0)) {
+                return Err(LoweringError::DivisionByZero {
+                    location: context.location(),
+                });
+            }
+            let dest = context.new_register();
+            context.emit(LLInstruction::BinaryOp {
+                dest,
+                op: *op,
+                lhs,
+                rhs,
+                ty: LLType::I64,
+            });
+            Ok(dest)
+        }
+        HLExpression::Conditional {
+            condition,
+            then_value,
+            else_value,
+        } => {
+            // Each value is computed in a block of its own, and a phi in the
+            // block both jump to picks the one control came through.
+            let ty = expression_type(then_value, context)?;
+            let flag = lower_condition(condition, context)?;
+            let then_block = context.new_block();
+            let else_block = context.new_block();
+            let merge_block = context.new_block();
+            context.finish_block(
+                Terminator::CondBr {
+                    cond: LowLevelValue::Register(flag),
+                    then_block,
+                    else_block,
+                },
+                then_block,
+            );
+            let merge = Terminator::Br {
+                target: merge_block,
+            };
+            let then_value = lower_branch_value(then_value, context)?;
+            let then_end = context.current_block;
+            context.finish_block(merge.clone(), else_block);
+            let else_value = lower_branch_value(else_value, context)?;
+            let else_end = context.current_block;
+            context.finish_block(merge, merge_block);
+
+            let dest = context.new_register();
+            context.emit(LLInstruction::Phi {
+                dest,
+                ty,
+                incoming: vec![(then_value, then_end), (else_value, else_end)],
+            });
+            Ok(dest)
+        }
+        HLExpression::Variable(name) => {
+            // A variable's current value is loaded from its slot.
+            let source_ptr = context.variable(name)?.0;
+            let dest = context.new_register();
+            context.emit(LLInstruction::Load { dest, source_ptr });
+            Ok(dest)
         }
-        // Other cases will be handled later. For now, we only support function calls
-        // on the right side of an assignment.
-        _ => unimplemented!("Expression type not yet supported for lowering"),
+        // For now, constants are only passed as arguments.
+        HLExpression::Literal(_) => Err(context.unsupported(UnsupportedConstruct::AssignedLiteral)),
     }
 }
 
@@ -140,36 +437,75 @@ fn lower_expression(expression: &HLExpression, context: &mut LoweringContext) ->
 fn lower_expression_to_value(
     expression: &HLExpression,
     context: &mut LoweringContext,
-) -> LowLevelValue {
-    match expression {
-        HLExpression::Variable(name) => {
-            // If an argument is a variable, we look up which register it's stored in.
-            let register = context
-                .variable_map
-                .get(name)
-                .expect("Variable not found! This indicates a logic error before lowering.");
-            LowLevelValue::Register(*register)
-        }
+) -> Result<LowLevelValue, LoweringError> {
+    let value = match expression {
         HLExpression::Literal(HLValue::Integer(val)) => {
             // If an argument is a literal integer, we turn it into a constant.
             LowLevelValue::Constant(LLConstant::I64(*val))
         }
         HLExpression::Literal(HLValue::String(val)) => {
-            // A real implementation would store the string in memory and pass a pointer.
-            // For now, we convert common string commands to integer codes.
-            // 0 for "ascending", 1 for "descending". Other strings are not yet supported.
-            let val_as_int = match val.to_lowercase().as_str() {
-                "ascending" => 0,
-                "descending" => 1,
-                _ => unimplemented!("String literal '{}' is not yet supported", val),
-            };
-            LowLevelValue::Constant(LLConstant::I64(val_as_int))
+            // Sort orders travel as integer codes: 0 for "ascending", 1 for
+            // "descending". Any other string becomes a C string constant.
+            match val.to_lowercase().as_str() {
+                "ascending" => LowLevelValue::Constant(LLConstant::I64(0)),
+                "descending" => LowLevelValue::Constant(LLConstant::I64(1)),
+                _ => LowLevelValue::Constant(LLConstant::Str(val.clone())),
+            }
+        }
+        HLExpression::Literal(HLValue::IntegerArray(values)) => {
+            LowLevelValue::Constant(LLConstant::I64Array(values.clone()))
+        }
+        HLExpression::Literal(HLValue::Float(val)) => {
+            LowLevelValue::Constant(LLConstant::F64(*val))
+        }
+        HLExpression::FunctionCall { .. } => {
+            return Err(context.unsupported(UnsupportedConstruct::CallAsArgument));
         }
-        // Other cases are not yet supported as arguments.
-        _ => unimplemented!("Expression type not yet supported as argument"),
+        // Variables are loaded, and arithmetic computed, into a register of
+        // their own first.
+        HLExpression::Variable(_)
+        | HLExpression::BinaryOp { .. }
+        | HLExpression::Conditional { .. } => {
+            LowLevelValue::Register(lower_expression(expression, context)?)
+        }
+    };
+    Ok(value)
+}
+
+/// Lowers one of the values a `Conditional` picks from. Unlike an argument,
+/// it may be a call, whose result is the value.
+fn lower_branch_value(
+    expression: &HLExpression,
+    context: &mut LoweringContext,
+) -> Result<LowLevelValue, LoweringError> {
+    match expression {
+        HLExpression::FunctionCall { .. } => Ok(LowLevelValue::Register(lower_expression(
+            expression, context,
+        )?)),
+        _ => lower_expression_to_value(expression, context),
     }
 }
 
+/// The type of the value `expression` produces, i.e. of a variable it is
+/// assigned to. Calls to functions outside the runtime are assumed to return
+/// a pointer, as code generation declares them.
+fn expression_type(
+    expression: &HLExpression,
+    context: &LoweringContext,
+) -> Result<LLType, LoweringError> {
+    Ok(match expression {
+        HLExpression::FunctionCall { function, .. } => find_runtime_function(function)
+            .map(|runtime_function| runtime_function.return_type.ll_type())
+            .unwrap_or(LLType::Pointer(Box::new(LLType::I8))),
+        HLExpression::BinaryOp { .. } | HLExpression::Literal(HLValue::Integer(_)) => LLType::I64,
+        HLExpression::Literal(HLValue::Float(_)) => LLType::F64,
+        HLExpression::Literal(HLValue::String(_)) => LLType::Pointer(Box::new(LLType::I8)),
+        HLExpression::Literal(HLValue::IntegerArray(_)) => LLType::Pointer(Box::new(LLType::I64)),
+        HLExpression::Variable(name) => context.variable(name)?.1.clone(),
+        HLExpression::Conditional { then_value, .. } => expression_type(then_value, context)?,
+    })
+}
+
 // Unit tests for the lowering pass.
 #[cfg(test)]
 mod tests {
@@ -202,7 +538,7 @@ mod tests {
         };
 
         // 2. Act: Run the lowering function
-        let ll_program = lower_hl_to_ll(&hl_program);
+        let ll_program = lower_hl_to_ll(&hl_program).unwrap();
 
         // 3. Assert: Check the structure of the output LLProgram
         assert_eq!(
@@ -219,7 +555,11 @@ mod tests {
         );
 
         let instructions = &main_fn.basic_blocks[0].instructions;
-        assert_eq!(instructions.len(), 3, "Should have three call instructions");
+        assert_eq!(
+            instructions.len(),
+            7,
+            "Should have three calls, a slot for var_0, a store and two loads"
+        );
 
         // Check the first call (create_random_array)
         if let LLInstruction::Call {
@@ -244,7 +584,7 @@ mod tests {
             dest,
             function_name,
             arguments,
-        } = &instructions[1]
+        } = &instructions[4]
         {
             assert!(
                 dest.is_none(),
@@ -252,7 +592,7 @@ mod tests {
             );
             assert_eq!(*function_name, "sort_array");
             assert_eq!(arguments.len(), 2);
-            assert_eq!(arguments[0], LowLevelValue::Register(Register(0))); // Uses the result of the first call
+            assert_eq!(arguments[0], LowLevelValue::Register(Register(2))); // var_0, loaded from its slot
             assert_eq!(arguments[1], LowLevelValue::Constant(LLConstant::I64(0))); // "ascending" -> 0
         } else {
             panic!("Second instruction was not a Call");
@@ -263,21 +603,412 @@ mod tests {
     fn test_argument_lowering() {
         // Arrange
         let mut context = LoweringContext::new();
-        let reg0 = context.new_register();
-        context.variable_map.insert("var_0".to_string(), reg0);
+        let slot = context.new_register();
+        context
+            .variable_map
+            .insert("var_0".to_string(), (slot, LLType::I64));
 
         let var_expr = HLExpression::Variable("var_0".to_string());
         let int_expr = HLExpression::Literal(HLValue::Integer(42));
         let str_expr = HLExpression::Literal(HLValue::String("descending".to_string()));
+        let float_expr = HLExpression::Literal(HLValue::Float(2.5));
 
         // Act
-        let var_val = lower_expression_to_value(&var_expr, &mut context);
-        let int_val = lower_expression_to_value(&int_expr, &mut context);
-        let str_val = lower_expression_to_value(&str_expr, &mut context);
+        let var_val = lower_expression_to_value(&var_expr, &mut context).unwrap();
+        let int_val = lower_expression_to_value(&int_expr, &mut context).unwrap();
+        let str_val = lower_expression_to_value(&str_expr, &mut context).unwrap();
+        let float_val = lower_expression_to_value(&float_expr, &mut context).unwrap();
 
         // Assert
-        assert_eq!(var_val, LowLevelValue::Register(Register(0)));
+        assert_eq!(var_val, LowLevelValue::Register(Register(1)));
+        assert_eq!(
+            context.instructions,
+            [LLInstruction::Load {
+                dest: Register(1),
+                source_ptr: slot,
+            }]
+        );
         assert_eq!(int_val, LowLevelValue::Constant(LLConstant::I64(42)));
         assert_eq!(str_val, LowLevelValue::Constant(LLConstant::I64(1))); // "descending" -> 1
+        assert_eq!(float_val, LowLevelValue::Constant(LLConstant::F64(2.5)));
+    }
+
+    #[test]
+    fn test_main_returns_an_exit_status_and_other_entries_return_nothing() {
+        // Arrange
+        let hl_program = HLProgram { statements: vec![] };
+
+        // Act
+        let main_fn = &lower_hl_to_ll(&hl_program).unwrap().functions[0];
+        let embedded = &lower_hl_to_ll_with_entry(&hl_program, "run_naldom")
+            .unwrap()
+            .functions[0];
+
+        // Assert
+        assert_eq!(main_fn.return_type, LLType::I32);
+        assert_eq!(
+            main_fn.basic_blocks[0].terminator,
+            Terminator::Return(Some(LowLevelValue::Constant(LLConstant::I32(0))))
+        );
+        assert_eq!(embedded.name, "run_naldom");
+        assert_eq!(embedded.return_type, LLType::Void);
+        assert_eq!(
+            embedded.basic_blocks[0].terminator,
+            Terminator::Return(None)
+        );
+    }
+
+    fn call(function: &str) -> HLStatement {
+        HLStatement::Call {
+            function: function.to_string(),
+            arguments: vec![],
+        }
+    }
+
+    #[test]
+    fn test_if_branches_to_blocks_that_rejoin() {
+        // Arrange
+        let hl_program = HLProgram {
+            statements: vec![
+                HLStatement::If {
+                    condition: HLExpression::FunctionCall {
+                        function: "is_sorted".to_string(),
+                        arguments: vec![],
+                    },
+                    then_body: vec![call("yes")],
+                    else_body: vec![call("no")],
+                },
+                call("after"),
+            ],
+        };
+
+        // Act
+        let blocks = &lower_hl_to_ll(&hl_program).unwrap().functions[0].basic_blocks;
+
+        // Assert
+        let ids: Vec<usize> = blocks.iter().map(|block| block.id).collect();
+        assert_eq!(ids, [0, 1, 2, 3]);
+        assert!(matches!(
+            blocks[0].instructions[1],
+            LLInstruction::ICmp {
+                predicate: ICmpPredicate::Ne,
+                ..
+            }
+        ));
+        assert_eq!(
+            blocks[0].terminator,
+            Terminator::CondBr {
+                cond: LowLevelValue::Register(Register(1)),
+                then_block: 1,
+                else_block: 2,
+            }
+        );
+        assert_eq!(blocks[1].terminator, Terminator::Br { target: 3 });
+        assert_eq!(blocks[2].terminator, Terminator::Br { target: 3 });
+        assert!(matches!(blocks[3].terminator, Terminator::Return(_)));
+        // Everything inside the `if` comes from statement 0.
+        assert_eq!(blocks[2].annotations[0].value, "0");
+        assert_eq!(blocks[3].annotations[0].value, "1");
+    }
+
+    #[test]
+    fn test_conditional_value_is_picked_by_a_phi_where_the_branches_rejoin() {
+        // Arrange: `var_0 = (if is_sorted() then 1 else (2 * 3))`.
+        let hl_program = HLProgram {
+            statements: vec![HLStatement::Assign {
+                variable: "var_0".to_string(),
+                expression: HLExpression::Conditional {
+                    condition: Box::new(HLExpression::FunctionCall {
+                        function: "is_sorted".to_string(),
+                        arguments: vec![],
+                    }),
+                    then_value: Box::new(HLExpression::Literal(HLValue::Integer(1))),
+                    else_value: Box::new(HLExpression::BinaryOp {
+                        op: BinOp::Mul,
+                        lhs: Box::new(HLExpression::Literal(HLValue::Integer(2))),
+                        rhs: Box::new(HLExpression::Literal(HLValue::Integer(3))),
+                    }),
+                },
+            }],
+        };
+
+        // Act
+        let main_fn = &lower_hl_to_ll(&hl_program).unwrap().functions[0];
+
+        // Assert
+        let text = main_fn.to_string();
+        assert!(
+            text.contains(
+                "  %1 = icmp ne %0, 0  ; hl_statement=0\n  br %1, bb1, bb2\nbb1:\n  br bb3\nbb2:\n  %2 = mul i64 2, 3  ; hl_statement=0\n  br bb3\nbb3:\n  %3 = phi i64 [1, bb1], [%2, bb2]  ; hl_statement=0\n  %4 = alloc i64"
+            ),
+            "{}",
+            text
+        );
+        assert_eq!(main_fn.predecessors(3), [1, 2]);
+    }
+
+    #[test]
+    fn test_exit_ends_the_block_and_drops_the_statements_after_it() {
+        // Arrange
+        let hl_program = HLProgram {
+            statements: vec![call("before"), call("naldom_exit"), call("after")],
+        };
+
+        // Act
+        let blocks = &lower_hl_to_ll(&hl_program).unwrap().functions[0].basic_blocks;
+
+        // Assert
+        assert_eq!(blocks.len(), 1);
+        let called: Vec<&str> = blocks[0]
+            .instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                LLInstruction::Call { function_name, .. } => Some(function_name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(called, ["before", "naldom_exit"]);
+        assert_eq!(blocks[0].terminator, Terminator::Unreachable);
+    }
+
+    #[test]
+    fn test_exit_in_a_branch_skips_only_the_merge_jump() {
+        // Arrange
+        let branch = |else_body| HLStatement::If {
+            condition: HLExpression::FunctionCall {
+                function: "is_sorted".to_string(),
+                arguments: vec![],
+            },
+            then_body: vec![call("naldom_exit"), call("dead")],
+            else_body,
+        };
+        let one_branch = HLProgram {
+            statements: vec![branch(vec![call("no")]), call("after")],
+        };
+        let both_branches = HLProgram {
+            statements: vec![branch(vec![call("naldom_exit")]), call("after")],
+        };
+
+        // Act
+        let one = &lower_hl_to_ll(&one_branch).unwrap().functions[0].basic_blocks;
+        let both = &lower_hl_to_ll(&both_branches).unwrap().functions[0].basic_blocks;
+
+        // Assert
+        assert_eq!(one[1].terminator, Terminator::Unreachable);
+        assert_eq!(one[1].instructions.len(), 1);
+        assert_eq!(one[2].terminator, Terminator::Br { target: 3 });
+        assert!(matches!(one[3].terminator, Terminator::Return(_)));
+        assert_eq!(one[3].instructions.len(), 1);
+        // Neither branch reaches the merge block, so it stays empty.
+        assert_eq!(both[2].terminator, Terminator::Unreachable);
+        assert!(both[3].instructions.is_empty());
+        assert_eq!(both[3].terminator, Terminator::Unreachable);
+    }
+
+    #[test]
+    fn test_instructions_record_their_statement_and_keep_it_when_others_are_removed() {
+        // Arrange
+        let hl_program = HLProgram {
+            statements: vec![call("first"), call("dead"), call("last")],
+        };
+        let mut block = lower_hl_to_ll(&hl_program).unwrap().functions[0].basic_blocks[0].clone();
+
+        // Act: drop the middle call, as dead code elimination would.
+        block.retain_instructions(|instruction| {
+            !matches!(instruction, LLInstruction::Call { function_name, .. } if function_name == "dead")
+        });
+
+        // Assert
+        assert_eq!(block.instructions.len(), 2);
+        let provenance: Vec<(usize, &str)> = block
+            .annotations
+            .iter()
+            .map(|annotation| (annotation.instruction, annotation.value.as_str()))
+            .collect();
+        assert_eq!(provenance, [(0, "0"), (1, "2")]);
+        assert!(
+            block
+                .annotations_of(1)
+                .all(|annotation| annotation.key == PROVENANCE_KEY)
+        );
+    }
+
+    #[test]
+    fn test_unsupported_constructs_are_reported_with_their_statement() {
+        // Arrange: one program per construct lowering cannot handle.
+        let assign = |expression| HLStatement::Assign {
+            variable: "var_1".to_string(),
+            expression,
+        };
+        let cases = [
+            (
+                assign(HLExpression::Literal(HLValue::Integer(5))),
+                UnsupportedConstruct::AssignedLiteral,
+            ),
+            (
+                HLStatement::Call {
+                    function: "print_array".to_string(),
+                    arguments: vec![HLExpression::FunctionCall {
+                        function: "create_random_array".to_string(),
+                        arguments: vec![],
+                    }],
+                },
+                UnsupportedConstruct::CallAsArgument,
+            ),
+            (
+                assign(HLExpression::Variable("never_assigned".to_string())),
+                UnsupportedConstruct::UnassignedVariable,
+            ),
+        ];
+
+        for (statement, construct) in cases {
+            let hl_program = HLProgram {
+                statements: vec![call("first"), statement],
+            };
+
+            // Act
+            let error = lower_hl_to_ll(&hl_program).unwrap_err();
+
+            // Assert
+            let LoweringError::Unsupported(feature) = &error else {
+                panic!("Expected an unsupported feature, got {:?}", error);
+            };
+            assert_eq!(feature.construct, construct);
+            assert_eq!(feature.location, "IR-HL statement #2");
+            assert!(
+                error.to_string().starts_with("Naldom does not support"),
+                "{}",
+                error
+            );
+        }
+    }
+
+    fn arithmetic(op: BinOp, rhs: i64) -> HLExpression {
+        HLExpression::BinaryOp {
+            op,
+            lhs: Box::new(HLExpression::Variable("var_0".to_string())),
+            rhs: Box::new(HLExpression::Literal(HLValue::Integer(rhs))),
+        }
+    }
+
+    #[test]
+    fn test_arithmetic_arguments_get_a_register_of_their_own() {
+        // Arrange
+        let hl_program = HLProgram {
+            statements: vec![
+                HLStatement::Assign {
+                    variable: "var_0".to_string(),
+                    expression: HLExpression::FunctionCall {
+                        function: "array_length".to_string(),
+                        arguments: vec![],
+                    },
+                },
+                HLStatement::Call {
+                    function: "print_int".to_string(),
+                    arguments: vec![arithmetic(BinOp::Mul, 2)],
+                },
+            ],
+        };
+
+        // Act
+        let instructions =
+            &lower_hl_to_ll(&hl_program).unwrap().functions[0].basic_blocks[0].instructions;
+
+        // Assert
+        assert_eq!(
+            instructions[4],
+            LLInstruction::BinaryOp {
+                dest: Register(3),
+                op: BinOp::Mul,
+                lhs: LowLevelValue::Register(Register(2)),
+                rhs: LowLevelValue::Constant(LLConstant::I64(2)),
+                ty: LLType::I64,
+            }
+        );
+        assert_eq!(
+            instructions[5],
+            LLInstruction::Call {
+                dest: None,
+                function_name: "print_int".to_string(),
+                arguments: vec![LowLevelValue::Register(Register(3))],
+            }
+        );
+    }
+
+    #[test]
+    fn test_division_by_a_constant_zero_is_an_error() {
+        // Arrange
+        let program = |divisor| HLProgram {
+            statements: vec![
+                HLStatement::Assign {
+                    variable: "var_0".to_string(),
+                    expression: HLExpression::FunctionCall {
+                        function: "array_length".to_string(),
+                        arguments: vec![],
+                    },
+                },
+                HLStatement::Assign {
+                    variable: "var_1".to_string(),
+                    expression: arithmetic(BinOp::SDiv, divisor),
+                },
+            ],
+        };
+
+        // Act
+        let by_zero = lower_hl_to_ll(&program(0)).unwrap_err();
+        let by_two = lower_hl_to_ll(&program(2));
+
+        // Assert
+        assert_eq!(
+            by_zero,
+            LoweringError::DivisionByZero {
+                location: "IR-HL statement #2".to_string(),
+            }
+        );
+        assert_eq!(
+            by_zero.to_string(),
+            "Lowering Error: IR-HL statement #2 divides by the constant zero."
+        );
+        assert!(by_two.is_ok());
+    }
+
+    #[test]
+    fn test_reassigned_variable_is_stored_into_one_slot_and_loaded_fresh() {
+        // Arrange: create an array, then create a new one with the same name.
+        let create = |size| HLStatement::Assign {
+            variable: "var_0".to_string(),
+            expression: HLExpression::FunctionCall {
+                function: "create_random_array".to_string(),
+                arguments: vec![HLExpression::Literal(HLValue::Integer(size))],
+            },
+        };
+        let print = HLStatement::Call {
+            function: "print_array".to_string(),
+            arguments: vec![HLExpression::Variable("var_0".to_string())],
+        };
+        let hl_program = HLProgram {
+            statements: vec![create(3), print.clone(), create(5), print],
+        };
+
+        // Act
+        let instructions =
+            &lower_hl_to_ll(&hl_program).unwrap().functions[0].basic_blocks[0].instructions;
+
+        // Assert
+        let text: Vec<String> = instructions.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            text,
+            [
+                "%0 = call @create_random_array(3)",
+                "%1 = alloc i8*",
+                "store %0, %1",
+                "%2 = load %1",
+                "call @print_array(%2)",
+                "%3 = call @create_random_array(5)",
+                "store %3, %1",
+                "%4 = load %1",
+                "call @print_array(%4)",
+            ]
+        );
     }
 }
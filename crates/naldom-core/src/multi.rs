@@ -0,0 +1,146 @@
+// crates/naldom-core/src/multi.rs
+
+//! Several programs in one executable, for `naldom-cli --multi`.
+//!
+//! Each program is compiled on its own into an entry function named by
+//! [`program_symbol`]. [`link_programs`] then puts them into one LLProgram
+//! with a synthesized `main` that registers every program with the runtime
+//! and lets the runtime pick one by the first command-line argument. The
+//! LL IR has no branches, so the name comparison happens in the runtime.
+
+use naldom_ir::{
+    BasicBlock, LLConstant, LLFunction, LLInstruction, LLProgram, LLType, LLValue, Register,
+    Terminator,
+};
+
+pub const FUNC_REGISTER_PROGRAM: &str = "naldom_register_program";
+pub const FUNC_DISPATCH: &str = "naldom_dispatch";
+
+/// The entry function of the program called `name`. The prefix keeps program
+/// names from clashing with `main`, the runtime or the C library.
+pub fn program_symbol(name: &str) -> String {
+    format!("naldom_program_{}", name)
+}
+
+/// Combines separately lowered programs, given as `(name, program)` with each
+/// entry function named by [`program_symbol`], and adds the dispatching `main`.
+pub fn link_programs(programs: Vec<(String, LLProgram)>) -> LLProgram {
+    let names: Vec<String> = programs.iter().map(|(name, _)| name.clone()).collect();
+    let mut functions: Vec<LLFunction> = programs
+        .into_iter()
+        .flat_map(|(_, program)| program.functions)
+        .collect();
+    // Last, after the programs it registers.
+    functions.push(dispatcher(&names));
+    LLProgram { functions }
+}
+
+/// `int main(int argc, char **argv)`: registers each program under its name,
+/// then returns the status of `naldom_dispatch(argc, argv)`.
+fn dispatcher(names: &[String]) -> LLFunction {
+    let argc = Register(0);
+    let argv = Register(1);
+    let status = Register(2);
+    let mut instructions: Vec<LLInstruction> = names
+        .iter()
+        .map(|name| LLInstruction::Call {
+            dest: None,
+            function_name: FUNC_REGISTER_PROGRAM.to_string(),
+            arguments: vec![
+                LLValue::Constant(LLConstant::Str(name.clone())),
+                LLValue::Constant(LLConstant::Function(program_symbol(name))),
+            ],
+        })
+        .collect();
+    instructions.push(LLInstruction::Call {
+        dest: Some(status),
+        function_name: FUNC_DISPATCH.to_string(),
+        arguments: vec![LLValue::Register(argc), LLValue::Register(argv)],
+    });
+
+    LLFunction {
+        name: "main".to_string(),
+        parameters: vec![
+            (LLType::I32, argc),
+            (
+                LLType::Pointer(Box::new(LLType::Pointer(Box::new(LLType::I8)))),
+                argv,
+            ),
+        ],
+        return_type: LLType::I32,
+        basic_blocks: vec![BasicBlock {
+            id: 0,
+            instructions,
+            terminator: Terminator::Return(Some(LLValue::Register(status))),
+            annotations: Vec::new(),
+        }],
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lowering_hl_to_ll::lower_hl_to_ll_with_entry;
+    use naldom_ir::HLProgram;
+
+    fn empty_program(name: &str) -> (String, LLProgram) {
+        let hl_program = HLProgram { statements: vec![] };
+        (
+            name.to_string(),
+            lower_hl_to_ll_with_entry(&hl_program, &program_symbol(name)).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_link_programs_appends_a_dispatching_main() {
+        // Act
+        let program = link_programs(vec![empty_program("demo1"), empty_program("demo2")]);
+
+        // Assert
+        let names: Vec<&str> = program.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["naldom_program_demo1", "naldom_program_demo2", "main"]
+        );
+        let main = &program.functions[2];
+        let calls: Vec<&LLInstruction> = main.basic_blocks[0].instructions.iter().collect();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(
+            calls[1],
+            &LLInstruction::Call {
+                dest: None,
+                function_name: FUNC_REGISTER_PROGRAM.to_string(),
+                arguments: vec![
+                    LLValue::Constant(LLConstant::Str("demo2".to_string())),
+                    LLValue::Constant(LLConstant::Function("naldom_program_demo2".to_string())),
+                ],
+            }
+        );
+        assert!(
+            matches!(calls[2], LLInstruction::Call { function_name, .. } if function_name == FUNC_DISPATCH)
+        );
+    }
+
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_dispatcher_compiles_to_a_c_main() {
+        // Act
+        let llvm_ir = crate::codegen_llvm::generate_llvm_ir(
+            &link_programs(vec![empty_program("demo1")]),
+            "x86_64-unknown-linux-gnu",
+        )
+        .expect("the linked program should verify");
+
+        // Assert
+        assert!(llvm_ir.contains("define void @naldom_program_demo1()"));
+        assert!(llvm_ir.contains("define i32 @main(i32 %0, "));
+        let registration = llvm_ir
+            .lines()
+            .find(|line| line.contains("call void @naldom_register_program("))
+            .expect("main should register the program");
+        assert!(registration.contains("@naldom_program_demo1"));
+        assert!(llvm_ir.contains("call i64 @naldom_dispatch(i64"));
+        assert!(llvm_ir.contains("trunc i64"));
+    }
+}
@@ -0,0 +1,94 @@
+// crates/naldom-core/src/observer.rs
+
+//! Hooks for embedders that want to see the pipeline's artifacts as they are
+//! produced, e.g. to show the IR in an editor, without waiting for the
+//! [`CompileOutput`](crate::compiler::CompileOutput) or copying anything.
+
+use naldom_ir::{HLProgram, Intent, LLProgram};
+use std::sync::Mutex;
+
+/// Receives each artifact of a compilation, borrowed, right after the stage
+/// that produced it and before the next stage starts. Every callback does
+/// nothing by default.
+///
+/// Returning an error aborts the compilation with
+/// [`CompileError::Aborted`](crate::compiler::CompileError::Aborted); later
+/// stages do not run and later callbacks do not fire.
+pub trait ArtifactObserver: Send + Sync {
+    /// The validated IntentGraph, with procedures expanded and defaults
+    /// filled in.
+    fn on_intents(&self, _intents: &[Intent]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// The IR-HL program, after the IR-HL passes.
+    fn on_hl(&self, _hl_program: &HLProgram) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// The IR-LL program, after the IR-LL passes.
+    fn on_ll(&self, _ll_program: &LLProgram) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// The generated LLVM IR. Not called for targets that do not use LLVM.
+    fn on_llvm_ir(&self, _llvm_ir: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// The artifacts a [`CollectingObserver`] has seen; `None` for those whose
+/// callback has not fired.
+#[derive(Debug, Clone, Default)]
+pub struct CollectedArtifacts {
+    pub intents: Option<Vec<Intent>>,
+    pub hl_program: Option<HLProgram>,
+    pub ll_program: Option<LLProgram>,
+    pub llvm_ir: Option<String>,
+}
+
+/// An observer that keeps a copy of every artifact, for callers that want
+/// them after the compilation rather than while it runs.
+#[derive(Debug, Default)]
+pub struct CollectingObserver {
+    artifacts: Mutex<CollectedArtifacts>,
+}
+
+impl CollectingObserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The artifacts seen so far, leaving the observer empty for the next
+    /// compilation.
+    pub fn take(&self) -> CollectedArtifacts {
+        std::mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CollectedArtifacts> {
+        // A poisoned lock only means a panic mid-update; the copies are still usable.
+        self.artifacts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl ArtifactObserver for CollectingObserver {
+    fn on_intents(&self, intents: &[Intent]) -> Result<(), String> {
+        self.lock().intents = Some(intents.to_vec());
+        Ok(())
+    }
+
+    fn on_hl(&self, hl_program: &HLProgram) -> Result<(), String> {
+        self.lock().hl_program = Some(hl_program.clone());
+        Ok(())
+    }
+
+    fn on_ll(&self, ll_program: &LLProgram) -> Result<(), String> {
+        self.lock().ll_program = Some(ll_program.clone());
+        Ok(())
+    }
+
+    fn on_llvm_ir(&self, llvm_ir: &str) -> Result<(), String> {
+        self.lock().llvm_ir = Some(llvm_ir.to_string());
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+// crates/naldom-core/src/parallel_analysis.rs
+
+//! Semantic analysis of independent units, e.g. the programs of a `--multi`
+//! build, side by side on rayon's thread pool.
+//!
+//! Units share only what never changes during analysis: their
+//! [`AnalyzerConfig`], behind an `Arc`, and the intent schema registry, which
+//! is static. Each unit gets its own [`SemanticAnalyzer`] and with it its own
+//! symbol table, so no unit sees the arrays or variables of another. Results
+//! come back in the order the units were given, whichever thread finishes
+//! first, so the merged warnings and the error reported are the same from one
+//! run to the next.
+
+use crate::semantic_analyzer::{
+    AnalyzerConfig, DefaultedParam, SemanticAnalyzer, SemanticError, SymbolType,
+};
+use naldom_ir::Intent;
+use rayon::prelude::*;
+use std::sync::Arc;
+
+/// A sequence of intents analyzed on its own, named for messages.
+#[derive(Debug, Clone)]
+pub struct AnalysisUnit {
+    pub name: String,
+    pub intents: Vec<Intent>,
+}
+
+/// The names a unit declared, each list sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolSummary {
+    pub arrays: Vec<String>,
+    pub matrices: Vec<String>,
+    pub variables: Vec<String>,
+}
+
+/// The outcome of analyzing one unit.
+#[derive(Debug)]
+pub struct UnitAnalysis {
+    pub name: String,
+    pub result: Result<Vec<Intent>, SemanticError>,
+    pub warnings: Vec<String>,
+    pub defaulted: Vec<DefaultedParam>,
+    /// What the unit declared before it finished, or failed.
+    pub symbols: SymbolSummary,
+}
+
+/// Analyzes every unit with its own analyzer, in parallel, and returns the
+/// outcomes in the order of `units`.
+pub fn analyze_units(units: &[AnalysisUnit], config: Arc<AnalyzerConfig>) -> Vec<UnitAnalysis> {
+    units
+        .par_iter()
+        .map(|unit| analyze_unit(unit, Arc::clone(&config)))
+        .collect()
+}
+
+fn analyze_unit(unit: &AnalysisUnit, config: Arc<AnalyzerConfig>) -> UnitAnalysis {
+    let mut analyzer = SemanticAnalyzer::with_config(config);
+    let result = analyzer.analyze(&unit.intents);
+    let mut symbols = SymbolSummary::default();
+    for symbol in analyzer.symbol_table().symbols() {
+        let names = match symbol.symbol_type {
+            SymbolType::Array(_) => &mut symbols.arrays,
+            SymbolType::Matrix => &mut symbols.matrices,
+            SymbolType::Integer => &mut symbols.variables,
+        };
+        names.push(symbol.name.clone());
+    }
+    symbols.arrays.sort();
+    symbols.matrices.sort();
+    symbols.variables.sort();
+    UnitAnalysis {
+        name: unit.name.clone(),
+        result,
+        warnings: analyzer.warnings().to_vec(),
+        defaulted: analyzer.defaulted().to_vec(),
+        symbols,
+    }
+}
+
+/// Every unit's warnings, prefixed with its name, in unit order.
+pub fn merged_warnings(analyses: &[UnitAnalysis]) -> Vec<String> {
+    analyses
+        .iter()
+        .flat_map(|analysis| {
+            analysis
+                .warnings
+                .iter()
+                .map(move |warning| format!("{}: {}", analysis.name, warning))
+        })
+        .collect()
+}
+
+/// Every unit with its validated intents, or the name and error of the
+/// first unit (in unit order) that failed.
+pub fn into_validated(
+    analyses: Vec<UnitAnalysis>,
+) -> Result<Vec<AnalysisUnit>, (String, SemanticError)> {
+    analyses
+        .into_iter()
+        .map(|analysis| match analysis.result {
+            Ok(intents) => Ok(AnalysisUnit {
+                name: analysis.name,
+                intents,
+            }),
+            Err(error) => Err((analysis.name, error)),
+        })
+        .collect()
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_to_intent_graph;
+    use crate::program_generator::generate_program;
+
+    fn unit(name: &str, json: &str) -> AnalysisUnit {
+        AnalysisUnit {
+            name: name.to_string(),
+            intents: parse_to_intent_graph(json).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_units_do_not_see_each_others_symbols() {
+        // Arrange: `b` and `c` use what `a` declares.
+        let units = [
+            unit(
+                "a",
+                r#"[{"intent": "SetVariable", "parameters": {"name": "count", "value": 3}},
+                    {"intent": "CreateArray", "parameters": {"size": 5, "name": "scores"}}]"#,
+            ),
+            unit(
+                "b",
+                r#"[{"intent": "CreateArray", "parameters": {"size": 2}},
+                    {"intent": "SortArray", "parameters": {"order": "ascending", "target": "scores"}}]"#,
+            ),
+            unit(
+                "c",
+                r#"[{"intent": "CreateArray", "parameters": {"size": "count"}}]"#,
+            ),
+        ];
+
+        // Act
+        let analyses = analyze_units(&units, Arc::default());
+
+        // Assert
+        assert!(analyses[0].result.is_ok());
+        assert_eq!(analyses[0].symbols.variables, ["count"]);
+        assert!(analyses[0].symbols.arrays.contains(&"scores".to_string()));
+        assert!(matches!(
+            &analyses[1].result,
+            Err(SemanticError::UnknownArray { name, .. }) if name == "scores"
+        ));
+        assert!(!analyses[1].symbols.arrays.contains(&"scores".to_string()));
+        assert!(matches!(
+            &analyses[2].result,
+            Err(SemanticError::UndefinedVariable { name, .. }) if name == "count"
+        ));
+        assert!(analyses[2].symbols.variables.is_empty());
+        let (failed, _) = into_validated(analyses).unwrap_err();
+        assert_eq!(failed, "b");
+    }
+
+    #[test]
+    fn test_parallel_results_match_sequential_analysis_in_unit_order() {
+        // Arrange
+        let units: Vec<AnalysisUnit> = (1..=50)
+            .map(|block| unit(&format!("block_{}", block), &generate_program(block)))
+            .collect();
+
+        // Act
+        let analyses = analyze_units(&units, Arc::default());
+
+        // Assert
+        assert_eq!(analyses.len(), units.len());
+        for (analysis, unit) in analyses.iter().zip(&units) {
+            let mut analyzer = SemanticAnalyzer::new();
+            let sequential = analyzer.analyze(&unit.intents);
+            assert_eq!(analysis.name, unit.name);
+            assert_eq!(analysis.result, sequential);
+            assert_eq!(analysis.warnings, analyzer.warnings());
+        }
+    }
+
+    #[test]
+    fn test_merged_warnings_are_named_and_in_unit_order() {
+        // Arrange
+        let analysis = |name: &str, warnings: &[&str]| UnitAnalysis {
+            name: name.to_string(),
+            result: Ok(Vec::new()),
+            warnings: warnings.iter().map(ToString::to_string).collect(),
+            defaulted: Vec::new(),
+            symbols: SymbolSummary::default(),
+        };
+        let analyses = [
+            analysis("b", &["first"]),
+            analysis("a", &[]),
+            analysis("c", &["second", "third"]),
+        ];
+
+        // Act
+        let warnings = merged_warnings(&analyses);
+
+        // Assert
+        assert_eq!(warnings, ["b: first", "c: second", "c: third"]);
+    }
+}
@@ -1,9 +1,78 @@
 // crates/naldom-core/src/parser.rs
 
+//! Parses the model's answer, a JSON IntentGraph, in two phases: first into
+//! [`RawIntent`]s, which keep every intent by name whether this compiler
+//! knows it or not, then into typed [`Intent`]s.
+//!
+//! Read-only tooling (`diff`, and `--explain` and `--emit intent-graph` on an
+//! IntentGraph file) works on the raw form, so a plan written for a newer
+//! schema still shows up there. Compiling needs the typed form, and rejects
+//! intents it does not know.
+
+use crate::schema;
 use naldom_ir::Intent;
-use serde_json;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// An intent as the model wrote it: its name and its parameters, unchecked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RawIntent {
+    #[serde(rename = "intent")]
+    pub name: String,
+    /// `null` when the intent has none.
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub parameters: Value,
+}
+
+impl RawIntent {
+    /// Whether this compiler knows the intent, i.e. the schema registry has it.
+    pub fn is_known(&self) -> bool {
+        schema::find(&self.name).is_some()
+    }
+
+    /// The typed intent, if the intent is known and its parameters fit it.
+    pub fn to_intent(&self) -> Result<Intent, serde_json::Error> {
+        serde_json::to_value(self).and_then(serde_json::from_value)
+    }
+}
+
+/// An error raised while parsing an IntentGraph.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The answer is not a JSON array of intents, or an intent's parameters
+    /// do not fit it.
+    Json(serde_json::Error),
+    /// A top-level intent this compiler does not know, e.g. one from a newer
+    /// schema. `index` counts from 0.
+    UnknownIntent { index: usize, name: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Json(error) => write!(f, "{}", error),
+            ParseError::UnknownIntent { index, name } => write!(
+                f,
+                "intent #{} is '{}', which this version of Naldom does not know",
+                index + 1,
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
 
-pub fn parse_to_intent_graph(llm_output: &str) -> Result<Vec<Intent>, serde_json::Error> {
+impl From<serde_json::Error> for ParseError {
+    fn from(error: serde_json::Error) -> Self {
+        ParseError::Json(error)
+    }
+}
+
+/// The JSON array in `llm_output`, which may be wrapped in prose or a code
+/// fence.
+fn json_array(llm_output: &str) -> &str {
     // A robust method to find and extract the JSON array part of the string.
     let json_part = if let Some(start_index) = llm_output.find('[') {
         // If we found a start bracket, find the corresponding end bracket starting from that point.
@@ -20,6 +89,69 @@ pub fn parse_to_intent_graph(llm_output: &str) -> Result<Vec<Intent>, serde_json
         // Pass the whole string to serde_json to handle the error.
         llm_output
     };
+    json_part.trim()
+}
+
+/// Parses `llm_output` into raw intents, known or not.
+pub fn parse_raw_intents(llm_output: &str) -> Result<Vec<RawIntent>, serde_json::Error> {
+    serde_json::from_str(json_array(llm_output))
+}
+
+/// Parses `llm_output` into typed intents. Every top-level intent must be
+/// known.
+pub fn parse_to_intent_graph(llm_output: &str) -> Result<Vec<Intent>, ParseError> {
+    let raw = parse_raw_intents(llm_output)?;
+    if let Some((index, unknown)) = raw.iter().enumerate().find(|(_, raw)| !raw.is_known()) {
+        return Err(ParseError::UnknownIntent {
+            index,
+            name: unknown.name.clone(),
+        });
+    }
+    raw.iter()
+        .map(|raw| raw.to_intent().map_err(ParseError::from))
+        .collect()
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANSWER: &str = r#"Here is the plan:
+[
+    {"intent": "CreateArray", "parameters": {"size": 5}},
+    {"intent": "GroupBy", "parameters": {"key": "parity"}},
+    {"intent": "PrintArray"}
+]"#;
+
+    #[test]
+    fn test_raw_intents_keep_unknown_intents_by_name() {
+        // Act
+        let raw = parse_raw_intents(ANSWER).unwrap();
+
+        // Assert
+        let names: Vec<&str> = raw.iter().map(|raw| raw.name.as_str()).collect();
+        assert_eq!(names, ["CreateArray", "GroupBy", "PrintArray"]);
+        assert_eq!(raw[1].parameters, serde_json::json!({"key": "parity"}));
+        assert_eq!(raw[2].parameters, Value::Null);
+        assert!(raw[0].is_known());
+        assert!(!raw[1].is_known());
+        assert!(matches!(raw[2].to_intent(), Ok(Intent::PrintArray(None))));
+    }
+
+    #[test]
+    fn test_typed_parse_rejects_unknown_intents_by_name() {
+        // Act
+        let error = parse_to_intent_graph(ANSWER).unwrap_err();
 
-    serde_json::from_str(json_part.trim())
+        // Assert
+        assert!(matches!(
+            &error,
+            ParseError::UnknownIntent { index: 1, name } if name == "GroupBy"
+        ));
+        assert_eq!(
+            error.to_string(),
+            "intent #2 is 'GroupBy', which this version of Naldom does not know"
+        );
+    }
 }
@@ -0,0 +1,471 @@
+// crates/naldom-core/src/passes.rs
+
+//! Transformations of the lowered program, run by a [`PassManager`] between
+//! lowering and code generation.
+//!
+//! A pass rewrites IR-HL or IR-LL in place. When asked, the manager snapshots
+//! the textual form of the IR before and after each pass and records what the
+//! pass changed as a unified diff (`--print-pass-diff`), so a pass that
+//! misbehaves shows exactly what it did.
+
+use naldom_ir::{HLProgram, LLFunction, LLInstruction, LLProgram, LLValue, Register, Terminator};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Lines of unchanged IR shown around each change in a diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// Above this many cells, the middle of a diff is shown as removed and
+/// re-added wholesale instead of being aligned line by line.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// A pass over IR-HL.
+pub trait HlPass: Send + Sync {
+    /// The name diffs are labelled with, e.g. `dce`.
+    fn name(&self) -> &'static str;
+    fn run(&self, program: &mut HLProgram);
+}
+
+/// A pass over IR-LL.
+pub trait LlPass: Send + Sync {
+    /// The name diffs are labelled with, e.g. `dce`.
+    fn name(&self) -> &'static str;
+    fn run(&self, program: &mut LLProgram);
+}
+
+/// What one pass changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassDiff {
+    pub pass: &'static str,
+    /// A unified diff of the IR's textual form, or `None` when the pass left
+    /// the program as it was.
+    pub diff: Option<String>,
+}
+
+impl PassDiff {
+    /// The diff, or a line saying the pass changed nothing.
+    pub fn render(&self) -> String {
+        match &self.diff {
+            Some(diff) => diff.clone(),
+            None => format!("pass `{}`: no changes\n", self.pass),
+        }
+    }
+}
+
+/// The passes run on every compilation, in order: IR-HL passes right after
+/// lowering, IR-LL passes right before code generation.
+pub struct PassManager {
+    hl_passes: Vec<Box<dyn HlPass>>,
+    ll_passes: Vec<Box<dyn LlPass>>,
+}
+
+impl Default for PassManager {
+    /// The standard pipeline: dead code elimination on IR-LL.
+    fn default() -> Self {
+        PassManager::new().with_ll_pass(DeadCodeElimination)
+    }
+}
+
+impl PassManager {
+    /// A manager that runs no passes.
+    pub fn new() -> Self {
+        PassManager {
+            hl_passes: Vec::new(),
+            ll_passes: Vec::new(),
+        }
+    }
+
+    /// Appends a pass over IR-HL.
+    pub fn with_hl_pass(mut self, pass: impl HlPass + 'static) -> Self {
+        self.hl_passes.push(Box::new(pass));
+        self
+    }
+
+    /// Appends a pass over IR-LL.
+    pub fn with_ll_pass(mut self, pass: impl LlPass + 'static) -> Self {
+        self.ll_passes.push(Box::new(pass));
+        self
+    }
+
+    /// Runs the IR-HL passes. Returns what each changed when `record` is set,
+    /// and nothing otherwise.
+    pub fn run_hl(&self, program: &mut HLProgram, record: bool) -> Vec<PassDiff> {
+        self.hl_passes
+            .iter()
+            .filter_map(|pass| run_pass(pass.name(), program, |p| pass.run(p), record))
+            .collect()
+    }
+
+    /// Runs the IR-LL passes. Returns what each changed when `record` is set,
+    /// and nothing otherwise.
+    pub fn run_ll(&self, program: &mut LLProgram, record: bool) -> Vec<PassDiff> {
+        self.ll_passes
+            .iter()
+            .filter_map(|pass| run_pass(pass.name(), program, |p| pass.run(p), record))
+            .collect()
+    }
+}
+
+/// Runs one pass, diffing the program's textual form around it if `record`.
+fn run_pass<P: fmt::Display>(
+    name: &'static str,
+    program: &mut P,
+    run: impl FnOnce(&mut P),
+    record: bool,
+) -> Option<PassDiff> {
+    if !record {
+        run(program);
+        return None;
+    }
+    let before = program.to_string();
+    run(program);
+    Some(PassDiff {
+        pass: name,
+        diff: unified_diff(&before, &program.to_string(), name),
+    })
+}
+
+/// Removes instructions whose results are never used and that have no other
+/// effect: allocations, loads, comparisons, calls to the runtime's read-only
+/// queries, and stores to a slot nothing loads from. Repeats until nothing
+/// more goes, since a removed instruction may have held the last use of
+/// another.
+pub struct DeadCodeElimination;
+
+/// Runtime functions that only read their arguments, so an unused call to
+/// one can go.
+const PURE_FUNCTIONS: &[&str] = &[
+    "sum_array",
+    "min_array",
+    "max_array",
+    "array_length",
+    "average_array",
+    "median_array",
+    "array_contains",
+    "find_index",
+    "concat_arrays",
+    "copy_array",
+    "array_to_float",
+    "array_to_int",
+    "is_sorted",
+    "is_empty",
+];
+
+impl LlPass for DeadCodeElimination {
+    fn name(&self) -> &'static str {
+        "dce"
+    }
+
+    fn run(&self, program: &mut LLProgram) {
+        for function in &mut program.functions {
+            while remove_dead_instructions(function) {}
+        }
+    }
+}
+
+/// One round of dead code elimination. Returns whether anything was removed.
+fn remove_dead_instructions(function: &mut LLFunction) -> bool {
+    let used = used_registers(function);
+    let mut removed = false;
+    for block in &mut function.basic_blocks {
+        let before = block.instructions.len();
+        block.retain_instructions(|instruction| {
+            dead_result(instruction).is_none_or(|dest| used.contains(&dest))
+        });
+        removed |= block.instructions.len() != before;
+    }
+    removed
+}
+
+/// The register that must be read for `instruction` to matter: the one it
+/// defines, if the instruction does nothing else, or the slot a store writes.
+fn dead_result(instruction: &LLInstruction) -> Option<Register> {
+    match instruction {
+        LLInstruction::Store { dest_ptr, .. } => Some(*dest_ptr),
+        LLInstruction::Alloc { dest, .. }
+        | LLInstruction::Load { dest, .. }
+        | LLInstruction::ICmp { dest, .. }
+        | LLInstruction::BinaryOp { dest, .. }
+        | LLInstruction::Phi { dest, .. } => Some(*dest),
+        LLInstruction::Call {
+            dest,
+            function_name,
+            ..
+        } if PURE_FUNCTIONS.contains(&function_name.as_str()) => *dest,
+        LLInstruction::Call { .. } => None,
+    }
+}
+
+/// Every register read by an instruction or terminator of `function`. A
+/// store only writes its slot, so it does not count as reading it.
+fn used_registers(function: &LLFunction) -> HashSet<Register> {
+    let mut used = HashSet::new();
+    let mut use_value = |value: &LLValue| {
+        if let LLValue::Register(register) = value {
+            used.insert(*register);
+        }
+    };
+    for block in &function.basic_blocks {
+        for instruction in &block.instructions {
+            match instruction {
+                LLInstruction::Alloc { .. } => {}
+                LLInstruction::Load { source_ptr, .. } => {
+                    use_value(&LLValue::Register(*source_ptr))
+                }
+                LLInstruction::Store { value, .. } => use_value(value),
+                LLInstruction::Call { arguments, .. } => arguments.iter().for_each(&mut use_value),
+                LLInstruction::ICmp { lhs, rhs, .. } | LLInstruction::BinaryOp { lhs, rhs, .. } => {
+                    use_value(lhs);
+                    use_value(rhs);
+                }
+                LLInstruction::Phi { incoming, .. } => {
+                    incoming.iter().for_each(|(value, _)| use_value(value))
+                }
+            }
+        }
+        match &block.terminator {
+            Terminator::Return(Some(value)) | Terminator::CondBr { cond: value, .. } => {
+                use_value(value)
+            }
+            Terminator::Return(None) | Terminator::Br { .. } | Terminator::Unreachable => {}
+        }
+    }
+    used
+}
+
+/// One line of a diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// A unified diff from `before` to `after`, by line, or `None` when they are
+/// equal. The file headers name `pass`, or whatever else is compared.
+pub fn unified_diff(before: &str, after: &str, pass: &str) -> Option<String> {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+    let lines = diff_lines(&old, &new);
+    let changed: Vec<usize> = (0..lines.len())
+        .filter(|&i| !matches!(lines[i], DiffLine::Same(_)))
+        .collect();
+    if changed.is_empty() {
+        return None;
+    }
+
+    // Each change with its context; overlapping ranges merge into one hunk.
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        let start = i.saturating_sub(DIFF_CONTEXT);
+        let end = (i + DIFF_CONTEXT + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- before {}\n+++ after {}\n", pass, pass);
+    let mut old_line = 0;
+    let mut new_line = 0;
+    let mut position = 0;
+    for (start, end) in hunks {
+        for line in &lines[position..start] {
+            advance(line, &mut old_line, &mut new_line);
+        }
+        let (old_start, new_start) = (old_line, new_line);
+        let mut body = String::new();
+        for line in &lines[start..end] {
+            advance(line, &mut old_line, &mut new_line);
+            let (marker, text) = match line {
+                DiffLine::Same(text) => (' ', text),
+                DiffLine::Removed(text) => ('-', text),
+                DiffLine::Added(text) => ('+', text),
+            };
+            body.push_str(&format!("{}{}\n", marker, text));
+        }
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n{}",
+            hunk_range(old_start, old_line - old_start),
+            hunk_range(new_start, new_line - new_start),
+            body
+        ));
+        position = end;
+    }
+    Some(out)
+}
+
+/// Counts `line` towards the old and the new side it appears on.
+fn advance(line: &DiffLine, old_line: &mut usize, new_line: &mut usize) {
+    match line {
+        DiffLine::Same(_) => {
+            *old_line += 1;
+            *new_line += 1;
+        }
+        DiffLine::Removed(_) => *old_line += 1,
+        DiffLine::Added(_) => *new_line += 1,
+    }
+}
+
+/// `start,len` of a hunk header, with `start` counted from 1. An empty range
+/// names the line before it, as `diff -u` does.
+fn hunk_range(start: usize, len: usize) -> String {
+    if len == 0 {
+        format!("{},0", start)
+    } else {
+        format!("{},{}", start + 1, len)
+    }
+}
+
+/// Aligns `old` with `new` on a longest common subsequence of their lines,
+/// after setting aside the lines they start and end with in common.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffLine<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut lines: Vec<DiffLine> = old[..prefix].iter().map(|l| DiffLine::Same(l)).collect();
+    if (old_middle.len() + 1) * (new_middle.len() + 1) > MAX_DIFF_CELLS {
+        lines.extend(old_middle.iter().map(|l| DiffLine::Removed(l)));
+        lines.extend(new_middle.iter().map(|l| DiffLine::Added(l)));
+    } else {
+        lines.extend(align(old_middle, new_middle));
+    }
+    lines.extend(old[old.len() - suffix..].iter().map(|l| DiffLine::Same(l)));
+    lines
+}
+
+/// The classic dynamic-programming alignment, removals before additions.
+fn align<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffLine<'a>> {
+    let width = new.len() + 1;
+    // `common[i * width + j]`: the longest common subsequence of `old[i..]`
+    // and `new[j..]`.
+    let mut common = vec![0usize; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i * width + j] = if old[i] == new[j] {
+                common[(i + 1) * width + j + 1] + 1
+            } else {
+                common[(i + 1) * width + j].max(common[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(DiffLine::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if j == new.len()
+            || (i < old.len() && common[(i + 1) * width + j] >= common[i * width + j + 1])
+        {
+            lines.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lowering_hl_to_ll::lower_hl_to_ll;
+    use naldom_ir::{HLExpression, HLStatement, HLValue};
+
+    /// `var_0 = create_random_array(5)`, then `var_1 = sum_array(var_0)`
+    /// whose result nothing reads, then a print.
+    fn program_with_dead_sum() -> LLProgram {
+        let call = |function: &str, arguments| HLExpression::FunctionCall {
+            function: function.to_string(),
+            arguments,
+        };
+        let array = || HLExpression::Variable("var_0".to_string());
+        lower_hl_to_ll(&HLProgram {
+            statements: vec![
+                HLStatement::Assign {
+                    variable: "var_0".to_string(),
+                    expression: call(
+                        "create_random_array",
+                        vec![HLExpression::Literal(HLValue::Integer(5))],
+                    ),
+                },
+                HLStatement::Assign {
+                    variable: "var_1".to_string(),
+                    expression: call("sum_array", vec![array()]),
+                },
+                HLStatement::Call {
+                    function: "print_array".to_string(),
+                    arguments: vec![array(), HLExpression::Literal(HLValue::Integer(0))],
+                },
+            ],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_dce_removes_unused_queries_but_keeps_effects() {
+        // Arrange
+        let mut program = program_with_dead_sum();
+
+        // Act
+        PassManager::default().run_ll(&mut program, false);
+
+        // Assert
+        let calls: Vec<&str> = program.functions[0].basic_blocks[0]
+            .instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                LLInstruction::Call { function_name, .. } => Some(function_name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(calls, ["create_random_array", "print_array"]);
+    }
+
+    #[test]
+    fn test_pass_diff_names_the_pass_and_shows_the_removed_call() {
+        // Arrange
+        let mut program = program_with_dead_sum();
+
+        // Act
+        let diffs = PassManager::default().run_ll(&mut program, true);
+        let unchanged = PassManager::default().run_ll(&mut program, true);
+
+        // Assert
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(
+            diffs[0].render(),
+            "--- before dce\n+++ after dce\n@@ -3,10 +3,6 @@\n   %0 = call @create_random_array(5)  ; hl_statement=0\n   %1 = alloc i8*  ; hl_statement=0\n   store %0, %1  ; hl_statement=0\n-  %2 = load %1  ; hl_statement=1\n-  %3 = call @sum_array(%2)  ; hl_statement=1\n-  %4 = alloc i64  ; hl_statement=1\n-  store %3, %4  ; hl_statement=1\n   %5 = load %1  ; hl_statement=2\n   call @print_array(%5, 0)  ; hl_statement=2\n   ret 0\n"
+        );
+        assert_eq!(unchanged[0].render(), "pass `dce`: no changes\n");
+    }
+
+    #[test]
+    fn test_unified_diff_hunks_keep_three_lines_of_context() {
+        // Arrange
+        let before = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let after = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+
+        // Act
+        let diff = unified_diff(before, after, "test").unwrap();
+
+        // Assert
+        assert_eq!(
+            diff,
+            "--- before test\n+++ after test\n@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n@@ -8,3 +8,4 @@\n h\n i\n j\n+k\n"
+        );
+        assert_eq!(unified_diff(before, before, "test"), None);
+    }
+}
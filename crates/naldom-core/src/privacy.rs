@@ -0,0 +1,260 @@
+// crates/naldom-core/src/privacy.rs
+
+//! Keeping private notes out of the prompt.
+//!
+//! A source file is usually a Markdown document in which only the `:::naldom`
+//! blocks are the program. Before the document is sent to the model, the prose
+//! around the blocks is replaced by [`REDACTED`] markers, so the model still
+//! sees where each block stood without reading the notes themselves:
+//!
+//! ```text
+//! My shopping list: eggs, milk.       [redacted]
+//! :::naldom                           :::naldom
+//! Create an array of 10 numbers.  =>  Create an array of 10 numbers.
+//! :::                                 :::
+//! Call Alice about the rent.          [redacted]
+//! ```
+//!
+//! A document without any block is the program as a whole and is sent intact.
+
+/// The line opening a program block.
+const BLOCK_START: &str = ":::naldom";
+
+/// The line closing a program block.
+const BLOCK_END: &str = ":::";
+
+/// What stands in for removed prose.
+pub const REDACTED: &str = "[redacted]";
+
+/// The sentences of prose kept on each side of a block by default.
+pub const DEFAULT_CONTEXT_SENTENCES: usize = 1;
+
+/// How much of the prose around the blocks reaches the model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Privacy {
+    /// Only the blocks; every stretch of prose becomes one marker.
+    Strict,
+    /// The blocks, and up to `context_sentences` sentences of prose directly
+    /// before and after each one. The rest of the prose becomes markers.
+    Normal { context_sentences: usize },
+}
+
+impl Default for Privacy {
+    fn default() -> Self {
+        Privacy::Normal {
+            context_sentences: DEFAULT_CONTEXT_SENTENCES,
+        }
+    }
+}
+
+/// A stretch of the document: prose, or a block with its fences.
+enum Segment<'a> {
+    Prose(Vec<&'a str>),
+    Block(Vec<&'a str>),
+}
+
+/// The part of `source` that may be sent to the model under `privacy`.
+pub fn redact(source: &str, privacy: Privacy) -> String {
+    let segments = split_blocks(source);
+    if !segments
+        .iter()
+        .any(|segment| matches!(segment, Segment::Block(_)))
+    {
+        return source.to_string();
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    for (index, segment) in segments.iter().enumerate() {
+        match segment {
+            Segment::Block(block) => lines.extend(block.iter().map(|line| line.to_string())),
+            Segment::Prose(prose) => {
+                let text = prose.join(" ");
+                if text.trim().is_empty() {
+                    continue;
+                }
+                let kept = match privacy {
+                    Privacy::Strict => REDACTED.to_string(),
+                    Privacy::Normal { context_sentences } => keep_context(
+                        &text,
+                        context_sentences,
+                        index > 0,
+                        index + 1 < segments.len(),
+                    ),
+                };
+                lines.push(kept);
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// The program in `source`: the contents of its `:::naldom` blocks without
+/// their fences, or the whole document when it has no block.
+pub fn program_text(source: &str) -> String {
+    let segments = split_blocks(source);
+    let blocks: Vec<&str> = segments
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Block(block) => Some(block),
+            Segment::Prose(_) => None,
+        })
+        .flat_map(|block| {
+            block
+                .iter()
+                .copied()
+                .filter(|line| !matches!(line.trim(), BLOCK_START | BLOCK_END))
+        })
+        .collect();
+    if blocks.is_empty() {
+        return source.to_string();
+    }
+    blocks.join("\n")
+}
+
+/// Splits `source` into alternating prose and blocks. A block missing its
+/// closing fence runs to the end of the document.
+fn split_blocks(source: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut current = Vec::new();
+    let mut in_block = false;
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if !in_block && trimmed == BLOCK_START {
+            if !current.is_empty() {
+                segments.push(Segment::Prose(std::mem::take(&mut current)));
+            }
+            in_block = true;
+            current.push(line);
+        } else if in_block && trimmed == BLOCK_END {
+            current.push(line);
+            segments.push(Segment::Block(std::mem::take(&mut current)));
+            in_block = false;
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        segments.push(if in_block {
+            Segment::Block(current)
+        } else {
+            Segment::Prose(current)
+        });
+    }
+    segments
+}
+
+/// The first `count` sentences of `prose` when a block precedes it and the
+/// last `count` when one follows it, with a marker for every run of
+/// sentences dropped in between.
+fn keep_context(prose: &str, count: usize, block_before: bool, block_after: bool) -> String {
+    let sentences = sentences(prose);
+    let len = sentences.len();
+    let kept =
+        |index: usize| (block_before && index < count) || (block_after && index + count >= len);
+
+    let mut parts: Vec<&str> = Vec::new();
+    for (index, sentence) in sentences.iter().enumerate() {
+        if kept(index) {
+            parts.push(sentence);
+        } else if parts.last() != Some(&REDACTED) {
+            parts.push(REDACTED);
+        }
+    }
+    parts.join(" ")
+}
+
+/// The sentences of `prose`: each ends at a `.`, `!` or `?` followed by
+/// whitespace, or at the end of the text.
+fn sentences(prose: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = prose.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let at_boundary = chars.peek().is_none_or(|(_, next)| next.is_whitespace());
+        if matches!(c, '.' | '!' | '?') && at_boundary {
+            let end = index + c.len_utf8();
+            sentences.push(prose[start..end].trim());
+            start = end;
+        }
+    }
+    sentences.push(prose[start..].trim());
+    sentences.retain(|sentence| !sentence.is_empty());
+    sentences
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = "# Groceries\nEggs, milk. Call Alice about the rent. Sort the list.\n:::naldom\nCreate an array of 10 numbers.\n:::\nThen print it. My PIN is 1234. Print it again.\n:::naldom\nPrint the array.\n:::\nThe end.";
+
+    #[test]
+    fn test_normal_mode_keeps_the_sentences_next_to_each_block() {
+        // Act
+        let redacted = redact(DOCUMENT, Privacy::default());
+
+        // Assert
+        assert_eq!(
+            redacted,
+            "[redacted] Sort the list.\n:::naldom\nCreate an array of 10 numbers.\n:::\nThen print it. [redacted] Print it again.\n:::naldom\nPrint the array.\n:::\nThe end."
+        );
+    }
+
+    #[test]
+    fn test_context_sentences_are_counted_on_each_side() {
+        // Arrange
+        let document = "A. B. C. D.\n:::naldom\nPrint the array.\n:::\nE. F. G.";
+
+        // Act
+        let none = redact(
+            document,
+            Privacy::Normal {
+                context_sentences: 0,
+            },
+        );
+        let two = redact(
+            document,
+            Privacy::Normal {
+                context_sentences: 2,
+            },
+        );
+
+        // Assert
+        assert_eq!(
+            none,
+            "[redacted]\n:::naldom\nPrint the array.\n:::\n[redacted]"
+        );
+        assert_eq!(
+            two,
+            "[redacted] C. D.\n:::naldom\nPrint the array.\n:::\nE. F. [redacted]"
+        );
+    }
+
+    #[test]
+    fn test_documents_without_blocks_are_sent_intact() {
+        // Arrange
+        let document = "Create an array of 5 numbers. Sort it.";
+
+        // Act & Assert
+        assert_eq!(redact(document, Privacy::Strict), document);
+    }
+
+    #[test]
+    fn test_program_text_is_the_blocks_without_their_fences() {
+        assert_eq!(
+            program_text(DOCUMENT),
+            "Create an array of 10 numbers.\nPrint the array."
+        );
+        assert_eq!(program_text("Print the array."), "Print the array.");
+    }
+
+    #[test]
+    fn test_an_unclosed_block_runs_to_the_end() {
+        // Act
+        let redacted = redact("Notes.\n:::naldom\nPrint the array.", Privacy::Strict);
+
+        // Assert
+        assert_eq!(redacted, "[redacted]\n:::naldom\nPrint the array.");
+    }
+}
@@ -0,0 +1,61 @@
+// crates/naldom-core/src/program_generator.rs
+
+//! Synthetic programs for the benchmarks and stress tests: valid intent JSON
+//! of any length, in the shape the LLM would return, so the whole pipeline
+//! can be timed from the parser onwards without a model.
+
+/// The JSON of a program of `intents` intents: `CreateArray`, `SortArray`,
+/// `PrintArray` triples, each acting on the array its triple created. The
+/// last triple is cut short when `intents` is not a multiple of three.
+///
+/// Array sizes cycle through a few small values, and sort orders alternate,
+/// so the lowered program is not one statement repeated.
+pub fn generate_program(intents: usize) -> String {
+    let entries: Vec<String> = (0..intents)
+        .map(|index| {
+            let triple = index / 3;
+            match index % 3 {
+                0 => format!(
+                    r#"{{"intent": "CreateArray", "parameters": {{"size": {}}}}}"#,
+                    8 + triple % 5
+                ),
+                1 => format!(
+                    r#"{{"intent": "SortArray", "parameters": {{"order": "{}", "targetOrdinal": {}}}}}"#,
+                    if triple % 2 == 0 { "ascending" } else { "descending" },
+                    triple + 1
+                ),
+                _ => format!(
+                    r#"{{"intent": "PrintArray", "parameters": {{"targetOrdinal": {}}}}}"#,
+                    triple + 1
+                ),
+            }
+        })
+        .collect();
+    format!("[\n{}\n]", entries.join(",\n"))
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_to_intent_graph;
+    use crate::semantic_analyzer::SemanticAnalyzer;
+    use naldom_ir::Intent;
+
+    #[test]
+    fn test_generated_programs_parse_and_analyze() {
+        // Arrange
+        let json = generate_program(10);
+
+        // Act
+        let intent_graph = parse_to_intent_graph(&json).expect("Parsing failed");
+        let validated_graph = SemanticAnalyzer::new()
+            .analyze(&intent_graph)
+            .expect("Analysis failed");
+
+        // Assert
+        assert_eq!(validated_graph.len(), 10);
+        assert!(matches!(validated_graph[9], Intent::CreateArray(_)));
+        assert_eq!(validated_graph[7].target_ordinal(), Some(3));
+    }
+}
@@ -0,0 +1,229 @@
+// crates/naldom-core/src/progress.rs
+
+//! Progress reporting for frontends (editors, playgrounds) that want to follow a
+//! compilation live instead of waiting for the final result.
+
+use serde::Serialize;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A named stage of the compiler pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Inference,
+    Parse,
+    Analysis,
+    Lowering,
+    LowLevelLowering,
+    Codegen,
+    Link,
+}
+
+impl Stage {
+    /// The stable name used in progress events.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Inference => "inference",
+            Stage::Parse => "parse",
+            Stage::Analysis => "analysis",
+            Stage::Lowering => "lowering",
+            Stage::LowLevelLowering => "low_level_lowering",
+            Stage::Codegen => "codegen",
+            Stage::Link => "link",
+        }
+    }
+}
+
+/// A single progress event. Serialized as one JSON object per line by `JsonProgress`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent<'a> {
+    StageStart { stage: Stage },
+    StageEnd { stage: Stage, duration_ms: f64 },
+    Diagnostic { level: &'a str, message: &'a str },
+    ArtifactWritten { path: &'a str },
+}
+
+impl<'a> ProgressEvent<'a> {
+    pub fn stage_end(stage: Stage, duration: Duration) -> Self {
+        ProgressEvent::StageEnd {
+            stage,
+            duration_ms: duration.as_secs_f64() * 1000.0,
+        }
+    }
+
+    pub fn artifact_written(path: &'a Path) -> Option<Self> {
+        path.to_str()
+            .map(|path| ProgressEvent::ArtifactWritten { path })
+    }
+}
+
+/// Receives progress events from the compiler. Implementations must be cheap,
+/// because events are emitted synchronously between pipeline stages.
+pub trait ProgressSink: Send + Sync {
+    fn emit(&self, event: &ProgressEvent);
+}
+
+/// The default sink: discards every event.
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn emit(&self, _event: &ProgressEvent) {}
+}
+
+/// Emits newline-delimited JSON events. Each event is written with a single
+/// `write_all` under a lock and flushed immediately, so lines never interleave.
+pub struct JsonProgress<W: Write + Send> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send> JsonProgress<W> {
+    pub fn new(writer: W) -> Self {
+        JsonProgress {
+            writer: Mutex::new(writer),
+        }
+    }
+}
+
+impl JsonProgress<std::io::Stderr> {
+    pub fn stderr() -> Self {
+        Self::new(std::io::stderr())
+    }
+}
+
+impl<W: Write + Send> ProgressSink for JsonProgress<W> {
+    fn emit(&self, event: &ProgressEvent) {
+        let Ok(mut line) = serde_json::to_string(event) else {
+            return;
+        };
+        line.push('\n');
+        // A poisoned lock only means another thread panicked mid-write; keep reporting.
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writer.write_all(line.as_bytes());
+        let _ = writer.flush();
+    }
+}
+
+/// A human-readable progress display on stderr: a spinner-style status line per
+/// stage on a terminal, plain "finished" lines otherwise.
+pub struct HumanProgress {
+    interactive: bool,
+}
+
+impl Default for HumanProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HumanProgress {
+    pub fn new() -> Self {
+        HumanProgress {
+            interactive: std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+impl ProgressSink for HumanProgress {
+    fn emit(&self, event: &ProgressEvent) {
+        let mut stderr = std::io::stderr().lock();
+        let _ = match event {
+            ProgressEvent::StageStart { stage } if self.interactive => {
+                write!(stderr, "\r⠿ {}...", stage.as_str())
+            }
+            ProgressEvent::StageStart { .. } => Ok(()),
+            ProgressEvent::StageEnd { stage, duration_ms } => {
+                writeln!(stderr, "\r✓ {} ({:.1} ms)", stage.as_str(), duration_ms)
+            }
+            ProgressEvent::Diagnostic { level, message } => {
+                writeln!(stderr, "\r{}: {}", level, message)
+            }
+            ProgressEvent::ArtifactWritten { path } => writeln!(stderr, "\r→ wrote {}", path),
+        };
+        let _ = stderr.flush();
+    }
+}
+
+/// Forwards every event to another sink while recording how long each stage
+/// took, for reports written after the compilation (e.g. build manifests).
+pub struct TimingRecorder {
+    inner: Box<dyn ProgressSink>,
+    timings: Arc<Mutex<Vec<(Stage, f64)>>>,
+}
+
+impl TimingRecorder {
+    pub fn new(inner: Box<dyn ProgressSink>) -> Self {
+        TimingRecorder {
+            inner,
+            timings: Arc::default(),
+        }
+    }
+
+    /// A handle to the recorded `(stage, duration_ms)` pairs, in completion
+    /// order. It stays valid after the recorder is handed to the compiler.
+    pub fn timings(&self) -> Arc<Mutex<Vec<(Stage, f64)>>> {
+        Arc::clone(&self.timings)
+    }
+}
+
+impl ProgressSink for TimingRecorder {
+    fn emit(&self, event: &ProgressEvent) {
+        if let ProgressEvent::StageEnd { stage, duration_ms } = event {
+            let mut timings = self.timings.lock().unwrap_or_else(|e| e.into_inner());
+            timings.push((*stage, *duration_ms));
+        }
+        self.inner.emit(event);
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_events_are_single_lines() {
+        // Arrange
+        let sink = JsonProgress::new(Vec::new());
+
+        // Act
+        sink.emit(&ProgressEvent::StageStart {
+            stage: Stage::Inference,
+        });
+        sink.emit(&ProgressEvent::Diagnostic {
+            level: "error",
+            message: "line one\nline two",
+        });
+        let output = String::from_utf8(sink.writer.into_inner().unwrap()).unwrap();
+
+        // Assert
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], r#"{"event":"stage_start","stage":"inference"}"#);
+        let diagnostic: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(diagnostic["event"], "diagnostic");
+        assert_eq!(diagnostic["message"], "line one\nline two");
+    }
+
+    #[test]
+    fn test_timing_recorder_keeps_stage_durations() {
+        // Arrange
+        let recorder = TimingRecorder::new(Box::new(NoProgress));
+        let timings = recorder.timings();
+
+        // Act
+        recorder.emit(&ProgressEvent::StageStart {
+            stage: Stage::Parse,
+        });
+        recorder.emit(&ProgressEvent::stage_end(
+            Stage::Parse,
+            Duration::from_millis(3),
+        ));
+
+        // Assert
+        assert_eq!(*timings.lock().unwrap(), [(Stage::Parse, 3.0)]);
+    }
+}
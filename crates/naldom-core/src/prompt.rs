@@ -0,0 +1,604 @@
+// crates/naldom-core/src/prompt.rs
+
+//! Prompt construction for the NLD parser.
+//!
+//! Everything here is pure and synchronous, so the exact text sent to the model
+//! can be unit-tested without a running llama.cpp server.
+
+use crate::privacy::{Privacy, redact};
+use crate::schema::{INTENT_SCHEMAS, IntentSchema, ParamDefault, ParamValues, intent_names};
+use crate::stdlib::render_procedures;
+
+/// A fully assembled request for the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prompt {
+    /// The complete prompt text: system instructions followed by the user's source.
+    pub text: String,
+    /// The GBNF grammar constraining the model's output.
+    pub grammar: String,
+    /// Sequences at which generation must stop.
+    pub stop: Vec<String>,
+    /// How the model picks its answer.
+    pub sampling: Sampling,
+}
+
+/// The sampling temperature the model is asked to use by default.
+pub const DEFAULT_TEMPERATURE: f32 = 0.1;
+
+/// The sampler settings sent to the model provider.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sampling {
+    pub temperature: f32,
+    /// Seeds the provider's sampler; `None` lets the provider choose one.
+    pub seed: Option<u64>,
+}
+
+impl Sampling {
+    /// Greedy decoding from a fixed sampler seed, so the same model answers
+    /// the same prompt the same way every time.
+    pub const DETERMINISTIC: Sampling = Sampling {
+        temperature: 0.0,
+        seed: Some(0),
+    };
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Sampling {
+            temperature: DEFAULT_TEMPERATURE,
+            seed: None,
+        }
+    }
+}
+
+/// Knobs for prompt construction.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PromptOptions {
+    /// Maximum number of (estimated) tokens of user source to include.
+    /// Longer sources are truncated; `None` keeps the source intact.
+    pub max_user_tokens: Option<usize>,
+    /// How much of the prose around the `:::naldom` blocks is sent.
+    pub privacy: Privacy,
+    /// Why the model's previous answer was rejected, sent ahead of the user's
+    /// source so the next answer can avoid the same mistake.
+    pub feedback: Option<String>,
+    /// Replaces the built-in instructions at the top of the prompt, e.g. with
+    /// a variant being evaluated. The intent listing still follows it.
+    pub instructions: Option<String>,
+    /// Passed on to the model with the prompt.
+    pub sampling: Sampling,
+    /// Constrains the answer to the shape of an intent list only, instead of
+    /// also to each intent's parameter names and enumerated values. Some
+    /// models stall under the strict grammar.
+    pub loose_grammar: bool,
+}
+
+/// A rough, model-independent estimate: one token per four characters.
+const CHARS_PER_TOKEN: usize = 4;
+
+const SYSTEM_PROMPT_HEADER: &str = r#"
+CONTEXT:
+You are an expert Frontend Compiler. Your task is to analyze the user's request, which is written in a natural language called Naldom, and transform it into a strictly structured JSON array of "intents". This JSON is the Abstract Syntax Tree (AST) for the Naldom language.
+TASK:
+1. Analyze the user's request.
+2. Identify the sequence of operations the user wants to perform.
+3. For each operation, map it to one of the "AVAILABLE INTENTS".
+4. Construct a JSON object for each intent.
+5. Combine these objects into a single JSON array.
+6. Respond with ONLY the raw JSON array.
+IMPORTANT:
+- You MUST respond with ONLY the JSON array. Do not include any extra text, explanations, markdown formatting, or "think" blocks.
+- If a parameter is not specified by the user, you MUST use a sensible default value as specified below.
+- You MUST NOT generate an intent that operates on a variable before it has been created.
+- "PrintArray" shows only the first and last elements of long arrays. Set "full" to true only if the user asks for every element.
+- If the user asks to print every array at once ("print both arrays", "print everything"), use one "PrintArray" intent with "target" set to "all". Never name an array "all".
+- Operations act on the most recently created array. If the user refers to another array by position ("the first array", "the second one"), set "targetOrdinal" to its position in creation order, starting at 1.
+- If the user names an array ("an array called scores"), set "name" on its "CreateArray" to that name exactly as written. When a later operation refers to an array by that name, set "target" to the name instead of using "targetOrdinal".
+- If the user lists the numbers an array holds ("an array of 3, 1 and 4"), set "values" on its "CreateArray" to those numbers in order and leave out "size".
+- If the user asks for whole numbers ("5 random integers"), set "dtype" on its "CreateArray" to "int".
+- If the user adds a number to an existing array ("add 42 to the array"), use an "AppendToArray" intent with that "value".
+- If the user sets every element of an array to one number ("fill the array with zeros"), use a "FillArray" intent with that "value". If the user removes every element ("empty the array", "clear it"), use a "ClearArray" intent.
+- If the user keeps only part of an array ("keep only the first 5 elements"), use a "SliceArray" intent whose "start" is the index of the first element to keep, counting from 0, and whose "end" is the index just past the last one, e.g. "start": 0 and "end": 5. Leave out "end" to keep everything from "start" on. To keep the last elements ("keep only the last 3 elements"), set "fromEnd" to true and "start" to how many to keep, e.g. "start": 3 and "fromEnd": true, and leave out "end".
+- If the user applies arithmetic to every element ("multiply every element by 3", "add 10 to each element"), use a "MapArray" intent whose "operation" is "add", "subtract", "multiply" or "divide" and whose "operand" is the number.
+- If the user turns an array's elements into whole numbers or decimal numbers ("round the numbers down to integers", "turn them back into decimals"), use a "ConvertArray" intent whose "to" is "int" or "float".
+- If the user names a sorting algorithm ("sort it with bubble sort"), set "algorithm" on its "SortArray" to "quick", "merge" or "bubble". Leave it out otherwise.
+- If the user joins two named arrays into a new one ("join a and b into c"), use a "ConcatArrays" intent with the names as "first" and "second", in the order given, and the new array's "name" if it has one.
+- If the user wants a copy of an array ("make a copy of the array, sort the copy, print both"), use a "CopyArray" intent, with the copy's "name" if it has one. Operations after it act on the copy; to act on the original, set "target" to its name or "targetOrdinal" to its position.
+- If the user asks whether an array holds a number ("does the array contain 17?"), use a "ContainsValue" intent with that "value".
+- If the user asks where a number is in an array ("find where 42 is and print its position"), use a "FindIndex" intent with that "value".
+- If the user asks for a summary or description of an array ("describe the array"), use a "DescribeArray" intent.
+- If the user asks for a histogram or a chart of how the values are spread ("show a histogram with 5 buckets"), use a "PrintHistogram" intent, with "buckets" only if the user gives a number of buckets or bins.
+- If the user asks for a matrix or grid ("a 3 by 4 matrix"), use "CreateMatrix" with "rows" and "cols", and "PrintMatrix" (no parameters) to print it. Matrices are not arrays: never use array intents on them.
+- If the user wants to know how long steps take ("time how long the sort takes"), put a "StartTimer" intent before those steps and a "PrintElapsed" intent after them. Neither has parameters.
+- If the user wants the program to stop ("stop the program", "exit with code 3"), use an "Exit" intent, with "code" only if the user gives an exit code. Nothing after it runs.
+- If the user names a number ("let the threshold be 50"), use a "SetVariable" intent with that "name" and "value". A later "size" may then be the name as a string instead of a number, e.g. "size": "threshold".
+- If the user names a sum, minimum, maximum or count ("sum the array, call it total"), set "name" on its "SumArray", "MinArray", "MaxArray" or "CountElements". A later "size" or "operand" may then be that name as a string, e.g. "size": "total".
+- If a sentence does not describe an operation, represent it with a "Note" intent whose "text" is that sentence. Never invent an intent that is not listed.
+- If the user asks to do something several times ("three times", "repeat 5 times"), use a single "Repeat" intent with that "count" whose "body" is the JSON array of intents to repeat. Create arrays before the "Repeat", never inside its "body".
+- If the user makes steps depend on the array ("if the array is sorted, ..."), use a "Conditional" intent whose "condition" is "is_sorted" or "is_empty", with the intents to run in "then" and, if given, the alternative in "else".
+- If the request matches one of the "AVAILABLE PROCEDURES", prefer a single "UseProcedure" intent with that "name" over spelling out its steps. Put any non-default parameters in "arguments".
+DEFAULT VALUES:
+"#;
+
+const GRAMMAR_TEMPLATE: &str = r#"
+root   ::= intents
+intents ::= "[" ws intent ("," ws intent)* ws "]"
+{INTENT_RULES}
+params ::= "{" ws param ("," ws param)* ws "}"
+param  ::= "\"" string "\"" ws ":" ws value
+value  ::= string-literal | number | boolean | params | intents | numbers
+numbers ::= "[" ws (number ("," ws number)*)? ws "]"
+string-literal ::= "\"" string "\""
+string ::= ([^"\\] | "\\" (["\\/bfnrt] | "u" [0-9a-fA-F] [0-9a-fA-F] [0-9a-fA-F] [0-9a-fA-F]))*
+boolean ::= "true" | "false"
+number ::= "-"? ([0-9] | [1-9] [0-9]*) ("." [0-9]+)? ([eE] [-+]? [0-9]+)?
+ws ::= [ \t\n\r]*
+"#;
+
+/// The stop sequences sent with every request.
+pub const STOP_SEQUENCES: [&str; 2] = ["\nUSER REQUEST:", "ASSISTANT:"];
+
+/// Builds the complete prompt for `user_source`, redacted as `opts.privacy`
+/// asks.
+pub fn build_prompt(user_source: &str, opts: &PromptOptions) -> Prompt {
+    let mut text = opts
+        .instructions
+        .clone()
+        .unwrap_or_else(|| SYSTEM_PROMPT_HEADER.to_string());
+    text.push_str(&render_default_values());
+    text.push_str("ALLOWED VALUES:\n");
+    text.push_str(&render_allowed_values());
+    text.push_str("AVAILABLE INTENTS (JSON Schema):\n");
+    text.push_str(&render_available_intents());
+    text.push_str("\nAVAILABLE PROCEDURES:\n");
+    text.push_str(&render_procedures());
+    if let Some(feedback) = &opts.feedback {
+        text.push_str("\nYOUR PREVIOUS ANSWER WAS REJECTED:\n");
+        text.push_str(feedback);
+        text.push('\n');
+    }
+    text.push_str("\nUSER REQUEST:\n");
+    let user_source = redact(user_source, opts.privacy);
+    text.push_str(trim_to_budget(&user_source, opts.max_user_tokens));
+
+    Prompt {
+        text,
+        grammar: build_grammar(opts.loose_grammar),
+        stop: STOP_SEQUENCES.iter().map(|s| s.to_string()).collect(),
+        sampling: opts.sampling,
+    }
+}
+
+/// Renders the registry as the JSON-like schema listing shown to the model.
+fn render_available_intents() -> String {
+    let entries: Vec<String> = INTENT_SCHEMAS
+        .iter()
+        .map(|schema| {
+            let mut entry = format!("    {{\n        \"intent\": \"{}\"", schema.name);
+            if !schema.parameters.is_empty() {
+                let params: Vec<String> = schema
+                    .parameters
+                    .iter()
+                    .map(|p| {
+                        let optional = if p.is_optional() { " (optional)" } else { "" };
+                        format!("\"{}\": \"{}{}\"", p.name, p.ty, optional)
+                    })
+                    .collect();
+                entry.push_str(&format!(
+                    ",\n        \"parameters\": {{ {} }}",
+                    params.join(", ")
+                ));
+            }
+            entry.push_str("\n    }");
+            entry
+        })
+        .collect();
+    format!("[\n{}\n]", entries.join(",\n"))
+}
+
+/// One line per parameter with a fixed registry default, e.g. the sort order.
+fn render_default_values() -> String {
+    INTENT_SCHEMAS
+        .iter()
+        .flat_map(|schema| schema.parameters.iter().map(move |p| (schema.name, p)))
+        .filter_map(|(intent, p)| match p.default {
+            ParamDefault::Value(value) => Some(format!(
+                "- For the \"{}\" intent, if \"{}\" is not specified, you MUST default to {}.\n",
+                intent, p.name, value
+            )),
+            ParamDefault::Required | ParamDefault::FromContext(_) => None,
+        })
+        .collect()
+}
+
+/// One line per parameter the registry restricts to a set or range of
+/// values, e.g. the sort order or the repeat count.
+fn render_allowed_values() -> String {
+    INTENT_SCHEMAS
+        .iter()
+        .flat_map(|schema| schema.parameters.iter().map(move |p| (schema.name, p)))
+        .filter_map(|(intent, p)| match p.values {
+            ParamValues::OneOf(values) => {
+                let values: Vec<String> = values.iter().map(|v| format!("\"{}\"", v)).collect();
+                Some(format!(
+                    "- For the \"{}\" intent, \"{}\" MUST be one of {}.\n",
+                    intent,
+                    p.name,
+                    values.join(", ")
+                ))
+            }
+            ParamValues::Range { min, max } => Some(format!(
+                "- For the \"{}\" intent, \"{}\" MUST be between {} and {}.\n",
+                intent, p.name, min, max
+            )),
+            ParamValues::Any => None,
+        })
+        .collect()
+}
+
+/// Builds the GBNF grammar from the registry. The strict grammar gives every
+/// intent its own rule, which admits only the intent's parameter names and,
+/// for parameters with a closed set of values, only those values. The loose
+/// grammar admits any registered intent name with any parameters.
+pub fn build_grammar(loose: bool) -> String {
+    let intent_rules = if loose {
+        let names: Vec<String> = intent_names().map(|name| format!("\"{}\"", name)).collect();
+        format!(
+            r#"intent ::= "{{" ws "\"intent\"" ws ":" ws "\"" intent-name "\"" ("," ws "\"parameters\"" ws ":" ws params)? ws "}}"
+intent-name ::= {}"#,
+            names.join(" | ")
+        )
+    } else {
+        strict_intent_rules()
+    };
+    GRAMMAR_TEMPLATE.replace("{INTENT_RULES}", &intent_rules)
+}
+
+/// The `intent` rule of the strict grammar and the per-intent rules under it.
+fn strict_intent_rules() -> String {
+    let alternatives: Vec<String> = INTENT_SCHEMAS
+        .iter()
+        .map(|schema| format!("{}-intent", rule_name(schema.name)))
+        .collect();
+    let mut rules = vec![format!(
+        r#"intent ::= "{{" ws "\"intent\"" ws ":" ws ({}) ws "}}""#,
+        alternatives.join(" | ")
+    )];
+    for schema in INTENT_SCHEMAS {
+        rules.extend(intent_rules(schema));
+    }
+    rules.join("\n")
+}
+
+/// The rules for one intent: its name, then optionally its parameters, each
+/// with the values it accepts.
+fn intent_rules(schema: &IntentSchema) -> Vec<String> {
+    let rule = rule_name(schema.name);
+    let params = if schema.parameters.is_empty() {
+        r#""{" ws "}""#.to_string()
+    } else {
+        format!(r#""{{" ws ({0}-param ("," ws {0}-param)*)? ws "}}""#, rule)
+    };
+    let mut rules = vec![format!(
+        r#"{}-intent ::= "\"{}\"" ("," ws "\"parameters\"" ws ":" ws {})?"#,
+        rule, schema.name, params
+    )];
+    if schema.parameters.is_empty() {
+        return rules;
+    }
+    let mut alternatives = Vec::new();
+    for param in schema.parameters {
+        let value = match param.values {
+            ParamValues::OneOf(values) => {
+                let value_rule = format!("{}-{}", rule, rule_name(param.name));
+                let literals: Vec<String> =
+                    values.iter().map(|v| format!(r#""\"{}\"""#, v)).collect();
+                rules.push(format!("{} ::= {}", value_rule, literals.join(" | ")));
+                value_rule
+            }
+            ParamValues::Any | ParamValues::Range { .. } => "value".to_string(),
+        };
+        alternatives.push(format!(r#""\"{}\"" ws ":" ws {}"#, param.name, value));
+    }
+    rules.insert(
+        1,
+        format!("{}-param ::= {}", rule, alternatives.join(" | ")),
+    );
+    rules
+}
+
+/// `name` as a GBNF rule name, which allows only lowercase letters, digits
+/// and dashes: `SortArray` becomes `sort-array`.
+fn rule_name(name: &str) -> String {
+    let mut rule = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            rule.push('-');
+        }
+        rule.push(c.to_ascii_lowercase());
+    }
+    rule
+}
+
+/// Truncates `source` to the token budget, respecting UTF-8 boundaries.
+fn trim_to_budget(source: &str, max_tokens: Option<usize>) -> &str {
+    let Some(max_tokens) = max_tokens else {
+        return source;
+    };
+    let max_chars = max_tokens.saturating_mul(CHARS_PER_TOKEN);
+    match source.char_indices().nth(max_chars) {
+        Some((byte_index, _)) => &source[..byte_index],
+        None => source,
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = ":::naldom\nCreate an array of 10 random numbers.\n:::";
+
+    #[test]
+    fn test_user_text_appears_exactly_once_at_the_end() {
+        let prompt = build_prompt(SOURCE, &PromptOptions::default());
+
+        assert_eq!(prompt.text.matches(SOURCE).count(), 1);
+        assert!(prompt.text.ends_with(SOURCE));
+    }
+
+    #[test]
+    fn test_stop_sequences_are_present() {
+        let prompt = build_prompt(SOURCE, &PromptOptions::default());
+
+        assert_eq!(prompt.stop, vec!["\nUSER REQUEST:", "ASSISTANT:"]);
+    }
+
+    #[test]
+    fn test_grammar_and_prompt_list_every_registered_intent() {
+        let prompt = build_prompt(SOURCE, &PromptOptions::default());
+        let loose_grammar = build_grammar(true);
+
+        let grammar_line = loose_grammar
+            .lines()
+            .find(|line| line.starts_with("intent-name ::="))
+            .unwrap();
+        let grammar_names: Vec<&str> = grammar_line["intent-name ::=".len()..]
+            .split('|')
+            .map(|name| name.trim().trim_matches('"'))
+            .collect();
+        assert_eq!(grammar_names, intent_names().collect::<Vec<_>>());
+        for name in intent_names() {
+            assert!(prompt.text.contains(&format!("\"intent\": \"{}\"", name)));
+            let rule = format!("{}-intent ::= \"\\\"{}\\\"\"", rule_name(name), name);
+            assert!(prompt.grammar.contains(&rule), "no rule for {}", name);
+        }
+    }
+
+    /// A minimal GBNF syntax check: every line is `name ::= expression`, no
+    /// rule is defined twice, every rule referenced is defined, and string
+    /// literals, character classes and groups are all closed.
+    fn check_gbnf(grammar: &str) -> Result<(), String> {
+        let mut defined = Vec::new();
+        let mut referenced = Vec::new();
+        for line in grammar.lines().filter(|line| !line.trim().is_empty()) {
+            let (name, body) = line
+                .split_once("::=")
+                .ok_or_else(|| format!("no '::=' in: {}", line))?;
+            let name = name.trim();
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase() || c == '-') {
+                return Err(format!("bad rule name '{}'", name));
+            }
+            if defined.contains(&name) {
+                return Err(format!("'{}' is defined twice", name));
+            }
+            defined.push(name);
+            let mut chars = body.chars().peekable();
+            let mut depth = 0;
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' | '[' => {
+                        let close = if c == '"' { '"' } else { ']' };
+                        loop {
+                            match chars.next() {
+                                Some('\\') => {
+                                    chars.next();
+                                }
+                                Some(c) if c == close => break,
+                                Some(_) => {}
+                                None => return Err(format!("unclosed {} in '{}'", c, name)),
+                            }
+                        }
+                    }
+                    '(' => depth += 1,
+                    ')' if depth == 0 => return Err(format!("unbalanced ')' in '{}'", name)),
+                    ')' => depth -= 1,
+                    '|' | '*' | '+' | '?' => {}
+                    c if c.is_whitespace() => {}
+                    c if c.is_ascii_lowercase() => {
+                        let mut reference = c.to_string();
+                        while let Some(&c) = chars.peek()
+                            && (c.is_ascii_lowercase() || c == '-')
+                        {
+                            reference.push(c);
+                            chars.next();
+                        }
+                        referenced.push(reference);
+                    }
+                    other => return Err(format!("unexpected '{}' in '{}'", other, name)),
+                }
+            }
+            if depth != 0 {
+                return Err(format!("unclosed '(' in '{}'", name));
+            }
+        }
+        if !defined.contains(&"root") {
+            return Err("no root rule".to_string());
+        }
+        match referenced.iter().find(|r| !defined.contains(&r.as_str())) {
+            Some(missing) => Err(format!("'{}' is referenced but not defined", missing)),
+            None => Ok(()),
+        }
+    }
+
+    #[test]
+    fn test_gbnf_checker_rejects_broken_grammars() {
+        assert!(check_gbnf("root ::= \"a\" b").is_err());
+        assert!(check_gbnf("root ::= (\"a\"").is_err());
+        assert!(check_gbnf("root ::= \"a").is_err());
+        assert!(check_gbnf("root ::= [a-z").is_err());
+        assert!(check_gbnf("root ::= \"a\"\nroot ::= \"b\"").is_err());
+        assert!(check_gbnf("root ::= (\"a\" | [^\"\\\\])*").is_ok());
+    }
+
+    #[test]
+    fn test_strict_grammar_enumerates_closed_value_sets() {
+        // Act
+        let grammar = build_prompt(SOURCE, &PromptOptions::default()).grammar;
+
+        // Assert
+        assert_eq!(check_gbnf(&grammar), Ok(()));
+        for rule in [
+            r#"sort-array-order ::= "\"ascending\"" | "\"descending\"""#,
+            r#"sort-array-algorithm ::= "\"quick\"" | "\"merge\"" | "\"bubble\"""#,
+            r#"map-array-operation ::= "\"add\"" | "\"subtract\"" | "\"multiply\"" | "\"divide\"""#,
+            r#"convert-array-to ::= "\"float\"" | "\"int\"""#,
+            r#"conditional-condition ::= "\"is_sorted\"" | "\"is_empty\"""#,
+        ] {
+            assert!(
+                grammar.lines().any(|line| line == rule),
+                "missing: {}",
+                rule
+            );
+        }
+        assert!(grammar.contains(r#""\"order\"" ws ":" ws sort-array-order"#));
+        assert!(!grammar.contains("intent-name"));
+    }
+
+    #[test]
+    fn test_loose_grammar_reverts_to_the_open_rules() {
+        // Arrange
+        let opts = PromptOptions {
+            loose_grammar: true,
+            ..PromptOptions::default()
+        };
+
+        // Act
+        let grammar = build_prompt(SOURCE, &opts).grammar;
+
+        // Assert
+        assert_eq!(check_gbnf(&grammar), Ok(()));
+        assert!(
+            grammar
+                .contains(r#""\"" intent-name "\"" ("," ws "\"parameters\"" ws ":" ws params)?"#)
+        );
+        assert!(!grammar.contains("sort-array"));
+        assert!(!grammar.contains("ascending"));
+    }
+
+    #[test]
+    fn test_prompt_states_allowed_values_and_ranges() {
+        let prompt = build_prompt(SOURCE, &PromptOptions::default());
+
+        assert!(prompt.text.contains(
+            "- For the \"SortArray\" intent, \"order\" MUST be one of \"ascending\", \"descending\".\n"
+        ));
+        assert!(prompt.text.contains(&format!(
+            "- For the \"Repeat\" intent, \"count\" MUST be between 0 and {}.\n",
+            crate::semantic_analyzer::MAX_REPETITIONS
+        )));
+    }
+
+    #[test]
+    fn test_prompt_advertises_every_procedure() {
+        let prompt = build_prompt(SOURCE, &PromptOptions::default());
+
+        for procedure in crate::stdlib::PROCEDURES {
+            assert!(prompt.text.contains(&format!("- {}(", procedure.name)));
+        }
+    }
+
+    #[test]
+    fn test_token_budget_trims_user_text() {
+        let opts = PromptOptions {
+            max_user_tokens: Some(3),
+            ..PromptOptions::default()
+        };
+
+        let prompt = build_prompt(SOURCE, &opts);
+
+        assert!(prompt.text.ends_with("\nUSER REQUEST:\n:::naldom\nCr"));
+        assert!(!prompt.text.contains("random numbers"));
+    }
+
+    #[test]
+    fn test_strict_privacy_sends_no_prose_and_keeps_blocks_in_order() {
+        let source = "Dear diary, I owe Bob money.\n:::naldom\nCreate an array of 5 numbers.\n:::\nMy password is hunter2.\n:::naldom\nPrint the array.\n:::\nSee you tomorrow.";
+        let opts = PromptOptions {
+            privacy: Privacy::Strict,
+            ..PromptOptions::default()
+        };
+
+        let prompt = build_prompt(source, &opts);
+
+        for prose in ["diary", "Bob", "hunter2", "tomorrow"] {
+            assert!(!prompt.text.contains(prose), "'{}' was sent", prose);
+        }
+        assert!(prompt.text.ends_with(
+            "\nUSER REQUEST:\n[redacted]\n:::naldom\nCreate an array of 5 numbers.\n:::\n[redacted]\n:::naldom\nPrint the array.\n:::\n[redacted]"
+        ));
+    }
+
+    #[test]
+    fn test_feedback_precedes_the_user_request() {
+        let opts = PromptOptions {
+            feedback: Some("Create the array first.".to_string()),
+            ..PromptOptions::default()
+        };
+
+        let prompt = build_prompt(SOURCE, &opts);
+
+        assert!(prompt.text.contains(
+            "YOUR PREVIOUS ANSWER WAS REJECTED:\nCreate the array first.\n\nUSER REQUEST:\n"
+        ));
+        assert!(prompt.text.ends_with(SOURCE));
+    }
+
+    #[test]
+    fn test_instructions_replace_the_built_in_header() {
+        let opts = PromptOptions {
+            instructions: Some("Answer with intents.\n".to_string()),
+            ..PromptOptions::default()
+        };
+
+        let prompt = build_prompt(SOURCE, &opts);
+
+        assert!(prompt.text.starts_with("Answer with intents.\n"));
+        assert!(!prompt.text.contains("You are an expert Frontend Compiler."));
+        assert!(prompt.text.contains("AVAILABLE INTENTS (JSON Schema):\n"));
+        assert!(prompt.text.ends_with(SOURCE));
+    }
+
+    #[test]
+    fn test_sampling_travels_with_the_prompt() {
+        let opts = PromptOptions {
+            sampling: Sampling::DETERMINISTIC,
+            ..PromptOptions::default()
+        };
+
+        let default = build_prompt(SOURCE, &PromptOptions::default());
+        let deterministic = build_prompt(SOURCE, &opts);
+
+        assert_eq!(default.sampling.temperature, DEFAULT_TEMPERATURE);
+        assert_eq!(default.sampling.seed, None);
+        assert_eq!(deterministic.sampling.temperature, 0.0);
+        assert_eq!(deterministic.sampling.seed, Some(0));
+    }
+
+    #[test]
+    fn test_token_budget_respects_utf8_boundaries() {
+        assert_eq!(trim_to_budget("ääää", Some(0)), "");
+        assert_eq!(trim_to_budget("äääää", Some(1)), "ääää");
+        assert_eq!(trim_to_budget("ää", Some(1)), "ää");
+    }
+}
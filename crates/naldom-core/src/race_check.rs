@@ -0,0 +1,388 @@
+// crates/naldom-core/src/race_check.rs
+
+//! Data races between background bodies and the main flow.
+//!
+//! A background body runs concurrently with the intents after it until it is
+//! joined. An array the body creates or sorts must not be used by the main
+//! flow before the join, and an array the body prints must not be sorted by
+//! the main flow before the join; a `Wait` inside the body only makes the
+//! problem easier to miss. IntentGraphs have no background intents yet, so the
+//! check runs over [`FlowStep`]s that a future lowering of those intents (and
+//! the tests) build by hand.
+
+use crate::semantic_analyzer::ordinal_name;
+use naldom_ir::Intent;
+use std::fmt;
+
+/// One step of a program with background execution. Indices are positions in
+/// the IntentGraph, used for error messages.
+#[derive(Debug, Clone)]
+pub enum FlowStep {
+    /// An intent in the main flow.
+    Intent { index: usize, intent: Intent },
+    /// Starts `body` in the background.
+    Background {
+        index: usize,
+        body: Vec<(usize, Intent)>,
+    },
+    /// Waits for the background body started at intent `background`.
+    Join { index: usize, background: usize },
+}
+
+/// How an intent touches an array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// A main-flow intent that uses an array a running background body also uses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RaceError {
+    /// The main-flow intent.
+    pub intent_index: usize,
+    /// The `Background` step whose body has not been joined.
+    pub background_index: usize,
+    /// The intent inside the body that touches the same array.
+    pub body_intent_index: usize,
+    /// The body's access; the main flow's is a write whenever this is a read.
+    pub body_access: Access,
+    /// The array both touch, counting from 1 in creation order.
+    pub ordinal: u32,
+}
+
+impl fmt::Display for RaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Semantic Error: Intent #{} uses the {} array while the background body started at intent #{} may still {} it (intent #{}).",
+            self.intent_index + 1,
+            ordinal_name(self.ordinal),
+            self.background_index + 1,
+            match self.body_access {
+                Access::Read => "read",
+                Access::Write => "modify",
+            },
+            self.body_intent_index + 1
+        )?;
+        write!(
+            f,
+            "\n  suggestion: join the background body started at intent #{} before intent #{}",
+            self.background_index + 1,
+            self.intent_index + 1
+        )
+    }
+}
+
+impl std::error::Error for RaceError {}
+
+/// A background body that has started but not been joined.
+struct Running {
+    index: usize,
+    /// `(ordinal, access, body intent index)` for every array the body touches.
+    accesses: Vec<(u32, Access, usize)>,
+}
+
+/// Checks that no main-flow intent uses an array concurrently with a running
+/// background body, unless both only read it. Stops at the first race.
+pub fn check_races(steps: &[FlowStep]) -> Result<(), RaceError> {
+    let mut arrays = 0u32;
+    let mut running: Vec<Running> = Vec::new();
+    for step in steps {
+        match step {
+            FlowStep::Intent { index, intent } => {
+                let conflict = array_accesses(intent, &mut arrays).into_iter().find_map(
+                    |(ordinal, access)| {
+                        running.iter().find_map(|body| {
+                            body.accesses
+                                .iter()
+                                .find(|(touched, body_access, _)| {
+                                    *touched == ordinal
+                                        && (access == Access::Write
+                                            || *body_access == Access::Write)
+                                })
+                                .map(|&(_, body_access, body_intent_index)| RaceError {
+                                    intent_index: *index,
+                                    background_index: body.index,
+                                    body_intent_index,
+                                    body_access,
+                                    ordinal,
+                                })
+                        })
+                    },
+                );
+                if let Some(race) = conflict {
+                    return Err(race);
+                }
+            }
+            FlowStep::Background { index, body } => {
+                let accesses = body
+                    .iter()
+                    .flat_map(|(body_index, intent)| {
+                        array_accesses(intent, &mut arrays)
+                            .into_iter()
+                            .map(|(ordinal, access)| (ordinal, access, *body_index))
+                    })
+                    .collect();
+                running.push(Running {
+                    index: *index,
+                    accesses,
+                });
+            }
+            FlowStep::Join { background, .. } => {
+                running.retain(|body| body.index != *background);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The arrays `intent` touches and how, counting `CreateArray`,
+/// `ConcatArrays` and `CopyArray` as a write to the array they create and a
+/// `Repeat` or `Conditional` as everything its bodies touch.
+/// `arrays` is the number of arrays created so far.
+pub(crate) fn array_accesses(intent: &Intent, arrays: &mut u32) -> Vec<(u32, Access)> {
+    let access = match intent {
+        Intent::CreateArray(_) => {
+            *arrays += 1;
+            return vec![(*arrays, Access::Write)];
+        }
+        // Reads both operands and writes the array it creates.
+        Intent::ConcatArrays(params) => {
+            let mut accesses: Vec<(u32, Access)> = [params.first_ordinal, params.second_ordinal]
+                .into_iter()
+                .flatten()
+                .filter(|ordinal| (1..=*arrays).contains(ordinal))
+                .map(|ordinal| (ordinal, Access::Read))
+                .collect();
+            *arrays += 1;
+            accesses.push((*arrays, Access::Write));
+            return accesses;
+        }
+        // Reads every array created so far.
+        Intent::PrintArray(Some(params)) if params.prints_all() => {
+            let last = intent.target_ordinal().unwrap_or(*arrays).min(*arrays);
+            return (1..=last).map(|ordinal| (ordinal, Access::Read)).collect();
+        }
+        // Reads the original and writes the copy.
+        Intent::CopyArray(_) => {
+            let source = intent.target_ordinal().unwrap_or(*arrays);
+            let mut accesses: Vec<(u32, Access)> = (1..=*arrays)
+                .contains(&source)
+                .then_some((source, Access::Read))
+                .into_iter()
+                .collect();
+            *arrays += 1;
+            accesses.push((*arrays, Access::Write));
+            return accesses;
+        }
+        Intent::SortArray(_)
+        | Intent::ReverseArray(_)
+        | Intent::AppendToArray(_)
+        | Intent::FillArray(_)
+        | Intent::ClearArray(_)
+        | Intent::SliceArray(_)
+        | Intent::MapArray(_)
+        | Intent::ConvertArray(_) => Access::Write,
+        Intent::PrintArray(_)
+        | Intent::SumArray(_)
+        | Intent::MinArray(_)
+        | Intent::MaxArray(_)
+        | Intent::CountElements(_)
+        | Intent::AverageArray(_)
+        | Intent::MedianArray(_)
+        | Intent::ContainsValue(_)
+        | Intent::FindIndex(_)
+        | Intent::PrintHistogram(_)
+        | Intent::DescribeArray(_) => Access::Read,
+        Intent::Repeat(params) => {
+            return params
+                .body
+                .iter()
+                .flat_map(|intent| array_accesses(intent, arrays))
+                .collect();
+        }
+        // The test reads the array; arrays created in a branch end with it.
+        Intent::Conditional(params) => {
+            let ordinal = intent.target_ordinal().unwrap_or(*arrays);
+            let mut accesses: Vec<(u32, Access)> = (1..=*arrays)
+                .contains(&ordinal)
+                .then_some((ordinal, Access::Read))
+                .into_iter()
+                .collect();
+            for body in [&params.then_body, &params.else_body] {
+                let mut branch_arrays = *arrays;
+                accesses.extend(
+                    body.iter()
+                        .flat_map(|intent| array_accesses(intent, &mut branch_arrays)),
+                );
+            }
+            return accesses;
+        }
+        // Matrices are not arrays.
+        Intent::CreateMatrix(_)
+        | Intent::PrintMatrix(_)
+        | Intent::Wait(_)
+        | Intent::StartTimer(_)
+        | Intent::PrintElapsed(_)
+        | Intent::Exit(_)
+        | Intent::Note(_)
+        | Intent::SetVariable(_)
+        | Intent::UseProcedure(_) => {
+            return Vec::new();
+        }
+    };
+    // Unresolvable references are the analyzer's to report.
+    let ordinal = intent.target_ordinal().unwrap_or(*arrays);
+    (1..=*arrays)
+        .contains(&ordinal)
+        .then_some((ordinal, access))
+        .into_iter()
+        .collect()
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use naldom_ir::{CountArg, CreateArrayParams, PrintArrayParams, SortArrayParams, WaitParams};
+
+    fn create(index: usize) -> (usize, Intent) {
+        (
+            index,
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+        )
+    }
+
+    fn sort(index: usize, ordinal: Option<u32>) -> (usize, Intent) {
+        (
+            index,
+            Intent::SortArray(SortArrayParams {
+                order: Some("ascending".to_string()),
+                algorithm: None,
+                target_ordinal: ordinal,
+                target: None,
+            }),
+        )
+    }
+
+    fn print(index: usize, ordinal: Option<u32>) -> (usize, Intent) {
+        (
+            index,
+            Intent::PrintArray(Some(PrintArrayParams {
+                target_ordinal: ordinal,
+                target: None,
+                full: Some(false),
+            })),
+        )
+    }
+
+    fn wait(index: usize) -> (usize, Intent) {
+        (index, Intent::Wait(WaitParams { duration_ms: 100 }))
+    }
+
+    fn main_flow((index, intent): (usize, Intent)) -> FlowStep {
+        FlowStep::Intent { index, intent }
+    }
+
+    #[test]
+    fn test_using_an_array_sorted_in_an_unjoined_body_is_a_race() {
+        // Arrange: create; background { wait; sort }; print
+        let steps = [
+            main_flow(create(0)),
+            FlowStep::Background {
+                index: 1,
+                body: vec![wait(2), sort(3, None)],
+            },
+            main_flow(print(4, None)),
+        ];
+
+        // Act
+        let race = check_races(&steps).unwrap_err();
+
+        // Assert
+        assert_eq!(
+            race,
+            RaceError {
+                intent_index: 4,
+                background_index: 1,
+                body_intent_index: 3,
+                body_access: Access::Write,
+                ordinal: 1,
+            }
+        );
+        assert_eq!(
+            race.to_string(),
+            "Semantic Error: Intent #5 uses the 1st array while the background body started at intent #2 may still modify it (intent #4).\n  suggestion: join the background body started at intent #2 before intent #5"
+        );
+    }
+
+    #[test]
+    fn test_joining_first_removes_the_race() {
+        let steps = [
+            main_flow(create(0)),
+            FlowStep::Background {
+                index: 1,
+                body: vec![wait(2), sort(3, None)],
+            },
+            FlowStep::Join {
+                index: 4,
+                background: 1,
+            },
+            main_flow(print(5, None)),
+        ];
+
+        assert_eq!(check_races(&steps), Ok(()));
+    }
+
+    #[test]
+    fn test_arrays_created_in_a_body_count_in_program_order() {
+        // The body creates the 2nd array; printing the 1st is safe, but
+        // printing the latest one is not.
+        let background = FlowStep::Background {
+            index: 1,
+            body: vec![create(2), wait(3)],
+        };
+        let safe = [
+            main_flow(create(0)),
+            background.clone(),
+            main_flow(print(4, Some(1))),
+        ];
+        let racy = [main_flow(create(0)), background, main_flow(print(4, None))];
+
+        assert_eq!(check_races(&safe), Ok(()));
+        assert_eq!(
+            check_races(&racy).map_err(|race| (race.ordinal, race.body_intent_index)),
+            Err((2, 2))
+        );
+    }
+
+    #[test]
+    fn test_concurrent_reads_are_fine_but_a_write_against_a_read_is_not() {
+        let background = FlowStep::Background {
+            index: 1,
+            body: vec![print(2, Some(1))],
+        };
+        let both_read = [
+            main_flow(create(0)),
+            background.clone(),
+            main_flow(print(3, Some(1))),
+        ];
+        let main_writes = [
+            main_flow(create(0)),
+            background,
+            main_flow(sort(3, Some(1))),
+        ];
+
+        assert_eq!(check_races(&both_read), Ok(()));
+        assert_eq!(
+            check_races(&main_writes).unwrap_err().body_access,
+            Access::Read
+        );
+    }
+}
@@ -0,0 +1,181 @@
+// crates/naldom-core/src/runtime_header.rs
+
+//! Generates `naldom_runtime.h` from the runtime ABI registry, for third-party
+//! runtimes and C projects that link Naldom objects.
+
+use naldom_ir::runtime_abi::{
+    ABI_VERSION_MAJOR, ABI_VERSION_MINOR, AbiType, NALDOM_ARRAY_FIELDS, NALDOM_MATRIX_FIELDS,
+    RUNTIME_FUNCTIONS, RuntimeFunction,
+};
+
+/// The file name the header is conventionally installed under.
+pub const HEADER_FILE_NAME: &str = "naldom_runtime.h";
+
+/// The C spelling of an ABI type.
+pub fn c_type(ty: AbiType) -> &'static str {
+    match ty {
+        AbiType::Void => "void",
+        AbiType::I32 => "int32_t",
+        AbiType::I64 => "int64_t",
+        AbiType::U64 => "uint64_t",
+        AbiType::F64 => "double",
+        AbiType::F64Ptr => "double *",
+        AbiType::I64Ptr => "const int64_t *",
+        AbiType::CStr => "const char *",
+        AbiType::CStrArray => "const char *const *",
+        AbiType::EntryFn => "NaldomEntry",
+        AbiType::ArrayPtr => "NaldomArray *",
+        AbiType::ConstArrayPtr => "const NaldomArray *",
+        AbiType::MatrixPtr => "NaldomMatrix *",
+        AbiType::ConstMatrixPtr => "const NaldomMatrix *",
+    }
+}
+
+/// Joins a C type and a name, without a space after a trailing `*`.
+fn declaration(ty: AbiType, name: &str) -> String {
+    let ty = c_type(ty);
+    if ty.ends_with('*') {
+        format!("{}{}", ty, name)
+    } else {
+        format!("{} {}", ty, name)
+    }
+}
+
+/// The C prototype of a runtime function, e.g. `void print_array(const NaldomArray *arr);`.
+pub fn c_prototype(function: &RuntimeFunction) -> String {
+    let params = if function.params.is_empty() {
+        "void".to_string()
+    } else {
+        function
+            .params
+            .iter()
+            .map(|param| declaration(param.ty, param.name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!(
+        "{}({});",
+        declaration(function.return_type, function.name),
+        params
+    )
+}
+
+/// Renders the complete header.
+pub fn generate_runtime_header() -> String {
+    let mut lines = vec![
+        format!(
+            "/* {} - generated from the Naldom runtime ABI registry. Do not edit. */",
+            HEADER_FILE_NAME
+        ),
+        "#ifndef NALDOM_RUNTIME_H".to_string(),
+        "#define NALDOM_RUNTIME_H".to_string(),
+        String::new(),
+        "#include <stdint.h>".to_string(),
+        String::new(),
+        format!(
+            "#define NALDOM_RUNTIME_ABI_VERSION_MAJOR {}",
+            ABI_VERSION_MAJOR
+        ),
+        format!(
+            "#define NALDOM_RUNTIME_ABI_VERSION_MINOR {}",
+            ABI_VERSION_MINOR
+        ),
+        "#define NALDOM_RUNTIME_ABI_VERSION \\".to_string(),
+        "    ((NALDOM_RUNTIME_ABI_VERSION_MAJOR << 16) | NALDOM_RUNTIME_ABI_VERSION_MINOR)"
+            .to_string(),
+        String::new(),
+        "#ifdef __cplusplus".to_string(),
+        "extern \"C\" {".to_string(),
+        "#endif".to_string(),
+        String::new(),
+        "/* An array owned by the runtime: `size` doubles starting at `data`, with room for `capacity`. */".to_string(),
+        "typedef struct NaldomArray {".to_string(),
+    ];
+    for field in NALDOM_ARRAY_FIELDS {
+        lines.push(format!("    {};", declaration(field.ty, field.name)));
+    }
+    lines.push("} NaldomArray;".to_string());
+    lines.push(String::new());
+    lines.push(
+        "/* A matrix owned by the runtime: `rows * cols` doubles starting at `data`, row by row. */"
+            .to_string(),
+    );
+    lines.push("typedef struct NaldomMatrix {".to_string());
+    for field in NALDOM_MATRIX_FIELDS {
+        lines.push(format!("    {};", declaration(field.ty, field.name)));
+    }
+    lines.push("} NaldomMatrix;".to_string());
+    lines.push(String::new());
+    lines.push("/* A compiled program's entry function. */".to_string());
+    lines.push("typedef void (*NaldomEntry)(void);".to_string());
+
+    for function in RUNTIME_FUNCTIONS {
+        lines.push(String::new());
+        lines.push(format!("/* {} */", function.doc));
+        lines.push(c_prototype(function));
+    }
+
+    lines.extend([
+        String::new(),
+        "#ifdef __cplusplus".to_string(),
+        "}".to_string(),
+        "#endif".to_string(),
+        String::new(),
+        "#endif /* NALDOM_RUNTIME_H */".to_string(),
+        String::new(),
+    ]);
+    lines.join("\n")
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use naldom_ir::runtime_abi::find_runtime_function;
+
+    #[test]
+    fn test_prototypes_use_exact_c_types() {
+        let prototype = |name| c_prototype(find_runtime_function(name).unwrap());
+
+        assert_eq!(
+            prototype("create_random_array"),
+            "NaldomArray *create_random_array(int64_t size);"
+        );
+        assert_eq!(
+            prototype("print_array"),
+            "void print_array(const NaldomArray *arr, int64_t full);"
+        );
+        assert_eq!(
+            prototype("naldom_async_sleep"),
+            "void naldom_async_sleep(uint64_t ms);"
+        );
+        assert_eq!(
+            prototype("naldom_dispatch"),
+            "int64_t naldom_dispatch(int64_t argc, const char *const *argv);"
+        );
+        assert_eq!(
+            prototype("naldom_register_program"),
+            "void naldom_register_program(const char *name, NaldomEntry entry);"
+        );
+    }
+
+    #[test]
+    fn test_header_declares_every_registered_function_once() {
+        // Act
+        let header = generate_runtime_header();
+
+        // Assert
+        assert!(header.contains(
+            "    double *data;\n    int64_t size;\n    int64_t capacity;\n} NaldomArray;"
+        ));
+        assert!(
+            header.contains(
+                "    double *data;\n    int64_t rows;\n    int64_t cols;\n} NaldomMatrix;"
+            )
+        );
+        assert!(header.contains("#define NALDOM_RUNTIME_ABI_VERSION_MAJOR 4"));
+        for function in RUNTIME_FUNCTIONS {
+            assert_eq!(header.matches(&c_prototype(function)).count(), 1);
+        }
+    }
+}
@@ -0,0 +1,468 @@
+// crates/naldom-core/src/runtime_shim.rs
+
+//! Generates the runtime a source backend's output runs against, from the
+//! runtime ABI registry plus a snippet per function and backend.
+//!
+//! The registry supplies every function's name, parameters and doc line; a
+//! backend only supplies a header, a function template and the bodies. The
+//! unit tests fail as soon as a registered function lacks a snippet for some
+//! backend, so adding a runtime function cannot silently leave a backend
+//! behind.
+
+use naldom_ir::runtime_abi::{
+    ABI_VERSION_MAJOR, ABI_VERSION_MINOR, RUNTIME_FUNCTIONS, RuntimeFunction,
+};
+use std::fmt;
+
+/// A source backend whose output needs a generated runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShimBackend {
+    Python,
+}
+
+impl ShimBackend {
+    /// Every backend with a runtime shim.
+    pub const ALL: &'static [ShimBackend] = &[ShimBackend::Python];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShimBackend::Python => "python",
+        }
+    }
+
+    /// The file name the generated runtime is imported under.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ShimBackend::Python => "naldom_runtime.py",
+        }
+    }
+
+    fn template(&self) -> &'static ShimTemplate {
+        match self {
+            ShimBackend::Python => &PYTHON,
+        }
+    }
+}
+
+impl fmt::Display for ShimBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What a backend does for one runtime function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Snippet {
+    /// The function's body, already indented for the backend.
+    Body(&'static str),
+    /// Programs for this backend never call the function, e.g. the native
+    /// multi-program dispatcher. Listed so the choice is explicit.
+    NotProvided,
+}
+
+/// How one backend spells its runtime.
+struct ShimTemplate {
+    /// Starts the file. Sees `{file_name}`, `{abi_major}` and `{abi_minor}`.
+    header: &'static str,
+    /// Wraps each body. Sees `{name}`, `{params}`, `{doc}` and `{body}`.
+    function: &'static str,
+    /// Spells one parameter. Sees `{name}`.
+    param: &'static str,
+    /// Bodies keyed by runtime function name.
+    snippets: &'static [(&'static str, Snippet)],
+}
+
+/// Errors raised while generating a runtime shim.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShimError {
+    /// A registered runtime function has no snippet for the backend.
+    MissingSnippet {
+        backend: ShimBackend,
+        function: &'static str,
+    },
+    /// The backend has a snippet for a function the registry does not know.
+    UnknownSnippet {
+        backend: ShimBackend,
+        function: &'static str,
+    },
+}
+
+impl fmt::Display for ShimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShimError::MissingSnippet { backend, function } => write!(
+                f,
+                "The {} runtime shim has no snippet for the runtime function '{}'.",
+                backend, function
+            ),
+            ShimError::UnknownSnippet { backend, function } => write!(
+                f,
+                "The {} runtime shim has a snippet for '{}', which is not a registered runtime function.",
+                backend, function
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ShimError {}
+
+/// Replaces every `{key}` in `template` with its value. Unknown keys stay as
+/// written, so backends may use braces of their own.
+fn fill(template: &str, vars: &[(&str, &str)]) -> String {
+    vars.iter()
+        .fold(template.to_string(), |text, (key, value)| {
+            text.replace(&format!("{{{}}}", key), value)
+        })
+}
+
+fn snippet(backend: ShimBackend, function: &RuntimeFunction) -> Result<Snippet, ShimError> {
+    backend
+        .template()
+        .snippets
+        .iter()
+        .find(|(name, _)| *name == function.name)
+        .map(|(_, snippet)| *snippet)
+        .ok_or(ShimError::MissingSnippet {
+            backend,
+            function: function.name,
+        })
+}
+
+/// Checks that `backend` has exactly one snippet per registered function.
+pub fn check_snippets(backend: ShimBackend) -> Result<(), ShimError> {
+    for function in RUNTIME_FUNCTIONS {
+        snippet(backend, function)?;
+    }
+    match backend.template().snippets.iter().find(|(name, _)| {
+        !RUNTIME_FUNCTIONS
+            .iter()
+            .any(|function| function.name == *name)
+    }) {
+        Some((name, _)) => Err(ShimError::UnknownSnippet {
+            backend,
+            function: name,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Renders the complete runtime for `backend`.
+pub fn generate_runtime_shim(backend: ShimBackend) -> Result<String, ShimError> {
+    check_snippets(backend)?;
+    let template = backend.template();
+    let mut output = fill(
+        template.header,
+        &[
+            ("file_name", backend.file_name()),
+            ("abi_major", &ABI_VERSION_MAJOR.to_string()),
+            ("abi_minor", &ABI_VERSION_MINOR.to_string()),
+        ],
+    );
+    for function in RUNTIME_FUNCTIONS {
+        let Snippet::Body(body) = snippet(backend, function)? else {
+            continue;
+        };
+        let params = function
+            .params
+            .iter()
+            .map(|param| fill(template.param, &[("name", param.name)]))
+            .collect::<Vec<_>>()
+            .join(", ");
+        output.push_str(&fill(
+            template.function,
+            &[
+                ("name", function.name),
+                ("params", &params),
+                ("doc", function.doc),
+                ("body", body.trim_end()),
+            ],
+        ));
+    }
+    Ok(output)
+}
+
+/// The Python runtime. Arrays are lists of floats that functions modify in
+/// place, the same model as the IR-HL interpreter, and output matches the
+/// interpreter's. Sort orders arrive as the strings IR-HL carries.
+const PYTHON: ShimTemplate = ShimTemplate {
+    header: r#"# {file_name} - generated from the Naldom runtime ABI registry. Do not edit.
+# Runtime ABI {abi_major}.{abi_minor}.
+
+import math
+import random
+import sys
+import time
+
+_rng = random.Random()
+
+# Arrays longer than twice this print only their first and last elements.
+_PRINT_EDGE = 20
+
+# Longer histogram bars are scaled down to this many characters.
+_MAX_BAR_WIDTH = 50
+
+
+def _round(value):
+    """Rounds half away from zero, like the native runtime."""
+    return int(math.copysign(math.floor(abs(value) + 0.5), value))
+
+
+def _render(values, full, decimals):
+    items = [f"{value:.{decimals}f}" for value in values]
+    if not full and len(items) > 2 * _PRINT_EDGE:
+        omitted = len(items) - 2 * _PRINT_EDGE
+        items = items[:_PRINT_EDGE] + [f"… ({omitted:,} omitted)"] + items[-_PRINT_EDGE:]
+    return "[" + ", ".join(items) + "]"
+"#,
+    function: "\n\ndef {name}({params}):\n    \"\"\"{doc}\"\"\"\n{body}\n",
+    param: "{name}",
+    snippets: &[
+        (
+            "create_random_array",
+            Snippet::Body(
+                r#"    print(f"Runtime: Creating an array of {size} random numbers...")
+    return [_rng.random() * 100.0 for _ in range(max(size, 0))]"#,
+            ),
+        ),
+        (
+            "create_random_int_array",
+            Snippet::Body(
+                r#"    print(f"Runtime: Creating an array of {size} random whole numbers...")
+    return [float(math.floor(_rng.random() * 100.0)) for _ in range(max(size, 0))]"#,
+            ),
+        ),
+        (
+            "create_array_from_values",
+            Snippet::Body(
+                r#"    print(f"Runtime: Creating an array of {len} values...")
+    return [float(value) for value in values]"#,
+            ),
+        ),
+        (
+            "sort_array",
+            Snippet::Body(
+                r#"    print("Runtime: Sorting the array...")
+    arr.sort(reverse=order in (1, "descending"))"#,
+            ),
+        ),
+        (
+            "print_array",
+            Snippet::Body("    print(_render(arr, full, 2))"),
+        ),
+        ("sum_array", Snippet::Body("    return _round(sum(arr))")),
+        (
+            "min_array",
+            Snippet::Body("    return _round(min(arr, default=0.0))"),
+        ),
+        (
+            "max_array",
+            Snippet::Body("    return _round(max(arr, default=0.0))"),
+        ),
+        ("array_length", Snippet::Body("    return len(arr)")),
+        (
+            "average_array",
+            Snippet::Body("    return sum(arr) / len(arr) if arr else 0.0"),
+        ),
+        (
+            "median_array",
+            Snippet::Body(
+                r#"    ordered = sorted(arr)
+    mid = len(ordered) // 2
+    if not ordered:
+        return 0.0
+    if len(ordered) % 2 == 0:
+        return (ordered[mid - 1] + ordered[mid]) / 2
+    return ordered[mid]"#,
+            ),
+        ),
+        (
+            "array_contains",
+            Snippet::Body("    return int(float(value) in arr)"),
+        ),
+        (
+            "find_index",
+            Snippet::Body(
+                r#"    for index, element in enumerate(arr):
+        if element == float(value):
+            return index
+    return -1"#,
+            ),
+        ),
+        (
+            "is_sorted",
+            Snippet::Body("    return int(all(a <= b for a, b in zip(arr, arr[1:])))"),
+        ),
+        (
+            "describe_array",
+            Snippet::Body(
+                r#"    count = "1 element" if len(arr) == 1 else f"{len(arr)} elements"
+    ordered = "yes" if all(a <= b for a, b in zip(arr, arr[1:])) else "no"
+    if not arr:
+        print(f"Array {name}: {count}, sorted ascending: {ordered}")
+        return
+    print(f"Array {name}: {count}, min {_round(min(arr))}, max {_round(max(arr))}, sorted ascending: {ordered}")"#,
+            ),
+        ),
+        ("is_empty", Snippet::Body("    return int(not arr)")),
+        ("reverse_array", Snippet::Body("    arr.reverse()")),
+        (
+            "append_to_array",
+            Snippet::Body("    arr.append(float(value))"),
+        ),
+        (
+            "fill_array",
+            Snippet::Body("    arr[:] = [float(value)] * len(arr)"),
+        ),
+        ("clear_array", Snippet::Body("    arr.clear()")),
+        (
+            "slice_array",
+            Snippet::Body(
+                r#"    end = min(max(end, 0), len(arr))
+    start = min(max(start, 0), end)
+    arr[:] = arr[start:end]"#,
+            ),
+        ),
+        (
+            "map_array",
+            Snippet::Body(
+                r#"    # An unknown operation or a division by zero leaves the array unchanged.
+    if op == 3 and operand == 0:
+        return
+    apply = {
+        0: lambda value: value + operand,
+        1: lambda value: value - operand,
+        2: lambda value: value * operand,
+        3: lambda value: value / operand,
+    }.get(op)
+    if apply is not None:
+        arr[:] = [apply(value) for value in arr]"#,
+            ),
+        ),
+        ("concat_arrays", Snippet::Body("    return first + second")),
+        ("copy_array", Snippet::Body("    return list(arr)")),
+        ("array_to_float", Snippet::Body("    return list(arr)")),
+        (
+            "array_to_int",
+            Snippet::Body("    return [float(math.trunc(value)) for value in arr]"),
+        ),
+        (
+            "print_int_array",
+            Snippet::Body("    print(_render(arr, full, 0))"),
+        ),
+        (
+            "print_histogram",
+            Snippet::Body(
+                r#"    if not arr or buckets <= 0:
+        return
+    low, high = min(arr), max(arr)
+    width = (high - low) / buckets
+    counts = [0] * buckets
+    for value in arr:
+        counts[min(int((value - low) / width), buckets - 1) if width > 0 else 0] += 1
+    largest = max(counts)
+    for bucket, count in enumerate(counts):
+        last = bucket + 1 == buckets
+        start = low + width * bucket
+        end = high if last else low + width * (bucket + 1)
+        bar = -(-count * _MAX_BAR_WIDTH // largest) if largest > _MAX_BAR_WIDTH else count
+        print(f"[{start:>8.2f}, {end:>8.2f}{']' if last else ')'} {'#' * bar} {count}")"#,
+            ),
+        ),
+        ("print_integer", Snippet::Body("    print(value)")),
+        (
+            "print_bool",
+            Snippet::Body(r#"    print("true" if value else "false")"#),
+        ),
+        ("print_float", Snippet::Body(r#"    print(f"{value:.2f}")"#)),
+        (
+            "create_random_matrix",
+            Snippet::Body(
+                r#"    print(f"Runtime: Creating a {rows}x{cols} matrix of random numbers...")
+    return [[_rng.random() * 100.0 for _ in range(max(cols, 0))] for _ in range(max(rows, 0))]"#,
+            ),
+        ),
+        (
+            "print_matrix",
+            Snippet::Body(
+                r#"    for row in matrix:
+        print(_render(row, 1, 2))"#,
+            ),
+        ),
+        ("naldom_seed_random", Snippet::Body("    _rng.seed(seed)")),
+        (
+            "start_timer",
+            Snippet::Body("    return time.perf_counter_ns()"),
+        ),
+        (
+            "print_elapsed",
+            Snippet::Body(
+                r#"    print(f"elapsed: {(time.perf_counter_ns() - timer) / 1e6:.2f} ms")"#,
+            ),
+        ),
+        // Nothing in the Python runtime fails short of Python itself, which
+        // always stops the program.
+        ("naldom_set_error_mode", Snippet::Body("    pass")),
+        ("naldom_exit", Snippet::Body("    sys.exit(code)")),
+        (
+            "naldom_async_sleep",
+            Snippet::Body("    time.sleep(max(ms, 0) / 1000)"),
+        ),
+        // Profiling markers only matter to the native runtime.
+        ("naldom_profile_init", Snippet::Body("    pass")),
+        ("naldom_profile_begin", Snippet::Body("    pass")),
+        ("naldom_profile_end", Snippet::Body("    pass")),
+        ("naldom_register_program", Snippet::NotProvided),
+        ("naldom_dispatch", Snippet::NotProvided),
+    ],
+};
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_backend_has_a_snippet_for_every_runtime_function() {
+        for backend in ShimBackend::ALL {
+            assert_eq!(check_snippets(*backend), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_signatures_and_docs_come_from_the_registry() {
+        // Act
+        let shim = generate_runtime_shim(ShimBackend::Python).unwrap();
+
+        // Assert
+        assert!(shim.starts_with(&format!(
+            "# naldom_runtime.py - generated from the Naldom runtime ABI registry. Do not edit.\n# Runtime ABI {}.{}.\n",
+            ABI_VERSION_MAJOR, ABI_VERSION_MINOR
+        )));
+        assert!(shim.contains(
+            "\n\ndef sort_array(arr, order, algorithm):\n    \"\"\"Sorts `arr` in place: ascending"
+        ));
+        assert!(shim.contains("\n\ndef start_timer():\n"));
+        assert!(!shim.contains("def naldom_dispatch"));
+        for function in RUNTIME_FUNCTIONS {
+            if snippet(ShimBackend::Python, function) != Ok(Snippet::NotProvided) {
+                assert_eq!(
+                    shim.matches(&format!("\ndef {}(", function.name)).count(),
+                    1,
+                    "{}",
+                    function.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_fill_leaves_unknown_keys_alone() {
+        assert_eq!(
+            fill(
+                "{name}({params}) {other}",
+                &[("name", "f"), ("params", "x")]
+            ),
+            "f(x) {other}"
+        );
+    }
+}
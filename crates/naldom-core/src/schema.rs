@@ -0,0 +1,615 @@
+// crates/naldom-core/src/schema.rs
+
+//! The intent schema registry: one table describing every intent the LLM may
+//! emit. The prompt and the grammar are generated from it, so adding an intent
+//! means adding one entry here (plus the `Intent` variant itself).
+//!
+//! The registry is also the only place parameter defaults are written down:
+//! the prompt states them and the analyzer fills them in from here.
+
+use crate::semantic_analyzer::{
+    CONDITIONS, ELEMENT_TYPES, MAP_OPERATIONS, MAX_EXIT_CODE, MAX_REPETITIONS, MAX_WAIT_MS,
+    SORT_ALGORITHMS, SORT_ORDERS,
+};
+
+/// What an intent gets when the model leaves a parameter out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamDefault {
+    /// The parameter must be given.
+    Required,
+    /// This value, written as JSON.
+    Value(&'static str),
+    /// A value that depends on the surrounding intents, described in words.
+    FromContext(&'static str),
+}
+
+/// Which values of its type a parameter accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamValues {
+    /// Any value of the type; the analyzer checks it in context.
+    Any,
+    /// Exactly one of these strings. The strict grammar lists them.
+    OneOf(&'static [&'static str]),
+    /// A number from `min` to `max`, inclusive. The prompt states the range.
+    Range { min: u64, max: u64 },
+}
+
+/// Describes one parameter of an intent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParamSchema {
+    /// The JSON key, exactly as serde expects it.
+    pub name: &'static str,
+    /// The type shown to the model, e.g. `"u32"` or `"String"`.
+    pub ty: &'static str,
+    pub default: ParamDefault,
+    pub values: ParamValues,
+}
+
+impl ParamSchema {
+    /// Whether the model may leave the parameter out.
+    pub fn is_optional(&self) -> bool {
+        self.default != ParamDefault::Required
+    }
+}
+
+/// Describes one intent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntentSchema {
+    pub name: &'static str,
+    pub parameters: &'static [ParamSchema],
+}
+
+/// Selects an existing array by creation order (1 = the first array created).
+const TARGET_ORDINAL: ParamSchema = ParamSchema {
+    name: "targetOrdinal",
+    ty: "u32",
+    default: ParamDefault::FromContext("the most recently created array"),
+    values: ParamValues::Any,
+};
+
+/// Selects an existing array by the name it was created with.
+const TARGET: ParamSchema = ParamSchema {
+    name: "target",
+    ty: "String",
+    default: ParamDefault::FromContext("the array selected by targetOrdinal"),
+    values: ParamValues::Any,
+};
+
+/// Names the number an intent prints, for later intents to use as a count.
+const RESULT_NAME: ParamSchema = ParamSchema {
+    name: "name",
+    ty: "String",
+    default: ParamDefault::FromContext("no name"),
+    values: ParamValues::Any,
+};
+
+/// Every intent the compiler understands, in the order they are shown to the model.
+pub const INTENT_SCHEMAS: &[IntentSchema] = &[
+    IntentSchema {
+        name: "CreateArray",
+        parameters: &[
+            ParamSchema {
+                name: "size",
+                ty: "u32",
+                default: ParamDefault::FromContext("the number of values"),
+                values: ParamValues::Any,
+            },
+            ParamSchema {
+                name: "values",
+                ty: "Array<i64>",
+                default: ParamDefault::FromContext("random numbers"),
+                values: ParamValues::Any,
+            },
+            ParamSchema {
+                name: "dtype",
+                ty: "String",
+                default: ParamDefault::FromContext("int for listed values, otherwise float"),
+                values: ParamValues::OneOf(ELEMENT_TYPES),
+            },
+            ParamSchema {
+                name: "name",
+                ty: "String",
+                default: ParamDefault::FromContext("no name"),
+                values: ParamValues::Any,
+            },
+        ],
+    },
+    IntentSchema {
+        name: "SortArray",
+        parameters: &[
+            ParamSchema {
+                name: "order",
+                ty: "String",
+                default: ParamDefault::Value("\"ascending\""),
+                values: ParamValues::OneOf(SORT_ORDERS),
+            },
+            ParamSchema {
+                name: "algorithm",
+                ty: "String",
+                default: ParamDefault::FromContext("the runtime's choice by length"),
+                values: ParamValues::OneOf(SORT_ALGORITHMS),
+            },
+            TARGET_ORDINAL,
+            TARGET,
+        ],
+    },
+    IntentSchema {
+        name: "PrintArray",
+        parameters: &[
+            TARGET_ORDINAL,
+            TARGET,
+            ParamSchema {
+                name: "full",
+                ty: "bool",
+                default: ParamDefault::Value("false"),
+                values: ParamValues::Any,
+            },
+        ],
+    },
+    IntentSchema {
+        name: "SumArray",
+        parameters: &[TARGET_ORDINAL, RESULT_NAME],
+    },
+    IntentSchema {
+        name: "ReverseArray",
+        parameters: &[TARGET_ORDINAL],
+    },
+    IntentSchema {
+        name: "AppendToArray",
+        parameters: &[
+            ParamSchema {
+                name: "value",
+                ty: "i64",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+            TARGET_ORDINAL,
+        ],
+    },
+    IntentSchema {
+        name: "FillArray",
+        parameters: &[
+            ParamSchema {
+                name: "value",
+                ty: "i64",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+            TARGET_ORDINAL,
+        ],
+    },
+    IntentSchema {
+        name: "ClearArray",
+        parameters: &[TARGET_ORDINAL],
+    },
+    IntentSchema {
+        name: "SliceArray",
+        parameters: &[
+            ParamSchema {
+                name: "start",
+                ty: "u32",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+            ParamSchema {
+                name: "end",
+                ty: "u32",
+                default: ParamDefault::FromContext("the length of the array"),
+                values: ParamValues::Any,
+            },
+            ParamSchema {
+                name: "fromEnd",
+                ty: "bool",
+                default: ParamDefault::Value("false"),
+                values: ParamValues::Any,
+            },
+            TARGET_ORDINAL,
+        ],
+    },
+    IntentSchema {
+        name: "ConcatArrays",
+        parameters: &[
+            ParamSchema {
+                name: "first",
+                ty: "String",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+            ParamSchema {
+                name: "second",
+                ty: "String",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+            ParamSchema {
+                name: "name",
+                ty: "String",
+                default: ParamDefault::FromContext("no name"),
+                values: ParamValues::Any,
+            },
+        ],
+    },
+    IntentSchema {
+        name: "CopyArray",
+        parameters: &[
+            ParamSchema {
+                name: "name",
+                ty: "String",
+                default: ParamDefault::FromContext("no name"),
+                values: ParamValues::Any,
+            },
+            TARGET_ORDINAL,
+            TARGET,
+        ],
+    },
+    IntentSchema {
+        name: "CreateMatrix",
+        parameters: &[
+            ParamSchema {
+                name: "rows",
+                ty: "u32",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+            ParamSchema {
+                name: "cols",
+                ty: "u32",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+        ],
+    },
+    IntentSchema {
+        name: "PrintMatrix",
+        parameters: &[],
+    },
+    IntentSchema {
+        name: "MapArray",
+        parameters: &[
+            ParamSchema {
+                name: "operation",
+                ty: "String",
+                default: ParamDefault::Required,
+                values: ParamValues::OneOf(MAP_OPERATIONS),
+            },
+            ParamSchema {
+                name: "operand",
+                ty: "i64",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+            TARGET_ORDINAL,
+        ],
+    },
+    IntentSchema {
+        name: "ConvertArray",
+        parameters: &[
+            ParamSchema {
+                name: "to",
+                ty: "String",
+                default: ParamDefault::Required,
+                values: ParamValues::OneOf(ELEMENT_TYPES),
+            },
+            TARGET_ORDINAL,
+        ],
+    },
+    IntentSchema {
+        name: "MinArray",
+        parameters: &[TARGET_ORDINAL, RESULT_NAME],
+    },
+    IntentSchema {
+        name: "MaxArray",
+        parameters: &[TARGET_ORDINAL, RESULT_NAME],
+    },
+    IntentSchema {
+        name: "CountElements",
+        parameters: &[TARGET_ORDINAL, RESULT_NAME],
+    },
+    IntentSchema {
+        name: "AverageArray",
+        parameters: &[TARGET_ORDINAL],
+    },
+    IntentSchema {
+        name: "MedianArray",
+        parameters: &[TARGET_ORDINAL],
+    },
+    IntentSchema {
+        name: "ContainsValue",
+        parameters: &[
+            ParamSchema {
+                name: "value",
+                ty: "i64",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+            TARGET_ORDINAL,
+        ],
+    },
+    IntentSchema {
+        name: "FindIndex",
+        parameters: &[
+            ParamSchema {
+                name: "value",
+                ty: "i64",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+            TARGET_ORDINAL,
+        ],
+    },
+    IntentSchema {
+        name: "PrintHistogram",
+        parameters: &[
+            ParamSchema {
+                name: "buckets",
+                ty: "u32",
+                default: ParamDefault::Value("10"),
+                values: ParamValues::Any,
+            },
+            TARGET_ORDINAL,
+        ],
+    },
+    IntentSchema {
+        name: "DescribeArray",
+        parameters: &[TARGET_ORDINAL, TARGET],
+    },
+    IntentSchema {
+        name: "Repeat",
+        parameters: &[
+            ParamSchema {
+                name: "count",
+                ty: "u32",
+                default: ParamDefault::Required,
+                values: ParamValues::Range {
+                    min: 0,
+                    max: MAX_REPETITIONS,
+                },
+            },
+            ParamSchema {
+                name: "body",
+                ty: "Array<Intent>",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+        ],
+    },
+    IntentSchema {
+        name: "Conditional",
+        parameters: &[
+            ParamSchema {
+                name: "condition",
+                ty: "String",
+                default: ParamDefault::Required,
+                values: ParamValues::OneOf(CONDITIONS),
+            },
+            TARGET_ORDINAL,
+            ParamSchema {
+                name: "then",
+                ty: "Array<Intent>",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+            ParamSchema {
+                name: "else",
+                ty: "Array<Intent>",
+                default: ParamDefault::Value("[]"),
+                values: ParamValues::Any,
+            },
+        ],
+    },
+    IntentSchema {
+        name: "Wait",
+        parameters: &[ParamSchema {
+            name: "durationMs",
+            ty: "u64",
+            default: ParamDefault::Required,
+            values: ParamValues::Range {
+                min: 0,
+                max: MAX_WAIT_MS,
+            },
+        }],
+    },
+    IntentSchema {
+        name: "StartTimer",
+        parameters: &[],
+    },
+    IntentSchema {
+        name: "PrintElapsed",
+        parameters: &[],
+    },
+    IntentSchema {
+        name: "Exit",
+        parameters: &[ParamSchema {
+            name: "code",
+            ty: "i32",
+            default: ParamDefault::Value("0"),
+            values: ParamValues::Range {
+                min: 0,
+                max: MAX_EXIT_CODE as u64,
+            },
+        }],
+    },
+    IntentSchema {
+        name: "Note",
+        parameters: &[ParamSchema {
+            name: "text",
+            ty: "String",
+            default: ParamDefault::Required,
+            values: ParamValues::Any,
+        }],
+    },
+    IntentSchema {
+        name: "SetVariable",
+        parameters: &[
+            ParamSchema {
+                name: "name",
+                ty: "String",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+            ParamSchema {
+                name: "value",
+                ty: "i64",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+        ],
+    },
+    IntentSchema {
+        name: "UseProcedure",
+        parameters: &[
+            ParamSchema {
+                name: "name",
+                ty: "String",
+                default: ParamDefault::Required,
+                values: ParamValues::Any,
+            },
+            ParamSchema {
+                name: "arguments",
+                ty: "Object",
+                default: ParamDefault::Value("{}"),
+                values: ParamValues::Any,
+            },
+        ],
+    },
+];
+
+/// Looks up an intent by name.
+pub fn find(name: &str) -> Option<&'static IntentSchema> {
+    INTENT_SCHEMAS.iter().find(|schema| schema.name == name)
+}
+
+/// The registry default of `intent`'s `param`, or `None` when the parameter
+/// is required or its default depends on context.
+pub fn default_value(intent: &str, param: &str) -> Option<serde_json::Value> {
+    let schema = find(intent)?.parameters.iter().find(|p| p.name == param)?;
+    match schema.default {
+        ParamDefault::Value(json) => {
+            Some(serde_json::from_str(json).expect("registry defaults are valid JSON literals"))
+        }
+        ParamDefault::Required | ParamDefault::FromContext(_) => None,
+    }
+}
+
+/// The names of all registered intents.
+pub fn intent_names() -> impl Iterator<Item = &'static str> {
+    INTENT_SCHEMAS.iter().map(|schema| schema.name)
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{PromptOptions, build_prompt};
+    use crate::semantic_analyzer::SemanticAnalyzer;
+    use naldom_ir::{CountArg, CreateArrayParams, Intent};
+
+    /// A sample JSON value for a parameter type, used to prove the registry
+    /// matches what serde actually accepts.
+    fn sample_value(param: &ParamSchema) -> serde_json::Value {
+        if let ParamValues::OneOf(values) = param.values {
+            return serde_json::json!(values[0]);
+        }
+        match param.ty {
+            "String" => serde_json::json!("ascending"),
+            "Object" => serde_json::json!({}),
+            "Array<Intent>" => serde_json::json!([{ "intent": "PrintArray" }]),
+            "Array<i64>" => serde_json::json!([1]),
+            "bool" => serde_json::json!(true),
+            _ => serde_json::json!(1),
+        }
+    }
+
+    #[test]
+    fn test_every_registered_intent_deserializes() {
+        for schema in INTENT_SCHEMAS {
+            let mut intent = serde_json::json!({ "intent": schema.name });
+            if !schema.parameters.is_empty() {
+                let parameters: serde_json::Map<_, _> = schema
+                    .parameters
+                    .iter()
+                    .map(|p| (p.name.to_string(), sample_value(p)))
+                    .collect();
+                intent["parameters"] = parameters.into();
+            }
+
+            let parsed: Result<Intent, _> = serde_json::from_value(intent.clone());
+            assert!(
+                parsed.is_ok(),
+                "Registry entry {} does not match the Intent enum: {}",
+                intent,
+                parsed.unwrap_err()
+            );
+        }
+    }
+
+    /// `schema`'s intent with its required parameters set, plus `extra`.
+    fn intent_with(schema: &IntentSchema, extra: Option<(&str, serde_json::Value)>) -> Intent {
+        let mut parameters: serde_json::Map<_, _> = schema
+            .parameters
+            .iter()
+            .filter(|p| !p.is_optional())
+            .map(|p| (p.name.to_string(), sample_value(p)))
+            .collect();
+        if let Some((name, value)) = extra {
+            parameters.insert(name.to_string(), value);
+        }
+        let intent = serde_json::json!({ "intent": schema.name, "parameters": parameters });
+        serde_json::from_value(intent).unwrap()
+    }
+
+    /// What the compiler ends up with for `intent`: the analyzed form, or the
+    /// parsed one for procedures, which are expanded rather than analyzed.
+    fn resolved(intent: Intent) -> String {
+        if matches!(intent, Intent::UseProcedure(_)) {
+            return format!("{:?}", intent);
+        }
+        let graph = [
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(1)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            intent,
+        ];
+        let validated = SemanticAnalyzer::new().analyze(&graph).unwrap();
+        format!("{:?}", validated[1])
+    }
+
+    #[test]
+    fn test_prompt_serde_and_analyzer_agree_with_registry_defaults() {
+        let prompt = build_prompt("", &PromptOptions::default()).text;
+        for schema in INTENT_SCHEMAS {
+            for param in schema.parameters.iter().filter(|p| p.is_optional()) {
+                assert!(
+                    prompt.contains(&format!("\"{}\"", param.name)),
+                    "The prompt never mentions {}.{}",
+                    schema.name,
+                    param.name
+                );
+                let ParamDefault::Value(json) = param.default else {
+                    continue;
+                };
+                assert!(
+                    prompt.contains(&format!(
+                        "- For the \"{}\" intent, if \"{}\" is not specified, you MUST default to {}.",
+                        schema.name, param.name, json
+                    )),
+                    "The prompt does not state the default of {}.{}",
+                    schema.name,
+                    param.name
+                );
+
+                let default = default_value(schema.name, param.name).unwrap();
+                let omitted = resolved(intent_with(schema, None));
+                let explicit = resolved(intent_with(schema, Some((param.name, default))));
+                assert_eq!(
+                    omitted, explicit,
+                    "Leaving out {}.{} does not mean {}",
+                    schema.name, param.name, json
+                );
+            }
+        }
+    }
+}
@@ -1,12 +1,63 @@
 // crates/naldom-core/src/semantic_analyzer.rs
 
-use naldom_ir::{CreateArrayParams, Intent, SortArrayParams, WaitParams};
+use crate::cancel::CancellationToken;
+use crate::fs_policy::FsPolicy;
+use crate::schema;
+use naldom_ir::{
+    ALL_ARRAYS, AppendParams, AverageArrayParams, ClearArrayParams, ConcatParams,
+    ConditionalParams, ContainsParams, ConvertParams, CopyParams, CountArg, CountElementsParams,
+    CreateArrayParams, CreateMatrixParams, DescribeArrayParams, ExitParams, ExtremeParams,
+    FillParams, FindIndexParams, HistogramParams, Intent, MapParams, MedianArrayParams, NoteParams,
+    NumberArg, PrintArrayParams, RepeatParams, ReverseArrayParams, SetVariableParams, SliceParams,
+    SortArrayParams, SumArrayParams, WaitParams,
+};
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// The kind of number an array holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementType {
+    Float,
+    Int,
+}
+
+impl ElementType {
+    /// The element type called `name`, one of [`ELEMENT_TYPES`].
+    pub fn from_name(name: &str) -> Option<ElementType> {
+        match name {
+            "float" => Some(ElementType::Float),
+            "int" => Some(ElementType::Int),
+            _ => None,
+        }
+    }
+
+    /// The name intents use for the element type.
+    pub fn name(self) -> &'static str {
+        match self {
+            ElementType::Float => "float",
+            ElementType::Int => "int",
+        }
+    }
+}
 
 /// Represents the types known to our type system.
 #[derive(Debug, Clone, PartialEq)]
 pub enum SymbolType {
-    Array,
+    /// An array, of the element type it was created with or last converted to.
+    Array(ElementType),
+    /// A matrix created by `CreateMatrix`. Array operations reject it.
+    Matrix,
+    /// A constant set by `SetVariable`, or a result named by `SumArray`,
+    /// `MinArray`, `MaxArray` or `CountElements`.
+    Integer,
+}
+
+impl SymbolType {
+    /// Whether the symbol is an array, of either element type.
+    pub fn is_array(&self) -> bool {
+        matches!(self, SymbolType::Array(_))
+    }
 }
 
 /// Represents a declared symbol (e.g., a variable) in the program.
@@ -14,10 +65,16 @@ pub enum SymbolType {
 pub struct Symbol {
     pub name: String,
     pub symbol_type: SymbolType,
+    /// The array's position in creation order, counting from 1. Unused for
+    /// integers.
+    pub ordinal: u32,
+    /// The value of a constant; `None` for arrays, and for results, which
+    /// are only known when the program runs.
+    pub value: Option<i64>,
 }
 
 /// The Symbol Table stores all symbols declared in a given scope.
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct SymbolTable {
     symbols: HashMap<String, Symbol>,
 }
@@ -36,19 +93,715 @@ impl SymbolTable {
     pub fn get(&self, name: &str) -> Option<&Symbol> {
         self.symbols.get(name)
     }
+
+    /// The name the program gave the array at `ordinal`, if it still has one:
+    /// a later array with the same name takes it over.
+    pub fn array_name(&self, ordinal: u32) -> Option<&str> {
+        self.symbols
+            .values()
+            .filter(|symbol| symbol.symbol_type.is_array() && symbol.ordinal == ordinal)
+            .map(|symbol| symbol.name.as_str())
+            .filter(|name| !is_reserved_name(name))
+            .min()
+    }
+
+    /// Every symbol, in no particular order.
+    pub fn symbols(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.values()
+    }
+
+    /// The names of the symbols of type `symbol_type`, in no particular order.
+    pub fn names_of(&self, symbol_type: SymbolType) -> impl Iterator<Item = &str> {
+        self.symbols
+            .values()
+            .filter(move |symbol| symbol.symbol_type == symbol_type)
+            .map(|symbol| symbol.name.as_str())
+    }
+}
+
+/// Whether `name` has the form `var_N` the compiler names arrays with.
+fn is_reserved_name(name: &str) -> bool {
+    name.strip_prefix("var_")
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// An error found while validating the IntentGraph.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SemanticError {
+    /// An intent operated on an array before any array was created.
+    UseBeforeCreate {
+        intent_index: usize,
+        operation: &'static str,
+    },
+    /// An intent referenced an array by a name no array was created with.
+    UnknownArray {
+        intent_index: usize,
+        operation: &'static str,
+        name: String,
+    },
+    /// An array or variable was given a name of the form the compiler uses
+    /// internally, or the name that stands for every array.
+    ReservedName { intent_index: usize, name: String },
+    /// A parameter referenced a variable no `SetVariable` intent has set.
+    /// `suggestion` is the set variable with the closest name, if any is close.
+    UndefinedVariable {
+        intent_index: usize,
+        name: String,
+        suggestion: Option<String>,
+    },
+    /// A `SliceArray` starts after it ends.
+    InvalidSlice {
+        intent_index: usize,
+        start: u32,
+        end: u32,
+    },
+    /// A `SliceArray` counting from the end also gave an `end`.
+    EndOfSliceFromEnd { intent_index: usize, end: u32 },
+    /// A `PrintHistogram` asked for zero buckets.
+    ZeroBuckets { intent_index: usize },
+    /// An `Exit` gave a code outside 0 to 255, which a process cannot report.
+    InvalidExitCode { intent_index: usize, code: i32 },
+    /// A `CreateArray` gave neither a size nor any values.
+    MissingSize { intent_index: usize },
+    /// A `CreateArray` listed values, and a size that does not match them.
+    SizeConflict {
+        intent_index: usize,
+        size: u32,
+        values: usize,
+    },
+    /// A parameter that needs a number referenced an array.
+    NotANumber { intent_index: usize, name: String },
+    /// A variable used as a count holds a negative value, or one too large.
+    InvalidCount {
+        intent_index: usize,
+        name: String,
+        value: i64,
+    },
+    /// An intent referenced an array by an ordinal beyond the arrays created so far.
+    OrdinalOutOfRange {
+        intent_index: usize,
+        operation: &'static str,
+        ordinal: u32,
+        available: usize,
+    },
+    /// An intent operated on a symbol of the wrong type.
+    TypeMismatch {
+        intent_index: usize,
+        operation: &'static str,
+        variable: String,
+        found: SymbolType,
+    },
+    /// An array would need more memory than the runtime will allocate.
+    ArrayTooLarge {
+        intent_index: usize,
+        size: u32,
+        bytes: u64,
+    },
+    /// A wait is longer than generated code can represent.
+    WaitTooLong {
+        intent_index: usize,
+        duration_ms: u64,
+    },
+    /// A `Repeat` would run its body more often than generated code allows.
+    /// `repetitions` includes the counts of any enclosing repeats.
+    TooManyRepetitions {
+        intent_index: usize,
+        repetitions: u64,
+    },
+    /// An array or matrix is created inside the body of a `Repeat`, by
+    /// `CreateArray`, `ConcatArrays`, `CopyArray` or `CreateMatrix`.
+    CreateInRepeat { intent_index: usize },
+    /// A matrix would need more memory than the runtime will allocate.
+    MatrixTooLarge {
+        intent_index: usize,
+        rows: u32,
+        cols: u32,
+        bytes: u64,
+    },
+    /// A `PrintMatrix` comes before any `CreateMatrix`.
+    MatrixBeforeCreate { intent_index: usize },
+    /// A `SortArray` would act on a matrix: the most recently created value
+    /// is one, or no array exists besides it.
+    SortMatrix { intent_index: usize },
+    /// A `ConvertArray` would act on a matrix: the most recently created
+    /// value is one, or no array exists besides it.
+    ConvertMatrix { intent_index: usize },
+    /// A `Conditional` tests something other than one of [`CONDITIONS`].
+    UnknownCondition {
+        intent_index: usize,
+        condition: String,
+    },
+    /// A `MapArray` asks for an operation other than one of
+    /// [`MAP_OPERATIONS`].
+    UnknownMapOperation {
+        intent_index: usize,
+        operation: String,
+    },
+    /// A `SortArray` asks for an order other than one of [`SORT_ORDERS`].
+    UnknownSortOrder { intent_index: usize, order: String },
+    /// A `SortArray` names an algorithm other than one of
+    /// [`SORT_ALGORITHMS`].
+    UnknownSortAlgorithm {
+        intent_index: usize,
+        algorithm: String,
+    },
+    /// A `CreateArray` asks for an element type other than one of
+    /// [`ELEMENT_TYPES`].
+    UnknownDtype { intent_index: usize, dtype: String },
+    /// A `ConcatArrays` joins an array of whole numbers with one of decimal
+    /// numbers.
+    MixedElementTypes {
+        intent_index: usize,
+        operation: &'static str,
+        first: ElementType,
+        second: ElementType,
+    },
+    /// A `ConvertArray` asks for an element type other than one of
+    /// [`ELEMENT_TYPES`].
+    UnknownElementType { intent_index: usize, to: String },
+    /// A `PrintElapsed` comes before any `StartTimer` that always runs.
+    ElapsedBeforeTimer { intent_index: usize },
+    /// A `UseProcedure` intent reached the analyzer without being expanded.
+    UnexpandedProcedure { intent_index: usize, name: String },
+    /// A string parameter is longer than the configured limit.
+    StringTooLong {
+        intent_index: usize,
+        intent: &'static str,
+        parameter: &'static str,
+        bytes: usize,
+        limit: usize,
+    },
+    /// A string parameter contains a NUL character, which would silently cut
+    /// it short once it becomes a C string.
+    NulInString {
+        intent_index: usize,
+        intent: &'static str,
+        parameter: &'static str,
+    },
+}
+
+impl SemanticError {
+    /// The index of the offending intent in the analyzed graph.
+    pub fn intent_index(&self) -> usize {
+        match self {
+            SemanticError::UseBeforeCreate { intent_index, .. }
+            | SemanticError::UnknownArray { intent_index, .. }
+            | SemanticError::ReservedName { intent_index, .. }
+            | SemanticError::UndefinedVariable { intent_index, .. }
+            | SemanticError::InvalidSlice { intent_index, .. }
+            | SemanticError::EndOfSliceFromEnd { intent_index, .. }
+            | SemanticError::ZeroBuckets { intent_index }
+            | SemanticError::InvalidExitCode { intent_index, .. }
+            | SemanticError::MissingSize { intent_index }
+            | SemanticError::SizeConflict { intent_index, .. }
+            | SemanticError::NotANumber { intent_index, .. }
+            | SemanticError::InvalidCount { intent_index, .. }
+            | SemanticError::OrdinalOutOfRange { intent_index, .. }
+            | SemanticError::TypeMismatch { intent_index, .. }
+            | SemanticError::ArrayTooLarge { intent_index, .. }
+            | SemanticError::WaitTooLong { intent_index, .. }
+            | SemanticError::TooManyRepetitions { intent_index, .. }
+            | SemanticError::CreateInRepeat { intent_index }
+            | SemanticError::MatrixTooLarge { intent_index, .. }
+            | SemanticError::MatrixBeforeCreate { intent_index }
+            | SemanticError::SortMatrix { intent_index }
+            | SemanticError::ConvertMatrix { intent_index }
+            | SemanticError::UnknownCondition { intent_index, .. }
+            | SemanticError::UnknownMapOperation { intent_index, .. }
+            | SemanticError::UnknownSortOrder { intent_index, .. }
+            | SemanticError::UnknownSortAlgorithm { intent_index, .. }
+            | SemanticError::UnknownDtype { intent_index, .. }
+            | SemanticError::MixedElementTypes { intent_index, .. }
+            | SemanticError::UnknownElementType { intent_index, .. }
+            | SemanticError::ElapsedBeforeTimer { intent_index }
+            | SemanticError::UnexpandedProcedure { intent_index, .. }
+            | SemanticError::StringTooLong { intent_index, .. }
+            | SemanticError::NulInString { intent_index, .. } => *intent_index,
+        }
+    }
+}
+
+impl fmt::Display for SemanticError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SemanticError::UseBeforeCreate {
+                operation: "print", ..
+            } => write!(
+                f,
+                "Semantic Error: Attempted to print, but nothing has been created yet."
+            ),
+            SemanticError::UseBeforeCreate { operation, .. } => write!(
+                f,
+                "Semantic Error: Attempted to {}, but no array has been created yet.",
+                operation
+            ),
+            SemanticError::UnknownArray {
+                operation, name, ..
+            } => write!(
+                f,
+                "Semantic Error: Attempted to {} '{}', but no array named '{}' has been created.",
+                operation, name, name
+            ),
+            SemanticError::ReservedName { name, .. } => write!(
+                f,
+                "Semantic Error: '{}' cannot be used as a name; names of the form var_N and '{}' are reserved.",
+                name, ALL_ARRAYS
+            ),
+            SemanticError::UndefinedVariable {
+                name, suggestion, ..
+            } => {
+                write!(
+                    f,
+                    "Semantic Error: No variable named '{}' has been set.",
+                    name
+                )?;
+                match suggestion {
+                    Some(suggestion) => write!(f, " Did you mean '{}'?", suggestion),
+                    None => Ok(()),
+                }
+            }
+            SemanticError::InvalidSlice { start, end, .. } => write!(
+                f,
+                "Semantic Error: A slice cannot start at {} and end at {}; the start must not be after the end.",
+                start, end
+            ),
+            SemanticError::EndOfSliceFromEnd { end, .. } => write!(
+                f,
+                "Semantic Error: A slice that counts from the end keeps everything up to the end, so it cannot also end at {}.",
+                end
+            ),
+            SemanticError::ZeroBuckets { .. } => {
+                write!(f, "Semantic Error: A histogram needs at least one bucket.")
+            }
+            SemanticError::InvalidExitCode { code, .. } => write!(
+                f,
+                "Semantic Error: Exit code {} is out of range; exit codes run from 0 to {}.",
+                code, MAX_EXIT_CODE
+            ),
+            SemanticError::MissingSize { .. } => write!(
+                f,
+                "Semantic Error: CreateArray needs either a size or a list of values."
+            ),
+            SemanticError::SizeConflict { size, values, .. } => write!(
+                f,
+                "Semantic Error: CreateArray lists {} values, but its size is {}.",
+                values, size
+            ),
+            SemanticError::NotANumber { name, .. } => write!(
+                f,
+                "Semantic Error: '{}' is an array, but a number is needed here.",
+                name
+            ),
+            SemanticError::InvalidCount { name, value, .. } => write!(
+                f,
+                "Semantic Error: '{}' is {}, which cannot be used as a count.",
+                name, value
+            ),
+            SemanticError::OrdinalOutOfRange {
+                operation,
+                ordinal,
+                available,
+                ..
+            } => write!(
+                f,
+                "Semantic Error: Attempted to {} the {} array, but there {} only {} {}.",
+                operation,
+                ordinal_name(*ordinal),
+                if *available == 1 { "is" } else { "are" },
+                available,
+                if *available == 1 { "array" } else { "arrays" }
+            ),
+            SemanticError::TypeMismatch {
+                operation,
+                variable,
+                found,
+                ..
+            } => write!(
+                f,
+                "Semantic Error: Attempted to {} '{}', which is not an Array. It has type {:?}.",
+                operation, variable, found
+            ),
+            SemanticError::ArrayTooLarge { size, bytes, .. } => write!(
+                f,
+                "Semantic Error: An array of {} elements would require {}, but arrays are limited to {}.",
+                size,
+                format_bytes(*bytes),
+                format_bytes(MAX_ARRAY_BYTES)
+            ),
+            SemanticError::WaitTooLong { duration_ms, .. } => write!(
+                f,
+                "Semantic Error: A wait of {} ms is too long; waits are limited to {} ms.",
+                duration_ms, MAX_WAIT_MS
+            ),
+            SemanticError::TooManyRepetitions { repetitions, .. } => write!(
+                f,
+                "Semantic Error: Repeating {} times is too many; repeats are limited to {} repetitions in total.",
+                repetitions, MAX_REPETITIONS
+            ),
+            SemanticError::CreateInRepeat { .. } => write!(
+                f,
+                "Semantic Error: Arrays and matrices cannot be created inside a Repeat; create them before it."
+            ),
+            SemanticError::MatrixTooLarge {
+                rows, cols, bytes, ..
+            } => write!(
+                f,
+                "Semantic Error: A {}x{} matrix would require {}, but matrices are limited to {}.",
+                rows,
+                cols,
+                format_bytes(*bytes),
+                format_bytes(MAX_ARRAY_BYTES)
+            ),
+            SemanticError::MatrixBeforeCreate { .. } => write!(
+                f,
+                "Semantic Error: PrintMatrix needs a CreateMatrix before it."
+            ),
+            SemanticError::SortMatrix { .. } => write!(
+                f,
+                "Semantic Error: SortArray cannot sort a matrix. Flatten the matrix into an array first, then sort the array."
+            ),
+            SemanticError::ConvertMatrix { .. } => write!(
+                f,
+                "Semantic Error: ConvertArray cannot convert a matrix. Flatten the matrix into an array first, then convert the array."
+            ),
+            SemanticError::UnknownCondition { condition, .. } => write!(
+                f,
+                "Semantic Error: Unknown condition '{}'. Expected one of: {}.",
+                condition,
+                CONDITIONS.join(", ")
+            ),
+            SemanticError::UnknownMapOperation { operation, .. } => write!(
+                f,
+                "Semantic Error: MapArray does not support the operation '{}'. Expected one of: {}.",
+                operation,
+                MAP_OPERATIONS.join(", ")
+            ),
+            SemanticError::UnknownSortOrder { order, .. } => write!(
+                f,
+                "Semantic Error: SortArray cannot sort in '{}' order. Expected one of: {}.",
+                order,
+                SORT_ORDERS.join(", ")
+            ),
+            SemanticError::UnknownSortAlgorithm { algorithm, .. } => write!(
+                f,
+                "Semantic Error: SortArray has no '{}' algorithm. Expected one of: {}.",
+                algorithm,
+                SORT_ALGORITHMS.join(", ")
+            ),
+            SemanticError::UnknownDtype { dtype, .. } => write!(
+                f,
+                "Semantic Error: CreateArray cannot create an array of '{}' elements. Expected one of: {}.",
+                dtype,
+                ELEMENT_TYPES.join(", ")
+            ),
+            SemanticError::MixedElementTypes {
+                operation,
+                first,
+                second,
+                ..
+            } => write!(
+                f,
+                "Semantic Error: Cannot {} an array of {} elements with an array of {} elements. Convert one of them with ConvertArray first.",
+                operation,
+                first.name(),
+                second.name()
+            ),
+            SemanticError::UnknownElementType { to, .. } => write!(
+                f,
+                "Semantic Error: ConvertArray cannot convert to '{}'. Expected one of: {}.",
+                to,
+                ELEMENT_TYPES.join(", ")
+            ),
+            SemanticError::ElapsedBeforeTimer { .. } => write!(
+                f,
+                "Semantic Error: PrintElapsed needs a StartTimer before it."
+            ),
+            SemanticError::UnexpandedProcedure { name, .. } => write!(
+                f,
+                "Semantic Error: Procedure '{}' must be expanded before analysis.",
+                name
+            ),
+            SemanticError::StringTooLong {
+                intent,
+                parameter,
+                bytes,
+                limit,
+                ..
+            } => write!(
+                f,
+                "Semantic Error: The '{}' parameter of {} is {} long, but string parameters are limited to {}.",
+                parameter,
+                intent,
+                format_bytes(*bytes as u64),
+                format_bytes(*limit as u64)
+            ),
+            SemanticError::NulInString {
+                intent, parameter, ..
+            } => write!(
+                f,
+                "Semantic Error: The '{}' parameter of {} contains a NUL character.",
+                parameter, intent
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SemanticError {}
+
+/// The most memory a single array may occupy (4 GiB).
+pub const MAX_ARRAY_BYTES: u64 = 4 << 30;
+
+/// The size of one array element; arrays hold `f64`s.
+const ELEMENT_BYTES: u64 = 8;
+
+/// The longest accepted wait. Durations travel through generated code as `i64`
+/// milliseconds; this bound also keeps them representable in microseconds.
+pub const MAX_WAIT_MS: u64 = i64::MAX as u64 / 1000;
+
+/// The largest exit code a process can report.
+pub const MAX_EXIT_CODE: i32 = 255;
+
+/// The most times any intent may run through `Repeat`s, counting nested
+/// repeats as the product of their counts. Repeats are unrolled, so this also
+/// bounds the size of the generated code.
+pub const MAX_REPETITIONS: u64 = 1000;
+
+/// The tests a `Conditional` can make of an array.
+pub const CONDITIONS: &[&str] = &["is_sorted", "is_empty"];
+
+/// The arithmetic a `MapArray` can apply to every element.
+pub const MAP_OPERATIONS: &[&str] = &["add", "subtract", "multiply", "divide"];
+
+/// The orders a `SortArray` can sort in.
+pub const SORT_ORDERS: &[&str] = &["ascending", "descending"];
+
+/// The algorithms a `SortArray` can ask for by name.
+pub const SORT_ALGORITHMS: &[&str] = &["quick", "merge", "bubble"];
+
+/// The element types a `ConvertArray` can convert an array to.
+pub const ELEMENT_TYPES: &[&str] = &["float", "int"];
+
+/// The default limit on the length of a string parameter, in bytes (4 KiB).
+pub const MAX_STRING_BYTES: usize = 4 << 10;
+
+/// Formats a byte count with a binary unit: 34359738360 -> "32 GiB".
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["bytes", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let rounded = format!("{:.1}", value);
+    let rounded = rounded.strip_suffix(".0").unwrap_or(&rounded);
+    format!("{} {}", rounded, UNITS[unit])
+}
+
+/// Spells out an ordinal as English text: 1 -> "1st", 12 -> "12th", 23 -> "23rd".
+pub fn ordinal_name(n: u32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+/// How many edits apart a misspelt variable name may be from the one
+/// suggested in its place.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// The number of single-character insertions, deletions and substitutions
+/// that turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// A parameter the model left out, which the analyzer filled in from the
+/// schema registry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefaultedParam {
+    pub intent_index: usize,
+    pub name: &'static str,
+    /// The filled-in value as shown to users, e.g. `ascending`.
+    pub value: String,
+}
+
+/// What a [`SemanticAnalyzer`] has learned from the intents analyzed so far,
+/// captured by [`snapshot`](SemanticAnalyzer::snapshot) to go back to later.
+#[derive(Clone)]
+pub struct AnalyzerSnapshot {
+    symbol_table: SymbolTable,
+    variable_counter: u32,
+    created_arrays: Vec<String>,
+    created_matrices: Vec<String>,
+    latest_is_matrix: bool,
+    timer_started: bool,
+}
+
+/// The settings of a [`SemanticAnalyzer`]. They never change during an
+/// analysis, so analyzers running side by side share one behind an `Arc`.
+#[derive(Debug, Clone)]
+pub struct AnalyzerConfig {
+    /// The limit on string parameters, in bytes after normalization.
+    pub max_string_bytes: usize,
+    /// Stops analysis at the next intent once cancelled.
+    pub cancellation: CancellationToken,
+    /// The paths file intents may use; `None` allows every path.
+    pub fs_policy: Option<FsPolicy>,
+}
+
+impl Default for AnalyzerConfig {
+    fn default() -> Self {
+        AnalyzerConfig {
+            max_string_bytes: MAX_STRING_BYTES,
+            cancellation: CancellationToken::new(),
+            fs_policy: None,
+        }
+    }
 }
 
 /// The Semantic Analyzer walks the IntentGraph and validates it.
-#[derive(Default)]
 pub struct SemanticAnalyzer {
     symbol_table: SymbolTable,
     variable_counter: u32,
-    last_created_variable: Option<String>,
+    /// Every created array, in creation order; ordinals index into this.
+    created_arrays: Vec<String>,
+    /// Every created matrix, in creation order. `PrintMatrix` uses the last.
+    created_matrices: Vec<String>,
+    /// Whether a matrix was created after the most recent array, so an
+    /// operation without a target would act on it.
+    latest_is_matrix: bool,
+    /// Whether a `StartTimer` has run on every path to the current intent.
+    timer_started: bool,
+    /// The index of an `Exit` reached on every path to the current intent,
+    /// until the intents after it have been warned about.
+    exited_at: Option<usize>,
+    warnings: Vec<String>,
+    defaulted: Vec<DefaultedParam>,
+    /// How often the intent being analyzed runs: the product of the counts
+    /// of the `Repeat`s around it.
+    repetitions: u64,
+    config: Arc<AnalyzerConfig>,
+}
+
+impl Default for SemanticAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SemanticAnalyzer {
     pub fn new() -> Self {
-        Self::default()
+        Self::with_config(Arc::default())
+    }
+
+    /// An analyzer with the settings in `config`, which it shares.
+    pub fn with_config(config: Arc<AnalyzerConfig>) -> Self {
+        SemanticAnalyzer {
+            symbol_table: SymbolTable::new(),
+            variable_counter: 0,
+            created_arrays: Vec::new(),
+            created_matrices: Vec::new(),
+            latest_is_matrix: false,
+            timer_started: false,
+            exited_at: None,
+            warnings: Vec::new(),
+            defaulted: Vec::new(),
+            repetitions: 1,
+            config,
+        }
+    }
+
+    /// Replaces the limit on string parameters (by default [`MAX_STRING_BYTES`]).
+    pub fn with_max_string_bytes(mut self, max_string_bytes: usize) -> Self {
+        Arc::make_mut(&mut self.config).max_string_bytes = max_string_bytes;
+        self
+    }
+
+    /// Restricts the paths file intents may use, or lifts the restriction.
+    pub fn with_fs_policy(mut self, fs_policy: Option<FsPolicy>) -> Self {
+        Arc::make_mut(&mut self.config).fs_policy = fs_policy;
+        self
+    }
+
+    /// Stops analysis at the next intent once `cancellation` is cancelled. The
+    /// graph returned then is incomplete and must be discarded.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        Arc::make_mut(&mut self.config).cancellation = cancellation;
+        self
+    }
+
+    /// Forgets every array and variable seen so far, as well as the last
+    /// analysis' warnings and defaults. Configuration is kept.
+    pub fn reset(&mut self) {
+        self.symbol_table = SymbolTable::new();
+        self.variable_counter = 0;
+        self.created_arrays.clear();
+        self.created_matrices.clear();
+        self.latest_is_matrix = false;
+        self.timer_started = false;
+        self.exited_at = None;
+        self.warnings.clear();
+        self.defaulted.clear();
+        self.repetitions = 1;
+    }
+
+    /// Captures the arrays and variables seen so far.
+    pub fn snapshot(&self) -> AnalyzerSnapshot {
+        AnalyzerSnapshot {
+            symbol_table: self.symbol_table.clone(),
+            variable_counter: self.variable_counter,
+            created_arrays: self.created_arrays.clone(),
+            created_matrices: self.created_matrices.clone(),
+            latest_is_matrix: self.latest_is_matrix,
+            timer_started: self.timer_started,
+        }
+    }
+
+    /// Goes back to the arrays and variables of `snapshot`, as if the intents
+    /// analyzed after it was taken had never been seen. Warnings and defaults
+    /// are cleared. Configuration is kept.
+    pub fn restore(&mut self, snapshot: &AnalyzerSnapshot) {
+        self.symbol_table = snapshot.symbol_table.clone();
+        self.variable_counter = snapshot.variable_counter;
+        self.created_arrays = snapshot.created_arrays.clone();
+        self.created_matrices = snapshot.created_matrices.clone();
+        self.latest_is_matrix = snapshot.latest_is_matrix;
+        self.timer_started = snapshot.timer_started;
+        self.exited_at = None;
+        self.warnings.clear();
+        self.defaulted.clear();
+        self.repetitions = 1;
+    }
+
+    /// The arrays, matrices and variables declared so far.
+    pub fn symbol_table(&self) -> &SymbolTable {
+        &self.symbol_table
+    }
+
+    /// Non-fatal findings collected during the last analysis.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// The parameters filled in from their registry defaults during the last
+    /// analysis, in intent order.
+    pub fn defaulted(&self) -> &[DefaultedParam] {
+        &self.defaulted
     }
 
     /// Generates a new, unique variable name for internal tracking.
@@ -58,72 +811,1021 @@ impl SemanticAnalyzer {
         name
     }
 
-    /// The main entry point for semantic analysis.
-    pub fn analyze(&mut self, intent_graph: &[Intent]) -> Result<Vec<Intent>, String> {
-        let validated_graph = intent_graph.to_vec();
+    /// The main entry point for semantic analysis. In the returned graph every
+    /// intent that acts on an array has its `target_ordinal` resolved, and
+    /// every omitted parameter holds its registry default.
+    ///
+    /// Each call analyzes a separate program: the analyzer is [`reset`] first,
+    /// so one instance can check any number of programs.
+    ///
+    /// [`reset`]: SemanticAnalyzer::reset
+    pub fn analyze(&mut self, intent_graph: &[Intent]) -> Result<Vec<Intent>, SemanticError> {
+        self.reset();
+        self.extend(intent_graph)
+    }
 
-        for intent in intent_graph {
-            self.analyze_intent(intent)?;
-        }
+    /// Analyzes `intent_graph` as a continuation of the intents analyzed so
+    /// far, e.g. the next line typed into a REPL: arrays created earlier can
+    /// be referenced, and ordinals keep counting. Warnings and defaults are
+    /// reported for this call only, with intent indices relative to it.
+    pub fn extend(&mut self, intent_graph: &[Intent]) -> Result<Vec<Intent>, SemanticError> {
+        self.warnings.clear();
+        self.defaulted.clear();
+        let cancellation = self.config.cancellation.clone();
+        intent_graph
+            .iter()
+            .enumerate()
+            .take_while(|_| !cancellation.is_cancelled())
+            .map(|(index, intent)| self.analyze_intent(index, intent))
+            .collect()
+    }
 
-        Ok(validated_graph)
+    /// Analyzes `intent`, the one at `index` of a graph whose earlier intents
+    /// have been analyzed already. Unlike [`extend`](Self::extend), warnings
+    /// and defaults accumulate, and are reported at `index`.
+    pub fn analyze_at(&mut self, index: usize, intent: &Intent) -> Result<Intent, SemanticError> {
+        self.analyze_intent(index, intent)
     }
 
-    /// Analyzes a single intent.
-    fn analyze_intent(&mut self, intent: &Intent) -> Result<(), String> {
+    /// Analyzes a single intent, returning its validated form.
+    fn analyze_intent(&mut self, index: usize, intent: &Intent) -> Result<Intent, SemanticError> {
+        if let Some(exit) = self.exited_at.take() {
+            self.warnings.push(format!(
+                "intent #{} exits the program; the intents after it never run",
+                exit + 1
+            ));
+        }
         match intent {
-            Intent::CreateArray(params) => self.analyze_create_array(params),
-            Intent::SortArray(params) => self.analyze_sort_array(params),
-            Intent::PrintArray => self.analyze_print_array(),
-            Intent::Wait(params) => self.analyze_wait(params),
+            Intent::CreateArray(params) => self.analyze_create_array(index, params),
+            Intent::SortArray(params) => self.analyze_sort_array(index, params),
+            Intent::PrintArray(params) => self.analyze_print_array(index, params.as_ref()),
+            Intent::SumArray(params) => self.analyze_sum_array(index, params.as_ref()),
+            Intent::ReverseArray(params) => self.analyze_reverse_array(index, params.as_ref()),
+            Intent::AppendToArray(params) => self.analyze_append(index, params),
+            Intent::FillArray(params) => self.analyze_fill(index, params),
+            Intent::ClearArray(params) => self.analyze_clear_array(index, params.as_ref()),
+            Intent::SliceArray(params) => self.analyze_slice(index, params),
+            Intent::MapArray(params) => self.analyze_map(index, params),
+            Intent::ConvertArray(params) => self.analyze_convert(index, params),
+            Intent::ConcatArrays(params) => self.analyze_concat(index, params),
+            Intent::CopyArray(params) => self.analyze_copy(index, params),
+            Intent::CreateMatrix(params) => self.analyze_create_matrix(index, params),
+            Intent::PrintMatrix(_) if self.created_matrices.is_empty() => {
+                Err(SemanticError::MatrixBeforeCreate {
+                    intent_index: index,
+                })
+            }
+            Intent::PrintMatrix(_) => Ok(Intent::PrintMatrix(None)),
+            Intent::ContainsValue(params) => {
+                let ordinal = self.resolve_array(index, "search", params.target_ordinal)?;
+                Ok(Intent::ContainsValue(ContainsParams {
+                    value: params.value,
+                    target_ordinal: Some(ordinal),
+                }))
+            }
+            Intent::FindIndex(params) => {
+                let ordinal = self.resolve_array(index, "search", params.target_ordinal)?;
+                Ok(Intent::FindIndex(FindIndexParams {
+                    value: params.value,
+                    target_ordinal: Some(ordinal),
+                }))
+            }
+            Intent::PrintHistogram(params) => self.analyze_histogram(index, params.as_ref()),
+            Intent::DescribeArray(params) => self.analyze_describe(index, params.as_ref()),
+            Intent::MinArray(params) => {
+                let params = self.analyze_extreme(
+                    index,
+                    "MinArray",
+                    "find the minimum of",
+                    params.as_ref(),
+                )?;
+                Ok(Intent::MinArray(Some(params)))
+            }
+            Intent::MaxArray(params) => {
+                let params = self.analyze_extreme(
+                    index,
+                    "MaxArray",
+                    "find the maximum of",
+                    params.as_ref(),
+                )?;
+                Ok(Intent::MaxArray(Some(params)))
+            }
+            Intent::CountElements(params) => self.analyze_count_elements(index, params.as_ref()),
+            Intent::AverageArray(params) => {
+                let requested = params.as_ref().and_then(|p| p.target_ordinal);
+                let ordinal = self.resolve_array(index, "average", requested)?;
+                Ok(Intent::AverageArray(Some(AverageArrayParams {
+                    target_ordinal: Some(ordinal),
+                })))
+            }
+            Intent::MedianArray(params) => {
+                let requested = params.as_ref().and_then(|p| p.target_ordinal);
+                let ordinal = self.resolve_array(index, "find the median of", requested)?;
+                Ok(Intent::MedianArray(Some(MedianArrayParams {
+                    target_ordinal: Some(ordinal),
+                })))
+            }
+            Intent::Repeat(params) => self.analyze_repeat(index, params),
+            Intent::Conditional(params) => self.analyze_conditional(index, params),
+            Intent::Wait(params) => self.analyze_wait(index, params),
+            Intent::StartTimer(_) => {
+                self.timer_started = true;
+                Ok(Intent::StartTimer(None))
+            }
+            Intent::PrintElapsed(_) if !self.timer_started => {
+                Err(SemanticError::ElapsedBeforeTimer {
+                    intent_index: index,
+                })
+            }
+            Intent::PrintElapsed(_) => Ok(Intent::PrintElapsed(None)),
+            Intent::Exit(params) => self.analyze_exit(index, params.as_ref()),
+            Intent::Note(params) => self.analyze_note(index, params),
+            Intent::SetVariable(params) => self.analyze_set_variable(index, params),
+            Intent::UseProcedure(params) => Err(SemanticError::UnexpandedProcedure {
+                intent_index: index,
+                name: params.name.clone(),
+            }),
+        }
+    }
+
+    /// Resolves the array an operation acts on: the one created with `name`,
+    /// otherwise the one at `ordinal` in creation order, otherwise the most
+    /// recently created one. Returns the resolved ordinal.
+    fn resolve_target(
+        &self,
+        intent_index: usize,
+        operation: &'static str,
+        name: Option<&str>,
+        ordinal: Option<u32>,
+    ) -> Result<u32, SemanticError> {
+        let Some(name) = name else {
+            return self.resolve_array(intent_index, operation, ordinal);
+        };
+        let symbol = self
+            .symbol_table
+            .get(name)
+            .ok_or_else(|| SemanticError::UnknownArray {
+                intent_index,
+                operation,
+                name: name.to_string(),
+            })?;
+        if !symbol.symbol_type.is_array() {
+            return Err(SemanticError::TypeMismatch {
+                intent_index,
+                operation,
+                variable: name.to_string(),
+                found: symbol.symbol_type.clone(),
+            });
+        }
+        self.resolve_array(intent_index, operation, Some(symbol.ordinal))
+    }
+
+    /// A count parameter with constants replaced by their values. A named
+    /// result stays a variable: its value is only known when the program runs.
+    fn resolve_count(
+        &self,
+        intent_index: usize,
+        count: &CountArg,
+    ) -> Result<CountArg, SemanticError> {
+        let name = match count {
+            CountArg::Literal(_) => return Ok(count.clone()),
+            CountArg::Variable(name) => name,
+        };
+        let Some(value) = self.number_value(intent_index, name)? else {
+            return Ok(count.clone());
+        };
+        u32::try_from(value)
+            .map(CountArg::Literal)
+            .map_err(|_| SemanticError::InvalidCount {
+                intent_index,
+                name: name.clone(),
+                value,
+            })
+    }
+
+    /// A number parameter with constants replaced by their values; see
+    /// [`resolve_count`](Self::resolve_count).
+    fn resolve_number(
+        &self,
+        intent_index: usize,
+        number: &NumberArg,
+    ) -> Result<NumberArg, SemanticError> {
+        match number {
+            NumberArg::Literal(_) => Ok(number.clone()),
+            NumberArg::Variable(name) => Ok(self
+                .number_value(intent_index, name)?
+                .map_or_else(|| number.clone(), NumberArg::Literal)),
+        }
+    }
+
+    /// The value of the constant `name`, or `None` if it names a result.
+    fn number_value(&self, intent_index: usize, name: &str) -> Result<Option<i64>, SemanticError> {
+        let Some(symbol) = self.symbol_table.get(name) else {
+            return Err(SemanticError::UndefinedVariable {
+                intent_index,
+                name: name.to_string(),
+                suggestion: self.closest_variable(name),
+            });
+        };
+        if symbol.symbol_type != SymbolType::Integer {
+            return Err(SemanticError::NotANumber {
+                intent_index,
+                name: name.to_string(),
+            });
+        }
+        Ok(symbol.value)
+    }
+
+    /// The set variable whose name is closest to `name`, if one is within
+    /// [`MAX_SUGGESTION_DISTANCE`] edits. Ties go to the alphabetically first.
+    fn closest_variable(&self, name: &str) -> Option<String> {
+        self.symbol_table
+            .names_of(SymbolType::Integer)
+            .map(|candidate| (edit_distance(name, candidate), candidate))
+            .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+            .min()
+            .map(|(_, candidate)| candidate.to_string())
+    }
+
+    /// Resolves the array an operation acts on: the one at `ordinal` in creation
+    /// order, or the most recently created one. Returns the resolved ordinal.
+    fn resolve_array(
+        &self,
+        intent_index: usize,
+        operation: &'static str,
+        ordinal: Option<u32>,
+    ) -> Result<u32, SemanticError> {
+        if self.created_arrays.is_empty() {
+            return Err(SemanticError::UseBeforeCreate {
+                intent_index,
+                operation,
+            });
+        }
+        let available = self.created_arrays.len();
+        let ordinal = ordinal.unwrap_or(available as u32);
+        if ordinal == 0 || ordinal as usize > available {
+            return Err(SemanticError::OrdinalOutOfRange {
+                intent_index,
+                operation,
+                ordinal,
+                available,
+            });
+        }
+
+        let var_name = &self.created_arrays[ordinal as usize - 1];
+        let symbol = self.symbol_table.get(var_name).unwrap();
+        if !symbol.symbol_type.is_array() {
+            return Err(SemanticError::TypeMismatch {
+                intent_index,
+                operation,
+                variable: var_name.clone(),
+                found: symbol.symbol_type.clone(),
+            });
+        }
+
+        Ok(ordinal)
+    }
+
+    /// The element type of the array at `ordinal`, which `resolve_array` has
+    /// already checked.
+    fn element_type(&self, ordinal: u32) -> ElementType {
+        let var_name = &self.created_arrays[ordinal as usize - 1];
+        match self
+            .symbol_table
+            .get(var_name)
+            .map(|symbol| &symbol.symbol_type)
+        {
+            Some(SymbolType::Array(element_type)) => *element_type,
+            _ => ElementType::Float,
+        }
+    }
+
+    fn analyze_create_array(
+        &mut self,
+        index: usize,
+        params: &CreateArrayParams,
+    ) -> Result<Intent, SemanticError> {
+        let size = self.resolve_size(index, params)?;
+        // The runtime refuses a named result too large to allocate.
+        if let CountArg::Literal(size) = size {
+            let bytes = u64::from(size).saturating_mul(ELEMENT_BYTES);
+            if bytes > MAX_ARRAY_BYTES {
+                return Err(SemanticError::ArrayTooLarge {
+                    intent_index: index,
+                    size,
+                    bytes,
+                });
+            }
+        }
+        let name = match &params.name {
+            Some(name) => Some(self.validate_name(index, "CreateArray", name)?),
+            None => None,
+        };
+        let (dtype, element_type) = match &params.dtype {
+            Some(dtype) => {
+                let dtype = self.validate_string(index, "CreateArray", "dtype", dtype)?;
+                let Some(element_type) = ElementType::from_name(&dtype) else {
+                    return Err(SemanticError::UnknownDtype {
+                        intent_index: index,
+                        dtype,
+                    });
+                };
+                (Some(dtype), element_type)
+            }
+            None if params.holds_whole_numbers() => (None, ElementType::Int),
+            None => (None, ElementType::Float),
+        };
+        self.register_array(name.as_deref(), element_type);
+        Ok(Intent::CreateArray(CreateArrayParams {
+            size: Some(size),
+            name,
+            values: params.values.clone(),
+            dtype,
+        }))
+    }
+
+    /// Adds a newly created array to the symbol table, under `name` too if it
+    /// has one.
+    fn register_array(&mut self, name: Option<&str>, element_type: ElementType) {
+        let new_var_name = self.new_variable_name();
+        let ordinal = self.created_arrays.len() as u32 + 1;
+        self.symbol_table.insert(Symbol {
+            name: new_var_name.clone(),
+            symbol_type: SymbolType::Array(element_type),
+            ordinal,
+            value: None,
+        });
+        // A later array with the same name takes it over.
+        if let Some(name) = name {
+            self.symbol_table.insert(Symbol {
+                name: name.to_string(),
+                symbol_type: SymbolType::Array(element_type),
+                ordinal,
+                value: None,
+            });
         }
+        self.created_arrays.push(new_var_name);
+        self.latest_is_matrix = false;
     }
 
-    fn analyze_create_array(&mut self, _params: &CreateArrayParams) -> Result<(), String> {
+    fn analyze_create_matrix(
+        &mut self,
+        index: usize,
+        params: &CreateMatrixParams,
+    ) -> Result<Intent, SemanticError> {
+        let bytes = u64::from(params.rows)
+            .saturating_mul(u64::from(params.cols))
+            .saturating_mul(ELEMENT_BYTES);
+        if bytes > MAX_ARRAY_BYTES {
+            return Err(SemanticError::MatrixTooLarge {
+                intent_index: index,
+                rows: params.rows,
+                cols: params.cols,
+                bytes,
+            });
+        }
         let new_var_name = self.new_variable_name();
-        let symbol = Symbol {
+        self.symbol_table.insert(Symbol {
             name: new_var_name.clone(),
-            symbol_type: SymbolType::Array,
+            symbol_type: SymbolType::Matrix,
+            ordinal: self.created_matrices.len() as u32 + 1,
+            value: None,
+        });
+        self.created_matrices.push(new_var_name);
+        self.latest_is_matrix = true;
+        Ok(Intent::CreateMatrix(params.clone()))
+    }
+
+    /// Resolves both operands of a `ConcatArrays` before registering the
+    /// array it creates, so the new array's name may reuse an operand's.
+    fn analyze_concat(
+        &mut self,
+        index: usize,
+        params: &ConcatParams,
+    ) -> Result<Intent, SemanticError> {
+        let first = self.resolve_target(index, "concatenate", Some(&params.first), None)?;
+        let second = self.resolve_target(index, "concatenate", Some(&params.second), None)?;
+        let element_type = self.element_type(first);
+        if element_type != self.element_type(second) {
+            return Err(SemanticError::MixedElementTypes {
+                intent_index: index,
+                operation: "concatenate",
+                first: element_type,
+                second: self.element_type(second),
+            });
+        }
+        let name = match &params.name {
+            Some(name) => Some(self.validate_name(index, "ConcatArrays", name)?),
+            None => None,
+        };
+        self.register_array(name.as_deref(), element_type);
+        Ok(Intent::ConcatArrays(ConcatParams {
+            first: params.first.clone(),
+            second: params.second.clone(),
+            name,
+            first_ordinal: Some(first),
+            second_ordinal: Some(second),
+        }))
+    }
+
+    /// Resolves the array a `CopyArray` copies before registering the copy,
+    /// which keeps the original's element type.
+    fn analyze_copy(&mut self, index: usize, params: &CopyParams) -> Result<Intent, SemanticError> {
+        let source = self.resolve_target(
+            index,
+            "copy",
+            params.target.as_deref(),
+            params.target_ordinal,
+        )?;
+        let name = match &params.name {
+            Some(name) => Some(self.validate_name(index, "CopyArray", name)?),
+            None => None,
+        };
+        self.register_array(name.as_deref(), self.element_type(source));
+        Ok(Intent::CopyArray(CopyParams {
+            name,
+            target: params.target.clone(),
+            target_ordinal: Some(source),
+        }))
+    }
+
+    /// The number of elements a `CreateArray` creates: its `size`, or how many
+    /// `values` it lists. Both may be given if they agree.
+    fn resolve_size(
+        &self,
+        intent_index: usize,
+        params: &CreateArrayParams,
+    ) -> Result<CountArg, SemanticError> {
+        let size = match &params.size {
+            Some(count) => Some(self.resolve_count(intent_index, count)?),
+            None => None,
+        };
+        if params.values.is_empty() {
+            return size.ok_or(SemanticError::MissingSize { intent_index });
+        }
+        let values = params.values.len();
+        match size {
+            Some(CountArg::Literal(size)) if size as usize != values => {
+                Err(SemanticError::SizeConflict {
+                    intent_index,
+                    size,
+                    values,
+                })
+            }
+            // Too many values to count in a `u32` are far too many to
+            // allocate. A named result is not known yet; the values decide.
+            _ => Ok(CountArg::Literal(u32::try_from(values).unwrap_or(u32::MAX))),
+        }
+    }
+
+    fn analyze_sort_array(
+        &mut self,
+        index: usize,
+        params: &SortArrayParams,
+    ) -> Result<Intent, SemanticError> {
+        let order = match &params.order {
+            Some(order) => self.validate_string(index, "SortArray", "order", order)?,
+            None => self.fill_default(index, "SortArray", "order"),
+        };
+        if !SORT_ORDERS.contains(&order.as_str()) {
+            return Err(SemanticError::UnknownSortOrder {
+                intent_index: index,
+                order,
+            });
+        }
+        if let Some(algorithm) = &params.algorithm
+            && !SORT_ALGORITHMS.contains(&algorithm.as_str())
+        {
+            return Err(SemanticError::UnknownSortAlgorithm {
+                intent_index: index,
+                algorithm: algorithm.clone(),
+            });
+        }
+        let untargeted = params.target.is_none() && params.target_ordinal.is_none();
+        if (untargeted && self.latest_is_matrix)
+            || (self.created_arrays.is_empty() && !self.created_matrices.is_empty())
+        {
+            return Err(SemanticError::SortMatrix {
+                intent_index: index,
+            });
+        }
+        let ordinal = self.resolve_target(
+            index,
+            "sort",
+            params.target.as_deref(),
+            params.target_ordinal,
+        )?;
+        Ok(Intent::SortArray(SortArrayParams {
+            order: Some(order),
+            algorithm: params.algorithm.clone(),
+            target_ordinal: Some(ordinal),
+            target: params.target.clone(),
+        }))
+    }
+
+    fn analyze_print_array(
+        &mut self,
+        index: usize,
+        params: Option<&PrintArrayParams>,
+    ) -> Result<Intent, SemanticError> {
+        let target = params.and_then(|p| p.target.clone());
+        let ordinal = if params.is_some_and(PrintArrayParams::prints_all) {
+            // The ordinal of the last array is the number of arrays printed.
+            self.resolve_array(index, "print", None)?
+        } else {
+            let requested = params.and_then(|p| p.target_ordinal);
+            self.resolve_target(index, "print", target.as_deref(), requested)?
+        };
+        let full = match params.and_then(|p| p.full) {
+            Some(full) => full,
+            None => self.fill_default(index, "PrintArray", "full"),
+        };
+        Ok(Intent::PrintArray(Some(PrintArrayParams {
+            target_ordinal: Some(ordinal),
+            target,
+            full: Some(full),
+        })))
+    }
+
+    fn analyze_histogram(
+        &mut self,
+        index: usize,
+        params: Option<&HistogramParams>,
+    ) -> Result<Intent, SemanticError> {
+        let requested = params.and_then(|p| p.target_ordinal);
+        let ordinal = self.resolve_array(index, "chart", requested)?;
+        let buckets = match params.and_then(|p| p.buckets) {
+            Some(0) => {
+                return Err(SemanticError::ZeroBuckets {
+                    intent_index: index,
+                });
+            }
+            Some(buckets) => buckets,
+            None => self.fill_default(index, "PrintHistogram", "buckets"),
         };
-        self.symbol_table.insert(symbol);
-        self.last_created_variable = Some(new_var_name);
-        Ok(())
+        Ok(Intent::PrintHistogram(Some(HistogramParams {
+            buckets: Some(buckets),
+            target_ordinal: Some(ordinal),
+        })))
+    }
+
+    fn analyze_describe(
+        &mut self,
+        index: usize,
+        params: Option<&DescribeArrayParams>,
+    ) -> Result<Intent, SemanticError> {
+        let target = params.and_then(|p| p.target.as_deref());
+        let requested = params.and_then(|p| p.target_ordinal);
+        let ordinal = self.resolve_target(index, "describe", target, requested)?;
+        Ok(Intent::DescribeArray(Some(DescribeArrayParams {
+            target_ordinal: Some(ordinal),
+            target: self.symbol_table.array_name(ordinal).map(str::to_string),
+        })))
+    }
+
+    fn analyze_sum_array(
+        &mut self,
+        index: usize,
+        params: Option<&SumArrayParams>,
+    ) -> Result<Intent, SemanticError> {
+        let requested = params.and_then(|p| p.target_ordinal);
+        let ordinal = self.resolve_array(index, "sum", requested)?;
+        let name = self.bind_result(index, "SumArray", params.and_then(|p| p.name.as_deref()))?;
+        Ok(Intent::SumArray(Some(SumArrayParams {
+            target_ordinal: Some(ordinal),
+            name,
+        })))
+    }
+
+    fn analyze_reverse_array(
+        &mut self,
+        index: usize,
+        params: Option<&ReverseArrayParams>,
+    ) -> Result<Intent, SemanticError> {
+        let requested = params.and_then(|p| p.target_ordinal);
+        let ordinal = self.resolve_array(index, "reverse", requested)?;
+        Ok(Intent::ReverseArray(Some(ReverseArrayParams {
+            target_ordinal: Some(ordinal),
+        })))
+    }
+
+    fn analyze_count_elements(
+        &mut self,
+        index: usize,
+        params: Option<&CountElementsParams>,
+    ) -> Result<Intent, SemanticError> {
+        let requested = params.and_then(|p| p.target_ordinal);
+        let ordinal = self.resolve_array(index, "count the elements of", requested)?;
+        let name = self.bind_result(
+            index,
+            "CountElements",
+            params.and_then(|p| p.name.as_deref()),
+        )?;
+        Ok(Intent::CountElements(Some(CountElementsParams {
+            target_ordinal: Some(ordinal),
+            name,
+        })))
+    }
+
+    fn analyze_append(
+        &mut self,
+        index: usize,
+        params: &AppendParams,
+    ) -> Result<Intent, SemanticError> {
+        let ordinal = self.resolve_array(index, "append to", params.target_ordinal)?;
+        Ok(Intent::AppendToArray(AppendParams {
+            value: params.value,
+            target_ordinal: Some(ordinal),
+        }))
+    }
+
+    fn analyze_fill(&mut self, index: usize, params: &FillParams) -> Result<Intent, SemanticError> {
+        let ordinal = self.resolve_array(index, "fill", params.target_ordinal)?;
+        Ok(Intent::FillArray(FillParams {
+            value: params.value,
+            target_ordinal: Some(ordinal),
+        }))
+    }
+
+    fn analyze_clear_array(
+        &mut self,
+        index: usize,
+        params: Option<&ClearArrayParams>,
+    ) -> Result<Intent, SemanticError> {
+        let requested = params.and_then(|p| p.target_ordinal);
+        let ordinal = self.resolve_array(index, "empty", requested)?;
+        Ok(Intent::ClearArray(Some(ClearArrayParams {
+            target_ordinal: Some(ordinal),
+        })))
+    }
+
+    fn analyze_slice(
+        &mut self,
+        index: usize,
+        params: &SliceParams,
+    ) -> Result<Intent, SemanticError> {
+        if let Some(end) = params.end {
+            if params.from_end {
+                return Err(SemanticError::EndOfSliceFromEnd {
+                    intent_index: index,
+                    end,
+                });
+            }
+            if params.start > end {
+                return Err(SemanticError::InvalidSlice {
+                    intent_index: index,
+                    start: params.start,
+                    end,
+                });
+            }
+        }
+        let ordinal = self.resolve_array(index, "slice", params.target_ordinal)?;
+        Ok(Intent::SliceArray(SliceParams {
+            start: params.start,
+            end: params.end,
+            from_end: params.from_end,
+            target_ordinal: Some(ordinal),
+        }))
     }
 
-    fn analyze_sort_array(&mut self, _params: &SortArrayParams) -> Result<(), String> {
-        let var_name = self.last_created_variable.as_ref().ok_or_else(|| {
-            "Semantic Error: Attempted to sort, but no array has been created yet.".to_string()
-        })?;
+    fn analyze_map(&mut self, index: usize, params: &MapParams) -> Result<Intent, SemanticError> {
+        if !MAP_OPERATIONS.contains(&params.operation.as_str()) {
+            return Err(SemanticError::UnknownMapOperation {
+                intent_index: index,
+                operation: params.operation.clone(),
+            });
+        }
+        let operand = self.resolve_number(index, &params.operand)?;
+        let ordinal = self.resolve_array(index, "change", params.target_ordinal)?;
+        Ok(Intent::MapArray(MapParams {
+            operation: params.operation.clone(),
+            operand,
+            target_ordinal: Some(ordinal),
+        }))
+    }
 
+    /// Records the array's new element type on its `var_N` symbol, which
+    /// lowering follows. Converting to the type the array already has draws a
+    /// warning and changes nothing.
+    fn analyze_convert(
+        &mut self,
+        index: usize,
+        params: &ConvertParams,
+    ) -> Result<Intent, SemanticError> {
+        let to = self.validate_string(index, "ConvertArray", "to", &params.to)?;
+        let Some(element_type) = ElementType::from_name(&to) else {
+            return Err(SemanticError::UnknownElementType {
+                intent_index: index,
+                to,
+            });
+        };
+        let symbol_type = SymbolType::Array(element_type);
+        if (params.target_ordinal.is_none() && self.latest_is_matrix)
+            || (self.created_arrays.is_empty() && !self.created_matrices.is_empty())
+        {
+            return Err(SemanticError::ConvertMatrix {
+                intent_index: index,
+            });
+        }
+        let ordinal = self.resolve_array(index, "convert", params.target_ordinal)?;
+        let var_name = &self.created_arrays[ordinal as usize - 1];
         let symbol = self.symbol_table.get(var_name).unwrap();
-        if symbol.symbol_type != SymbolType::Array {
-            return Err(format!(
-                "Semantic Error: Attempted to sort '{}', which is not an Array. It has type {:?}.",
-                var_name, symbol.symbol_type
+        if symbol.symbol_type == symbol_type {
+            self.warnings.push(format!(
+                "the {} array already holds {} elements (intent #{}); converting it has no effect",
+                ordinal_name(ordinal),
+                to,
+                index + 1
             ));
+        } else {
+            self.symbol_table.insert(Symbol {
+                symbol_type,
+                ..symbol.clone()
+            });
         }
+        Ok(Intent::ConvertArray(ConvertParams {
+            to,
+            target_ordinal: Some(ordinal),
+        }))
+    }
+
+    /// Resolves the array a `MinArray` or `MaxArray` intent searches.
+    fn analyze_extreme(
+        &mut self,
+        index: usize,
+        intent: &'static str,
+        operation: &'static str,
+        params: Option<&ExtremeParams>,
+    ) -> Result<ExtremeParams, SemanticError> {
+        let requested = params.and_then(|p| p.target_ordinal);
+        let ordinal = self.resolve_array(index, operation, requested)?;
+        let name = self.bind_result(index, intent, params.and_then(|p| p.name.as_deref()))?;
+        Ok(ExtremeParams {
+            target_ordinal: Some(ordinal),
+            name,
+        })
+    }
 
-        Ok(())
+    /// Records the name a `SumArray`, `MinArray`, `MaxArray` or
+    /// `CountElements` gives its result, as an integer whose value is only
+    /// known when the program runs. Like `SetVariable`, naming something
+    /// again draws a warning.
+    fn bind_result(
+        &mut self,
+        index: usize,
+        intent: &'static str,
+        name: Option<&str>,
+    ) -> Result<Option<String>, SemanticError> {
+        let Some(name) = name else {
+            return Ok(None);
+        };
+        let name = self.validate_name(index, intent, name)?;
+        if self.symbol_table.get(&name).is_some() {
+            self.warnings.push(format!(
+                "'{}' is set again (intent #{}); later intents use the new result",
+                name,
+                index + 1
+            ));
+        }
+        self.symbol_table.insert(Symbol {
+            name: name.clone(),
+            symbol_type: SymbolType::Integer,
+            ordinal: 0,
+            value: None,
+        });
+        Ok(Some(name))
     }
 
-    fn analyze_print_array(&mut self) -> Result<(), String> {
-        let var_name = self.last_created_variable.as_ref().ok_or_else(|| {
-            "Semantic Error: Attempted to print, but nothing has been created yet.".to_string()
-        })?;
+    /// Analyzes a `Repeat`'s body in place of the `Repeat` itself: errors in
+    /// the body are reported at `index`.
+    fn analyze_repeat(
+        &mut self,
+        index: usize,
+        params: &RepeatParams,
+    ) -> Result<Intent, SemanticError> {
+        let outer = self.repetitions;
+        let repetitions = outer.saturating_mul(u64::from(params.count));
+        if repetitions > MAX_REPETITIONS {
+            return Err(SemanticError::TooManyRepetitions {
+                intent_index: index,
+                repetitions,
+            });
+        }
+        self.repetitions = repetitions;
+        let timer_started = self.timer_started;
+        let symbol_table = (params.count == 0).then(|| self.symbol_table.clone());
+        let body: Result<Vec<Intent>, SemanticError> = params
+            .body
+            .iter()
+            .map(|intent| match intent {
+                Intent::CreateArray(_)
+                | Intent::ConcatArrays(_)
+                | Intent::CopyArray(_)
+                | Intent::CreateMatrix(_) => Err(SemanticError::CreateInRepeat {
+                    intent_index: index,
+                }),
+                _ => self.analyze_intent(index, intent),
+            })
+            .collect();
+        self.repetitions = outer;
+        // A body that never runs starts no timer, exits nothing and names
+        // no results.
+        if let Some(symbol_table) = symbol_table {
+            self.symbol_table = symbol_table;
+            self.timer_started = timer_started;
+            self.exited_at = None;
+        }
+        Ok(Intent::Repeat(RepeatParams {
+            count: params.count,
+            body: body?,
+        }))
+    }
 
-        let symbol = self.symbol_table.get(var_name).unwrap();
-        if symbol.symbol_type != SymbolType::Array {
-            return Err(format!(
-                "Semantic Error: Attempted to print '{}', which is not an Array. It has type {:?}.",
-                var_name, symbol.symbol_type
+    /// Analyzes both branches of a `Conditional` against the arrays that
+    /// exist before it. Arrays created in a branch are forgotten after it.
+    /// Errors in either branch are reported at `index`.
+    fn analyze_conditional(
+        &mut self,
+        index: usize,
+        params: &ConditionalParams,
+    ) -> Result<Intent, SemanticError> {
+        if !CONDITIONS.contains(&params.condition.as_str()) {
+            return Err(SemanticError::UnknownCondition {
+                intent_index: index,
+                condition: params.condition.clone(),
+            });
+        }
+        let ordinal = self.resolve_array(index, "test", params.target_ordinal)?;
+        let then_body = self.analyze_branch(index, &params.then_body)?;
+        let else_body = self.analyze_branch(index, &params.else_body)?;
+        Ok(Intent::Conditional(ConditionalParams {
+            condition: params.condition.clone(),
+            target_ordinal: Some(ordinal),
+            then_body,
+            else_body,
+        }))
+    }
+
+    /// Analyzes one branch of a `Conditional`, then restores the symbols,
+    /// arrays, matrices and timer to what they were before it. An `Exit` in
+    /// a branch does not end the intents after the `Conditional`.
+    fn analyze_branch(
+        &mut self,
+        index: usize,
+        body: &[Intent],
+    ) -> Result<Vec<Intent>, SemanticError> {
+        let symbol_table = self.symbol_table.clone();
+        let created_arrays = self.created_arrays.len();
+        let created_matrices = self.created_matrices.len();
+        let latest_is_matrix = self.latest_is_matrix;
+        let timer_started = self.timer_started;
+        let body = body
+            .iter()
+            .map(|intent| self.analyze_intent(index, intent))
+            .collect();
+        self.symbol_table = symbol_table;
+        self.created_arrays.truncate(created_arrays);
+        self.created_matrices.truncate(created_matrices);
+        self.latest_is_matrix = latest_is_matrix;
+        self.timer_started = timer_started;
+        self.exited_at = None;
+        body
+    }
+
+    /// Validates the exit code. Intents after the `Exit` draw a warning, not
+    /// an error: they are dead, but harmless.
+    fn analyze_exit(
+        &mut self,
+        index: usize,
+        params: Option<&ExitParams>,
+    ) -> Result<Intent, SemanticError> {
+        let code = match params.and_then(|p| p.code) {
+            Some(code) if !(0..=MAX_EXIT_CODE).contains(&code) => {
+                return Err(SemanticError::InvalidExitCode {
+                    intent_index: index,
+                    code,
+                });
+            }
+            Some(code) => code,
+            None => self.fill_default(index, "Exit", "code"),
+        };
+        self.exited_at = Some(index);
+        Ok(Intent::Exit(Some(ExitParams { code: Some(code) })))
+    }
+
+    fn analyze_wait(&mut self, index: usize, params: &WaitParams) -> Result<Intent, SemanticError> {
+        if params.duration_ms > MAX_WAIT_MS {
+            return Err(SemanticError::WaitTooLong {
+                intent_index: index,
+                duration_ms: params.duration_ms,
+            });
+        }
+        Ok(Intent::Wait(params.clone()))
+    }
+
+    fn analyze_note(&mut self, index: usize, params: &NoteParams) -> Result<Intent, SemanticError> {
+        let text = self.validate_string(index, "Note", "text", &params.text)?;
+        self.warnings
+            .push(format!("note (intent #{}): {}", index + 1, text));
+        Ok(Intent::Note(NoteParams { text }))
+    }
+
+    /// Records a constant. Setting a name again, whether it named a variable
+    /// or an array, draws a warning; later intents see the new value.
+    fn analyze_set_variable(
+        &mut self,
+        index: usize,
+        params: &SetVariableParams,
+    ) -> Result<Intent, SemanticError> {
+        let name = self.validate_name(index, "SetVariable", &params.name)?;
+        if self.symbol_table.get(&name).is_some() {
+            self.warnings.push(format!(
+                "'{}' is set again (intent #{}); later intents use the new value {}",
+                name,
+                index + 1,
+                params.value
             ));
         }
+        self.symbol_table.insert(Symbol {
+            name: name.clone(),
+            symbol_type: SymbolType::Integer,
+            ordinal: 0,
+            value: Some(params.value),
+        });
+        Ok(Intent::SetVariable(SetVariableParams {
+            name,
+            value: params.value,
+        }))
+    }
+
+    /// The registry default of an omitted parameter, recorded so `--explain`
+    /// can tell it apart from a value the model chose.
+    fn fill_default<T: serde::de::DeserializeOwned>(
+        &mut self,
+        intent_index: usize,
+        intent: &'static str,
+        name: &'static str,
+    ) -> T {
+        let value = schema::default_value(intent, name)
+            .unwrap_or_else(|| panic!("{}.{} has no registry default", intent, name));
+        self.defaulted.push(DefaultedParam {
+            intent_index,
+            name,
+            value: match &value {
+                serde_json::Value::String(text) => text.clone(),
+                other => other.to_string(),
+            },
+        });
+        serde_json::from_value(value).unwrap_or_else(|e| {
+            panic!("{}.{} has a mistyped registry default: {}", intent, name, e)
+        })
+    }
 
-        Ok(())
+    /// Checks the name an array or variable is given. Names of the form
+    /// `var_N` would clash with the compiler's own names for arrays, and
+    /// `all` with printing every array.
+    fn validate_name(
+        &self,
+        intent_index: usize,
+        intent: &'static str,
+        name: &str,
+    ) -> Result<String, SemanticError> {
+        let name = self.validate_string(intent_index, intent, "name", name)?;
+        if is_reserved_name(&name) || name == ALL_ARRAYS {
+            return Err(SemanticError::ReservedName { intent_index, name });
+        }
+        Ok(name)
     }
 
-    fn analyze_wait(&mut self, _params: &WaitParams) -> Result<(), String> {
-        Ok(())
+    /// Checks a string parameter from the model and returns it with Windows
+    /// newlines normalized to `\n`. JSON parsing already guarantees valid
+    /// UTF-8; NULs are rejected rather than escaped, because no intent has a
+    /// use for them.
+    fn validate_string(
+        &self,
+        intent_index: usize,
+        intent: &'static str,
+        parameter: &'static str,
+        value: &str,
+    ) -> Result<String, SemanticError> {
+        if value.contains('\0') {
+            return Err(SemanticError::NulInString {
+                intent_index,
+                intent,
+                parameter,
+            });
+        }
+        let normalized = value.replace("\r\n", "\n");
+        if normalized.len() > self.config.max_string_bytes {
+            return Err(SemanticError::StringTooLong {
+                intent_index,
+                intent,
+                parameter,
+                bytes: normalized.len(),
+                limit: self.config.max_string_bytes,
+            });
+        }
+        Ok(normalized)
     }
 }
 
@@ -137,13 +1839,18 @@ mod tests {
         // Arrange
         let intent_graph = vec![
             Intent::CreateArray(CreateArrayParams {
-                size: 5,
-                // The `source` field is removed here
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
             }),
             Intent::SortArray(SortArrayParams {
-                order: "ascending".to_string(),
+                order: Some("ascending".to_string()),
+                algorithm: None,
+                target_ordinal: None,
+                target: None,
             }),
-            Intent::PrintArray,
+            Intent::PrintArray(None),
         ];
         let mut analyzer = SemanticAnalyzer::new();
 
@@ -159,11 +1866,16 @@ mod tests {
         // Arrange
         let intent_graph = vec![
             Intent::SortArray(SortArrayParams {
-                order: "ascending".to_string(),
+                order: Some("ascending".to_string()),
+                algorithm: None,
+                target_ordinal: None,
+                target: None,
             }),
             Intent::CreateArray(CreateArrayParams {
-                size: 5,
-                // The `source` field is removed here
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
             }),
         ];
         let mut analyzer = SemanticAnalyzer::new();
@@ -173,14 +1885,14 @@ mod tests {
 
         // Assert
         assert!(result.is_err());
-        let error_message = result.unwrap_err();
+        let error_message = result.unwrap_err().to_string();
         assert!(error_message.contains("Attempted to sort, but no array has been created yet."));
     }
 
     #[test]
     fn test_analyze_print_before_create() {
         // Arrange
-        let intent_graph = vec![Intent::PrintArray];
+        let intent_graph = vec![Intent::PrintArray(None)];
         let mut analyzer = SemanticAnalyzer::new();
 
         // Act
@@ -188,7 +1900,1361 @@ mod tests {
 
         // Assert
         assert!(result.is_err());
-        let error_message = result.unwrap_err();
+        let error_message = result.unwrap_err().to_string();
         assert!(error_message.contains("Attempted to print, but nothing has been created yet."));
     }
+
+    #[test]
+    fn test_analyze_note_surfaces_warning() {
+        // Arrange
+        let intent_graph = vec![Intent::Note(NoteParams {
+            text: "This program is a demo.".to_string(),
+        })];
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let result = analyzer.analyze(&intent_graph);
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(
+            analyzer.warnings(),
+            ["note (intent #1): This program is a demo."]
+        );
+    }
+
+    fn create(size: u32) -> Intent {
+        Intent::CreateArray(CreateArrayParams {
+            size: Some(CountArg::Literal(size)),
+            name: None,
+            values: Vec::new(),
+            dtype: None,
+        })
+    }
+
+    fn print(target_ordinal: Option<u32>) -> Intent {
+        Intent::PrintArray(Some(PrintArrayParams {
+            target_ordinal,
+            target: None,
+            full: Some(false),
+        }))
+    }
+
+    #[test]
+    fn test_analyze_resolves_ordinal_references() {
+        // Arrange
+        let intent_graph = vec![
+            create(5),
+            create(3),
+            Intent::SortArray(SortArrayParams {
+                order: Some("descending".to_string()),
+                algorithm: None,
+                target_ordinal: Some(1),
+                target: None,
+            }),
+            print(Some(2)),
+            Intent::PrintArray(None),
+        ];
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let validated = analyzer.analyze(&intent_graph).unwrap();
+
+        // Assert
+        let ordinals: Vec<Option<u32>> = validated.iter().map(Intent::target_ordinal).collect();
+        assert_eq!(ordinals, [None, None, Some(1), Some(2), Some(2)]);
+    }
+
+    fn create_named(size: u32, name: &str) -> Intent {
+        Intent::CreateArray(CreateArrayParams {
+            size: Some(CountArg::Literal(size)),
+            name: Some(name.to_string()),
+            values: Vec::new(),
+            dtype: None,
+        })
+    }
+
+    fn sort_named(target: &str) -> Intent {
+        Intent::SortArray(SortArrayParams {
+            order: Some("ascending".to_string()),
+            algorithm: None,
+            target_ordinal: None,
+            target: Some(target.to_string()),
+        })
+    }
+
+    #[test]
+    fn test_analyze_resolves_names_to_their_arrays() {
+        // Arrange
+        let intent_graph = vec![
+            create_named(5, "scores"),
+            create_named(3, "ages"),
+            sort_named("scores"),
+            create_named(4, "scores"),
+            sort_named("scores"),
+        ];
+
+        // Act
+        let validated = SemanticAnalyzer::new().analyze(&intent_graph).unwrap();
+        let unknown = SemanticAnalyzer::new().analyze(&[create(5), sort_named("scores")]);
+        let reserved = SemanticAnalyzer::new().analyze(&[create_named(5, "var_0")]);
+
+        // Assert
+        assert_eq!(validated[2].target_ordinal(), Some(1));
+        assert_eq!(validated[2].target_name(), Some("scores"));
+        assert_eq!(validated[4].target_ordinal(), Some(3));
+        assert_eq!(
+            unknown.unwrap_err().to_string(),
+            "Semantic Error: Attempted to sort 'scores', but no array named 'scores' has been created."
+        );
+        assert!(matches!(
+            reserved,
+            Err(SemanticError::ReservedName {
+                intent_index: 0,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_concat_needs_both_arrays_and_registers_the_result() {
+        let concat = |first: &str, second: &str| {
+            Intent::ConcatArrays(ConcatParams {
+                first: first.to_string(),
+                second: second.to_string(),
+                name: Some("both".to_string()),
+                first_ordinal: None,
+                second_ordinal: None,
+            })
+        };
+
+        // Act
+        let missing = SemanticAnalyzer::new().analyze(&[create_named(5, "a"), concat("a", "b")]);
+        let validated = SemanticAnalyzer::new()
+            .analyze(&[
+                create_named(5, "a"),
+                create_named(3, "b"),
+                concat("b", "a"),
+                sort_named("both"),
+            ])
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            missing.unwrap_err(),
+            SemanticError::UnknownArray {
+                intent_index: 1,
+                operation: "concatenate",
+                name: "b".to_string(),
+            }
+        );
+        let Intent::ConcatArrays(params) = &validated[2] else {
+            panic!("expected ConcatArrays, got {:?}", validated[2]);
+        };
+        assert_eq!(
+            (params.first_ordinal, params.second_ordinal),
+            (Some(2), Some(1))
+        );
+        assert_eq!(validated[3].target_ordinal(), Some(3));
+    }
+
+    #[test]
+    fn test_copy_registers_a_second_array_next_to_the_original() {
+        // Arrange
+        let copy = Intent::CopyArray(CopyParams {
+            name: Some("b".to_string()),
+            target: Some("a".to_string()),
+            target_ordinal: None,
+        });
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let validated = analyzer.analyze(&[create_named(3, "a"), copy]).unwrap();
+
+        // Assert
+        let Intent::CopyArray(params) = &validated[1] else {
+            panic!("expected CopyArray, got {:?}", validated[1]);
+        };
+        assert_eq!(params.target_ordinal, Some(1));
+        assert_eq!(analyzer.symbol_table.array_name(1), Some("a"));
+        assert_eq!(analyzer.symbol_table.array_name(2), Some("b"));
+    }
+
+    #[test]
+    fn test_printing_all_arrays_counts_the_arrays_created_so_far() {
+        // Arrange
+        let print_all = Intent::PrintArray(Some(PrintArrayParams {
+            target: Some(ALL_ARRAYS.to_string()),
+            ..PrintArrayParams::default()
+        }));
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let validated = analyzer
+            .analyze(&[create(3), create(4), print_all.clone(), create(5)])
+            .unwrap();
+        let before_any = SemanticAnalyzer::new().analyze(&[print_all]);
+        let named_all = SemanticAnalyzer::new().analyze(&[create_named(3, ALL_ARRAYS)]);
+
+        // Assert
+        let Intent::PrintArray(Some(params)) = &validated[2] else {
+            panic!("expected PrintArray, got {:?}", validated[2]);
+        };
+        assert!(params.prints_all());
+        assert_eq!(params.target_ordinal, Some(2));
+        assert!(matches!(
+            before_any,
+            Err(SemanticError::UseBeforeCreate {
+                intent_index: 0,
+                ..
+            })
+        ));
+        assert!(matches!(
+            named_all,
+            Err(SemanticError::ReservedName {
+                intent_index: 0,
+                ..
+            })
+        ));
+    }
+
+    fn set(name: &str, value: i64) -> Intent {
+        Intent::SetVariable(SetVariableParams {
+            name: name.to_string(),
+            value,
+        })
+    }
+
+    fn create_sized_by(variable: &str) -> Intent {
+        Intent::CreateArray(CreateArrayParams {
+            size: Some(CountArg::Variable(variable.to_string())),
+            name: None,
+            values: Vec::new(),
+            dtype: None,
+        })
+    }
+
+    #[test]
+    fn test_variables_are_substituted_into_counts() {
+        // Arrange
+        let intent_graph = vec![set("count", 7), create_sized_by("count")];
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let validated = analyzer.analyze(&intent_graph).unwrap();
+
+        // Assert
+        assert!(matches!(
+            &validated[0],
+            Intent::SetVariable(params) if params.name == "count" && params.value == 7
+        ));
+        assert!(matches!(
+            &validated[1],
+            Intent::CreateArray(params) if params.size == Some(CountArg::Literal(7))
+        ));
+        assert!(analyzer.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_setting_a_variable_again_warns_and_uses_the_new_value() {
+        // Arrange
+        let intent_graph = vec![set("count", 7), set("count", 3), create_sized_by("count")];
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let validated = analyzer.analyze(&intent_graph).unwrap();
+
+        // Assert
+        assert_eq!(
+            analyzer.warnings(),
+            ["'count' is set again (intent #2); later intents use the new value 3"]
+        );
+        assert!(matches!(
+            &validated[2],
+            Intent::CreateArray(params) if params.size == Some(CountArg::Literal(3))
+        ));
+    }
+
+    #[test]
+    fn test_undefined_and_mistyped_variables_are_errors() {
+        // Act
+        let misspelt =
+            SemanticAnalyzer::new().analyze(&[set("count", 7), create_sized_by("cuont")]);
+        let unknown = SemanticAnalyzer::new().analyze(&[set("count", 7), create_sized_by("size")]);
+        let array = SemanticAnalyzer::new()
+            .analyze(&[create_named(5, "scores"), create_sized_by("scores")]);
+        let negative =
+            SemanticAnalyzer::new().analyze(&[set("count", -2), create_sized_by("count")]);
+        let sorted =
+            SemanticAnalyzer::new().analyze(&[set("count", 7), create(5), sort_named("count")]);
+
+        // Assert
+        assert_eq!(
+            misspelt.unwrap_err().to_string(),
+            "Semantic Error: No variable named 'cuont' has been set. Did you mean 'count'?"
+        );
+        assert_eq!(
+            unknown.unwrap_err().to_string(),
+            "Semantic Error: No variable named 'size' has been set."
+        );
+        assert!(matches!(
+            array,
+            Err(SemanticError::NotANumber {
+                intent_index: 1,
+                ..
+            })
+        ));
+        assert_eq!(
+            negative.unwrap_err().to_string(),
+            "Semantic Error: 'count' is -2, which cannot be used as a count."
+        );
+        assert!(matches!(
+            sorted,
+            Err(SemanticError::TypeMismatch {
+                found: SymbolType::Integer,
+                ..
+            })
+        ));
+    }
+
+    fn sum_called(name: &str) -> Intent {
+        Intent::SumArray(Some(SumArrayParams {
+            target_ordinal: None,
+            name: Some(name.to_string()),
+        }))
+    }
+
+    #[test]
+    fn test_named_results_stay_variables_that_only_numbers_accept() {
+        // Arrange
+        let scale_by_total = Intent::MapArray(MapParams {
+            operation: "multiply".to_string(),
+            operand: NumberArg::Variable("total".to_string()),
+            target_ordinal: None,
+        });
+        let intent_graph = vec![
+            create(5),
+            sum_called("total"),
+            create_sized_by("total"),
+            scale_by_total,
+        ];
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let validated = analyzer.analyze(&intent_graph).unwrap();
+        let sorted =
+            SemanticAnalyzer::new().analyze(&[create(5), sum_called("total"), sort_named("total")]);
+        let used_before = SemanticAnalyzer::new().analyze(&[
+            create(5),
+            create_sized_by("total"),
+            sum_called("total"),
+        ]);
+        let never_named = SemanticAnalyzer::new().analyze(&[
+            create(5),
+            Intent::Repeat(RepeatParams {
+                count: 0,
+                body: vec![sum_called("total")],
+            }),
+            create_sized_by("total"),
+        ]);
+
+        // Assert
+        assert!(matches!(
+            &validated[2],
+            Intent::CreateArray(params)
+                if params.size == Some(CountArg::Variable("total".to_string()))
+        ));
+        assert!(matches!(
+            &validated[3],
+            Intent::MapArray(params)
+                if params.operand == NumberArg::Variable("total".to_string())
+        ));
+        assert!(analyzer.warnings().is_empty());
+        assert!(matches!(
+            sorted,
+            Err(SemanticError::TypeMismatch {
+                found: SymbolType::Integer,
+                ..
+            })
+        ));
+        assert!(matches!(
+            used_before,
+            Err(SemanticError::UndefinedVariable {
+                intent_index: 1,
+                ..
+            })
+        ));
+        assert!(matches!(
+            never_named,
+            Err(SemanticError::UndefinedVariable {
+                intent_index: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_arrays_from_values_take_their_size_from_them() {
+        let create_from = |values: &[i64], size: Option<u32>| {
+            Intent::CreateArray(CreateArrayParams {
+                size: size.map(CountArg::Literal),
+                name: None,
+                values: values.to_vec(),
+                dtype: None,
+            })
+        };
+
+        // Act
+        let listed = SemanticAnalyzer::new().analyze(&[create_from(&[3, 1, 4], None)]);
+        let agreeing = SemanticAnalyzer::new().analyze(&[create_from(&[3, 1, 4], Some(3))]);
+        let conflicting = SemanticAnalyzer::new().analyze(&[create_from(&[3, 1, 4], Some(5))]);
+        let neither = SemanticAnalyzer::new().analyze(&[create_from(&[], None)]);
+
+        // Assert
+        assert!(matches!(
+            &listed.unwrap()[0],
+            Intent::CreateArray(params)
+                if params.size == Some(CountArg::Literal(3)) && params.values == [3, 1, 4]
+        ));
+        assert!(agreeing.is_ok());
+        assert_eq!(
+            conflicting.unwrap_err().to_string(),
+            "Semantic Error: CreateArray lists 3 values, but its size is 5."
+        );
+        assert!(matches!(
+            neither,
+            Err(SemanticError::MissingSize { intent_index: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_append_needs_an_existing_array() {
+        let append = |target_ordinal| {
+            Intent::AppendToArray(AppendParams {
+                value: 42,
+                target_ordinal,
+            })
+        };
+
+        // Act
+        let before_create = SemanticAnalyzer::new().analyze(&[append(None)]);
+        let resolved = SemanticAnalyzer::new().analyze(&[create(5), create(3), append(None)]);
+
+        // Assert
+        assert!(matches!(
+            before_create,
+            Err(SemanticError::UseBeforeCreate {
+                intent_index: 0,
+                ..
+            })
+        ));
+        assert_eq!(resolved.unwrap()[2].target_ordinal(), Some(2));
+    }
+
+    #[test]
+    fn test_slice_must_not_start_after_it_ends() {
+        let slice = |start, end| {
+            Intent::SliceArray(SliceParams {
+                start,
+                end,
+                from_end: false,
+                target_ordinal: None,
+            })
+        };
+
+        // Act
+        let reversed = SemanticAnalyzer::new().analyze(&[create(5), slice(3, Some(1))]);
+        let before_create = SemanticAnalyzer::new().analyze(&[slice(0, Some(2))]);
+        let open_ended = SemanticAnalyzer::new().analyze(&[create(5), slice(3, None)]);
+
+        // Assert
+        assert_eq!(
+            reversed.unwrap_err(),
+            SemanticError::InvalidSlice {
+                intent_index: 1,
+                start: 3,
+                end: 1,
+            }
+        );
+        assert!(matches!(
+            before_create,
+            Err(SemanticError::UseBeforeCreate { .. })
+        ));
+        assert_eq!(open_ended.unwrap()[1].target_ordinal(), Some(1));
+    }
+
+    #[test]
+    fn test_slice_from_the_end_has_no_end() {
+        let slice = |end| {
+            Intent::SliceArray(SliceParams {
+                start: 2,
+                end,
+                from_end: true,
+                target_ordinal: None,
+            })
+        };
+
+        // Act
+        let with_end = SemanticAnalyzer::new().analyze(&[create(5), slice(Some(4))]);
+        let without_end = SemanticAnalyzer::new().analyze(&[create(5), slice(None)]);
+
+        // Assert
+        let error = with_end.unwrap_err();
+        assert_eq!(
+            error,
+            SemanticError::EndOfSliceFromEnd {
+                intent_index: 1,
+                end: 4,
+            }
+        );
+        assert!(
+            error.to_string().contains("cannot also end at 4"),
+            "{}",
+            error
+        );
+        assert_eq!(without_end.unwrap()[1].target_ordinal(), Some(1));
+    }
+
+    fn convert(to: &str) -> Intent {
+        Intent::ConvertArray(ConvertParams {
+            to: to.to_string(),
+            target_ordinal: None,
+        })
+    }
+
+    #[test]
+    fn test_convert_records_the_element_type_of_the_array() {
+        // Arrange
+        let intent_graph = vec![create(5), create(3), convert("int"), convert("float")];
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        analyzer.analyze(&intent_graph[..3]).unwrap();
+        let after_int = analyzer
+            .symbol_table
+            .get("var_1")
+            .unwrap()
+            .symbol_type
+            .clone();
+        let validated = analyzer.analyze(&intent_graph).unwrap();
+        let after_float = analyzer
+            .symbol_table
+            .get("var_1")
+            .unwrap()
+            .symbol_type
+            .clone();
+
+        // Assert
+        assert_eq!(after_int, SymbolType::Array(ElementType::Int));
+        assert_eq!(after_float, SymbolType::Array(ElementType::Float));
+        assert_eq!(
+            analyzer.symbol_table.get("var_0").unwrap().symbol_type,
+            SymbolType::Array(ElementType::Float)
+        );
+        assert_eq!(validated[2].target_ordinal(), Some(2));
+        assert!(analyzer.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_operations_accept_converted_arrays() {
+        // Arrange
+        let intent_graph = vec![
+            create(5),
+            convert("int"),
+            Intent::SortArray(SortArrayParams {
+                order: None,
+                algorithm: None,
+                target_ordinal: None,
+                target: None,
+            }),
+            Intent::PrintArray(None),
+        ];
+
+        // Act
+        let validated = SemanticAnalyzer::new().analyze(&intent_graph).unwrap();
+
+        // Assert
+        assert_eq!(validated[2].target_ordinal(), Some(1));
+        assert_eq!(validated[3].target_ordinal(), Some(1));
+    }
+
+    #[test]
+    fn test_converting_to_the_same_type_warns_and_unknown_types_fail() {
+        // Arrange
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let same = analyzer.analyze(&[create(5), convert("float")]);
+        let warnings = analyzer.warnings().to_vec();
+        let unknown = SemanticAnalyzer::new().analyze(&[create(5), convert("string")]);
+
+        // Assert
+        assert!(same.is_ok());
+        assert_eq!(
+            warnings,
+            ["the 1st array already holds float elements (intent #2); converting it has no effect"]
+        );
+        let error = unknown.unwrap_err();
+        assert_eq!(
+            error,
+            SemanticError::UnknownElementType {
+                intent_index: 1,
+                to: "string".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Semantic Error: ConvertArray cannot convert to 'string'. Expected one of: float, int."
+        );
+    }
+
+    #[test]
+    fn test_dtype_sets_the_element_type_and_unknown_dtypes_fail() {
+        // Arrange
+        let create_typed = |dtype: &str| {
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(4)),
+                name: None,
+                values: Vec::new(),
+                dtype: Some(dtype.to_string()),
+            })
+        };
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        analyzer.analyze(&[create_typed("int"), create(3)]).unwrap();
+        let unknown = SemanticAnalyzer::new().analyze(&[create_typed("complex")]);
+
+        // Assert
+        assert_eq!(
+            analyzer.symbol_table.get("var_0").unwrap().symbol_type,
+            SymbolType::Array(ElementType::Int)
+        );
+        assert_eq!(
+            analyzer.symbol_table.get("var_1").unwrap().symbol_type,
+            SymbolType::Array(ElementType::Float)
+        );
+        let error = unknown.unwrap_err();
+        assert_eq!(
+            error,
+            SemanticError::UnknownDtype {
+                intent_index: 0,
+                dtype: "complex".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Semantic Error: CreateArray cannot create an array of 'complex' elements. Expected one of: float, int."
+        );
+    }
+
+    #[test]
+    fn test_listed_values_are_whole_numbers_unless_typed_float() {
+        // Arrange
+        let listed = |dtype: Option<&str>| {
+            Intent::CreateArray(CreateArrayParams {
+                size: None,
+                name: None,
+                values: vec![3, 1],
+                dtype: dtype.map(str::to_string),
+            })
+        };
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        analyzer
+            .analyze(&[listed(None), listed(Some("float"))])
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            analyzer.symbol_table.get("var_0").unwrap().symbol_type,
+            SymbolType::Array(ElementType::Int)
+        );
+        assert_eq!(
+            analyzer.symbol_table.get("var_1").unwrap().symbol_type,
+            SymbolType::Array(ElementType::Float)
+        );
+    }
+
+    #[test]
+    fn test_concatenating_arrays_of_different_element_types_fails() {
+        // Arrange
+        let intent_graph = vec![
+            create_named(3, "a"),
+            create_named(3, "b"),
+            Intent::ConvertArray(ConvertParams {
+                to: "int".to_string(),
+                target_ordinal: Some(2),
+            }),
+            Intent::ConcatArrays(ConcatParams {
+                first: "a".to_string(),
+                second: "b".to_string(),
+                name: None,
+                first_ordinal: None,
+                second_ordinal: None,
+            }),
+        ];
+
+        // Act
+        let error = SemanticAnalyzer::new().analyze(&intent_graph).unwrap_err();
+
+        // Assert
+        assert_eq!(
+            error,
+            SemanticError::MixedElementTypes {
+                intent_index: 3,
+                operation: "concatenate",
+                first: ElementType::Float,
+                second: ElementType::Int,
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Semantic Error: Cannot concatenate an array of float elements with an array of int elements. Convert one of them with ConvertArray first."
+        );
+    }
+
+    fn create_matrix(rows: u32, cols: u32) -> Intent {
+        Intent::CreateMatrix(CreateMatrixParams { rows, cols })
+    }
+
+    #[test]
+    fn test_matrices_are_not_arrays() {
+        // Arrange
+        let sort = Intent::SortArray(SortArrayParams {
+            order: None,
+            algorithm: None,
+            target_ordinal: None,
+            target: None,
+        });
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let printed = analyzer.analyze(&[create_matrix(2, 3), Intent::PrintMatrix(None)]);
+        let matrix_type = analyzer
+            .symbol_table
+            .get("var_0")
+            .unwrap()
+            .symbol_type
+            .clone();
+        let sorted_matrix = SemanticAnalyzer::new().analyze(&[create_matrix(2, 3), sort.clone()]);
+        let sorted_array =
+            SemanticAnalyzer::new().analyze(&[create_matrix(2, 3), create(4), sort.clone()]);
+        let sorted_after =
+            SemanticAnalyzer::new().analyze(&[create(4), create_matrix(2, 3), sort.clone()]);
+        let printed_early = SemanticAnalyzer::new().analyze(&[Intent::PrintMatrix(None)]);
+
+        // Assert
+        assert!(printed.is_ok());
+        assert_eq!(matrix_type, SymbolType::Matrix);
+        let error = sorted_matrix.unwrap_err();
+        assert_eq!(error, SemanticError::SortMatrix { intent_index: 1 });
+        assert_eq!(
+            error.to_string(),
+            "Semantic Error: SortArray cannot sort a matrix. Flatten the matrix into an array first, then sort the array."
+        );
+        assert_eq!(sorted_array.unwrap()[2].target_ordinal(), Some(1));
+        assert_eq!(
+            sorted_after.unwrap_err(),
+            SemanticError::SortMatrix { intent_index: 2 }
+        );
+        assert_eq!(
+            printed_early.unwrap_err(),
+            SemanticError::MatrixBeforeCreate { intent_index: 0 }
+        );
+    }
+
+    #[test]
+    fn test_converting_a_matrix_is_an_error() {
+        // Arrange
+        let convert = |target_ordinal| {
+            Intent::ConvertArray(ConvertParams {
+                to: "int".to_string(),
+                target_ordinal,
+            })
+        };
+
+        // Act
+        let only_matrix = SemanticAnalyzer::new().analyze(&[create_matrix(2, 3), convert(None)]);
+        let latest_matrix =
+            SemanticAnalyzer::new().analyze(&[create(4), create_matrix(2, 3), convert(None)]);
+        let array_by_ordinal =
+            SemanticAnalyzer::new().analyze(&[create(4), create_matrix(2, 3), convert(Some(1))]);
+
+        // Assert
+        let error = only_matrix.unwrap_err();
+        assert_eq!(error, SemanticError::ConvertMatrix { intent_index: 1 });
+        assert_eq!(
+            error.to_string(),
+            "Semantic Error: ConvertArray cannot convert a matrix. Flatten the matrix into an array first, then convert the array."
+        );
+        assert_eq!(
+            latest_matrix.unwrap_err(),
+            SemanticError::ConvertMatrix { intent_index: 2 }
+        );
+        assert_eq!(array_by_ordinal.unwrap()[2].target_ordinal(), Some(1));
+    }
+
+    #[test]
+    fn test_histograms_need_an_array_and_at_least_one_bucket() {
+        // Arrange
+        let histogram = |buckets| {
+            Intent::PrintHistogram(Some(HistogramParams {
+                buckets,
+                target_ordinal: None,
+            }))
+        };
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let defaulted = analyzer.analyze(&[create(4), histogram(None)]);
+        let zero = SemanticAnalyzer::new().analyze(&[create(4), histogram(Some(0))]);
+        let no_array = SemanticAnalyzer::new().analyze(&[histogram(Some(5))]);
+
+        // Assert
+        assert!(matches!(
+            &defaulted.unwrap()[1],
+            Intent::PrintHistogram(Some(p)) if p.buckets == Some(10) && p.target_ordinal == Some(1)
+        ));
+        assert_eq!(analyzer.defaulted()[0].name, "buckets");
+        let error = zero.unwrap_err();
+        assert_eq!(error, SemanticError::ZeroBuckets { intent_index: 1 });
+        assert_eq!(
+            error.to_string(),
+            "Semantic Error: A histogram needs at least one bucket."
+        );
+        assert!(matches!(
+            no_array.unwrap_err(),
+            SemanticError::UseBeforeCreate { .. }
+        ));
+    }
+
+    #[test]
+    fn test_describe_array_resolves_the_name_it_prints() {
+        // Arrange
+        let named = Intent::CreateArray(CreateArrayParams {
+            size: Some(CountArg::Literal(5)),
+            name: Some("scores".to_string()),
+            values: Vec::new(),
+            dtype: None,
+        });
+        let by_name = Intent::DescribeArray(Some(DescribeArrayParams {
+            target_ordinal: None,
+            target: Some("scores".to_string()),
+        }));
+
+        // Act
+        let validated = SemanticAnalyzer::new()
+            .analyze(&[named, create(3), by_name, Intent::DescribeArray(None)])
+            .unwrap();
+        let no_array = SemanticAnalyzer::new().analyze(&[Intent::DescribeArray(None)]);
+
+        // Assert
+        assert!(matches!(
+            &validated[2],
+            Intent::DescribeArray(Some(p))
+                if p.target_ordinal == Some(1) && p.target.as_deref() == Some("scores")
+        ));
+        assert!(matches!(
+            &validated[3],
+            Intent::DescribeArray(Some(p)) if p.target_ordinal == Some(2) && p.target.is_none()
+        ));
+        assert!(matches!(
+            no_array.unwrap_err(),
+            SemanticError::UseBeforeCreate { .. }
+        ));
+    }
+
+    #[test]
+    fn test_intents_after_an_exit_draw_a_warning() {
+        // Arrange
+        let exit = |code| Intent::Exit(Some(ExitParams { code }));
+        let in_branch = Intent::Conditional(ConditionalParams {
+            condition: "is_sorted".to_string(),
+            target_ordinal: None,
+            then_body: vec![exit(Some(1))],
+            else_body: Vec::new(),
+        });
+        let mut analyzer = SemanticAnalyzer::new();
+        let mut branch_analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let validated = analyzer
+            .analyze(&[create(3), exit(None), print(None)])
+            .unwrap();
+        branch_analyzer
+            .analyze(&[create(3), in_branch, print(None)])
+            .unwrap();
+        let out_of_range = SemanticAnalyzer::new().analyze(&[exit(Some(256))]);
+
+        // Assert
+        assert!(matches!(&validated[1], Intent::Exit(Some(p)) if p.code == Some(0)));
+        assert_eq!(analyzer.defaulted()[0].name, "code");
+        assert_eq!(
+            analyzer.warnings(),
+            ["intent #2 exits the program; the intents after it never run"]
+        );
+        assert!(branch_analyzer.warnings().is_empty());
+        let error = out_of_range.unwrap_err();
+        assert_eq!(
+            error,
+            SemanticError::InvalidExitCode {
+                intent_index: 0,
+                code: 256
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Semantic Error: Exit code 256 is out of range; exit codes run from 0 to 255."
+        );
+    }
+
+    #[test]
+    fn test_analyze_rejects_matrices_beyond_the_memory_limit() {
+        // Act
+        let error = SemanticAnalyzer::new()
+            .analyze(&[create_matrix(100_000, 100_000)])
+            .unwrap_err();
+
+        // Assert
+        assert_eq!(
+            error,
+            SemanticError::MatrixTooLarge {
+                intent_index: 0,
+                rows: 100_000,
+                cols: 100_000,
+                bytes: 80_000_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_print_elapsed_needs_a_timer_started_on_every_path() {
+        // Arrange
+        let conditional_timer = Intent::Conditional(ConditionalParams {
+            condition: "is_sorted".to_string(),
+            target_ordinal: None,
+            then_body: vec![Intent::StartTimer(None)],
+            else_body: Vec::new(),
+        });
+
+        // Act
+        let timed = SemanticAnalyzer::new().analyze(&[
+            Intent::StartTimer(None),
+            create(5),
+            Intent::PrintElapsed(None),
+        ]);
+        let untimed = SemanticAnalyzer::new().analyze(&[create(5), Intent::PrintElapsed(None)]);
+        let in_branch = SemanticAnalyzer::new().analyze(&[
+            create(5),
+            conditional_timer,
+            Intent::PrintElapsed(None),
+        ]);
+
+        // Assert
+        assert_eq!(timed.unwrap().len(), 3);
+        let error = untimed.unwrap_err();
+        assert_eq!(error, SemanticError::ElapsedBeforeTimer { intent_index: 1 });
+        assert_eq!(
+            error.to_string(),
+            "Semantic Error: PrintElapsed needs a StartTimer before it."
+        );
+        assert_eq!(in_branch.unwrap_err().intent_index(), 2);
+    }
+
+    #[test]
+    fn test_map_rejects_unsupported_operations() {
+        // Arrange
+        let map = |operation: &str| {
+            Intent::MapArray(MapParams {
+                operation: operation.to_string(),
+                operand: NumberArg::Literal(3),
+                target_ordinal: None,
+            })
+        };
+
+        // Act
+        let unsupported = SemanticAnalyzer::new().analyze(&[create(5), map("modulo")]);
+        let multiply = SemanticAnalyzer::new().analyze(&[create(5), map("multiply")]);
+
+        // Assert
+        let error = unsupported.unwrap_err();
+        assert_eq!(
+            error,
+            SemanticError::UnknownMapOperation {
+                intent_index: 1,
+                operation: "modulo".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Semantic Error: MapArray does not support the operation 'modulo'. Expected one of: add, subtract, multiply, divide."
+        );
+        assert_eq!(multiply.unwrap()[1].target_ordinal(), Some(1));
+    }
+
+    #[test]
+    fn test_sort_rejects_unknown_orders() {
+        // Arrange
+        let sideways = Intent::SortArray(SortArrayParams {
+            order: Some("sideways".to_string()),
+            algorithm: None,
+            target_ordinal: None,
+            target: None,
+        });
+
+        // Act
+        let error = SemanticAnalyzer::new()
+            .analyze(&[create(5), sideways])
+            .unwrap_err();
+
+        // Assert
+        assert_eq!(
+            error.to_string(),
+            "Semantic Error: SortArray cannot sort in 'sideways' order. Expected one of: ascending, descending."
+        );
+    }
+
+    #[test]
+    fn test_sort_rejects_unknown_algorithms() {
+        // Arrange
+        let sort = |algorithm: &str| {
+            Intent::SortArray(SortArrayParams {
+                order: None,
+                algorithm: Some(algorithm.to_string()),
+                target_ordinal: None,
+                target: None,
+            })
+        };
+
+        // Act
+        let unknown = SemanticAnalyzer::new().analyze(&[create(5), sort("bogo")]);
+        let bubble = SemanticAnalyzer::new().analyze(&[create(5), sort("bubble")]);
+
+        // Assert
+        let error = unknown.unwrap_err();
+        assert_eq!(
+            error,
+            SemanticError::UnknownSortAlgorithm {
+                intent_index: 1,
+                algorithm: "bogo".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Semantic Error: SortArray has no 'bogo' algorithm. Expected one of: quick, merge, bubble."
+        );
+        let Intent::SortArray(params) = &bubble.unwrap()[1] else {
+            panic!("Expected a SortArray");
+        };
+        assert_eq!(params.algorithm.as_deref(), Some("bubble"));
+    }
+
+    #[test]
+    fn test_analyze_starts_fresh_while_extend_continues() {
+        // Arrange
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        analyzer.analyze(&[create(5)]).unwrap();
+        analyzer.analyze(&[create(3)]).unwrap();
+        let fresh_arrays = analyzer.created_arrays.clone();
+        let extended = analyzer.extend(&[create(4), print(Some(1))]).unwrap();
+        let isolated = analyzer.analyze(&[print(Some(2))]);
+
+        // Assert
+        assert_eq!(fresh_arrays, ["var_0"]);
+        assert_eq!(analyzer.created_arrays, Vec::<String>::new());
+        assert_eq!(extended[1].target_ordinal(), Some(1));
+        assert!(matches!(
+            isolated,
+            Err(SemanticError::UseBeforeCreate { .. })
+        ));
+    }
+
+    #[test]
+    fn test_analyze_ordinal_out_of_range_reports_count() {
+        // Arrange
+        let intent_graph = vec![create(5), create(3), print(Some(3))];
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let error = analyzer.analyze(&intent_graph).unwrap_err();
+
+        // Assert
+        assert_eq!(error.intent_index(), 2);
+        assert_eq!(
+            error.to_string(),
+            "Semantic Error: Attempted to print the 3rd array, but there are only 2 arrays."
+        );
+    }
+
+    #[test]
+    fn test_analyze_rejects_arrays_beyond_the_memory_limit() {
+        // Arrange
+        let largest = (MAX_ARRAY_BYTES / ELEMENT_BYTES) as u32;
+
+        // Act
+        let at_limit = SemanticAnalyzer::new().analyze(&[create(largest)]);
+        let above_limit = SemanticAnalyzer::new().analyze(&[create(largest + 1)]);
+        let max = SemanticAnalyzer::new().analyze(&[create(u32::MAX)]);
+
+        // Assert
+        assert!(at_limit.is_ok());
+        assert!(matches!(
+            above_limit,
+            Err(SemanticError::ArrayTooLarge { .. })
+        ));
+        assert_eq!(
+            max.unwrap_err().to_string(),
+            "Semantic Error: An array of 4294967295 elements would require 32 GiB, but arrays are limited to 4 GiB."
+        );
+    }
+
+    #[test]
+    fn test_analyze_rejects_waits_beyond_the_limit() {
+        let wait = |duration_ms| vec![Intent::Wait(WaitParams { duration_ms })];
+
+        assert!(SemanticAnalyzer::new().analyze(&wait(MAX_WAIT_MS)).is_ok());
+        assert!(matches!(
+            SemanticAnalyzer::new().analyze(&wait(MAX_WAIT_MS + 1)),
+            Err(SemanticError::WaitTooLong {
+                intent_index: 0,
+                ..
+            })
+        ));
+        assert!(SemanticAnalyzer::new().analyze(&wait(u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn test_analyze_repeat_checks_its_body() {
+        // Arrange
+        let create = || {
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            })
+        };
+        let repeat = |count, body| Intent::Repeat(RepeatParams { count, body });
+        let nested = repeat(10, vec![repeat(200, vec![Intent::PrintArray(None)])]);
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let validated = analyzer.analyze(&[create(), repeat(3, vec![Intent::PrintArray(None)])]);
+        let creates = analyzer.analyze(&[create(), repeat(2, vec![create()])]);
+        let too_many = analyzer.analyze(&[create(), nested]);
+
+        // Assert
+        let validated = validated.unwrap();
+        let Intent::Repeat(params) = &validated[1] else {
+            panic!("expected a Repeat, got {:?}", validated[1]);
+        };
+        assert_eq!(params.count, 3);
+        assert_eq!(params.body[0].target_ordinal(), Some(1));
+        assert_eq!(
+            creates.unwrap_err(),
+            SemanticError::CreateInRepeat { intent_index: 1 }
+        );
+        assert_eq!(
+            too_many.unwrap_err(),
+            SemanticError::TooManyRepetitions {
+                intent_index: 1,
+                repetitions: 2000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_analyze_conditional_scopes_arrays_to_their_branch() {
+        // Arrange
+        let create = || {
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            })
+        };
+        let conditional = |condition: &str, then_body| {
+            Intent::Conditional(ConditionalParams {
+                condition: condition.to_string(),
+                target_ordinal: None,
+                then_body,
+                else_body: vec![Intent::PrintArray(None)],
+            })
+        };
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let validated = analyzer.analyze(&[
+            create(),
+            conditional("is_sorted", vec![create(), Intent::PrintArray(None)]),
+        ]);
+        let escaped = analyzer.analyze(&[
+            create(),
+            conditional("is_empty", vec![create()]),
+            Intent::PrintArray(Some(PrintArrayParams {
+                target_ordinal: Some(2),
+                target: None,
+                full: None,
+            })),
+        ]);
+        let unknown = analyzer.analyze(&[create(), conditional("is_big", vec![])]);
+
+        // Assert
+        let validated = validated.unwrap();
+        let Intent::Conditional(params) = &validated[1] else {
+            panic!("expected a Conditional, got {:?}", validated[1]);
+        };
+        assert_eq!(params.target_ordinal, Some(1));
+        assert_eq!(params.then_body[1].target_ordinal(), Some(2));
+        assert_eq!(params.else_body[0].target_ordinal(), Some(1));
+        assert!(matches!(
+            escaped,
+            Err(SemanticError::OrdinalOutOfRange {
+                intent_index: 2,
+                available: 1,
+                ..
+            })
+        ));
+        assert_eq!(
+            unknown.unwrap_err().to_string(),
+            "Semantic Error: Unknown condition 'is_big'. Expected one of: is_sorted, is_empty."
+        );
+    }
+
+    fn note(text: &str) -> Vec<Intent> {
+        vec![Intent::Note(NoteParams {
+            text: text.to_string(),
+        })]
+    }
+
+    #[test]
+    fn test_analyze_rejects_strings_beyond_the_limit() {
+        // Arrange
+        let huge = "x".repeat(10 << 20);
+
+        // Act
+        let at_limit = SemanticAnalyzer::new().analyze(&note(&"x".repeat(MAX_STRING_BYTES)));
+        let error = SemanticAnalyzer::new().analyze(&note(&huge)).unwrap_err();
+        let relaxed = SemanticAnalyzer::new()
+            .with_max_string_bytes(usize::MAX)
+            .analyze(&note(&huge));
+
+        // Assert
+        assert!(at_limit.is_ok());
+        assert_eq!(
+            error.to_string(),
+            "Semantic Error: The 'text' parameter of Note is 10 MiB long, but string parameters are limited to 4 KiB."
+        );
+        assert!(relaxed.is_ok());
+    }
+
+    #[test]
+    fn test_analyze_fills_omitted_parameters_from_the_registry() {
+        // Arrange
+        let intent_graph = vec![
+            create(3),
+            Intent::SortArray(SortArrayParams {
+                order: None,
+                algorithm: None,
+                target_ordinal: None,
+                target: None,
+            }),
+            Intent::SortArray(SortArrayParams {
+                order: Some("descending".to_string()),
+                algorithm: None,
+                target_ordinal: None,
+                target: None,
+            }),
+            Intent::PrintArray(None),
+        ];
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let validated = analyzer.analyze(&intent_graph).unwrap();
+
+        // Assert
+        assert!(
+            matches!(&validated[1], Intent::SortArray(p) if p.order.as_deref() == Some("ascending"))
+        );
+        assert!(matches!(&validated[3], Intent::PrintArray(Some(p)) if p.full == Some(false)));
+        assert_eq!(
+            analyzer.defaulted(),
+            [
+                DefaultedParam {
+                    intent_index: 1,
+                    name: "order",
+                    value: "ascending".to_string(),
+                },
+                DefaultedParam {
+                    intent_index: 3,
+                    name: "full",
+                    value: "false".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_analyze_rejects_nul_characters() {
+        // Arrange
+        let order = vec![
+            create(3),
+            Intent::SortArray(SortArrayParams {
+                order: Some("a\u{0}b".to_string()),
+                algorithm: None,
+                target_ordinal: None,
+                target: None,
+            }),
+        ];
+
+        // Act
+        let error = SemanticAnalyzer::new().analyze(&order).unwrap_err();
+
+        // Assert
+        assert_eq!(error.intent_index(), 1);
+        assert_eq!(
+            error.to_string(),
+            "Semantic Error: The 'order' parameter of SortArray contains a NUL character."
+        );
+        assert!(SemanticAnalyzer::new().analyze(&note("a\u{0}b")).is_err());
+    }
+
+    #[test]
+    fn test_analyze_normalizes_windows_newlines() {
+        // Arrange
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Act
+        let validated = analyzer.analyze(&note("first\r\nsecond\r\n")).unwrap();
+
+        // Assert
+        assert!(matches!(&validated[0], Intent::Note(params) if params.text == "first\nsecond\n"));
+        assert_eq!(analyzer.warnings(), ["note (intent #1): first\nsecond\n"]);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 bytes");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(u64::from(u32::MAX) * 8), "32 GiB");
+    }
+
+    #[test]
+    fn test_ordinal_name() {
+        let names: Vec<String> = [1, 2, 3, 4, 11, 12, 13, 21, 102]
+            .into_iter()
+            .map(ordinal_name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "102nd"
+            ]
+        );
+    }
 }
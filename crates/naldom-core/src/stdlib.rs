@@ -0,0 +1,384 @@
+// crates/naldom-core/src/stdlib.rs
+
+//! The standard library of named procedures.
+//!
+//! A procedure is a reusable sequence of intents the model can request with a
+//! single `UseProcedure` intent instead of spelling the sequence out. Templates
+//! are intent JSON with `{param}` placeholders; expansion substitutes the
+//! arguments (or defaults) and parses the result like any LLM response.
+
+use naldom_ir::{Intent, ProcedureArg, UseProcedureParams};
+use std::fmt;
+
+/// A parameter of a procedure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcedureParam {
+    pub name: &'static str,
+    /// `"u32"` or `"String"`, as in the intent schema registry.
+    pub ty: &'static str,
+    /// The value used when the model omits the argument, as a JSON literal.
+    pub default: &'static str,
+}
+
+/// A named procedure and the intents it expands to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Procedure {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub params: &'static [ProcedureParam],
+    /// A JSON intent array with `{param}` placeholders.
+    pub template: &'static str,
+}
+
+/// Every procedure in the standard library.
+pub const PROCEDURES: &[Procedure] = &[
+    Procedure {
+        name: "benchmark_sort",
+        description: "Creates a large array and sorts it; compile with --profile to time the sort.",
+        params: &[
+            ProcedureParam {
+                name: "size",
+                ty: "u32",
+                default: "100000",
+            },
+            ProcedureParam {
+                name: "order",
+                ty: "String",
+                default: "\"ascending\"",
+            },
+        ],
+        template: r#"[
+            {"intent": "CreateArray", "parameters": {"size": {size}}},
+            {"intent": "SortArray", "parameters": {"order": {order}}}
+        ]"#,
+    },
+    Procedure {
+        name: "show_sorted",
+        description: "Creates an array and prints it before and after sorting.",
+        params: &[
+            ProcedureParam {
+                name: "size",
+                ty: "u32",
+                default: "10",
+            },
+            ProcedureParam {
+                name: "order",
+                ty: "String",
+                default: "\"ascending\"",
+            },
+        ],
+        template: r#"[
+            {"intent": "CreateArray", "parameters": {"size": {size}}},
+            {"intent": "PrintArray"},
+            {"intent": "SortArray", "parameters": {"order": {order}}},
+            {"intent": "PrintArray"}
+        ]"#,
+    },
+];
+
+/// Looks up a procedure by name.
+pub fn find_procedure(name: &str) -> Option<&'static Procedure> {
+    PROCEDURES.iter().find(|procedure| procedure.name == name)
+}
+
+/// An error raised while expanding a `UseProcedure` intent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProcedureError {
+    /// The named procedure does not exist.
+    UnknownProcedure { intent_index: usize, name: String },
+    /// An argument does not match any parameter of the procedure.
+    UnknownArgument {
+        intent_index: usize,
+        procedure: &'static str,
+        argument: String,
+    },
+    /// An argument has the wrong type for its parameter.
+    InvalidArgument {
+        intent_index: usize,
+        procedure: &'static str,
+        argument: String,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for ProcedureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcedureError::UnknownProcedure { intent_index, name } => {
+                let available: Vec<&str> = PROCEDURES.iter().map(|p| p.name).collect();
+                write!(
+                    f,
+                    "Procedure Error: intent #{} uses unknown procedure '{}'. Available procedures: {}.",
+                    intent_index + 1,
+                    name,
+                    available.join(", ")
+                )
+            }
+            ProcedureError::UnknownArgument {
+                intent_index,
+                procedure,
+                argument,
+            } => write!(
+                f,
+                "Procedure Error: intent #{} passes unknown argument '{}' to procedure '{}'.",
+                intent_index + 1,
+                argument,
+                procedure
+            ),
+            ProcedureError::InvalidArgument {
+                intent_index,
+                procedure,
+                argument,
+                expected,
+            } => write!(
+                f,
+                "Procedure Error: intent #{} passes argument '{}' to procedure '{}', which expects a {}.",
+                intent_index + 1,
+                argument,
+                procedure,
+                expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProcedureError {}
+
+/// Where an intent of an expanded graph came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Provenance {
+    /// The index of the originating intent in the unexpanded graph.
+    pub source_index: usize,
+    /// The procedure that produced the intent, if any.
+    pub procedure: Option<&'static str>,
+}
+
+/// An IntentGraph with every `UseProcedure` replaced by its intents.
+#[derive(Debug, Clone)]
+pub struct Expansion {
+    pub intents: Vec<Intent>,
+    /// One entry per intent in `intents`.
+    pub provenance: Vec<Provenance>,
+}
+
+impl Expansion {
+    /// Explains where intent `index` came from, if it was produced by a procedure.
+    pub fn describe(&self, index: usize) -> Option<String> {
+        let provenance = self.provenance.get(index)?;
+        let procedure = provenance.procedure?;
+        Some(format!(
+            "intent #{} was expanded from procedure '{}' (intent #{})",
+            index + 1,
+            procedure,
+            provenance.source_index + 1
+        ))
+    }
+}
+
+/// Expands every `UseProcedure` intent in `intent_graph`.
+pub fn expand(intent_graph: &[Intent]) -> Result<Expansion, ProcedureError> {
+    let mut expansion = Expansion {
+        intents: Vec::new(),
+        provenance: Vec::new(),
+    };
+    for (index, intent) in intent_graph.iter().enumerate() {
+        match intent {
+            Intent::UseProcedure(params) => {
+                let (procedure, intents) = expand_procedure(index, params)?;
+                expansion
+                    .provenance
+                    .extend(intents.iter().map(|_| Provenance {
+                        source_index: index,
+                        procedure: Some(procedure.name),
+                    }));
+                expansion.intents.extend(intents);
+            }
+            other => {
+                expansion.provenance.push(Provenance {
+                    source_index: index,
+                    procedure: None,
+                });
+                expansion.intents.push(other.clone());
+            }
+        }
+    }
+    Ok(expansion)
+}
+
+fn expand_procedure(
+    intent_index: usize,
+    params: &UseProcedureParams,
+) -> Result<(&'static Procedure, Vec<Intent>), ProcedureError> {
+    let procedure =
+        find_procedure(&params.name).ok_or_else(|| ProcedureError::UnknownProcedure {
+            intent_index,
+            name: params.name.clone(),
+        })?;
+
+    if let Some(argument) = params
+        .arguments
+        .keys()
+        .find(|argument| !procedure.params.iter().any(|p| p.name == *argument))
+    {
+        return Err(ProcedureError::UnknownArgument {
+            intent_index,
+            procedure: procedure.name,
+            argument: argument.clone(),
+        });
+    }
+
+    let mut json = procedure.template.to_string();
+    for param in procedure.params {
+        let value = match params.arguments.get(param.name) {
+            Some(argument) => argument_literal(param, argument).ok_or_else(|| {
+                ProcedureError::InvalidArgument {
+                    intent_index,
+                    procedure: procedure.name,
+                    argument: param.name.to_string(),
+                    expected: param.ty,
+                }
+            })?,
+            None => param.default.to_string(),
+        };
+        json = json.replace(&format!("{{{}}}", param.name), &value);
+    }
+
+    // Templates are covered by tests and arguments are type-checked above.
+    let intents = serde_json::from_str(&json).expect("Procedure templates must parse");
+    Ok((procedure, intents))
+}
+
+/// Renders an argument as a JSON literal, if it matches the parameter's type.
+fn argument_literal(param: &ProcedureParam, argument: &ProcedureArg) -> Option<String> {
+    match (param.ty, argument) {
+        ("String", ProcedureArg::Text(text)) => serde_json::to_string(text).ok(),
+        ("String", ProcedureArg::Integer(_)) => None,
+        ("u32", ProcedureArg::Integer(value)) => u32::try_from(*value).ok().map(|v| v.to_string()),
+        (_, ProcedureArg::Integer(value)) => Some(value.to_string()),
+        (_, ProcedureArg::Text(_)) => None,
+    }
+}
+
+/// Renders the procedure list shown to the model.
+pub fn render_procedures() -> String {
+    PROCEDURES
+        .iter()
+        .map(|procedure| {
+            let params: Vec<String> = procedure
+                .params
+                .iter()
+                .map(|p| format!("{}: {} = {}", p.name, p.ty, p.default))
+                .collect();
+            format!(
+                "- {}({}): {}",
+                procedure.name,
+                params.join(", "),
+                procedure.description
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_to_intent_graph;
+    use naldom_ir::CountArg;
+
+    fn expand_json(json: &str) -> Result<Expansion, ProcedureError> {
+        expand(&parse_to_intent_graph(json).unwrap())
+    }
+
+    #[test]
+    fn test_every_template_expands_with_defaults() {
+        for procedure in PROCEDURES {
+            let json = format!(
+                r#"[{{"intent": "UseProcedure", "parameters": {{"name": "{}"}}}}]"#,
+                procedure.name
+            );
+            let expansion = expand_json(&json).expect("Defaults must produce a valid template");
+            assert!(!expansion.intents.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_expansion_substitutes_arguments_and_records_provenance() {
+        // Act
+        let expansion = expand_json(
+            r#"[
+                {"intent": "Note", "parameters": {"text": "Benchmark time."}},
+                {"intent": "UseProcedure", "parameters": {"name": "benchmark_sort", "arguments": {"size": 500, "order": "descending"}}}
+            ]"#,
+        )
+        .unwrap();
+
+        // Assert
+        assert!(
+            matches!(&expansion.intents[1], Intent::CreateArray(p) if p.size == Some(CountArg::Literal(500)))
+        );
+        assert!(
+            matches!(&expansion.intents[2], Intent::SortArray(p) if p.order.as_deref() == Some("descending"))
+        );
+        assert_eq!(expansion.describe(0), None);
+        assert_eq!(
+            expansion.describe(2).unwrap(),
+            "intent #3 was expanded from procedure 'benchmark_sort' (intent #2)"
+        );
+    }
+
+    #[test]
+    fn test_unknown_procedure_lists_available_ones() {
+        // Act
+        let error =
+            expand_json(r#"[{"intent": "UseProcedure", "parameters": {"name": "make_coffee"}}]"#)
+                .unwrap_err();
+
+        // Assert
+        assert!(matches!(
+            error,
+            ProcedureError::UnknownProcedure {
+                intent_index: 0,
+                ..
+            }
+        ));
+        assert!(
+            error
+                .to_string()
+                .contains("Available procedures: benchmark_sort")
+        );
+    }
+
+    #[test]
+    fn test_argument_errors() {
+        let wrong_type = expand_json(
+            r#"[{"intent": "UseProcedure", "parameters": {"name": "show_sorted", "arguments": {"size": "big"}}}]"#,
+        );
+        let negative = expand_json(
+            r#"[{"intent": "UseProcedure", "parameters": {"name": "show_sorted", "arguments": {"size": -1}}}]"#,
+        );
+        let unknown = expand_json(
+            r#"[{"intent": "UseProcedure", "parameters": {"name": "show_sorted", "arguments": {"colour": "red"}}}]"#,
+        );
+
+        assert!(matches!(
+            wrong_type,
+            Err(ProcedureError::InvalidArgument {
+                expected: "u32",
+                ..
+            })
+        ));
+        assert!(matches!(
+            negative,
+            Err(ProcedureError::InvalidArgument {
+                expected: "u32",
+                ..
+            })
+        ));
+        assert!(matches!(
+            unknown,
+            Err(ProcedureError::UnknownArgument { argument, .. }) if argument == "colour"
+        ));
+    }
+}
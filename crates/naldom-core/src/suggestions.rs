@@ -0,0 +1,259 @@
+// crates/naldom-core/src/suggestions.rs
+
+//! Deterministic fix suggestions for semantic errors.
+//!
+//! Many semantic errors in LLM-produced plans have an obvious repair (the model
+//! put the create after the sort, or forgot it entirely). Each rule here looks
+//! at a `SemanticError` and proposes a patched IntentGraph; nothing here calls
+//! the LLM.
+
+use crate::semantic_analyzer::{SemanticAnalyzer, SemanticError};
+use naldom_ir::{CountArg, CreateArrayParams, Intent};
+
+/// The array size used when a suggestion has to invent a `CreateArray`.
+pub const DEFAULT_ARRAY_SIZE: u32 = 10;
+
+/// How many rounds of suggestion application `auto_fix` attempts.
+pub const MAX_FIX_ROUNDS: usize = 3;
+
+/// A proposed repair for a semantic error.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub description: String,
+    pub patched_intents: Vec<Intent>,
+}
+
+/// Produces zero or more suggestions for `error`, most conservative first.
+pub fn suggest(error: &SemanticError, intents: &[Intent]) -> Vec<Suggestion> {
+    let mut suggestions = Vec::new();
+    if let SemanticError::UseBeforeCreate { intent_index, .. } = error {
+        suggestions.extend(reorder_create(*intent_index, intents));
+        suggestions.push(insert_default_create(*intent_index, intents));
+    }
+    if let SemanticError::UndefinedVariable {
+        intent_index,
+        name,
+        suggestion: Some(suggestion),
+    } = error
+    {
+        suggestions.extend(rename_variable(*intent_index, name, suggestion, intents));
+    }
+    suggestions
+}
+
+/// Rule: a `CreateArray` exists later in the graph, so move it before the failing intent.
+fn reorder_create(intent_index: usize, intents: &[Intent]) -> Option<Suggestion> {
+    let create_index = intents
+        .iter()
+        .skip(intent_index + 1)
+        .position(|intent| matches!(intent, Intent::CreateArray(_)))?
+        + intent_index
+        + 1;
+
+    let mut patched_intents = intents.to_vec();
+    let create = patched_intents.remove(create_index);
+    patched_intents.insert(intent_index, create);
+
+    Some(Suggestion {
+        description: format!(
+            "move the CreateArray at intent #{} before intent #{}",
+            create_index + 1,
+            intent_index + 1
+        ),
+        patched_intents,
+    })
+}
+
+/// Rule: insert a `CreateArray` with the default size right before the failing intent.
+fn insert_default_create(intent_index: usize, intents: &[Intent]) -> Suggestion {
+    let mut patched_intents = intents.to_vec();
+    patched_intents.insert(
+        intent_index,
+        Intent::CreateArray(CreateArrayParams {
+            size: Some(CountArg::Literal(DEFAULT_ARRAY_SIZE)),
+            name: None,
+            values: Vec::new(),
+            dtype: None,
+        }),
+    );
+
+    Suggestion {
+        description: format!(
+            "create an array of {} random numbers before intent #{}",
+            DEFAULT_ARRAY_SIZE,
+            intent_index + 1
+        ),
+        patched_intents,
+    }
+}
+
+/// Rule: the failing intent misspelt a variable, so use the closest set one.
+fn rename_variable(
+    intent_index: usize,
+    name: &str,
+    replacement: &str,
+    intents: &[Intent],
+) -> Option<Suggestion> {
+    let mut patched_intents = intents.to_vec();
+    let Intent::CreateArray(params) = patched_intents.get_mut(intent_index)? else {
+        return None;
+    };
+    if params.size != Some(CountArg::Variable(name.to_string())) {
+        return None;
+    }
+    params.size = Some(CountArg::Variable(replacement.to_string()));
+
+    Some(Suggestion {
+        description: format!(
+            "use '{}' instead of '{}' in intent #{}",
+            replacement,
+            name,
+            intent_index + 1
+        ),
+        patched_intents,
+    })
+}
+
+/// Formats an error followed by its suggestions, one per line.
+pub fn render_with_suggestions(error: &SemanticError, intents: &[Intent]) -> String {
+    let mut message = error.to_string();
+    for suggestion in suggest(error, intents) {
+        message.push_str(&format!("\n  suggestion: {}", suggestion.description));
+    }
+    message
+}
+
+/// Repeatedly analyzes `intents`, applying the first suggestion for each error,
+/// for at most `MAX_FIX_ROUNDS` rounds. Returns the validated graph and the
+/// descriptions of every applied suggestion, or the last error if no fix worked.
+pub fn auto_fix(intents: &[Intent]) -> Result<(Vec<Intent>, Vec<String>), SemanticError> {
+    let mut current = intents.to_vec();
+    let mut applied = Vec::new();
+
+    loop {
+        let error = match SemanticAnalyzer::new().analyze(&current) {
+            Ok(validated) => return Ok((validated, applied)),
+            Err(error) => error,
+        };
+        if applied.len() == MAX_FIX_ROUNDS {
+            return Err(error);
+        }
+        let Some(suggestion) = suggest(&error, &current).into_iter().next() else {
+            return Err(error);
+        };
+        applied.push(suggestion.description);
+        current = suggestion.patched_intents;
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use naldom_ir::{SetVariableParams, SortArrayParams};
+
+    fn sort() -> Intent {
+        Intent::SortArray(SortArrayParams {
+            order: Some("ascending".to_string()),
+            algorithm: None,
+            target_ordinal: None,
+            target: None,
+        })
+    }
+
+    #[test]
+    fn test_sort_before_create_suggests_reorder_then_insert() {
+        // Arrange
+        let intents = vec![
+            sort(),
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::PrintArray(None),
+        ];
+        let error = SemanticAnalyzer::new().analyze(&intents).unwrap_err();
+
+        // Act
+        let suggestions = suggest(&error, &intents);
+
+        // Assert
+        assert_eq!(suggestions.len(), 2);
+        assert!(suggestions[0].description.contains("move the CreateArray"));
+        assert!(matches!(
+            suggestions[0].patched_intents[0],
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                ..
+            })
+        ));
+        assert!(suggestions[1].description.contains("create an array of 10"));
+        assert_eq!(suggestions[1].patched_intents.len(), 4);
+    }
+
+    #[test]
+    fn test_auto_fix_produces_valid_graph() {
+        // Arrange
+        let intents = vec![
+            sort(),
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Literal(5)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+            Intent::PrintArray(None),
+        ];
+
+        // Act
+        let (validated, applied) = auto_fix(&intents).expect("Suggestions should fix the graph");
+
+        // Assert
+        assert_eq!(applied.len(), 1);
+        assert!(matches!(validated[0], Intent::CreateArray(_)));
+        assert!(matches!(validated[1], Intent::SortArray(_)));
+    }
+
+    #[test]
+    fn test_misspelt_variable_suggests_the_closest_one() {
+        // Arrange
+        let intents = vec![
+            Intent::SetVariable(SetVariableParams {
+                name: "count".to_string(),
+                value: 4,
+            }),
+            Intent::CreateArray(CreateArrayParams {
+                size: Some(CountArg::Variable("cont".to_string())),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
+            }),
+        ];
+
+        // Act
+        let (validated, applied) = auto_fix(&intents).expect("The suggestion should fix the graph");
+
+        // Assert
+        assert_eq!(applied, ["use 'count' instead of 'cont' in intent #2"]);
+        assert!(matches!(
+            &validated[1],
+            Intent::CreateArray(params) if params.size == Some(CountArg::Literal(4))
+        ));
+    }
+
+    #[test]
+    fn test_auto_fix_inserts_create_when_none_exists() {
+        // Arrange
+        let intents = vec![Intent::PrintArray(None)];
+
+        // Act
+        let (validated, applied) = auto_fix(&intents).unwrap();
+
+        // Assert
+        assert_eq!(applied.len(), 1);
+        assert_eq!(validated.len(), 2);
+    }
+}
@@ -0,0 +1,226 @@
+// crates/naldom-core/src/targets.rs
+
+//! Compilation targets and target-triple resolution.
+//!
+//! Host detection needs LLVM, so it lives behind the `llvm` feature; everything
+//! else here is pure, which keeps `--target python` usable in builds without LLVM.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The triple used for every WebAssembly build.
+pub const WASM_TRIPLE: &str = "wasm32-unknown-unknown";
+
+/// The function a wasm module exports for its host to call.
+pub const WASM_ENTRY: &str = "naldom_main";
+
+/// The LLVM major version this build generates IR for, chosen with the
+/// `llvm17`/`llvm18`/`llvm19` features. `None` without LLVM support, or when
+/// `llvm` was enabled without a version feature.
+#[cfg(feature = "llvm19")]
+pub const LLVM_MAJOR: Option<u32> = Some(19);
+#[cfg(all(feature = "llvm18", not(feature = "llvm19")))]
+pub const LLVM_MAJOR: Option<u32> = Some(18);
+#[cfg(all(feature = "llvm17", not(any(feature = "llvm18", feature = "llvm19"))))]
+pub const LLVM_MAJOR: Option<u32> = Some(17);
+#[cfg(not(any(feature = "llvm17", feature = "llvm18", feature = "llvm19")))]
+pub const LLVM_MAJOR: Option<u32> = None;
+
+/// A backend the compiler can emit code for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetKind {
+    /// An executable for the host (or an overridden) triple, via LLVM.
+    Native,
+    /// A WebAssembly module, via LLVM.
+    Wasm,
+    /// Python source generated from IR-HL. Never touches LLVM.
+    Python,
+}
+
+impl TargetKind {
+    /// The name accepted by `--target`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TargetKind::Native => "native",
+            TargetKind::Wasm => "wasm",
+            TargetKind::Python => "python",
+        }
+    }
+
+    /// Whether this target is compiled through LLVM.
+    pub fn uses_llvm(&self) -> bool {
+        !matches!(self, TargetKind::Python)
+    }
+
+    /// The name of the generated program's single function.
+    pub fn entry_name(&self) -> &'static str {
+        match self {
+            TargetKind::Wasm => WASM_ENTRY,
+            TargetKind::Native | TargetKind::Python => "main",
+        }
+    }
+}
+
+impl fmt::Display for TargetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TargetKind {
+    type Err = TargetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "native" => Ok(TargetKind::Native),
+            "wasm" => Ok(TargetKind::Wasm),
+            "python" => Ok(TargetKind::Python),
+            other => Err(TargetError::UnknownTarget(other.to_string())),
+        }
+    }
+}
+
+/// Errors raised while choosing a target or its triple.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetError {
+    /// `--target` named a backend that does not exist.
+    UnknownTarget(String),
+    /// The target is not compiled through LLVM, so it has no triple.
+    NoTriple(TargetKind),
+    /// The override does not describe the requested target.
+    TripleMismatch { target: TargetKind, triple: String },
+    /// The host triple is needed but this build has no LLVM support.
+    LlvmUnavailable,
+}
+
+impl fmt::Display for TargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetError::UnknownTarget(name) => write!(
+                f,
+                "Unknown target '{}'. Expected one of: native, wasm, python.",
+                name
+            ),
+            TargetError::NoTriple(target) => {
+                write!(f, "The '{}' target does not use a target triple.", target)
+            }
+            TargetError::TripleMismatch { target, triple } => write!(
+                f,
+                "Target triple '{}' cannot be used with the '{}' target.",
+                triple, target
+            ),
+            TargetError::LlvmUnavailable => write!(
+                f,
+                "This build of Naldom has no LLVM support; only the 'python' target is available."
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TargetError {}
+
+/// The triple of the machine running the compiler. Detected once and cached.
+#[cfg(feature = "llvm")]
+pub fn host_triple() -> String {
+    use std::sync::OnceLock;
+
+    static HOST_TRIPLE: OnceLock<String> = OnceLock::new();
+    HOST_TRIPLE
+        .get_or_init(|| {
+            inkwell::targets::TargetMachine::get_default_triple()
+                .as_str()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .clone()
+}
+
+/// Picks the triple to compile `target` for. An explicit `override_` wins;
+/// otherwise native builds use the host triple and wasm builds `WASM_TRIPLE`.
+pub fn resolve_triple(target: &TargetKind, override_: Option<&str>) -> Result<String, TargetError> {
+    match (target, override_) {
+        (TargetKind::Python, _) => Err(TargetError::NoTriple(*target)),
+        (TargetKind::Wasm, Some(triple)) if !triple.starts_with("wasm") => {
+            Err(TargetError::TripleMismatch {
+                target: *target,
+                triple: triple.to_string(),
+            })
+        }
+        (_, Some(triple)) if triple.trim().is_empty() => Err(TargetError::TripleMismatch {
+            target: *target,
+            triple: triple.to_string(),
+        }),
+        (_, Some(triple)) => Ok(triple.to_string()),
+        (TargetKind::Wasm, None) => Ok(WASM_TRIPLE.to_string()),
+        (TargetKind::Native, None) => native_host_triple(),
+    }
+}
+
+#[cfg(feature = "llvm")]
+fn native_host_triple() -> Result<String, TargetError> {
+    Ok(host_triple())
+}
+
+#[cfg(not(feature = "llvm"))]
+fn native_host_triple() -> Result<String, TargetError> {
+    Err(TargetError::LlvmUnavailable)
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_names_round_trip() {
+        for target in [TargetKind::Native, TargetKind::Wasm, TargetKind::Python] {
+            assert_eq!(target.as_str().parse::<TargetKind>(), Ok(target));
+        }
+        assert_eq!(
+            "riscv".parse::<TargetKind>(),
+            Err(TargetError::UnknownTarget("riscv".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_resolve_triple_prefers_override() {
+        assert_eq!(
+            resolve_triple(&TargetKind::Native, Some("aarch64-unknown-linux-gnu")),
+            Ok("aarch64-unknown-linux-gnu".to_string())
+        );
+        assert_eq!(
+            resolve_triple(&TargetKind::Wasm, None),
+            Ok(WASM_TRIPLE.to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_triple_rejects_mismatches() {
+        assert_eq!(
+            resolve_triple(&TargetKind::Python, None),
+            Err(TargetError::NoTriple(TargetKind::Python))
+        );
+        assert!(matches!(
+            resolve_triple(&TargetKind::Wasm, Some("x86_64-unknown-linux-gnu")),
+            Err(TargetError::TripleMismatch { .. })
+        ));
+    }
+
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_native_triple_defaults_to_host() {
+        let triple = resolve_triple(&TargetKind::Native, None).unwrap();
+
+        assert!(!triple.is_empty());
+        assert_eq!(triple, host_triple());
+    }
+
+    #[cfg(not(feature = "llvm"))]
+    #[test]
+    fn test_native_triple_requires_llvm() {
+        assert_eq!(
+            resolve_triple(&TargetKind::Native, None),
+            Err(TargetError::LlvmUnavailable)
+        );
+    }
+}
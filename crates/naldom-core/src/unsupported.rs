@@ -0,0 +1,179 @@
+// crates/naldom-core/src/unsupported.rs
+
+//! The constructs the IR can express but the backends cannot compile yet.
+//!
+//! Lowering and code generation report them as [`UnsupportedFeature`] errors
+//! instead of panicking, so a language limitation reads as a diagnostic, not
+//! as a compiler crash. Every construct's wording lives in this registry.
+
+use std::fmt;
+
+/// A construct the backends do not handle yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnsupportedConstruct {
+    /// `var_0 = 5` in IR-HL.
+    AssignedLiteral,
+    /// `f(g(x))` in IR-HL.
+    CallAsArgument,
+    /// A call whose result is neither a number nor a pointer.
+    AggregateReturnValue,
+    /// An IR-HL variable read before any statement assigns it.
+    UnassignedVariable,
+    /// An IR-LL register used before any instruction defines it.
+    UnallocatedRegister,
+    /// A value, stack slot or parameter of type `void`.
+    VoidValue,
+}
+
+impl UnsupportedConstruct {
+    /// Every construct, in declaration order.
+    pub const ALL: [UnsupportedConstruct; 6] = [
+        UnsupportedConstruct::AssignedLiteral,
+        UnsupportedConstruct::CallAsArgument,
+        UnsupportedConstruct::AggregateReturnValue,
+        UnsupportedConstruct::UnassignedVariable,
+        UnsupportedConstruct::UnallocatedRegister,
+        UnsupportedConstruct::VoidValue,
+    ];
+
+    /// The construct's technical name, e.g. `literal as assigned value`.
+    pub fn name(self) -> &'static str {
+        match self {
+            UnsupportedConstruct::AssignedLiteral => "literal as assigned value",
+            UnsupportedConstruct::CallAsArgument => "nested function call as argument",
+            UnsupportedConstruct::AggregateReturnValue => "aggregate return value",
+            UnsupportedConstruct::UnassignedVariable => "variable read before assignment",
+            UnsupportedConstruct::UnallocatedRegister => "register used before definition",
+            UnsupportedConstruct::VoidValue => "void-typed value",
+        }
+    }
+
+    /// What Naldom does not support, in words for users.
+    pub fn description(self) -> &'static str {
+        match self {
+            UnsupportedConstruct::AssignedLiteral => "assigning a constant to a variable",
+            UnsupportedConstruct::CallAsArgument => {
+                "passing the result of a call straight to another call"
+            }
+            UnsupportedConstruct::AggregateReturnValue => {
+                "calling a function that returns an array or a struct by value"
+            }
+            UnsupportedConstruct::UnassignedVariable => {
+                "reading a variable before any statement assigns it"
+            }
+            UnsupportedConstruct::UnallocatedRegister => {
+                "using a register before any instruction defines it"
+            }
+            UnsupportedConstruct::VoidValue => "a value, stack slot or parameter of type void",
+        }
+    }
+
+    /// How to get along without the construct until it is supported.
+    pub fn tracking_hint(self) -> &'static str {
+        match self {
+            UnsupportedConstruct::AssignedLiteral => "pass the constant directly where it is used",
+            UnsupportedConstruct::CallAsArgument => {
+                "assign the inner call's result to a variable first"
+            }
+            UnsupportedConstruct::AggregateReturnValue => "return a pointer to the value instead",
+            UnsupportedConstruct::UnassignedVariable => {
+                "assign the variable before the statement that reads it"
+            }
+            UnsupportedConstruct::UnallocatedRegister => {
+                "define the register with an alloc, load or call before using it"
+            }
+            UnsupportedConstruct::VoidValue => {
+                "drop the value: a function that returns nothing has no result to keep"
+            }
+        }
+    }
+}
+
+/// A construct the compiler met but cannot compile yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedFeature {
+    pub construct: UnsupportedConstruct,
+    /// Where the construct was found, e.g. `IR-HL statement #3`.
+    pub location: String,
+    /// How to do without it; see [`UnsupportedConstruct::tracking_hint`].
+    pub tracking_hint: &'static str,
+}
+
+impl UnsupportedFeature {
+    pub fn new(construct: UnsupportedConstruct, location: impl Into<String>) -> Self {
+        UnsupportedFeature {
+            construct,
+            location: location.into(),
+            tracking_hint: construct.tracking_hint(),
+        }
+    }
+}
+
+impl fmt::Display for UnsupportedFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Naldom does not support {} yet (construct: {}, {})\n  help: {}",
+            self.construct.description(),
+            self.construct.name(),
+            self.location,
+            self.tracking_hint
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedFeature {}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// The construct's expected position in `ALL`. The match is exhaustive,
+    /// so a new construct cannot be added without a place in the registry.
+    fn ordinal(construct: UnsupportedConstruct) -> usize {
+        match construct {
+            UnsupportedConstruct::AssignedLiteral => 0,
+            UnsupportedConstruct::CallAsArgument => 1,
+            UnsupportedConstruct::AggregateReturnValue => 2,
+            UnsupportedConstruct::UnassignedVariable => 3,
+            UnsupportedConstruct::UnallocatedRegister => 4,
+            UnsupportedConstruct::VoidValue => 5,
+        }
+    }
+
+    #[test]
+    fn test_every_construct_is_registered_with_its_own_wording() {
+        let mut names = HashSet::new();
+        let mut descriptions = HashSet::new();
+        for (position, construct) in UnsupportedConstruct::ALL.into_iter().enumerate() {
+            // A construct missing from `ALL` leaves a gap in the ordinals.
+            assert_eq!(ordinal(construct), position, "{:?}", construct);
+            for text in [
+                construct.name(),
+                construct.description(),
+                construct.tracking_hint(),
+            ] {
+                assert!(!text.trim().is_empty(), "{:?}", construct);
+            }
+            assert!(names.insert(construct.name()), "{:?}", construct);
+            assert!(
+                descriptions.insert(construct.description()),
+                "{:?}",
+                construct
+            );
+        }
+    }
+
+    #[test]
+    fn test_unsupported_feature_renders_the_construct_and_location() {
+        let error =
+            UnsupportedFeature::new(UnsupportedConstruct::CallAsArgument, "IR-HL statement #3");
+
+        assert_eq!(
+            error.to_string(),
+            "Naldom does not support passing the result of a call straight to another call yet (construct: nested function call as argument, IR-HL statement #3)\n  help: assign the inner call's result to a variable first"
+        );
+    }
+}
@@ -1,49 +1,665 @@
 // crates/naldom-ir/src/lib.rs
 
-use serde::Deserialize;
+pub mod runtime_abi;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 
 /// Represents a single user intent, parsed from the LLM's JSON output.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(tag = "intent", content = "parameters", rename_all = "PascalCase")]
 pub enum Intent {
     CreateArray(CreateArrayParams),
     SortArray(SortArrayParams),
-    PrintArray,
+    /// `parameters` may be omitted entirely, hence the `Option`.
+    PrintArray(Option<PrintArrayParams>),
+    /// Prints the sum of an array's elements. `parameters` may be omitted.
+    SumArray(Option<SumArrayParams>),
+    /// Reverses an array in place. `parameters` may be omitted.
+    ReverseArray(Option<ReverseArrayParams>),
+    /// Adds a number to the end of an array ("add 42 to the array").
+    AppendToArray(AppendParams),
+    /// Sets every element of an array to one number ("fill the array with
+    /// zeros").
+    FillArray(FillParams),
+    /// Removes every element of an array ("empty the array"). `parameters`
+    /// may be omitted.
+    ClearArray(Option<ClearArrayParams>),
+    /// Keeps only part of an array ("keep only the first 5 elements").
+    SliceArray(SliceParams),
+    /// Applies arithmetic to every element of an array ("multiply every
+    /// element by 3").
+    MapArray(MapParams),
+    /// Converts an array's elements to another element type ("turn the
+    /// numbers into whole numbers").
+    ConvertArray(ConvertParams),
+    /// Creates an array holding the elements of two named arrays, one after
+    /// the other ("join a and b into c").
+    ConcatArrays(ConcatParams),
+    /// Creates an independent copy of an array ("make a copy of the array"):
+    /// later changes to either leave the other as it was.
+    CopyArray(CopyParams),
+    /// Creates a matrix of random numbers ("a 3 by 4 matrix"). Matrices are
+    /// not arrays: array intents cannot act on them.
+    CreateMatrix(CreateMatrixParams),
+    /// Prints the most recently created matrix, one row per line.
+    /// `parameters` may be omitted.
+    PrintMatrix(Option<PrintMatrixParams>),
+    /// Prints the smallest element of an array. `parameters` may be omitted.
+    MinArray(Option<ExtremeParams>),
+    /// Prints the largest element of an array. `parameters` may be omitted.
+    MaxArray(Option<ExtremeParams>),
+    /// Prints how many elements an array has. `parameters` may be omitted.
+    CountElements(Option<CountElementsParams>),
+    /// Prints the mean of an array's elements. `parameters` may be omitted.
+    AverageArray(Option<AverageArrayParams>),
+    /// Prints the median of an array's elements. `parameters` may be omitted.
+    MedianArray(Option<MedianArrayParams>),
+    /// Prints whether an array holds a value, as `true` or `false`.
+    ContainsValue(ContainsParams),
+    /// Prints the position of the first element equal to a value, counting
+    /// from 0, or -1 if no element is.
+    FindIndex(FindIndexParams),
+    /// Prints a text histogram of an array. `parameters` may be omitted.
+    PrintHistogram(Option<HistogramParams>),
+    /// Prints a sentence about an array: its name, length, smallest and
+    /// largest element, and whether it is sorted. `parameters` may be omitted.
+    DescribeArray(Option<DescribeArrayParams>),
+    /// Runs a block of intents a fixed number of times.
+    Repeat(RepeatParams),
+    /// Runs one of two blocks of intents, depending on a test of an array.
+    Conditional(ConditionalParams),
     Wait(WaitParams),
+    /// Starts measuring time ("time how long the sort takes"). `parameters`
+    /// may be omitted.
+    StartTimer(Option<TimerParams>),
+    /// Prints the time since the most recent `StartTimer`, in milliseconds.
+    /// `parameters` may be omitted.
+    PrintElapsed(Option<TimerParams>),
+    /// Ends the program at once with an exit code ("stop the program").
+    /// `parameters` may be omitted.
+    Exit(Option<ExitParams>),
+    Note(NoteParams),
+    /// Names a constant ("let the threshold be 50") that later intents can
+    /// use in place of a number.
+    SetVariable(SetVariableParams),
+    /// A named standard-library procedure, expanded into intents before analysis.
+    UseProcedure(UseProcedureParams),
 }
 
 /// Parameters for the `CreateArray` intent.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct CreateArrayParams {
-    pub size: u32,
-    // The `source` field is removed for now to simplify things.
-    // We will re-introduce it when we support different array sources.
+    /// How many elements to create. `None` when `values` lists them. In a
+    /// validated graph it is always given, and the analyzer has replaced a
+    /// constant by its value; only a named result stays a variable.
+    #[serde(default)]
+    pub size: Option<CountArg>,
+    /// The elements, when the user listed them ("the values 3, 1, 4"). Empty
+    /// means random elements.
+    #[serde(default)]
+    pub values: Vec<i64>,
+    /// `"float"` or `"int"`: whether the elements are decimal or whole
+    /// numbers. `None` means `"int"` for listed values and `"float"` for
+    /// random ones.
+    #[serde(default)]
+    pub dtype: Option<String>,
+    /// The name the user gave the array ("an array called scores"), by which
+    /// later intents can refer to it through `target`.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+impl CreateArrayParams {
+    /// The number of elements, if it is known without looking up variables.
+    pub fn literal_size(&self) -> Option<u32> {
+        if !self.values.is_empty() {
+            return u32::try_from(self.values.len()).ok();
+        }
+        self.size.as_ref()?.literal()
+    }
+
+    /// Whether the elements are whole numbers: `dtype` says so, or, without
+    /// one, the user listed them.
+    pub fn holds_whole_numbers(&self) -> bool {
+        match self.dtype.as_deref() {
+            Some(dtype) => dtype == "int",
+            None => !self.values.is_empty(),
+        }
+    }
+}
+
+/// A count given as a number, or as the name of a constant set by an earlier
+/// `SetVariable` intent or of a result named by an earlier `SumArray`,
+/// `MinArray`, `MaxArray` or `CountElements`. A constant is known when the
+/// program is compiled; a result only when it runs.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum CountArg {
+    Literal(u32),
+    Variable(String),
+}
+
+impl fmt::Display for CountArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CountArg::Literal(count) => write!(f, "{}", count),
+            CountArg::Variable(name) => f.write_str(name),
+        }
+    }
+}
+
+impl CountArg {
+    /// The count, if it was given as a number.
+    pub fn literal(&self) -> Option<u32> {
+        match self {
+            CountArg::Literal(count) => Some(*count),
+            CountArg::Variable(_) => None,
+        }
+    }
+}
+
+/// A number that may be negative, given like a [`CountArg`].
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum NumberArg {
+    Literal(i64),
+    Variable(String),
+}
+
+impl fmt::Display for NumberArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumberArg::Literal(number) => write!(f, "{}", number),
+            NumberArg::Variable(name) => f.write_str(name),
+        }
+    }
 }
 
 /// Parameters for the `SortArray` intent.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
 pub struct SortArrayParams {
-    pub order: String,
+    /// `"ascending"` or `"descending"`. `None` means the model left it out; in
+    /// a validated graph the analyzer has filled in the registry default.
+    #[serde(default)]
+    pub order: Option<String>,
+    /// `"quick"`, `"merge"` or `"bubble"`. `None` leaves the choice to the
+    /// runtime, which picks by length.
+    #[serde(default)]
+    pub algorithm: Option<String>,
+    /// Which array to sort, counting from 1 in creation order ("the second
+    /// array" is 2). `None` means the most recently created array; in a
+    /// validated graph the analyzer has always resolved it to `Some`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+    /// Which array to sort, by the `name` it was created with. Takes
+    /// precedence over `target_ordinal`.
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+/// Parameters for the `PrintArray` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PrintArrayParams {
+    /// Which array to print; see `SortArrayParams::target_ordinal`. When
+    /// printing every array, the analyzer sets it to the number of arrays
+    /// that exist at that point.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+    /// Which array to print by name; see `SortArrayParams::target`.
+    /// [`ALL_ARRAYS`] prints every array, in the order they were created.
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Print every element. Otherwise long arrays show only their first and
+    /// last elements. `None` means the model left it out; in a validated graph
+    /// the analyzer has filled in the registry default.
+    #[serde(default)]
+    pub full: Option<bool>,
+}
+
+/// The `target` of a `PrintArray` that prints every array.
+pub const ALL_ARRAYS: &str = "all";
+
+impl PrintArrayParams {
+    /// Whether this prints every array rather than one.
+    pub fn prints_all(&self) -> bool {
+        self.target.as_deref() == Some(ALL_ARRAYS)
+    }
+}
+
+/// Parameters for the `SumArray` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SumArrayParams {
+    /// Which array to sum; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+    /// The name the result is kept under ("call it total"), by which later
+    /// intents can use it as a number; see [`CountArg`]. `None` only prints it.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Parameters for the `ReverseArray` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ReverseArrayParams {
+    /// Which array to reverse; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+}
+
+/// Parameters for the `AppendToArray` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AppendParams {
+    pub value: i64,
+    /// Which array to append to; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+}
+
+/// Parameters for the `FillArray` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FillParams {
+    pub value: i64,
+    /// Which array to fill; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+}
+
+/// Parameters for the `ClearArray` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ClearArrayParams {
+    /// Which array to empty; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+}
+
+/// Parameters for the `SliceArray` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SliceParams {
+    /// The index of the first element to keep, counting from 0.
+    pub start: u32,
+    /// The index just past the last element to keep. `None` keeps everything
+    /// from `start` on.
+    #[serde(default)]
+    pub end: Option<u32>,
+    /// Whether `start` counts back from the end of the array, so `start: 3`
+    /// keeps the last 3 elements. Such a slice has no `end`.
+    #[serde(default)]
+    pub from_end: bool,
+    /// Which array to slice; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+}
+
+/// Parameters for the `ConcatArrays` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConcatParams {
+    /// The name of the array whose elements come first.
+    pub first: String,
+    /// The name of the array whose elements follow.
+    pub second: String,
+    /// The name of the new array, by which later intents can refer to it.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Where `first` is in creation order. Never read from the model's JSON;
+    /// in a validated graph the analyzer has always resolved it to `Some`.
+    #[serde(skip_deserializing)]
+    pub first_ordinal: Option<u32>,
+    /// Where `second` is in creation order; see `first_ordinal`.
+    #[serde(skip_deserializing)]
+    pub second_ordinal: Option<u32>,
+}
+
+/// Parameters for the `CopyArray` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyParams {
+    /// The name of the copy, by which later intents can refer to it.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The name of the array to copy, when the model gave one.
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Which array to copy; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+}
+
+/// Parameters for the `MapArray` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MapParams {
+    /// `add`, `subtract`, `multiply` or `divide`.
+    pub operation: String,
+    /// The number each element is combined with.
+    pub operand: NumberArg,
+    /// Which array to change; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+}
+
+/// Parameters for the `ConvertArray` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertParams {
+    /// The element type to convert to: `float` or `int`.
+    pub to: String,
+    /// Which array to convert; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+}
+
+/// Parameters for the `MinArray` and `MaxArray` intents.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtremeParams {
+    /// Which array to search; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+    /// The name the result is kept under ("call it total"), by which later
+    /// intents can use it as a number; see [`CountArg`]. `None` only prints it.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Parameters for the `CountElements` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CountElementsParams {
+    /// Which array to count; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+    /// The name the result is kept under ("call it total"), by which later
+    /// intents can use it as a number; see [`CountArg`]. `None` only prints it.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Parameters for the `AverageArray` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AverageArrayParams {
+    /// Which array to average; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+}
+
+/// Parameters for the `MedianArray` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MedianArrayParams {
+    /// Which array to take the median of; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+}
+
+/// Parameters for the `ContainsValue` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ContainsParams {
+    pub value: i64,
+    /// Which array to search; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+}
+
+/// Parameters for the `FindIndex` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FindIndexParams {
+    pub value: i64,
+    /// Which array to search; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+}
+
+/// Parameters for the `PrintHistogram` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HistogramParams {
+    /// How many equal-width ranges to count elements in; 10 when omitted.
+    #[serde(default)]
+    pub buckets: Option<u32>,
+    /// Which array to chart; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+}
+
+/// Parameters for the `DescribeArray` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DescribeArrayParams {
+    /// Which array to describe; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+    /// Which array to describe by name; see `SortArrayParams::target`. In a
+    /// validated graph it is the array's name whenever it has one, however
+    /// the model referred to it.
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+/// Parameters for the `Repeat` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RepeatParams {
+    /// How many times to run `body`.
+    pub count: u32,
+    /// The intents to repeat, in order. Arrays must be created before the
+    /// loop, not inside it.
+    pub body: Vec<Intent>,
+}
+
+/// Parameters for the `Conditional` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConditionalParams {
+    /// The test, e.g. `"is_sorted"` or `"is_empty"`.
+    pub condition: String,
+    /// Which array to test; see `SortArrayParams::target_ordinal`.
+    #[serde(default)]
+    pub target_ordinal: Option<u32>,
+    /// The intents to run when the test holds. Arrays created here are only
+    /// visible inside the block.
+    #[serde(rename = "then")]
+    pub then_body: Vec<Intent>,
+    /// The intents to run otherwise; often empty.
+    #[serde(rename = "else", default)]
+    pub else_body: Vec<Intent>,
+}
+
+/// Parameters for the `CreateMatrix` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CreateMatrixParams {
+    pub rows: u32,
+    pub cols: u32,
+}
+
+/// Parameters for the `PrintMatrix` intent. There are none yet; the model
+/// may still send an empty object.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct PrintMatrixParams {}
+
+/// Parameters for the `StartTimer` and `PrintElapsed` intents. There are
+/// none yet; the model may still send an empty object.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct TimerParams {}
+
+/// Parameters for the `Exit` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ExitParams {
+    /// The process exit code; 0 when omitted.
+    #[serde(default)]
+    pub code: Option<i32>,
 }
 
 /// Parameters for the `Wait` intent.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct WaitParams {
     pub duration_ms: u64,
 }
 
+/// Parameters for the `SetVariable` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SetVariableParams {
+    pub name: String,
+    pub value: i64,
+}
+
+/// Parameters for the `Note` intent: non-actionable text the model wants to keep.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NoteParams {
+    pub text: String,
+}
+
+impl Intent {
+    /// The intent's name as it appears in the LLM's JSON.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Intent::CreateArray(_) => "CreateArray",
+            Intent::SortArray(_) => "SortArray",
+            Intent::PrintArray(_) => "PrintArray",
+            Intent::SumArray(_) => "SumArray",
+            Intent::ReverseArray(_) => "ReverseArray",
+            Intent::AppendToArray(_) => "AppendToArray",
+            Intent::FillArray(_) => "FillArray",
+            Intent::ClearArray(_) => "ClearArray",
+            Intent::SliceArray(_) => "SliceArray",
+            Intent::MapArray(_) => "MapArray",
+            Intent::ConvertArray(_) => "ConvertArray",
+            Intent::ConcatArrays(_) => "ConcatArrays",
+            Intent::CopyArray(_) => "CopyArray",
+            Intent::CreateMatrix(_) => "CreateMatrix",
+            Intent::PrintMatrix(_) => "PrintMatrix",
+            Intent::MinArray(_) => "MinArray",
+            Intent::MaxArray(_) => "MaxArray",
+            Intent::CountElements(_) => "CountElements",
+            Intent::AverageArray(_) => "AverageArray",
+            Intent::MedianArray(_) => "MedianArray",
+            Intent::ContainsValue(_) => "ContainsValue",
+            Intent::FindIndex(_) => "FindIndex",
+            Intent::PrintHistogram(_) => "PrintHistogram",
+            Intent::DescribeArray(_) => "DescribeArray",
+            Intent::Repeat(_) => "Repeat",
+            Intent::Conditional(_) => "Conditional",
+            Intent::Wait(_) => "Wait",
+            Intent::StartTimer(_) => "StartTimer",
+            Intent::PrintElapsed(_) => "PrintElapsed",
+            Intent::Exit(_) => "Exit",
+            Intent::Note(_) => "Note",
+            Intent::SetVariable(_) => "SetVariable",
+            Intent::UseProcedure(_) => "UseProcedure",
+        }
+    }
+
+    /// The name a `SumArray`, `MinArray`, `MaxArray` or `CountElements`
+    /// keeps its result under, when the model gave one.
+    pub fn result_name(&self) -> Option<&str> {
+        match self {
+            Intent::SumArray(params) => params.as_ref().and_then(|p| p.name.as_deref()),
+            Intent::MinArray(params) | Intent::MaxArray(params) => {
+                params.as_ref().and_then(|p| p.name.as_deref())
+            }
+            Intent::CountElements(params) => params.as_ref().and_then(|p| p.name.as_deref()),
+            _ => None,
+        }
+    }
+
+    /// The name of the array an operation targets, when the model gave one.
+    pub fn target_name(&self) -> Option<&str> {
+        match self {
+            Intent::SortArray(params) => params.target.as_deref(),
+            Intent::PrintArray(params) => params
+                .as_ref()
+                .filter(|p| !p.prints_all())
+                .and_then(|p| p.target.as_deref()),
+            Intent::DescribeArray(params) => params.as_ref().and_then(|p| p.target.as_deref()),
+            Intent::CopyArray(params) => params.target.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The array an operation targets, counting from 1 in creation order, for
+    /// intents that act on an existing array.
+    pub fn target_ordinal(&self) -> Option<u32> {
+        match self {
+            Intent::SortArray(params) => params.target_ordinal,
+            Intent::PrintArray(params) => params.as_ref().and_then(|p| p.target_ordinal),
+            Intent::SumArray(params) => params.as_ref().and_then(|p| p.target_ordinal),
+            Intent::ReverseArray(params) => params.as_ref().and_then(|p| p.target_ordinal),
+            Intent::AppendToArray(params) => params.target_ordinal,
+            Intent::FillArray(params) => params.target_ordinal,
+            Intent::ClearArray(params) => params.as_ref().and_then(|p| p.target_ordinal),
+            Intent::SliceArray(params) => params.target_ordinal,
+            Intent::MapArray(params) => params.target_ordinal,
+            Intent::ConvertArray(params) => params.target_ordinal,
+            Intent::MinArray(params) | Intent::MaxArray(params) => {
+                params.as_ref().and_then(|p| p.target_ordinal)
+            }
+            Intent::CountElements(params) => params.as_ref().and_then(|p| p.target_ordinal),
+            Intent::AverageArray(params) => params.as_ref().and_then(|p| p.target_ordinal),
+            Intent::MedianArray(params) => params.as_ref().and_then(|p| p.target_ordinal),
+            Intent::ContainsValue(params) => params.target_ordinal,
+            Intent::FindIndex(params) => params.target_ordinal,
+            Intent::PrintHistogram(params) => params.as_ref().and_then(|p| p.target_ordinal),
+            Intent::DescribeArray(params) => params.as_ref().and_then(|p| p.target_ordinal),
+            // The array copied, not the copy.
+            Intent::CopyArray(params) => params.target_ordinal,
+            Intent::Conditional(params) => params.target_ordinal,
+            // Reads two arrays; see `ConcatParams::first_ordinal`.
+            Intent::CreateArray(_)
+            | Intent::ConcatArrays(_)
+            | Intent::CreateMatrix(_)
+            | Intent::PrintMatrix(_)
+            | Intent::Repeat(_)
+            | Intent::Wait(_)
+            | Intent::StartTimer(_)
+            | Intent::PrintElapsed(_)
+            | Intent::Exit(_)
+            | Intent::Note(_)
+            | Intent::SetVariable(_)
+            | Intent::UseProcedure(_) => None,
+        }
+    }
+}
+
+/// Parameters for the `UseProcedure` intent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UseProcedureParams {
+    pub name: String,
+    /// Overrides for the procedure's parameters; missing ones use defaults.
+    #[serde(default)]
+    pub arguments: BTreeMap<String, ProcedureArg>,
+}
+
+/// A procedure argument as written by the model.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ProcedureArg {
+    Integer(i64),
+    Text(String),
+}
+
 /// High-Level Intermediate Representation (IR-HL).
 ///
 /// This represents the program in a more traditional, abstract way, with
 /// statements, expressions, and variables. It's the bridge between the
 /// user's "intent" and the actual code generation.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct HLProgram {
     pub statements: Vec<HLStatement>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum HLStatement {
     /// Assigns the result of an expression to a variable.
     /// e.g., `var_0 = create_random_array(10)`
@@ -57,9 +673,17 @@ pub enum HLStatement {
         function: String,
         arguments: Vec<HLExpression>,
     },
+    /// Runs `then_body` if `condition` evaluates to a non-zero integer, and
+    /// `else_body` otherwise.
+    /// e.g., `if is_sorted(var_0) { print_array(var_0) }`
+    If {
+        condition: HLExpression,
+        then_body: Vec<HLStatement>,
+        else_body: Vec<HLStatement>,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum HLExpression {
     /// A literal value, like a number or a string.
     Literal(HLValue),
@@ -71,13 +695,32 @@ pub enum HLExpression {
         function: String,
         arguments: Vec<HLExpression>,
     },
+    /// Integer arithmetic on two expressions.
+    /// e.g., `(var_1 - 3)` for the start of the last three elements.
+    BinaryOp {
+        op: BinOp,
+        lhs: Box<HLExpression>,
+        rhs: Box<HLExpression>,
+    },
+    /// `then_value` if `condition` evaluates to a non-zero integer, and
+    /// `else_value` otherwise. Only the chosen value is evaluated.
+    /// e.g., `(if is_sorted(var_0) then 1 else 0)`
+    Conditional {
+        condition: Box<HLExpression>,
+        then_value: Box<HLExpression>,
+        else_value: Box<HLExpression>,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum HLValue {
     Integer(i64),
     String(String),
-    // We can add more types like Float, Bool, etc. later.
+    /// A constant list of integers, e.g. the elements of a listed array.
+    IntegerArray(Vec<i64>),
+    /// A floating-point number, e.g. an average.
+    Float(f64),
+    // We can add more types like Bool, etc. later.
 }
 
 /// Low-Level Intermediate Representation (IR-LL).
@@ -85,12 +728,12 @@ pub enum HLValue {
 /// This is a much lower-level, explicit representation, very close to LLVM IR or assembly.
 /// It operates on concepts like virtual registers, basic blocks, and simple, atomic instructions.
 /// This representation is the final step before generating target-specific code (like LLVM IR).
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct LLProgram {
     pub functions: Vec<LLFunction>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct LLFunction {
     pub name: String,
     pub parameters: Vec<(LLType, Register)>,
@@ -98,21 +741,102 @@ pub struct LLFunction {
     pub basic_blocks: Vec<BasicBlock>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct BasicBlock {
     pub id: usize,
     pub instructions: Vec<LLInstruction>,
     pub terminator: Terminator,
+    /// Notes passes attach to instructions (provenance, estimates, decisions).
+    /// They never change what the code does, and live beside the instructions
+    /// so a pass that only rewrites instructions can ignore them.
+    pub annotations: Vec<Annotation>,
+}
+
+/// A `key=value` note on the instruction at index `instruction` of its block.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub instruction: usize,
+    pub key: String,
+    pub value: String,
+}
+
+impl LLFunction {
+    /// The ids of the blocks no terminator branches to, in order. The entry
+    /// block is reached by calling the function, so it is never one of them.
+    pub fn unreferenced_blocks(&self) -> Vec<usize> {
+        let targets: Vec<usize> = self
+            .basic_blocks
+            .iter()
+            .flat_map(|block| block.terminator.successors())
+            .collect();
+        self.basic_blocks
+            .iter()
+            .skip(1)
+            .map(|block| block.id)
+            .filter(|id| !targets.contains(id))
+            .collect()
+    }
+
+    /// The ids of the blocks whose terminator may jump to block `id`, in
+    /// order.
+    pub fn predecessors(&self, id: usize) -> Vec<usize> {
+        self.basic_blocks
+            .iter()
+            .filter(|block| block.terminator.successors().contains(&id))
+            .map(|block| block.id)
+            .collect()
+    }
+}
+
+impl BasicBlock {
+    /// The annotations on the instruction at `index`, in the order they were added.
+    pub fn annotations_of(&self, index: usize) -> impl Iterator<Item = &Annotation> {
+        self.annotations
+            .iter()
+            .filter(move |annotation| annotation.instruction == index)
+    }
+
+    /// Keeps only the instructions `keep` accepts. Annotations of removed
+    /// instructions are dropped and the rest follow their instruction to its
+    /// new index, so passes that delete instructions should go through this.
+    pub fn retain_instructions(&mut self, mut keep: impl FnMut(&LLInstruction) -> bool) {
+        let mut kept = 0;
+        let new_index: Vec<Option<usize>> = self
+            .instructions
+            .iter()
+            .map(|instruction| {
+                keep(instruction).then(|| {
+                    kept += 1;
+                    kept - 1
+                })
+            })
+            .collect();
+        let mut decisions = new_index.iter();
+        self.instructions
+            .retain(|_| decisions.next().is_some_and(Option::is_some));
+        self.annotations.retain_mut(|annotation| {
+            match new_index.get(annotation.instruction).copied().flatten() {
+                Some(moved_to) => {
+                    annotation.instruction = moved_to;
+                    true
+                }
+                None => false,
+            }
+        });
+    }
 }
 
 /// A virtual register, representing a temporary value. e.g., `%0`, `%1`.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Register(pub u32);
 
 /// Represents the primitive types in our low-level language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum LLType {
     Void,
+    /// A boolean, as produced by `ICmp`.
+    I1,
+    I8,
     I32,
     I64,
     F64,
@@ -120,7 +844,7 @@ pub enum LLType {
 }
 
 /// Represents a single, atomic operation.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum LLInstruction {
     /// Allocates space on the stack. Returns a pointer to the allocated space.
     Alloc { dest: Register, ty: LLType },
@@ -137,29 +861,565 @@ pub enum LLInstruction {
         function_name: String,
         arguments: Vec<LLValue>,
     },
-    // We will add more instructions like Add, Sub, ICmp later.
+    /// Compares two integers, producing an `I1`. The right operand is
+    /// converted to the left one's type.
+    ICmp {
+        dest: Register,
+        predicate: ICmpPredicate,
+        lhs: LLValue,
+        rhs: LLValue,
+    },
+    /// Applies `op` to two integers, producing a `ty`. Both operands are
+    /// converted to `ty` first.
+    BinaryOp {
+        dest: Register,
+        op: BinOp,
+        lhs: LLValue,
+        rhs: LLValue,
+        ty: LLType,
+    },
+    /// The `ty` value of the pair whose block control came from: each pair
+    /// is a value and the id of a predecessor block, and every predecessor
+    /// needs exactly one. Phis come first in their block.
+    Phi {
+        dest: Register,
+        ty: LLType,
+        incoming: Vec<(LLValue, usize)>,
+    },
+}
+
+/// An integer arithmetic operator. Overflow wraps; division is signed and
+/// rounds toward zero.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    SDiv,
+}
+
+impl BinOp {
+    /// The operator as IR-HL prints it, e.g. `-`.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::SDiv => "/",
+        }
+    }
+}
+
+/// How `ICmp` compares its operands. Comparisons are signed.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ICmpPredicate {
+    Eq,
+    Ne,
+    Slt,
+    Sle,
+    Sgt,
+    Sge,
 }
 
 /// Represents an instruction that terminates a basic block, controlling flow.
-#[derive(Debug, Clone, PartialEq)]
+/// Branch targets are `BasicBlock::id`s in the same function.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum Terminator {
     /// Returns from a function.
     Return(Option<LLValue>),
-    // We will add branching instructions like `Br` and `CondBr` later.
+    /// Jumps to `target`.
+    Br { target: usize },
+    /// Jumps to `then_block` if the `I1` `cond` is true, else to `else_block`.
+    CondBr {
+        cond: LLValue,
+        then_block: usize,
+        else_block: usize,
+    },
+    /// Marks the end of a block control never gets past, e.g. after a call
+    /// that exits the process.
+    Unreachable,
+}
+
+impl Terminator {
+    /// The ids of the blocks this terminator may jump to.
+    pub fn successors(&self) -> Vec<usize> {
+        match self {
+            Terminator::Br { target } => vec![*target],
+            Terminator::CondBr {
+                then_block,
+                else_block,
+                ..
+            } => vec![*then_block, *else_block],
+            Terminator::Return(_) | Terminator::Unreachable => Vec::new(),
+        }
+    }
 }
 
 /// Represents a value that can be used as an operand in an instruction.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum LLValue {
     Register(Register),
     Constant(LLConstant),
 }
 
 /// Represents a constant literal value.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum LLConstant {
     I32(i32),
     I64(i64),
     F64(f64),
-    // We can add string literals, etc., later.
+    /// A NUL-terminated string, passed as a pointer to its first byte.
+    Str(String),
+    /// The address of a function defined earlier in the same program.
+    Function(String),
+    /// A constant array of `i64`s, passed as a pointer to its first element.
+    I64Array(Vec<i64>),
+}
+
+// --- Textual form ---
+//
+// Both IRs print one statement or instruction per line, in a form stable
+// enough to diff: `var_0 = create_random_array(10)` for IR-HL, and
+// `%1 = call @sum_array(%0)` under `bb0:` labels for IR-LL.
+
+impl fmt::Display for HLProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_hl_statements(f, &self.statements, 0)
+    }
+}
+
+fn write_hl_statements(
+    f: &mut fmt::Formatter<'_>,
+    statements: &[HLStatement],
+    depth: usize,
+) -> fmt::Result {
+    let indent = "    ".repeat(depth);
+    for statement in statements {
+        match statement {
+            HLStatement::Assign {
+                variable,
+                expression,
+            } => writeln!(f, "{}{} = {}", indent, variable, expression)?,
+            HLStatement::Call {
+                function,
+                arguments,
+            } => writeln!(f, "{}{}({})", indent, function, join(arguments))?,
+            HLStatement::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                writeln!(f, "{}if {} {{", indent, condition)?;
+                write_hl_statements(f, then_body, depth + 1)?;
+                if !else_body.is_empty() {
+                    writeln!(f, "{}}} else {{", indent)?;
+                    write_hl_statements(f, else_body, depth + 1)?;
+                }
+                writeln!(f, "{}}}", indent)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for HLExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HLExpression::Literal(value) => write!(f, "{}", value),
+            HLExpression::Variable(name) => f.write_str(name),
+            HLExpression::FunctionCall {
+                function,
+                arguments,
+            } => write!(f, "{}({})", function, join(arguments)),
+            HLExpression::BinaryOp { op, lhs, rhs } => {
+                write!(f, "({} {} {})", lhs, op.symbol(), rhs)
+            }
+            HLExpression::Conditional {
+                condition,
+                then_value,
+                else_value,
+            } => write!(
+                f,
+                "(if {} then {} else {})",
+                condition, then_value, else_value
+            ),
+        }
+    }
+}
+
+impl fmt::Display for HLValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HLValue::Integer(value) => write!(f, "{}", value),
+            HLValue::String(text) => write!(f, "{:?}", text),
+            HLValue::IntegerArray(values) => write!(f, "[{}]", join(values)),
+            HLValue::Float(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+impl fmt::Display for LLProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, function) in self.functions.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", function)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for LLFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parameters: Vec<String> = self
+            .parameters
+            .iter()
+            .map(|(ty, register)| format!("{} {}", ty, register))
+            .collect();
+        writeln!(
+            f,
+            "fn @{}({}) -> {} {{",
+            self.name,
+            parameters.join(", "),
+            self.return_type
+        )?;
+        for block in &self.basic_blocks {
+            write!(f, "{}", block)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+impl fmt::Display for BasicBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "bb{}:", self.id)?;
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            write!(f, "  {}", instruction)?;
+            for annotation in self.annotations_of(index) {
+                write!(f, "  ; {}={}", annotation.key, annotation.value)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "  {}", self.terminator)
+    }
+}
+
+impl fmt::Display for LLInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LLInstruction::Alloc { dest, ty } => write!(f, "{} = alloc {}", dest, ty),
+            LLInstruction::Load { dest, source_ptr } => write!(f, "{} = load {}", dest, source_ptr),
+            LLInstruction::Store { value, dest_ptr } => write!(f, "store {}, {}", value, dest_ptr),
+            LLInstruction::Call {
+                dest,
+                function_name,
+                arguments,
+            } => {
+                if let Some(dest) = dest {
+                    write!(f, "{} = ", dest)?;
+                }
+                write!(f, "call @{}({})", function_name, join(arguments))
+            }
+            LLInstruction::ICmp {
+                dest,
+                predicate,
+                lhs,
+                rhs,
+            } => write!(f, "{} = icmp {} {}, {}", dest, predicate, lhs, rhs),
+            LLInstruction::BinaryOp {
+                dest,
+                op,
+                lhs,
+                rhs,
+                ty,
+            } => write!(f, "{} = {} {} {}, {}", dest, op, ty, lhs, rhs),
+            LLInstruction::Phi { dest, ty, incoming } => {
+                let incoming: Vec<String> = incoming
+                    .iter()
+                    .map(|(value, block)| format!("[{}, bb{}]", value, block))
+                    .collect();
+                write!(f, "{} = phi {} {}", dest, ty, incoming.join(", "))
+            }
+        }
+    }
+}
+
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BinOp::Add => "add",
+            BinOp::Sub => "sub",
+            BinOp::Mul => "mul",
+            BinOp::SDiv => "sdiv",
+        })
+    }
+}
+
+impl fmt::Display for ICmpPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ICmpPredicate::Eq => "eq",
+            ICmpPredicate::Ne => "ne",
+            ICmpPredicate::Slt => "slt",
+            ICmpPredicate::Sle => "sle",
+            ICmpPredicate::Sgt => "sgt",
+            ICmpPredicate::Sge => "sge",
+        })
+    }
+}
+
+impl fmt::Display for Terminator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Terminator::Return(None) => f.write_str("ret"),
+            Terminator::Return(Some(value)) => write!(f, "ret {}", value),
+            Terminator::Br { target } => write!(f, "br bb{}", target),
+            Terminator::CondBr {
+                cond,
+                then_block,
+                else_block,
+            } => write!(f, "br {}, bb{}, bb{}", cond, then_block, else_block),
+            Terminator::Unreachable => f.write_str("unreachable"),
+        }
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "%{}", self.0)
+    }
+}
+
+impl fmt::Display for LLType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LLType::Void => f.write_str("void"),
+            LLType::I1 => f.write_str("i1"),
+            LLType::I8 => f.write_str("i8"),
+            LLType::I32 => f.write_str("i32"),
+            LLType::I64 => f.write_str("i64"),
+            LLType::F64 => f.write_str("f64"),
+            LLType::Pointer(pointee) => write!(f, "{}*", pointee),
+        }
+    }
+}
+
+impl fmt::Display for LLValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LLValue::Register(register) => write!(f, "{}", register),
+            LLValue::Constant(constant) => write!(f, "{}", constant),
+        }
+    }
+}
+
+impl fmt::Display for LLConstant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LLConstant::I32(value) => write!(f, "{}", value),
+            LLConstant::I64(value) => write!(f, "{}", value),
+            LLConstant::F64(value) => write!(f, "{:?}", value),
+            LLConstant::Str(text) => write!(f, "{:?}", text),
+            LLConstant::Function(name) => write!(f, "@{}", name),
+            LLConstant::I64Array(values) => write!(f, "[{}]", join(values)),
+        }
+    }
+}
+
+/// `items` separated by commas.
+fn join<T: fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(function: &str, arguments: Vec<HLExpression>) -> HLExpression {
+        HLExpression::FunctionCall {
+            function: function.to_string(),
+            arguments,
+        }
+    }
+
+    #[test]
+    fn test_hl_programs_print_one_statement_per_line() {
+        // Arrange
+        let array = || HLExpression::Variable("var_0".to_string());
+        let program = HLProgram {
+            statements: vec![
+                HLStatement::Assign {
+                    variable: "var_0".to_string(),
+                    expression: call(
+                        "create_array_from_values",
+                        vec![HLExpression::Literal(HLValue::IntegerArray(vec![3, 1, 2]))],
+                    ),
+                },
+                HLStatement::If {
+                    condition: call("is_sorted", vec![array()]),
+                    then_body: vec![HLStatement::Call {
+                        function: "describe_array".to_string(),
+                        arguments: vec![
+                            array(),
+                            HLExpression::Literal(HLValue::String("var_0".to_string())),
+                        ],
+                    }],
+                    else_body: vec![HLStatement::Call {
+                        function: "sort_array".to_string(),
+                        arguments: vec![
+                            array(),
+                            HLExpression::Literal(HLValue::String("descending".to_string())),
+                        ],
+                    }],
+                },
+                HLStatement::Call {
+                    function: "print_float".to_string(),
+                    arguments: vec![HLExpression::Literal(HLValue::Float(2.0))],
+                },
+            ],
+        };
+
+        // Act
+        let text = program.to_string();
+
+        // Assert
+        assert_eq!(
+            text,
+            "var_0 = create_array_from_values([3, 1, 2])\n\
+             if is_sorted(var_0) {\n\
+             \x20   describe_array(var_0, \"var_0\")\n\
+             } else {\n\
+             \x20   sort_array(var_0, \"descending\")\n\
+             }\n\
+             print_float(2.0)\n"
+        );
+    }
+
+    #[test]
+    fn test_ll_programs_print_labeled_blocks_and_annotations() {
+        // Arrange
+        let program = LLProgram {
+            functions: vec![LLFunction {
+                name: "main".to_string(),
+                parameters: vec![(LLType::I32, Register(0))],
+                return_type: LLType::I32,
+                basic_blocks: vec![
+                    BasicBlock {
+                        id: 0,
+                        instructions: vec![
+                            LLInstruction::Call {
+                                dest: Some(Register(1)),
+                                function_name: "create_random_array".to_string(),
+                                arguments: vec![LLValue::Constant(LLConstant::I64(10))],
+                            },
+                            LLInstruction::Call {
+                                dest: Some(Register(2)),
+                                function_name: "is_sorted".to_string(),
+                                arguments: vec![LLValue::Register(Register(1))],
+                            },
+                            LLInstruction::ICmp {
+                                dest: Register(3),
+                                predicate: ICmpPredicate::Ne,
+                                lhs: LLValue::Register(Register(2)),
+                                rhs: LLValue::Constant(LLConstant::I32(0)),
+                            },
+                        ],
+                        terminator: Terminator::CondBr {
+                            cond: LLValue::Register(Register(3)),
+                            then_block: 2,
+                            else_block: 1,
+                        },
+                        annotations: vec![Annotation {
+                            instruction: 0,
+                            key: "hl_statement".to_string(),
+                            value: "0".to_string(),
+                        }],
+                    },
+                    BasicBlock {
+                        id: 1,
+                        instructions: vec![LLInstruction::Call {
+                            dest: None,
+                            function_name: "sort_array".to_string(),
+                            arguments: vec![
+                                LLValue::Register(Register(1)),
+                                LLValue::Constant(LLConstant::I64(0)),
+                                LLValue::Constant(LLConstant::I64(0)),
+                            ],
+                        }],
+                        terminator: Terminator::Br { target: 2 },
+                        annotations: Vec::new(),
+                    },
+                    BasicBlock {
+                        id: 2,
+                        instructions: Vec::new(),
+                        terminator: Terminator::Return(Some(LLValue::Constant(LLConstant::I32(0)))),
+                        annotations: Vec::new(),
+                    },
+                ],
+            }],
+        };
+
+        // Act
+        let text = program.to_string();
+
+        // Assert
+        assert_eq!(
+            text,
+            "fn @main(i32 %0) -> i32 {\n\
+             bb0:\n\
+             \x20 %1 = call @create_random_array(10)  ; hl_statement=0\n\
+             \x20 %2 = call @is_sorted(%1)\n\
+             \x20 %3 = icmp ne %2, 0\n\
+             \x20 br %3, bb2, bb1\n\
+             bb1:\n\
+             \x20 call @sort_array(%1, 0, 0)\n\
+             \x20 br bb2\n\
+             bb2:\n\
+             \x20 ret 0\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_blocks_know_which_blocks_branch_to_them() {
+        // Arrange
+        let block = |id, terminator| BasicBlock {
+            id,
+            instructions: Vec::new(),
+            terminator,
+            annotations: Vec::new(),
+        };
+        let function = LLFunction {
+            name: "main".to_string(),
+            parameters: Vec::new(),
+            return_type: LLType::Void,
+            basic_blocks: vec![
+                block(
+                    0,
+                    Terminator::CondBr {
+                        cond: LLValue::Constant(LLConstant::I32(1)),
+                        then_block: 2,
+                        else_block: 3,
+                    },
+                ),
+                block(1, Terminator::Br { target: 3 }),
+                block(2, Terminator::Br { target: 0 }),
+                block(3, Terminator::Return(None)),
+            ],
+        };
+
+        // Act
+        let unreferenced = function.unreferenced_blocks();
+
+        // Assert
+        assert_eq!(unreferenced, [1]);
+        assert_eq!(function.predecessors(3), [0, 1]);
+        assert_eq!(function.predecessors(0), [2]);
+    }
 }
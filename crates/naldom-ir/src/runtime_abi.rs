@@ -0,0 +1,692 @@
+// crates/naldom-ir/src/runtime_abi.rs
+
+//! The runtime ABI registry: every function generated code may call, with its
+//! exact C signature, plus the `NaldomArray` layout.
+//!
+//! Code generation, the generated C header and the runtime's own ABI test all
+//! read this table, so adding a runtime function means adding one entry here.
+
+use crate::LLType;
+
+/// Bumped on incompatible changes to any signature or to `NaldomArray`.
+pub const ABI_VERSION_MAJOR: u32 = 4;
+/// Bumped when functions are added.
+pub const ABI_VERSION_MINOR: u32 = 9;
+
+/// The import module wasm hosts provide the runtime functions under, so an
+/// import reads `naldom_runtime.print_array`.
+pub const WASM_IMPORT_MODULE: &str = "naldom_runtime";
+
+/// A type that crosses the runtime boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbiType {
+    Void,
+    /// `int32_t`, used for yes/no answers (0 or 1).
+    I32,
+    I64,
+    U64,
+    F64,
+    /// `double *`, used inside `NaldomArray`.
+    F64Ptr,
+    /// `const int64_t *`, a list of integers owned by the caller.
+    I64Ptr,
+    /// `const char *`, a NUL-terminated string owned by the caller.
+    CStr,
+    /// `const char *const *`, an `argv`-style list of strings.
+    CStrArray,
+    /// `NaldomEntry`, i.e. `void (*)(void)`: a compiled program's entry function.
+    EntryFn,
+    /// `NaldomArray *`, an array the callee may modify.
+    ArrayPtr,
+    /// `const NaldomArray *`, an array the callee only reads.
+    ConstArrayPtr,
+    /// `NaldomMatrix *`, a matrix the callee may modify.
+    MatrixPtr,
+    /// `const NaldomMatrix *`, a matrix the callee only reads.
+    ConstMatrixPtr,
+}
+
+impl AbiType {
+    /// The IR-LL type a value of this type has once it crosses into
+    /// generated code. The runtime's structs are opaque there, so pointers
+    /// to them are byte pointers.
+    pub fn ll_type(self) -> LLType {
+        match self {
+            AbiType::Void => LLType::Void,
+            AbiType::I32 => LLType::I32,
+            AbiType::I64 | AbiType::U64 => LLType::I64,
+            AbiType::F64 => LLType::F64,
+            AbiType::F64Ptr => LLType::Pointer(Box::new(LLType::F64)),
+            AbiType::I64Ptr => LLType::Pointer(Box::new(LLType::I64)),
+            AbiType::CStrArray => LLType::Pointer(Box::new(LLType::Pointer(Box::new(LLType::I8)))),
+            AbiType::CStr
+            | AbiType::EntryFn
+            | AbiType::ArrayPtr
+            | AbiType::ConstArrayPtr
+            | AbiType::MatrixPtr
+            | AbiType::ConstMatrixPtr => LLType::Pointer(Box::new(LLType::I8)),
+        }
+    }
+}
+
+/// A named parameter of a runtime function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbiParam {
+    pub name: &'static str,
+    pub ty: AbiType,
+}
+
+/// One exported runtime function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuntimeFunction {
+    pub name: &'static str,
+    pub params: &'static [AbiParam],
+    pub return_type: AbiType,
+    /// A one-line description, copied into the generated header.
+    pub doc: &'static str,
+    /// Whether only the native runtime provides the function. Wasm hosts
+    /// supply the array functions, but not ones that need threads or clocks.
+    pub native_only: bool,
+}
+
+/// A field of `NaldomArray`, in declaration order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbiField {
+    pub name: &'static str,
+    pub ty: AbiType,
+}
+
+/// The `#[repr(C)]` layout of `NaldomArray`.
+pub const NALDOM_ARRAY_FIELDS: &[AbiField] = &[
+    AbiField {
+        name: "data",
+        ty: AbiType::F64Ptr,
+    },
+    AbiField {
+        name: "size",
+        ty: AbiType::I64,
+    },
+    AbiField {
+        name: "capacity",
+        ty: AbiType::I64,
+    },
+];
+
+/// The `#[repr(C)]` layout of `NaldomMatrix`: `rows * cols` elements in
+/// row-major order.
+pub const NALDOM_MATRIX_FIELDS: &[AbiField] = &[
+    AbiField {
+        name: "data",
+        ty: AbiType::F64Ptr,
+    },
+    AbiField {
+        name: "rows",
+        ty: AbiType::I64,
+    },
+    AbiField {
+        name: "cols",
+        ty: AbiType::I64,
+    },
+];
+
+/// Every function the runtime exports to generated code.
+pub const RUNTIME_FUNCTIONS: &[RuntimeFunction] = &[
+    RuntimeFunction {
+        name: "create_random_array",
+        params: &[AbiParam {
+            name: "size",
+            ty: AbiType::I64,
+        }],
+        return_type: AbiType::ArrayPtr,
+        doc: "Allocates an array of `size` random numbers in [0, 100).",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "create_random_int_array",
+        params: &[AbiParam {
+            name: "size",
+            ty: AbiType::I64,
+        }],
+        return_type: AbiType::ArrayPtr,
+        doc: "Allocates an array of `size` random whole numbers in [0, 100).",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "create_array_from_values",
+        params: &[
+            AbiParam {
+                name: "values",
+                ty: AbiType::I64Ptr,
+            },
+            AbiParam {
+                name: "len",
+                ty: AbiType::I64,
+            },
+        ],
+        return_type: AbiType::ArrayPtr,
+        doc: "Allocates an array holding copies of the `len` integers at `values`.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "sort_array",
+        params: &[
+            AbiParam {
+                name: "arr",
+                ty: AbiType::ArrayPtr,
+            },
+            AbiParam {
+                name: "order",
+                ty: AbiType::I64,
+            },
+            AbiParam {
+                name: "algorithm",
+                ty: AbiType::I64,
+            },
+        ],
+        return_type: AbiType::Void,
+        doc: "Sorts `arr` in place: ascending for order 0, descending for order 1. Algorithm 0 lets the runtime choose by length; 1 is quicksort, 2 merge sort, 3 bubble sort.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "print_array",
+        params: &[
+            AbiParam {
+                name: "arr",
+                ty: AbiType::ConstArrayPtr,
+            },
+            AbiParam {
+                name: "full",
+                ty: AbiType::I64,
+            },
+        ],
+        return_type: AbiType::Void,
+        doc: "Prints `arr` to stdout and flushes. Unless `full` is 1, long arrays show only their first and last 20 elements.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "sum_array",
+        params: &[AbiParam {
+            name: "arr",
+            ty: AbiType::ConstArrayPtr,
+        }],
+        return_type: AbiType::I64,
+        doc: "Returns the sum of the elements of `arr`, rounded to the nearest integer; 0 for a null array.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "min_array",
+        params: &[AbiParam {
+            name: "arr",
+            ty: AbiType::ConstArrayPtr,
+        }],
+        return_type: AbiType::I64,
+        doc: "Returns the smallest element of `arr`, rounded to the nearest integer; 0 for a null or empty array.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "max_array",
+        params: &[AbiParam {
+            name: "arr",
+            ty: AbiType::ConstArrayPtr,
+        }],
+        return_type: AbiType::I64,
+        doc: "Returns the largest element of `arr`, rounded to the nearest integer; 0 for a null or empty array.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "array_length",
+        params: &[AbiParam {
+            name: "arr",
+            ty: AbiType::ConstArrayPtr,
+        }],
+        return_type: AbiType::I64,
+        doc: "Returns the number of elements of `arr`; 0 for a null array.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "average_array",
+        params: &[AbiParam {
+            name: "arr",
+            ty: AbiType::ConstArrayPtr,
+        }],
+        return_type: AbiType::F64,
+        doc: "Returns the mean of the elements of `arr`; 0.0 for a null or empty array.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "median_array",
+        params: &[AbiParam {
+            name: "arr",
+            ty: AbiType::ConstArrayPtr,
+        }],
+        return_type: AbiType::F64,
+        doc: "Returns the median of the elements of `arr`, the mean of the middle two for an even count; 0.0 for a null or empty array.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "array_contains",
+        params: &[
+            AbiParam {
+                name: "arr",
+                ty: AbiType::ConstArrayPtr,
+            },
+            AbiParam {
+                name: "value",
+                ty: AbiType::I64,
+            },
+        ],
+        return_type: AbiType::I32,
+        doc: "Returns 1 if an element of `arr` equals `value`, 0 otherwise; 0 for a null array.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "find_index",
+        params: &[
+            AbiParam {
+                name: "arr",
+                ty: AbiType::ConstArrayPtr,
+            },
+            AbiParam {
+                name: "value",
+                ty: AbiType::I64,
+            },
+        ],
+        return_type: AbiType::I64,
+        doc: "Returns the index of the first element of `arr` equal to `value`, or -1 if there is none; -1 for a null array.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "is_sorted",
+        params: &[AbiParam {
+            name: "arr",
+            ty: AbiType::ConstArrayPtr,
+        }],
+        return_type: AbiType::I32,
+        doc: "Returns 1 if `arr` is in ascending order, 0 otherwise; 1 for a null or empty array.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "describe_array",
+        params: &[
+            AbiParam {
+                name: "arr",
+                ty: AbiType::ConstArrayPtr,
+            },
+            AbiParam {
+                name: "name",
+                ty: AbiType::CStr,
+            },
+        ],
+        return_type: AbiType::Void,
+        doc: "Prints a sentence giving the length, range and order of `arr` under `name`; nothing for a null array.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "is_empty",
+        params: &[AbiParam {
+            name: "arr",
+            ty: AbiType::ConstArrayPtr,
+        }],
+        return_type: AbiType::I32,
+        doc: "Returns 1 if `arr` has no elements or is null, 0 otherwise.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "reverse_array",
+        params: &[AbiParam {
+            name: "arr",
+            ty: AbiType::ArrayPtr,
+        }],
+        return_type: AbiType::Void,
+        doc: "Reverses `arr` in place.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "append_to_array",
+        params: &[
+            AbiParam {
+                name: "arr",
+                ty: AbiType::ArrayPtr,
+            },
+            AbiParam {
+                name: "value",
+                ty: AbiType::I64,
+            },
+        ],
+        return_type: AbiType::Void,
+        doc: "Appends `value` to `arr`, growing its storage when it is full.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "fill_array",
+        params: &[
+            AbiParam {
+                name: "arr",
+                ty: AbiType::ArrayPtr,
+            },
+            AbiParam {
+                name: "value",
+                ty: AbiType::I64,
+            },
+        ],
+        return_type: AbiType::Void,
+        doc: "Sets every element of `arr` to `value`.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "clear_array",
+        params: &[AbiParam {
+            name: "arr",
+            ty: AbiType::ArrayPtr,
+        }],
+        return_type: AbiType::Void,
+        doc: "Removes every element of `arr` and releases its storage.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "slice_array",
+        params: &[
+            AbiParam {
+                name: "arr",
+                ty: AbiType::ArrayPtr,
+            },
+            AbiParam {
+                name: "start",
+                ty: AbiType::I64,
+            },
+            AbiParam {
+                name: "end",
+                ty: AbiType::I64,
+            },
+        ],
+        return_type: AbiType::Void,
+        doc: "Keeps only the elements of `arr` from `start` up to `end`, both clamped to its bounds.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "map_array",
+        params: &[
+            AbiParam {
+                name: "arr",
+                ty: AbiType::ArrayPtr,
+            },
+            AbiParam {
+                name: "op",
+                ty: AbiType::I64,
+            },
+            AbiParam {
+                name: "operand",
+                ty: AbiType::I64,
+            },
+        ],
+        return_type: AbiType::Void,
+        doc: "Combines every element of `arr` with `operand`: `op` 0 adds, 1 subtracts, 2 multiplies, 3 divides. Dividing by zero leaves `arr` unchanged.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "concat_arrays",
+        params: &[
+            AbiParam {
+                name: "first",
+                ty: AbiType::ConstArrayPtr,
+            },
+            AbiParam {
+                name: "second",
+                ty: AbiType::ConstArrayPtr,
+            },
+        ],
+        return_type: AbiType::ArrayPtr,
+        doc: "Returns a new array holding the elements of `first` followed by those of `second`; a null array counts as empty.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "copy_array",
+        params: &[AbiParam {
+            name: "arr",
+            ty: AbiType::ConstArrayPtr,
+        }],
+        return_type: AbiType::ArrayPtr,
+        doc: "Returns a new array holding the elements of `arr`; a null array copies as empty.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "array_to_float",
+        params: &[AbiParam {
+            name: "arr",
+            ty: AbiType::ConstArrayPtr,
+        }],
+        return_type: AbiType::ArrayPtr,
+        doc: "Returns a new array holding the elements of `arr` as floating-point numbers; a null array converts to an empty one.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "array_to_int",
+        params: &[AbiParam {
+            name: "arr",
+            ty: AbiType::ConstArrayPtr,
+        }],
+        return_type: AbiType::ArrayPtr,
+        doc: "Returns a new array holding the elements of `arr` truncated toward zero; a null array converts to an empty one.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "print_int_array",
+        params: &[
+            AbiParam {
+                name: "arr",
+                ty: AbiType::ConstArrayPtr,
+            },
+            AbiParam {
+                name: "full",
+                ty: AbiType::I64,
+            },
+        ],
+        return_type: AbiType::Void,
+        doc: "Prints `arr`, an array of whole numbers, like `print_array` but without decimals.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "print_histogram",
+        params: &[
+            AbiParam {
+                name: "arr",
+                ty: AbiType::ConstArrayPtr,
+            },
+            AbiParam {
+                name: "buckets",
+                ty: AbiType::I64,
+            },
+        ],
+        return_type: AbiType::Void,
+        doc: "Prints a text histogram of `arr` with `buckets` equal-width ranges; nothing for a null or empty array or a non-positive `buckets`.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "print_integer",
+        params: &[AbiParam {
+            name: "value",
+            ty: AbiType::I64,
+        }],
+        return_type: AbiType::Void,
+        doc: "Prints `value` on a line of its own and flushes.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "print_bool",
+        params: &[AbiParam {
+            name: "value",
+            ty: AbiType::I32,
+        }],
+        return_type: AbiType::Void,
+        doc: "Prints `true` if `value` is non-zero, `false` otherwise, on a line of its own and flushes.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "print_float",
+        params: &[AbiParam {
+            name: "value",
+            ty: AbiType::F64,
+        }],
+        return_type: AbiType::Void,
+        doc: "Prints `value` with two decimals on a line of its own and flushes.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "create_random_matrix",
+        params: &[
+            AbiParam {
+                name: "rows",
+                ty: AbiType::I64,
+            },
+            AbiParam {
+                name: "cols",
+                ty: AbiType::I64,
+            },
+        ],
+        return_type: AbiType::MatrixPtr,
+        doc: "Allocates a `rows` by `cols` matrix of random numbers in [0, 100).",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "print_matrix",
+        params: &[AbiParam {
+            name: "matrix",
+            ty: AbiType::ConstMatrixPtr,
+        }],
+        return_type: AbiType::Void,
+        doc: "Prints `matrix` to stdout, one row per line, and flushes.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "naldom_seed_random",
+        params: &[AbiParam {
+            name: "seed",
+            ty: AbiType::U64,
+        }],
+        return_type: AbiType::Void,
+        doc: "Makes every following `create_random_array` reproducible from `seed`.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "start_timer",
+        params: &[],
+        return_type: AbiType::I64,
+        doc: "Starts a timer: returns a reading of the monotonic clock, in nanoseconds, to pass to `print_elapsed`.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "print_elapsed",
+        params: &[AbiParam {
+            name: "timer",
+            ty: AbiType::I64,
+        }],
+        return_type: AbiType::Void,
+        doc: "Prints the time since `timer` was started as `elapsed: <ms> ms`, with two decimals.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "naldom_set_error_mode",
+        params: &[AbiParam {
+            name: "mode",
+            ty: AbiType::I32,
+        }],
+        return_type: AbiType::Void,
+        doc: "Selects what a failing runtime function does: 0 ends the program, 1 warns and returns an empty result.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "naldom_exit",
+        params: &[AbiParam {
+            name: "code",
+            ty: AbiType::I32,
+        }],
+        return_type: AbiType::Void,
+        doc: "Ends the process with exit status `code`. Never returns.",
+        native_only: false,
+    },
+    RuntimeFunction {
+        name: "naldom_async_sleep",
+        params: &[AbiParam {
+            name: "ms",
+            ty: AbiType::U64,
+        }],
+        return_type: AbiType::Void,
+        doc: "Blocks the calling thread for `ms` milliseconds.",
+        native_only: true,
+    },
+    RuntimeFunction {
+        name: "naldom_profile_init",
+        params: &[AbiParam {
+            name: "intent_count",
+            ty: AbiType::I64,
+        }],
+        return_type: AbiType::Void,
+        doc: "Starts profiling `intent_count` intents; the report is printed to stderr at exit.",
+        native_only: true,
+    },
+    RuntimeFunction {
+        name: "naldom_profile_begin",
+        params: &[
+            AbiParam {
+                name: "intent_index",
+                ty: AbiType::I64,
+            },
+            AbiParam {
+                name: "intent_name",
+                ty: AbiType::CStr,
+            },
+        ],
+        return_type: AbiType::Void,
+        doc: "Marks the start of intent `intent_index` (0-based).",
+        native_only: true,
+    },
+    RuntimeFunction {
+        name: "naldom_profile_end",
+        params: &[AbiParam {
+            name: "intent_index",
+            ty: AbiType::I64,
+        }],
+        return_type: AbiType::Void,
+        doc: "Marks the end of intent `intent_index`.",
+        native_only: true,
+    },
+    RuntimeFunction {
+        name: "naldom_register_program",
+        params: &[
+            AbiParam {
+                name: "name",
+                ty: AbiType::CStr,
+            },
+            AbiParam {
+                name: "entry",
+                ty: AbiType::EntryFn,
+            },
+        ],
+        return_type: AbiType::Void,
+        doc: "Makes `entry` runnable by passing `name` as the first argument to `naldom_dispatch`.",
+        native_only: true,
+    },
+    RuntimeFunction {
+        name: "naldom_dispatch",
+        params: &[
+            AbiParam {
+                name: "argc",
+                ty: AbiType::I64,
+            },
+            AbiParam {
+                name: "argv",
+                ty: AbiType::CStrArray,
+            },
+        ],
+        return_type: AbiType::I64,
+        doc: "Runs the registered program named by `argv[1]` and returns the exit status; lists the programs on stderr and returns 2 for an unknown name.",
+        native_only: true,
+    },
+];
+
+/// Looks up a runtime function by its symbol name.
+pub fn find_runtime_function(name: &str) -> Option<&'static RuntimeFunction> {
+    RUNTIME_FUNCTIONS
+        .iter()
+        .find(|function| function.name == name)
+}
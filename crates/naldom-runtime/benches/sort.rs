@@ -0,0 +1,38 @@
+// crates/naldom-runtime/benches/sort.rs
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use naldom_runtime::sort::{SortAlgorithm, sort_with};
+use std::hint::black_box;
+
+/// Lengths around the insertion sort threshold; at 16 the short-array path
+/// should be the faster one.
+const LENGTHS: [usize; 3] = [16, 32, 64];
+
+/// A shuffled array of `len` distinct values.
+fn shuffled(len: usize) -> Vec<f64> {
+    (0..len).map(|i| ((i * 37) % len) as f64).collect()
+}
+
+fn bench_sort(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sort");
+    for len in LENGTHS {
+        let values = shuffled(len);
+        for algorithm in [SortAlgorithm::Insertion, SortAlgorithm::Pattern] {
+            group.bench_with_input(
+                BenchmarkId::new(algorithm.name(), len),
+                &values,
+                |b, values| {
+                    b.iter_batched_ref(
+                        || values.clone(),
+                        |values| sort_with(black_box(values), |a, b| a.total_cmp(b), algorithm),
+                        criterion::BatchSize::SmallInput,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_sort);
+criterion_main!(benches);
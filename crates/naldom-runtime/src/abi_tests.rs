@@ -0,0 +1,289 @@
+// crates/naldom-runtime/src/abi_tests.rs
+
+//! Checks the exported `extern "C"` functions against the ABI registry in
+//! `naldom-ir`, which the compiler and the generated C header are built from.
+//!
+//! Each export is listed with its expected Rust signature; the coercion in
+//! `exported!` fails to compile if an implementation changes, and the
+//! comparison below fails if the registry changes. Functions behind a
+//! disabled runtime feature are left out of the comparison.
+
+use crate::array::{
+    NaldomArray, append_to_array, array_contains, array_length, array_to_float, array_to_int,
+    average_array, clear_array, concat_arrays, copy_array, create_array_from_values,
+    create_random_array, create_random_int_array, describe_array, fill_array, find_index, is_empty,
+    is_sorted, map_array, max_array, median_array, min_array, naldom_seed_random, print_array,
+    print_bool, print_float, print_int_array, print_integer, reverse_array, slice_array,
+    sort_array, sum_array,
+};
+use crate::dispatch::{EntryFn, naldom_dispatch, naldom_register_program};
+use crate::error_mode::naldom_set_error_mode;
+use crate::exit::naldom_exit;
+use crate::histogram::print_histogram;
+use crate::matrix::{NaldomMatrix, create_random_matrix, print_matrix};
+#[cfg(feature = "async-sleep")]
+use crate::naldom_async_sleep;
+use crate::profile::{naldom_profile_begin, naldom_profile_end, naldom_profile_init};
+use crate::timer::{print_elapsed, start_timer};
+use naldom_ir::runtime_abi::{
+    AbiType, NALDOM_ARRAY_FIELDS, NALDOM_MATRIX_FIELDS, RUNTIME_FUNCTIONS,
+};
+use std::ffi::c_char;
+use std::mem::{offset_of, size_of};
+
+trait AbiRepr {
+    const ABI: AbiType;
+}
+
+impl AbiRepr for () {
+    const ABI: AbiType = AbiType::Void;
+}
+impl AbiRepr for i32 {
+    const ABI: AbiType = AbiType::I32;
+}
+impl AbiRepr for i64 {
+    const ABI: AbiType = AbiType::I64;
+}
+impl AbiRepr for u64 {
+    const ABI: AbiType = AbiType::U64;
+}
+impl AbiRepr for f64 {
+    const ABI: AbiType = AbiType::F64;
+}
+impl AbiRepr for *mut f64 {
+    const ABI: AbiType = AbiType::F64Ptr;
+}
+impl AbiRepr for *const i64 {
+    const ABI: AbiType = AbiType::I64Ptr;
+}
+impl AbiRepr for *const c_char {
+    const ABI: AbiType = AbiType::CStr;
+}
+impl AbiRepr for *const *const c_char {
+    const ABI: AbiType = AbiType::CStrArray;
+}
+impl AbiRepr for EntryFn {
+    const ABI: AbiType = AbiType::EntryFn;
+}
+impl AbiRepr for *mut NaldomArray {
+    const ABI: AbiType = AbiType::ArrayPtr;
+}
+impl AbiRepr for *const NaldomArray {
+    const ABI: AbiType = AbiType::ConstArrayPtr;
+}
+impl AbiRepr for *mut NaldomMatrix {
+    const ABI: AbiType = AbiType::MatrixPtr;
+}
+impl AbiRepr for *const NaldomMatrix {
+    const ABI: AbiType = AbiType::ConstMatrixPtr;
+}
+
+/// `(parameter types, return type)` of an exported function.
+type Signature = (Vec<AbiType>, AbiType);
+
+trait ExternFn {
+    fn signature() -> Signature;
+}
+
+impl<R: AbiRepr> ExternFn for extern "C" fn() -> R {
+    fn signature() -> Signature {
+        (Vec::new(), R::ABI)
+    }
+}
+impl<A: AbiRepr, R: AbiRepr> ExternFn for extern "C" fn(A) -> R {
+    fn signature() -> Signature {
+        (vec![A::ABI], R::ABI)
+    }
+}
+impl<A: AbiRepr, R: AbiRepr> ExternFn for unsafe extern "C" fn(A) -> R {
+    fn signature() -> Signature {
+        (vec![A::ABI], R::ABI)
+    }
+}
+/// A function that never returns is `void` as far as callers can tell.
+impl<A: AbiRepr> ExternFn for extern "C" fn(A) -> ! {
+    fn signature() -> Signature {
+        (vec![A::ABI], AbiType::Void)
+    }
+}
+impl<A: AbiRepr, B: AbiRepr, R: AbiRepr> ExternFn for extern "C" fn(A, B) -> R {
+    fn signature() -> Signature {
+        (vec![A::ABI, B::ABI], R::ABI)
+    }
+}
+impl<A: AbiRepr, B: AbiRepr, R: AbiRepr> ExternFn for unsafe extern "C" fn(A, B) -> R {
+    fn signature() -> Signature {
+        (vec![A::ABI, B::ABI], R::ABI)
+    }
+}
+
+impl<A: AbiRepr, B: AbiRepr, C: AbiRepr, R: AbiRepr> ExternFn
+    for unsafe extern "C" fn(A, B, C) -> R
+{
+    fn signature() -> Signature {
+        (vec![A::ABI, B::ABI, C::ABI], R::ABI)
+    }
+}
+
+fn signature_of<F: ExternFn>(_function: F) -> Signature {
+    F::signature()
+}
+
+macro_rules! exported {
+    ($name:ident: $ty:ty) => {
+        (stringify!($name), signature_of::<$ty>($name))
+    };
+}
+
+fn exports() -> Vec<(&'static str, Signature)> {
+    let exports = vec![
+        exported!(create_random_array: extern "C" fn(i64) -> *mut NaldomArray),
+        exported!(create_random_int_array: extern "C" fn(i64) -> *mut NaldomArray),
+        exported!(create_array_from_values: unsafe extern "C" fn(*const i64, i64) -> *mut NaldomArray),
+        exported!(sort_array: unsafe extern "C" fn(*mut NaldomArray, i64, i64)),
+        exported!(print_array: unsafe extern "C" fn(*const NaldomArray, i64)),
+        exported!(sum_array: unsafe extern "C" fn(*const NaldomArray) -> i64),
+        exported!(min_array: unsafe extern "C" fn(*const NaldomArray) -> i64),
+        exported!(max_array: unsafe extern "C" fn(*const NaldomArray) -> i64),
+        exported!(array_length: unsafe extern "C" fn(*const NaldomArray) -> i64),
+        exported!(average_array: unsafe extern "C" fn(*const NaldomArray) -> f64),
+        exported!(median_array: unsafe extern "C" fn(*const NaldomArray) -> f64),
+        exported!(array_contains: unsafe extern "C" fn(*const NaldomArray, i64) -> i32),
+        exported!(find_index: unsafe extern "C" fn(*const NaldomArray, i64) -> i64),
+        exported!(is_sorted: unsafe extern "C" fn(*const NaldomArray) -> i32),
+        exported!(describe_array: unsafe extern "C" fn(*const NaldomArray, *const c_char)),
+        exported!(is_empty: unsafe extern "C" fn(*const NaldomArray) -> i32),
+        exported!(reverse_array: unsafe extern "C" fn(*mut NaldomArray)),
+        exported!(append_to_array: unsafe extern "C" fn(*mut NaldomArray, i64)),
+        exported!(fill_array: unsafe extern "C" fn(*mut NaldomArray, i64)),
+        exported!(clear_array: unsafe extern "C" fn(*mut NaldomArray)),
+        exported!(slice_array: unsafe extern "C" fn(*mut NaldomArray, i64, i64)),
+        exported!(map_array: unsafe extern "C" fn(*mut NaldomArray, i64, i64)),
+        exported!(concat_arrays: unsafe extern "C" fn(*const NaldomArray, *const NaldomArray) -> *mut NaldomArray),
+        exported!(copy_array: unsafe extern "C" fn(*const NaldomArray) -> *mut NaldomArray),
+        exported!(array_to_float: unsafe extern "C" fn(*const NaldomArray) -> *mut NaldomArray),
+        exported!(array_to_int: unsafe extern "C" fn(*const NaldomArray) -> *mut NaldomArray),
+        exported!(print_int_array: unsafe extern "C" fn(*const NaldomArray, i64)),
+        exported!(print_histogram: unsafe extern "C" fn(*const NaldomArray, i64)),
+        exported!(print_integer: extern "C" fn(i64)),
+        exported!(print_bool: extern "C" fn(i32)),
+        exported!(print_float: extern "C" fn(f64)),
+        exported!(create_random_matrix: extern "C" fn(i64, i64) -> *mut NaldomMatrix),
+        exported!(print_matrix: unsafe extern "C" fn(*const NaldomMatrix)),
+        exported!(naldom_seed_random: extern "C" fn(u64)),
+        exported!(start_timer: extern "C" fn() -> i64),
+        exported!(print_elapsed: extern "C" fn(i64)),
+        exported!(naldom_set_error_mode: extern "C" fn(i32)),
+        exported!(naldom_exit: extern "C" fn(i32) -> !),
+        exported!(naldom_profile_init: extern "C" fn(i64)),
+        exported!(naldom_profile_begin: unsafe extern "C" fn(i64, *const c_char)),
+        exported!(naldom_profile_end: extern "C" fn(i64)),
+        exported!(naldom_register_program: unsafe extern "C" fn(*const c_char, EntryFn)),
+        exported!(naldom_dispatch: unsafe extern "C" fn(i64, *const *const c_char) -> i64),
+    ];
+    #[cfg(feature = "async-sleep")]
+    let exports = [
+        exports,
+        vec![exported!(naldom_async_sleep: extern "C" fn(u64))],
+    ]
+    .concat();
+    exports
+}
+
+/// Whether this build of the runtime provides the registry function `name`.
+fn provided(name: &str) -> bool {
+    name != "naldom_async_sleep" || cfg!(feature = "async-sleep")
+}
+
+fn field_abi<T: AbiRepr>(_field: fn(&NaldomArray) -> &T) -> AbiType {
+    T::ABI
+}
+
+fn matrix_field_abi<T: AbiRepr>(_field: fn(&NaldomMatrix) -> &T) -> AbiType {
+    T::ABI
+}
+
+#[test]
+fn test_exports_match_the_registry() {
+    let exports = exports();
+    let provided_functions: Vec<_> = RUNTIME_FUNCTIONS
+        .iter()
+        .filter(|function| provided(function.name))
+        .collect();
+    assert_eq!(exports.len(), provided_functions.len());
+
+    for function in provided_functions {
+        let (_, signature) = exports
+            .iter()
+            .find(|(name, _)| *name == function.name)
+            .unwrap_or_else(|| panic!("The runtime does not export '{}'", function.name));
+        let registered: Vec<AbiType> = function.params.iter().map(|p| p.ty).collect();
+        assert_eq!(
+            signature,
+            &(registered, function.return_type),
+            "Signature of '{}' drifted from the registry",
+            function.name
+        );
+    }
+}
+
+#[test]
+fn test_naldom_array_layout_matches_the_registry() {
+    let fields = [
+        (
+            "data",
+            field_abi(|a| &a.data),
+            offset_of!(NaldomArray, data),
+        ),
+        (
+            "size",
+            field_abi(|a| &a.size),
+            offset_of!(NaldomArray, size),
+        ),
+        (
+            "capacity",
+            field_abi(|a| &a.capacity),
+            offset_of!(NaldomArray, capacity),
+        ),
+    ];
+
+    assert_eq!(fields.len(), NALDOM_ARRAY_FIELDS.len());
+    // Every registered field is pointer-sized, so the C layout has no padding.
+    for (index, ((name, ty, offset), registered)) in
+        fields.iter().zip(NALDOM_ARRAY_FIELDS).enumerate()
+    {
+        assert_eq!((*name, *ty), (registered.name, registered.ty));
+        assert_eq!(*offset, index * 8);
+    }
+    assert_eq!(size_of::<NaldomArray>(), 24);
+}
+
+#[test]
+fn test_naldom_matrix_layout_matches_the_registry() {
+    let fields = [
+        (
+            "data",
+            matrix_field_abi(|m| &m.data),
+            offset_of!(NaldomMatrix, data),
+        ),
+        (
+            "rows",
+            matrix_field_abi(|m| &m.rows),
+            offset_of!(NaldomMatrix, rows),
+        ),
+        (
+            "cols",
+            matrix_field_abi(|m| &m.cols),
+            offset_of!(NaldomMatrix, cols),
+        ),
+    ];
+
+    assert_eq!(fields.len(), NALDOM_MATRIX_FIELDS.len());
+    for (index, ((name, ty, offset), registered)) in
+        fields.iter().zip(NALDOM_MATRIX_FIELDS).enumerate()
+    {
+        assert_eq!((*name, *ty), (registered.name, registered.ty));
+        assert_eq!(*offset, index * 8);
+    }
+    assert_eq!(size_of::<NaldomMatrix>(), 24);
+}
@@ -0,0 +1,1241 @@
+// crates/naldom-runtime/src/array.rs
+
+//! Array functions called from compiled Naldom code.
+
+use crate::embed::record_printed_array;
+use crate::error_mode::runtime_error;
+use crate::output::print_logical;
+use crate::sort::{SortAlgorithm, sort};
+use std::cell::Cell;
+use std::ffi::{CStr, c_char};
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A "fat pointer" for our arrays, containing the data, its size, and how
+/// many elements fit before the data must be reallocated.
+/// The layout is part of the ABI shared with generated code.
+#[repr(C)]
+pub struct NaldomArray {
+    pub data: *mut f64,
+    pub size: i64,
+    pub capacity: i64,
+}
+
+impl NaldomArray {
+    /// Borrows the elements of `arr`, or `None` for a null array.
+    ///
+    /// # Safety
+    /// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+    pub(crate) unsafe fn values<'a>(arr: *const NaldomArray) -> Option<&'a [f64]> {
+        let arr = unsafe { arr.as_ref()? };
+        if arr.data.is_null() {
+            return None;
+        }
+        let len = usize::try_from(arr.size).ok()?;
+        Some(unsafe { std::slice::from_raw_parts(arr.data, len) })
+    }
+
+    /// Mutably borrows the elements of `arr`, or `None` for a null array.
+    ///
+    /// # Safety
+    /// Same as [`NaldomArray::values`].
+    unsafe fn values_mut<'a>(arr: *mut NaldomArray) -> Option<&'a mut [f64]> {
+        let arr = unsafe { arr.as_mut()? };
+        if arr.data.is_null() {
+            return None;
+        }
+        let len = usize::try_from(arr.size).ok()?;
+        Some(unsafe { std::slice::from_raw_parts_mut(arr.data, len) })
+    }
+
+    /// Moves `values` into a newly allocated array owned by the generated program.
+    fn from_vec(values: Vec<f64>) -> *mut NaldomArray {
+        let mut values = std::mem::ManuallyDrop::new(values);
+        Box::into_raw(Box::new(NaldomArray {
+            data: values.as_mut_ptr(),
+            size: values.len() as i64,
+            capacity: values.capacity() as i64,
+        }))
+    }
+
+    /// Gives the elements of `arr` back to a `Vec`, runs `update` on it, and
+    /// stores the possibly reallocated result. Does nothing for a null array.
+    ///
+    /// # Safety
+    /// Same as [`NaldomArray::values`]; in particular `data`, `size` and
+    /// `capacity` must come from [`NaldomArray::from_vec`] or an earlier update.
+    unsafe fn update(arr: *mut NaldomArray, update: impl FnOnce(&mut Vec<f64>)) {
+        let Some(arr) = (unsafe { arr.as_mut() }) else {
+            return;
+        };
+        if arr.data.is_null() {
+            return;
+        }
+        let (Ok(size), Ok(capacity)) = (usize::try_from(arr.size), usize::try_from(arr.capacity))
+        else {
+            return;
+        };
+        // SAFETY: the caller guarantees the fields describe a `Vec`'s buffer,
+        // which `ManuallyDrop` below hands back without freeing it.
+        let mut values = unsafe { Vec::from_raw_parts(arr.data, size, capacity) };
+        update(&mut values);
+        let mut values = std::mem::ManuallyDrop::new(values);
+        arr.data = values.as_mut_ptr();
+        arr.size = values.len() as i64;
+        arr.capacity = values.capacity() as i64;
+    }
+}
+
+thread_local! {
+    /// The random generator's state, shared by every array the program
+    /// creates. Zero means "not seeded yet"; xorshift never produces zero.
+    static RNG_STATE: Cell<u64> = const { Cell::new(0) };
+}
+
+/// A small xorshift generator; programs only need "random looking" numbers.
+pub(crate) struct XorShift(u64);
+
+impl XorShift {
+    /// Continues the program's sequence, seeding it from the clock on first use.
+    pub(crate) fn resume() -> Self {
+        match RNG_STATE.get() {
+            0 => {
+                let nanos = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_nanos() as u64)
+                    .unwrap_or(0);
+                XorShift(nanos | 1)
+            }
+            state => XorShift(state),
+        }
+    }
+
+    /// Stores the state, so the next array continues the sequence.
+    pub(crate) fn suspend(self) {
+        RNG_STATE.set(self.0);
+    }
+
+    /// Returns a value in `[0.0, 1.0)`.
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Seeds the generator behind `create_random_array`. The same seed yields the
+/// same numbers as the IR-HL interpreter with that seed.
+#[unsafe(no_mangle)]
+pub extern "C" fn naldom_seed_random(seed: u64) {
+    // Spread small seeds over all bits; xorshift's first outputs are otherwise
+    // close to zero.
+    RNG_STATE.set(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1);
+}
+
+/// Allocates room for `size` elements, or explains why that is impossible.
+pub(crate) fn allocate_elements(size: i64) -> Result<Vec<f64>, String> {
+    let len = usize::try_from(size.max(0))
+        .map_err(|_| format!("an array of {} elements does not fit in memory", size))?;
+    let bytes = len
+        .checked_mul(std::mem::size_of::<f64>())
+        .filter(|&bytes| bytes <= isize::MAX as usize)
+        .ok_or_else(|| format!("an array of {} elements does not fit in memory", size))?;
+    let mut values = Vec::new();
+    values
+        .try_reserve_exact(len)
+        .map_err(|_| format!("could not allocate {} bytes for {} elements", bytes, size))?;
+    Ok(values)
+}
+
+/// Creates an array of `size` random numbers between 0.0 and 100.0.
+/// Returns null (which every other array function ignores) if the array
+/// cannot be allocated and the program runs in continue mode; otherwise the
+/// failure ends the program (see [`crate::error_mode`]).
+#[unsafe(no_mangle)]
+pub extern "C" fn create_random_array(size: i64) -> *mut NaldomArray {
+    print_logical(&format!(
+        "Runtime: Creating an array of {} random numbers...\n",
+        size
+    ));
+    let mut values = match allocate_elements(size) {
+        Ok(values) => values,
+        Err(reason) => {
+            runtime_error(&reason);
+            return std::ptr::null_mut();
+        }
+    };
+    let mut rng = XorShift::resume();
+    values.extend((0..size.max(0)).map(|_| rng.next_f64() * 100.0));
+    rng.suspend();
+    NaldomArray::from_vec(values)
+}
+
+/// Creates an array of `size` random whole numbers between 0 and 99.
+/// Returns null if the array cannot be allocated.
+#[unsafe(no_mangle)]
+pub extern "C" fn create_random_int_array(size: i64) -> *mut NaldomArray {
+    print_logical(&format!(
+        "Runtime: Creating an array of {} random whole numbers...\n",
+        size
+    ));
+    let mut values = match allocate_elements(size) {
+        Ok(values) => values,
+        Err(reason) => {
+            runtime_error(&reason);
+            return std::ptr::null_mut();
+        }
+    };
+    let mut rng = XorShift::resume();
+    values.extend((0..size.max(0)).map(|_| (rng.next_f64() * 100.0).floor()));
+    rng.suspend();
+    NaldomArray::from_vec(values)
+}
+
+/// Creates an array holding the `len` integers at `values`, as the program
+/// listed them. Returns null if the array cannot be allocated.
+///
+/// # Safety
+/// `values` must point to `len` readable `i64`s, or be null if `len` is 0.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn create_array_from_values(
+    values: *const i64,
+    len: i64,
+) -> *mut NaldomArray {
+    print_logical(&format!(
+        "Runtime: Creating an array of {} values...\n",
+        len
+    ));
+    let mut elements = match allocate_elements(len) {
+        Ok(elements) => elements,
+        Err(reason) => {
+            runtime_error(&reason);
+            return std::ptr::null_mut();
+        }
+    };
+    if !values.is_null() {
+        // SAFETY: the caller guarantees `len` readable elements, and
+        // `allocate_elements` accepted `len`, so it fits a slice. A negative
+        // `len` reads nothing.
+        let values = unsafe { std::slice::from_raw_parts(values, len.max(0) as usize) };
+        elements.extend(values.iter().map(|&value| value as f64));
+    }
+    NaldomArray::from_vec(elements)
+}
+
+/// Sorts the array in place: 0 for ascending, 1 for descending. The
+/// algorithm is the one `algorithm` names (see [`SortAlgorithm::from_code`]),
+/// or for 0 one that depends on the length; see [`crate::sort`].
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sort_array(arr: *mut NaldomArray, order: i64, algorithm: i64) {
+    let Some(values) = (unsafe { NaldomArray::values_mut(arr) }) else {
+        return;
+    };
+    print_logical("Runtime: Sorting the array...\n");
+    let algorithm = SortAlgorithm::from_code(algorithm);
+    if order == 1 {
+        sort(values, |a, b| b.total_cmp(a), algorithm);
+    } else {
+        sort(values, |a, b| a.total_cmp(b), algorithm);
+    }
+}
+
+/// Reverses the array in place.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn reverse_array(arr: *mut NaldomArray) {
+    let Some(values) = (unsafe { NaldomArray::values_mut(arr) }) else {
+        return;
+    };
+    values.reverse();
+}
+
+/// Appends `value` to the array, growing its storage when it is full. Leaves
+/// the array unchanged if the storage cannot grow.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn append_to_array(arr: *mut NaldomArray, value: i64) {
+    unsafe {
+        NaldomArray::update(arr, |values| {
+            if values.try_reserve(1).is_err() {
+                runtime_error(&format!(
+                    "could not grow an array of {} elements",
+                    values.len()
+                ));
+                return;
+            }
+            values.push(value as f64);
+        })
+    };
+}
+
+/// Sets every element of the array to `value`.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fill_array(arr: *mut NaldomArray, value: i64) {
+    let Some(values) = (unsafe { NaldomArray::values_mut(arr) }) else {
+        return;
+    };
+    values.fill(value as f64);
+}
+
+/// Removes every element of the array and releases its storage. The array
+/// stays usable: it prints as `[]`, and appending grows it again.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn clear_array(arr: *mut NaldomArray) {
+    unsafe {
+        NaldomArray::update(arr, |values| {
+            values.clear();
+            values.shrink_to_fit();
+        })
+    };
+}
+
+/// Keeps only the elements from index `start` up to, but not including, `end`.
+/// Both are clamped to the array's bounds, so a negative `start` counts as 0,
+/// an `end` past the last element as its length, and `end <= start` empties
+/// the array.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn slice_array(arr: *mut NaldomArray, start: i64, end: i64) {
+    unsafe {
+        NaldomArray::update(arr, |values| {
+            let len = values.len() as i64;
+            let end = end.clamp(0, len) as usize;
+            let start = (start.clamp(0, len) as usize).min(end);
+            values.truncate(end);
+            values.drain(..start);
+        })
+    };
+}
+
+/// Returns a new array holding the elements of `first` followed by those of
+/// `second`. A null array counts as empty. Returns null if the new array
+/// cannot be allocated.
+///
+/// # Safety
+/// `first` and `second` must each be null or a pointer returned by one of the
+/// runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concat_arrays(
+    first: *const NaldomArray,
+    second: *const NaldomArray,
+) -> *mut NaldomArray {
+    let first = unsafe { NaldomArray::values(first) }.unwrap_or_default();
+    let second = unsafe { NaldomArray::values(second) }.unwrap_or_default();
+    let len = first.len().saturating_add(second.len());
+    let mut values = match allocate_elements(i64::try_from(len).unwrap_or(i64::MAX)) {
+        Ok(values) => values,
+        Err(reason) => {
+            runtime_error(&reason);
+            return std::ptr::null_mut();
+        }
+    };
+    values.extend_from_slice(first);
+    values.extend_from_slice(second);
+    NaldomArray::from_vec(values)
+}
+
+/// Returns a new array holding the elements of `arr`, which is left as it
+/// was. A null array copies as empty. Returns null if the new array cannot
+/// be allocated.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn copy_array(arr: *const NaldomArray) -> *mut NaldomArray {
+    let source = unsafe { NaldomArray::values(arr) }.unwrap_or_default();
+    let mut values = match allocate_elements(i64::try_from(source.len()).unwrap_or(i64::MAX)) {
+        Ok(values) => values,
+        Err(reason) => {
+            runtime_error(&reason);
+            return std::ptr::null_mut();
+        }
+    };
+    values.extend_from_slice(source);
+    NaldomArray::from_vec(values)
+}
+
+/// Combines every element with `operand`: `op` 0 adds it, 1 subtracts it,
+/// 2 multiplies by it and 3 divides by it. Dividing by zero, or an unknown
+/// `op`, leaves the array unchanged with a warning.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn map_array(arr: *mut NaldomArray, op: i64, operand: i64) {
+    let operand = operand as f64;
+    let apply: fn(f64, f64) -> f64 = match op {
+        0 => |value, operand| value + operand,
+        1 => |value, operand| value - operand,
+        2 => |value, operand| value * operand,
+        3 if operand == 0.0 => {
+            eprintln!("Runtime Warning: cannot divide by zero; the array is unchanged.");
+            return;
+        }
+        3 => |value, operand| value / operand,
+        _ => {
+            eprintln!(
+                "Runtime Warning: unknown operation {}; the array is unchanged.",
+                op
+            );
+            return;
+        }
+    };
+    unsafe {
+        NaldomArray::update(arr, |values| {
+            for value in values.iter_mut() {
+                *value = apply(*value, operand);
+            }
+        })
+    };
+}
+
+/// Returns a new array holding the elements of `arr` as floating-point
+/// numbers. They already are, so this is a copy; it exists so that a
+/// conversion back from whole numbers allocates like any other. A null array
+/// converts to an empty one. Returns null if the new array cannot be
+/// allocated.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_to_float(arr: *const NaldomArray) -> *mut NaldomArray {
+    let values = unsafe { NaldomArray::values(arr) }.unwrap_or_default();
+    convert_values(values, |value| value)
+}
+
+/// Returns a new array holding the elements of `arr` truncated toward zero
+/// to whole numbers. A null array converts to an empty one. Returns null if
+/// the new array cannot be allocated.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_to_int(arr: *const NaldomArray) -> *mut NaldomArray {
+    let values = unsafe { NaldomArray::values(arr) }.unwrap_or_default();
+    // `+ 0.0` turns -0.0 into 0.0, so -0.5 prints as `0`.
+    convert_values(values, |value| value.trunc() + 0.0)
+}
+
+/// A new array holding `convert` applied to each of `values`.
+fn convert_values(values: &[f64], convert: fn(f64) -> f64) -> *mut NaldomArray {
+    let mut converted = match allocate_elements(i64::try_from(values.len()).unwrap_or(i64::MAX)) {
+        Ok(converted) => converted,
+        Err(reason) => {
+            runtime_error(&reason);
+            return std::ptr::null_mut();
+        }
+    };
+    converted.extend(values.iter().map(|&value| convert(value)));
+    NaldomArray::from_vec(converted)
+}
+
+/// Arrays longer than twice this are printed as their first and last
+/// `PRINT_EDGE` elements, unless the full array is requested.
+pub const PRINT_EDGE: usize = 20;
+
+/// Prints the array as one logical, immediately flushed block. Long arrays
+/// are truncated unless `full` is 1. Inside [`crate::embed::run_embedded`]
+/// the host also gets a copy of every element.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn print_array(arr: *const NaldomArray, full: i64) {
+    let Some(values) = (unsafe { NaldomArray::values(arr) }) else {
+        return;
+    };
+    record_printed_array(values);
+    print_logical(&format_array(values, full == 1, 2));
+}
+
+/// Prints an array of whole numbers, as converted by `array_to_int`, like
+/// `print_array` but without decimals.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn print_int_array(arr: *const NaldomArray, full: i64) {
+    let Some(values) = (unsafe { NaldomArray::values(arr) }) else {
+        return;
+    };
+    record_printed_array(values);
+    print_logical(&format_array(values, full == 1, 0));
+}
+
+/// Returns the sum of the elements, rounded to the nearest integer (the
+/// elements themselves are fractional). A null array sums to 0.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sum_array(arr: *const NaldomArray) -> i64 {
+    let Some(values) = (unsafe { NaldomArray::values(arr) }) else {
+        return 0;
+    };
+    values.iter().sum::<f64>().round() as i64
+}
+
+/// Returns the number of elements. A null array has none.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_length(arr: *const NaldomArray) -> i64 {
+    unsafe { NaldomArray::values(arr) }.map_or(0, |values| values.len() as i64)
+}
+
+/// Returns the smallest element, rounded to the nearest integer. A null or
+/// empty array yields 0.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn min_array(arr: *const NaldomArray) -> i64 {
+    let values = unsafe { NaldomArray::values(arr) }.unwrap_or_default();
+    values
+        .iter()
+        .copied()
+        .reduce(f64::min)
+        .unwrap_or(0.0)
+        .round() as i64
+}
+
+/// Returns the largest element, rounded to the nearest integer. A null or
+/// empty array yields 0.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn max_array(arr: *const NaldomArray) -> i64 {
+    let values = unsafe { NaldomArray::values(arr) }.unwrap_or_default();
+    values
+        .iter()
+        .copied()
+        .reduce(f64::max)
+        .unwrap_or(0.0)
+        .round() as i64
+}
+
+/// Returns the mean of the elements. A null or empty array yields 0.0.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn average_array(arr: *const NaldomArray) -> f64 {
+    let values = unsafe { NaldomArray::values(arr) }.unwrap_or_default();
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Returns the median of the elements: the middle one, or the mean of the
+/// middle two for an even count. A null or empty array yields 0.0. The
+/// array itself is left in its order.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn median_array(arr: *const NaldomArray) -> f64 {
+    let mut values = unsafe { NaldomArray::values(arr) }
+        .unwrap_or_default()
+        .to_vec();
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_unstable_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Returns 1 if an element equals `value`, 0 otherwise. A null array holds
+/// nothing.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_contains(arr: *const NaldomArray, value: i64) -> i32 {
+    let values = unsafe { NaldomArray::values(arr) }.unwrap_or_default();
+    i32::from(values.contains(&(value as f64)))
+}
+
+/// Returns the index of the first element equal to `value`, or -1 if no
+/// element is. A null array holds nothing.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn find_index(arr: *const NaldomArray, value: i64) -> i64 {
+    let values = unsafe { NaldomArray::values(arr) }.unwrap_or_default();
+    values
+        .iter()
+        .position(|element| *element == value as f64)
+        .map_or(-1, |index| index as i64)
+}
+
+/// Returns 1 if the elements are in ascending order, 0 otherwise. A null or
+/// empty array counts as sorted.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn is_sorted(arr: *const NaldomArray) -> i32 {
+    let values = unsafe { NaldomArray::values(arr) }.unwrap_or_default();
+    i32::from(values.is_sorted_by(|a, b| a.total_cmp(b).is_le()))
+}
+
+/// Prints a one-line summary of the array under `name`: its length, its
+/// smallest and largest elements rounded to whole numbers, and whether it is
+/// sorted ascending. Nothing is printed for a null array.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array
+/// constructors, and `name` must be a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn describe_array(arr: *const NaldomArray, name: *const c_char) {
+    let Some(values) = (unsafe { NaldomArray::values(arr) }) else {
+        return;
+    };
+    let name = unsafe { CStr::from_ptr(name) }.to_string_lossy();
+    print_logical(&describe(values, &name));
+}
+
+/// The sentence `describe_array` prints for `values`.
+fn describe(values: &[f64], name: &str) -> String {
+    let count = match values.len() {
+        1 => "1 element".to_string(),
+        len => format!("{} elements", len),
+    };
+    let sorted = if values.is_sorted_by(|a, b| a.total_cmp(b).is_le()) {
+        "yes"
+    } else {
+        "no"
+    };
+    let min = values.iter().copied().reduce(f64::min);
+    let max = values.iter().copied().reduce(f64::max);
+    match min.zip(max) {
+        Some((min, max)) => format!(
+            "Array {}: {}, min {}, max {}, sorted ascending: {}\n",
+            name,
+            count,
+            min.round() as i64,
+            max.round() as i64,
+            sorted
+        ),
+        None => format!("Array {}: {}, sorted ascending: {}\n", name, count, sorted),
+    }
+}
+
+/// Returns 1 if the array has no elements or is null, 0 otherwise.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn is_empty(arr: *const NaldomArray) -> i32 {
+    let values = unsafe { NaldomArray::values(arr) }.unwrap_or_default();
+    i32::from(values.is_empty())
+}
+
+/// Prints `value` on a line of its own, e.g. the total from `sum_array`.
+#[unsafe(no_mangle)]
+pub extern "C" fn print_integer(value: i64) {
+    print_logical(&format!("{}\n", value));
+}
+
+/// Prints `true` for a non-zero `value` and `false` for zero on a line of its
+/// own, e.g. the answer from `array_contains`.
+#[unsafe(no_mangle)]
+pub extern "C" fn print_bool(value: i32) {
+    print_logical(if value != 0 { "true\n" } else { "false\n" });
+}
+
+/// Prints `value` with two decimals on a line of its own, e.g. the mean from
+/// `average_array`.
+#[unsafe(no_mangle)]
+pub extern "C" fn print_float(value: f64) {
+    print_logical(&format!("{:.2}\n", value));
+}
+
+/// Renders the block printed by `print_array`, with `decimals` digits after
+/// the point.
+pub(crate) fn format_array(values: &[f64], full: bool, decimals: usize) -> String {
+    let mut text = String::from("\n--- Naldom Native Output ---\n[");
+    if full || values.len() <= 2 * PRINT_EDGE {
+        write_values(&mut text, values, decimals);
+    } else {
+        let omitted = values.len() - 2 * PRINT_EDGE;
+        write_values(&mut text, &values[..PRINT_EDGE], decimals);
+        let _ = write!(text, ", … ({} omitted), ", group_thousands(omitted));
+        write_values(&mut text, &values[values.len() - PRINT_EDGE..], decimals);
+    }
+    text.push_str("]\n--------------------------\n\n");
+    text
+}
+
+/// Appends `values` as a comma-separated list.
+pub(crate) fn write_values(text: &mut String, values: &[f64], decimals: usize) {
+    for (i, value) in values.iter().enumerate() {
+        let separator = if i + 1 == values.len() { "" } else { ", " };
+        let _ = write!(text, "{:.*}{}", decimals, value, separator);
+    }
+}
+
+/// Formats `n` with comma thousands separators: 9999960 -> "9,999,960".
+fn group_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error_mode::ContinueModeGuard;
+    use crate::sort::PARALLEL_SORT_MIN_LEN;
+
+    #[test]
+    fn test_format_array_matches_native_output() {
+        assert_eq!(
+            format_array(&[1.0, 2.5, 30.125], false, 2),
+            "\n--- Naldom Native Output ---\n[1.00, 2.50, 30.12]\n--------------------------\n\n"
+        );
+    }
+
+    #[test]
+    fn test_long_arrays_are_truncated_unless_full() {
+        // Arrange
+        let values: Vec<f64> = (0..100).map(f64::from).collect();
+
+        // Act
+        let truncated = format_array(&values, false, 2);
+        let full = format_array(&values, true, 2);
+
+        // Assert
+        assert!(truncated.contains("[0.00, 1.00, "));
+        assert!(truncated.contains(", 19.00, … (60 omitted), 80.00, "));
+        assert!(truncated.contains(", 99.00]"));
+        assert!(!truncated.contains("20.00"));
+        assert!(full.contains(", 50.00, "));
+        assert!(!full.contains("omitted"));
+        assert_eq!(group_thousands(9_999_960), "9,999,960");
+        assert_eq!(group_thousands(960), "960");
+    }
+
+    #[test]
+    fn test_create_and_sort_array() {
+        // Arrange
+        let arr = create_random_array(20);
+
+        // Act
+        unsafe { sort_array(arr, 1, 0) };
+
+        // Assert
+        let values = unsafe { NaldomArray::values(arr) }.unwrap();
+        assert_eq!(values.len(), 20);
+        assert!(values.iter().all(|v| (0.0..100.0).contains(v)));
+        assert!(values.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn test_a_named_algorithm_sorts_like_the_default() {
+        for algorithm in 1..=3 {
+            // Arrange
+            naldom_seed_random(algorithm as u64);
+            let arr = create_random_array(50);
+            let mut expected = unsafe { NaldomArray::values(arr) }.unwrap().to_vec();
+            expected.sort_by(|a, b| a.total_cmp(b));
+
+            // Act
+            unsafe { sort_array(arr, 0, algorithm) };
+
+            // Assert
+            assert_eq!(unsafe { NaldomArray::values(arr) }.unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_random_int_arrays_hold_whole_numbers() {
+        // Arrange
+        naldom_seed_random(11);
+
+        // Act
+        let arr = create_random_int_array(40);
+
+        // Assert
+        let values = unsafe { NaldomArray::values(arr) }.unwrap();
+        assert_eq!(values.len(), 40);
+        assert!(
+            values
+                .iter()
+                .all(|v| v.fract() == 0.0 && (0.0..100.0).contains(v))
+        );
+    }
+
+    #[test]
+    fn test_long_arrays_sort_like_short_ones() {
+        // Arrange
+        naldom_seed_random(3);
+        let arr = create_random_array(PARALLEL_SORT_MIN_LEN as i64 + 1);
+        let mut expected = unsafe { NaldomArray::values(arr) }.unwrap().to_vec();
+        expected.sort_by(|a, b| a.total_cmp(b));
+
+        // Act
+        unsafe { sort_array(arr, 0, 0) };
+
+        // Assert
+        assert_eq!(unsafe { NaldomArray::values(arr) }.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_seeded_arrays_are_reproducible() {
+        // Arrange
+        let first_run = || {
+            naldom_seed_random(7);
+            let a = create_random_array(3);
+            let b = create_random_array(3);
+            unsafe {
+                [
+                    NaldomArray::values(a).unwrap(),
+                    NaldomArray::values(b).unwrap(),
+                ]
+                .concat()
+            }
+        };
+
+        // Act
+        let first = first_run();
+        let second = first_run();
+
+        // Assert
+        assert_eq!(first, second);
+        assert_ne!(first[..3], first[3..]);
+    }
+
+    #[test]
+    fn test_sum_array_rounds_the_total() {
+        // Arrange
+        let mut data = [1.25, 2.5, 30.125];
+        let arr = NaldomArray {
+            data: data.as_mut_ptr(),
+            size: 3,
+            capacity: 3,
+        };
+
+        // Act
+        let total = unsafe { sum_array(&arr) };
+
+        // Assert
+        assert_eq!(total, 34);
+        assert_eq!(unsafe { sum_array(std::ptr::null()) }, 0);
+    }
+
+    #[test]
+    fn test_min_and_max_round_the_extremes() {
+        // Arrange
+        let mut data = [12.5, 3.4, 97.6];
+        let arr = NaldomArray {
+            data: data.as_mut_ptr(),
+            size: 3,
+            capacity: 3,
+        };
+
+        // Act
+        let (min, max) = unsafe { (min_array(&arr), max_array(&arr)) };
+
+        // Assert
+        assert_eq!((min, max), (3, 98));
+        assert_eq!(unsafe { min_array(std::ptr::null()) }, 0);
+    }
+
+    #[test]
+    fn test_array_length_counts_the_elements() {
+        // Arrange
+        let arr = create_random_array(7);
+
+        // Act
+        let length = unsafe { array_length(arr) };
+
+        // Assert
+        assert_eq!(length, 7);
+        assert_eq!(unsafe { array_length(std::ptr::null()) }, 0);
+    }
+
+    #[test]
+    fn test_average_and_median_of_the_elements() {
+        // Arrange
+        let odd = unsafe { create_array_from_values([4i64, 1, 3].as_ptr(), 3) };
+        let even = unsafe { create_array_from_values([4i64, 1, 3, 10].as_ptr(), 4) };
+
+        // Act
+        let (odd_average, odd_median) = unsafe { (average_array(odd), median_array(odd)) };
+        let (even_average, even_median) = unsafe { (average_array(even), median_array(even)) };
+
+        // Assert
+        assert!((odd_average - 8.0 / 3.0).abs() < 1e-12);
+        assert_eq!(odd_median, 3.0);
+        assert_eq!(even_average, 4.5);
+        assert_eq!(even_median, 3.5);
+        assert_eq!(
+            unsafe { NaldomArray::values(even) }.unwrap(),
+            &[4.0, 1.0, 3.0, 10.0]
+        );
+        assert_eq!(unsafe { average_array(std::ptr::null()) }, 0.0);
+        assert_eq!(unsafe { median_array(std::ptr::null()) }, 0.0);
+    }
+
+    #[test]
+    fn test_array_contains_finds_listed_values() {
+        // Arrange
+        let arr = unsafe { create_array_from_values([4i64, -1, 17].as_ptr(), 3) };
+
+        // Act
+        let (found, missing) = unsafe { (array_contains(arr, 17), array_contains(arr, 5)) };
+
+        // Assert
+        assert_eq!(found, 1);
+        assert_eq!(missing, 0);
+        assert_eq!(unsafe { array_contains(std::ptr::null(), 17) }, 0);
+    }
+
+    #[test]
+    fn test_find_index_returns_the_first_position_or_minus_one() {
+        // Arrange
+        let arr = unsafe { create_array_from_values([4i64, 42, 7, 42].as_ptr(), 4) };
+
+        // Act
+        let (found, missing) = unsafe { (find_index(arr, 42), find_index(arr, 5)) };
+
+        // Assert
+        assert_eq!(found, 1);
+        assert_eq!(missing, -1);
+        assert_eq!(unsafe { find_index(std::ptr::null(), 42) }, -1);
+    }
+
+    #[test]
+    fn test_reverse_array_in_place() {
+        // Arrange
+        let mut data = [1.0, 2.0, 3.0];
+        let mut arr = NaldomArray {
+            data: data.as_mut_ptr(),
+            size: 3,
+            capacity: 3,
+        };
+
+        // Act
+        unsafe { reverse_array(&mut arr) };
+
+        // Assert
+        assert_eq!(data, [3.0, 2.0, 1.0]);
+        unsafe { reverse_array(std::ptr::null_mut()) };
+    }
+
+    #[test]
+    fn test_append_grows_the_array() {
+        // Arrange
+        let arr = unsafe { create_array_from_values([1, 2, 3].as_ptr(), 3) };
+
+        // Act
+        unsafe {
+            append_to_array(arr, 4);
+            append_to_array(arr, 5);
+            append_to_array(std::ptr::null_mut(), 6);
+        }
+
+        // Assert
+        assert_eq!(
+            unsafe { NaldomArray::values(arr) },
+            Some(&[1.0, 2.0, 3.0, 4.0, 5.0][..])
+        );
+        assert!(unsafe { (*arr).capacity } >= 5);
+    }
+
+    #[test]
+    fn test_fill_overwrites_every_element() {
+        // Arrange
+        let arr = unsafe { create_array_from_values([1, 2, 3].as_ptr(), 3) };
+
+        // Act
+        unsafe {
+            fill_array(arr, 0);
+            fill_array(std::ptr::null_mut(), 7);
+        }
+
+        // Assert
+        assert_eq!(
+            unsafe { NaldomArray::values(arr) },
+            Some(&[0.0, 0.0, 0.0][..])
+        );
+    }
+
+    #[test]
+    fn test_cleared_arrays_print_as_empty_and_can_grow_again() {
+        // Arrange
+        let arr = unsafe { create_array_from_values([1, 2, 3].as_ptr(), 3) };
+
+        // Act
+        unsafe { clear_array(arr) };
+        let printed = format_array(unsafe { NaldomArray::values(arr) }.unwrap(), false, 2);
+        unsafe {
+            clear_array(std::ptr::null_mut());
+            append_to_array(arr, 4);
+        }
+
+        // Assert
+        assert!(printed.contains("\n[]\n"));
+        assert_eq!(unsafe { NaldomArray::values(arr) }, Some(&[4.0][..]));
+    }
+
+    #[test]
+    fn test_slice_array_clamps_out_of_range_bounds() {
+        // Arrange
+        let values = [1i64, 2, 3, 4, 5];
+        let (first, middle, clamped, emptied) = unsafe {
+            (
+                create_array_from_values(values.as_ptr(), 5),
+                create_array_from_values(values.as_ptr(), 5),
+                create_array_from_values(values.as_ptr(), 5),
+                create_array_from_values(values.as_ptr(), 5),
+            )
+        };
+
+        // Act
+        unsafe {
+            slice_array(first, 0, 3);
+            slice_array(middle, 1, 4);
+            slice_array(clamped, -2, 99);
+            slice_array(emptied, 4, 2);
+            slice_array(std::ptr::null_mut(), 0, 1);
+        }
+
+        // Assert
+        let values = |arr| unsafe { NaldomArray::values(arr) }.unwrap().to_vec();
+        assert_eq!(values(first), [1.0, 2.0, 3.0]);
+        assert_eq!(values(middle), [2.0, 3.0, 4.0]);
+        assert_eq!(values(clamped), [1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert!(values(emptied).is_empty());
+    }
+
+    #[test]
+    fn test_concat_arrays_copies_both_in_order() {
+        // Arrange
+        let first = unsafe { create_array_from_values([1i64, 2].as_ptr(), 2) };
+        let second = unsafe { create_array_from_values([3i64, 4, 5].as_ptr(), 3) };
+
+        // Act
+        let joined = unsafe { concat_arrays(first, second) };
+        let with_null = unsafe { concat_arrays(std::ptr::null(), second) };
+
+        // Assert
+        assert_eq!(
+            unsafe { NaldomArray::values(joined) },
+            Some(&[1.0, 2.0, 3.0, 4.0, 5.0][..])
+        );
+        assert_eq!(
+            unsafe { NaldomArray::values(with_null) },
+            Some(&[3.0, 4.0, 5.0][..])
+        );
+        assert_eq!(unsafe { NaldomArray::values(first) }, Some(&[1.0, 2.0][..]));
+    }
+
+    #[test]
+    fn test_copies_are_independent_of_the_original() {
+        // Arrange
+        let original = unsafe { create_array_from_values([3i64, 1, 2].as_ptr(), 3) };
+
+        // Act
+        let copy = unsafe { copy_array(original) };
+        unsafe { sort_array(copy, 0, 0) };
+        let empty = unsafe { copy_array(std::ptr::null()) };
+
+        // Assert
+        assert_ne!(copy, original);
+        assert_eq!(
+            unsafe { NaldomArray::values(copy) },
+            Some(&[1.0, 2.0, 3.0][..])
+        );
+        assert_eq!(
+            unsafe { NaldomArray::values(original) },
+            Some(&[3.0, 1.0, 2.0][..])
+        );
+        assert_eq!(unsafe { NaldomArray::values(empty) }, Some(&[][..]));
+    }
+
+    #[test]
+    fn test_array_conversions_copy_and_truncate_toward_zero() {
+        // Arrange
+        let values = [2.75, -1.5, -0.25, 4.0];
+        let arr = NaldomArray::from_vec(values.to_vec());
+
+        // Act
+        let ints = unsafe { array_to_int(arr) };
+        let floats = unsafe { array_to_float(ints) };
+        let from_null = unsafe { array_to_int(std::ptr::null()) };
+
+        // Assert
+        assert_eq!(
+            unsafe { NaldomArray::values(ints) },
+            Some(&[2.0, -1.0, 0.0, 4.0][..])
+        );
+        assert_eq!(
+            unsafe { NaldomArray::values(floats) },
+            Some(&[2.0, -1.0, 0.0, 4.0][..])
+        );
+        assert_eq!(unsafe { NaldomArray::values(from_null) }, Some(&[][..]));
+        assert_eq!(unsafe { NaldomArray::values(arr) }, Some(&values[..]));
+        assert_eq!(
+            format_array(&[2.0, -1.0, 0.0], false, 0),
+            "\n--- Naldom Native Output ---\n[2, -1, 0]\n--------------------------\n\n"
+        );
+    }
+
+    #[test]
+    fn test_map_array_applies_each_operation_and_skips_division_by_zero() {
+        // Arrange
+        let values = [2i64, 4, 6];
+        let arrays: Vec<*mut NaldomArray> = (0..5)
+            .map(|_| unsafe { create_array_from_values(values.as_ptr(), 3) })
+            .collect();
+
+        // Act
+        unsafe {
+            map_array(arrays[0], 0, 10);
+            map_array(arrays[1], 1, 1);
+            map_array(arrays[2], 2, 3);
+            map_array(arrays[3], 3, 4);
+            map_array(arrays[4], 3, 0);
+            map_array(std::ptr::null_mut(), 2, 3);
+        }
+
+        // Assert
+        let values = |arr| unsafe { NaldomArray::values(arr) }.unwrap().to_vec();
+        assert_eq!(values(arrays[0]), [12.0, 14.0, 16.0]);
+        assert_eq!(values(arrays[1]), [1.0, 3.0, 5.0]);
+        assert_eq!(values(arrays[2]), [6.0, 12.0, 18.0]);
+        assert_eq!(values(arrays[3]), [0.5, 1.0, 1.5]);
+        assert_eq!(values(arrays[4]), [2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn test_is_sorted_and_is_empty() {
+        // Arrange
+        let mut ascending = [1.0, 2.0, 2.0, 3.0];
+        let mut shuffled = [2.0, 1.0, 3.0];
+        let array = |data: &mut [f64]| NaldomArray {
+            data: data.as_mut_ptr(),
+            size: data.len() as i64,
+            capacity: data.len() as i64,
+        };
+        let ascending = array(&mut ascending);
+        let shuffled = array(&mut shuffled);
+
+        // Act & Assert
+        unsafe {
+            assert_eq!(is_sorted(&ascending), 1);
+            assert_eq!(is_sorted(&shuffled), 0);
+            assert_eq!(is_sorted(std::ptr::null()), 1);
+            assert_eq!(is_empty(&shuffled), 0);
+            assert_eq!(is_empty(std::ptr::null()), 1);
+        }
+    }
+
+    #[test]
+    fn test_descriptions_state_the_range_and_order() {
+        // Act
+        let sorted = describe(&[3.0, 12.4, 50.0, 96.6], "var_0");
+        let unsorted = describe(&[4.0, 17.0, 3.0], "scores");
+        let single = describe(&[7.0], "one");
+        let empty = describe(&[], "none");
+
+        // Assert
+        assert_eq!(
+            sorted,
+            "Array var_0: 4 elements, min 3, max 97, sorted ascending: yes\n"
+        );
+        assert_eq!(
+            unsorted,
+            "Array scores: 3 elements, min 3, max 17, sorted ascending: no\n"
+        );
+        assert_eq!(
+            single,
+            "Array one: 1 element, min 7, max 7, sorted ascending: yes\n"
+        );
+        assert_eq!(empty, "Array none: 0 elements, sorted ascending: yes\n");
+    }
+
+    #[test]
+    fn test_describing_a_null_array_prints_nothing() {
+        unsafe { describe_array(std::ptr::null(), c"var_0".as_ptr()) };
+    }
+
+    #[test]
+    fn test_impossible_sizes_yield_null_arrays() {
+        // Arrange
+        let _continue = ContinueModeGuard::new();
+
+        // Act
+        let huge = create_random_array(i64::MAX);
+
+        // Assert
+        assert!(huge.is_null());
+        unsafe {
+            sort_array(huge, 0, 0);
+            print_array(huge, 0);
+        }
+        assert!(allocate_elements(-5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_create_array_from_values_copies_them() {
+        // Arrange
+        let values = [3, -1, 4];
+
+        // Act
+        let arr = unsafe { create_array_from_values(values.as_ptr(), 3) };
+        let empty = unsafe { create_array_from_values(std::ptr::null(), 0) };
+
+        // Assert
+        assert_eq!(
+            unsafe { NaldomArray::values(arr) },
+            Some(&[3.0, -1.0, 4.0][..])
+        );
+        assert_eq!(unsafe { NaldomArray::values(empty) }, Some(&[][..]));
+    }
+
+    #[test]
+    fn test_negative_size_is_not_reinterpreted_as_huge() {
+        let mut data = [1.0];
+        let arr = NaldomArray {
+            data: data.as_mut_ptr(),
+            size: -1,
+            capacity: 1,
+        };
+        assert!(unsafe { NaldomArray::values(&arr) }.is_none());
+    }
+}
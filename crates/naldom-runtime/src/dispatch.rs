@@ -0,0 +1,156 @@
+// crates/naldom-runtime/src/dispatch.rs
+
+//! The dispatcher behind `naldom-cli --multi`: one executable holding several
+//! programs, selected by name with the first command-line argument.
+//!
+//! The generated `main` registers every program with
+//! `naldom_register_program`, then hands `argc`/`argv` to `naldom_dispatch`.
+
+use std::ffi::{CStr, c_char};
+use std::sync::Mutex;
+
+/// A compiled program's entry function.
+pub type EntryFn = extern "C" fn();
+
+static PROGRAMS: Mutex<Vec<(String, EntryFn)>> = Mutex::new(Vec::new());
+
+/// Makes `entry` runnable as `<executable> <name>`.
+///
+/// # Safety
+/// `name` must be a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn naldom_register_program(name: *const c_char, entry: EntryFn) {
+    if name.is_null() {
+        return;
+    }
+    let name = unsafe { CStr::from_ptr(name) }
+        .to_string_lossy()
+        .into_owned();
+    PROGRAMS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push((name, entry));
+}
+
+/// Runs the registered program named by `argv[1]` and returns the exit status:
+/// 0 once it finishes, or 2 after listing the available programs on stderr
+/// when no known name was given.
+///
+/// # Safety
+/// `argv` must point to `argc` valid NUL-terminated strings, as passed to `main`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn naldom_dispatch(argc: i64, argv: *const *const c_char) -> i64 {
+    let requested = (argc >= 2 && !argv.is_null())
+        .then(|| unsafe { *argv.add(1) })
+        .filter(|arg| !arg.is_null())
+        .map(|arg| {
+            unsafe { CStr::from_ptr(arg) }
+                .to_string_lossy()
+                .into_owned()
+        });
+    // Release the lock before running the program.
+    let selected = select(
+        &PROGRAMS.lock().unwrap_or_else(|e| e.into_inner()),
+        requested.as_deref(),
+    );
+    match selected {
+        Ok(entry) => {
+            entry();
+            0
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            2
+        }
+    }
+}
+
+/// The entry of the program called `requested`, or the message listing the
+/// available ones.
+fn select(programs: &[(String, EntryFn)], requested: Option<&str>) -> Result<EntryFn, String> {
+    let available = programs
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let Some(requested) = requested else {
+        return Err(format!(
+            "Usage: pass the name of the program to run.\nAvailable programs: {}",
+            available
+        ));
+    };
+    programs
+        .iter()
+        .find(|(name, _)| name == requested)
+        .map(|&(_, entry)| entry)
+        .ok_or_else(|| {
+            format!(
+                "Unknown program '{}'. Available programs: {}",
+                requested, available
+            )
+        })
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" fn counting_program() {
+        RUNS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    extern "C" fn other_program() {}
+
+    #[test]
+    fn test_select_finds_programs_by_name_and_lists_them_otherwise() {
+        // Arrange
+        let programs: Vec<(String, EntryFn)> = vec![
+            ("demo1".to_string(), counting_program),
+            ("demo2".to_string(), other_program),
+        ];
+
+        // Act
+        let found = select(&programs, Some("demo2"));
+        let unknown = select(&programs, Some("demo3"));
+        let missing = select(&programs, None);
+
+        // Assert
+        assert_eq!(
+            found.map(|entry| entry as usize),
+            Ok(other_program as EntryFn as usize)
+        );
+        assert_eq!(
+            unknown.map(|entry| entry as usize),
+            Err("Unknown program 'demo3'. Available programs: demo1, demo2".to_string())
+        );
+        assert!(
+            missing
+                .unwrap_err()
+                .ends_with("Available programs: demo1, demo2")
+        );
+    }
+
+    #[test]
+    fn test_dispatch_runs_the_registered_program_named_by_argv() {
+        // Arrange
+        let name = CString::new("counting").unwrap();
+        unsafe { naldom_register_program(name.as_ptr(), counting_program) };
+        let args = [CString::new("multi").unwrap(), name.clone()];
+        let argv: Vec<*const c_char> = args.iter().map(|arg| arg.as_ptr()).collect();
+        let before = RUNS.load(Ordering::SeqCst);
+
+        // Act
+        let status = unsafe { naldom_dispatch(2, argv.as_ptr()) };
+        let usage_status = unsafe { naldom_dispatch(1, argv.as_ptr()) };
+
+        // Assert
+        assert_eq!(status, 0);
+        assert_eq!(usage_status, 2);
+        assert_eq!(RUNS.load(Ordering::SeqCst), before + 1);
+    }
+}
@@ -0,0 +1,156 @@
+// crates/naldom-runtime/src/embed.rs
+
+//! Running a compiled program inside a host application.
+//!
+//! A program built with `naldom-cli --crate-type obj` or `staticlib` is
+//! linked into the host together with this runtime. The host calls its
+//! entry function through [`run_embedded`] to get what the program printed
+//! back as data instead of text on the terminal: the output, and a copy of
+//! every array the program printed.
+
+use crate::output::capture_output;
+use std::cell::RefCell;
+
+thread_local! {
+    /// When set, the elements of every array printed on this thread are
+    /// copied here.
+    static PRINTED_ARRAYS: RefCell<Option<Vec<Vec<f64>>>> = const { RefCell::new(None) };
+}
+
+/// What a program run by [`run_embedded`] produced.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EmbeddedRun {
+    /// The entry function's return value.
+    pub exit_code: i32,
+    /// The elements of each array the program printed, in the order it
+    /// printed them, including the elements a shortened print leaves out.
+    pub printed_arrays: Vec<Vec<f64>>,
+    /// Everything the program printed on the calling thread.
+    pub stdout: String,
+}
+
+/// Runs `entry`, typically a compiled program's entry function, and
+/// collects what it prints instead of writing it to stdout.
+///
+/// Only prints on the calling thread are collected. An `Exit` intent ends
+/// the host process as it would end the program's own.
+pub fn run_embedded(entry: impl FnOnce() -> i32) -> EmbeddedRun {
+    /// Stops recording even if `entry` panics.
+    struct EndRecording;
+    impl Drop for EndRecording {
+        fn drop(&mut self) {
+            PRINTED_ARRAYS.with_borrow_mut(|arrays| *arrays = None);
+        }
+    }
+
+    PRINTED_ARRAYS.with_borrow_mut(|arrays| *arrays = Some(Vec::new()));
+    let _end = EndRecording;
+    let mut exit_code = 0;
+    let stdout = capture_output(|| exit_code = entry());
+    EmbeddedRun {
+        exit_code,
+        printed_arrays: PRINTED_ARRAYS
+            .with_borrow_mut(|arrays| arrays.take())
+            .unwrap_or_default(),
+        stdout,
+    }
+}
+
+/// Keeps a copy of `values` for the [`run_embedded`] call in progress on
+/// this thread, if any.
+pub(crate) fn record_printed_array(values: &[f64]) {
+    PRINTED_ARRAYS.with_borrow_mut(|arrays| {
+        if let Some(arrays) = arrays {
+            arrays.push(values.to_vec());
+        }
+    });
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::array::{
+        create_array_from_values, create_random_array, naldom_seed_random, print_array,
+        print_int_array, sort_array,
+    };
+
+    /// The numbers between the brackets of each printed array.
+    fn parse_printed_arrays(stdout: &str) -> Vec<Vec<f64>> {
+        stdout
+            .lines()
+            .filter_map(|line| line.strip_prefix('[')?.strip_suffix(']'))
+            .map(|list| list.split(", ").map(|n| n.parse().unwrap()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_the_canonical_program_hands_back_its_sorted_array() {
+        // Arrange: what the compiled "create 10 random numbers, print them,
+        // sort them and print them again" does.
+        let entry = || {
+            naldom_seed_random(7);
+            let arr = create_random_array(10);
+            unsafe {
+                print_array(arr, 0);
+                sort_array(arr, 0, 0);
+                print_array(arr, 0);
+            }
+            0
+        };
+
+        // Act
+        let run = run_embedded(entry);
+
+        // Assert
+        assert_eq!(run.exit_code, 0);
+        assert_eq!(run.printed_arrays.len(), 2);
+        let sorted = &run.printed_arrays[1];
+        assert!(sorted.is_sorted());
+        let mut unsorted = run.printed_arrays[0].clone();
+        unsorted.sort_by(f64::total_cmp);
+        assert_eq!(&unsorted, sorted);
+        let printed = parse_printed_arrays(&run.stdout);
+        assert_eq!(printed.len(), 2);
+        for (parsed, recorded) in printed[1].iter().zip(sorted) {
+            assert!(
+                (parsed - recorded).abs() <= 0.005,
+                "{} vs {}",
+                parsed,
+                recorded
+            );
+        }
+    }
+
+    #[test]
+    fn test_shortened_prints_still_hand_back_every_element() {
+        // Arrange
+        let values: Vec<i64> = (0..100).collect();
+
+        // Act
+        let run = run_embedded(|| {
+            let arr = unsafe { create_array_from_values(values.as_ptr(), values.len() as i64) };
+            unsafe { print_int_array(arr, 0) };
+            3
+        });
+
+        // Assert
+        assert_eq!(run.exit_code, 3);
+        assert!(run.stdout.contains("omitted"));
+        assert_eq!(
+            run.printed_arrays,
+            [(0..100).map(|n| n as f64).collect::<Vec<_>>()]
+        );
+    }
+
+    #[test]
+    fn test_arrays_are_only_recorded_during_a_run() {
+        // Act
+        record_printed_array(&[1.0]);
+        let run = run_embedded(|| 0);
+
+        // Assert
+        assert!(run.printed_arrays.is_empty());
+        PRINTED_ARRAYS.with_borrow(|arrays| assert!(arrays.is_none()));
+    }
+}
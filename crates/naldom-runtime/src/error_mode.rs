@@ -0,0 +1,100 @@
+// crates/naldom-runtime/src/error_mode.rs
+
+//! What the runtime does when an operation fails, e.g. an array that does
+//! not fit in memory. By default the program stops; a program built with
+//! `--on-error continue` warns instead and carries on with a neutral result.
+//!
+//! The mode is set once, at program start, and holds for the whole process.
+
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// Print the error and end the program. The default.
+pub const ERROR_MODE_ABORT: i32 = 0;
+/// Print a warning and carry on with a neutral result: a null array, which
+/// every array function treats as empty.
+pub const ERROR_MODE_CONTINUE: i32 = 1;
+
+/// The exit status of a program a runtime error ended.
+pub const RUNTIME_ERROR_EXIT_CODE: i32 = 1;
+
+static ERROR_MODE: AtomicI32 = AtomicI32::new(ERROR_MODE_ABORT);
+
+/// Selects how the following runtime errors are handled. Codes other than
+/// [`ERROR_MODE_CONTINUE`] abort.
+#[unsafe(no_mangle)]
+pub extern "C" fn naldom_set_error_mode(mode: i32) {
+    ERROR_MODE.store(mode, Ordering::Relaxed);
+}
+
+/// Whether a program in error mode `mode` carries on after an error.
+fn continues(mode: i32) -> bool {
+    mode == ERROR_MODE_CONTINUE
+}
+
+/// Reports that an operation failed because of `reason`. Ends the program
+/// unless it runs in continue mode, in which case the caller goes on with
+/// its neutral result.
+pub(crate) fn runtime_error(reason: &str) {
+    if continues(ERROR_MODE.load(Ordering::Relaxed)) {
+        eprintln!("Runtime Warning: {}; continuing.", reason);
+        return;
+    }
+    eprintln!("Runtime Error: {}.", reason);
+    crate::exit::naldom_exit(RUNTIME_ERROR_EXIT_CODE)
+}
+
+/// Holds the process in continue mode for one test and restores the
+/// previous mode when dropped. Tests that take one run one at a time, so a
+/// test finishing early cannot switch another back to abort mid-run.
+#[cfg(test)]
+pub(crate) struct ContinueModeGuard {
+    previous: i32,
+    _exclusive: std::sync::MutexGuard<'static, ()>,
+}
+
+#[cfg(test)]
+impl ContinueModeGuard {
+    pub(crate) fn new() -> Self {
+        static EXCLUSIVE: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        let exclusive = EXCLUSIVE.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = ERROR_MODE.swap(ERROR_MODE_CONTINUE, Ordering::Relaxed);
+        Self {
+            previous,
+            _exclusive: exclusive,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Drop for ContinueModeGuard {
+    fn drop(&mut self) {
+        ERROR_MODE.store(self.previous, Ordering::Relaxed);
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_the_continue_code_carries_on() {
+        assert!(continues(ERROR_MODE_CONTINUE));
+        assert!(!continues(ERROR_MODE_ABORT));
+        assert!(!continues(7));
+    }
+
+    #[test]
+    fn test_continue_mode_returns_to_the_caller() {
+        // Arrange
+        let guard = ContinueModeGuard::new();
+
+        // Act
+        runtime_error("the test failed on purpose");
+
+        // Assert: still running, and back in abort mode once the guard goes.
+        assert_eq!(ERROR_MODE.load(Ordering::Relaxed), ERROR_MODE_CONTINUE);
+        drop(guard);
+        assert_eq!(ERROR_MODE.load(Ordering::Relaxed), ERROR_MODE_ABORT);
+    }
+}
@@ -0,0 +1,10 @@
+// crates/naldom-runtime/src/exit.rs
+
+//! Ending a compiled program early, for the `Exit` intent.
+
+/// Ends the process with exit status `code`. Everything printed so far has
+/// been flushed already; the `atexit` handler flushes stdout once more.
+#[unsafe(no_mangle)]
+pub extern "C" fn naldom_exit(code: i32) -> ! {
+    std::process::exit(code)
+}
@@ -0,0 +1,127 @@
+// crates/naldom-runtime/src/histogram.rs
+
+//! Text histograms of arrays, called from compiled Naldom code.
+
+use crate::array::NaldomArray;
+use crate::output::print_logical;
+use std::fmt::Write;
+
+/// The longest bar a histogram prints; longer bars are scaled down.
+const MAX_BAR_WIDTH: usize = 50;
+
+/// Prints a histogram of the array: `buckets` equal-width ranges from the
+/// smallest to the largest element, one line each with a bar of `#` and the
+/// element count. Nothing is printed for a null or empty array, or when
+/// `buckets` is not positive.
+///
+/// # Safety
+/// `arr` must be null or a pointer returned by one of the runtime's array constructors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn print_histogram(arr: *const NaldomArray, buckets: i64) {
+    let Some(values) = (unsafe { NaldomArray::values(arr) }) else {
+        return;
+    };
+    let Ok(buckets) = usize::try_from(buckets) else {
+        return;
+    };
+    if values.is_empty() || buckets == 0 {
+        return;
+    }
+    print_logical(&format_histogram(values, buckets));
+}
+
+/// Renders the histogram of the non-empty `values` in `buckets` rows. The
+/// last range includes the largest element; all others exclude their upper
+/// bound.
+fn format_histogram(values: &[f64], buckets: usize) -> String {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let width = (max - min) / buckets as f64;
+    let mut counts = vec![0usize; buckets];
+    for value in values {
+        // Equal elements leave a width of zero; they all land in the first bucket.
+        let bucket = if width > 0.0 {
+            (((value - min) / width) as usize).min(buckets - 1)
+        } else {
+            0
+        };
+        counts[bucket] += 1;
+    }
+    let largest = counts.iter().copied().max().unwrap_or(0);
+
+    let mut text = String::from("\n--- Naldom Native Output ---\n");
+    for (bucket, count) in counts.iter().enumerate() {
+        let low = min + width * bucket as f64;
+        let high = if bucket + 1 == buckets {
+            max
+        } else {
+            min + width * (bucket + 1) as f64
+        };
+        let close = if bucket + 1 == buckets { ']' } else { ')' };
+        let bar = if largest > MAX_BAR_WIDTH {
+            (count * MAX_BAR_WIDTH).div_ceil(largest)
+        } else {
+            *count
+        };
+        let _ = writeln!(
+            text,
+            "[{:>8.2}, {:>8.2}{} {} {}",
+            low,
+            high,
+            close,
+            "#".repeat(bar),
+            count
+        );
+    }
+    text.push_str("--------------------------\n\n");
+    text
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histograms_count_elements_per_bucket() {
+        // Act
+        let text = format_histogram(&[0.0, 1.0, 2.5, 9.0, 10.0], 2);
+
+        // Assert
+        assert_eq!(
+            text,
+            "\n--- Naldom Native Output ---\n[    0.00,     5.00) ### 3\n[    5.00,    10.00] ## 2\n--------------------------\n\n"
+        );
+    }
+
+    #[test]
+    fn test_equal_elements_share_the_first_bucket() {
+        // Act
+        let text = format_histogram(&[4.0, 4.0, 4.0], 3);
+
+        // Assert
+        assert!(text.contains("[    4.00,     4.00) ### 3\n"));
+        assert!(text.contains("[    4.00,     4.00]  0\n"));
+    }
+
+    #[test]
+    fn test_long_bars_are_scaled_down() {
+        // Arrange
+        let values: Vec<f64> = (0..200).map(|i| if i < 150 { 0.0 } else { 1.0 }).collect();
+
+        // Act
+        let text = format_histogram(&values, 2);
+
+        // Assert
+        assert!(text.contains(&format!(") {} 150\n", "#".repeat(MAX_BAR_WIDTH))));
+        assert!(text.contains(&format!("] {} 50\n", "#".repeat(17))));
+    }
+
+    #[test]
+    fn test_null_arrays_and_zero_buckets_print_nothing() {
+        unsafe {
+            print_histogram(std::ptr::null(), 10);
+            print_histogram(std::ptr::null(), 0);
+        }
+    }
+}
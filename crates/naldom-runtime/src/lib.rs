@@ -1,25 +1,57 @@
 // crates/naldom-runtime/src/lib.rs
 
-use std::time::Duration;
-use tokio::runtime::Runtime;
+pub mod array;
+pub mod dispatch;
+pub mod embed;
+pub mod error_mode;
+pub mod exit;
+pub mod histogram;
+pub mod log;
+pub mod matrix;
+pub mod output;
+pub mod profile;
+pub mod self_test;
+pub mod sort;
+pub mod timer;
 
-lazy_static::lazy_static! {
-    static ref TOKIO_RUNTIME: Runtime = Runtime::new().expect("Failed to create Tokio runtime");
-}
+#[cfg(test)]
+mod abi_tests;
 
 /// A dummy function to force Cargo to link this crate.
 pub fn ensure_linked() {}
 
-/// The FFI function called from compiled Naldom code.
-#[unsafe(no_mangle)]
-pub extern "C" fn naldom_async_sleep(ms: u64) {
-    TOKIO_RUNTIME.block_on(async {
-        tokio::time::sleep(Duration::from_millis(ms)).await;
-    });
+#[cfg(feature = "async-sleep")]
+pub use sleep::naldom_async_sleep;
+
+#[cfg(feature = "async-sleep")]
+mod sleep {
+    use std::sync::OnceLock;
+    use std::time::Duration;
+    use tokio::runtime::{Builder, Runtime};
+
+    /// Built on the first sleep, so programs that never wait pay nothing for
+    /// it. A current-thread runtime starts no worker threads.
+    fn tokio_runtime() -> &'static Runtime {
+        static TOKIO_RUNTIME: OnceLock<Runtime> = OnceLock::new();
+        TOKIO_RUNTIME.get_or_init(|| {
+            Builder::new_current_thread()
+                .enable_time()
+                .build()
+                .expect("Failed to create Tokio runtime")
+        })
+    }
+
+    /// The FFI function called from compiled Naldom code.
+    #[unsafe(no_mangle)]
+    pub extern "C" fn naldom_async_sleep(ms: u64) {
+        tokio_runtime().block_on(async {
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+        });
+    }
 }
 
 // --- Unit Tests ---
-#[cfg(test)]
+#[cfg(all(test, feature = "async-sleep"))]
 mod tests {
     use super::*;
     use std::time::Instant; // FIX: Import the Instant struct
@@ -0,0 +1,61 @@
+// crates/naldom-runtime/src/log.rs
+
+//! Diagnostics the runtime writes to stderr when `NALDOM_RUNTIME_LOG` asks
+//! for them, e.g. `NALDOM_RUNTIME_LOG=info` to see which sort algorithm ran.
+//! Nothing is logged by default, and the program's stdout is never touched.
+
+use std::sync::OnceLock;
+
+/// The environment variable holding the log level.
+pub const LOG_ENV: &str = "NALDOM_RUNTIME_LOG";
+
+/// How much the runtime logs, from least to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Off,
+    Info,
+    Debug,
+}
+
+impl Level {
+    /// Parses a level name; anything unrecognised turns logging off.
+    pub fn parse(name: &str) -> Level {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "info" => Level::Info,
+            "debug" | "trace" => Level::Debug,
+            _ => Level::Off,
+        }
+    }
+}
+
+/// The level from the environment, read once per process.
+fn level() -> Level {
+    static LEVEL: OnceLock<Level> = OnceLock::new();
+    *LEVEL.get_or_init(|| {
+        std::env::var(LOG_ENV)
+            .map(|name| Level::parse(&name))
+            .unwrap_or(Level::Off)
+    })
+}
+
+/// Writes `message()` to stderr at info level. The message is only built
+/// when it will be written.
+pub(crate) fn info(message: impl FnOnce() -> String) {
+    if level() >= Level::Info {
+        eprintln!("Runtime [info]: {}", message());
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_parse_case_insensitively() {
+        assert_eq!(Level::parse("INFO"), Level::Info);
+        assert_eq!(Level::parse(" debug "), Level::Debug);
+        assert_eq!(Level::parse("verbose"), Level::Off);
+        assert!(Level::Debug >= Level::Info);
+    }
+}
@@ -0,0 +1,137 @@
+// crates/naldom-runtime/src/matrix.rs
+
+//! Matrix functions called from compiled Naldom code.
+
+use crate::array::{XorShift, allocate_elements, write_values};
+use crate::error_mode::runtime_error;
+use crate::output::print_logical;
+
+/// A matrix: `rows * cols` elements at `data`, row by row. The layout is
+/// part of the ABI shared with generated code.
+#[repr(C)]
+pub struct NaldomMatrix {
+    pub data: *mut f64,
+    pub rows: i64,
+    pub cols: i64,
+}
+
+impl NaldomMatrix {
+    /// Borrows the elements of `matrix` with its row and column counts, or
+    /// `None` for a null matrix.
+    ///
+    /// # Safety
+    /// `matrix` must be null or a pointer returned by `create_random_matrix`.
+    unsafe fn values<'a>(matrix: *const NaldomMatrix) -> Option<(&'a [f64], usize, usize)> {
+        let matrix = unsafe { matrix.as_ref()? };
+        if matrix.data.is_null() {
+            return None;
+        }
+        let rows = usize::try_from(matrix.rows).ok()?;
+        let cols = usize::try_from(matrix.cols).ok()?;
+        let values = unsafe { std::slice::from_raw_parts(matrix.data, rows.checked_mul(cols)?) };
+        Some((values, rows, cols))
+    }
+
+    /// Moves `values`, `rows * cols` of them, into a newly allocated matrix
+    /// owned by the generated program.
+    fn from_vec(values: Vec<f64>, rows: i64, cols: i64) -> *mut NaldomMatrix {
+        let values = Box::leak(values.into_boxed_slice());
+        Box::into_raw(Box::new(NaldomMatrix {
+            data: values.as_mut_ptr(),
+            rows,
+            cols,
+        }))
+    }
+}
+
+/// Creates a `rows` by `cols` matrix of random numbers between 0.0 and 100.0,
+/// continuing the sequence `create_random_array` draws from. Returns null if
+/// the matrix cannot be allocated.
+#[unsafe(no_mangle)]
+pub extern "C" fn create_random_matrix(rows: i64, cols: i64) -> *mut NaldomMatrix {
+    print_logical(&format!(
+        "Runtime: Creating a {}x{} matrix of random numbers...\n",
+        rows, cols
+    ));
+    let (rows, cols) = (rows.max(0), cols.max(0));
+    let elements = rows
+        .checked_mul(cols)
+        .ok_or_else(|| format!("a {}x{} matrix does not fit in memory", rows, cols))
+        .and_then(|len| Ok((len, allocate_elements(len)?)));
+    let (len, mut values) = match elements {
+        Ok(elements) => elements,
+        Err(reason) => {
+            runtime_error(&reason);
+            return std::ptr::null_mut();
+        }
+    };
+    let mut rng = XorShift::resume();
+    values.extend((0..len).map(|_| rng.next_f64() * 100.0));
+    rng.suspend();
+    NaldomMatrix::from_vec(values, rows, cols)
+}
+
+/// Prints a matrix, one bracketed row per line, with two decimals. Matrices
+/// always print in full.
+///
+/// # Safety
+/// `matrix` must be null or a pointer returned by `create_random_matrix`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn print_matrix(matrix: *const NaldomMatrix) {
+    let Some((values, rows, cols)) = (unsafe { NaldomMatrix::values(matrix) }) else {
+        return;
+    };
+    print_logical(&format_matrix(values, rows, cols));
+}
+
+/// Renders `values` as `rows` lines of `cols` elements.
+fn format_matrix(values: &[f64], rows: usize, cols: usize) -> String {
+    let mut text = String::from("\n--- Naldom Native Output ---\n");
+    for row in 0..rows {
+        text.push('[');
+        write_values(&mut text, &values[row * cols..(row + 1) * cols], 2);
+        text.push_str("]\n");
+    }
+    text.push_str("--------------------------\n\n");
+    text
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::array::naldom_seed_random;
+
+    #[test]
+    fn test_random_matrices_hold_rows_times_cols_elements() {
+        // Arrange
+        naldom_seed_random(5);
+
+        // Act
+        let matrix = create_random_matrix(3, 4);
+
+        // Assert
+        let (values, rows, cols) = unsafe { NaldomMatrix::values(matrix) }.unwrap();
+        assert_eq!((values.len(), rows, cols), (12, 3, 4));
+        assert!(values.iter().all(|v| (0.0..100.0).contains(v)));
+    }
+
+    #[test]
+    fn test_matrices_print_one_row_per_line() {
+        // Act
+        let text = format_matrix(&[1.0, 2.5, 3.0, 4.0, 5.0, 6.125], 2, 3);
+        let empty_rows = format_matrix(&[], 2, 0);
+
+        // Assert
+        assert_eq!(
+            text,
+            "\n--- Naldom Native Output ---\n[1.00, 2.50, 3.00]\n[4.00, 5.00, 6.12]\n--------------------------\n\n"
+        );
+        assert!(empty_rows.contains("\n[]\n[]\n"));
+    }
+
+    #[test]
+    fn test_null_matrices_are_ignored() {
+        unsafe { print_matrix(std::ptr::null()) };
+    }
+}
@@ -0,0 +1,71 @@
+// crates/naldom-runtime/src/output.rs
+
+//! The single path through which compiled programs write to stdout.
+//!
+//! Every logical print is rendered completely, written with one `write_all`,
+//! and flushed immediately. An `atexit` handler flushes once more, because a
+//! C `main` calling `exit` never runs Rust's own stdout cleanup.
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::sync::Once;
+
+static REGISTER_ATEXIT: Once = Once::new();
+
+thread_local! {
+    /// When set, prints on this thread are collected here instead of written.
+    static CAPTURE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+unsafe extern "C" {
+    fn atexit(callback: extern "C" fn()) -> std::ffi::c_int;
+}
+
+/// Runs `callback` when the program exits, including via C `exit`.
+pub(crate) fn at_exit(callback: extern "C" fn()) {
+    // SAFETY: `callback` is a plain `extern "C" fn` with no captured state.
+    unsafe {
+        atexit(callback);
+    }
+}
+
+extern "C" fn flush_at_exit() {
+    let _ = io::stdout().lock().flush();
+}
+
+/// Writes one logical print to stdout and flushes it.
+pub fn print_logical(text: &str) {
+    let captured = CAPTURE.with_borrow_mut(|capture| match capture {
+        Some(buffer) => {
+            buffer.push_str(text);
+            true
+        }
+        None => false,
+    });
+    if captured {
+        return;
+    }
+    REGISTER_ATEXIT.call_once(|| at_exit(flush_at_exit));
+    let mut stdout = io::stdout().lock();
+    let _ = stdout.write_all(text.as_bytes());
+    let _ = stdout.flush();
+}
+
+/// Runs `f` and returns everything it printed on this thread, instead of
+/// writing it to stdout.
+pub fn capture_output(f: impl FnOnce()) -> String {
+    /// Ends the capture even if `f` panics.
+    struct EndCapture;
+    impl Drop for EndCapture {
+        fn drop(&mut self) {
+            CAPTURE.with_borrow_mut(|capture| *capture = None);
+        }
+    }
+
+    CAPTURE.with_borrow_mut(|capture| *capture = Some(String::new()));
+    let _end = EndCapture;
+    f();
+    CAPTURE
+        .with_borrow_mut(|capture| capture.take())
+        .unwrap_or_default()
+}
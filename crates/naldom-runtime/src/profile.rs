@@ -0,0 +1,135 @@
+// crates/naldom-runtime/src/profile.rs
+
+//! The `--profile` runtime: wall-clock time per intent, printed to stderr when
+//! the program exits.
+//!
+//! Compiled programs call `naldom_profile_init` once, then bracket every intent
+//! with `naldom_profile_begin`/`naldom_profile_end`.
+
+use crate::output::at_exit;
+use std::ffi::{CStr, c_char};
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::sync::{Mutex, Once};
+use std::time::{Duration, Instant};
+
+/// The time recorded for one intent.
+#[derive(Debug, Clone, Default)]
+struct IntentTiming {
+    name: String,
+    total: Duration,
+    started: Option<Instant>,
+}
+
+static PROFILE: Mutex<Vec<IntentTiming>> = Mutex::new(Vec::new());
+static REGISTER_REPORT: Once = Once::new();
+
+fn with_profile<T>(f: impl FnOnce(&mut Vec<IntentTiming>) -> T) -> T {
+    // A poisoned lock only means a panic mid-update; the timings are still usable.
+    let mut profile = PROFILE.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut profile)
+}
+
+/// Sizes the timing table and arranges for the report to be printed at exit.
+#[unsafe(no_mangle)]
+pub extern "C" fn naldom_profile_init(intent_count: i64) {
+    with_profile(|profile| {
+        *profile = vec![IntentTiming::default(); intent_count.max(0) as usize];
+    });
+    REGISTER_REPORT.call_once(|| at_exit(report_at_exit));
+}
+
+/// Marks the start of intent `intent_index`. Out-of-range indices are ignored.
+///
+/// # Safety
+/// `intent_name` must be null or a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn naldom_profile_begin(intent_index: i64, intent_name: *const c_char) {
+    let name = if intent_name.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(intent_name) }
+            .to_string_lossy()
+            .into_owned()
+    };
+    with_profile(|profile| {
+        if let Some(timing) = usize::try_from(intent_index)
+            .ok()
+            .and_then(|i| profile.get_mut(i))
+        {
+            timing.name = name;
+            timing.started = Some(Instant::now());
+        }
+    });
+}
+
+/// Marks the end of intent `intent_index`.
+#[unsafe(no_mangle)]
+pub extern "C" fn naldom_profile_end(intent_index: i64) {
+    let now = Instant::now();
+    with_profile(|profile| {
+        if let Some(timing) = usize::try_from(intent_index)
+            .ok()
+            .and_then(|i| profile.get_mut(i))
+            && let Some(started) = timing.started.take()
+        {
+            timing.total += now - started;
+        }
+    });
+}
+
+/// Renders the timing table, one line per intent.
+fn format_report(profile: &[IntentTiming]) -> String {
+    let mut report = String::from("\n--- Naldom Profile ---\n");
+    for (index, timing) in profile.iter().enumerate() {
+        let _ = writeln!(
+            report,
+            "intent {} {}: {:.1} ms",
+            index + 1,
+            timing.name,
+            timing.total.as_secs_f64() * 1000.0
+        );
+    }
+    let total: Duration = profile.iter().map(|timing| timing.total).sum();
+    let _ = writeln!(report, "total: {:.1} ms", total.as_secs_f64() * 1000.0);
+    report
+}
+
+extern "C" fn report_at_exit() {
+    let report = with_profile(|profile| format_report(profile));
+    let mut stderr = std::io::stderr().lock();
+    let _ = stderr.write_all(report.as_bytes());
+    let _ = stderr.flush();
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_report_lists_every_intent() {
+        // Arrange
+        let profile = vec![
+            IntentTiming {
+                name: "CreateArray".to_string(),
+                total: Duration::from_micros(3200),
+                started: None,
+            },
+            IntentTiming {
+                name: "SortArray".to_string(),
+                total: Duration::from_micros(41_700),
+                started: None,
+            },
+        ];
+
+        // Act
+        let report = format_report(&profile);
+
+        // Assert
+        assert_eq!(
+            report,
+            "\n--- Naldom Profile ---\nintent 1 CreateArray: 3.2 ms\nintent 2 SortArray: 41.7 ms\ntotal: 44.9 ms\n"
+        );
+    }
+}
@@ -0,0 +1,215 @@
+// crates/naldom-runtime/src/self_test.rs
+
+//! An in-process self-test of the exported runtime functions.
+//!
+//! It calls each function the way generated code does and checks the result.
+//! That catches ABI or layout regressions in the runtime without involving the
+//! compiler. `naldom-cli doctor --runtime` runs it. The profiling functions are
+//! left out, because initializing them prints a report when the process exits.
+//! The sleep and file checks only exist with the `async-sleep` and `file-io`
+//! features.
+
+use crate::array::{NaldomArray, create_random_array, format_array, print_array, sort_array};
+#[cfg(feature = "async-sleep")]
+use crate::naldom_async_sleep;
+use crate::output::capture_output;
+use std::any::Any;
+#[cfg(feature = "file-io")]
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+#[cfg(feature = "async-sleep")]
+use std::time::{Duration, Instant};
+
+/// The outcome of one self-test check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: &'static str,
+    /// `Err` holds a one-line description of what went wrong.
+    pub outcome: Result<(), String>,
+}
+
+impl CheckResult {
+    pub fn passed(&self) -> bool {
+        self.outcome.is_ok()
+    }
+}
+
+/// The number of elements in the array the checks work on.
+const ARRAY_SIZE: usize = 16;
+
+/// How long the sleep check sleeps.
+#[cfg(feature = "async-sleep")]
+const SLEEP: Duration = Duration::from_millis(10);
+
+/// Runs every check in order and reports each one. A failing or panicking
+/// check does not stop the remaining checks. The file check writes into
+/// `scratch_dir`, which must already exist.
+///
+/// Must not be called from within a Tokio runtime, because
+/// `naldom_async_sleep` blocks on the runtime's own executor.
+pub fn run(scratch_dir: &Path) -> Vec<CheckResult> {
+    // The array is never freed: the runtime has no free function, just as
+    // generated programs keep their arrays until exit.
+    let mut array: *mut NaldomArray = std::ptr::null_mut();
+    let mut printed = String::new();
+    let mut results = Vec::new();
+
+    results.push(check("create_random_array", || {
+        capture_output(|| array = create_random_array(ARRAY_SIZE as i64));
+        let values = values_of(array)?;
+        if values.len() != ARRAY_SIZE {
+            return Err(format!(
+                "expected {} elements, got {}",
+                ARRAY_SIZE,
+                values.len()
+            ));
+        }
+        match values.iter().find(|v| !(0.0..100.0).contains(*v)) {
+            Some(value) => Err(format!("{} is outside [0, 100)", value)),
+            None => Ok(()),
+        }
+    }));
+    results.push(check("sort_array (ascending)", || {
+        capture_output(|| unsafe { sort_array(array, 0, 0) });
+        expect_sorted(values_of(array)?, |a, b| a <= b)
+    }));
+    results.push(check("sort_array (descending)", || {
+        capture_output(|| unsafe { sort_array(array, 1, 0) });
+        expect_sorted(values_of(array)?, |a, b| a >= b)
+    }));
+    results.push(check("print_array", || {
+        let values = values_of(array)?;
+        printed = capture_output(|| unsafe { print_array(array, 0) });
+        if printed != format_array(values, false, 2) {
+            return Err(format!("unexpected output {:?}", printed));
+        }
+        Ok(())
+    }));
+    #[cfg(feature = "async-sleep")]
+    results.push(check("naldom_async_sleep", || {
+        let start = Instant::now();
+        naldom_async_sleep(SLEEP.as_millis() as u64);
+        let elapsed = start.elapsed();
+        if elapsed < SLEEP {
+            return Err(format!(
+                "returned after {:?}, expected {:?}",
+                elapsed, SLEEP
+            ));
+        }
+        Ok(())
+    }));
+    #[cfg(not(feature = "file-io"))]
+    let _ = scratch_dir;
+    #[cfg(feature = "file-io")]
+    results.push(check("file round-trip", || {
+        let path = scratch_dir.join("naldom-self-test.txt");
+        fs::write(&path, &printed)
+            .map_err(|e| format!("could not write '{}': {}", path.display(), e))?;
+        let read_back = fs::read_to_string(&path)
+            .map_err(|e| format!("could not read '{}': {}", path.display(), e))?;
+        let _ = fs::remove_file(&path);
+        if read_back != printed {
+            return Err("the file contents changed on the way".to_string());
+        }
+        Ok(())
+    }));
+    results
+}
+
+/// Runs one check, turning a panic into a failure.
+fn check(name: &'static str, f: impl FnOnce() -> Result<(), String>) -> CheckResult {
+    let outcome = panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(format!("panicked: {}", panic_message(&*payload))));
+    CheckResult { name, outcome }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+fn values_of<'a>(array: *const NaldomArray) -> Result<&'a [f64], String> {
+    // SAFETY: `array` is null or was returned by `create_random_array`.
+    unsafe { NaldomArray::values(array) }.ok_or_else(|| "no array was created".to_string())
+}
+
+fn expect_sorted(values: &[f64], in_order: impl Fn(f64, f64) -> bool) -> Result<(), String> {
+    match values.windows(2).position(|w| !in_order(w[0], w[1])) {
+        Some(i) => Err(format!(
+            "elements {} and {} are out of order ({} then {})",
+            i,
+            i + 1,
+            values[i],
+            values[i + 1]
+        )),
+        None => Ok(()),
+    }
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_every_check_passes() {
+        // Arrange
+        let dir = scratch_dir("naldom-self-test");
+
+        // Act
+        let results = run(&dir);
+
+        // Assert
+        let names: Vec<&str> = results.iter().map(|r| r.name).collect();
+        let mut expected = vec![
+            "create_random_array",
+            "sort_array (ascending)",
+            "sort_array (descending)",
+            "print_array",
+        ];
+        if cfg!(feature = "async-sleep") {
+            expected.push("naldom_async_sleep");
+        }
+        if cfg!(feature = "file-io") {
+            expected.push("file round-trip");
+        }
+        assert_eq!(names, expected);
+        for result in &results {
+            assert_eq!(result.outcome, Ok(()), "{} failed", result.name);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(feature = "file-io")]
+    #[test]
+    fn test_file_failure_does_not_stop_the_other_checks() {
+        // Arrange
+        let missing = scratch_dir("naldom-self-test-missing").join("does-not-exist");
+
+        // Act
+        let results = run(&missing);
+
+        // Assert
+        let (failed, passed): (Vec<_>, Vec<_>) = results.iter().partition(|r| !r.passed());
+        assert_eq!(passed.len(), results.len() - 1);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].name, "file round-trip");
+        assert!(
+            failed[0]
+                .outcome
+                .as_ref()
+                .unwrap_err()
+                .starts_with("could not write")
+        );
+    }
+}
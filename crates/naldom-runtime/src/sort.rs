@@ -0,0 +1,284 @@
+// crates/naldom-runtime/src/sort.rs
+
+//! The sorting behind `sort_array`, which picks an algorithm by length:
+//! insertion sort for short arrays, where it beats the setup of the general
+//! sort, the standard library's pattern-defeating quicksort otherwise, and,
+//! in runtimes built with `parallel-sort`, two threads and a merge for very
+//! long arrays. A program that names an algorithm gets that one instead.
+//! With `NALDOM_RUNTIME_LOG=info` every sort logs its choice.
+
+use crate::log;
+use std::cmp::Ordering;
+
+/// Arrays shorter than this are insertion sorted.
+pub const INSERTION_SORT_MAX_LEN: usize = 32;
+
+/// Arrays at least this long are sorted on two threads when the runtime is
+/// built with the `parallel-sort` feature.
+pub const PARALLEL_SORT_MIN_LEN: usize = 1 << 16;
+
+/// How an array is ordered.
+pub type Compare = fn(&f64, &f64) -> Ordering;
+
+/// The algorithms `sort` chooses from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortAlgorithm {
+    Insertion,
+    /// The standard library's unstable sort, a pattern-defeating quicksort.
+    Pattern,
+    /// Both halves on their own thread, then a merge. Runtimes built without
+    /// `parallel-sort` run [`SortAlgorithm::Pattern`] instead.
+    Parallel,
+    /// A top-down merge sort. Only used when a program asks for it.
+    Merge,
+    /// Only used when a program asks for it.
+    Bubble,
+}
+
+impl SortAlgorithm {
+    /// The algorithm `sort_array` was asked for: 1 for quicksort, 2 for
+    /// merge sort, 3 for bubble sort. Any other code, 0 included, leaves the
+    /// choice to [`SortAlgorithm::for_len`].
+    pub fn from_code(code: i64) -> Option<SortAlgorithm> {
+        match code {
+            1 => Some(SortAlgorithm::Pattern),
+            2 => Some(SortAlgorithm::Merge),
+            3 => Some(SortAlgorithm::Bubble),
+            _ => None,
+        }
+    }
+
+    /// The algorithm for an array of `len` elements.
+    pub fn for_len(len: usize) -> SortAlgorithm {
+        if len < INSERTION_SORT_MAX_LEN {
+            SortAlgorithm::Insertion
+        } else if cfg!(feature = "parallel-sort") && len >= PARALLEL_SORT_MIN_LEN {
+            SortAlgorithm::Parallel
+        } else {
+            SortAlgorithm::Pattern
+        }
+    }
+
+    /// The name the log uses.
+    pub fn name(self) -> &'static str {
+        match self {
+            SortAlgorithm::Insertion => "insertion sort",
+            SortAlgorithm::Pattern => "pattern-defeating quicksort",
+            SortAlgorithm::Parallel => "parallel merge sort",
+            SortAlgorithm::Merge => "merge sort",
+            SortAlgorithm::Bubble => "bubble sort",
+        }
+    }
+}
+
+/// Sorts `values` by `compare` with `algorithm`, or with the one
+/// [`SortAlgorithm::for_len`] picks when it is `None`, and logs the choice.
+pub fn sort(values: &mut [f64], compare: Compare, algorithm: Option<SortAlgorithm>) {
+    let algorithm = match algorithm.unwrap_or_else(|| SortAlgorithm::for_len(values.len())) {
+        SortAlgorithm::Parallel if !cfg!(feature = "parallel-sort") => SortAlgorithm::Pattern,
+        algorithm => algorithm,
+    };
+    log::info(|| {
+        format!(
+            "sort_array: {} for {} elements",
+            algorithm.name(),
+            values.len()
+        )
+    });
+    sort_with(values, compare, algorithm);
+}
+
+/// Sorts `values` by `compare` with exactly `algorithm`, without logging.
+pub fn sort_with(values: &mut [f64], compare: Compare, algorithm: SortAlgorithm) {
+    match algorithm {
+        SortAlgorithm::Insertion => insertion_sort(values, compare),
+        SortAlgorithm::Pattern => values.sort_unstable_by(compare),
+        SortAlgorithm::Parallel => parallel_sort(values, compare),
+        SortAlgorithm::Merge => merge_sort(values, compare),
+        SortAlgorithm::Bubble => bubble_sort(values, compare),
+    }
+}
+
+/// Stops early once a pass swaps nothing.
+fn bubble_sort(values: &mut [f64], compare: Compare) {
+    let mut unsorted = values.len();
+    while unsorted > 1 {
+        let mut last_swap = 0;
+        for i in 1..unsorted {
+            if compare(&values[i], &values[i - 1]) == Ordering::Less {
+                values.swap(i, i - 1);
+                last_swap = i;
+            }
+        }
+        unsorted = last_swap;
+    }
+}
+
+fn merge_sort(values: &mut [f64], compare: Compare) {
+    if values.len() < 2 {
+        return;
+    }
+    let mid = values.len() / 2;
+    merge_sort(&mut values[..mid], compare);
+    merge_sort(&mut values[mid..], compare);
+    let mut merged = Vec::with_capacity(values.len());
+    let (left, right) = values.split_at(mid);
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        if compare(&right[j], &left[i]) == Ordering::Less {
+            merged.push(right[j]);
+            j += 1;
+        } else {
+            merged.push(left[i]);
+            i += 1;
+        }
+    }
+    merged.extend_from_slice(&left[i..]);
+    merged.extend_from_slice(&right[j..]);
+    values.copy_from_slice(&merged);
+}
+
+fn insertion_sort(values: &mut [f64], compare: Compare) {
+    for i in 1..values.len() {
+        let value = values[i];
+        let mut j = i;
+        while j > 0 && compare(&value, &values[j - 1]) == Ordering::Less {
+            values[j] = values[j - 1];
+            j -= 1;
+        }
+        values[j] = value;
+    }
+}
+
+#[cfg(not(feature = "parallel-sort"))]
+fn parallel_sort(values: &mut [f64], compare: Compare) {
+    values.sort_unstable_by(compare);
+}
+
+/// Sorts each half of the array on its own thread, then merges them.
+#[cfg(feature = "parallel-sort")]
+fn parallel_sort(values: &mut [f64], compare: Compare) {
+    let mid = values.len() / 2;
+    let (left, right) = values.split_at_mut(mid);
+    std::thread::scope(|scope| {
+        scope.spawn(|| left.sort_unstable_by(compare));
+        right.sort_unstable_by(compare);
+    });
+    let mut merged = Vec::with_capacity(values.len());
+    let (mut i, mut j) = (0, mid);
+    while i < mid && j < values.len() {
+        if compare(&values[j], &values[i]) == Ordering::Less {
+            merged.push(values[j]);
+            j += 1;
+        } else {
+            merged.push(values[i]);
+            i += 1;
+        }
+    }
+    merged.extend_from_slice(&values[i..mid]);
+    merged.extend_from_slice(&values[j..]);
+    values.copy_from_slice(&merged);
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGORITHMS: [SortAlgorithm; 5] = [
+        SortAlgorithm::Insertion,
+        SortAlgorithm::Pattern,
+        SortAlgorithm::Parallel,
+        SortAlgorithm::Merge,
+        SortAlgorithm::Bubble,
+    ];
+
+    fn ascending(a: &f64, b: &f64) -> Ordering {
+        a.total_cmp(b)
+    }
+
+    fn descending(a: &f64, b: &f64) -> Ordering {
+        b.total_cmp(a)
+    }
+
+    /// A small xorshift, so the randomized cases are the same on every run.
+    fn random_values(seed: u64, len: usize) -> Vec<f64> {
+        let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                // Few distinct values, so duplicates are common.
+                (state % 50) as f64 - 10.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_the_algorithm_is_chosen_by_length() {
+        assert_eq!(SortAlgorithm::for_len(0), SortAlgorithm::Insertion);
+        assert_eq!(SortAlgorithm::for_len(31), SortAlgorithm::Insertion);
+        assert_eq!(SortAlgorithm::for_len(32), SortAlgorithm::Pattern);
+        let long = if cfg!(feature = "parallel-sort") {
+            SortAlgorithm::Parallel
+        } else {
+            SortAlgorithm::Pattern
+        };
+        assert_eq!(SortAlgorithm::for_len(PARALLEL_SORT_MIN_LEN), long);
+    }
+
+    #[test]
+    fn test_algorithm_codes_select_an_algorithm_or_defer_to_the_length() {
+        assert_eq!(SortAlgorithm::from_code(0), None);
+        assert_eq!(SortAlgorithm::from_code(1), Some(SortAlgorithm::Pattern));
+        assert_eq!(SortAlgorithm::from_code(2), Some(SortAlgorithm::Merge));
+        assert_eq!(SortAlgorithm::from_code(3), Some(SortAlgorithm::Bubble));
+        assert_eq!(SortAlgorithm::from_code(42), None);
+    }
+
+    #[test]
+    fn test_every_algorithm_sorts_every_shape_in_both_orders() {
+        for len in [0, 1, 2, 15, 31, 32, 33, 100, 1_000] {
+            let shapes = [
+                random_values(len as u64, len),
+                (0..len).map(|i| i as f64).collect(),
+                (0..len).rev().map(|i| i as f64).collect(),
+                vec![4.5; len],
+            ];
+            for shape in &shapes {
+                for compare in [ascending as Compare, descending] {
+                    let mut expected = shape.clone();
+                    expected.sort_by(compare);
+                    for algorithm in ALGORITHMS {
+                        // Act
+                        let mut values = shape.clone();
+                        sort_with(&mut values, compare, algorithm);
+
+                        // Assert
+                        assert_eq!(values, expected, "{:?} with {} elements", algorithm, len);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The chosen algorithm agrees with the standard library's stable sort
+    /// on many random arrays around the size thresholds.
+    #[test]
+    fn test_randomized_arrays_match_the_reference_sort() {
+        for seed in 0..500u64 {
+            // Arrange
+            let len = (seed as usize * 7) % (3 * INSERTION_SORT_MAX_LEN);
+            let mut values = random_values(seed, len);
+            let mut reference = values.clone();
+            reference.sort_by(ascending);
+
+            // Act
+            sort(&mut values, ascending, None);
+
+            // Assert
+            assert_eq!(values, reference, "seed {}", seed);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// crates/naldom-runtime/src/timer.rs
+
+//! Timers for `StartTimer`/`PrintElapsed`: a timer is a reading of the
+//! monotonic clock, in nanoseconds since the first timer of the process, so
+//! it crosses the ABI as a plain `i64`.
+
+use crate::output::print_logical;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// The instant timers count from, fixed by the first reading.
+fn epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+/// Nanoseconds since the epoch; saturates after 292 years.
+fn now_ns() -> i64 {
+    i64::try_from(epoch().elapsed().as_nanos()).unwrap_or(i64::MAX)
+}
+
+/// Starts a timer and returns it, to be passed to `print_elapsed`.
+#[unsafe(no_mangle)]
+pub extern "C" fn start_timer() -> i64 {
+    now_ns()
+}
+
+/// Prints the time since `timer` was started, in milliseconds.
+#[unsafe(no_mangle)]
+pub extern "C" fn print_elapsed(timer: i64) {
+    print_logical(&format_elapsed(now_ns().saturating_sub(timer)));
+}
+
+/// Renders the line printed by `print_elapsed` for `elapsed_ns`.
+fn format_elapsed(elapsed_ns: i64) -> String {
+    format!("elapsed: {:.2} ms\n", elapsed_ns.max(0) as f64 / 1e6)
+}
+
+// --- Unit Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::capture_output;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_elapsed_prints_milliseconds_with_two_decimals() {
+        assert_eq!(format_elapsed(12_345_678), "elapsed: 12.35 ms\n");
+        assert_eq!(format_elapsed(-5), "elapsed: 0.00 ms\n");
+    }
+
+    #[test]
+    fn test_elapsed_time_covers_the_time_since_the_start() {
+        // Arrange
+        let timer = start_timer();
+        std::thread::sleep(Duration::from_millis(5));
+
+        // Act
+        let printed = capture_output(|| print_elapsed(timer));
+
+        // Assert
+        let ms: f64 = printed
+            .trim()
+            .strip_prefix("elapsed: ")
+            .and_then(|rest| rest.strip_suffix(" ms"))
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(ms >= 5.0, "{}", printed);
+    }
+}
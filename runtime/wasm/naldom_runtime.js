@@ -17,12 +17,169 @@ const naldomImports = {
             return 0; // Return a null pointer for now.
         },
         
-        sort_array: (arrayPtr, order) => {
-            console.log(`Runtime (JS): "sort_array" called for pointer ${arrayPtr} with order ${order}. Not implemented yet.`);
+        create_random_int_array: (size) => {
+            console.log(`Runtime (JS): "create_random_int_array" called with size ${size}. Not implemented yet.`);
+            return 0;
+        },
+        
+        create_random_matrix: (rows, cols) => {
+            console.log(`Runtime (JS): "create_random_matrix" called with ${rows}x${cols}. Not implemented yet.`);
+            return 0;
+        },
+        
+        print_matrix: (matrixPtr) => {
+            console.log(`Runtime (JS): "print_matrix" called. Not implemented yet.`);
+        },
+        
+        create_array_from_values: (valuesPtr, len) => {
+            console.log(`Runtime (JS): "create_array_from_values" called with ${len} values at ${valuesPtr}. Not implemented yet.`);
+            return 0;
+        },
+        
+        sort_array: (arrayPtr, order, algorithm) => {
+            console.log(`Runtime (JS): "sort_array" called for pointer ${arrayPtr} with order ${order} and algorithm ${algorithm}. Not implemented yet.`);
         },
         
         print_array: (arrayPtr) => {
             console.log(`Runtime (JS): "print_array" called for pointer ${arrayPtr}. Not implemented yet.`);
+        },
+
+        min_array: (arrayPtr) => {
+            console.log(`Runtime (JS): "min_array" called for pointer ${arrayPtr}. Not implemented yet.`);
+            return 0n;
+        },
+
+        max_array: (arrayPtr) => {
+            console.log(`Runtime (JS): "max_array" called for pointer ${arrayPtr}. Not implemented yet.`);
+            return 0n;
+        },
+
+        array_length: (arrayPtr) => {
+            console.log(`Runtime (JS): "array_length" called for pointer ${arrayPtr}. Not implemented yet.`);
+            return 0n;
+        },
+
+        average_array: (arrayPtr) => {
+            console.log(`Runtime (JS): "average_array" called for pointer ${arrayPtr}. Not implemented yet.`);
+            return 0.0;
+        },
+
+        median_array: (arrayPtr) => {
+            console.log(`Runtime (JS): "median_array" called for pointer ${arrayPtr}. Not implemented yet.`);
+            return 0.0;
+        },
+
+        array_contains: (arrayPtr, value) => {
+            console.log(`Runtime (JS): "array_contains" called for pointer ${arrayPtr} with ${value}. Not implemented yet.`);
+            return 0;
+        },
+
+        find_index: (arrayPtr, value) => {
+            console.log(`Runtime (JS): "find_index" called for pointer ${arrayPtr} with ${value}. Not implemented yet.`);
+            return -1n; // i64 results cross the boundary as BigInt.
+        },
+
+        is_sorted: (arrayPtr) => {
+            console.log(`Runtime (JS): "is_sorted" called for pointer ${arrayPtr}. Not implemented yet.`);
+            return 1;
+        },
+
+        describe_array: (arrayPtr, namePtr) => {
+            console.log(`Runtime (JS): "describe_array" called for pointer ${arrayPtr} with name at ${namePtr}. Not implemented yet.`);
+        },
+
+        is_empty: (arrayPtr) => {
+            console.log(`Runtime (JS): "is_empty" called for pointer ${arrayPtr}. Not implemented yet.`);
+            return 0;
+        },
+
+        reverse_array: (arrayPtr) => {
+            console.log(`Runtime (JS): "reverse_array" called for pointer ${arrayPtr}. Not implemented yet.`);
+        },
+
+        append_to_array: (arrayPtr, value) => {
+            console.log(`Runtime (JS): "append_to_array" called for pointer ${arrayPtr} with value ${value}. Not implemented yet.`);
+        },
+
+        fill_array: (arrayPtr, value) => {
+            console.log(`Runtime (JS): "fill_array" called for pointer ${arrayPtr} with value ${value}. Not implemented yet.`);
+        },
+
+        clear_array: (arrayPtr) => {
+            console.log(`Runtime (JS): "clear_array" called for pointer ${arrayPtr}. Not implemented yet.`);
+        },
+
+        slice_array: (arrayPtr, start, end) => {
+            console.log(`Runtime (JS): "slice_array" called for pointer ${arrayPtr} from ${start} to ${end}. Not implemented yet.`);
+        },
+
+        map_array: (arrayPtr, op, operand) => {
+            console.log(`Runtime (JS): "map_array" called for pointer ${arrayPtr} with operation ${op} and operand ${operand}. Not implemented yet.`);
+        },
+
+        concat_arrays: (firstPtr, secondPtr) => {
+            console.log(`Runtime (JS): "concat_arrays" called for pointers ${firstPtr} and ${secondPtr}. Not implemented yet.`);
+            return 0;
+        },
+
+        copy_array: (arrayPtr) => {
+            console.log(`Runtime (JS): "copy_array" called for pointer ${arrayPtr}. Not implemented yet.`);
+            return 0;
+        },
+
+        array_to_float: (arrayPtr) => {
+            console.log(`Runtime (JS): "array_to_float" called for pointer ${arrayPtr}. Not implemented yet.`);
+            return 0;
+        },
+
+        array_to_int: (arrayPtr) => {
+            console.log(`Runtime (JS): "array_to_int" called for pointer ${arrayPtr}. Not implemented yet.`);
+            return 0;
+        },
+
+        print_int_array: (arrayPtr, full) => {
+            console.log(`Runtime (JS): "print_int_array" called for pointer ${arrayPtr} (full: ${full}). Not implemented yet.`);
+        },
+
+        print_histogram: (arrayPtr, buckets) => {
+            console.log(`Runtime (JS): "print_histogram" called for pointer ${arrayPtr} (buckets: ${buckets}). Not implemented yet.`);
+        },
+
+        sum_array: (arrayPtr) => {
+            console.log(`Runtime (JS): "sum_array" called for pointer ${arrayPtr}. Not implemented yet.`);
+            return 0n; // i64 results cross the boundary as BigInt.
+        },
+
+        print_integer: (value) => {
+            console.log(value.toString());
+        },
+
+        print_bool: (value) => {
+            console.log(value !== 0 ? "true" : "false");
+        },
+
+        print_float: (value) => {
+            console.log(value.toFixed(2));
+        },
+
+        // Timers are nanosecond readings of the monotonic clock, as BigInt.
+        start_timer: () => {
+            return BigInt(Math.round(performance.now() * 1e6));
+        },
+
+        print_elapsed: (timer) => {
+            const start = Number(timer) / 1e6;
+            console.log(`elapsed: ${Math.max(performance.now() - start, 0).toFixed(2)} ms`);
+        },
+
+        // Nothing in this placeholder fails, so the mode has nothing to
+        // govern.
+        naldom_set_error_mode: (mode) => {},
+
+        // Generated code must not continue after an exit; throwing unwinds
+        // out of the module's entry point.
+        naldom_exit: (code) => {
+            throw new Error(`Naldom program exited with code ${code}`);
         }
     }
 };
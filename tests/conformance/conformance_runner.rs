@@ -0,0 +1,136 @@
+// tests/conformance/conformance_runner.rs
+
+// The conformance corpus: every `NN-name.intents.json` in this directory is
+// compiled with `naldom-cli --from-intents --seed 42`, the resulting program
+// is run, and its stdout is compared with `NN-name.expected.txt`. No LLM is
+// involved, so any difference comes from lowering, codegen or the runtime.
+//
+// Every entry is checked before the test fails, so one run reports all
+// regressions. Run with `NALDOM_CONFORMANCE_UPDATE=1` to rewrite the
+// expectations from the current output instead, and review the diff.
+
+use assert_cmd::Command;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The seed every entry is compiled with, so random arrays are reproducible.
+const SEED: &str = "42";
+
+const UPDATE_ENV: &str = "NALDOM_CONFORMANCE_UPDATE";
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../tests/conformance")
+}
+
+/// The corpus entries as `(name, intents file)`, sorted by name.
+fn corpus_entries() -> Result<Vec<(String, PathBuf)>, Box<dyn Error>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(corpus_dir())? {
+        let path = entry?.path();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        if let Some(name) = file_name.strip_suffix(".intents.json") {
+            entries.push((name.to_string(), path.clone()));
+        }
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+/// Compiles and runs one entry, returning the program's stdout.
+fn run_entry(intents: &Path, executable: &Path) -> Result<String, String> {
+    let compile = Command::cargo_bin("naldom-cli")
+        .map_err(|e| e.to_string())?
+        .arg(intents)
+        .args(["--from-intents", "--seed", SEED, "-o"])
+        .arg(executable)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !compile.status.success() {
+        return Err(format!(
+            "compilation failed:\n{}",
+            String::from_utf8_lossy(&compile.stderr)
+        ));
+    }
+
+    let run = std::process::Command::new(executable)
+        .output()
+        .map_err(|e| format!("could not run the program: {}", e))?;
+    if !run.status.success() {
+        return Err(format!(
+            "the program exited with {}:\n{}",
+            run.status,
+            String::from_utf8_lossy(&run.stderr)
+        ));
+    }
+    String::from_utf8(run.stdout).map_err(|e| format!("stdout is not UTF-8: {}", e))
+}
+
+/// The first line where `actual` departs from `expected`.
+fn describe_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    for line in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => continue,
+            (None, None) => break,
+            (e, a) => {
+                return format!(
+                    "line {}:\n  expected: {}\n  actual:   {}",
+                    line,
+                    e.unwrap_or("<end of output>"),
+                    a.unwrap_or("<end of output>")
+                );
+            }
+        }
+    }
+    // Only the trailing newline differs.
+    format!("expected {:?}\n  actual {:?}", expected, actual)
+}
+
+#[test]
+fn test_conformance_corpus() -> Result<(), Box<dyn Error>> {
+    let update = std::env::var_os(UPDATE_ENV).is_some_and(|value| value == "1");
+    let temp = assert_fs::TempDir::new()?;
+    let entries = corpus_entries()?;
+    assert!(!entries.is_empty(), "the conformance corpus is empty");
+
+    let mut failures = Vec::new();
+    for (name, intents) in &entries {
+        let expected_path = corpus_dir().join(format!("{}.expected.txt", name));
+        let actual = match run_entry(intents, &temp.path().join(name)) {
+            Ok(actual) => actual,
+            Err(message) => {
+                failures.push(format!("{}: {}", name, message));
+                continue;
+            }
+        };
+        if update {
+            fs::write(&expected_path, &actual)?;
+            continue;
+        }
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{}: {}",
+                name,
+                describe_difference(&expected, &actual)
+            )),
+            Err(_) => failures.push(format!(
+                "{}: {} is missing; run with {}=1 to create it",
+                name,
+                expected_path.display(),
+                UPDATE_ENV
+            )),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} conformance entries failed:\n\n{}",
+        failures.len(),
+        entries.len(),
+        failures.join("\n\n")
+    );
+    Ok(())
+}
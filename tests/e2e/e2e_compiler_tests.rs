@@ -5,6 +5,7 @@ use assert_fs::prelude::*;
 use predicates::prelude::*;
 use std::error::Error;
 
+#[cfg(feature = "llvm")]
 #[test]
 fn test_compile_and_run_simple_program_native() -> Result<(), Box<dyn Error>> {
     let temp = assert_fs::TempDir::new()?;
@@ -32,3 +33,1824 @@ Print the result.
 
     Ok(())
 }
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_progress_json_reports_stages_on_stderr() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 5 random numbers.\nPrint it.\n:::")?;
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("--emit")
+        .arg("llvm-ir")
+        .arg("--progress")
+        .arg("json");
+    let output = cmd.assert().success().get_output().clone();
+
+    let stderr = String::from_utf8(output.stderr)?;
+    let events: Vec<serde_json::Value> = stderr
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    let stages: Vec<&str> = events
+        .iter()
+        .filter(|event| event["event"] == "stage_start")
+        .map(|event| event["stage"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        stages,
+        [
+            "inference",
+            "parse",
+            "analysis",
+            "lowering",
+            "low_level_lowering",
+            "codegen"
+        ]
+    );
+    assert!(
+        events
+            .iter()
+            .filter(|event| event["event"] == "stage_end")
+            .all(|event| event["duration_ms"].is_number())
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_piped_program_output_is_complete() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let cache_dir = temp.child("cache");
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        r#":::naldom
+Create an array of 5 random numbers.
+Print the result.
+Sort it in ascending order.
+Print the result again.
+:::"#,
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 5}}, {"intent": "PrintArray"}, {"intent": "SortArray", "parameters": {"order": "ascending"}}, {"intent": "PrintArray"}]"#,
+    )?;
+    let output_executable = temp.child("piped_program");
+
+    // `--diff-previous` makes `--run` read the program's stdout through a
+    // pipe instead of handing it the terminal, so the runtime must flush
+    // every print itself rather than relying on a terminal's line buffering.
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .args(["--run", "--diff-previous"])
+        .env("NALDOM_MOCK_RESPONSE", response.path())
+        .env("NALDOM_CACHE_DIR", cache_dir.path());
+    let output = cmd.output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(stdout.matches("--- Naldom Native Output ---").count(), 2);
+    let creating = stdout.find("Runtime: Creating").unwrap();
+    let sorting = stdout.find("Runtime: Sorting").unwrap();
+    let report = stdout
+        .find("No earlier output of this program to compare with.")
+        .unwrap();
+    assert!(creating < sorting && sorting < report, "{}", stdout);
+
+    // What the CLI captured, and stored for the next run, is all of it too.
+    let runs: Vec<_> =
+        std::fs::read_dir(cache_dir.child("runs").path())?.collect::<Result<_, _>>()?;
+    assert_eq!(runs.len(), 1);
+    let captured = std::fs::read_to_string(runs[0].path())?;
+    assert_eq!(captured.matches("--- Naldom Native Output ---").count(), 2);
+    assert!(captured.find("Runtime: Creating") < captured.find("Runtime: Sorting"));
+
+    Ok(())
+}
+
+#[test]
+fn test_python_target_writes_python_source() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        r#":::naldom
+Create an array of 5 random numbers.
+Print the result.
+:::"#,
+    )?;
+    let output_file = temp.child("program.py");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("--target")
+        .arg("python")
+        .arg("-o")
+        .arg(output_file.path());
+    cmd.assert().success();
+
+    output_file.assert(predicate::str::contains("create_random_array(5)"));
+
+    Ok(())
+}
+
+/// Only meaningful for `cargo test -p naldom-cli --no-default-features`: a CLI
+/// built without LLVM must refuse LLVM targets before running inference.
+#[cfg(not(feature = "llvm"))]
+#[test]
+fn test_native_target_requires_llvm_feature() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 5 random numbers.\n:::")?;
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path()).arg("--target").arg("native");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("no LLVM support"));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_compile_and_run_with_spaces_and_unicode_in_paths() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let project_dir = temp.child("my project/ünïcödé dir");
+    project_dir.create_dir_all()?;
+    let input_file = project_dir.child("my program.md");
+    input_file.write_str(
+        r#":::naldom
+Create an array of 5 random numbers.
+Print the result.
+:::"#,
+    )?;
+    // An absolute output path, so `--run` must not prefix it with `./`.
+    let output_executable = project_dir.child("my prögram");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("--- Naldom Native Output ---"));
+    output_executable.assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_run_with_relative_output_path_in_spaced_directory() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let work_dir = temp.child("work space");
+    work_dir.create_dir_all()?;
+    work_dir
+        .child("program.md")
+        .write_str(":::naldom\nCreate an array of 3 random numbers.\nPrint it.\n:::")?;
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.current_dir(work_dir.path())
+        .arg("program.md")
+        .arg("-o")
+        .arg("out dir/a.out")
+        .arg("--run");
+    work_dir.child("out dir").create_dir_all()?;
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("--- Naldom Native Output ---"));
+
+    Ok(())
+}
+
+#[test]
+fn test_emit_runtime_header() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let header = temp.child("naldom_runtime.h");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg("emit")
+        .arg("runtime-header")
+        .arg("-o")
+        .arg(header.path());
+    cmd.assert().success();
+
+    header.assert(predicate::str::contains(
+        "NaldomArray *create_random_array(int64_t size);",
+    ));
+    header.assert(predicate::str::contains(
+        "#define NALDOM_RUNTIME_ABI_VERSION_MAJOR",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_emit_python_runtime() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let runtime = temp.child("naldom_runtime.py");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg("emit")
+        .arg("python-runtime")
+        .arg("-o")
+        .arg(runtime.path());
+    cmd.assert().success();
+
+    runtime.assert(predicate::str::contains(
+        "def sort_array(arr, order, algorithm):",
+    ));
+    runtime.assert(predicate::str::contains("# Runtime ABI "));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_profile_reports_time_per_intent() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        r#":::naldom
+Create an array of 5 random numbers.
+Sort it in ascending order.
+Print the result.
+:::"#,
+    )?;
+    let mock_response = temp.child("response.json");
+    mock_response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 5}}, {"intent": "SortArray", "parameters": {"order": "ascending"}}, {"intent": "PrintArray"}]"#,
+    )?;
+    let output_executable = temp.child("profiled_program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.env("NALDOM_MOCK_RESPONSE", mock_response.path())
+        .arg(input_file.path())
+        .arg("--profile")
+        .arg("-o")
+        .arg(output_executable.path());
+    cmd.assert().success();
+
+    let output = Command::new(output_executable.path()).output()?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    let rows: Vec<&str> = stderr
+        .lines()
+        .filter(|line| line.starts_with("intent "))
+        .collect();
+    assert_eq!(rows.len(), 3, "unexpected profile:\n{}", stderr);
+    for (row, name) in rows.iter().zip(["CreateArray", "SortArray", "PrintArray"]) {
+        assert!(row.contains(name), "{} should mention {}", row, name);
+        let millis: f64 = row
+            .rsplit(": ")
+            .next()
+            .and_then(|value| value.strip_suffix(" ms"))
+            .and_then(|value| value.parse().ok())
+            .expect("Each row ends with a duration in ms");
+        assert!((0.0..10_000.0).contains(&millis));
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_compile_rejects_absurd_array_size() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 4294967295 random numbers.\n:::")?;
+    let mock_response = temp.child("response.json");
+    mock_response
+        .write_str(r#"[{"intent": "CreateArray", "parameters": {"size": 4294967295}}]"#)?;
+    let output_executable = temp.child("huge_program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.env("NALDOM_MOCK_RESPONSE", mock_response.path())
+        .arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path());
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "An array of 4294967295 elements would require 32 GiB",
+    ));
+    output_executable.assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_print_link_command_does_not_link() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 5 random numbers.\nPrint it.\n:::")?;
+    let output_executable = temp.child("linked program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.env("LLVM_PREFIX", "/opt/fake-llvm")
+        .arg(input_file.path())
+        .arg("--print-link-command")
+        .arg("-o")
+        .arg(output_executable.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("/opt/fake-llvm/bin/llc -O0"))
+        .stdout(predicate::str::contains("/opt/fake-llvm/bin/clang"))
+        .stdout(predicate::str::contains("-lnaldom_runtime"))
+        .stdout(predicate::str::contains(format!(
+            "-o '{}'",
+            output_executable.path().display()
+        )));
+    output_executable.assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn test_lint_levels_control_exit_status() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file
+        .write_str(":::naldom\nCreate an array of 5 random numbers.\nWait 0 ms.\nPrint it.\n:::")?;
+    let mock_response = temp.child("response.json");
+    mock_response.write_str(
+        r#"[
+            {"intent": "CreateArray", "parameters": {"size": 5}},
+            {"intent": "Wait", "parameters": {"durationMs": 0}},
+            {"intent": "PrintArray"}
+        ]"#,
+    )?;
+    let compile = |flags: &[&str]| -> Result<assert_cmd::assert::Assert, Box<dyn Error>> {
+        let mut cmd = Command::cargo_bin("naldom-cli")?;
+        cmd.env("NALDOM_MOCK_RESPONSE", mock_response.path())
+            .arg(input_file.path())
+            .args(["--target", "python", "-o"])
+            .arg(temp.child("out.py").path())
+            .args(flags);
+        Ok(cmd.assert())
+    };
+
+    compile(&[])?
+        .success()
+        .stderr(predicate::str::contains("warning: zero_wait (intent #2)"));
+    compile(&["--allow", "zero_wait"])?
+        .success()
+        .stderr(predicate::str::contains("zero_wait").not());
+    compile(&["--deny", "zero_wait"])?
+        .failure()
+        .stderr(predicate::str::contains("Lint Error: zero_wait"));
+    compile(&["--deny", "zero_wiat"])?
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unknown lint 'zero_wiat'. Available lints: unused_array, zero_wait",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn test_demo_runs_without_input_or_tools() -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg("demo")
+        .env("PATH", "")
+        .env_remove("LLVM_PREFIX")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("== Plan =="))
+        .stdout(predicate::str::contains("== Pseudo-code =="))
+        .stdout(predicate::str::contains("== Output =="))
+        .stdout(predicate::str::contains("Runtime: Sorting the array..."));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_wasm_link_check_reports_native_only_symbols() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 5 random numbers.\nWait 100 ms.\n:::")?;
+    let output_module = temp.child("program.wasm");
+
+    // The default mock program contains a Wait intent.
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .args(["--target", "wasm", "-o"])
+        .arg(output_module.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "naldom_async_sleep (only the native runtime provides it)",
+        ))
+        .stderr(predicate::str::contains("wasm-ld").not());
+    output_module.assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn test_doctor_reports_missing_tools_and_runtime_checks() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+
+    // No LLVM tools under the prefix, so only the toolchain checks fail.
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.args(["doctor", "--runtime"])
+        .env("LLVM_PREFIX", temp.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("FAIL llc:"))
+        .stdout(predicate::str::contains("ok   create_random_array: passed"))
+        .stdout(predicate::str::contains("ok   file round-trip: passed"))
+        .stderr(predicate::str::contains("3 checks failed."));
+
+    Ok(())
+}
+
+#[test]
+fn test_front_matter_sets_defaults_that_flags_override() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        "---\nnaldom:\n  target: wasm\n  opt_level: 2\n  seed: 7\n---\n:::naldom\nCreate an array of 5 random numbers.\n:::",
+    )?;
+
+    // Without flags, the front matter decides.
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path()).arg("--print-config");
+    cmd.assert().success().stdout(
+        "target = wasm (front matter)\nopt_level = 2 (front matter)\nseed = 7 (front matter)\non_error = abort (default)\npost_process = none (default)\n",
+    );
+
+    // A flag wins over the front matter.
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .args(["--print-config", "--target", "python"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("target = python (command line)"));
+
+    Ok(())
+}
+
+#[test]
+fn test_on_error_continue_sets_the_mode_at_program_start() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let intents = temp.child("program.json");
+    intents.write_str(r#"[{"intent": "CreateArray", "parameters": {"size": 5}}]"#)?;
+    let aborting = temp.child("aborting.py");
+    let continuing = temp.child("continuing.py");
+
+    // Abort is the runtime's default, so nothing sets it.
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg("--from-intents")
+        .arg(intents.path())
+        .args(["--target", "python", "-o"])
+        .arg(aborting.path());
+    cmd.assert().success();
+    aborting.assert(predicate::str::contains("naldom_set_error_mode").not());
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg("--from-intents")
+        .arg(intents.path())
+        .args(["--target", "python", "--on-error", "continue", "-o"])
+        .arg(continuing.path());
+    cmd.assert().success();
+    continuing.assert(predicate::str::starts_with("naldom_set_error_mode(1)\n"));
+
+    Ok(())
+}
+
+// `ulimit -v` caps the program's address space, so an array the compiler
+// accepts still fails to allocate when the program runs.
+#[cfg(all(feature = "llvm", unix))]
+#[test]
+fn test_runtime_errors_end_the_program_unless_it_continues() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let intents = temp.child("program.json");
+    intents.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 100000000}}, {"intent": "PrintArray"}]"#,
+    )?;
+    let aborting = temp.child("aborting");
+    let continuing = temp.child("continuing");
+    let run_limited = |executable: &std::path::Path| {
+        std::process::Command::new("sh")
+            .arg("-c")
+            .arg(r#"ulimit -v 524288 && exec "$0""#)
+            .arg(executable)
+            .output()
+    };
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg("--from-intents")
+        .arg(intents.path())
+        .arg("-o")
+        .arg(aborting.path());
+    cmd.assert().success();
+    let output = run_limited(aborting.path())?;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        String::from_utf8(output.stderr)?
+            .contains("Runtime Error: could not allocate 800000000 bytes for 100000000 elements.")
+    );
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg("--from-intents")
+        .arg(intents.path())
+        .args(["--on-error", "continue", "-o"])
+        .arg(continuing.path());
+    cmd.assert().success();
+    let output = run_limited(continuing.path())?;
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8(output.stderr)?.contains(
+        "Runtime Warning: could not allocate 800000000 bytes for 100000000 elements; continuing."
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_explain_names_the_error_mode_from_the_front_matter() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        "---\nnaldom:\n  on_error: continue\n---\n:::naldom\nCreate an array of 5 random numbers.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(r#"[{"intent": "CreateArray", "parameters": {"size": 5}}]"#)?;
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .args(["--target", "python", "--explain"])
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert().success().stdout(predicate::str::contains(
+        "On a runtime error: warn and go on with an empty result (continue).",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_cached_answers_are_reused_until_the_cache_is_cleared() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let cache_dir = temp.child("cache");
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 5 random numbers.\n:::")?;
+    let response = temp.child("response.json");
+    response.write_str(r#"[{"intent": "CreateArray", "parameters": {"size": 5}}]"#)?;
+    let output_file = temp.child("program.py");
+    let build = || -> Result<Command, Box<dyn Error>> {
+        let mut cmd = Command::cargo_bin("naldom-cli")?;
+        cmd.arg(input_file.path())
+            .args(["--target", "python", "--cache", "-o"])
+            .arg(output_file.path())
+            .env("NALDOM_CACHE_DIR", cache_dir.path())
+            .env("NALDOM_MOCK_RESPONSE", response.path());
+        Ok(cmd)
+    };
+
+    build()?
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Using Mock LLM Inference"));
+    // The model is not asked again, so a changed answer goes unnoticed.
+    response.write_str(r#"[{"intent": "CreateArray", "parameters": {"size": 6}}]"#)?;
+    build()?
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Using Mock LLM Inference").not());
+    output_file.assert(predicate::str::contains("create_random_array(5)"));
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.args(["cache", "stats"])
+        .env("NALDOM_CACHE_DIR", cache_dir.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("llm:       1 entries"))
+        .stdout(predicate::str::contains("50% (1 of 2 lookups)"));
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.args(["cache", "clear", "--llm"])
+        .env("NALDOM_CACHE_DIR", cache_dir.path());
+    cmd.assert().success().stdout("Removed 1 cache entries.\n");
+    build()?.assert().success();
+    output_file.assert(predicate::str::contains("create_random_array(6)"));
+
+    Ok(())
+}
+
+#[test]
+fn test_unknown_intents_diff_by_name_but_do_not_build() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let before = temp.child("before.json");
+    before.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 5}}, {"intent": "GroupBy", "parameters": {"key": "parity"}}]"#,
+    )?;
+    let after = temp.child("after.json");
+    after.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 5}}, {"intent": "GroupBy", "parameters": {"key": "sign"}}]"#,
+    )?;
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg("diff").arg(before.path()).arg(after.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "-GroupBy {\"key\":\"parity\"}  (unknown intent)",
+        ))
+        .stdout(predicate::str::contains(
+            "+GroupBy {\"key\":\"sign\"}  (unknown intent)",
+        ));
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(before.path())
+        .args(["--from-intents", "--target", "python", "-o"])
+        .arg(temp.child("program.py").path());
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "intent #2 is 'GroupBy', which this version of Naldom does not know",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_post_process_commands_run_on_the_artifact() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 5 random numbers.\n:::")?;
+    let response = temp.child("response.json");
+    response.write_str(r#"[{"intent": "CreateArray", "parameters": {"size": 5}}]"#)?;
+    let output_file = temp.child("program.py");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .args(["--target", "python", "-o"])
+        .arg(output_file.path())
+        .args(["--post-process", "cp {output} {output}.{target}"])
+        .args(["--post-process", "cp {source} {output}.source"])
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert().success();
+    temp.child("program.py.python")
+        .assert(predicate::str::contains("create_random_array(5)"));
+    temp.child("program.py.source")
+        .assert(predicate::str::contains("Create an array"));
+
+    // A failing command fails the build with its stderr.
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .args(["--target", "python", "-o"])
+        .arg(output_file.path())
+        .args(["--post-process", "sh -c 'echo upload refused >&2; exit 2'"])
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "exited with status 2\nupload refused",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_print_pass_diff_reports_every_pass() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 5 random numbers.\n:::")?;
+    let response = temp.child("response.json");
+    response.write_str(r#"[{"intent": "CreateArray", "parameters": {"size": 5}}]"#)?;
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .args(["--target", "python", "--print-pass-diff", "-o"])
+        .arg(temp.child("program.py").path())
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    // The array is never used, so dce drops the stack slot it is kept in.
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("--- before dce\n+++ after dce\n"))
+        .stderr(predicate::str::contains("-  %1 = alloc i8*"));
+
+    Ok(())
+}
+
+#[test]
+fn test_malformed_front_matter_names_the_line() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str("---\nnaldom:\n  target: wasm\n  optlevel: 2\n---\n:::naldom\n:::")?;
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path());
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Front Matter Error: line 4: naldom: unknown field `optlevel`",
+    ));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_long_arrays_are_printed_truncated() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 1000 random numbers and print it.\n:::")?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 1000}}, {"intent": "PrintArray"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("… (960 omitted)"));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_sum_array_prints_the_total() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file
+        .write_str(":::naldom\nCreate an array of 10 random numbers and print their sum.\n:::")?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 10}}, {"intent": "SumArray"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"random numbers\.\.\.\n\d+\n")?);
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_count_elements_prints_the_array_length() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate 7 numbers, print how many there are.\n:::")?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 7}}, {"intent": "CountElements"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("random numbers...\n7\n"));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_average_and_median_print_two_decimals() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        ":::naldom\nTake the values 4, 1, 3 and 10, print their average and their median.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"values": [4, 1, 3, 10]}}, {"intent": "AverageArray"}, {"intent": "MedianArray"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("4 values...\n4.50\n3.50\n"));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_contains_value_prints_true_or_false() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        ":::naldom\nTake the values 4, 17 and 3, check whether they contain 17 and whether they contain 5.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"values": [4, 17, 3]}}, {"intent": "ContainsValue", "parameters": {"value": 17}}, {"intent": "ContainsValue", "parameters": {"value": 5}}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("3 values...\ntrue\nfalse\n"));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_find_index_prints_the_position_or_minus_one() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        ":::naldom\nTake the values 4, 42 and 3, find where 42 is and where 5 is, and print their positions.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"values": [4, 42, 3]}}, {"intent": "FindIndex", "parameters": {"value": 42}}, {"intent": "FindIndex", "parameters": {"value": 5}}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("3 values...\n1\n-1\n"));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_exit_ends_the_program_with_its_code() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        ":::naldom\nTake the values 4, 17 and 3, print their sum, stop the program with code 3, then print the count.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"values": [4, 17, 3]}}, {"intent": "SumArray"}, {"intent": "Exit", "parameters": {"code": 3}}, {"intent": "CountElements"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert().success();
+
+    let output = Command::new(output_executable.path()).output()?;
+    assert_eq!(output.status.code(), Some(3));
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.ends_with("24\n"), "unexpected output:\n{}", stdout);
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_describe_array_prints_a_summary_sentence() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        ":::naldom\nTake the values 4, 17 and 3, describe them, sort them and describe them again.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"values": [4, 17, 3]}}, {"intent": "DescribeArray", "parameters": {}}, {"intent": "SortArray", "parameters": {"order": "ascending"}}, {"intent": "DescribeArray", "parameters": {}}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Array var_0: 3 elements, min 3, max 17, sorted ascending: no\n",
+        ))
+        .stdout(predicate::str::contains(
+            "Array var_0: 3 elements, min 3, max 17, sorted ascending: yes\n",
+        ));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_copy_array_leaves_the_original_unsorted() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        ":::naldom\nTake the values 3, 1 and 2, make a copy, sort the copy and print both.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"values": [3, 1, 2], "name": "a"}}, {"intent": "CopyArray", "parameters": {"name": "b"}}, {"intent": "SortArray", "parameters": {"target": "b"}}, {"intent": "PrintArray", "parameters": {"target": "a"}}, {"intent": "PrintArray", "parameters": {"target": "b"}}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("[3, 1, 2]"))
+        .stdout(predicate::str::contains("[1, 2, 3]"));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_printing_all_arrays_prints_them_in_creation_order() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        ":::naldom\nTake the values 1 and 2, then 3 and 4, then 5, and print all three arrays.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"values": [1, 2]}}, {"intent": "CreateArray", "parameters": {"values": [3, 4]}}, {"intent": "CreateArray", "parameters": {"values": [5]}}, {"intent": "PrintArray", "parameters": {"target": "all"}}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"(?s)\[1, 2\].*\[3, 4\].*\[5\]")?);
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_clear_array_prints_an_empty_array() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        ":::naldom\nTake the values 3, 1 and 2, fill them with zeros and print them, then empty the array and print it.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"values": [3, 1, 2]}}, {"intent": "FillArray", "parameters": {"value": 0}}, {"intent": "PrintArray", "parameters": {}}, {"intent": "ClearArray", "parameters": {}}, {"intent": "PrintArray", "parameters": {}}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("[0, 0, 0]"))
+        .stdout(predicate::str::contains("\n[]\n"));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_print_histogram_prints_bars_of_hashes() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        ":::naldom\nCreate an array of 50 random numbers and show a histogram with 5 buckets.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 50}}, {"intent": "PrintHistogram", "parameters": {"buckets": 5}}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+
+    // A row is `[   low,   high) ### count`, e.g. `[    0.51,    20.33) ######## 8`.
+    let stdout = String::from_utf8(output)?;
+    let rows: Vec<&str> = stdout
+        .lines()
+        .filter(|line| {
+            line.strip_prefix('[')
+                .map(|rest| rest.trim_start().trim_start_matches('-'))
+                .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        })
+        .filter(|line| line.contains(") #") || line.contains("] #"))
+        .collect();
+    assert!(!rows.is_empty(), "no histogram rows in:\n{}", stdout);
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_reverse_array_reverses_before_printing() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 5 numbers, reverse it, print it.\n:::")?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 5}}, {"intent": "SortArray", "parameters": {"order": "ascending"}}, {"intent": "ReverseArray"}, {"intent": "PrintArray"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    let output = cmd.assert().success().get_output().clone();
+
+    // Sorted ascending then reversed, so the printed values must descend.
+    let stdout = String::from_utf8(output.stdout)?;
+    let printed = stdout
+        .lines()
+        .find(|line| line.starts_with('['))
+        .ok_or("no array was printed")?;
+    let values: Vec<f64> = printed
+        .trim_matches(['[', ']'])
+        .split(", ")
+        .map(str::parse)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(values.len(), 5);
+    assert!(values.windows(2).all(|w| w[0] >= w[1]), "{}", printed);
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_append_grows_the_array_past_its_created_size() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file
+        .write_str(":::naldom\nCreate an array of 3 numbers, add 42 and 7 to it, print it.\n:::")?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 3}}, {"intent": "AppendToArray", "parameters": {"value": 42}}, {"intent": "AppendToArray", "parameters": {"value": 7}}, {"intent": "PrintArray"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    let output = cmd.assert().success().get_output().clone();
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let printed = stdout
+        .lines()
+        .find(|line| line.starts_with('['))
+        .ok_or("no array was printed")?;
+    let values: Vec<&str> = printed.trim_matches(['[', ']']).split(", ").collect();
+    assert_eq!(values.len(), 5, "{}", printed);
+    assert_eq!(values[3..], ["42.00", "7.00"]);
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_slice_keeps_only_the_first_elements() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        ":::naldom\nCreate an array of 10 numbers, keep only the first 5 elements, print it.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 10}}, {"intent": "SliceArray", "parameters": {"start": 0, "end": 5}}, {"intent": "PrintArray"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    let output = cmd.assert().success().get_output().clone();
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let printed = stdout
+        .lines()
+        .find(|line| line.starts_with('['))
+        .ok_or("no array was printed")?;
+    let values: Vec<&str> = printed.trim_matches(['[', ']']).split(", ").collect();
+    assert_eq!(values.len(), 5, "{}", printed);
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_map_multiplies_every_element() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        ":::naldom\nCreate an array of 3, 1 and 4, multiply every element by 3, then add 10 to each element and print it.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"values": [3, 1, 4]}}, {"intent": "MapArray", "parameters": {"operation": "multiply", "operand": 3}}, {"intent": "MapArray", "parameters": {"operation": "add", "operand": 10}}, {"intent": "PrintArray"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("[19, 13, 22]"));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_convert_prints_whole_numbers_then_floats_again() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        ":::naldom\nCreate an array of 7, 2 and 9, halve every element and print it as whole numbers, then as decimals.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"values": [7, 2, 9], "dtype": "float"}}, {"intent": "ConvertArray", "parameters": {"to": "int"}}, {"intent": "MapArray", "parameters": {"operation": "divide", "operand": 2}}, {"intent": "PrintArray"}, {"intent": "ConvertArray", "parameters": {"to": "float"}}, {"intent": "PrintArray"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("[3, 1, 4]"))
+        .stdout(predicate::str::contains("[3.00, 1.00, 4.00]"));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_sort_runs_the_algorithm_the_program_names() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        ":::naldom\nCreate an array of 3, 1, 4, 1 and 5, sort it descending with bubble sort and print it.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"values": [3, 1, 4, 1, 5]}}, {"intent": "SortArray", "parameters": {"order": "descending", "algorithm": "bubble"}}, {"intent": "PrintArray"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path())
+        .env("NALDOM_RUNTIME_LOG", "info");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("[5, 4, 3, 1, 1]"))
+        .stderr(predicate::str::contains(
+            "sort_array: bubble sort for 5 elements",
+        ));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_int_arrays_print_whole_numbers() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 5 random integers and print it.\n:::")?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 5, "dtype": "int"}}, {"intent": "PrintArray"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Runtime: Creating an array of 5 random whole numbers...",
+        ))
+        .stdout(predicate::str::is_match(r"\n\[\d+(, \d+){4}\]\n")?);
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_print_matrix_prints_one_line_per_row() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate a 3 by 4 matrix and print it.\n:::")?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateMatrix", "parameters": {"rows": 3, "cols": 4}}, {"intent": "PrintMatrix"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+
+    let stdout = String::from_utf8(output)?;
+    let rows = stdout
+        .lines()
+        .filter(|line| line.starts_with('[') && line.ends_with(']'))
+        .filter(|line| line.matches(", ").count() == 3)
+        .count();
+    assert_eq!(rows, 3, "{}", stdout);
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_print_elapsed_reports_milliseconds() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(
+        ":::naldom\nCreate an array of 1000 random numbers and time how long sorting it takes.\n:::",
+    )?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 1000}}, {"intent": "StartTimer"}, {"intent": "SortArray", "parameters": {"order": "ascending"}}, {"intent": "PrintElapsed"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .arg("-o")
+        .arg(output_executable.path())
+        .arg("--run")
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"elapsed: \d+(\.\d+)? ms")?);
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_wasm_module_exports_entry_and_memory_only() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file
+        .write_str(":::naldom\nCreate an array of 5 random numbers. Sort it and print it.\n:::")?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[
+            {"intent": "CreateArray", "parameters": {"size": 5}},
+            {"intent": "SortArray", "parameters": {"order": "ascending"}},
+            {"intent": "PrintArray"}
+        ]"#,
+    )?;
+    let output_module = temp.child("program.wasm");
+
+    // Requires llc and wasm-ld.
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.env("NALDOM_MOCK_RESPONSE", response.path())
+        .arg(input_file.path())
+        .args(["--target", "wasm", "-o"])
+        .arg(output_module.path());
+    cmd.assert().success();
+
+    let bytes = std::fs::read(output_module.path())?;
+    let mut exports = Vec::new();
+    let mut import_modules = Vec::new();
+    for payload in wasmparser::Parser::new(0).parse_all(&bytes) {
+        match payload? {
+            wasmparser::Payload::ExportSection(reader) => {
+                for export in reader {
+                    exports.push(export?.name.to_string());
+                }
+            }
+            wasmparser::Payload::ImportSection(reader) => {
+                for import in reader {
+                    import_modules.push(import?.module.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    exports.sort();
+    assert_eq!(exports, ["memory", "naldom_main"]);
+    assert!(!import_modules.is_empty());
+    assert!(
+        import_modules
+            .iter()
+            .all(|module| module == "naldom_runtime")
+    );
+    temp.child("program.d.ts")
+        .assert(predicate::str::contains(
+            "sort_array(arr: Pointer, order: bigint, algorithm: bigint): void;",
+        ))
+        .assert(predicate::str::contains("naldom_main(): void;"));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_object_output_uses_the_custom_entry_name() -> Result<(), Box<dyn Error>> {
+    use object::{Object, ObjectSymbol};
+
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 5 random numbers and print it.\n:::")?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 5}}, {"intent": "PrintArray"}]"#,
+    )?;
+    let output_object = temp.child("embedded.o");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.env("NALDOM_MOCK_RESPONSE", response.path())
+        .arg(input_file.path())
+        .args(["--crate-type", "obj", "--entry-name", "run_naldom", "-o"])
+        .arg(output_object.path());
+    cmd.assert().success();
+
+    let bytes = std::fs::read(output_object.path())?;
+    let file = object::File::parse(&*bytes)?;
+    let defined: Vec<String> = file
+        .symbols()
+        .filter(|symbol| symbol.is_definition())
+        .filter_map(|symbol| symbol.name().ok().map(str::to_string))
+        .collect();
+    // Mach-O prefixes C symbols with an underscore.
+    let defines = |name: &str| {
+        defined
+            .iter()
+            .any(|symbol| symbol.trim_start_matches('_') == name)
+    };
+    assert!(defines("run_naldom"));
+    assert!(!defines("main"));
+
+    Ok(())
+}
+
+#[test]
+fn test_library_outputs_refuse_run() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 5 random numbers.\n:::")?;
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .args(["--crate-type", "staticlib", "--run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--run cannot be used with --crate-type staticlib: it produces no executable",
+        ))
+        .stdout(predicate::str::contains("Mock LLM").not());
+
+    Ok(())
+}
+
+#[test]
+fn test_build_id_is_printed_and_recorded_in_the_manifest() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 5 random numbers.\nPrint it.\n:::")?;
+    let output_file = temp.child("program.py");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .args([
+            "--target",
+            "python",
+            "--manifest",
+            "--manifest-timings",
+            "-o",
+        ])
+        .arg(output_file.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+
+    let stdout = String::from_utf8(output)?;
+    let build_id = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("build "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .ok_or("no build line in the output")?;
+    assert_eq!(build_id.len(), 6);
+    temp.child("program.py.naldom-manifest.json")
+        .assert(predicate::str::contains(format!(
+            "\"build_id\": \"{}\"",
+            build_id
+        )));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_multi_builds_one_binary_that_runs_programs_by_name() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let demo1 = temp.child("demo1.json");
+    demo1.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 3}}, {"intent": "PrintArray"}]"#,
+    )?;
+    let demo2 = temp.child("demo2.json");
+    demo2.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 6}},
+            {"intent": "SortArray", "parameters": {"order": "ascending"}},
+            {"intent": "PrintArray"}]"#,
+    )?;
+    let executable = temp.child("demos");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg("--multi")
+        .arg("--from-intents")
+        .arg(demo1.path())
+        .arg(demo2.path())
+        .arg("-o")
+        .arg(executable.path());
+    cmd.assert().success();
+
+    Command::new(executable.path())
+        .arg("demo1")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Creating an array of 3 random numbers",
+        ))
+        .stdout(predicate::str::contains("Sorting").not());
+    Command::new(executable.path())
+        .arg("demo2")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Creating an array of 6 random numbers",
+        ));
+    Command::new(executable.path())
+        .arg("demo3")
+        .assert()
+        .code(2)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains(
+            "Unknown program 'demo3'. Available programs: demo1, demo2",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn test_emit_intent_json_prints_the_validated_plan() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let intents = temp.child("program.json");
+    intents.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 8}},
+            {"intent": "SortArray", "parameters": {}},
+            {"intent": "PrintArray"}]"#,
+    )?;
+    let output = temp.child("program.py");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg("--from-intents")
+        .arg(intents.path())
+        .args(["--target", "python", "--emit", "intent-json", "-o"])
+        .arg(output.path());
+    let assert = cmd.assert().success();
+
+    let plan: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    let plan = plan.as_array().ok_or("the plan is not a JSON array")?;
+    assert_eq!(plan.len(), 3);
+    assert_eq!(plan[1]["intent"], "SortArray");
+    assert_eq!(plan[1]["parameters"]["order"], "ascending");
+    assert_eq!(plan[2]["parameters"]["targetOrdinal"], 1);
+    output.assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn test_trace_prints_both_irs_in_their_textual_form() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let intents = temp.child("program.json");
+    intents.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 3}},
+            {"intent": "PrintArray"}]"#,
+    )?;
+    let output = temp.child("program.py");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg("--from-intents")
+        .arg(intents.path())
+        .args(["--target", "python", "--trace", "-o"])
+        .arg(output.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "... High-Level IR ...\nvar_0 = create_random_array(3)\nprint_array(var_0, 0)\n\n",
+        ))
+        .stdout(predicate::str::contains(
+            "... Low-Level IR ...\nfn @main() -> i32 {\nbb0:\n  %0 = call @create_random_array(3)",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn test_emit_intent_graph_prints_dot_without_building() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let intents = temp.child("program.json");
+    intents.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 8}},
+            {"intent": "CreateArray", "parameters": {"values": [3, 1, 4]}},
+            {"intent": "SortArray", "parameters": {"targetOrdinal": 1}},
+            {"intent": "PrintArray", "parameters": {"targetOrdinal": 1}},
+            {"intent": "PrintArray"}]"#,
+    )?;
+    let output = temp.child("program.py");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg("--from-intents")
+        .arg(intents.path())
+        .args(["--target", "python", "--emit", "intent-graph", "-o"])
+        .arg(output.path());
+    let assert = cmd.assert().success();
+
+    let dot = String::from_utf8(assert.get_output().stdout.clone())?;
+    assert!(dot.starts_with("digraph intents {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert_eq!(dot.matches('{').count(), dot.matches('}').count());
+    let (edges, nodes): (Vec<&str>, Vec<&str>) = dot
+        .lines()
+        .filter(|line| {
+            line.strip_prefix("  n")
+                .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        })
+        .partition(|line| line.contains(" -> "));
+    assert_eq!(nodes.len(), 5);
+    assert_eq!(edges.len(), 4);
+    output.assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_identical_builds_give_identical_artifacts() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let intents = temp.child("program.json");
+    intents.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 8}},
+            {"intent": "SortArray", "parameters": {"order": "descending"}},
+            {"intent": "PrintArray"}]"#,
+    )?;
+
+    let build = |dir: &str| -> Result<(), Box<dyn Error>> {
+        let out_dir = temp.child(dir);
+        out_dir.create_dir_all()?;
+        let mut cmd = Command::cargo_bin("naldom-cli")?;
+        cmd.current_dir(temp.path())
+            .arg("--from-intents")
+            .arg("program.json")
+            .args(["--seed", "7", "--save-temps", "--manifest", "-o"])
+            .arg(format!("{}/program", dir));
+        cmd.assert().success();
+        Ok(())
+    };
+    build("first")?;
+    build("second")?;
+
+    let read = |dir: &str, file: &str| std::fs::read(temp.child(dir).child(file).path());
+    for file in ["program.ll", "program.o", "program"] {
+        assert!(
+            read("first", file)? == read("second", file)?,
+            "{} differs between the builds",
+            file
+        );
+    }
+    let manifest = |dir: &str| -> Result<String, Box<dyn Error>> {
+        let json = String::from_utf8(read(dir, "program.naldom-manifest.json")?)?;
+        Ok(json.replace(dir, "<out>"))
+    };
+    assert_eq!(manifest("first")?, manifest("second")?);
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_deterministic_builds_give_identical_manifests_and_binaries() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let intents = temp.child("program.json");
+    intents.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 8}},
+            {"intent": "SortArray", "parameters": {"order": "ascending"}},
+            {"intent": "PrintArray"}]"#,
+    )?;
+
+    let build = |dir: &str| -> Result<(), Box<dyn Error>> {
+        let out_dir = temp.child(dir);
+        out_dir.create_dir_all()?;
+        let mut cmd = Command::cargo_bin("naldom-cli")?;
+        cmd.current_dir(temp.path())
+            .arg("--from-intents")
+            .arg("program.json")
+            .args(["--deterministic", "--manifest", "-o"])
+            .arg(format!("{}/program", dir));
+        cmd.assert().success();
+        Ok(())
+    };
+    build("first")?;
+    build("second")?;
+
+    let read = |dir: &str, file: &str| std::fs::read(temp.child(dir).child(file).path());
+    assert!(read("first", "program")? == read("second", "program")?);
+    let manifest = |dir: &str| -> Result<String, Box<dyn Error>> {
+        let json = String::from_utf8(read(dir, "program.naldom-manifest.json")?)?;
+        Ok(json.replace(dir, "<out>"))
+    };
+    assert_eq!(manifest("first")?, manifest("second")?);
+    assert!(manifest("first")?.contains("\"seed\": 0"));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_deterministic_rejects_conflicting_options() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 5 random numbers.\n:::")?;
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path())
+        .args(["--deterministic", "--llm-temperature", "0.9"]);
+    cmd.assert().failure().stderr(
+        predicate::str::contains(
+            "--deterministic conflicts with options given on the command line",
+        )
+        .and(predicate::str::contains(
+            "--llm-temperature 0.9: it implies --llm-temperature 0",
+        )),
+    );
+
+    // Without a recorded answer there is nothing to compile.
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg(input_file.path()).arg("--deterministic");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--locked needs the lockfile"));
+
+    Ok(())
+}
+
+#[test]
+fn test_eval_scores_two_prompts_side_by_side() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let cases = temp.child("cases");
+    cases
+        .child("create.nld")
+        .write_str("Create an array of 5 numbers.")?;
+    cases
+        .child("create.json")
+        .write_str(r#"[{"intent": "CreateArray", "parameters": {"size": 5}}]"#)?;
+    cases
+        .child("three.nld")
+        .write_str("Create an array of 3 numbers.")?;
+    cases
+        .child("three.json")
+        .write_str(r#"[{"intent": "CreateArray", "parameters": {"size": 3}}]"#)?;
+    let response = temp.child("response.json");
+    response.write_str(r#"[{"intent": "CreateArray", "parameters": {"size": 5}}]"#)?;
+    let variant = temp.child("variant.txt");
+    variant.write_str("Answer with a JSON array of intents.\n")?;
+    let report = temp.child("report.json");
+
+    let mut cmd = Command::cargo_bin("naldom-cli")?;
+    cmd.arg("eval")
+        .arg(cases.path())
+        .args(["--runs", "2", "--compare"])
+        .arg(variant.path())
+        .arg("--report")
+        .arg(report.path())
+        .env("NALDOM_MOCK_RESPONSE", response.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("first wrong answer for 'three'"))
+        .stdout(predicate::str::is_match(r"create\s+2/2\s+2/2\n")?)
+        .stdout(predicate::str::is_match(
+            r"exact match\s+50\.0%\s+50\.0%\n",
+        )?);
+    report.assert(predicate::str::contains(r#""prompt": "built-in""#));
+
+    Ok(())
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_diff_previous_compares_with_the_last_run() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let cache_dir = temp.child("cache");
+    let input_file = temp.child("program.md");
+    input_file.write_str(":::naldom\nCreate an array of 5 random numbers and print it.\n:::")?;
+    let response = temp.child("response.json");
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 5}}, {"intent": "PrintArray"}]"#,
+    )?;
+    let output_executable = temp.child("program");
+    let run = || -> Result<Command, Box<dyn Error>> {
+        let mut cmd = Command::cargo_bin("naldom-cli")?;
+        cmd.arg(input_file.path())
+            .arg("-o")
+            .arg(output_executable.path())
+            .args(["--seed", "7", "--run", "--diff-previous"])
+            .env("NALDOM_MOCK_RESPONSE", response.path())
+            .env("NALDOM_CACHE_DIR", cache_dir.path());
+        Ok(cmd)
+    };
+
+    run()?.assert().success().stdout(predicate::str::contains(
+        "No earlier output of this program to compare with.",
+    ));
+    run()?.assert().success().stdout(predicate::str::contains(
+        "Output unchanged since the previous run.",
+    ));
+
+    response.write_str(
+        r#"[{"intent": "CreateArray", "parameters": {"size": 5}}, {"intent": "SumArray"}]"#,
+    )?;
+    run()?.assert().success().stdout(predicate::str::contains(
+        "Output changed since the previous run:\n--- ",
+    ));
+
+    Ok(())
+}
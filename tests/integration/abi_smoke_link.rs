@@ -0,0 +1,68 @@
+// tests/integration/abi_smoke_link.rs
+
+//! Links the ABI smoke program against the runtime this workspace built and
+//! runs it. A registry signature that no longer matches the runtime still
+//! links, since C does not check types across objects, but the calls with
+//! the registered types then crash or print garbage instead of exiting
+//! cleanly. Needs `clang` on `PATH`.
+
+use naldom_core::abi_smoke::{SMOKE_STRING, abi_smoke_program};
+use naldom_core::codegen_llvm::{emit_object, generate_llvm_ir};
+use naldom_core::targets::host_triple;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// `target/debug`, where Cargo puts `libnaldom_runtime.a`. Test binaries
+/// live one level down, in `deps`.
+fn runtime_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let exe = std::env::current_exe()?;
+    exe.parent()
+        .and_then(|deps| deps.parent())
+        .map(PathBuf::from)
+        .ok_or_else(|| "the test binary has no parent directory".into())
+}
+
+#[test]
+fn test_smoke_program_links_against_the_runtime_and_exits_cleanly() -> Result<(), Box<dyn Error>> {
+    // Arrange
+    let triple = host_triple();
+    let llvm_ir = generate_llvm_ir(&abi_smoke_program(), &triple)?;
+    let object = emit_object(&llvm_ir, &triple)?;
+    let temp = assert_fs::TempDir::new()?;
+    let object_path = temp.path().join("abi_smoke.o");
+    let executable = temp.path().join("abi_smoke");
+    fs::write(&object_path, object)?;
+
+    // Act
+    let link = Command::new("clang")
+        .arg(&object_path)
+        .arg("-L")
+        .arg(runtime_dir()?)
+        .arg("-lnaldom_runtime")
+        .arg("-o")
+        .arg(&executable)
+        .output()?;
+    assert!(
+        link.status.success(),
+        "linking failed:\n{}",
+        String::from_utf8_lossy(&link.stderr)
+    );
+    let run = Command::new(&executable).output()?;
+
+    // Assert
+    assert!(
+        run.status.success(),
+        "the smoke program exited with {}:\n{}",
+        run.status,
+        String::from_utf8_lossy(&run.stderr)
+    );
+    let stdout = String::from_utf8(run.stdout)?;
+    assert!(
+        stdout.contains(&format!("Array {}: ", SMOKE_STRING)),
+        "describe_array did not get its name through:\n{}",
+        stdout
+    );
+    Ok(())
+}
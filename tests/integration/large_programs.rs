@@ -0,0 +1,69 @@
+// tests/integration/large_programs.rs
+
+use naldom_core::codegen_llvm::generate_llvm_ir;
+use naldom_core::lowering::LoweringContext;
+use naldom_core::lowering_hl_to_ll::lower_hl_to_ll;
+use naldom_core::parser::parse_to_intent_graph;
+use naldom_core::program_generator::generate_program;
+use naldom_core::semantic_analyzer::SemanticAnalyzer;
+use std::time::{Duration, Instant};
+
+/// How long one program may take from parsing to a verified module. Far
+/// above what a healthy pipeline needs, even in a debug build; a quadratic
+/// pass blows through it at 5,000 intents.
+const TIME_BUDGET: Duration = Duration::from_secs(30);
+
+/// Compiles a generated program of `intents` intents to LLVM IR, checking the
+/// time budget. Returns the number of instructions in the module.
+fn compile(intents: usize) -> usize {
+    let json = generate_program(intents);
+    let started = Instant::now();
+
+    let intent_graph = parse_to_intent_graph(&json).expect("Parsing failed");
+    let validated_graph = SemanticAnalyzer::new()
+        .analyze(&intent_graph)
+        .expect("Analysis failed");
+    let hl_program = LoweringContext::new().lower(&validated_graph);
+    // `generate_llvm_ir` verifies the module before printing it.
+    let llvm_ir = generate_llvm_ir(
+        &lower_hl_to_ll(&hl_program).unwrap(),
+        "x86_64-unknown-linux-gnu",
+    )
+    .expect("Codegen failed");
+
+    let elapsed = started.elapsed();
+    println!("{} intents compiled in {:?}", intents, elapsed);
+    assert!(
+        elapsed < TIME_BUDGET,
+        "{} intents took {:?}, over the budget of {:?}",
+        intents,
+        elapsed,
+        TIME_BUDGET
+    );
+
+    // Instructions are the indented lines inside function bodies.
+    llvm_ir
+        .lines()
+        .filter(|line| line.starts_with("  ") && !line.trim_start().starts_with(';'))
+        .count()
+}
+
+/// Ignored by default: it compiles 6,000 intents. Run with
+/// `cargo test -- --ignored --nocapture` to see the timings.
+#[test]
+#[ignore]
+fn test_large_programs_compile_in_linear_time_and_size() {
+    // Act
+    let small = compile(1_000);
+    let large = compile(5_000);
+
+    // Assert: five times the intents give about five times the code.
+    let ratio = large as f64 / small as f64;
+    println!("{} and {} instructions (ratio {:.2})", small, large, ratio);
+    assert!(
+        (4.5..=5.5).contains(&ratio),
+        "1,000 intents gave {} instructions but 5,000 gave {}",
+        small,
+        large
+    );
+}
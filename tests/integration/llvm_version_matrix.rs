@@ -0,0 +1,35 @@
+// tests/integration/llvm_version_matrix.rs
+
+use std::error::Error;
+use std::path::Path;
+use std::process::Command;
+
+/// Runs `check_llvm_versions.sh`, which type-checks the workspace with each of
+/// the `llvm17`/`llvm18`/`llvm19` features. Ignored by default: it builds the
+/// dependency tree three more times. Run with `cargo test -- --ignored`.
+#[test]
+#[ignore]
+fn test_every_llvm_version_feature_type_checks() -> Result<(), Box<dyn Error>> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .ancestors()
+        .find(|dir| dir.join("check_llvm_versions.sh").is_file())
+        .ok_or("check_llvm_versions.sh not found above the manifest directory")?;
+
+    // A separate target directory: the outer `cargo test` holds the lock on
+    // the default one.
+    let output = Command::new(root.join("check_llvm_versions.sh"))
+        .current_dir(root)
+        .env("CARGO_TARGET_DIR", root.join("target").join("llvm-matrix"))
+        .output()?;
+
+    assert!(
+        output.status.success(),
+        "version matrix failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout)?;
+    for version in ["17", "18", "19"] {
+        assert!(stdout.contains(&format!("Checking with LLVM {}...", version)));
+    }
+    Ok(())
+}
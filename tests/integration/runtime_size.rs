@@ -0,0 +1,61 @@
+// tests/integration/runtime_size.rs
+
+use std::error::Error;
+use std::path::Path;
+use std::process::Command;
+
+/// The most the release build of the minimal runtime may weigh. Almost all of
+/// it is the standard library; a jump means a dependency crept into the
+/// always-on part of the runtime instead of behind a feature.
+const MINIMAL_RUNTIME_BUDGET_BYTES: u64 = 24 * 1024 * 1024;
+
+/// Builds every runtime variant in release mode with `build_runtime_variants.sh`,
+/// prints the size of each, and checks the minimal one against its budget.
+/// Ignored by default: it builds the runtime four times. Run with
+/// `cargo test -- --ignored --nocapture` to see the sizes.
+#[test]
+#[ignore]
+fn test_minimal_runtime_stays_within_its_size_budget() -> Result<(), Box<dyn Error>> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .ancestors()
+        .find(|dir| dir.join("build_runtime_variants.sh").is_file())
+        .ok_or("build_runtime_variants.sh not found above the manifest directory")?;
+
+    // A separate target directory: the outer `cargo test` holds the lock on
+    // the default one.
+    let output = Command::new(root.join("build_runtime_variants.sh"))
+        .arg("--release")
+        .current_dir(root)
+        .env("CARGO_TARGET_DIR", root.join("target").join("runtime-size"))
+        .output()?;
+    assert!(
+        output.status.success(),
+        "building the variants failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Lines look like "minimal      22008134 bytes".
+    let stdout = String::from_utf8(output.stdout)?;
+    let sizes: Vec<(&str, u64)> = stdout
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            Some((words.next()?, words.next()?.parse().ok()?))
+        })
+        .collect();
+    println!("{}", stdout);
+    assert_eq!(sizes.len(), 4, "unexpected script output:\n{}", stdout);
+
+    let (_, minimal) = sizes
+        .iter()
+        .find(|(variant, _)| *variant == "minimal")
+        .ok_or("no minimal variant was built")?;
+    assert!(
+        *minimal <= MINIMAL_RUNTIME_BUDGET_BYTES,
+        "the minimal runtime is {} bytes, over its budget of {}",
+        minimal,
+        MINIMAL_RUNTIME_BUDGET_BYTES
+    );
+    assert!(sizes.iter().all(|(_, size)| size >= minimal));
+    Ok(())
+}
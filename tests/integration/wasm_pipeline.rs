@@ -0,0 +1,269 @@
+// tests/integration/wasm_pipeline.rs
+
+//! The wasm pipeline end to end, in-process: no `llc`, no `wasm-ld` and no
+//! wasm runtime, so it runs wherever the compiler builds. The object file is
+//! the relocatable module `wasm-ld` would link; its imports and calls are
+//! already those of the final program. With `--features wasm-run` the module
+//! also runs, in wasmi.
+
+use naldom_core::codegen_llvm::emit_object;
+use naldom_core::compiler::Compiler;
+use naldom_core::targets::{TargetKind, WASM_TRIPLE};
+use naldom_ir::runtime_abi::WASM_IMPORT_MODULE;
+use std::collections::HashMap;
+use std::error::Error;
+use wasmparser::{Operator, Parser, Payload, TypeRef, Validator};
+
+/// The canonical program: create an array, sort it, print it.
+const CANONICAL_PROGRAM: &str = r#"[
+    {"intent": "CreateArray", "parameters": {"size": 10}},
+    {"intent": "SortArray", "parameters": {"order": "ascending"}},
+    {"intent": "PrintArray"}
+]"#;
+
+/// How often the code calls each function imported from the runtime module.
+fn runtime_calls(module: &[u8]) -> Result<HashMap<String, usize>, Box<dyn Error>> {
+    let mut imports = Vec::new();
+    let mut calls = HashMap::new();
+    for payload in Parser::new(0).parse_all(module) {
+        match payload? {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    // Function indices count imported functions first.
+                    if let TypeRef::Func(_) = import.ty {
+                        imports.push((import.module.to_string(), import.name.to_string()));
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                for operator in body.get_operators_reader()? {
+                    if let Operator::Call { function_index } = operator?
+                        && let Some((module, name)) = imports.get(function_index as usize)
+                        && module == WASM_IMPORT_MODULE
+                    {
+                        *calls.entry(name.clone()).or_insert(0) += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(calls)
+}
+
+#[test]
+fn test_canonical_program_compiles_to_a_valid_wasm_object() -> Result<(), Box<dyn Error>> {
+    // Arrange
+    let output = Compiler::new()
+        .with_target(TargetKind::Wasm)
+        .compile_llm_output(CANONICAL_PROGRAM)?;
+
+    // Act
+    let object = emit_object(&output.code, WASM_TRIPLE)?;
+
+    // Assert
+    Validator::new().validate_all(&object)?;
+    let calls = runtime_calls(&object)?;
+    for function in ["create_random_array", "sort_array", "print_array"] {
+        assert_eq!(calls.get(function), Some(&1), "calls to {}", function);
+    }
+    Ok(())
+}
+
+/// Runs the canonical program in wasmi, with `--features wasm-run`: the
+/// validation above shows which calls the code contains, this shows which it
+/// makes. Still no `wasm-ld`: the object gets an export for its entry
+/// function, and the host stands in for the linker's other inputs.
+#[cfg(feature = "wasm-run")]
+mod wasm_run {
+    use super::CANONICAL_PROGRAM;
+    use naldom_core::codegen_llvm::emit_object;
+    use naldom_core::compiler::Compiler;
+    use naldom_core::targets::{TargetKind, WASM_ENTRY, WASM_TRIPLE};
+    use naldom_ir::runtime_abi::WASM_IMPORT_MODULE;
+    use std::collections::HashMap;
+    use std::error::Error;
+    use wasmi::{
+        Engine, ExternType, Global, Linker, Memory, MemoryType, Module, Mutability, Store, Table,
+        Val,
+    };
+    use wasmparser::{KnownCustom, Linking, Parser, Payload, SymbolFlags, SymbolInfo};
+
+    /// The size of the memory given to the module, in 64 KiB pages. The stack
+    /// grows down from its end.
+    const MEMORY_PAGES: u32 = 16;
+
+    /// Sections that come after the export section, by id: start, element,
+    /// data count, code and data.
+    const AFTER_EXPORTS: [u8; 5] = [8, 9, 12, 10, 11];
+
+    /// The index of the function the linking section defines as `name`. An
+    /// object has no export section; its symbols are only in the linking
+    /// metadata `wasm-ld` reads.
+    fn defined_function(module: &[u8], name: &str) -> Result<u32, Box<dyn Error>> {
+        for payload in Parser::new(0).parse_all(module) {
+            let Payload::CustomSection(section) = payload? else {
+                continue;
+            };
+            let KnownCustom::Linking(linking) = section.as_known() else {
+                continue;
+            };
+            for subsection in linking.subsections() {
+                let Linking::SymbolTable(symbols) = subsection? else {
+                    continue;
+                };
+                for symbol in symbols {
+                    if let SymbolInfo::Func {
+                        flags,
+                        index,
+                        name: Some(symbol_name),
+                    } = symbol?
+                        && symbol_name == name
+                        && !flags.contains(SymbolFlags::UNDEFINED)
+                    {
+                        return Ok(index);
+                    }
+                }
+            }
+        }
+        Err(format!("the object defines no function '{}'", name).into())
+    }
+
+    /// Appends `value` as unsigned LEB128.
+    fn write_leb128(bytes: &mut Vec<u8>, mut value: u32) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes.push(byte);
+                return;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+
+    /// `module` with an export section that exports function `index` as
+    /// `name`, in front of the first section that has to follow it.
+    fn with_export(module: &[u8], name: &str, index: u32) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut contents = Vec::new();
+        write_leb128(&mut contents, 1);
+        write_leb128(&mut contents, name.len() as u32);
+        contents.extend_from_slice(name.as_bytes());
+        contents.push(0x00); // A function.
+        write_leb128(&mut contents, index);
+        let mut export_section = vec![7];
+        write_leb128(&mut export_section, contents.len() as u32);
+        export_section.extend(contents);
+
+        // Sections are contiguous, so each one runs from the end of the last.
+        let mut output = Vec::with_capacity(module.len() + export_section.len());
+        let mut start = 0;
+        for payload in Parser::new(0).parse_all(module) {
+            let payload = payload?;
+            if let Payload::Version { range, .. } = &payload {
+                output.extend_from_slice(&module[range.clone()]);
+                start = range.end;
+                continue;
+            }
+            let Some((id, range)) = payload.as_section() else {
+                continue;
+            };
+            if id == 7 {
+                return Err("the object already has an export section".into());
+            }
+            if AFTER_EXPORTS.contains(&id) && !export_section.is_empty() {
+                output.append(&mut export_section);
+            }
+            output.extend_from_slice(&module[start..range.end]);
+            start = range.end;
+        }
+        output.append(&mut export_section);
+        Ok(output)
+    }
+
+    /// Runs the entry function of `object`, returning how often it called
+    /// each runtime function. The runtime imports are stubs that only count
+    /// their calls and return zeros, which is enough for a program that hands
+    /// each result to the next call. The memory, stack pointer and table
+    /// imports are the ones `wasm-ld` would otherwise provide.
+    fn run(object: &[u8]) -> Result<HashMap<String, usize>, Box<dyn Error>> {
+        let module = with_export(object, WASM_ENTRY, defined_function(object, WASM_ENTRY)?)?;
+        let engine = Engine::default();
+        let module = Module::new(&engine, &module[..])?;
+        let mut store = Store::new(&engine, HashMap::new());
+        let mut linker = Linker::<HashMap<String, usize>>::new(&engine);
+
+        for import in module.imports() {
+            let (module_name, name) = (import.module(), import.name());
+            match import.ty() {
+                ExternType::Func(ty) if module_name == WASM_IMPORT_MODULE => {
+                    let function = name.to_string();
+                    let result_types = ty.results().to_vec();
+                    linker.func_new(
+                        module_name,
+                        name,
+                        ty.clone(),
+                        move |mut caller, _, results| {
+                            *caller.data_mut().entry(function.clone()).or_insert(0) += 1;
+                            for (result, ty) in results.iter_mut().zip(&result_types) {
+                                *result = Val::default(*ty);
+                            }
+                            Ok(())
+                        },
+                    )?;
+                }
+                ExternType::Memory(_) => {
+                    let ty = MemoryType::new(MEMORY_PAGES, None).map_err(wasmi::Error::from)?;
+                    let memory = Memory::new(&mut store, ty).map_err(wasmi::Error::from)?;
+                    linker.define(module_name, name, memory)?;
+                }
+                // `__stack_pointer`, the only global an object imports.
+                ExternType::Global(_) => {
+                    let top = Val::I32((MEMORY_PAGES * 65_536) as i32);
+                    linker.define(
+                        module_name,
+                        name,
+                        Global::new(&mut store, top, Mutability::Var),
+                    )?;
+                }
+                ExternType::Table(ty) => {
+                    let table = Table::new(&mut store, *ty, Val::default(ty.element()))
+                        .map_err(wasmi::Error::from)?;
+                    linker.define(module_name, name, table)?;
+                }
+                ExternType::Func(_) => {
+                    return Err(format!("unexpected import {}.{}", module_name, name).into());
+                }
+            }
+        }
+
+        let instance = linker.instantiate(&mut store, &module)?.start(&mut store)?;
+        let entry = instance
+            .get_func(&store, WASM_ENTRY)
+            .ok_or("the entry function was not exported")?;
+        let ty = entry.ty(&store);
+        let params: Vec<Val> = ty.params().iter().map(|ty| Val::default(*ty)).collect();
+        let mut results: Vec<Val> = ty.results().iter().map(|ty| Val::default(*ty)).collect();
+        entry.call(&mut store, &params, &mut results)?;
+        Ok(store.into_data())
+    }
+
+    #[test]
+    fn test_canonical_program_calls_each_runtime_function_once() -> Result<(), Box<dyn Error>> {
+        // Arrange
+        let output = Compiler::new()
+            .with_target(TargetKind::Wasm)
+            .compile_llm_output(CANONICAL_PROGRAM)?;
+        let object = emit_object(&output.code, WASM_TRIPLE)?;
+
+        // Act
+        let calls = run(&object)?;
+
+        // Assert
+        for function in ["create_random_array", "sort_array", "print_array"] {
+            assert_eq!(calls.get(function), Some(&1), "calls to {}", function);
+        }
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@
 
 mod parser_tests {
     use naldom_core::parser::parse_to_intent_graph;
-    use naldom_ir::{CreateArrayParams, Intent, SortArrayParams};
+    use naldom_ir::{CountArg, Intent};
 
     #[test]
     fn test_parse_valid_json() {
@@ -31,20 +31,20 @@ mod parser_tests {
 
         match &intent_graph[0] {
             Intent::CreateArray(params) => {
-                assert_eq!(params.size, 10);
-                assert_eq!(params.source, "random");
+                assert_eq!(params.size, Some(CountArg::Literal(10)));
+                assert!(params.values.is_empty());
             }
             _ => panic!("Expected first intent to be CreateArray"),
         }
 
         match &intent_graph[1] {
             Intent::SortArray(params) => {
-                assert_eq!(params.order, "ascending");
+                assert_eq!(params.order.as_deref(), Some("ascending"));
             }
             _ => panic!("Expected second intent to be SortArray"),
         }
 
-        assert!(matches!(intent_graph[2], Intent::PrintArray));
+        assert!(matches!(intent_graph[2], Intent::PrintArray(_)));
     }
 
     #[test]
@@ -63,7 +63,8 @@ mod parser_tests {
 mod lowering_tests {
     use naldom_core::lowering::LoweringContext;
     use naldom_ir::{
-        CreateArrayParams, HLExpression, HLProgram, HLStatement, HLValue, Intent, SortArrayParams,
+        CountArg, CreateArrayParams, HLExpression, HLProgram, HLStatement, HLValue, Intent,
+        SortArrayParams,
     };
 
     #[test]
@@ -71,13 +72,18 @@ mod lowering_tests {
         // Arrange: Manually create an IntentGraph.
         let intent_graph = vec![
             Intent::CreateArray(CreateArrayParams {
-                size: 10,
-                source: "random".to_string(),
+                size: Some(CountArg::Literal(10)),
+                name: None,
+                values: Vec::new(),
+                dtype: None,
             }),
             Intent::SortArray(SortArrayParams {
-                order: "ascending".to_string(),
+                order: Some("ascending".to_string()),
+                algorithm: None,
+                target_ordinal: None,
+                target: None,
             }),
-            Intent::PrintArray,
+            Intent::PrintArray(None),
         ];
 
         let mut context = LoweringContext::default();
@@ -100,11 +106,15 @@ mod lowering_tests {
                     arguments: vec![
                         HLExpression::Variable("var_0".to_string()),
                         HLExpression::Literal(HLValue::String("ascending".to_string())),
+                        HLExpression::Literal(HLValue::Integer(0)),
                     ],
                 },
                 HLStatement::Call {
                     function: "print_array".to_string(),
-                    arguments: vec![HLExpression::Variable("var_0".to_string())],
+                    arguments: vec![
+                        HLExpression::Variable("var_0".to_string()),
+                        HLExpression::Literal(HLValue::Integer(0)),
+                    ],
                 },
             ],
         };
@@ -115,7 +125,7 @@ mod lowering_tests {
 
 mod codegen_tests {
     use naldom_core::codegen_python::PythonCodeGenerator;
-    use naldom_ir::{HLProgram, HLStatement, HLExpression, HLValue};
+    use naldom_ir::{HLExpression, HLProgram, HLStatement, HLValue};
 
     #[test]
     fn test_generate_python_code() {
@@ -158,4 +168,4 @@ mod codegen_tests {
 
         assert_eq!(python_code, expected_code);
     }
-}
\ No newline at end of file
+}